@@ -0,0 +1,94 @@
+use chrono::Utc;
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Serialize, Serializer};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use super::timestamp;
+
+/// Characters of content kept in an item's preview.
+pub const PREVIEW_CHARS: usize = 200;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ClipboardItem {
+    pub id: String,
+    pub content: String,
+    pub content_hash: String,
+    pub content_type: String,
+    pub source_app: Option<String>,
+    pub device_id: String,
+    pub created_at: i64,
+    pub is_promoted: bool,
+}
+
+/// Serialized with `created_at_iso` too: `created_at` in the user's time zone.
+impl Serialize for ClipboardItem {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut item = serializer.serialize_struct("ClipboardItem", 9)?;
+        item.serialize_field("id", &self.id)?;
+        item.serialize_field("content", &self.content)?;
+        item.serialize_field("content_hash", &self.content_hash)?;
+        item.serialize_field("content_type", &self.content_type)?;
+        item.serialize_field("source_app", &self.source_app)?;
+        item.serialize_field("device_id", &self.device_id)?;
+        item.serialize_field("created_at", &self.created_at)?;
+        item.serialize_field("created_at_iso", &timestamp::to_local_iso(self.created_at))?;
+        item.serialize_field("is_promoted", &self.is_promoted)?;
+        item.end()
+    }
+}
+
+impl ClipboardItem {
+    pub fn new(content: String, device_id: &str) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            content_hash: Self::hash_content(&content),
+            content,
+            content_type: "text/plain".to_string(),
+            source_app: None,
+            device_id: device_id.to_string(),
+            created_at: Utc::now().timestamp_millis(),
+            is_promoted: false,
+        }
+    }
+
+    /// The start of `content` shown in lists.
+    pub fn preview(content: &str) -> String {
+        content.chars().take(PREVIEW_CHARS).collect()
+    }
+
+    pub fn hash_content(content: &str) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(content.as_bytes());
+        format!("{:x}", hasher.finalize())
+    }
+}
+
+/// A history item with everything known about it, for a detail view.
+#[derive(Debug, Clone, Serialize)]
+pub struct ClipboardItemDetails {
+    #[serde(flatten)]
+    pub item: ClipboardItem,
+    pub is_pinned: bool,
+    /// Slots currently holding this item
+    pub slot_numbers: Vec<u32>,
+    /// Size of the encrypted attachment in bytes, for image/file items
+    pub attachment_size: Option<i64>,
+}
+
+/// A history item for list views, with only the first `PREVIEW_CHARS`
+/// characters in `content`. The full content is fetched by ID when needed.
+#[derive(Debug, Clone, Serialize)]
+pub struct ClipboardItemPreview {
+    #[serde(flatten)]
+    pub item: ClipboardItem,
+    /// Length of the full content, in characters
+    pub content_length: usize,
+    pub is_truncated: bool,
+    /// Page title, for items that are a link whose title was fetched
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub link_title: Option<String>,
+    /// Programming language, for items that look like code
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+}
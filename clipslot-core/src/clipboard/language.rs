@@ -0,0 +1,170 @@
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+/// Languages `detect` can recognise, as stored and shown in the history window.
+pub const LANGUAGES: &[&str] = &[
+    "c", "cpp", "csharp", "css", "go", "html", "java", "javascript", "json", "python", "rust",
+    "shell", "sql", "typescript",
+];
+
+/// Only the start of long content is looked at.
+const MAX_DETECT_BYTES: usize = 16 * 1024;
+/// Score a language needs before content counts as code in it.
+const MIN_SCORE: u32 = 3;
+
+/// Patterns that suggest a language, and how strongly.
+const RULES: &[(&str, &str, u32)] = &[
+    ("c", r#"(?m)^\s*#include\s*[<"]"#, 2),
+    ("c", r"\b(printf|malloc|free|sizeof)\s*\(", 2),
+    ("c", r"\bint\s+main\s*\(", 2),
+    ("cpp", r#"(?m)^\s*#include\s*[<"]"#, 2),
+    ("cpp", r"\bstd::\w+", 3),
+    ("cpp", r"\b(cout|cin)\s*(<<|>>)", 1),
+    ("cpp", r"\btemplate\s*<", 3),
+    ("csharp", r"(?m)^\s*using\s+System", 3),
+    ("csharp", r"\bConsole\.Write(Line)?\(", 3),
+    ("csharp", r"\bnamespace\s+[\w.]+", 2),
+    ("csharp", r"\b(public|private)\s+(async\s+)?\w+\s+\w+\s*\{\s*get;", 3),
+    ("css", r"(?m)^\s*[\w-]+\s*:\s*[^;{}]+;\s*$", 1),
+    ("css", r"(?m)^\s*[.#]?[\w-]+(\s*[,>+~]?\s*[.#:]?[\w-]+)*\s*\{", 1),
+    ("css", r"@(media|import|keyframes|font-face)\b", 3),
+    ("css", r"\b\d+(px|rem|em|vh|vw)\b|#[0-9a-fA-F]{6}\b", 1),
+    ("go", r"(?m)^package\s+\w+\s*$", 3),
+    ("go", r"\bfunc\s+(\(\w+\s+\*?\w+\)\s*)?\w+\s*\(", 3),
+    ("go", r":=", 1),
+    ("go", r"\bfmt\.\w+\(", 3),
+    ("html", r"(?i)<!doctype html|<(html|head|body|div|span|p|a|ul|li|table|script)[\s>]", 3),
+    ("html", r"</\w+>", 1),
+    ("java", r"\bpublic\s+(static\s+)?(final\s+)?(class|void|interface)\b", 3),
+    ("java", r"\bSystem\.out\.print", 3),
+    ("java", r"@Override\b", 3),
+    ("java", r"\bprivate\s+(final\s+)?\w+(<[\w, ]+>)?\s+\w+\s*;", 2),
+    ("javascript", r"\b(const|let|var)\s+\w+\s*=", 2),
+    ("javascript", r"=>", 1),
+    ("javascript", r"\bfunction\s*\w*\s*\(", 2),
+    ("javascript", r"\bconsole\.\w+\(", 3),
+    ("javascript", r"\brequire\(|\bmodule\.exports\b", 3),
+    ("javascript", r"===|!==", 2),
+    ("javascript", r"\b(document|window)\.\w+", 2),
+    ("python", r"(?m)^\s*def\s+\w+\s*\(.*\)\s*(->.*)?:\s*$", 3),
+    ("python", r"(?m)^\s*class\s+\w+(\(.*\))?:\s*$", 3),
+    ("python", r"(?m)^\s*(from\s+[\w.]+\s+)?import\s+[\w.]+(\s+as\s+\w+)?\s*$", 2),
+    ("python", r"(?m)^\s*(if|elif|else|for|while|try|except|with)\b.*:\s*$", 2),
+    ("python", r"\bself\.\w+", 1),
+    ("python", r"\b(None|True|False)\b", 1),
+    ("rust", r"\bfn\s+\w+\s*[<(]", 2),
+    ("rust", r"\blet\s+(mut\s+)?\w+(:\s*[\w<>&]+)?\s*=", 2),
+    ("rust", r"\bimpl(<.*>)?\s+[\w:]+", 2),
+    ("rust", r"#!?\[\w+", 3),
+    ("rust", r"\b\w+!\(", 2),
+    ("rust", r"\b(Option|Result|Vec|String|Box)<", 2),
+    ("rust", r"&(mut\s+)?self\b|\bpub(\(crate\))?\s+(fn|struct|enum|mod)\b", 3),
+    ("shell", r"(?m)^#!.*\b(ba|z)?sh\b", 5),
+    ("shell", r"(?m)^\s*(\$\s+)?(sudo|cd|ls|echo|export|chmod|mkdir|rm|curl)\s", 2),
+    ("shell", r"(?m)^\s*(\$\s+)?(git|npm|npx|cargo|apt(-get)?|brew|docker|kubectl)\s", 2),
+    ("shell", r"\|\s*(grep|awk|sed|xargs|sort|head|tail|wc)\b", 3),
+    ("shell", r"\$\{?\w+\}?|\$\(", 1),
+    ("shell", r"\s--?[a-z][\w-]*", 1),
+    ("sql", r"\bSELECT\b[\s\S]+?\bFROM\b", 3),
+    ("sql", r"(?i)\bselect\s+[\w*,.\s()]+?\s+from\s+\w+", 2),
+    ("sql", r"(?i)\b(insert\s+into|delete\s+from|create\s+(table|index|view)|alter\s+table)\b", 3),
+    ("sql", r"(?i)\bupdate\s+\w+\s+set\b", 3),
+    ("sql", r"(?i)\b(where|inner join|left join|group by|order by)\b", 1),
+    ("typescript", r":\s*(string|number|boolean|any|void|unknown)(\[\])?\b", 3),
+    ("typescript", r"\binterface\s+\w+\s*\{", 3),
+    ("typescript", r"\btype\s+\w+\s*=", 2),
+    ("typescript", r"\b(const|let)\s+\w+\s*:\s*\w+", 2),
+    ("typescript", r"\bas\s+(const|string|number|any)\b", 2),
+    ("typescript", r"\b(const|let|var)\s+\w+\s*=|=>|===", 1),
+];
+
+fn rules() -> &'static [(&'static str, Regex, u32)] {
+    static RULES_COMPILED: OnceLock<Vec<(&'static str, Regex, u32)>> = OnceLock::new();
+    RULES_COMPILED.get_or_init(|| {
+        RULES
+            .iter()
+            .map(|&(language, pattern, weight)| {
+                (language, Regex::new(pattern).expect("valid language rule"), weight)
+            })
+            .collect()
+    })
+}
+
+/// The programming language `content` is written in, if it looks like code.
+pub fn detect(content: &str) -> Option<&'static str> {
+    let mut end = content.len().min(MAX_DETECT_BYTES);
+    while !content.is_char_boundary(end) {
+        end -= 1;
+    }
+    let text = content[..end].trim();
+    if text.len() < 8 {
+        return None;
+    }
+    if (text.starts_with('{') || text.starts_with('['))
+        && serde_json::from_str::<serde_json::Value>(text).is_ok()
+    {
+        return Some("json");
+    }
+
+    let mut scores: Vec<(&'static str, u32)> = Vec::new();
+    for (language, re, weight) in rules() {
+        if !re.is_match(text) {
+            continue;
+        }
+        match scores.iter_mut().find(|(l, _)| l == language) {
+            Some((_, score)) => *score += weight,
+            None => scores.push((language, *weight)),
+        }
+    }
+    // Ties go to the language listed first in the rules
+    scores
+        .into_iter()
+        .filter(|&(_, score)| score >= MIN_SCORE)
+        .fold(None, |best: Option<(&str, u32)>, (language, score)| match best {
+            Some((_, best_score)) if best_score >= score => best,
+            _ => Some((language, score)),
+        })
+        .map(|(language, _)| language)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detects_languages() {
+        let cases = [
+            ("sql", "SELECT id, name FROM users WHERE created_at > now() ORDER BY id;"),
+            ("sql", "update accounts set balance = 0 where id = 4"),
+            ("rust", "fn main() {\n    let mut v: Vec<u32> = Vec::new();\n    dbg!(v);\n}"),
+            ("python", "def greet(name):\n    if name:\n        print(f\"hi {name}\")\n"),
+            ("javascript", "const total = items.reduce((a, b) => a + b);\nconsole.log(total);"),
+            ("typescript", "interface User {\n  id: number;\n  name: string;\n}"),
+            ("go", "package main\n\nfunc main() {\n\tfmt.Println(\"hi\")\n}"),
+            ("java", "public class Main {\n  void run() {\n    System.out.println(1);\n  }\n}"),
+            ("cpp", "#include <vector>\nstd::vector<int> v;"),
+            ("shell", "#!/bin/bash\nset -e\necho \"$HOME\""),
+            ("shell", "git log --oneline | grep fix"),
+            ("html", "<div class=\"card\"><p>Hello</p></div>"),
+            ("css", ".card {\n  padding: 8px;\n  color: #333333;\n}"),
+            ("json", "{\"id\": 1, \"tags\": [\"a\", \"b\"]}"),
+        ];
+        for (expected, code) in cases {
+            assert_eq!(detect(code), Some(expected), "{}", code);
+            assert!(LANGUAGES.contains(&expected));
+        }
+    }
+
+    #[test]
+    fn test_prose_is_not_code() {
+        for text in [
+            "Select the best option from the list and let me know.",
+            "Meeting moved to 3pm tomorrow, see you there!",
+            "https://github.com/tokio-rs/axum",
+            "hello",
+        ] {
+            assert_eq!(detect(text), None, "{}", text);
+        }
+    }
+}
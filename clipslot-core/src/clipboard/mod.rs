@@ -0,0 +1,3 @@
+pub mod item;
+pub mod language;
+pub mod timestamp;
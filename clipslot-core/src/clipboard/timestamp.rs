@@ -0,0 +1,69 @@
+//! Timestamps are stored as UTC milliseconds; these turn them into the
+//! user's local time.
+
+use std::fmt::Display;
+
+use chrono::{DateTime, Local, SecondsFormat, TimeZone, Utc};
+
+fn in_zone<Tz: TimeZone>(millis: i64, zone: &Tz) -> DateTime<Tz> {
+    DateTime::<Utc>::from_timestamp_millis(millis)
+        .unwrap_or_default()
+        .with_timezone(zone)
+}
+
+/// RFC 3339 with the local UTC offset, e.g. `2026-10-16T13:57:52.123+02:00`.
+pub fn to_local_iso(millis: i64) -> String {
+    to_iso_in(millis, &Local)
+}
+
+/// Local calendar day as `YYYY-MM-DD`, for grouping by day.
+pub fn local_day(millis: i64) -> String {
+    local_day_in(millis, &Local)
+}
+
+/// Local time in a `chrono` strftime format, e.g. `"%H:%M"`.
+pub fn format_local(millis: i64, format: &str) -> String {
+    in_zone(millis, &Local).format(format).to_string()
+}
+
+fn to_iso_in<Tz: TimeZone>(millis: i64, zone: &Tz) -> String
+where
+    Tz::Offset: Display,
+{
+    in_zone(millis, zone).to_rfc3339_opts(SecondsFormat::Millis, false)
+}
+
+fn local_day_in<Tz: TimeZone>(millis: i64, zone: &Tz) -> String
+where
+    Tz::Offset: Display,
+{
+    in_zone(millis, zone).format("%Y-%m-%d").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::FixedOffset;
+
+    // 2026-10-15T23:30:00Z
+    const LATE_EVENING_UTC: i64 = 1_792_107_000_000;
+
+    #[test]
+    fn test_iso_keeps_offset() {
+        let berlin = FixedOffset::east_opt(2 * 3600).unwrap();
+        assert_eq!(
+            to_iso_in(LATE_EVENING_UTC + 5, &berlin),
+            "2026-10-16T01:30:00.005+02:00"
+        );
+        assert_eq!(to_iso_in(LATE_EVENING_UTC, &Utc), "2026-10-15T23:30:00.000+00:00");
+    }
+
+    #[test]
+    fn test_day_follows_time_zone() {
+        let berlin = FixedOffset::east_opt(2 * 3600).unwrap();
+        let new_york = FixedOffset::west_opt(4 * 3600).unwrap();
+        assert_eq!(local_day_in(LATE_EVENING_UTC, &Utc), "2026-10-15");
+        assert_eq!(local_day_in(LATE_EVENING_UTC, &berlin), "2026-10-16");
+        assert_eq!(local_day_in(LATE_EVENING_UTC, &new_york), "2026-10-15");
+    }
+}
@@ -0,0 +1,86 @@
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Most tokens sent for one item (the server's limit).
+const MAX_TOKENS: usize = 256;
+const MIN_WORD_CHARS: usize = 2;
+const MAX_WORD_CHARS: usize = 64;
+
+/// Derives search tokens the server can match without learning the words:
+/// HMAC-SHA256 of each normalized word, keyed by a key derived from the master
+/// key so every linked device produces the same tokens.
+pub struct BlindIndex {
+    key: [u8; 32],
+}
+
+impl BlindIndex {
+    pub fn new(master_key: &[u8; 32]) -> Self {
+        // Separate key, so tokens reveal nothing about the encryption key
+        let mut mac = HmacSha256::new_from_slice(master_key).expect("HMAC accepts any key length");
+        mac.update(b"clipslot blind index v1");
+        Self {
+            key: mac.finalize().into_bytes().into(),
+        }
+    }
+
+    /// Token for one search term, normalized the same way as indexed text.
+    pub fn token(&self, word: &str) -> String {
+        let mut mac = HmacSha256::new_from_slice(&self.key).expect("HMAC accepts any key length");
+        mac.update(word.to_lowercase().as_bytes());
+        hex(&mac.finalize().into_bytes())
+    }
+
+    /// Tokens for every distinct word in `text`.
+    pub fn tokens(&self, text: &str) -> Vec<String> {
+        normalize_words(text)
+            .iter()
+            .map(|w| self.token(w))
+            .collect()
+    }
+}
+
+/// Lowercased words of 2-64 letters or digits, deduplicated, in first-seen order.
+fn normalize_words(text: &str) -> Vec<String> {
+    let mut words: Vec<String> = Vec::new();
+    for word in text.split(|c: char| !c.is_alphanumeric()) {
+        let len = word.chars().count();
+        if !(MIN_WORD_CHARS..=MAX_WORD_CHARS).contains(&len) {
+            continue;
+        }
+        let word = word.to_lowercase();
+        if !words.contains(&word) {
+            words.push(word);
+            if words.len() == MAX_TOKENS {
+                break;
+            }
+        }
+    }
+    words
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokens_are_normalized_and_deduplicated() {
+        let index = BlindIndex::new(&[42u8; 32]);
+        let tokens = index.tokens("Hello, hello WORLD! a");
+
+        assert_eq!(tokens, vec![index.token("hello"), index.token("world")]);
+        assert_eq!(tokens[0].len(), 64);
+    }
+
+    #[test]
+    fn test_tokens_depend_on_key() {
+        let a = BlindIndex::new(&[1u8; 32]);
+        let b = BlindIndex::new(&[2u8; 32]);
+        assert_ne!(a.token("secret"), b.token("secret"));
+    }
+}
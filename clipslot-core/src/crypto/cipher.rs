@@ -3,18 +3,39 @@ use aes_gcm::{
     Aes256Gcm, Nonce,
 };
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use hmac::{Hmac, Mac};
 use rand::RngCore;
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
 
 const ENC_PREFIX: &str = "ENC:";
 
 pub struct CryptoEngine {
     cipher: Aes256Gcm,
+    /// Key of the history integrity chain (see `storage::integrity`)
+    mac_key: [u8; 32],
 }
 
 impl CryptoEngine {
     pub fn new(key: &[u8; 32]) -> Self {
         let cipher = Aes256Gcm::new_from_slice(key).expect("valid 256-bit key");
-        Self { cipher }
+        // Separate key, so MACs reveal nothing about the encryption key
+        let mut mac =
+            <HmacSha256 as Mac>::new_from_slice(key).expect("HMAC accepts any key length");
+        mac.update(b"clipslot history integrity v1");
+        Self {
+            cipher,
+            mac_key: mac.finalize().into_bytes().into(),
+        }
+    }
+
+    /// HMAC-SHA256 of `message` with the integrity key, as hex.
+    pub fn integrity_mac(&self, message: &str) -> String {
+        let mut mac = <HmacSha256 as Mac>::new_from_slice(&self.mac_key)
+            .expect("HMAC accepts any key length");
+        mac.update(message.as_bytes());
+        format!("{:x}", mac.finalize().into_bytes())
     }
 
     /// Encrypt plaintext → "ENC:" + base64(nonce + ciphertext)
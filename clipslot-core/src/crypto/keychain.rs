@@ -50,20 +50,31 @@ pub fn import_master_key(key: &[u8; 32]) -> Result<(), String> {
     // Always save to file fallback
     save_to_file(key);
 
-    println!("[ClipSlot] Master key imported and stored");
+    tracing::info!("Master key imported and stored");
     Ok(())
 }
 
+/// Whether there's a database whose contents a newly generated key couldn't
+/// read.
+fn has_stored_data() -> bool {
+    APP_DATA_DIR
+        .get()
+        .is_some_and(|dir| dir.join("clipslot.db").exists())
+}
+
 /// Retrieve the master encryption key from the OS keychain,
 /// falling back to a key file in the app data directory.
 /// If neither exists, generate and store a new key in both.
+///
+/// If the keychain refuses access (e.g. the user denied its prompt) and there
+/// is no key file, no key is generated while history exists, since a new key
+/// couldn't read it.
 pub fn get_or_create_master_key() -> Result<[u8; 32], String> {
-    let entry =
-        keyring::Entry::new(SERVICE, USER).map_err(|e| format!("Keyring entry error: {}", e))?;
+    let entry = keyring::Entry::new(SERVICE, USER).map_err(|e| e.to_string());
 
     // Try keychain first
-    match entry.get_password() {
-        Ok(encoded) => {
+    let refused = match entry.as_ref().map(|entry| entry.get_password()) {
+        Ok(Ok(encoded)) => {
             let bytes = BASE64
                 .decode(&encoded)
                 .map_err(|e| format!("Failed to decode key from keychain: {}", e))?;
@@ -77,29 +88,39 @@ pub fn get_or_create_master_key() -> Result<[u8; 32], String> {
             key.copy_from_slice(&bytes);
             // Keep file in sync
             save_to_file(&key);
-            println!("[ClipSlot] Encryption key loaded from keychain");
-            Ok(key)
+            tracing::info!("Encryption key loaded from keychain");
+            return Ok(key);
         }
-        Err(keyring::Error::NoEntry) | Err(_) => {
-            // Keychain failed — try file-based fallback
-            if let Some(key) = load_from_file() {
-                // Restore to keychain (best-effort)
-                let encoded = BASE64.encode(&key);
-                let _ = entry.set_password(&encoded);
-                println!("[ClipSlot] Encryption key loaded from file fallback");
-                return Ok(key);
-            }
+        Ok(Err(keyring::Error::NoEntry)) => None,
+        Ok(Err(e)) => Some(e.to_string()),
+        Err(e) => Some(e.clone()),
+    };
 
-            // No key anywhere — generate a new one
-            let mut key = [0u8; 32];
-            rand::thread_rng().fill_bytes(&mut key);
-
-            let encoded = BASE64.encode(&key);
-            let _ = entry.set_password(&encoded);
-            save_to_file(&key);
+    // Keychain failed — try file-based fallback
+    if let Some(key) = load_from_file() {
+        // Restore to keychain (best-effort)
+        if let Ok(entry) = &entry {
+            let _ = entry.set_password(&BASE64.encode(key));
+        }
+        tracing::info!("Encryption key loaded from file fallback");
+        return Ok(key);
+    }
 
-            println!("[ClipSlot] New encryption key generated and stored");
-            Ok(key)
+    if let Some(reason) = refused {
+        if has_stored_data() {
+            return Err(format!("Keychain access denied: {}", reason));
         }
     }
+
+    // No key anywhere — generate a new one
+    let mut key = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut key);
+
+    if let Ok(entry) = &entry {
+        let _ = entry.set_password(&BASE64.encode(key));
+    }
+    save_to_file(&key);
+
+    tracing::info!("New encryption key generated and stored");
+    Ok(key)
 }
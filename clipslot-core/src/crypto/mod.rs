@@ -1,2 +1,3 @@
+pub mod blind_index;
 pub mod cipher;
 pub mod keychain;
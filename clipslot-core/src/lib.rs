@@ -0,0 +1,8 @@
+//! Storage, encryption and clipboard items for ClipSlot, without any Tauri
+//! dependency, so the app, tools and tests can share them.
+
+pub mod clipboard;
+pub mod config;
+pub mod crypto;
+pub mod slots;
+pub mod storage;
@@ -0,0 +1,44 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SlotInfo {
+    pub slot_number: u32,
+    pub name: String,
+    pub content: Option<String>,
+    pub content_preview: Option<String>,
+    pub updated_at: i64,
+    pub is_empty: bool,
+}
+
+/// A slot shared with a team. Kept apart from the personal slots, which a
+/// teammate's writes must never touch.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TeamSlot {
+    pub team_id: String,
+    pub slot_number: u32,
+    pub content: String,
+    pub updated_at: i64,
+}
+
+/// A slot without its content, for lists of slots. The content is fetched
+/// for one slot at a time, when it's needed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SlotSummary {
+    pub slot_number: u32,
+    pub name: String,
+    pub content_preview: Option<String>,
+    pub updated_at: i64,
+    pub is_empty: bool,
+}
+
+impl From<SlotInfo> for SlotSummary {
+    fn from(slot: SlotInfo) -> Self {
+        SlotSummary {
+            slot_number: slot.slot_number,
+            name: slot.name,
+            content_preview: slot.content_preview,
+            updated_at: slot.updated_at,
+            is_empty: slot.is_empty,
+        }
+    }
+}
@@ -0,0 +1,2306 @@
+use rayon::prelude::*;
+use rusqlite::functions::FunctionFlags;
+use rusqlite::{params, Connection, OptionalExtension, Result as SqliteResult, Row};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::panic::AssertUnwindSafe;
+use std::sync::{mpsc, Arc, Mutex};
+use tokio::sync::{broadcast, oneshot};
+
+use crate::clipboard::item::{ClipboardItem, ClipboardItemDetails, ClipboardItemPreview};
+use crate::clipboard::{language, timestamp};
+use crate::crypto::cipher::CryptoEngine;
+use crate::slots::{SlotInfo, SlotSummary, TeamSlot};
+use crate::storage::integrity::{self, IntegrityReport, Link, Mark};
+use crate::storage::page::{HistoryCursor, HistoryGroup, HistoryGroupBy, HistoryPage};
+use crate::storage::search::{SearchFilters, TextMatcher};
+use crate::storage::slot_store::SlotStore;
+
+/// Most items a search returns.
+const MAX_SEARCH_RESULTS: usize = 100;
+/// Items decrypted in parallel per round while searching.
+const SEARCH_BATCH: usize = 512;
+/// History items kept until a limit is chosen in settings.
+pub const DEFAULT_HISTORY_LIMIT: u32 = 500;
+/// Recent history items in the tray menu until a count is chosen in settings.
+pub const DEFAULT_TRAY_HISTORY_COUNT: u32 = 5;
+/// Most recent history items the tray menu can show.
+pub const MAX_TRAY_HISTORY_COUNT: u32 = 10;
+/// Slots stored locally; the account's slot count picks how many are in use.
+pub const MAX_SLOT_COUNT: u32 = 10;
+/// Seconds in which the same content isn't captured twice, until a window
+/// is chosen in settings.
+pub const DEFAULT_DEDUP_WINDOW_SECS: u32 = 2;
+
+/// A history item pulled from the sync server, still encrypted.
+pub struct SyncedItem {
+    pub id: String,
+    pub encrypted_content: String,
+    pub content_hash: String,
+    pub device_id: String,
+    pub created_at: i64,
+}
+
+/// What `Database::open` did about a database file that was damaged.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Recovery {
+    /// The file was fine.
+    None,
+    /// Started a fresh file and copied `rows` readable rows into it from the
+    /// damaged one, kept at `backup`.
+    Salvaged { backup: PathBuf, rows: usize },
+    /// Nothing could be read from the damaged file, kept at `backup`; started
+    /// empty.
+    Reset { backup: PathBuf },
+}
+
+/// Work queued for the database thread.
+type Job = Box<dyn FnOnce() + Send>;
+
+pub struct Database {
+    conn: Mutex<Connection>,
+    crypto: Arc<CryptoEngine>,
+    /// Number of every slot whose content or name changed, whatever changed it
+    slot_changes: broadcast::Sender<u32>,
+    slot_store: SlotStore,
+    /// Queue of the thread that runs work handed to `call`
+    jobs: mpsc::Sender<Job>,
+}
+
+impl Database {
+    pub fn new(data_dir: PathBuf, crypto: Arc<CryptoEngine>) -> SqliteResult<Self> {
+        std::fs::create_dir_all(&data_dir).ok();
+        let db_path = data_dir.join("clipslot.db");
+        tracing::info!("Database: {}", db_path.display());
+
+        Self::with_connection(Connection::open(&db_path)?, crypto)
+    }
+
+    /// A database kept only in memory, for when history can't be saved
+    /// (e.g. the encryption key is out of reach). Gone when the app quits.
+    pub fn in_memory(crypto: Arc<CryptoEngine>) -> SqliteResult<Self> {
+        tracing::info!("Database: in memory");
+        Self::with_connection(Connection::open_in_memory()?, crypto)
+    }
+
+    fn with_connection(conn: Connection, crypto: Arc<CryptoEngine>) -> SqliteResult<Self> {
+        // Day of a timestamp in the user's time zone, for grouping by day
+        conn.create_scalar_function("local_day", 1, FunctionFlags::SQLITE_UTF8, |ctx| {
+            Ok(timestamp::local_day(ctx.get(0)?))
+        })?;
+        // Keyed MAC for the history integrity chain; only connections opened
+        // here can extend it
+        let mac_crypto = crypto.clone();
+        conn.create_scalar_function("integrity_mac", 1, FunctionFlags::SQLITE_UTF8, move |ctx| {
+            Ok(mac_crypto.integrity_mac(&ctx.get::<String>(0)?))
+        })?;
+        let (slot_changes, _) = broadcast::channel(64);
+        let (jobs, queue) = mpsc::channel::<Job>();
+        std::thread::Builder::new()
+            .name("clipslot-db".to_string())
+            .spawn(move || {
+                // Ends once the database is dropped and the queue closes
+                for job in queue {
+                    if std::panic::catch_unwind(AssertUnwindSafe(job)).is_err() {
+                        tracing::error!("Database job panicked");
+                    }
+                }
+            })
+            .expect("failed to start the database thread");
+        let db = Self {
+            conn: Mutex::new(conn),
+            crypto,
+            slot_changes,
+            slot_store: SlotStore::default(),
+            jobs,
+        };
+        db.run_migrations()?;
+        db.migrate_encrypt_existing();
+        Ok(db)
+    }
+
+    /// Open the database like `new`, but if the file won't open or fails its
+    /// integrity check, move it aside and start a fresh one with whatever
+    /// rows can still be read from it.
+    pub fn open(data_dir: PathBuf, crypto: Arc<CryptoEngine>) -> Result<(Self, Recovery), String> {
+        std::fs::create_dir_all(&data_dir).ok();
+        let db_path = data_dir.join("clipslot.db");
+        let problem = match check_integrity(&db_path) {
+            Ok(()) => match Self::new(data_dir.clone(), crypto.clone()) {
+                Ok(db) => return Ok((db, Recovery::None)),
+                Err(e) => e.to_string(),
+            },
+            Err(problem) => problem,
+        };
+        tracing::error!("Database is damaged ({}), recovering", problem);
+
+        let backup = data_dir.join(format!(
+            "clipslot.db.corrupt-{}",
+            chrono::Utc::now().format("%Y%m%d-%H%M%S")
+        ));
+        for suffix in ["", "-wal", "-shm", "-journal"] {
+            let from = PathBuf::from(format!("{}{}", db_path.display(), suffix));
+            if from.exists() {
+                let to = format!("{}{}", backup.display(), suffix);
+                std::fs::rename(&from, &to)
+                    .map_err(|e| format!("Failed to move damaged database aside: {}", e))?;
+            }
+        }
+        tracing::info!("Damaged database moved to {}", backup.display());
+
+        let db = Self::new(data_dir, crypto)
+            .map_err(|e| format!("Failed to create a new database: {}", e))?;
+        let rows = db.salvage_from(&backup);
+        let recovery = if rows > 0 {
+            Recovery::Salvaged { backup, rows }
+        } else {
+            Recovery::Reset { backup }
+        };
+        tracing::info!("Database recovery: {:?}", recovery);
+        Ok((db, recovery))
+    }
+
+    /// Copy what can be read of every table from the database file at
+    /// `path`, table by table, skipping any that fail. Number of rows copied.
+    fn salvage_from(&self, path: &Path) -> usize {
+        let conn = self.conn.lock().unwrap();
+        if let Err(e) = conn.execute("ATTACH DATABASE ?1 AS damaged", [path.to_string_lossy()]) {
+            tracing::error!("Can't read damaged database: {}", e);
+            return 0;
+        }
+        let tables: Vec<String> = conn
+            .prepare("SELECT name FROM main.sqlite_master WHERE type = 'table'")
+            .and_then(|mut stmt| stmt.query_map([], |row| row.get(0))?.collect())
+            .unwrap_or_default();
+
+        let mut copied = 0;
+        for table in tables {
+            let columns = |schema: &str| -> SqliteResult<Vec<String>> {
+                let sql = format!("PRAGMA {}.table_info(\"{}\")", schema, table);
+                let mut stmt = conn.prepare(&sql)?;
+                let names = stmt.query_map([], |row| row.get(1))?.collect();
+                names
+            };
+            let result = columns("damaged").and_then(|old| {
+                let shared: Vec<String> = columns("main")?
+                    .into_iter()
+                    .filter(|c| old.contains(c))
+                    .map(|c| format!("\"{}\"", c))
+                    .collect();
+                if shared.is_empty() {
+                    return Ok(0);
+                }
+                let shared = shared.join(", ");
+                // Replace, so the defaults just written give way to the user's
+                conn.execute(
+                    &format!(
+                        "INSERT OR REPLACE INTO main.\"{t}\" ({c}) SELECT {c} FROM damaged.\"{t}\"",
+                        t = table,
+                        c = shared
+                    ),
+                    [],
+                )
+            });
+            match result {
+                Ok(rows) => copied += rows,
+                Err(e) => tracing::warn!("Couldn't salvage table {}: {}", table, e),
+            }
+        }
+        let _ = conn.execute("DETACH DATABASE damaged", []);
+        copied
+    }
+
+    /// Run `f` on the database thread and wait for it without blocking the
+    /// async runtime. Async code (sync tasks, commands, the local API) goes
+    /// through here; plain threads like the clipboard monitor call the
+    /// methods directly.
+    pub async fn call<R, F>(self: &Arc<Self>, f: F) -> R
+    where
+        F: FnOnce(&Database) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let db = Arc::clone(self);
+        let (tx, rx) = oneshot::channel();
+        let job: Job = Box::new(move || {
+            let _ = tx.send(f(&db));
+        });
+        self.jobs.send(job).expect("database thread stopped");
+        rx.await.expect("database job panicked")
+    }
+
+    fn run_migrations(&self) -> SqliteResult<()> {
+        let conn = self.conn.lock().unwrap();
+
+        conn.execute_batch(
+            "
+            CREATE TABLE IF NOT EXISTS clipboard_items (
+                id TEXT PRIMARY KEY,
+                content TEXT NOT NULL,
+                content_hash TEXT NOT NULL,
+                content_type TEXT NOT NULL DEFAULT 'text/plain',
+                source_app TEXT,
+                device_id TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                is_promoted INTEGER NOT NULL DEFAULT 0
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_created_at ON clipboard_items(created_at DESC);
+            CREATE INDEX IF NOT EXISTS idx_content_hash ON clipboard_items(content_hash);
+            CREATE INDEX IF NOT EXISTS idx_is_promoted ON clipboard_items(is_promoted);
+
+            CREATE TABLE IF NOT EXISTS app_config (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS slots (
+                slot_number INTEGER PRIMARY KEY,
+                item_id TEXT REFERENCES clipboard_items(id),
+                name TEXT NOT NULL,
+                updated_at INTEGER NOT NULL DEFAULT 0
+            );
+
+            -- Slots shared with teams, apart from the personal ones; content
+            -- re-encrypted with our own key
+            CREATE TABLE IF NOT EXISTS team_slots (
+                team_id TEXT NOT NULL,
+                slot_number INTEGER NOT NULL,
+                content TEXT NOT NULL,
+                updated_at INTEGER NOT NULL,
+                updated_by TEXT NOT NULL,
+                PRIMARY KEY (team_id, slot_number)
+            );
+
+            -- Encrypted image/file content of synced items, kept out of clipboard_items
+            CREATE TABLE IF NOT EXISTS attachments (
+                item_id TEXT PRIMARY KEY,
+                data BLOB NOT NULL
+            );
+
+            CREATE TRIGGER IF NOT EXISTS delete_item_attachment
+            AFTER DELETE ON clipboard_items
+            BEGIN
+                DELETE FROM attachments WHERE item_id = OLD.id;
+            END;
+
+            -- Favorites: kept through clear-history and the history limit
+            CREATE TABLE IF NOT EXISTS pinned_items (
+                item_id TEXT PRIMARY KEY,
+                pinned_at INTEGER NOT NULL
+            );
+
+            CREATE TRIGGER IF NOT EXISTS delete_item_pin
+            AFTER DELETE ON clipboard_items
+            BEGIN
+                DELETE FROM pinned_items WHERE item_id = OLD.id;
+            END;
+
+            -- Encrypted start of each history item, so lists don't decrypt
+            -- whole contents. Filled at capture, or on first listing.
+            CREATE TABLE IF NOT EXISTS item_previews (
+                item_id TEXT PRIMARY KEY,
+                preview TEXT NOT NULL,
+                content_length INTEGER NOT NULL
+            );
+
+            CREATE TRIGGER IF NOT EXISTS delete_item_preview
+            AFTER DELETE ON clipboard_items
+            BEGIN
+                DELETE FROM item_previews WHERE item_id = OLD.id;
+            END;
+
+            -- Encrypted page titles of items that are links, shown instead of the URL
+            CREATE TABLE IF NOT EXISTS link_titles (
+                item_id TEXT PRIMARY KEY,
+                title TEXT NOT NULL
+            );
+
+            CREATE TRIGGER IF NOT EXISTS delete_item_link_title
+            AFTER DELETE ON clipboard_items
+            BEGIN
+                DELETE FROM link_titles WHERE item_id = OLD.id;
+            END;
+
+            -- Programming language of items that look like code, stored with the preview
+            CREATE TABLE IF NOT EXISTS item_languages (
+                item_id TEXT PRIMARY KEY,
+                language TEXT NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_item_language ON item_languages(language);
+
+            CREATE TRIGGER IF NOT EXISTS delete_item_language
+            AFTER DELETE ON clipboard_items
+            BEGIN
+                DELETE FROM item_languages WHERE item_id = OLD.id;
+            END;
+
+            -- How often each item has been copied or pasted again, for ranking
+            CREATE TABLE IF NOT EXISTS item_usage (
+                item_id TEXT PRIMARY KEY,
+                use_count INTEGER NOT NULL,
+                last_used_at INTEGER NOT NULL
+            );
+
+            CREATE TRIGGER IF NOT EXISTS delete_item_usage
+            AFTER DELETE ON clipboard_items
+            BEGIN
+                DELETE FROM item_usage WHERE item_id = OLD.id;
+            END;
+
+            -- Tamper evidence for history while integrity mode is on; see
+            -- `storage::integrity`. Filled by triggers created with the mode.
+            CREATE TABLE IF NOT EXISTS integrity_chain (
+                seq INTEGER PRIMARY KEY,
+                item_id TEXT NOT NULL,
+                content_hash TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                mac TEXT NOT NULL,
+                deleted TEXT
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_integrity_item ON integrity_chain(item_id);
+
+            -- Signed newest link ('head') and last compacted one ('checkpoint')
+            CREATE TABLE IF NOT EXISTS integrity_marks (
+                name TEXT PRIMARY KEY,
+                seq INTEGER NOT NULL,
+                mac TEXT NOT NULL,
+                sig TEXT NOT NULL
+            );
+            ",
+        )?;
+
+        // Set default settings if not present
+        conn.execute(
+            "INSERT OR IGNORE INTO app_config (key, value) VALUES ('history_limit', ?1)",
+            params![DEFAULT_HISTORY_LIMIT.to_string()],
+        )?;
+        conn.execute(
+            "INSERT OR IGNORE INTO app_config (key, value) VALUES ('tray_history_count', ?1)",
+            params![DEFAULT_TRAY_HISTORY_COUNT.to_string()],
+        )?;
+        conn.execute(
+            "INSERT OR IGNORE INTO app_config (key, value) VALUES ('tray_left_click', 'menu')",
+            [],
+        )?;
+        conn.execute(
+            "INSERT OR IGNORE INTO app_config (key, value) VALUES ('check_updates_on_startup', 'true')",
+            [],
+        )?;
+        conn.execute(
+            "INSERT OR IGNORE INTO app_config (key, value) VALUES ('auto_clear_on_quit', 'false')",
+            [],
+        )?;
+        conn.execute(
+            "INSERT OR IGNORE INTO app_config (key, value) VALUES ('excluded_apps', '[]')",
+            [],
+        )?;
+        conn.execute(
+            "INSERT OR IGNORE INTO app_config (key, value) VALUES ('sync_server_url', ?1)",
+            [crate::config::SYNC_SERVER_URL],
+        )?;
+        // Migrate users still pointing at old localhost default
+        conn.execute(
+            "UPDATE app_config SET value = ?1 WHERE key = 'sync_server_url' AND value = 'http://localhost:3000'",
+            [crate::config::SYNC_SERVER_URL],
+        )?;
+        conn.execute(
+            "INSERT OR IGNORE INTO app_config (key, value) VALUES ('history_sync_enabled', 'false')",
+            [],
+        )?;
+        conn.execute(
+            "INSERT OR IGNORE INTO app_config (key, value) VALUES ('search_index_enabled', 'false')",
+            [],
+        )?;
+        conn.execute(
+            "INSERT OR IGNORE INTO app_config (key, value) VALUES ('local_api_enabled', 'false')",
+            [],
+        )?;
+
+        // Pre-populate every slot; only the first `slot_count` are shown and synced
+        for i in 1..=MAX_SLOT_COUNT {
+            conn.execute(
+                "INSERT OR IGNORE INTO slots (slot_number, name, updated_at) VALUES (?1, ?2, 0)",
+                params![i, format!("Slot {}", i)],
+            )?;
+        }
+
+        tracing::info!("Database migrations complete");
+        Ok(())
+    }
+
+    /// Encrypt any existing plaintext content (items without "ENC:" prefix).
+    fn migrate_encrypt_existing(&self) {
+        let conn = self.conn.lock().unwrap();
+
+        let mut stmt = match conn.prepare("SELECT id, content FROM clipboard_items") {
+            Ok(s) => s,
+            Err(e) => {
+                tracing::error!("Failed to prepare migration query: {}", e);
+                return;
+            }
+        };
+
+        let rows: Vec<(String, String)> = match stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?))) {
+            Ok(mapped) => mapped.filter_map(|r| r.ok()).collect(),
+            Err(e) => {
+                tracing::error!("Failed to query items for encryption migration: {}", e);
+                return;
+            }
+        };
+
+        let mut migrated = 0;
+        for (id, content) in &rows {
+            if content.starts_with("ENC:") {
+                continue;
+            }
+            match self.crypto.encrypt(content) {
+                Ok(encrypted) => {
+                    if let Err(e) = conn.execute(
+                        "UPDATE clipboard_items SET content = ?1 WHERE id = ?2",
+                        params![encrypted, id],
+                    ) {
+                        tracing::error!("Failed to encrypt item {}: {}", id, e);
+                    } else {
+                        migrated += 1;
+                    }
+                }
+                Err(e) => {
+                    tracing::error!("Encryption failed for item {}: {}", id, e);
+                }
+            }
+        }
+
+        if migrated > 0 {
+            tracing::info!(
+                "Encrypted {} existing plaintext items",
+                migrated
+            );
+        }
+    }
+
+    /// Insert a clipboard item, skipping it if the same content was captured
+    /// within the dedup window, or is among the last few items when
+    /// `dedup_recent_items` is set (for apps that set the clipboard again and
+    /// again). Returns true if inserted, false if skipped as duplicate.
+    pub fn insert_item(&self, item: &ClipboardItem) -> SqliteResult<bool> {
+        let window_secs = self.get_dedup_window_secs();
+        let recent_items = self
+            .get_setting("dedup_recent_items")
+            .and_then(|v| v.parse::<u32>().ok())
+            .unwrap_or(0);
+        let conn = self.conn.lock().unwrap();
+
+        // Check for recent duplicate (same hash within the window)
+        let cutoff = item.created_at - i64::from(window_secs) * 1000;
+        let exists: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM clipboard_items WHERE content_hash = ?1 AND created_at > ?2)
+                OR EXISTS(SELECT 1 FROM (
+                    SELECT content_hash FROM clipboard_items ORDER BY created_at DESC LIMIT ?3
+                ) WHERE content_hash = ?1)",
+            params![item.content_hash, cutoff, recent_items],
+            |row| row.get(0),
+        )?;
+
+        if exists {
+            return Ok(false);
+        }
+
+        let encrypted_content = self
+            .crypto
+            .encrypt(&item.content)
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(std::io::Error::other(e))))?;
+
+        conn.execute(
+            "INSERT OR REPLACE INTO clipboard_items
+             (id, content, content_hash, content_type, source_app, device_id, created_at, is_promoted)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                item.id,
+                encrypted_content,
+                item.content_hash,
+                item.content_type,
+                item.source_app,
+                item.device_id,
+                item.created_at,
+                item.is_promoted as i32,
+            ],
+        )?;
+        self.store_preview(&conn, &item.id, &item.content)?;
+        Ok(true)
+    }
+
+    /// Store the encrypted preview of an item, and the language it's written
+    /// in if it looks like code.
+    fn store_preview(&self, conn: &Connection, id: &str, content: &str) -> SqliteResult<()> {
+        let encrypted_preview = self
+            .crypto
+            .encrypt(&ClipboardItem::preview(content))
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(std::io::Error::other(e))))?;
+        conn.execute(
+            "INSERT OR REPLACE INTO item_previews (item_id, preview, content_length)
+             VALUES (?1, ?2, ?3)",
+            params![id, encrypted_preview, content.chars().count() as i64],
+        )?;
+        if let Some(language) = language::detect(content) {
+            conn.execute(
+                "INSERT OR REPLACE INTO item_languages (item_id, language) VALUES (?1, ?2)",
+                params![id, language],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Store the page title fetched for an item that is a link.
+    pub fn set_link_title(&self, item_id: &str, title: &str) -> SqliteResult<()> {
+        let encrypted_title = self
+            .crypto
+            .encrypt(title)
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(std::io::Error::other(e))))?;
+        let conn = self.conn.lock().unwrap();
+        // The item may have been deleted while the title was fetched
+        conn.execute(
+            "INSERT OR REPLACE INTO link_titles (item_id, title)
+             SELECT id, ?2 FROM clipboard_items WHERE id = ?1",
+            params![item_id, encrypted_title],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_history(&self, limit: u32, offset: u32) -> SqliteResult<Vec<ClipboardItem>> {
+        let mut items: Vec<ClipboardItem> = {
+            let conn = self.conn.lock().unwrap();
+            let mut stmt = conn.prepare(
+                "SELECT id, content, content_hash, content_type, source_app, device_id, created_at, is_promoted
+                 FROM clipboard_items
+                 WHERE is_promoted = 0
+                 ORDER BY created_at DESC
+                 LIMIT ?1 OFFSET ?2",
+            )?;
+            let rows = stmt.query_map(params![limit, offset], item_from_row)?;
+            rows.filter_map(|r| r.ok()).collect()
+        };
+
+        // Decrypt content in parallel, without holding the connection
+        items.par_iter_mut().for_each(|item| {
+            if let Ok(plain) = self.crypto.decrypt(&item.content) {
+                item.content = plain;
+            }
+        });
+
+        Ok(items)
+    }
+
+    /// Like `get_history`, but only the preview of each item is decrypted.
+    /// Items without a stored preview yet get one now.
+    pub fn get_history_previews(
+        &self,
+        limit: u32,
+        offset: u32,
+    ) -> SqliteResult<Vec<ClipboardItemPreview>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} {}
+             WHERE c.is_promoted = 0
+             ORDER BY c.created_at DESC
+             LIMIT ?1 OFFSET ?2",
+            PREVIEW_COLUMNS, PREVIEW_FROM
+        ))?;
+        let rows: Vec<PreviewRow> = stmt
+            .query_map(params![limit, offset], preview_row)?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(self.previews_from_rows(&conn, rows))
+    }
+
+    /// A page of history previews, newest first, starting after `after`
+    /// (the first page if `None`), only from `device_id` if given. Keyed on
+    /// (created_at, id), so items captured while scrolling never shift later
+    /// pages.
+    pub fn get_history_page(
+        &self,
+        after: Option<&HistoryCursor>,
+        page_size: u32,
+        device_id: Option<&str>,
+    ) -> SqliteResult<HistoryPage> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} {}
+             WHERE c.is_promoted = 0
+               AND (?1 IS NULL OR c.created_at < ?1 OR (c.created_at = ?1 AND c.id < ?2))
+               AND (?4 IS NULL OR c.device_id = ?4)
+             ORDER BY c.created_at DESC, c.id DESC
+             LIMIT ?3",
+            PREVIEW_COLUMNS, PREVIEW_FROM
+        ))?;
+        // One extra row tells whether there is another page
+        let mut rows: Vec<PreviewRow> = stmt
+            .query_map(
+                params![
+                    after.map(|c| c.created_at),
+                    after.map(|c| &c.id),
+                    page_size + 1,
+                    device_id
+                ],
+                preview_row,
+            )?
+            .filter_map(|r| r.ok())
+            .collect();
+        let has_more = rows.len() > page_size as usize;
+        rows.truncate(page_size as usize);
+        let next_cursor = rows
+            .last()
+            .filter(|_| has_more)
+            .map(|row| HistoryCursor::after(&row.item).to_string());
+        Ok(HistoryPage {
+            items: self.previews_from_rows(&conn, rows),
+            next_cursor,
+        })
+    }
+
+    /// Devices history items came from, with how many items each, most
+    /// items first.
+    pub fn get_history_device_counts(&self) -> SqliteResult<Vec<(String, u32)>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT device_id, COUNT(*) FROM clipboard_items
+             WHERE is_promoted = 0
+             GROUP BY device_id
+             ORDER BY COUNT(*) DESC, device_id",
+        )?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect()
+    }
+
+    /// History previews bucketed by day or source app, newest group first,
+    /// with at most `items_per_group` items each. Counts cover the whole group.
+    pub fn get_history_grouped(
+        &self,
+        group_by: HistoryGroupBy,
+        items_per_group: u32,
+    ) -> SqliteResult<Vec<HistoryGroup>> {
+        let key = group_by.key_sql();
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "WITH grouped AS (
+                 SELECT c.id, {key} AS group_key,
+                        ROW_NUMBER() OVER (
+                            PARTITION BY {key} ORDER BY c.created_at DESC, c.id DESC
+                        ) AS position,
+                        COUNT(*) OVER (PARTITION BY {key}) AS group_count,
+                        MAX(c.created_at) OVER (PARTITION BY {key}) AS group_latest
+                 FROM clipboard_items c
+                 WHERE c.is_promoted = 0
+             )
+             SELECT {columns}, g.group_key, g.group_count {from}
+             JOIN grouped g ON g.id = c.id
+             WHERE g.position <= ?1
+             ORDER BY g.group_latest DESC, g.group_key, c.created_at DESC, c.id DESC",
+            key = key,
+            columns = PREVIEW_COLUMNS,
+            from = PREVIEW_FROM,
+        ))?;
+        type GroupedRow = (PreviewRow, Option<String>, u32);
+        let rows: Vec<GroupedRow> = stmt
+            .query_map(params![items_per_group], |row| {
+                Ok((preview_row(row)?, row.get(11)?, row.get(12)?))
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        // Rows arrive group by group
+        let mut groups: Vec<HistoryGroup> = Vec::new();
+        for (row, key, count) in rows {
+            let Some(preview) = self.preview_from_row(&conn, row) else {
+                continue;
+            };
+            match groups.last_mut() {
+                Some(group) if group.key == key => group.items.push(preview),
+                _ => groups.push(HistoryGroup {
+                    key,
+                    count,
+                    items: vec![preview],
+                }),
+            }
+        }
+        Ok(groups)
+    }
+
+    fn previews_from_rows(
+        &self,
+        conn: &Connection,
+        rows: Vec<PreviewRow>,
+    ) -> Vec<ClipboardItemPreview> {
+        rows.into_iter()
+            .filter_map(|row| self.preview_from_row(conn, row))
+            .collect()
+    }
+
+    /// Decrypt a stored preview, creating it for items that predate previews.
+    fn preview_from_row(&self, conn: &Connection, row: PreviewRow) -> Option<ClipboardItemPreview> {
+        let PreviewRow {
+            preview,
+            content_length: length,
+            content,
+            link_title,
+            language,
+            mut item,
+        } = row;
+        let content_length = match (preview, length, content) {
+            (Some(preview), Some(length), _) => {
+                item.content = self.crypto.decrypt(&preview).unwrap_or(preview);
+                length as usize
+            }
+            (_, _, Some(encrypted)) => {
+                let content = self.crypto.decrypt(&encrypted).unwrap_or(encrypted);
+                if let Err(e) = self.store_preview(conn, &item.id, &content) {
+                    tracing::error!("Failed to store preview for {}: {}", item.id, e);
+                }
+                item.content = ClipboardItem::preview(&content);
+                content.chars().count()
+            }
+            _ => return None,
+        };
+        Some(ClipboardItemPreview {
+            is_truncated: content_length > item.content.chars().count(),
+            content_length,
+            link_title: link_title.and_then(|t| self.crypto.decrypt(&t).ok()),
+            language,
+            item,
+        })
+    }
+
+    /// Languages detected in history, for filtering by language.
+    pub fn get_languages(&self) -> SqliteResult<Vec<String>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt =
+            conn.prepare("SELECT DISTINCT language FROM item_languages ORDER BY language")?;
+        let rows = stmt.query_map([], |row| row.get(0))?;
+        rows.collect()
+    }
+
+    /// Search by decrypting all items in memory and filtering.
+    pub fn search(&self, query: &str) -> SqliteResult<Vec<ClipboardItem>> {
+        let matcher = TextMatcher::Substring(query.to_lowercase());
+        self.search_filtered(&SearchFilters::default(), &matcher)
+    }
+
+    /// Search with structured filters: metadata in SQL, text in memory.
+    pub fn search_filtered(
+        &self,
+        filters: &SearchFilters,
+        matcher: &TextMatcher,
+    ) -> SqliteResult<Vec<ClipboardItem>> {
+        let items: Vec<ClipboardItem> = {
+            let conn = self.conn.lock().unwrap();
+            let (where_clause, params) = filters.where_clause();
+            let mut stmt = conn.prepare(&format!(
+                "SELECT id, content, content_hash, content_type, source_app, device_id, created_at, is_promoted
+                 FROM clipboard_items
+                 WHERE {}
+                 ORDER BY created_at DESC",
+                where_clause
+            ))?;
+            let rows = stmt.query_map(rusqlite::params_from_iter(params), item_from_row)?;
+            rows.filter_map(|r| r.ok()).collect()
+        };
+
+        // Decrypt a batch at a time in parallel, newest first, until there
+        // are enough matches
+        let mut results = Vec::new();
+        let mut rows = items.into_iter();
+        while results.len() < MAX_SEARCH_RESULTS {
+            let batch: Vec<ClipboardItem> = rows.by_ref().take(SEARCH_BATCH).collect();
+            if batch.is_empty() {
+                break;
+            }
+            results.par_extend(batch.into_par_iter().filter_map(|mut item| {
+                item.content = self.crypto.decrypt(&item.content).ok()?;
+                matcher.is_match(&item.content).then_some(item)
+            }));
+        }
+        results.truncate(MAX_SEARCH_RESULTS);
+
+        Ok(results)
+    }
+
+    pub fn delete_item(&self, id: &str) -> SqliteResult<bool> {
+        let conn = self.conn.lock().unwrap();
+        let rows = conn.execute("DELETE FROM clipboard_items WHERE id = ?1", params![id])?;
+        // The item may have been in a slot, which is now empty
+        self.slot_store.invalidate_all();
+        Ok(rows > 0)
+    }
+
+    pub fn clear_history(&self) -> SqliteResult<u32> {
+        let conn = self.conn.lock().unwrap();
+        let rows = conn.execute(
+            "DELETE FROM clipboard_items
+             WHERE is_promoted = 0 AND id NOT IN (SELECT item_id FROM pinned_items)",
+            [],
+        )?;
+        Ok(rows as u32)
+    }
+
+    // ── Pinned Items ────────────────────────────────────────────────────
+
+    /// Pin a history item. Returns false if there is no such item.
+    pub fn pin_item(&self, id: &str) -> SqliteResult<bool> {
+        let conn = self.conn.lock().unwrap();
+        let now = chrono::Utc::now().timestamp_millis();
+        let rows = conn.execute(
+            "INSERT OR IGNORE INTO pinned_items (item_id, pinned_at)
+             SELECT id, ?2 FROM clipboard_items WHERE id = ?1",
+            params![id, now],
+        )?;
+        if rows > 0 {
+            return Ok(true);
+        }
+        conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM pinned_items WHERE item_id = ?1)",
+            params![id],
+            |row| row.get(0),
+        )
+    }
+
+    /// Unpin an item. Returns false if it wasn't pinned.
+    pub fn unpin_item(&self, id: &str) -> SqliteResult<bool> {
+        let conn = self.conn.lock().unwrap();
+        let rows = conn.execute("DELETE FROM pinned_items WHERE item_id = ?1", params![id])?;
+        Ok(rows > 0)
+    }
+
+    /// Pinned items, most recently pinned first.
+    pub fn get_pinned_items(&self) -> SqliteResult<Vec<ClipboardItem>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT c.id, c.content, c.content_hash, c.content_type, c.source_app, c.device_id,
+                    c.created_at, c.is_promoted
+             FROM pinned_items p
+             JOIN clipboard_items c ON c.id = p.item_id
+             ORDER BY p.pinned_at DESC",
+        )?;
+
+        let items: Vec<ClipboardItem> = stmt
+            .query_map([], item_from_row)?
+            .filter_map(|r| r.ok())
+            .map(|mut item| {
+                if let Ok(plain) = self.crypto.decrypt(&item.content) {
+                    item.content = plain;
+                }
+                item
+            })
+            .collect();
+
+        Ok(items)
+    }
+
+    // ── Usage ───────────────────────────────────────────────────────────
+
+    /// Count a copy or paste of a history item. Unknown items are ignored.
+    pub fn record_item_use(&self, id: &str) -> SqliteResult<()> {
+        let conn = self.conn.lock().unwrap();
+        let now = chrono::Utc::now().timestamp_millis();
+        conn.execute(
+            "INSERT INTO item_usage (item_id, use_count, last_used_at)
+             SELECT id, 1, ?2 FROM clipboard_items WHERE id = ?1
+             ON CONFLICT(item_id) DO UPDATE SET
+                use_count = use_count + 1, last_used_at = excluded.last_used_at",
+            params![id, now],
+        )?;
+        Ok(())
+    }
+
+    /// Count a paste from a slot as a use of the item it holds.
+    pub fn record_slot_use(&self, slot_number: u32) -> SqliteResult<()> {
+        let conn = self.conn.lock().unwrap();
+        let now = chrono::Utc::now().timestamp_millis();
+        conn.execute(
+            "INSERT INTO item_usage (item_id, use_count, last_used_at)
+             SELECT item_id, 1, ?2 FROM slots WHERE slot_number = ?1 AND item_id IS NOT NULL
+             ON CONFLICT(item_id) DO UPDATE SET
+                use_count = use_count + 1, last_used_at = excluded.last_used_at",
+            params![slot_number, now],
+        )?;
+        Ok(())
+    }
+
+    /// Items ranked by how often and how recently they were used: uses
+    /// (plus one, for the capture itself) divided by days since last use.
+    /// Items never reused rank by age alone.
+    pub fn get_frequent_items(&self, limit: u32) -> SqliteResult<Vec<ClipboardItemPreview>> {
+        let conn = self.conn.lock().unwrap();
+        let now = chrono::Utc::now().timestamp_millis();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} {}
+             LEFT JOIN item_usage u ON u.item_id = c.id
+             ORDER BY (1 + COALESCE(u.use_count, 0)) /
+                 (1.0 + (?2 - MAX(c.created_at, COALESCE(u.last_used_at, 0))) / 86400000.0) DESC,
+                 c.created_at DESC
+             LIMIT ?1",
+            PREVIEW_COLUMNS, PREVIEW_FROM
+        ))?;
+        let rows: Vec<PreviewRow> = stmt
+            .query_map(params![limit, now], preview_row)?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(self.previews_from_rows(&conn, rows))
+    }
+
+    pub fn get_count(&self) -> SqliteResult<u32> {
+        let conn = self.conn.lock().unwrap();
+        let count: u32 =
+            conn.query_row("SELECT COUNT(*) FROM clipboard_items WHERE is_promoted = 0", [], |row| row.get(0))?;
+        Ok(count)
+    }
+
+    /// When the newest history item was captured (ms since epoch), if any.
+    pub fn get_last_capture_at(&self) -> SqliteResult<Option<i64>> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT MAX(created_at) FROM clipboard_items WHERE is_promoted = 0",
+            [],
+            |row| row.get(0),
+        )
+    }
+
+    pub fn get_history_limit(&self) -> u32 {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT value FROM app_config WHERE key = 'history_limit'",
+            [],
+            |row| {
+                let val: String = row.get(0)?;
+                Ok(val.parse::<u32>().unwrap_or(DEFAULT_HISTORY_LIMIT))
+            },
+        )
+        .unwrap_or(DEFAULT_HISTORY_LIMIT)
+    }
+
+    /// Seconds in which the same content isn't captured twice (0 turns the
+    /// window off).
+    pub fn get_dedup_window_secs(&self) -> u32 {
+        self.get_setting("dedup_window_secs")
+            .and_then(|v| v.parse::<u32>().ok())
+            .unwrap_or(DEFAULT_DEDUP_WINDOW_SECS)
+    }
+
+    /// Number of recent history items shown in the tray menu (0 hides them).
+    pub fn get_tray_history_count(&self) -> u32 {
+        self.get_setting("tray_history_count")
+            .and_then(|v| v.parse::<u32>().ok())
+            .unwrap_or(DEFAULT_TRAY_HISTORY_COUNT)
+            .min(MAX_TRAY_HISTORY_COUNT)
+    }
+
+    /// Decrypted content of a single item.
+    pub fn get_item_content(&self, id: &str) -> SqliteResult<Option<String>> {
+        Ok(self
+            .get_item_encrypted(id)?
+            .and_then(|(encrypted, _)| self.crypto.decrypt(&encrypted).ok()))
+    }
+
+    // ── Slot Operations ──────────────────────────────────────────────────
+
+    /// Number of slots in use, as set on the sync account (all of them if never synced).
+    pub fn get_slot_count(&self) -> u32 {
+        self.get_setting("slot_count")
+            .and_then(|v| v.parse::<u32>().ok())
+            .filter(|n| (1..=MAX_SLOT_COUNT).contains(n))
+            .unwrap_or(MAX_SLOT_COUNT)
+    }
+
+    /// Save clipboard content to a slot. Creates a ClipboardItem if needed,
+    /// marks it as promoted, and updates the slot to point to it.
+    pub fn save_to_slot(&self, slot_number: u32, item: &ClipboardItem) -> SqliteResult<SlotInfo> {
+        let conn = self.conn.lock().unwrap();
+
+        let encrypted_content = self
+            .crypto
+            .encrypt(&item.content)
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(std::io::Error::other(e))))?;
+
+        // Insert or update the clipboard item (mark as promoted)
+        conn.execute(
+            "INSERT OR REPLACE INTO clipboard_items
+             (id, content, content_hash, content_type, source_app, device_id, created_at, is_promoted)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, 1)",
+            params![
+                item.id,
+                encrypted_content,
+                item.content_hash,
+                item.content_type,
+                item.source_app,
+                item.device_id,
+                item.created_at,
+            ],
+        )?;
+
+        // Update the slot
+        let now = chrono::Utc::now().timestamp_millis();
+        conn.execute(
+            "UPDATE slots SET item_id = ?1, updated_at = ?2 WHERE slot_number = ?3",
+            params![item.id, now, slot_number],
+        )?;
+
+        let name: String = conn.query_row(
+            "SELECT name FROM slots WHERE slot_number = ?1",
+            params![slot_number],
+            |row| row.get(0),
+        )?;
+
+        let preview = if item.content.chars().count() > 100 {
+            let end = item.content.char_indices().nth(100).map(|(i, _)| i).unwrap_or(item.content.len());
+            Some(format!("{}...", &item.content[..end]))
+        } else {
+            Some(item.content.clone())
+        };
+
+        self.slot_changed(slot_number);
+        Ok(SlotInfo {
+            slot_number,
+            name,
+            content: Some(item.content.clone()),
+            content_preview: preview,
+            updated_at: now,
+            is_empty: false,
+        })
+    }
+
+    pub fn get_slot(&self, slot_number: u32) -> SqliteResult<SlotInfo> {
+        if let Some(slot) = self.slot_store.get(slot_number) {
+            return Ok(slot);
+        }
+        let conn = self.conn.lock().unwrap();
+        let row_data: (u32, String, i64, Option<String>) = conn.query_row(
+            "SELECT s.slot_number, s.name, s.updated_at, c.content
+             FROM slots s
+             LEFT JOIN clipboard_items c ON s.item_id = c.id
+             WHERE s.slot_number = ?1",
+            params![slot_number],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )?;
+
+        let content = row_data.3.and_then(|encrypted| {
+            tracing::info!(
+                "get_slot {}: encrypted content present, starts_with ENC:{}",
+                slot_number,
+                encrypted.starts_with("ENC:")
+            );
+            match self.crypto.decrypt(&encrypted) {
+                Ok(plain) => {
+                    tracing::info!(content = %plain, "get_slot {}: decryption OK", slot_number);
+                    Some(plain)
+                }
+                Err(e) => {
+                    tracing::error!("get_slot {}: decryption FAILED: {}", slot_number, e);
+                    None
+                }
+            }
+        });
+
+        let preview = content.as_ref().map(|c| {
+            if c.chars().count() > 100 {
+                let end = c.char_indices().nth(100).map(|(i, _)| i).unwrap_or(c.len());
+                format!("{}...", &c[..end])
+            } else {
+                c.clone()
+            }
+        });
+
+        let slot = SlotInfo {
+            slot_number: row_data.0,
+            name: row_data.1,
+            content: content.clone(),
+            content_preview: preview,
+            updated_at: row_data.2,
+            is_empty: content.is_none(),
+        };
+        // Cached while still holding the connection, so a concurrent write
+        // can't be overtaken by this (older) read
+        self.slot_store.put(slot.clone());
+        Ok(slot)
+    }
+
+    pub fn get_all_slots(&self) -> SqliteResult<Vec<SlotInfo>> {
+        let slot_count = self.get_slot_count();
+        if let Some(slots) = self.slot_store.get_all(slot_count) {
+            return Ok(slots);
+        }
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT s.slot_number, s.name, s.updated_at, c.content
+             FROM slots s
+             LEFT JOIN clipboard_items c ON s.item_id = c.id
+             WHERE s.slot_number <= ?1
+             ORDER BY s.slot_number ASC",
+        )?;
+
+        let raw_rows: Vec<(u32, String, i64, Option<String>)> = stmt
+            .query_map(params![slot_count], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        let slots: Vec<SlotInfo> = raw_rows
+            .into_iter()
+            .map(|(slot_number, name, updated_at, encrypted)| {
+                let content = encrypted.and_then(|e| self.crypto.decrypt(&e).ok());
+                let preview = content.as_ref().map(|c| {
+                    if c.chars().count() > 100 {
+                        let end = c.char_indices().nth(100).map(|(i, _)| i).unwrap_or(c.len());
+                        format!("{}...", &c[..end])
+                    } else {
+                        c.clone()
+                    }
+                });
+                SlotInfo {
+                    slot_number,
+                    name,
+                    content: content.clone(),
+                    content_preview: preview,
+                    updated_at,
+                    is_empty: content.is_none(),
+                }
+            })
+            .collect();
+
+        for slot in &slots {
+            self.slot_store.put(slot.clone());
+        }
+        Ok(slots)
+    }
+
+    /// Every slot, without its content.
+    pub fn get_slot_summaries(&self) -> SqliteResult<Vec<SlotSummary>> {
+        Ok(self.get_all_slots()?.into_iter().map(SlotSummary::from).collect())
+    }
+
+    pub fn clear_slot(&self, slot_number: u32) -> SqliteResult<bool> {
+        let conn = self.conn.lock().unwrap();
+        let rows = conn.execute(
+            "UPDATE slots SET item_id = NULL, updated_at = 0 WHERE slot_number = ?1",
+            params![slot_number],
+        )?;
+        self.slot_changed(slot_number);
+        Ok(rows > 0)
+    }
+
+    pub fn rename_slot(&self, slot_number: u32, name: &str) -> SqliteResult<bool> {
+        let conn = self.conn.lock().unwrap();
+        let rows = conn.execute(
+            "UPDATE slots SET name = ?1 WHERE slot_number = ?2",
+            params![name, slot_number],
+        )?;
+        self.slot_changed(slot_number);
+        Ok(rows > 0)
+    }
+
+    /// Drop the cached copy of a slot and tell subscribers it changed.
+    fn slot_changed(&self, slot_number: u32) {
+        self.slot_store.invalidate(slot_number);
+        let _ = self.slot_changes.send(slot_number);
+    }
+
+    /// Slot numbers as slots change, from any source (shortcuts, commands, sync).
+    pub fn subscribe_slot_changes(&self) -> broadcast::Receiver<u32> {
+        self.slot_changes.subscribe()
+    }
+
+    /// Promote an existing clipboard item to a slot by item ID.
+    pub fn save_existing_item_to_slot(
+        &self,
+        slot_number: u32,
+        item_id: &str,
+    ) -> SqliteResult<SlotInfo> {
+        let conn = self.conn.lock().unwrap();
+
+        // Mark the item as promoted
+        conn.execute(
+            "UPDATE clipboard_items SET is_promoted = 1 WHERE id = ?1",
+            params![item_id],
+        )?;
+
+        // Update the slot
+        let now = chrono::Utc::now().timestamp_millis();
+        conn.execute(
+            "UPDATE slots SET item_id = ?1, updated_at = ?2 WHERE slot_number = ?3",
+            params![item_id, now, slot_number],
+        )?;
+
+        // Return the updated slot info
+        drop(conn);
+        self.slot_changed(slot_number);
+        self.get_slot(slot_number)
+    }
+
+    // ── Sync Helpers ──────────────────────────────────────────────────────
+
+    /// Get the raw encrypted content for a slot (without decrypting), plus its updated_at.
+    pub fn get_slot_raw(&self, slot_number: u32) -> SqliteResult<(Option<String>, i64)> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT c.content, s.updated_at
+             FROM slots s
+             LEFT JOIN clipboard_items c ON s.item_id = c.id
+             WHERE s.slot_number = ?1",
+            params![slot_number],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+    }
+
+    /// Save a pre-encrypted blob directly into a slot (from sync).
+    /// Creates a clipboard_items entry with is_promoted=1 and links it to the slot.
+    pub fn save_encrypted_to_slot(
+        &self,
+        slot_number: u32,
+        encrypted_content: &str,
+        updated_at: i64,
+        device_id: &str,
+    ) -> SqliteResult<()> {
+        let conn = self.conn.lock().unwrap();
+
+        let item_id = uuid::Uuid::new_v4().to_string();
+        let content_hash = format!("sync_{}", slot_number);
+
+        conn.execute(
+            "INSERT OR REPLACE INTO clipboard_items
+             (id, content, content_hash, content_type, source_app, device_id, created_at, is_promoted)
+             VALUES (?1, ?2, ?3, 'text/plain', 'sync', ?4, ?5, 1)",
+            params![item_id, encrypted_content, content_hash, device_id, updated_at],
+        )?;
+
+        conn.execute(
+            "UPDATE slots SET item_id = ?1, updated_at = ?2 WHERE slot_number = ?3",
+            params![item_id, updated_at, slot_number],
+        )?;
+
+        self.slot_changed(slot_number);
+        Ok(())
+    }
+
+    /// Save a pre-encrypted blob into a team's shared slot (from sync).
+    pub fn save_encrypted_to_team_slot(
+        &self,
+        team_id: &str,
+        slot_number: u32,
+        encrypted_content: &str,
+        updated_at: i64,
+        device_id: &str,
+    ) -> SqliteResult<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO team_slots
+             (team_id, slot_number, content, updated_at, updated_by)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![team_id, slot_number, encrypted_content, updated_at, device_id],
+        )?;
+        Ok(())
+    }
+
+    /// A team's shared slots that have content, by slot number. Slots that
+    /// fail to decrypt are left out.
+    pub fn get_team_slots(&self, team_id: &str) -> SqliteResult<Vec<TeamSlot>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT slot_number, content, updated_at FROM team_slots
+             WHERE team_id = ?1 ORDER BY slot_number",
+        )?;
+        let rows = stmt
+            .query_map(params![team_id], |row| {
+                Ok((row.get::<_, u32>(0)?, row.get::<_, String>(1)?, row.get(2)?))
+            })?
+            .collect::<SqliteResult<Vec<(u32, String, i64)>>>()?;
+        Ok(rows
+            .into_iter()
+            .filter_map(|(slot_number, encrypted, updated_at)| {
+                match self.crypto.decrypt(&encrypted) {
+                    Ok(content) => Some(TeamSlot {
+                        team_id: team_id.to_string(),
+                        slot_number,
+                        content,
+                        updated_at,
+                    }),
+                    Err(e) => {
+                        tracing::error!("Failed to decrypt team slot {}: {}", slot_number, e);
+                        None
+                    }
+                }
+            })
+            .collect())
+    }
+
+    /// Check if an item with the given content_hash exists in the database.
+    pub fn has_item_with_hash(&self, content_hash: &str) -> SqliteResult<bool> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM clipboard_items WHERE content_hash = ?1)",
+            params![content_hash],
+            |row| row.get(0),
+        )
+    }
+
+    /// Insert many pre-encrypted items from sync in one transaction, skipping
+    /// IDs already present. Returns the number actually inserted.
+    pub fn insert_synced_items_batch(&self, items: &[SyncedItem]) -> SqliteResult<usize> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let mut inserted = 0;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT OR IGNORE INTO clipboard_items
+                 (id, content, content_hash, content_type, source_app, device_id, created_at, is_promoted)
+                 VALUES (?1, ?2, ?3, 'text/plain', 'sync', ?4, ?5, 0)",
+            )?;
+            for item in items {
+                inserted += stmt.execute(params![
+                    item.id,
+                    item.encrypted_content,
+                    item.content_hash,
+                    item.device_id,
+                    item.created_at,
+                ])?;
+            }
+        }
+        tx.commit()?;
+        Ok(inserted)
+    }
+
+    /// Remove a history item deleted on another device. Matches by ID, or by
+    /// content hash for copies captured independently under a different ID.
+    /// Slot contents are never touched. Returns the number of rows removed.
+    pub fn delete_synced_item(&self, id: &str, content_hash: &str) -> SqliteResult<u32> {
+        let conn = self.conn.lock().unwrap();
+        let rows = conn.execute(
+            "DELETE FROM clipboard_items
+             WHERE is_promoted = 0 AND (id = ?1 OR content_hash = ?2)",
+            params![id, content_hash],
+        )?;
+        Ok(rows as u32)
+    }
+
+    /// One item, decrypted, with its pin, slot and attachment details.
+    pub fn get_item_details(&self, id: &str) -> SqliteResult<Option<ClipboardItemDetails>> {
+        let conn = self.conn.lock().unwrap();
+        let result = conn.query_row(
+            "SELECT c.id, c.content, c.content_hash, c.content_type, c.source_app, c.device_id,
+                    c.created_at, c.is_promoted,
+                    EXISTS(SELECT 1 FROM pinned_items WHERE item_id = c.id),
+                    (SELECT LENGTH(data) FROM attachments WHERE item_id = c.id)
+             FROM clipboard_items c
+             WHERE c.id = ?1",
+            params![id],
+            |row| {
+                Ok(ClipboardItemDetails {
+                    item: item_from_row(row)?,
+                    is_pinned: row.get(8)?,
+                    slot_numbers: Vec::new(),
+                    attachment_size: row.get(9)?,
+                })
+            },
+        );
+        let mut details = match result {
+            Ok(details) => details,
+            Err(rusqlite::Error::QueryReturnedNoRows) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+        let mut stmt =
+            conn.prepare("SELECT slot_number FROM slots WHERE item_id = ?1 ORDER BY slot_number")?;
+        details.slot_numbers = stmt
+            .query_map(params![id], |row| row.get(0))?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        if let Ok(plain) = self.crypto.decrypt(&details.item.content) {
+            details.item.content = plain;
+        }
+        Ok(Some(details))
+    }
+
+    /// Get the raw encrypted content for a clipboard item by ID.
+    pub fn get_item_encrypted(&self, id: &str) -> SqliteResult<Option<(String, String)>> {
+        let conn = self.conn.lock().unwrap();
+        let result = conn.query_row(
+            "SELECT content, content_hash FROM clipboard_items WHERE id = ?1",
+            params![id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        );
+        match result {
+            Ok(data) => Ok(Some(data)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Store the encrypted attachment of an item and mark the item as binary content.
+    pub fn save_attachment(&self, item_id: &str, data: &[u8]) -> SqliteResult<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO attachments (item_id, data) VALUES (?1, ?2)",
+            params![item_id, data],
+        )?;
+        conn.execute(
+            "UPDATE clipboard_items SET content_type = 'application/octet-stream' WHERE id = ?1",
+            params![item_id],
+        )?;
+        Ok(())
+    }
+
+    /// Get the encrypted attachment of an item, if it has one.
+    pub fn get_attachment(&self, item_id: &str) -> SqliteResult<Option<Vec<u8>>> {
+        let conn = self.conn.lock().unwrap();
+        let result = conn.query_row(
+            "SELECT data FROM attachments WHERE item_id = ?1",
+            params![item_id],
+            |row| row.get(0),
+        );
+        match result {
+            Ok(data) => Ok(Some(data)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Get unpromoted (history) items after `after`, a (created_at, id) position,
+    /// with their raw encrypted content for sync push, oldest first. Ordering by id
+    /// too means items copied in the same millisecond are never skipped.
+    /// Returns (id, encrypted_content, content_hash, created_at) tuples.
+    pub fn get_unpromoted_encrypted_items(
+        &self,
+        limit: u32,
+        after: (i64, &str),
+    ) -> SqliteResult<Vec<(String, String, String, i64)>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, content, content_hash, created_at
+             FROM clipboard_items
+             WHERE is_promoted = 0 AND (created_at, id) > (?2, ?3)
+             ORDER BY created_at ASC, id ASC
+             LIMIT ?1",
+        )?;
+        let items = stmt
+            .query_map(params![limit, after.0, after.1], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(items)
+    }
+
+    // ── Settings ─────────────────────────────────────────────────────────
+
+    pub fn get_setting(&self, key: &str) -> Option<String> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT value FROM app_config WHERE key = ?1",
+            params![key],
+            |row| row.get(0),
+        )
+        .ok()
+    }
+
+    pub fn set_setting(&self, key: &str, value: &str) -> SqliteResult<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO app_config (key, value) VALUES (?1, ?2)",
+            params![key, value],
+        )?;
+        Ok(())
+    }
+
+    /// This install's device ID, random and created on first use. Installs
+    /// from before it was stored tagged their captures with `legacy_id`,
+    /// derived from the hostname, which machines with the same name shared;
+    /// those items are moved to the new ID.
+    pub fn get_or_create_device_id(&self, legacy_id: &str) -> SqliteResult<String> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let stored: Option<String> = tx
+            .query_row(
+                "SELECT value FROM app_config WHERE key = 'device_id'",
+                [],
+                |row| row.get(0),
+            )
+            .optional()?;
+        if let Some(id) = stored {
+            return Ok(id);
+        }
+
+        let id = uuid::Uuid::new_v4().to_string();
+        tx.execute(
+            "INSERT INTO app_config (key, value) VALUES ('device_id', ?1)",
+            params![id],
+        )?;
+        let moved = tx.execute(
+            "UPDATE clipboard_items SET device_id = ?1 WHERE device_id = ?2",
+            params![id, legacy_id],
+        )?;
+        tx.commit()?;
+        tracing::info!("Created device ID {}, moved {} items to it", id, moved);
+        Ok(id)
+    }
+
+    pub fn get_all_settings(&self) -> SqliteResult<Vec<(String, String)>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT key, value FROM app_config ORDER BY key")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect()
+    }
+
+    // ── Diagnostics ─────────────────────────────────────────────────────
+
+    /// `PRAGMA user_version` and the `CREATE` statement of every table,
+    /// index and trigger, for bug reports.
+    pub fn get_schema(&self) -> SqliteResult<(i64, Vec<String>)> {
+        let conn = self.conn.lock().unwrap();
+        let version = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        let mut stmt = conn.prepare(
+            "SELECT sql FROM sqlite_master WHERE sql IS NOT NULL ORDER BY type DESC, name",
+        )?;
+        let statements = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<SqliteResult<Vec<String>>>()?;
+        Ok((version, statements))
+    }
+
+    // ── History Limit ───────────────────────────────────────────────────
+
+    pub fn enforce_history_limit(&self) -> SqliteResult<u32> {
+        let limit = self.get_history_limit();
+        let count = self.get_count()?;
+
+        if count <= limit {
+            return Ok(0);
+        }
+
+        let excess = count - limit;
+        let conn = self.conn.lock().unwrap();
+        let rows = conn.execute(
+            "DELETE FROM clipboard_items WHERE id IN (
+                SELECT id FROM clipboard_items
+                WHERE is_promoted = 0 AND id NOT IN (SELECT item_id FROM pinned_items)
+                ORDER BY created_at ASC
+                LIMIT ?1
+            )",
+            params![excess],
+        )?;
+
+        if rows > 0 {
+            tracing::info!("Expired {} old items (limit: {})", rows, limit);
+            self.compact_integrity_chain(&conn)?;
+        }
+
+        Ok(rows as u32)
+    }
+
+    /// Turn integrity mode on or off. Turning it on chains the items already
+    /// in history, oldest first; turning it off forgets the chain.
+    pub fn set_history_integrity(&self, enabled: bool) -> SqliteResult<()> {
+        let mut conn = self.conn.lock().unwrap();
+        if enabled && integrity_trigger_count(&conn)? == integrity::TRIGGER_NAMES.len() {
+            return Ok(());
+        }
+        let tx = conn.transaction()?;
+        tx.execute_batch(integrity::DROP_TRIGGERS)?;
+        tx.execute_batch("DELETE FROM integrity_chain; DELETE FROM integrity_marks;")?;
+        if enabled {
+            let items: Vec<(String, String, i64)> = {
+                let mut stmt = tx.prepare(
+                    "SELECT id, content_hash, created_at FROM clipboard_items
+                     ORDER BY created_at, id",
+                )?;
+                let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+                rows.collect::<SqliteResult<_>>()?
+            };
+            let (mut seq, mut prev) = (0, String::new());
+            for (id, content_hash, created_at) in items {
+                seq += 1;
+                let message = integrity::link_message(seq, &id, &content_hash, created_at, &prev);
+                let mac = self.crypto.integrity_mac(&message);
+                tx.execute(
+                    "INSERT INTO integrity_chain (seq, item_id, content_hash, created_at, mac)
+                     VALUES (?1, ?2, ?3, ?4, ?5)",
+                    params![seq, id, content_hash, created_at, mac],
+                )?;
+                prev = mac;
+            }
+            if seq > 0 {
+                self.write_integrity_mark(&tx, "head", seq, &prev)?;
+            }
+            tx.execute_batch(integrity::CREATE_TRIGGERS)?;
+        }
+        tx.commit()
+    }
+
+    /// Check history against the integrity chain, for items added, changed
+    /// or deleted other than through ClipSlot.
+    pub fn verify_history_integrity(&self) -> SqliteResult<IntegrityReport> {
+        if self.get_setting("history_integrity_enabled").as_deref() != Some("true") {
+            return Ok(IntegrityReport::default());
+        }
+        let conn = self.conn.lock().unwrap();
+        let mut problems = Vec::new();
+        if integrity_trigger_count(&conn)? < integrity::TRIGGER_NAMES.len() {
+            problems.push("Integrity tracking was switched off outside ClipSlot".to_string());
+        }
+        let links = load_integrity_links(&conn, i64::MAX)?;
+        let checkpoint = load_integrity_mark(&conn, "checkpoint")?;
+        let head = load_integrity_mark(&conn, "head")?;
+        let mac = |message: &str| self.crypto.integrity_mac(message);
+        problems.extend(integrity::verify_chain(
+            &links,
+            checkpoint.as_ref(),
+            head.as_ref(),
+            mac,
+        ));
+
+        let mut stmt = conn.prepare(
+            "SELECT c.id, c.content, c.content_hash, c.created_at,
+                    EXISTS(SELECT 1 FROM attachments WHERE item_id = c.id)
+             FROM clipboard_items c
+             ORDER BY c.created_at",
+        )?;
+        let items: Vec<(String, String, String, i64, bool)> = stmt
+            .query_map([], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?))
+            })?
+            .collect::<SqliteResult<_>>()?;
+
+        // An item added again under the same ID has a newer link
+        let latest: HashMap<&str, &Link> =
+            links.iter().map(|link| (link.item_id.as_str(), link)).collect();
+        for (id, content, content_hash, created_at, has_attachment) in &items {
+            let Some(link) = latest.get(id.as_str()).filter(|l| l.deleted.is_none()) else {
+                problems.push(format!("Item {} was added outside ClipSlot", id));
+                continue;
+            };
+            if link.content_hash != *content_hash || link.created_at != *created_at {
+                problems.push(format!("Item {} was changed outside ClipSlot", id));
+                continue;
+            }
+            // Attachment items keep a placeholder as content
+            let content_matches = self
+                .crypto
+                .decrypt(content)
+                .is_ok_and(|text| ClipboardItem::hash_content(&text) == *content_hash);
+            if !*has_attachment && !content_matches {
+                problems.push(format!("The content of item {} was changed outside ClipSlot", id));
+            }
+        }
+        let present: HashSet<&str> = items.iter().map(|item| item.0.as_str()).collect();
+        for link in &links {
+            let is_latest = latest.get(link.item_id.as_str()).is_some_and(|l| l.seq == link.seq);
+            if is_latest && link.deleted.is_none() && !present.contains(link.item_id.as_str()) {
+                problems.push(format!("Item {} was deleted outside ClipSlot", link.item_id));
+            }
+        }
+
+        Ok(IntegrityReport {
+            enabled: true,
+            checked: items.len(),
+            problems,
+        })
+    }
+
+    /// Drop the oldest links once their items are deleted, so the chain
+    /// doesn't grow forever; a signed checkpoint stands in for them. Links
+    /// that don't verify are kept, as evidence.
+    fn compact_integrity_chain(&self, conn: &Connection) -> SqliteResult<()> {
+        let first_live: Option<i64> = conn.query_row(
+            "SELECT MIN(seq) FROM integrity_chain WHERE deleted IS NULL",
+            [],
+            |row| row.get(0),
+        )?;
+        let links = load_integrity_links(conn, first_live.unwrap_or(i64::MAX))?;
+        let Some(last) = links.last() else {
+            return Ok(());
+        };
+        let mac = |message: &str| self.crypto.integrity_mac(message);
+        let checkpoint = load_integrity_mark(conn, "checkpoint")?;
+        let end = Mark {
+            seq: last.seq,
+            mac: last.mac.clone(),
+            sig: mac(&integrity::mark_message("head", last.seq, &last.mac)),
+        };
+        if !integrity::verify_chain(&links, checkpoint.as_ref(), Some(&end), mac).is_empty() {
+            return Ok(());
+        }
+        conn.execute("DELETE FROM integrity_chain WHERE seq <= ?1", params![last.seq])?;
+        self.write_integrity_mark(conn, "checkpoint", last.seq, &last.mac)
+    }
+
+    fn write_integrity_mark(
+        &self,
+        conn: &Connection,
+        name: &str,
+        seq: i64,
+        mac: &str,
+    ) -> SqliteResult<()> {
+        let sig = self
+            .crypto
+            .integrity_mac(&integrity::mark_message(name, seq, mac));
+        conn.execute(
+            "INSERT OR REPLACE INTO integrity_marks (name, seq, mac, sig) VALUES (?1, ?2, ?3, ?4)",
+            params![name, seq, mac, sig],
+        )?;
+        Ok(())
+    }
+}
+
+fn integrity_trigger_count(conn: &Connection) -> SqliteResult<usize> {
+    let [first, second] = integrity::TRIGGER_NAMES;
+    conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'trigger' AND name IN (?1, ?2)",
+        params![first, second],
+        |row| row.get(0),
+    )
+}
+
+/// Links of the integrity chain before `seq`, in order.
+fn load_integrity_links(conn: &Connection, before: i64) -> SqliteResult<Vec<Link>> {
+    let mut stmt = conn.prepare(
+        "SELECT seq, item_id, content_hash, created_at, mac, deleted FROM integrity_chain
+         WHERE seq < ?1 ORDER BY seq",
+    )?;
+    let links = stmt.query_map(params![before], |row| {
+        Ok(Link {
+            seq: row.get(0)?,
+            item_id: row.get(1)?,
+            content_hash: row.get(2)?,
+            created_at: row.get(3)?,
+            mac: row.get(4)?,
+            deleted: row.get(5)?,
+        })
+    })?;
+    links.collect()
+}
+
+fn load_integrity_mark(conn: &Connection, name: &str) -> SqliteResult<Option<Mark>> {
+    conn.query_row(
+        "SELECT seq, mac, sig FROM integrity_marks WHERE name = ?1",
+        params![name],
+        |row| {
+            Ok(Mark {
+                seq: row.get(0)?,
+                mac: row.get(1)?,
+                sig: row.get(2)?,
+            })
+        },
+    )
+    .optional()
+}
+
+/// Columns for building previews: the stored preview and its length, or the
+/// full content when there is no preview yet, plus display metadata. Used
+/// with `PREVIEW_FROM`.
+const PREVIEW_COLUMNS: &str = "c.id, p.preview, p.content_length,
+        CASE WHEN p.item_id IS NULL THEN c.content END,
+        c.content_hash, c.content_type, c.source_app, c.device_id, c.created_at, t.title,
+        l.language";
+const PREVIEW_FROM: &str = "FROM clipboard_items c
+        LEFT JOIN item_previews p ON p.item_id = c.id
+        LEFT JOIN link_titles t ON t.item_id = c.id
+        LEFT JOIN item_languages l ON l.item_id = c.id";
+
+/// A row selected with `PREVIEW_COLUMNS`. Everything but `language` is
+/// still encrypted.
+struct PreviewRow {
+    preview: Option<String>,
+    content_length: Option<i64>,
+    /// Full content, only for items without a stored preview
+    content: Option<String>,
+    link_title: Option<String>,
+    language: Option<String>,
+    /// The item without its content
+    item: ClipboardItem,
+}
+
+fn preview_row(row: &Row) -> SqliteResult<PreviewRow> {
+    let item = ClipboardItem {
+        id: row.get(0)?,
+        content: String::new(),
+        content_hash: row.get(4)?,
+        content_type: row.get(5)?,
+        source_app: row.get(6)?,
+        device_id: row.get(7)?,
+        created_at: row.get(8)?,
+        is_promoted: false,
+    };
+    Ok(PreviewRow {
+        preview: row.get(1)?,
+        content_length: row.get(2)?,
+        content: row.get(3)?,
+        link_title: row.get(9)?,
+        language: row.get(10)?,
+        item,
+    })
+}
+
+/// A `clipboard_items` row selected as `id, content, content_hash,
+/// content_type, source_app, device_id, created_at, is_promoted`. Content is
+/// still encrypted.
+/// `Err` with what's wrong if the database file at `path` can't be read or
+/// fails SQLite's quick integrity check. A missing file is fine.
+fn check_integrity(path: &Path) -> Result<(), String> {
+    if !path.exists() {
+        return Ok(());
+    }
+    let conn = Connection::open(path).map_err(|e| e.to_string())?;
+    let result: String = conn
+        .query_row("PRAGMA quick_check", [], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    if result == "ok" {
+        Ok(())
+    } else {
+        Err(result)
+    }
+}
+
+fn item_from_row(row: &Row) -> SqliteResult<ClipboardItem> {
+    Ok(ClipboardItem {
+        id: row.get(0)?,
+        content: row.get(1)?,
+        content_hash: row.get(2)?,
+        content_type: row.get(3)?,
+        source_app: row.get(4)?,
+        device_id: row.get(5)?,
+        created_at: row.get(6)?,
+        is_promoted: row.get::<_, i32>(7)? != 0,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    fn test_db() -> (Database, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("clipslot-test-{}", uuid::Uuid::new_v4()));
+        let db = Database::new(dir.clone(), Arc::new(CryptoEngine::new(&[7u8; 32]))).unwrap();
+        (db, dir)
+    }
+
+    #[test]
+    fn test_insert_item_dedup() {
+        let (db, dir) = test_db();
+        let insert = |content: &str, secs: i64| {
+            let mut item = ClipboardItem::new(content.to_string(), "test");
+            item.created_at = secs * 1000;
+            db.insert_item(&item).unwrap()
+        };
+        assert!(insert("a", 0));
+        assert!(!insert("a", 1));
+        assert!(insert("b", 10));
+        assert!(insert("a", 20));
+
+        db.set_setting("dedup_recent_items", "2").unwrap();
+        assert!(!insert("b", 30));
+        assert!(insert("c", 40));
+        assert!(!insert("a", 45));
+        assert!(insert("b", 50));
+
+        db.set_setting("dedup_recent_items", "0").unwrap();
+        db.set_setting("dedup_window_secs", "60").unwrap();
+        assert!(!insert("b", 60));
+        db.set_setting("dedup_window_secs", "0").unwrap();
+        assert!(insert("b", 70));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_team_slots_leave_personal_slots_alone() {
+        let (db, dir) = test_db();
+        let personal = db.crypto.encrypt("mine").unwrap();
+        db.save_encrypted_to_slot(8, &personal, 1, "local").unwrap();
+
+        let shared = db.crypto.encrypt("team").unwrap();
+        db.save_encrypted_to_team_slot("team-a", 8, &shared, 2, "remote").unwrap();
+
+        assert_eq!(db.get_slot(8).unwrap().content.as_deref(), Some("mine"));
+        let slots = db.get_team_slots("team-a").unwrap();
+        assert_eq!(slots.len(), 1);
+        assert_eq!((slots[0].slot_number, slots[0].content.as_str()), (8, "team"));
+        assert!(db.get_team_slots("team-b").unwrap().is_empty());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_insert_synced_items_batch_skips_known_ids() {
+        let (db, dir) = test_db();
+        let synced = |id: &str| SyncedItem {
+            id: id.to_string(),
+            encrypted_content: db.crypto.encrypt(id).unwrap(),
+            content_hash: format!("hash-{}", id),
+            device_id: "remote".to_string(),
+            created_at: 1,
+        };
+        assert_eq!(db.insert_synced_items_batch(&[synced("a"), synced("b")]).unwrap(), 2);
+        assert_eq!(db.insert_synced_items_batch(&[synced("b"), synced("c")]).unwrap(), 1);
+        assert!(db.has_item_with_hash("hash-c").unwrap());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_history_grouped_by_source_app() {
+        let (db, dir) = test_db();
+        for (i, app) in [Some("Safari"), None, Some("Safari"), Some("Safari")].iter().enumerate() {
+            let mut item = ClipboardItem::new(format!("item {}", i), "test");
+            item.created_at = i as i64;
+            item.source_app = app.map(str::to_string);
+            db.insert_item(&item).unwrap();
+        }
+
+        let groups = db.get_history_grouped(HistoryGroupBy::SourceApp, 2).unwrap();
+        let summary: Vec<_> = groups
+            .iter()
+            .map(|g| {
+                let contents: Vec<_> = g.items.iter().map(|p| p.item.content.as_str()).collect();
+                (g.key.as_deref(), g.count, contents)
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (Some("Safari"), 3, vec!["item 3", "item 2"]),
+                (None, 1, vec!["item 1"]),
+            ]
+        );
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_open_recovers_damaged_file() {
+        let (db, dir) = test_db();
+        let pragmas = "PRAGMA journal_mode = MEMORY; PRAGMA synchronous = OFF";
+        db.conn.lock().unwrap().execute_batch(pragmas).unwrap();
+        db.set_setting("history_limit", "1234").unwrap();
+        for i in 0..100 {
+            let item = ClipboardItem::new(format!("item {} {}", i, "x".repeat(500)), "test");
+            db.insert_item(&item).unwrap();
+        }
+        drop(db);
+        let crypto = Arc::new(CryptoEngine::new(&[7u8; 32]));
+
+        // Scribble over the second half of the file, where the items are
+        let path = dir.join("clipslot.db");
+        let mut bytes = std::fs::read(&path).unwrap();
+        let half = bytes.len() / 2;
+        bytes[half..].fill(0xAB);
+        std::fs::write(&path, bytes).unwrap();
+        let (db, recovery) = Database::open(dir.clone(), crypto.clone()).unwrap();
+        let Recovery::Salvaged { backup, rows } = recovery else {
+            panic!("expected a salvage, got {:?}", recovery);
+        };
+        assert!(backup.exists());
+        assert!(rows > 0);
+        assert_eq!(db.get_history_limit(), 1234);
+        drop(db);
+
+        // Nothing to salvage from a file that isn't a database at all
+        std::fs::write(&path, b"not a database").unwrap();
+        let (db, recovery) = Database::open(dir.clone(), crypto.clone()).unwrap();
+        assert!(matches!(recovery, Recovery::Reset { .. }));
+        assert_eq!(db.get_count().unwrap(), 0);
+        drop(db);
+
+        let (_, recovery) = Database::open(dir.clone(), crypto).unwrap();
+        assert_eq!(recovery, Recovery::None);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_history_by_device() {
+        let (db, dir) = test_db();
+        for (i, device) in ["laptop", "phone", "laptop"].iter().enumerate() {
+            let mut item = ClipboardItem::new(format!("item {}", i), device);
+            item.created_at = i as i64;
+            db.insert_item(&item).unwrap();
+        }
+
+        let page = db.get_history_page(None, 10, Some("laptop")).unwrap();
+        let contents: Vec<_> = page.items.iter().map(|p| p.item.content.as_str()).collect();
+        assert_eq!(contents, vec!["item 2", "item 0"]);
+        assert_eq!(
+            db.get_history_device_counts().unwrap(),
+            vec![("laptop".to_string(), 2), ("phone".to_string(), 1)]
+        );
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_device_id_replaces_legacy_id() {
+        let (db, dir) = test_db();
+        for device in ["hostname-id", "phone"] {
+            db.insert_item(&ClipboardItem::new(device.to_string(), device)).unwrap();
+        }
+
+        let id = db.get_or_create_device_id("hostname-id").unwrap();
+        assert_ne!(id, "hostname-id");
+        assert_eq!(db.get_or_create_device_id("hostname-id").unwrap(), id);
+        // A UUID sorts before "phone"
+        assert_eq!(
+            db.get_history_device_counts().unwrap(),
+            vec![(id, 1), ("phone".to_string(), 1)]
+        );
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_history_grouped_by_local_day() {
+        let (db, dir) = test_db();
+        let day = 24 * 3600 * 1000;
+        for (i, created_at) in [day / 2, day / 2 + 1, 3 * day + day / 2].iter().enumerate() {
+            let mut item = ClipboardItem::new(format!("item {}", i), "test");
+            item.created_at = *created_at;
+            db.insert_item(&item).unwrap();
+        }
+
+        let groups = db.get_history_grouped(HistoryGroupBy::Day, 10).unwrap();
+        let keys: Vec<_> = groups.iter().map(|g| (g.key.clone(), g.count)).collect();
+        assert_eq!(
+            keys,
+            vec![
+                (Some(timestamp::local_day(3 * day + day / 2)), 1),
+                (Some(timestamp::local_day(day / 2)), 2),
+            ]
+        );
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_history_integrity_finds_tampering() {
+        let (db, dir) = test_db();
+        let insert = |content: &str, created_at: i64| {
+            let mut item = ClipboardItem::new(content.to_string(), "test");
+            item.created_at = created_at;
+            db.insert_item(&item).unwrap();
+            item.id
+        };
+        let first = insert("before integrity mode", 1);
+        db.set_setting("history_integrity_enabled", "true").unwrap();
+        db.set_history_integrity(true).unwrap();
+        let second = insert("second", 2);
+        let third = insert("third", 3);
+        insert("fourth", 4);
+
+        // Deleting through ClipSlot and trimming history is fine
+        assert!(db.delete_item(&second).unwrap());
+        db.set_setting("history_limit", "2").unwrap();
+        db.enforce_history_limit().unwrap();
+        let report = db.verify_history_integrity().unwrap();
+        assert_eq!((report.checked, report.problems), (2, Vec::<String>::new()));
+        let remaining: i64 = db
+            .conn
+            .lock()
+            .unwrap()
+            .query_row("SELECT COUNT(*) FROM integrity_chain", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(remaining, 2, "links of {} and {} compacted", first, second);
+
+        // Someone with the file but not the key
+        let raw = Connection::open(dir.join("clipslot.db")).unwrap();
+        assert!(raw.execute("DELETE FROM clipboard_items", []).is_err());
+        raw.execute_batch(
+            "UPDATE clipboard_items SET content = 'ENC:swapped' WHERE created_at = 4;
+             DROP TRIGGER integrity_chain_delete;",
+        )
+        .unwrap();
+        raw.execute("DELETE FROM clipboard_items WHERE id = ?1", [&third]).unwrap();
+        let problems = db.verify_history_integrity().unwrap().problems;
+        assert_eq!(problems.len(), 3, "{:?}", problems);
+        assert!(problems[0].contains("switched off"));
+        assert!(problems[1].starts_with("The content of item"));
+        assert_eq!(problems[2], format!("Item {} was deleted outside ClipSlot", third));
+
+        db.set_history_integrity(false).unwrap();
+        db.set_setting("history_integrity_enabled", "false").unwrap();
+        assert!(!db.verify_history_integrity().unwrap().enabled);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_history_page_is_stable_across_new_items() {
+        let (db, dir) = test_db();
+        for i in 0..5 {
+            let mut item = ClipboardItem::new(format!("item {}", i), "test");
+            item.created_at = i;
+            db.insert_item(&item).unwrap();
+        }
+
+        let first = db.get_history_page(None, 2, None).unwrap();
+        let contents = |page: &HistoryPage| {
+            page.items.iter().map(|p| p.item.content.clone()).collect::<Vec<_>>()
+        };
+        assert_eq!(contents(&first), vec!["item 4", "item 3"]);
+
+        // A capture mid-scroll must not push "item 3" onto the next page
+        let mut newer = ClipboardItem::new("item 5".to_string(), "test");
+        newer.created_at = 5;
+        db.insert_item(&newer).unwrap();
+
+        let cursor: HistoryCursor = first.next_cursor.unwrap().parse().unwrap();
+        let second = db.get_history_page(Some(&cursor), 2, None).unwrap();
+        assert_eq!(contents(&second), vec!["item 2", "item 1"]);
+
+        let cursor: HistoryCursor = second.next_cursor.unwrap().parse().unwrap();
+        let last = db.get_history_page(Some(&cursor), 2, None).unwrap();
+        assert_eq!(contents(&last), vec!["item 0"]);
+        assert!(last.next_cursor.is_none());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_unpromoted_items_after_same_millisecond() {
+        let (db, dir) = test_db();
+        let mut ids = Vec::new();
+        for i in 0..3 {
+            let mut item = ClipboardItem::new(format!("item {}", i), "test");
+            item.created_at = 1_000;
+            ids.push(item.id.clone());
+            db.insert_item(&item).unwrap();
+        }
+        ids.sort();
+
+        // A page ending partway through the millisecond resumes after its last id
+        let first = db.get_unpromoted_encrypted_items(2, (0, "")).unwrap();
+        let first_ids: Vec<&str> = first.iter().map(|(id, ..)| id.as_str()).collect();
+        assert_eq!(first_ids, vec![ids[0].as_str(), ids[1].as_str()]);
+
+        let rest = db.get_unpromoted_encrypted_items(2, (1_000, &ids[1])).unwrap();
+        assert_eq!(rest.len(), 1);
+        assert_eq!(rest[0].0, ids[2]);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_link_title_shows_in_previews() {
+        let (db, dir) = test_db();
+        let link = ClipboardItem::new("https://github.com/tokio-rs/axum".to_string(), "test");
+        db.insert_item(&link).unwrap();
+        db.set_link_title(&link.id, "GitHub - tokio-rs/axum").unwrap();
+        // Titles for items deleted in the meantime are dropped
+        db.set_link_title("gone", "Nothing").unwrap();
+
+        let page = db.get_history_page(None, 10, None).unwrap();
+        assert_eq!(page.items.len(), 1);
+        assert_eq!(page.items[0].link_title.as_deref(), Some("GitHub - tokio-rs/axum"));
+
+        db.delete_item(&link.id).unwrap();
+        let conn = db.conn.lock().unwrap();
+        let titles: i64 = conn
+            .query_row("SELECT COUNT(*) FROM link_titles", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(titles, 0);
+        drop(conn);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_language_detected_on_insert() {
+        let (db, dir) = test_db();
+        let code = ClipboardItem::new("SELECT id, name FROM users WHERE id = 1".to_string(), "test");
+        let prose = ClipboardItem::new("See you at lunch tomorrow".to_string(), "test");
+        db.insert_item(&code).unwrap();
+        db.insert_item(&prose).unwrap();
+
+        let page = db.get_history_page(None, 10, None).unwrap();
+        let language = |id: &str| {
+            let item = page.items.iter().find(|p| p.item.id == id).unwrap();
+            item.language.clone()
+        };
+        assert_eq!(language(&code.id).as_deref(), Some("sql"));
+        assert_eq!(language(&prose.id), None);
+        assert_eq!(db.get_languages().unwrap(), vec!["sql".to_string()]);
+
+        let filters = SearchFilters {
+            language: Some("sql".to_string()),
+            ..Default::default()
+        };
+        let results = db.search_filtered(&filters, &TextMatcher::Any).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].id, code.id);
+
+        db.delete_item(&code.id).unwrap();
+        assert!(db.get_languages().unwrap().is_empty());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_frequent_items_rank_reused_first() {
+        let (db, dir) = test_db();
+        let mut reused = ClipboardItem::new("reused".to_string(), "test");
+        reused.created_at -= 60_000;
+        let newest = ClipboardItem::new("newest".to_string(), "test");
+        db.insert_item(&reused).unwrap();
+        db.insert_item(&newest).unwrap();
+        let order = |db: &Database| -> Vec<String> {
+            let items = db.get_frequent_items(10).unwrap();
+            items.into_iter().map(|p| p.item.content).collect()
+        };
+        assert_eq!(order(&db), vec!["newest", "reused"]);
+
+        db.record_item_use(&reused.id).unwrap();
+        db.record_item_use(&reused.id).unwrap();
+        db.record_item_use("gone").unwrap();
+        assert_eq!(order(&db), vec!["reused", "newest"]);
+
+        let conn = db.conn.lock().unwrap();
+        let uses: i64 = conn
+            .query_row("SELECT SUM(use_count) FROM item_usage", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(uses, 2);
+        drop(conn);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_call_runs_on_database_thread() {
+        let (db, dir) = test_db();
+        let db = Arc::new(db);
+        db.call(|db| db.set_setting("history_limit", "42")).await.unwrap();
+        let (thread, value) = db
+            .call(|db| {
+                let thread = std::thread::current().name().map(str::to_string);
+                (thread, db.get_setting("history_limit"))
+            })
+            .await;
+        assert_eq!(thread.as_deref(), Some("clipslot-db"));
+        assert_eq!(value.as_deref(), Some("42"));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    /// Search latency over a large history. Ignored by default; run with
+    /// `cargo test --release bench_ -- --ignored --nocapture`.
+    #[test]
+    #[ignore]
+    fn bench_search_10k_items() {
+        let (db, dir) = test_db();
+        // Only the search is measured; don't wait on the disk while filling
+        let pragmas = "PRAGMA journal_mode = MEMORY; PRAGMA synchronous = OFF";
+        db.conn.lock().unwrap().execute_batch(pragmas).unwrap();
+        let filler = "lorem ipsum dolor sit amet ".repeat(20);
+        for i in 0..10_000 {
+            let item = ClipboardItem::new(format!("item {} {}", i, filler), "bench");
+            db.insert_item(&item).unwrap();
+        }
+
+        // Nothing matches, so every row is decrypted
+        let matcher = TextMatcher::Substring("no such text".to_string());
+        let start = Instant::now();
+        let results = db.search_filtered(&SearchFilters::default(), &matcher).unwrap();
+        let search = start.elapsed();
+
+        let start = Instant::now();
+        let previews = db.get_history_previews(100, 0).unwrap();
+        let listing = start.elapsed();
+
+        println!("search: {:?}, first listing of 100: {:?}", search, listing);
+        let _ = std::fs::remove_dir_all(dir);
+        assert!(results.is_empty());
+        assert_eq!(previews.len(), 100);
+        assert!(search < Duration::from_millis(100), "search took {:?}", search);
+    }
+}
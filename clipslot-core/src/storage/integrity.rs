@@ -0,0 +1,235 @@
+//! Optional tamper evidence for history. Each item added gets a link in
+//! `integrity_chain`: an HMAC over the item and the previous link, keyed by
+//! a key derived from the master key. Deleting an item through ClipSlot
+//! signs its link as deleted. Someone with the database file but not the
+//! key can't add, change or remove items, or cut links out, without
+//! `Database::verify_history_integrity` noticing.
+//!
+//! The links are written by triggers, so every way items are added or
+//! deleted is covered; they call the `integrity_mac` SQL function, which
+//! only ClipSlot's connections have.
+
+use serde::Serialize;
+
+/// Triggers that keep the chain while integrity mode is on.
+pub const TRIGGER_NAMES: [&str; 2] = ["integrity_chain_insert", "integrity_chain_delete"];
+
+pub const CREATE_TRIGGERS: &str = "
+    CREATE TRIGGER IF NOT EXISTS integrity_chain_insert
+    AFTER INSERT ON clipboard_items
+    BEGIN
+        INSERT INTO integrity_chain (seq, item_id, content_hash, created_at, mac)
+        SELECT seq, NEW.id, NEW.content_hash, NEW.created_at,
+               integrity_mac(seq || ':' || NEW.id || ':' || NEW.content_hash || ':' ||
+                             NEW.created_at || ':' || prev)
+        FROM (SELECT COALESCE(MAX(seq),
+                              (SELECT seq FROM integrity_marks WHERE name = 'checkpoint'),
+                              0) + 1 AS seq,
+                     COALESCE((SELECT mac FROM integrity_chain ORDER BY seq DESC LIMIT 1),
+                              (SELECT mac FROM integrity_marks WHERE name = 'checkpoint'),
+                              '') AS prev
+              FROM integrity_chain);
+        INSERT OR REPLACE INTO integrity_marks (name, seq, mac, sig)
+        SELECT 'head', seq, mac, integrity_mac('head:' || seq || ':' || mac)
+        FROM integrity_chain ORDER BY seq DESC LIMIT 1;
+    END;
+
+    CREATE TRIGGER IF NOT EXISTS integrity_chain_delete
+    AFTER DELETE ON clipboard_items
+    BEGIN
+        UPDATE integrity_chain SET deleted = integrity_mac('deleted:' || mac)
+        WHERE item_id = OLD.id AND deleted IS NULL;
+    END;
+";
+
+pub const DROP_TRIGGERS: &str = "
+    DROP TRIGGER IF EXISTS integrity_chain_insert;
+    DROP TRIGGER IF EXISTS integrity_chain_delete;
+";
+
+/// One row of `integrity_chain`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Link {
+    pub seq: i64,
+    pub item_id: String,
+    pub content_hash: String,
+    pub created_at: i64,
+    pub mac: String,
+    /// Signature of the deletion, once the item was deleted
+    pub deleted: Option<String>,
+}
+
+/// A signed position in the chain: `head` is the newest link, `checkpoint`
+/// the last one dropped by compaction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mark {
+    pub seq: i64,
+    pub mac: String,
+    pub sig: String,
+}
+
+/// Result of `Database::verify_history_integrity`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct IntegrityReport {
+    /// Whether integrity mode is on; nothing is checked otherwise
+    pub enabled: bool,
+    /// History items checked
+    pub checked: usize,
+    /// What doesn't add up, in words; empty if nothing
+    pub problems: Vec<String>,
+}
+
+/// What a link's MAC covers. The insert trigger builds the same string.
+pub fn link_message(
+    seq: i64,
+    item_id: &str,
+    content_hash: &str,
+    created_at: i64,
+    prev: &str,
+) -> String {
+    format!("{}:{}:{}:{}:{}", seq, item_id, content_hash, created_at, prev)
+}
+
+pub fn deleted_message(mac: &str) -> String {
+    format!("deleted:{}", mac)
+}
+
+pub fn mark_message(name: &str, seq: i64, mac: &str) -> String {
+    format!("{}:{}:{}", name, seq, mac)
+}
+
+/// Check `links` (in order) follow on from `checkpoint`, each other and end
+/// at `head`. `mac` is the keyed HMAC. Problems found, in words.
+pub fn verify_chain(
+    links: &[Link],
+    checkpoint: Option<&Mark>,
+    head: Option<&Mark>,
+    mac: impl Fn(&str) -> String,
+) -> Vec<String> {
+    let mut problems = Vec::new();
+    let (mut seq, mut prev) = (0, String::new());
+    if let Some(checkpoint) = checkpoint {
+        if checkpoint.sig != mac(&mark_message("checkpoint", checkpoint.seq, &checkpoint.mac)) {
+            problems.push("The record of compacted history was changed".to_string());
+        }
+        (seq, prev) = (checkpoint.seq, checkpoint.mac.clone());
+    }
+
+    for link in links {
+        let expected =
+            link_message(link.seq, &link.item_id, &link.content_hash, link.created_at, &prev);
+        if link.seq != seq + 1 {
+            // The link after a gap can't match either
+            problems.push(removed(seq + 1, link.seq - 1));
+        } else if link.mac != mac(&expected) {
+            problems.push(format!("History record {} was changed", link.seq));
+        }
+        if let Some(deleted) = &link.deleted {
+            if *deleted != mac(&deleted_message(&link.mac)) {
+                problems.push(format!("Item {} was marked deleted outside ClipSlot", link.item_id));
+            }
+        }
+        (seq, prev) = (link.seq, link.mac.clone());
+    }
+
+    match head {
+        Some(head) if head.sig != mac(&mark_message("head", head.seq, &head.mac)) => {
+            problems.push("The record of the newest history item was changed".to_string());
+        }
+        Some(head) if head.seq > seq => problems.push(removed(seq + 1, head.seq)),
+        Some(head) if head.seq != seq || head.mac != prev => {
+            problems.push("The record of the newest history item was changed".to_string());
+        }
+        None if seq > 0 => {
+            problems.push("The record of the newest history item was removed".to_string());
+        }
+        _ => {}
+    }
+    problems
+}
+
+fn removed(first: i64, last: i64) -> String {
+    if first == last {
+        format!("History record {} was removed", first)
+    } else {
+        format!("History records {} to {} were removed", first, last)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mac(message: &str) -> String {
+        format!("mac({})", message)
+    }
+
+    fn chain(count: i64) -> (Vec<Link>, Mark) {
+        let mut links = Vec::new();
+        let mut prev = String::new();
+        for seq in 1..=count {
+            let id = format!("item{}", seq);
+            let link_mac = mac(&link_message(seq, &id, "hash", seq * 10, &prev));
+            links.push(Link {
+                seq,
+                item_id: id,
+                content_hash: "hash".to_string(),
+                created_at: seq * 10,
+                mac: link_mac.clone(),
+                deleted: None,
+            });
+            prev = link_mac;
+        }
+        let head = Mark {
+            seq: count,
+            sig: mac(&mark_message("head", count, &prev)),
+            mac: prev,
+        };
+        (links, head)
+    }
+
+    #[test]
+    fn test_intact_chain_verifies() {
+        let (mut links, head) = chain(4);
+        links[1].deleted = Some(mac(&deleted_message(&links[1].mac)));
+        assert_eq!(verify_chain(&links, None, Some(&head), mac), Vec::<String>::new());
+
+        // Compacted: the first two links replaced by a checkpoint
+        let checkpoint = Mark {
+            seq: 2,
+            mac: links[1].mac.clone(),
+            sig: mac(&mark_message("checkpoint", 2, &links[1].mac)),
+        };
+        assert!(verify_chain(&links[2..], Some(&checkpoint), Some(&head), mac).is_empty());
+    }
+
+    #[test]
+    fn test_tampering_is_found() {
+        let (links, head) = chain(4);
+
+        let mut changed = links.clone();
+        changed[2].content_hash = "other".to_string();
+        assert_eq!(
+            verify_chain(&changed, None, Some(&head), mac),
+            vec!["History record 3 was changed"]
+        );
+
+        let cut: Vec<Link> = links.iter().filter(|l| l.seq != 2).cloned().collect();
+        assert_eq!(
+            verify_chain(&cut, None, Some(&head), mac),
+            vec!["History record 2 was removed"]
+        );
+
+        let mut forged = links.clone();
+        forged[0].deleted = Some("forged".to_string());
+        assert_eq!(
+            verify_chain(&forged, None, Some(&head), mac),
+            vec!["Item item1 was marked deleted outside ClipSlot"]
+        );
+
+        assert_eq!(
+            verify_chain(&links[..3], None, Some(&head), mac),
+            vec!["History record 4 was removed"]
+        );
+    }
+}
@@ -0,0 +1,5 @@
+pub mod database;
+pub mod integrity;
+pub mod page;
+pub mod search;
+pub mod slot_store;
@@ -0,0 +1,102 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::clipboard::item::{ClipboardItem, ClipboardItemPreview};
+
+/// Most items one history page can hold.
+pub const MAX_PAGE_SIZE: u32 = 500;
+
+/// Position in the history list: the (created_at, id) of the last item seen.
+/// Passed to the frontend as an opaque string.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoryCursor {
+    pub created_at: i64,
+    pub id: String,
+}
+
+impl HistoryCursor {
+    /// The cursor for the page that follows `item`.
+    pub fn after(item: &ClipboardItem) -> Self {
+        HistoryCursor {
+            created_at: item.created_at,
+            id: item.id.clone(),
+        }
+    }
+}
+
+impl fmt::Display for HistoryCursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}_{}", self.created_at, self.id)
+    }
+}
+
+impl FromStr for HistoryCursor {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid history cursor: {}", s);
+        let (created_at, id) = s.split_once('_').ok_or_else(invalid)?;
+        if id.is_empty() {
+            return Err(invalid());
+        }
+        Ok(HistoryCursor {
+            created_at: created_at.parse().map_err(|_| invalid())?,
+            id: id.to_string(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HistoryPage {
+    pub items: Vec<ClipboardItemPreview>,
+    /// Pass back to get the next page; `None` on the last page
+    pub next_cursor: Option<String>,
+}
+
+/// How `get_history_grouped` buckets history.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoryGroupBy {
+    /// Local calendar day the item was captured
+    Day,
+    SourceApp,
+}
+
+impl HistoryGroupBy {
+    /// SQL expression for the group key, over `clipboard_items c`.
+    pub fn key_sql(self) -> &'static str {
+        match self {
+            HistoryGroupBy::Day => "local_day(c.created_at)",
+            HistoryGroupBy::SourceApp => "c.source_app",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HistoryGroup {
+    /// The day as `YYYY-MM-DD`, or the source app (`None` if unknown)
+    pub key: Option<String>,
+    /// Items in the whole group, not just those returned
+    pub count: u32,
+    pub items: Vec<ClipboardItemPreview>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor_round_trip() {
+        let cursor = HistoryCursor {
+            created_at: 1_700_000_000_000,
+            id: "3f2a_b".to_string(),
+        };
+        assert_eq!(cursor.to_string().parse::<HistoryCursor>().unwrap(), cursor);
+
+        for bad in ["", "123", "abc_id", "123_"] {
+            assert!(bad.parse::<HistoryCursor>().is_err(), "{}", bad);
+        }
+    }
+}
@@ -0,0 +1,199 @@
+use regex::{Regex, RegexBuilder};
+use rusqlite::types::Value;
+use serde::{Deserialize, Serialize};
+
+use crate::clipboard::item::ClipboardItem;
+
+/// Structured history search. Every filter is optional and they combine with
+/// AND. Content is encrypted at rest, so only the text match runs in memory;
+/// everything else is filtered in SQL.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default)]
+pub struct SearchFilters {
+    /// Treat the query as a (case-insensitive) regular expression
+    pub regex: bool,
+    /// Captured at or after this time, ms since epoch
+    pub from: Option<i64>,
+    /// Captured before this time, ms since epoch
+    pub to: Option<i64>,
+    pub source_app: Option<String>,
+    pub content_type: Option<String>,
+    /// Detected programming language, e.g. "sql"
+    pub language: Option<String>,
+    pub device_id: Option<String>,
+    pub pinned_only: bool,
+}
+
+impl SearchFilters {
+    /// SQL conditions on `clipboard_items` (no leading AND) and their
+    /// parameters, numbered from `?1`.
+    pub fn where_clause(&self) -> (String, Vec<Value>) {
+        let mut conditions = vec!["is_promoted = 0".to_string()];
+        let mut params: Vec<Value> = Vec::new();
+        let mut push = |sql: &str, value: Value| {
+            params.push(value);
+            conditions.push(sql.replace('?', &format!("?{}", params.len())));
+        };
+
+        if let Some(from) = self.from {
+            push("created_at >= ?", Value::Integer(from));
+        }
+        if let Some(to) = self.to {
+            push("created_at < ?", Value::Integer(to));
+        }
+        if let Some(app) = &self.source_app {
+            push("source_app = ?", Value::Text(app.clone()));
+        }
+        if let Some(content_type) = &self.content_type {
+            push("content_type = ?", Value::Text(content_type.clone()));
+        }
+        if let Some(language) = &self.language {
+            push(
+                "id IN (SELECT item_id FROM item_languages WHERE language = ?)",
+                Value::Text(language.clone()),
+            );
+        }
+        if let Some(device_id) = &self.device_id {
+            push("device_id = ?", Value::Text(device_id.clone()));
+        }
+        if self.pinned_only {
+            conditions.push("id IN (SELECT item_id FROM pinned_items)".to_string());
+        }
+        (conditions.join(" AND "), params)
+    }
+
+    /// Matcher for decrypted content: substring or regex, ignoring case.
+    pub fn matcher(&self, query: &str) -> Result<TextMatcher, String> {
+        if query.is_empty() {
+            return Ok(TextMatcher::Any);
+        }
+        if self.regex {
+            let re = RegexBuilder::new(query)
+                .case_insensitive(true)
+                .size_limit(1 << 20)
+                .build()
+                .map_err(|e| format!("Invalid regex: {}", e))?;
+            Ok(TextMatcher::Regex(re))
+        } else {
+            Ok(TextMatcher::Substring(query.to_lowercase()))
+        }
+    }
+}
+
+pub enum TextMatcher {
+    Any,
+    Substring(String),
+    Regex(Regex),
+}
+
+impl TextMatcher {
+    pub fn is_match(&self, content: &str) -> bool {
+        match self {
+            TextMatcher::Any => true,
+            TextMatcher::Substring(needle) => content.to_lowercase().contains(needle),
+            TextMatcher::Regex(re) => re.is_match(content),
+        }
+    }
+
+    /// Byte ranges `[start, end)` of `content` that match, in order and
+    /// not overlapping. Empty for `Any`.
+    pub fn match_ranges(&self, content: &str) -> Vec<(usize, usize)> {
+        match self {
+            TextMatcher::Any => Vec::new(),
+            TextMatcher::Substring(needle) => substring_ranges(content, needle),
+            TextMatcher::Regex(re) => re
+                .find_iter(content)
+                .filter(|m| !m.is_empty())
+                .map(|m| (m.start(), m.end()))
+                .collect(),
+        }
+    }
+}
+
+/// Where the lowercase `needle` occurs in `content`, ignoring case.
+fn substring_ranges(content: &str, needle: &str) -> Vec<(usize, usize)> {
+    if needle.is_empty() {
+        return Vec::new();
+    }
+    // Lowercasing can change a character's length, so remember which
+    // character of `content` each lowered byte came from
+    let mut lowered = String::with_capacity(content.len());
+    let mut origin = Vec::with_capacity(content.len());
+    for (start, c) in content.char_indices() {
+        for lower in c.to_lowercase() {
+            lowered.push(lower);
+        }
+        origin.resize(lowered.len(), (start, start + c.len_utf8()));
+    }
+    lowered
+        .match_indices(needle)
+        .map(|(at, hit)| (origin[at].0, origin[at + hit.len() - 1].1))
+        .collect()
+}
+
+/// A search result, with where the query matched so the hits can be
+/// highlighted.
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchHit {
+    #[serde(flatten)]
+    pub item: ClipboardItem,
+    /// Byte ranges `[start, end)` of `item.content` that matched
+    pub matches: Vec<(usize, usize)>,
+}
+
+impl SearchHit {
+    pub fn new(item: ClipboardItem, matcher: &TextMatcher) -> Self {
+        let matches = matcher.match_ranges(&item.content);
+        SearchHit { item, matches }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_where_clause_numbers_params() {
+        let filters = SearchFilters {
+            from: Some(10),
+            device_id: Some("dev".to_string()),
+            pinned_only: true,
+            ..Default::default()
+        };
+        let (sql, params) = filters.where_clause();
+        assert_eq!(
+            sql,
+            "is_promoted = 0 AND created_at >= ?1 AND device_id = ?2 \
+             AND id IN (SELECT item_id FROM pinned_items)"
+        );
+        assert_eq!(params, vec![Value::Integer(10), Value::Text("dev".to_string())]);
+    }
+
+    #[test]
+    fn test_matchers() {
+        let plain = SearchFilters::default().matcher("Hello").unwrap();
+        assert!(plain.is_match("say hello there"));
+        assert!(!plain.is_match("h.llo"));
+
+        let regex = SearchFilters { regex: true, ..Default::default() };
+        assert!(regex.matcher(r"^\d{3}-\d{4}$").unwrap().is_match("555-1234"));
+        assert!(regex.matcher("HELLO").unwrap().is_match("hello"));
+        assert!(regex.matcher("(unclosed").is_err());
+    }
+
+    #[test]
+    fn test_match_ranges() {
+        let plain = SearchFilters::default().matcher("ab").unwrap();
+        assert_eq!(plain.match_ranges("xAbyab"), vec![(1, 3), (4, 6)]);
+        // Byte offsets into the original text, even where lowercasing
+        // changes lengths ('İ' is 2 bytes, lowercased 3)
+        let dotted = SearchFilters::default().matcher("é").unwrap();
+        assert_eq!(dotted.match_ranges("İ É"), vec![(3, 5)]);
+
+        let regex = SearchFilters { regex: true, ..Default::default() };
+        let digits = regex.matcher(r"\d+").unwrap();
+        assert_eq!(digits.match_ranges("a12 b3"), vec![(1, 3), (5, 6)]);
+        assert!(regex.matcher("x*").unwrap().match_ranges("abc").is_empty());
+        assert!(TextMatcher::Any.match_ranges("abc").is_empty());
+    }
+}
@@ -0,0 +1,71 @@
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use crate::slots::SlotInfo;
+
+/// Decrypted slots kept in memory, so pasting a slot or rebuilding the tray
+/// doesn't go back to SQLite and decrypt every time. Entries are filled on
+/// read and dropped whenever the slot is written, locally or by sync.
+#[derive(Default)]
+pub struct SlotStore {
+    slots: RwLock<HashMap<u32, SlotInfo>>,
+}
+
+impl SlotStore {
+    pub fn get(&self, slot_number: u32) -> Option<SlotInfo> {
+        self.slots.read().unwrap().get(&slot_number).cloned()
+    }
+
+    /// Slots 1 to `slot_count`, or `None` if any of them isn't cached.
+    pub fn get_all(&self, slot_count: u32) -> Option<Vec<SlotInfo>> {
+        let slots = self.slots.read().unwrap();
+        (1..=slot_count).map(|n| slots.get(&n).cloned()).collect()
+    }
+
+    pub fn put(&self, slot: SlotInfo) {
+        self.slots.write().unwrap().insert(slot.slot_number, slot);
+    }
+
+    pub fn invalidate(&self, slot_number: u32) {
+        self.slots.write().unwrap().remove(&slot_number);
+    }
+
+    pub fn invalidate_all(&self) {
+        self.slots.write().unwrap().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn slot(slot_number: u32, content: &str) -> SlotInfo {
+        SlotInfo {
+            slot_number,
+            name: format!("Slot {}", slot_number),
+            content: Some(content.to_string()),
+            content_preview: Some(content.to_string()),
+            updated_at: 1,
+            is_empty: false,
+        }
+    }
+
+    #[test]
+    fn test_get_all_needs_every_slot() {
+        let store = SlotStore::default();
+        store.put(slot(1, "a"));
+        assert!(store.get_all(2).is_none());
+
+        store.put(slot(2, "b"));
+        let all = store.get_all(2).unwrap();
+        assert_eq!(all.iter().map(|s| s.slot_number).collect::<Vec<_>>(), vec![1, 2]);
+
+        store.invalidate(1);
+        assert!(store.get(1).is_none());
+        assert!(store.get_all(2).is_none());
+        assert_eq!(store.get(2).unwrap().content.as_deref(), Some("b"));
+
+        store.invalidate_all();
+        assert!(store.get(2).is_none());
+    }
+}
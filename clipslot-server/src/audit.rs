@@ -0,0 +1,40 @@
+use std::net::IpAddr;
+
+use uuid::Uuid;
+
+use crate::db::{self, with_db, Db};
+
+/// An account or client IP was locked out after repeated failed logins.
+pub const EVENT_LOGIN_LOCKED: &str = "login.locked";
+/// A device was removed through the revoke link in a "new device" email.
+pub const EVENT_DEVICE_REVOKED: &str = "device.revoked";
+
+/// Append a security-relevant event to the audit log. Failures are logged and
+/// never surface to the caller.
+pub async fn record(
+    db: &Db,
+    user_id: Option<Uuid>,
+    event: &str,
+    ip: Option<IpAddr>,
+    detail: serde_json::Value,
+) {
+    let result = with_db!(db, pool => {
+        sqlx::query(
+            "INSERT INTO audit_log (id, user_id, event, ip, detail, created_at)
+             VALUES ($1, $2, $3, $4, $5, $6)",
+        )
+        .bind(Uuid::new_v4())
+        .bind(user_id)
+        .bind(event)
+        .bind(ip.map(|ip| ip.to_string()))
+        .bind(detail.to_string())
+        .bind(db::now())
+        .execute(pool)
+        .await
+        .map(|_| ())
+    });
+
+    if let Err(e) = result {
+        tracing::error!("Failed to write audit log event {}: {}", event, e);
+    }
+}
@@ -0,0 +1,12 @@
+-- Tombstones for deleted history items, so deletions propagate to every device
+-- instead of the item being resurrected by a device that still has it.
+CREATE TABLE deleted_history (
+    id UUID NOT NULL,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    content_hash TEXT NOT NULL,
+    deleted_by UUID REFERENCES devices(id) ON DELETE SET NULL,
+    deleted_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, id)
+);
+
+CREATE INDEX idx_deleted_history_user ON deleted_history(user_id, deleted_at);
@@ -0,0 +1,31 @@
+-- Teams share a fixed range of slots (8-10) between their members' accounts.
+CREATE TABLE teams (
+    id UUID PRIMARY KEY,
+    name TEXT NOT NULL,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE TABLE team_members (
+    team_id UUID NOT NULL REFERENCES teams(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    -- 'owner' or 'member'; owners manage membership
+    role TEXT NOT NULL,
+    -- The team key wrapped with this member's own master key, so their other
+    -- devices can pick it up. NULL until the member has received the key.
+    encrypted_team_key TEXT,
+    joined_at TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (team_id, user_id)
+);
+
+CREATE INDEX idx_team_members_user ON team_members(user_id);
+
+-- Shared slots (encrypted with the team key — server cannot read content)
+CREATE TABLE team_slots (
+    team_id UUID NOT NULL REFERENCES teams(id) ON DELETE CASCADE,
+    slot_number INTEGER NOT NULL CHECK (slot_number BETWEEN 8 AND 10),
+    encrypted_blob BYTEA NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL,
+    updated_by UUID REFERENCES devices(id) ON DELETE SET NULL,
+    PRIMARY KEY (team_id, slot_number)
+);
@@ -0,0 +1,12 @@
+-- Ed25519 keys for signing session tokens. The newest key without retired_at signs;
+-- retired keys still verify until their tokens expire, revoked keys never do.
+CREATE TABLE jwt_signing_keys (
+    kid TEXT PRIMARY KEY,
+    algorithm TEXT NOT NULL,
+    -- PKCS#8 private key; protect database backups accordingly
+    private_key BYTEA NOT NULL,
+    public_key BYTEA NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    retired_at TIMESTAMPTZ,
+    revoked_at TIMESTAMPTZ
+);
@@ -0,0 +1,10 @@
+-- Blind-index tokens for searching encrypted history: HMACs of normalized words,
+-- keyed by a secret the server never sees
+CREATE TABLE history_search_tokens (
+    history_id UUID NOT NULL REFERENCES synced_history(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    token TEXT NOT NULL,
+    PRIMARY KEY (history_id, token)
+);
+
+CREATE INDEX idx_history_search_tokens_lookup ON history_search_tokens(user_id, token);
@@ -0,0 +1,12 @@
+-- One-time share links: an item encrypted with its own key (kept in the link's
+-- fragment, never sent here), served once and then deleted
+CREATE TABLE shares (
+    id TEXT PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    encrypted_blob TEXT NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX idx_shares_user ON shares(user_id);
+CREATE INDEX idx_shares_expires ON shares(expires_at);
@@ -0,0 +1,27 @@
+-- Cold tier for accounts with very large histories: a background task moves each
+-- user's items beyond the newest HOT_HISTORY_ITEMS here, so synced_history and
+-- its indexes stay small. Reads go through all_history, which spans both.
+CREATE TABLE archived_history (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    encrypted_blob BYTEA NOT NULL,
+    content_hash TEXT NOT NULL,
+    device_id UUID REFERENCES devices(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    attachment_id UUID REFERENCES attachments(id) ON DELETE SET NULL
+);
+
+CREATE INDEX idx_archived_history_user ON archived_history(user_id, created_at DESC, id DESC);
+CREATE UNIQUE INDEX idx_archived_history_dedup ON archived_history(user_id, content_hash);
+CREATE INDEX idx_archived_history_attachment ON archived_history(attachment_id);
+
+CREATE VIEW all_history AS
+    SELECT id, user_id, encrypted_blob, content_hash, device_id, created_at, attachment_id
+    FROM synced_history
+    UNION ALL
+    SELECT id, user_id, encrypted_blob, content_hash, device_id, created_at, attachment_id
+    FROM archived_history;
+
+-- Search tokens stay put when their item is archived, so they can't cascade
+-- from synced_history any more; deleting an item removes them explicitly
+ALTER TABLE history_search_tokens DROP CONSTRAINT history_search_tokens_history_id_fkey;
@@ -0,0 +1,5 @@
+-- Reported by the client so a user can tell devices of the same type apart
+-- and spot installs running an old version.
+ALTER TABLE devices ADD COLUMN app_version TEXT;
+ALTER TABLE devices ADD COLUMN os_version TEXT;
+ALTER TABLE devices ADD COLUMN hostname TEXT;
@@ -0,0 +1,15 @@
+-- Earlier contents of synced slots, so a bad write that reached every device
+-- can be undone. Written when a slot is overwritten; only the newest few per
+-- slot are kept (MAX_SLOT_VERSIONS).
+CREATE TABLE slot_versions (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    slot_number INTEGER NOT NULL,
+    encrypted_blob BYTEA NOT NULL,
+    -- When and by whom this content was written
+    updated_at TIMESTAMPTZ NOT NULL,
+    updated_by UUID REFERENCES devices(id) ON DELETE SET NULL,
+    replaced_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX idx_slot_versions_slot ON slot_versions(user_id, slot_number, replaced_at DESC);
@@ -0,0 +1,16 @@
+-- Anonymous usage counts from clients that opted in to telemetry, one row per
+-- reported day. Nothing here identifies a user or device.
+CREATE TABLE telemetry_reports (
+    id UUID PRIMARY KEY,
+    -- UTC day the counts are for, YYYY-MM-DD
+    day TEXT NOT NULL,
+    app_version TEXT NOT NULL,
+    platform TEXT NOT NULL,
+    captures BIGINT NOT NULL,
+    slot_saves BIGINT NOT NULL,
+    slot_pastes BIGINT NOT NULL,
+    sync_errors BIGINT NOT NULL,
+    received_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX idx_telemetry_reports_day ON telemetry_reports(day);
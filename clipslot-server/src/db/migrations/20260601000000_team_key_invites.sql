@@ -0,0 +1,4 @@
+-- The team key wrapped for a member by a teammate, with a code passed to them
+-- outside ClipSlot. Only that member can read it; it is cleared once they
+-- store their own copy in encrypted_team_key.
+ALTER TABLE team_members ADD COLUMN invite_key TEXT;
@@ -0,0 +1,45 @@
+-- SQLite equivalent of the Postgres schema. UUIDs are stored as 16-byte BLOBs
+-- and timestamps as RFC 3339 TEXT; ids and timestamps are supplied by the server.
+
+-- Users
+CREATE TABLE users (
+    id BLOB PRIMARY KEY NOT NULL,
+    email TEXT UNIQUE NOT NULL,
+    password_hash TEXT NOT NULL,
+    created_at TEXT NOT NULL
+);
+
+-- Devices
+CREATE TABLE devices (
+    id BLOB PRIMARY KEY NOT NULL,
+    user_id BLOB NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    device_type TEXT NOT NULL,
+    last_seen TEXT NOT NULL,
+    created_at TEXT NOT NULL
+);
+
+CREATE INDEX idx_devices_user_id ON devices(user_id);
+
+-- Synced Slots (encrypted blobs — server cannot read content)
+CREATE TABLE synced_slots (
+    user_id BLOB NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    slot_number INTEGER NOT NULL CHECK (slot_number BETWEEN 1 AND 10),
+    encrypted_blob BLOB NOT NULL,
+    updated_at TEXT NOT NULL,
+    updated_by BLOB REFERENCES devices(id) ON DELETE SET NULL,
+    PRIMARY KEY (user_id, slot_number)
+);
+
+-- Synced History (encrypted blobs — server cannot read content)
+CREATE TABLE synced_history (
+    id BLOB PRIMARY KEY NOT NULL,
+    user_id BLOB NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    encrypted_blob BLOB NOT NULL,
+    content_hash TEXT NOT NULL,
+    device_id BLOB REFERENCES devices(id) ON DELETE SET NULL,
+    created_at TEXT NOT NULL
+);
+
+CREATE INDEX idx_synced_history_user ON synced_history(user_id, created_at DESC);
+CREATE UNIQUE INDEX idx_synced_history_dedup ON synced_history(user_id, content_hash);
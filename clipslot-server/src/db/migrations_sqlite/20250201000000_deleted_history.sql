@@ -0,0 +1,12 @@
+-- Tombstones for deleted history items, so deletions propagate to every device
+-- instead of the item being resurrected by a device that still has it.
+CREATE TABLE deleted_history (
+    id BLOB NOT NULL,
+    user_id BLOB NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    content_hash TEXT NOT NULL,
+    deleted_by BLOB REFERENCES devices(id) ON DELETE SET NULL,
+    deleted_at TEXT NOT NULL,
+    PRIMARY KEY (user_id, id)
+);
+
+CREATE INDEX idx_deleted_history_user ON deleted_history(user_id, deleted_at);
@@ -0,0 +1,16 @@
+-- Long-lived API keys for scripts and automation. Only a SHA-256 hash of the
+-- key is stored; the plaintext is shown once at creation.
+CREATE TABLE api_keys (
+    id BLOB PRIMARY KEY NOT NULL,
+    user_id BLOB NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    prefix TEXT NOT NULL,
+    key_hash TEXT UNIQUE NOT NULL,
+    -- Comma-separated, e.g. "slots:read,history:read"
+    scopes TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    last_used_at TEXT,
+    revoked_at TEXT
+);
+
+CREATE INDEX idx_api_keys_user ON api_keys(user_id);
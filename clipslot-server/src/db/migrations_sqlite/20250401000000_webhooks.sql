@@ -0,0 +1,13 @@
+-- User-registered webhook endpoints, called with an HMAC-signed POST on sync events.
+CREATE TABLE webhooks (
+    id BLOB PRIMARY KEY NOT NULL,
+    user_id BLOB NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    url TEXT NOT NULL,
+    -- Signing secret; kept in plaintext because the server needs it to sign deliveries
+    secret TEXT NOT NULL,
+    -- Comma-separated, e.g. "slot.updated,history.created"
+    events TEXT NOT NULL,
+    created_at TEXT NOT NULL
+);
+
+CREATE INDEX idx_webhooks_user ON webhooks(user_id);
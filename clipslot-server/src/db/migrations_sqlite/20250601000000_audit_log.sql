@@ -0,0 +1,13 @@
+-- Append-only log of security-relevant events (e.g. login lockouts).
+CREATE TABLE audit_log (
+    id BLOB PRIMARY KEY NOT NULL,
+    -- NULL when the event isn't tied to a known account (e.g. an IP lockout)
+    user_id BLOB REFERENCES users(id) ON DELETE SET NULL,
+    event TEXT NOT NULL,
+    ip TEXT,
+    -- JSON object with event-specific details
+    detail TEXT NOT NULL,
+    created_at TEXT NOT NULL
+);
+
+CREATE INDEX idx_audit_log_user ON audit_log(user_id, created_at DESC);
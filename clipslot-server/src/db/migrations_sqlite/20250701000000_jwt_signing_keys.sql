@@ -0,0 +1,12 @@
+-- Ed25519 keys for signing session tokens. The newest key without retired_at signs;
+-- retired keys still verify until their tokens expire, revoked keys never do.
+CREATE TABLE jwt_signing_keys (
+    kid TEXT PRIMARY KEY NOT NULL,
+    algorithm TEXT NOT NULL,
+    -- PKCS#8 private key; protect database backups accordingly
+    private_key BLOB NOT NULL,
+    public_key BLOB NOT NULL,
+    created_at TEXT NOT NULL,
+    retired_at TEXT,
+    revoked_at TEXT
+);
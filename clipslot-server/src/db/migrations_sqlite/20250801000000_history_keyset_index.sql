@@ -0,0 +1,4 @@
+-- History pages are read newest first by (created_at, id); include id so the
+-- keyset cursor comparison is served by the index.
+DROP INDEX idx_synced_history_user;
+CREATE INDEX idx_synced_history_user ON synced_history(user_id, created_at DESC, id DESC);
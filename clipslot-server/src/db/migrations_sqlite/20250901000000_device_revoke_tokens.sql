@@ -0,0 +1,4 @@
+-- SHA-256 of the token in the "new device" email's revoke link
+ALTER TABLE devices ADD COLUMN revoke_token_hash TEXT;
+
+CREATE INDEX idx_devices_revoke_token ON devices(revoke_token_hash);
@@ -0,0 +1,23 @@
+-- Encrypted images and files, uploaded in chunks and referenced from history
+CREATE TABLE attachments (
+    id BLOB PRIMARY KEY NOT NULL,
+    user_id BLOB NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    size INTEGER NOT NULL,
+    chunk_count INTEGER NOT NULL,
+    completed_at TEXT,
+    created_at TEXT NOT NULL
+);
+
+CREATE INDEX idx_attachments_user ON attachments(user_id);
+
+CREATE TABLE attachment_chunks (
+    attachment_id BLOB NOT NULL REFERENCES attachments(id) ON DELETE CASCADE,
+    chunk_index INTEGER NOT NULL,
+    data BLOB NOT NULL,
+    PRIMARY KEY (attachment_id, chunk_index)
+);
+
+ALTER TABLE synced_history
+    ADD COLUMN attachment_id BLOB REFERENCES attachments(id) ON DELETE SET NULL;
+
+CREATE INDEX idx_synced_history_attachment ON synced_history(attachment_id);
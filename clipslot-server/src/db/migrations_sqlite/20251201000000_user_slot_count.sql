@@ -0,0 +1,4 @@
+-- Number of permanent slots on the account, numbered 1..slot_count. Slots
+-- above it are kept when the count is lowered but can no longer be written.
+ALTER TABLE users
+    ADD COLUMN slot_count INTEGER NOT NULL DEFAULT 10 CHECK (slot_count BETWEEN 1 AND 10);
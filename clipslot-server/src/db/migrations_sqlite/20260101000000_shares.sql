@@ -0,0 +1,12 @@
+-- One-time share links: an item encrypted with its own key (kept in the link's
+-- fragment, never sent here), served once and then deleted
+CREATE TABLE shares (
+    id TEXT PRIMARY KEY NOT NULL,
+    user_id BLOB NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    encrypted_blob TEXT NOT NULL,
+    expires_at TEXT NOT NULL,
+    created_at TEXT NOT NULL
+);
+
+CREATE INDEX idx_shares_user ON shares(user_id);
+CREATE INDEX idx_shares_expires ON shares(expires_at);
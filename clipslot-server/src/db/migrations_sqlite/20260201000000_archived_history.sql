@@ -0,0 +1,41 @@
+-- Cold tier for accounts with very large histories: a background task moves each
+-- user's items beyond the newest HOT_HISTORY_ITEMS here, so synced_history and
+-- its indexes stay small. Reads go through all_history, which spans both.
+CREATE TABLE archived_history (
+    id BLOB PRIMARY KEY NOT NULL,
+    user_id BLOB NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    encrypted_blob BLOB NOT NULL,
+    content_hash TEXT NOT NULL,
+    device_id BLOB REFERENCES devices(id) ON DELETE SET NULL,
+    created_at TEXT NOT NULL,
+    attachment_id BLOB REFERENCES attachments(id) ON DELETE SET NULL
+);
+
+CREATE INDEX idx_archived_history_user ON archived_history(user_id, created_at DESC, id DESC);
+CREATE UNIQUE INDEX idx_archived_history_dedup ON archived_history(user_id, content_hash);
+CREATE INDEX idx_archived_history_attachment ON archived_history(attachment_id);
+
+CREATE VIEW all_history AS
+    SELECT id, user_id, encrypted_blob, content_hash, device_id, created_at, attachment_id
+    FROM synced_history
+    UNION ALL
+    SELECT id, user_id, encrypted_blob, content_hash, device_id, created_at, attachment_id
+    FROM archived_history;
+
+-- Search tokens stay put when their item is archived, so they can't cascade
+-- from synced_history any more; deleting an item removes them explicitly.
+-- SQLite can't drop a foreign key, so the table is rebuilt without it.
+CREATE TABLE history_search_tokens_new (
+    history_id BLOB NOT NULL,
+    user_id BLOB NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    token TEXT NOT NULL,
+    PRIMARY KEY (history_id, token)
+);
+
+INSERT INTO history_search_tokens_new (history_id, user_id, token)
+    SELECT history_id, user_id, token FROM history_search_tokens;
+
+DROP TABLE history_search_tokens;
+ALTER TABLE history_search_tokens_new RENAME TO history_search_tokens;
+
+CREATE INDEX idx_history_search_tokens_lookup ON history_search_tokens(user_id, token);
@@ -0,0 +1,15 @@
+-- Earlier contents of synced slots, so a bad write that reached every device
+-- can be undone. Written when a slot is overwritten; only the newest few per
+-- slot are kept (MAX_SLOT_VERSIONS).
+CREATE TABLE slot_versions (
+    id BLOB PRIMARY KEY NOT NULL,
+    user_id BLOB NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    slot_number INTEGER NOT NULL,
+    encrypted_blob BLOB NOT NULL,
+    -- When and by whom this content was written
+    updated_at TEXT NOT NULL,
+    updated_by BLOB REFERENCES devices(id) ON DELETE SET NULL,
+    replaced_at TEXT NOT NULL
+);
+
+CREATE INDEX idx_slot_versions_slot ON slot_versions(user_id, slot_number, replaced_at DESC);
@@ -0,0 +1,16 @@
+-- Anonymous usage counts from clients that opted in to telemetry, one row per
+-- reported day. Nothing here identifies a user or device.
+CREATE TABLE telemetry_reports (
+    id BLOB PRIMARY KEY NOT NULL,
+    -- UTC day the counts are for, YYYY-MM-DD
+    day TEXT NOT NULL,
+    app_version TEXT NOT NULL,
+    platform TEXT NOT NULL,
+    captures INTEGER NOT NULL,
+    slot_saves INTEGER NOT NULL,
+    slot_pastes INTEGER NOT NULL,
+    sync_errors INTEGER NOT NULL,
+    received_at TEXT NOT NULL
+);
+
+CREATE INDEX idx_telemetry_reports_day ON telemetry_reports(day);
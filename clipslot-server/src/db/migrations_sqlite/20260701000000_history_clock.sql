@@ -0,0 +1,4 @@
+-- Timestamp of the user's latest history insert or deletion. History writes
+-- lock this row before taking their timestamp, so they commit in timestamp
+-- order and a delta cursor never passes a row that commits later.
+ALTER TABLE users ADD COLUMN history_clock TEXT;
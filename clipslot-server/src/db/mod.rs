@@ -1 +1,71 @@
 // Database module — migrations are handled by sqlx::migrate!()
+//
+// The server runs on Postgres or, for small self-hosted setups, SQLite. Queries
+// are written once in SQL both understand and run through `with_db!`, which
+// compiles the same body against whichever pool is configured. Keep new SQL
+// portable: `$N` parameters, no NOW() (bind `db::now()` instead), and ids
+// generated in Rust rather than by column defaults.
+
+use std::str::FromStr;
+
+use chrono::{DateTime, SubsecRound, Utc};
+use sqlx::postgres::PgPoolOptions;
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions};
+use sqlx::{PgPool, SqlitePool};
+
+#[derive(Clone)]
+pub enum Db {
+    Postgres(PgPool),
+    Sqlite(SqlitePool),
+}
+
+/// Run `$body` with `$pool` bound to the configured pool. The body is compiled
+/// once per backend, so it must evaluate to the same type for both.
+macro_rules! with_db {
+    ($db:expr, $pool:ident => $body:expr) => {
+        match $db {
+            $crate::db::Db::Postgres($pool) => $body,
+            $crate::db::Db::Sqlite($pool) => $body,
+        }
+    };
+}
+pub(crate) use with_db;
+
+impl Db {
+    /// Connect to the database named by `url` (`postgres://…` or `sqlite://…`).
+    pub async fn connect(url: &str) -> Result<Self, sqlx::Error> {
+        if url.starts_with("sqlite:") {
+            let options = SqliteConnectOptions::from_str(url)?
+                .create_if_missing(true)
+                .journal_mode(SqliteJournalMode::Wal);
+            let pool = SqlitePoolOptions::new()
+                .max_connections(5)
+                .connect_with(options)
+                .await?;
+            Ok(Db::Sqlite(pool))
+        } else {
+            let pool = PgPoolOptions::new()
+                .max_connections(10)
+                .connect(url)
+                .await?;
+            Ok(Db::Postgres(pool))
+        }
+    }
+
+    pub async fn migrate(&self) -> Result<(), sqlx::migrate::MigrateError> {
+        match self {
+            Db::Postgres(pool) => sqlx::migrate!("./src/db/migrations").run(pool).await,
+            Db::Sqlite(pool) => sqlx::migrate!("./src/db/migrations_sqlite").run(pool).await,
+        }
+    }
+
+    pub async fn close(&self) {
+        with_db!(self, pool => pool.close().await)
+    }
+}
+
+/// Current time for storing in the database, truncated to Postgres' microsecond
+/// precision so both backends round-trip timestamps (and history cursors) exactly.
+pub fn now() -> DateTime<Utc> {
+    Utc::now().trunc_subsecs(6)
+}
@@ -0,0 +1,64 @@
+use std::time::{Duration, Instant};
+
+use dashmap::mapref::entry::Entry;
+use dashmap::DashMap;
+use uuid::Uuid;
+
+use crate::db::{self, with_db, Db};
+
+/// `devices.last_seen` is written at most this often per device from REST requests.
+const UPDATE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Keeps `devices.last_seen` current for REST-only clients without a database
+/// write on every request. State is in memory and per instance.
+pub struct DeviceActivity {
+    db: Db,
+    /// When each device's last_seen was last written
+    written: DashMap<Uuid, Instant>,
+}
+
+impl DeviceActivity {
+    pub fn new(db: Db) -> Self {
+        Self {
+            db,
+            written: DashMap::new(),
+        }
+    }
+
+    /// Record activity for a device. The write happens in the background so it
+    /// never delays the request.
+    pub fn touch(&self, device_id: Uuid) {
+        let now = Instant::now();
+        match self.written.entry(device_id) {
+            Entry::Occupied(e) if now.duration_since(*e.get()) < UPDATE_INTERVAL => return,
+            Entry::Occupied(mut e) => {
+                e.insert(now);
+            }
+            Entry::Vacant(e) => {
+                e.insert(now);
+            }
+        }
+
+        let db = self.db.clone();
+        tokio::spawn(async move {
+            let result = with_db!(&db, pool => {
+                sqlx::query("UPDATE devices SET last_seen = $1 WHERE id = $2")
+                    .bind(db::now())
+                    .bind(device_id)
+                    .execute(pool)
+                    .await
+                    .map(|_| ())
+            });
+            if let Err(e) = result {
+                tracing::warn!("Failed to update last_seen for device {}: {}", device_id, e);
+            }
+        });
+    }
+
+    /// Forget devices whose interval has passed. Returns how many were removed.
+    pub fn prune(&self) -> usize {
+        let before = self.written.len();
+        self.written.retain(|_, t| t.elapsed() < UPDATE_INTERVAL);
+        before - self.written.len()
+    }
+}
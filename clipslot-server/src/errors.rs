@@ -0,0 +1,74 @@
+use axum::{http::StatusCode, Json};
+use serde::Serialize;
+
+use crate::middleware::request_id;
+
+/// Kind of error in an `ApiError`, so clients can tell failures apart
+/// without matching on the message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    BadRequest,
+    /// Missing, invalid or expired credentials
+    Unauthorized,
+    Forbidden,
+    NotFound,
+    Conflict,
+    Gone,
+    PayloadTooLarge,
+    UnsupportedMediaType,
+    /// The body was well-formed but failed validation
+    Unprocessable,
+    RateLimited,
+    /// The account has used up its storage for this kind of data
+    QuotaExceeded,
+    Internal,
+}
+
+impl ErrorCode {
+    pub fn for_status(status: StatusCode) -> Self {
+        match status {
+            StatusCode::UNAUTHORIZED => ErrorCode::Unauthorized,
+            StatusCode::FORBIDDEN => ErrorCode::Forbidden,
+            StatusCode::NOT_FOUND => ErrorCode::NotFound,
+            StatusCode::CONFLICT => ErrorCode::Conflict,
+            StatusCode::GONE => ErrorCode::Gone,
+            StatusCode::PAYLOAD_TOO_LARGE => ErrorCode::PayloadTooLarge,
+            StatusCode::UNSUPPORTED_MEDIA_TYPE => ErrorCode::UnsupportedMediaType,
+            StatusCode::UNPROCESSABLE_ENTITY => ErrorCode::Unprocessable,
+            StatusCode::TOO_MANY_REQUESTS => ErrorCode::RateLimited,
+            s if s.is_server_error() => ErrorCode::Internal,
+            _ => ErrorCode::BadRequest,
+        }
+    }
+}
+
+/// JSON body of every error response.
+#[derive(Serialize, utoipa::ToSchema)]
+pub struct ApiError {
+    pub error: String,
+    pub code: ErrorCode,
+    /// Also sent as the `X-Request-Id` header; quote it when reporting a problem
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+pub fn err(status: StatusCode, msg: &str) -> (StatusCode, Json<ApiError>) {
+    err_with_code(status, ErrorCode::for_status(status), msg)
+}
+
+/// Like `err`, for errors whose code says more than their status.
+pub fn err_with_code(
+    status: StatusCode,
+    code: ErrorCode,
+    msg: &str,
+) -> (StatusCode, Json<ApiError>) {
+    (
+        status,
+        Json(ApiError {
+            error: msg.to_string(),
+            code,
+            request_id: request_id::current(),
+        }),
+    )
+}
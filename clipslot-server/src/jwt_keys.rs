@@ -0,0 +1,257 @@
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use jsonwebtoken::{
+    decode, decode_header, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation,
+};
+use ring::rand::SystemRandom;
+use ring::signature::{Ed25519KeyPair, KeyPair};
+use serde::Serialize;
+
+use crate::db::{self, with_db, Db};
+use crate::models::user::{Claims, TOKEN_LIFETIME_SECS};
+
+/// Reload keys from the database at most this often when a token names an unknown kid.
+const MIN_RELOAD_INTERVAL: Duration = Duration::from_secs(10);
+
+/// A row of `jwt_signing_keys`.
+#[derive(sqlx::FromRow)]
+struct SigningKeyRow {
+    kid: String,
+    private_key: Vec<u8>,
+    public_key: Vec<u8>,
+    retired_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+struct KeySet {
+    /// Newest non-retired key; new tokens are signed with it
+    signing: Option<(String, EncodingKey)>,
+    /// Every key whose tokens are still accepted, by kid
+    verifying: HashMap<String, DecodingKey>,
+    /// Public keys in JWK form, for `/api/auth/jwks`
+    jwks: Vec<Jwk>,
+    loaded_at: Instant,
+}
+
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
+pub struct Jwk {
+    pub kty: &'static str,
+    pub crv: &'static str,
+    pub alg: &'static str,
+    #[serde(rename = "use")]
+    pub use_: &'static str,
+    pub kid: String,
+    /// Base64url-encoded Ed25519 public key
+    pub x: String,
+}
+
+/// Ed25519 (EdDSA) token signing keys, stored in the database so every instance
+/// shares them.
+///
+/// Tokens carry the signing key's id in the `kid` header. Rotating adds a new
+/// signing key and retires the previous one: it keeps verifying tokens until they
+/// expire, so sessions survive a routine rotation. Revoking a key (e.g. after a
+/// leak) stops accepting its tokens immediately. Instances pick up changes on
+/// their periodic reload, or as soon as they see an unknown kid.
+///
+/// HS256 tokens signed with the legacy `JWT_SECRET` are still accepted when the
+/// secret is configured, but never issued.
+///
+/// With a public URL configured, tokens carry it as `iss` and `aud` and only
+/// tokens naming it are accepted, so instances sharing keys or a secret can't
+/// use each other's tokens.
+pub struct JwtKeys {
+    db: Db,
+    legacy_secret: Option<String>,
+    public_url: Option<String>,
+    keys: RwLock<KeySet>,
+}
+
+impl JwtKeys {
+    /// Load keys, creating the first signing key if there is none yet.
+    pub async fn load(
+        db: Db,
+        legacy_secret: Option<String>,
+        public_url: Option<String>,
+    ) -> Result<Self, sqlx::Error> {
+        let mut keys = load_keys(&db).await?;
+        if keys.signing.is_none() {
+            let kid = create_key(&db).await?;
+            tracing::info!("Created JWT signing key {}", kid);
+            keys = load_keys(&db).await?;
+        }
+        Ok(Self {
+            db,
+            legacy_secret,
+            public_url,
+            keys: RwLock::new(keys),
+        })
+    }
+
+    /// Issuer and audience of tokens from this instance.
+    pub fn public_url(&self) -> Option<&str> {
+        self.public_url.as_deref()
+    }
+
+    /// Checks for `alg` tokens: expiry, plus issuer and audience when this
+    /// instance has a public URL. Tokens with an audience are always rejected
+    /// by instances without one.
+    fn validation(&self, alg: Algorithm) -> Validation {
+        let mut validation = Validation::new(alg);
+        if let Some(url) = &self.public_url {
+            validation.set_issuer(&[url]);
+            validation.set_audience(&[url]);
+            validation.set_required_spec_claims(&["exp", "iss", "aud"]);
+        }
+        validation
+    }
+
+    /// Re-read keys from the database to pick up rotations by other instances.
+    pub async fn reload(&self) -> Result<(), sqlx::Error> {
+        let keys = load_keys(&self.db).await?;
+        *self.keys.write().unwrap() = keys;
+        Ok(())
+    }
+
+    pub fn sign(&self, claims: &Claims) -> Result<String, jsonwebtoken::errors::Error> {
+        let keys = self.keys.read().unwrap();
+        let (kid, key) = keys
+            .signing
+            .as_ref()
+            .ok_or(jsonwebtoken::errors::ErrorKind::InvalidKeyFormat)?;
+        let mut header = Header::new(Algorithm::EdDSA);
+        header.kid = Some(kid.clone());
+        encode(&header, claims, key)
+    }
+
+    pub async fn verify(&self, token: &str) -> Result<Claims, ()> {
+        let header = decode_header(token).map_err(|_| ())?;
+
+        if header.alg == Algorithm::HS256 {
+            let secret = self.legacy_secret.as_ref().ok_or(())?;
+            return decode::<Claims>(
+                token,
+                &DecodingKey::from_secret(secret.as_bytes()),
+                &self.validation(Algorithm::HS256),
+            )
+            .map(|data| data.claims)
+            .map_err(|_| ());
+        }
+
+        let kid = header.kid.ok_or(())?;
+        if let Some(claims) = self.verify_with(&kid, token)? {
+            return Ok(claims);
+        }
+
+        // Possibly a key rotated in by another instance
+        let stale = self.keys.read().unwrap().loaded_at.elapsed() >= MIN_RELOAD_INTERVAL;
+        if stale && self.reload().await.is_ok() {
+            if let Some(claims) = self.verify_with(&kid, token)? {
+                return Ok(claims);
+            }
+        }
+        Err(())
+    }
+
+    /// Ok(None) if `kid` is unknown; Err if the token doesn't verify against it.
+    fn verify_with(&self, kid: &str, token: &str) -> Result<Option<Claims>, ()> {
+        let keys = self.keys.read().unwrap();
+        let Some(key) = keys.verifying.get(kid) else {
+            return Ok(None);
+        };
+        decode::<Claims>(token, key, &self.validation(Algorithm::EdDSA))
+            .map(|data| Some(data.claims))
+            .map_err(|_| ())
+    }
+
+    pub fn jwks(&self) -> Vec<Jwk> {
+        self.keys.read().unwrap().jwks.clone()
+    }
+}
+
+/// Add a new signing key and retire the current ones. With `revoke_previous`,
+/// tokens signed by the old keys stop working immediately. Returns the new kid.
+pub async fn rotate(db: &Db, revoke_previous: bool) -> Result<String, sqlx::Error> {
+    let now = db::now();
+    with_db!(db, pool => {
+        sqlx::query("UPDATE jwt_signing_keys SET retired_at = $1 WHERE retired_at IS NULL")
+            .bind(now)
+            .execute(pool)
+            .await
+            .map(|_| ())
+    })?;
+    if revoke_previous {
+        with_db!(db, pool => {
+            sqlx::query("UPDATE jwt_signing_keys SET revoked_at = $1 WHERE revoked_at IS NULL")
+                .bind(now)
+                .execute(pool)
+                .await
+                .map(|_| ())
+        })?;
+    }
+    create_key(db).await
+}
+
+async fn create_key(db: &Db) -> Result<String, sqlx::Error> {
+    let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new())
+        .expect("Failed to generate Ed25519 key");
+    let pair = Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).expect("Generated key is valid");
+    let kid = uuid::Uuid::new_v4().simple().to_string()[..16].to_string();
+
+    with_db!(db, pool => {
+        sqlx::query(
+            "INSERT INTO jwt_signing_keys (kid, algorithm, private_key, public_key, created_at)
+             VALUES ($1, $2, $3, $4, $5)",
+        )
+        .bind(&kid)
+        .bind("EdDSA")
+        .bind(pkcs8.as_ref())
+        .bind(pair.public_key().as_ref())
+        .bind(db::now())
+        .execute(pool)
+        .await
+        .map(|_| ())
+    })?;
+    Ok(kid)
+}
+
+async fn load_keys(db: &Db) -> Result<KeySet, sqlx::Error> {
+    // Retired keys verify until the last token they signed has expired
+    let cutoff = db::now() - chrono::Duration::seconds(TOKEN_LIFETIME_SECS as i64);
+    let rows = with_db!(db, pool => {
+        sqlx::query_as::<_, SigningKeyRow>(
+            "SELECT kid, private_key, public_key, retired_at FROM jwt_signing_keys
+             WHERE revoked_at IS NULL AND (retired_at IS NULL OR retired_at > $1)
+             ORDER BY created_at",
+        )
+        .bind(cutoff)
+        .fetch_all(pool)
+        .await
+    })?;
+
+    let mut keys = KeySet {
+        signing: None,
+        verifying: HashMap::new(),
+        jwks: Vec::new(),
+        loaded_at: Instant::now(),
+    };
+    for row in rows {
+        // Rows are oldest first, so the newest active key wins
+        if row.retired_at.is_none() {
+            keys.signing = Some((row.kid.clone(), EncodingKey::from_ed_der(&row.private_key)));
+        }
+        keys.verifying
+            .insert(row.kid.clone(), DecodingKey::from_ed_der(&row.public_key));
+        keys.jwks.push(Jwk {
+            kty: "OKP",
+            crv: "Ed25519",
+            alg: "EdDSA",
+            use_: "sig",
+            kid: row.kid,
+            x: URL_SAFE_NO_PAD.encode(&row.public_key),
+        });
+    }
+    Ok(keys)
+}
@@ -0,0 +1,89 @@
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::db::{with_db, Db};
+
+/// How long a device lookup is trusted. Devices removed through another
+/// instance lose access within this long.
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Whether the device a session token was issued to still exists, so a
+/// removed (e.g. stolen) device loses access before its token expires.
+/// Lookups are cached in memory, per instance.
+pub struct KnownDevices {
+    db: Db,
+    /// Whether each device existed, and when that was looked up
+    checked: DashMap<Uuid, (bool, Instant)>,
+    /// Devices removed through this instance, for their open WebSockets
+    removed: broadcast::Sender<Uuid>,
+}
+
+impl KnownDevices {
+    pub fn new(db: Db) -> Self {
+        Self {
+            db,
+            checked: DashMap::new(),
+            removed: broadcast::channel(64).0,
+        }
+    }
+
+    pub async fn exists(&self, device_id: Uuid) -> Result<bool, sqlx::Error> {
+        if let Some(entry) = self.checked.get(&device_id) {
+            let (exists, at) = *entry;
+            if at.elapsed() < CHECK_INTERVAL {
+                return Ok(exists);
+            }
+        }
+
+        let found = with_db!(&self.db, pool => {
+            sqlx::query_scalar::<_, Uuid>("SELECT id FROM devices WHERE id = $1")
+                .bind(device_id)
+                .fetch_optional(pool)
+                .await
+        })?;
+        let exists = found.is_some();
+        self.checked.insert(device_id, (exists, Instant::now()));
+        Ok(exists)
+    }
+
+    /// Record that a device was deleted: its tokens stop working here right
+    /// away, and its WebSockets close.
+    pub fn remove(&self, device_id: Uuid) {
+        self.checked.insert(device_id, (false, Instant::now()));
+        let _ = self.removed.send(device_id);
+    }
+
+    /// Resolves once the device is removed, through this instance or, found
+    /// by checking every `CHECK_INTERVAL`, another one.
+    pub async fn removed(&self, device_id: Uuid) {
+        let mut removed = self.removed.subscribe();
+        let mut check = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            tokio::select! {
+                result = removed.recv() => match result {
+                    Ok(id) if id == device_id => return,
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+                    Err(broadcast::error::RecvError::Closed) => {
+                        std::future::pending::<()>().await
+                    }
+                },
+                _ = check.tick() => {
+                    // A database error isn't a removal
+                    if matches!(self.exists(device_id).await, Ok(false)) {
+                        return;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Forget lookups too old to use. Returns how many were removed.
+    pub fn prune(&self) -> usize {
+        let before = self.checked.len();
+        self.checked.retain(|_, (_, at)| at.elapsed() < CHECK_INTERVAL);
+        before - self.checked.len()
+    }
+}
@@ -0,0 +1,187 @@
+use std::net::{IpAddr, SocketAddr};
+use std::time::{Duration, Instant};
+
+use axum::http::HeaderMap;
+use dashmap::DashMap;
+
+/// Thresholds for one kind of key (an account or a client IP).
+struct Policy {
+    /// Failures allowed before each further attempt has to wait
+    delay_after: u32,
+    /// Failures that lock the key out entirely
+    lockout_after: u32,
+}
+
+/// Shared IPs (offices, NAT) see more legitimate failures than a single account.
+const ACCOUNT_POLICY: Policy = Policy {
+    delay_after: 3,
+    lockout_after: 10,
+};
+const IP_POLICY: Policy = Policy {
+    delay_after: 10,
+    lockout_after: 50,
+};
+
+const MAX_DELAY: Duration = Duration::from_secs(60);
+pub const LOCKOUT_DURATION: Duration = Duration::from_secs(15 * 60);
+/// Failure counts are forgotten after this long without another failure.
+const FAILURE_WINDOW: Duration = Duration::from_secs(15 * 60);
+
+struct Attempts {
+    failures: u32,
+    last_failure: Instant,
+    locked_until: Option<Instant>,
+}
+
+impl Attempts {
+    /// How long until another attempt is allowed, if it isn't yet.
+    fn retry_after(&self, policy: &Policy, now: Instant) -> Option<Duration> {
+        if let Some(until) = self.locked_until {
+            return until.checked_duration_since(now);
+        }
+        if self.failures < policy.delay_after {
+            return None;
+        }
+        // 1s, 2s, 4s, ... after each failure past the threshold
+        let exp = (self.failures - policy.delay_after).min(6);
+        let delay = Duration::from_secs(1 << exp).min(MAX_DELAY);
+        (self.last_failure + delay).checked_duration_since(now)
+    }
+
+    fn is_stale(&self, now: Instant) -> bool {
+        match self.locked_until {
+            Some(until) => now >= until,
+            None => now.duration_since(self.last_failure) > FAILURE_WINDOW,
+        }
+    }
+}
+
+/// A key that became locked by the failure just recorded.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Lockout {
+    Account,
+    Ip,
+}
+
+/// Tracks failed logins per account and per client IP, with incremental delays
+/// and temporary lockouts. State is in memory and per instance.
+#[derive(Default)]
+pub struct LoginGuard {
+    attempts: DashMap<String, Attempts>,
+}
+
+impl LoginGuard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Err with the time to wait if this account or IP may not try again yet.
+    pub fn check(&self, email: &str, ip: IpAddr) -> Result<(), Duration> {
+        let now = Instant::now();
+        let wait = [
+            (account_key(email), &ACCOUNT_POLICY),
+            (ip_key(ip), &IP_POLICY),
+        ]
+        .iter()
+        .filter_map(|(key, policy)| self.attempts.get(key)?.retry_after(policy, now))
+        .max();
+
+        match wait {
+            // Round up so clients never retry a moment too early
+            Some(wait) => Err(Duration::from_secs(wait.as_secs_f64().ceil().max(1.0) as u64)),
+            None => Ok(()),
+        }
+    }
+
+    /// Count a failed attempt. Returns the keys that this failure locked out.
+    pub fn record_failure(&self, email: &str, ip: IpAddr) -> Vec<Lockout> {
+        let now = Instant::now();
+        let mut locked = Vec::new();
+
+        for (key, policy, kind) in [
+            (account_key(email), &ACCOUNT_POLICY, Lockout::Account),
+            (ip_key(ip), &IP_POLICY, Lockout::Ip),
+        ] {
+            let mut entry = self.attempts.entry(key).or_insert(Attempts {
+                failures: 0,
+                last_failure: now,
+                locked_until: None,
+            });
+            if entry.is_stale(now) {
+                entry.failures = 0;
+                entry.locked_until = None;
+            }
+            entry.failures += 1;
+            entry.last_failure = now;
+            if entry.failures >= policy.lockout_after && entry.locked_until.is_none() {
+                entry.locked_until = Some(now + LOCKOUT_DURATION);
+                locked.push(kind);
+            }
+        }
+
+        locked
+    }
+
+    /// A successful login clears the account's failures. The IP keeps its count
+    /// so one valid account can't be used to reset guessing against others.
+    pub fn record_success(&self, email: &str) {
+        self.attempts.remove(&account_key(email));
+    }
+
+    /// Drop entries whose lockout or failure window has passed. Returns how many were removed.
+    pub fn prune(&self) -> usize {
+        let now = Instant::now();
+        let before = self.attempts.len();
+        self.attempts.retain(|_, a| !a.is_stale(now));
+        before - self.attempts.len()
+    }
+}
+
+fn account_key(email: &str) -> String {
+    format!("account:{}", email)
+}
+
+fn ip_key(ip: IpAddr) -> String {
+    format!("ip:{}", ip)
+}
+
+/// The client's IP: the last `X-Forwarded-For` entry when running behind a
+/// trusted reverse proxy, otherwise the socket peer address. Only the last
+/// entry was added by the proxy; earlier ones come from the client and can be
+/// anything.
+pub fn client_ip(headers: &HeaderMap, peer: SocketAddr, trust_forwarded_for: bool) -> IpAddr {
+    if trust_forwarded_for {
+        let forwarded = headers
+            .get_all("X-Forwarded-For")
+            .iter()
+            .next_back()
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.rsplit(',').next())
+            .and_then(|ip| ip.trim().parse().ok());
+        if let Some(ip) = forwarded {
+            return ip;
+        }
+    }
+    peer.ip()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_client_ip_ignores_spoofed_forwarded_entries() {
+        let peer: SocketAddr = "10.0.0.2:443".parse().unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Forwarded-For", "1.2.3.4, 203.0.113.7".parse().unwrap());
+
+        let expected: IpAddr = "203.0.113.7".parse().unwrap();
+        assert_eq!(client_ip(&headers, peer, true), expected);
+        assert_eq!(client_ip(&headers, peer, false), peer.ip());
+
+        // Across several header lines, the proxy's is the last
+        headers.append("X-Forwarded-For", "198.51.100.9".parse().unwrap());
+        let expected: IpAddr = "198.51.100.9".parse().unwrap();
+        assert_eq!(client_ip(&headers, peer, true), expected);
+    }
+}
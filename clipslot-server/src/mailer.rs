@@ -0,0 +1,73 @@
+use lettre::message::Mailbox;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+
+use crate::config::SmtpConfig;
+
+/// Sends notification emails over SMTP. A mailer without configuration
+/// silently drops everything, so callers don't need to check.
+pub struct Mailer {
+    smtp: Option<Smtp>,
+}
+
+struct Smtp {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+    public_url: String,
+}
+
+impl Mailer {
+    pub fn new(config: Option<&SmtpConfig>) -> Self {
+        let smtp = config.map(|config| Smtp {
+            transport: AsyncSmtpTransport::<Tokio1Executor>::from_url(&config.url)
+                .expect("Invalid SMTP_URL")
+                .build(),
+            from: config.from.parse().expect("Invalid SMTP_FROM address"),
+            public_url: config.public_url.clone(),
+        });
+        Self { smtp }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.smtp.is_some()
+    }
+
+    /// Absolute URL for a path on this server, for links in emails.
+    pub fn link(&self, path: &str) -> Option<String> {
+        self.smtp
+            .as_ref()
+            .map(|smtp| format!("{}{}", smtp.public_url, path))
+    }
+
+    /// Send a plain-text email in the background. Failures are logged.
+    pub fn send(&self, to: &str, subject: &str, body: String) {
+        let Some(smtp) = &self.smtp else {
+            return;
+        };
+        let message = to
+            .parse::<Mailbox>()
+            .map_err(|e| e.to_string())
+            .and_then(|to| {
+                Message::builder()
+                    .from(smtp.from.clone())
+                    .to(to)
+                    .subject(subject)
+                    .body(body)
+                    .map_err(|e| e.to_string())
+            });
+        let message = match message {
+            Ok(m) => m,
+            Err(e) => {
+                tracing::error!("Failed to build email to {}: {}", to, e);
+                return;
+            }
+        };
+
+        let transport = smtp.transport.clone();
+        let to = to.to_string();
+        tokio::spawn(async move {
+            if let Err(e) = transport.send(message).await {
+                tracing::error!("Failed to send email to {}: {}", to, e);
+            }
+        });
+    }
+}
@@ -1,30 +1,121 @@
+mod audit;
 mod config;
 mod db;
+mod device_activity;
+mod errors;
+mod jwt_keys;
+mod known_devices;
+mod login_guard;
+mod mailer;
 mod middleware;
 mod models;
+mod relay;
 mod routes;
+mod shutdown;
+mod webhooks;
+mod ws_limits;
 
 use std::sync::Arc;
 
 use dashmap::DashMap;
-use sqlx::postgres::PgPoolOptions;
-use tokio::sync::broadcast;
 use axum::http::{header, HeaderValue, Method};
+use axum_server::tls_rustls::RustlsConfig;
+use tower_http::compression::predicate::{NotForContentType, Predicate, SizeAbove};
+use tower_http::compression::CompressionLayer;
 use tower_http::cors::CorsLayer;
+use tower_http::limit::RequestBodyLimitLayer;
 use tower_http::trace::TraceLayer;
 use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
-use uuid::Uuid;
+
+/// How long to wait for WebSocket handlers to finish during shutdown.
+const WS_DRAIN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+/// How often to re-read the TLS certificate and key from disk.
+const TLS_RELOAD_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+/// Responses smaller than this aren't worth compressing.
+const COMPRESSION_MIN_BYTES: u16 = 1024;
 
 #[derive(Clone)]
 pub struct AppState {
-    pub db: sqlx::PgPool,
-    pub jwt_secret: String,
-    /// Per-user broadcast channels for WebSocket relay.
-    /// Key: user_id, Value: sender that broadcasts (origin_device_id, json_payload).
-    pub user_channels: Arc<DashMap<Uuid, broadcast::Sender<(Uuid, String)>>>,
+    pub db: db::Db,
+    /// Ed25519 token signing keys, shared by all instances through the database.
+    pub jwt_keys: Arc<jwt_keys::JwtKeys>,
+    /// Per-user broadcast channels for WebSocket relay, with sequence numbers
+    /// and a short replay buffer for reconnecting devices.
+    pub user_channels: Arc<relay::UserChannels>,
     /// Temporary link codes for key exchange: code -> (encrypted_key, created_at).
     pub link_codes: Arc<DashMap<String, (String, std::time::Instant)>>,
+    /// Graceful shutdown signal and WebSocket connection tracking.
+    pub shutdown: shutdown::Shutdown,
+    /// Background delivery of sync events to user-registered webhooks.
+    pub webhooks: Arc<webhooks::WebhookDispatcher>,
+    /// Failed login tracking for incremental delays and lockouts.
+    pub login_guard: Arc<login_guard::LoginGuard>,
+    /// Rate-limited `last_seen` updates for devices using the REST API.
+    pub device_activity: Arc<device_activity::DeviceActivity>,
+    /// Cached checks that a token's device hasn't been removed.
+    pub known_devices: Arc<known_devices::KnownDevices>,
+    /// SMTP delivery for security notifications; a no-op when unconfigured.
+    pub mailer: Arc<mailer::Mailer>,
+    /// Concurrent WebSocket connections per user and per client IP.
+    pub ws_limits: Arc<ws_limits::ConnectionLimits>,
+    pub trust_forwarded_for: bool,
+    /// Bearer token for `/metrics`; unset keeps the endpoint disabled.
+    pub metrics_token: Option<Arc<str>>,
+}
+
+#[cfg(test)]
+impl AppState {
+    /// A state over a fresh SQLite database, holding one user with one device.
+    /// Returns the state with the user and device ids.
+    pub async fn for_test() -> (Self, uuid::Uuid, uuid::Uuid) {
+        let path = std::env::temp_dir().join(format!("clipslot-test-{}.db", uuid::Uuid::new_v4()));
+        let db = db::Db::connect(&format!("sqlite://{}", path.display()))
+            .await
+            .unwrap();
+        db.migrate().await.unwrap();
+
+        let (user_id, device_id) = (uuid::Uuid::new_v4(), uuid::Uuid::new_v4());
+        let now = db::now();
+        db::with_db!(&db, pool => {
+            sqlx::query(
+                "INSERT INTO users (id, email, password_hash, created_at) VALUES ($1, $2, '', $3)",
+            )
+            .bind(user_id)
+            .bind(format!("{}@example.com", user_id))
+            .bind(now)
+            .execute(pool)
+            .await
+            .unwrap();
+            sqlx::query(
+                "INSERT INTO devices (id, user_id, name, device_type, last_seen, created_at)
+                 VALUES ($1, $2, 'Test', 'desktop', $3, $3)",
+            )
+            .bind(device_id)
+            .bind(user_id)
+            .bind(now)
+            .execute(pool)
+            .await
+            .unwrap();
+        });
+
+        let state = Self {
+            jwt_keys: Arc::new(jwt_keys::JwtKeys::load(db.clone(), None, None).await.unwrap()),
+            user_channels: Arc::new(relay::UserChannels::new()),
+            link_codes: Arc::new(DashMap::new()),
+            shutdown: shutdown::Shutdown::new(),
+            webhooks: Arc::new(webhooks::WebhookDispatcher::new(db.clone())),
+            login_guard: Arc::new(login_guard::LoginGuard::new()),
+            device_activity: Arc::new(device_activity::DeviceActivity::new(db.clone())),
+            known_devices: Arc::new(known_devices::KnownDevices::new(db.clone())),
+            mailer: Arc::new(mailer::Mailer::new(None)),
+            ws_limits: Arc::new(ws_limits::ConnectionLimits::new()),
+            trust_forwarded_for: false,
+            metrics_token: None,
+            db,
+        };
+        (state, user_id, device_id)
+    }
 }
 
 #[derive(OpenApi)]
@@ -33,30 +124,103 @@ pub struct AppState {
         routes::auth::register,
         routes::auth::login,
         routes::auth::register_device,
+        routes::auth::update_device,
         routes::auth::delete_device,
         routes::auth::list_devices,
+        routes::auth::revoke_device_page,
+        routes::auth::revoke_device,
+        routes::auth::jwks,
+        routes::api_keys::create_api_key,
+        routes::api_keys::list_api_keys,
+        routes::api_keys::revoke_api_key,
         routes::sync::get_slots,
         routes::sync::update_slot,
+        routes::sync::list_slot_versions,
+        routes::sync::restore_slot_version,
+        routes::sync::get_slot_settings,
+        routes::sync::update_slot_settings,
         routes::sync::get_history,
         routes::sync::push_history,
+        routes::sync::search_history,
         routes::sync::delete_history,
+        routes::attachments::create_attachment,
+        routes::attachments::get_attachment,
+        routes::attachments::upload_chunk,
+        routes::attachments::complete_attachment,
+        routes::attachments::download_chunk,
+        routes::webhooks::create_webhook,
+        routes::webhooks::list_webhooks,
+        routes::webhooks::delete_webhook,
+        routes::teams::create_team,
+        routes::teams::list_teams,
+        routes::teams::delete_team,
+        routes::teams::list_members,
+        routes::teams::add_member,
+        routes::teams::remove_member,
+        routes::teams::set_team_key,
+        routes::teams::share_team_key,
+        routes::teams::get_team_slots,
+        routes::teams::update_team_slot,
+        routes::shares::create_share,
+        routes::shares::open_share,
+        routes::metrics::metrics,
+        routes::metrics::health,
+        routes::telemetry::report,
     ),
     components(schemas(
+        errors::ApiError,
+        errors::ErrorCode,
         models::user::RegisterRequest,
         models::user::LoginRequest,
         models::user::AuthResponse,
         models::device::RegisterDeviceRequest,
+        models::device::DeviceMetadata,
         models::device::DeviceResponse,
+        models::device::RevokeDeviceParams,
+        jwt_keys::Jwk,
+        routes::auth::JwksResponse,
+        models::api_key::CreateApiKeyRequest,
+        models::api_key::CreateApiKeyResponse,
+        models::api_key::ApiKeyResponse,
         models::sync::SlotResponse,
         models::sync::UpdateSlotRequest,
+        models::sync::SlotSettings,
+        models::sync::SlotVersionResponse,
         models::sync::PushHistoryRequest,
         models::sync::HistoryResponse,
+        models::sync::HistoryDeltaResponse,
+        models::sync::HistoryTombstone,
+        models::sync::HistoryListResponse,
+        models::sync::HistoryPageResponse,
         models::sync::HistoryQuery,
+        models::sync::HistorySearchQuery,
+        models::attachment::CreateAttachmentRequest,
+        models::attachment::AttachmentResponse,
+        models::webhook::CreateWebhookRequest,
+        models::webhook::CreateWebhookResponse,
+        models::webhook::WebhookResponse,
+        models::team::CreateTeamRequest,
+        models::team::TeamResponse,
+        models::team::AddMemberRequest,
+        models::team::TeamMemberResponse,
+        models::team::SetTeamKeyRequest,
+        models::team::ShareTeamKeyRequest,
+        models::team::TeamSlotResponse,
+        models::share::CreateShareRequest,
+        models::share::CreateShareResponse,
+        models::share::ShareResponse,
+        models::telemetry::TelemetryReport,
+        models::telemetry::TelemetryDay,
     )),
     modifiers(&SecurityAddon),
     tags(
         (name = "Auth", description = "Authentication & device management"),
-        (name = "Sync", description = "Encrypted clipboard sync (slots & history)")
+        (name = "Sync", description = "Encrypted clipboard sync (slots & history)"),
+        (name = "Webhooks", description = "HMAC-signed callbacks on sync events"),
+        (name = "Teams", description = "Slots shared between members of a team"),
+        (name = "Shares", description = "One-time links for handing a snippet to anyone"),
+        (name = "Metrics", description = "Operational counters for monitoring"),
+        (name = "Telemetry", description = "Anonymous usage counts from clients that opt in")
     ),
     security(("bearer" = []))
 )]
@@ -86,16 +250,50 @@ async fn main() {
 
     let config = config::Config::from_env();
 
-    let pool = PgPoolOptions::new()
-        .max_connections(10)
-        .connect(&config.database_url)
+    let pool = db::Db::connect(&config.database_url)
         .await
         .expect("Failed to connect to database");
 
-    sqlx::migrate!("./src/db/migrations")
-        .run(&pool)
-        .await
-        .expect("Failed to run migrations");
+    pool.migrate().await.expect("Failed to run migrations");
+
+    // `clipslot-server rotate-signing-key [--revoke-previous]`: rotate the JWT signing
+    // key and exit. Running instances pick up the new key within a minute.
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("rotate-signing-key") {
+        let revoke = args.iter().any(|a| a == "--revoke-previous");
+        let kid = jwt_keys::rotate(&pool, revoke)
+            .await
+            .expect("Failed to rotate signing key");
+        if revoke {
+            tracing::info!("New signing key {}; previous keys revoked", kid);
+        } else {
+            tracing::info!(
+                "New signing key {}; previous keys verify until their tokens expire",
+                kid
+            );
+        }
+        pool.close().await;
+        return;
+    }
+
+    let jwt_keys = Arc::new(
+        jwt_keys::JwtKeys::load(pool.clone(), config.jwt_secret.clone(), config.public_url.clone())
+            .await
+            .expect("Failed to load JWT signing keys"),
+    );
+
+    // Pick up signing keys rotated from another process (every 60 seconds)
+    {
+        let keys = jwt_keys.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(std::time::Duration::from_secs(60)).await;
+                if let Err(e) = keys.reload().await {
+                    tracing::error!("Failed to reload JWT signing keys: {}", e);
+                }
+            }
+        });
+    }
 
     let cors = if config.cors_origins == "*" {
         CorsLayer::permissive()
@@ -119,10 +317,22 @@ async fn main() {
                 header::CONTENT_TYPE,
                 header::ACCEPT,
                 header::ORIGIN,
+                middleware::request_id::REQUEST_ID_HEADER,
             ])
+            .expose_headers([middleware::request_id::REQUEST_ID_HEADER])
             .allow_credentials(true)
     };
 
+    // gzip or brotli, whichever the client prefers. Small bodies, images, event
+    // streams and (encrypted, so incompressible) attachment chunks are sent as-is.
+    let compression = CompressionLayer::new().compress_when(
+        SizeAbove::new(COMPRESSION_MIN_BYTES)
+            .and(NotForContentType::GRPC)
+            .and(NotForContentType::IMAGES)
+            .and(NotForContentType::SSE)
+            .and(NotForContentType::const_new("application/octet-stream")),
+    );
+
     let link_codes: Arc<DashMap<String, (String, std::time::Instant)>> =
         Arc::new(DashMap::new());
 
@@ -144,22 +354,212 @@ async fn main() {
         });
     }
 
+    let login_guard = Arc::new(login_guard::LoginGuard::new());
+
+    // Forget expired lockouts and stale failure counts (every 60 seconds)
+    {
+        let guard = login_guard.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(std::time::Duration::from_secs(60)).await;
+                let removed = guard.prune();
+                if removed > 0 {
+                    tracing::debug!("Cleaned up {} expired login attempt records", removed);
+                }
+            }
+        });
+    }
+
+    // Delete abandoned attachment uploads (every hour)
+    {
+        let db = pool.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(std::time::Duration::from_secs(3600)).await;
+                match routes::attachments::prune_unreferenced(&db).await {
+                    Ok(0) => {}
+                    Ok(removed) => {
+                        tracing::debug!("Cleaned up {} unreferenced attachments", removed)
+                    }
+                    Err(e) => tracing::error!("Failed to clean up attachments: {}", e),
+                }
+            }
+        });
+    }
+
+    // Move the oldest history of very large accounts to the archive (every hour)
+    {
+        let db = pool.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(std::time::Duration::from_secs(3600)).await;
+                match routes::sync::archive_old_history(&db).await {
+                    Ok(0) => {}
+                    Ok(moved) => tracing::info!("Archived {} history items", moved),
+                    Err(e) => tracing::error!("Failed to archive history: {}", e),
+                }
+            }
+        });
+    }
+
+    // Delete share links that expired unopened (every hour)
+    {
+        let db = pool.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(std::time::Duration::from_secs(3600)).await;
+                match routes::shares::prune_expired(&db).await {
+                    Ok(0) => {}
+                    Ok(removed) => tracing::debug!("Cleaned up {} expired shares", removed),
+                    Err(e) => tracing::error!("Failed to clean up shares: {}", e),
+                }
+            }
+        });
+    }
+
+    let device_activity = Arc::new(device_activity::DeviceActivity::new(pool.clone()));
+
+    // Forget last_seen write times once they no longer throttle anything (every 60 seconds)
+    {
+        let activity = device_activity.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(std::time::Duration::from_secs(60)).await;
+                let removed = activity.prune();
+                if removed > 0 {
+                    tracing::debug!("Cleaned up {} device activity records", removed);
+                }
+            }
+        });
+    }
+
+    let known_devices = Arc::new(known_devices::KnownDevices::new(pool.clone()));
+
+    // Forget stale device lookups (every 60 seconds)
+    {
+        let devices = known_devices.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(std::time::Duration::from_secs(60)).await;
+                let removed = devices.prune();
+                if removed > 0 {
+                    tracing::debug!("Cleaned up {} device lookups", removed);
+                }
+            }
+        });
+    }
+
+    let user_channels = Arc::new(relay::UserChannels::new());
+
+    if let Some(redis_url) = &config.redis_url {
+        user_channels
+            .connect_redis(redis_url)
+            .await
+            .expect("Failed to connect to Redis");
+    }
+
+    // Forget expired replay buffers and idle users' channels (every 60 seconds)
+    {
+        let channels = user_channels.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(std::time::Duration::from_secs(60)).await;
+                let removed = channels.prune();
+                if removed > 0 {
+                    tracing::debug!("Cleaned up {} idle relay channels", removed);
+                }
+            }
+        });
+    }
+
+    let shutdown = shutdown::Shutdown::new();
     let state = AppState {
-        db: pool,
-        jwt_secret: config.jwt_secret,
-        user_channels: Arc::new(DashMap::new()),
+        db: pool.clone(),
+        jwt_keys,
+        user_channels,
         link_codes,
+        shutdown: shutdown.clone(),
+        webhooks: Arc::new(webhooks::WebhookDispatcher::new(pool.clone())),
+        login_guard,
+        device_activity,
+        known_devices,
+        mailer: Arc::new(mailer::Mailer::new(config.smtp.as_ref())),
+        ws_limits: Arc::new(ws_limits::ConnectionLimits::new()),
+        trust_forwarded_for: config.trust_forwarded_for,
+        metrics_token: config.metrics_token.as_deref().map(Arc::from),
     };
 
     let app = routes::api_router(state)
         .merge(SwaggerUi::new("/docs").url("/api-docs/openapi.json", ApiDoc::openapi()))
+        .layer(RequestBodyLimitLayer::new(middleware::validate::MAX_BODY_BYTES))
+        .layer(compression)
         .layer(cors)
-        .layer(TraceLayer::new_for_http());
+        .layer(TraceLayer::new_for_http())
+        .layer(axum::middleware::from_fn(middleware::request_id::assign));
 
     let listener = tokio::net::TcpListener::bind(&config.listen_addr)
         .await
         .unwrap();
+    let scheme = if config.tls.is_some() { "https" } else { "http" };
     tracing::info!("Listening on {}", config.listen_addr);
-    tracing::info!("Swagger UI at http://{}/docs/", config.listen_addr);
-    axum::serve(listener, app).await.unwrap();
+    tracing::info!("Swagger UI at {}://{}/docs/", scheme, config.listen_addr);
+
+    // On SIGTERM/Ctrl+C: stop accepting connections, let in-flight requests finish,
+    // close WebSockets with a reconnect hint, then release the database pool.
+    let ws_shutdown = shutdown.clone();
+    let shutdown_signal = async move {
+        shutdown::signal().await;
+        tracing::info!("Shutdown signal received, draining connections");
+        ws_shutdown.trigger();
+    };
+    let service = app.into_make_service_with_connect_info::<std::net::SocketAddr>();
+
+    match &config.tls {
+        Some(tls) => {
+            let rustls = RustlsConfig::from_pem_file(&tls.cert_path, &tls.key_path)
+                .await
+                .expect("Failed to load TLS certificate and key");
+
+            // Pick up renewed certificates (e.g. from certbot) without a restart
+            {
+                let rustls = rustls.clone();
+                let (cert_path, key_path) = (tls.cert_path.clone(), tls.key_path.clone());
+                tokio::spawn(async move {
+                    loop {
+                        tokio::time::sleep(TLS_RELOAD_INTERVAL).await;
+                        if let Err(e) = rustls.reload_from_pem_file(&cert_path, &key_path).await {
+                            tracing::error!("Failed to reload TLS certificate: {}", e);
+                        }
+                    }
+                });
+            }
+
+            let handle = axum_server::Handle::new();
+            {
+                let handle = handle.clone();
+                tokio::spawn(async move {
+                    shutdown_signal.await;
+                    handle.graceful_shutdown(Some(WS_DRAIN_TIMEOUT));
+                });
+            }
+            axum_server::from_tcp_rustls(listener.into_std().unwrap(), rustls)
+                .handle(handle)
+                .serve(service)
+                .await
+                .unwrap();
+        }
+        None => {
+            axum::serve(listener, service)
+                .with_graceful_shutdown(shutdown_signal)
+                .await
+                .unwrap();
+        }
+    }
+
+    let remaining = shutdown.drained(WS_DRAIN_TIMEOUT).await;
+    if remaining > 0 {
+        tracing::warn!("{} WebSocket connection(s) still open at shutdown", remaining);
+    }
+    pool.close().await;
+    tracing::info!("Server stopped");
 }
@@ -1,27 +1,68 @@
 use axum::{
     extract::FromRequestParts,
     http::{request::Parts, StatusCode},
+    Json,
 };
-use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
 use uuid::Uuid;
 
-use crate::models::user::Claims;
+use crate::db::{self, with_db, Db};
+use crate::errors::{err, ApiError};
+use crate::models::api_key::{hash_key, split_scopes, API_KEY_PREFIX};
+use crate::jwt_keys::JwtKeys;
+use crate::models::user::{Claims, TOKEN_LIFETIME_SECS};
 use crate::AppState;
 
-/// Extractor for authenticated requests. Extracts user_id and device_id from JWT.
+/// Extractor for authenticated requests. Accepts a JWT (user_id and device_id
+/// from its claims) or an API key (scoped, never bound to a device).
 pub struct AuthUser {
     pub user_id: Uuid,
     pub device_id: Option<Uuid>,
+    /// Scopes granted by the API key used; None for a full JWT session.
+    pub scopes: Option<Vec<String>>,
+}
+
+impl AuthUser {
+    pub fn is_api_key(&self) -> bool {
+        self.scopes.is_some()
+    }
+
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes
+            .as_ref()
+            .is_none_or(|scopes| scopes.iter().any(|s| s == scope))
+    }
+
+    /// Rejects API keys; for account-level endpoints that need a full session.
+    pub fn require_session(&self) -> Result<(), (StatusCode, Json<ApiError>)> {
+        if self.is_api_key() {
+            return Err(err(StatusCode::FORBIDDEN, "Not available with an API key"));
+        }
+        Ok(())
+    }
+
+    /// Rejects API keys that weren't granted `scope`. Sessions pass.
+    pub fn require_scope(&self, scope: &str) -> Result<(), (StatusCode, Json<ApiError>)> {
+        if !self.has_scope(scope) {
+            return Err(err(
+                StatusCode::FORBIDDEN,
+                &format!("API key lacks the {} scope", scope),
+            ));
+        }
+        Ok(())
+    }
 }
 
 impl FromRequestParts<AppState> for AuthUser {
-    type Rejection = (StatusCode, &'static str);
+    type Rejection = (StatusCode, Json<ApiError>);
 
     fn from_request_parts(
         parts: &mut Parts,
         state: &AppState,
     ) -> impl std::future::Future<Output = Result<Self, Self::Rejection>> + Send {
-        let jwt_secret = state.jwt_secret.clone();
+        let jwt_keys = state.jwt_keys.clone();
+        let db = state.db.clone();
+        let device_activity = state.device_activity.clone();
+        let known_devices = state.known_devices.clone();
         let auth_header = parts
             .headers
             .get("Authorization")
@@ -30,53 +71,94 @@ impl FromRequestParts<AppState> for AuthUser {
 
         async move {
             let header = auth_header
-                .ok_or((StatusCode::UNAUTHORIZED, "Missing Authorization header"))?;
+                .ok_or_else(|| err(StatusCode::UNAUTHORIZED, "Missing Authorization header"))?;
 
             let token = header
                 .strip_prefix("Bearer ")
-                .ok_or((StatusCode::UNAUTHORIZED, "Invalid Authorization format"))?;
+                .ok_or_else(|| err(StatusCode::UNAUTHORIZED, "Invalid Authorization format"))?;
+
+            if token.starts_with(API_KEY_PREFIX) {
+                return authenticate_api_key(&db, token)
+                    .await
+                    .map_err(|(status, msg)| err(status, msg));
+            }
+
+            let claims = jwt_keys
+                .verify(token)
+                .await
+                .map_err(|_| err(StatusCode::UNAUTHORIZED, "Invalid or expired token"))?;
 
-            let token_data = decode::<Claims>(
-                token,
-                &DecodingKey::from_secret(jwt_secret.as_bytes()),
-                &Validation::default(),
-            )
-            .map_err(|_| (StatusCode::UNAUTHORIZED, "Invalid or expired token"))?;
+            if let Some(device_id) = claims.device_id {
+                // Removed devices lose access before their tokens expire
+                let exists = known_devices
+                    .exists(device_id)
+                    .await
+                    .map_err(|_| err(StatusCode::INTERNAL_SERVER_ERROR, "Database error"))?;
+                if !exists {
+                    return Err(err(StatusCode::UNAUTHORIZED, "Device has been removed"));
+                }
+                device_activity.touch(device_id);
+            }
 
             Ok(AuthUser {
-                user_id: token_data.claims.sub,
-                device_id: token_data.claims.device_id,
+                user_id: claims.sub,
+                device_id: claims.device_id,
+                scopes: None,
             })
         }
     }
 }
 
+async fn authenticate_api_key(db: &Db, key: &str) -> Result<AuthUser, (StatusCode, &'static str)> {
+    let key_hash = hash_key(key);
+
+    let row = with_db!(db, pool => {
+        sqlx::query_as::<_, (Uuid, Uuid, String)>(
+            "SELECT id, user_id, scopes FROM api_keys WHERE key_hash = $1 AND revoked_at IS NULL",
+        )
+        .bind(&key_hash)
+        .fetch_optional(pool)
+        .await
+    })
+    .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Database error"))?;
+
+    let (key_id, user_id, scopes) =
+        row.ok_or((StatusCode::UNAUTHORIZED, "Invalid or revoked API key"))?;
+
+    let _ = with_db!(db, pool => {
+        sqlx::query("UPDATE api_keys SET last_used_at = $1 WHERE id = $2")
+            .bind(db::now())
+            .bind(key_id)
+            .execute(pool)
+            .await
+            .map(|_| ())
+    });
+
+    Ok(AuthUser {
+        user_id,
+        device_id: None,
+        scopes: Some(split_scopes(&scopes)),
+    })
+}
+
 pub fn create_token(
     user_id: Uuid,
     device_id: Option<Uuid>,
-    secret: &str,
+    keys: &JwtKeys,
 ) -> Result<String, jsonwebtoken::errors::Error> {
     let now = chrono::Utc::now().timestamp() as usize;
     let claims = Claims {
         sub: user_id,
         device_id,
-        exp: now + 7 * 24 * 3600,
+        exp: now + TOKEN_LIFETIME_SECS,
         iat: now,
+        iss: keys.public_url().map(str::to_string),
+        aud: keys.public_url().map(str::to_string),
     };
-    encode(
-        &Header::default(),
-        &claims,
-        &EncodingKey::from_secret(secret.as_bytes()),
-    )
+    keys.sign(&claims)
 }
 
 /// Validate a token string and return claims. Used by WebSocket auth.
-pub fn validate_token(token: &str, secret: &str) -> Result<Claims, ()> {
-    decode::<Claims>(
-        token,
-        &DecodingKey::from_secret(secret.as_bytes()),
-        &Validation::default(),
-    )
-    .map(|data| data.claims)
-    .map_err(|_| ())
+pub async fn validate_token(token: &str, keys: &JwtKeys) -> Result<Claims, ()> {
+    keys.verify(token).await
 }
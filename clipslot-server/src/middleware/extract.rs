@@ -0,0 +1,122 @@
+use axum::{
+    extract::{
+        rejection::{JsonRejection, PathRejection, QueryRejection},
+        FromRequest, FromRequestParts, Path, Query, Request,
+    },
+    http::{request::Parts, StatusCode},
+    Json,
+};
+
+use crate::errors::{err, ApiError};
+
+// axum's own extractors reject with a plain-text body. These wrappers report
+// the same problems as `ApiError` JSON, with a code and the request ID, like
+// every other error. Handlers take them in place of `Json`, `Path` and `Query`.
+
+/// `Json<T>` with JSON rejections: 415 without a JSON content type, 413 for an
+/// oversized body, 422 for anything malformed.
+pub struct ApiJson<T>(pub T);
+
+/// `Path<T>` with JSON rejections.
+pub struct ApiPath<T>(pub T);
+
+/// `Query<T>` with JSON rejections.
+pub struct ApiQuery<T>(pub T);
+
+pub fn json_rejection(rejection: JsonRejection) -> (StatusCode, Json<ApiError>) {
+    let status = match &rejection {
+        JsonRejection::MissingJsonContentType(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+        r if r.status() == StatusCode::PAYLOAD_TOO_LARGE => StatusCode::PAYLOAD_TOO_LARGE,
+        _ => StatusCode::UNPROCESSABLE_ENTITY,
+    };
+    err(status, &rejection.body_text())
+}
+
+impl<S, T> FromRequest<S> for ApiJson<T>
+where
+    S: Send + Sync,
+    Json<T>: FromRequest<S, Rejection = JsonRejection>,
+{
+    type Rejection = (StatusCode, Json<ApiError>);
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(value) = Json::<T>::from_request(req, state)
+            .await
+            .map_err(json_rejection)?;
+        Ok(Self(value))
+    }
+}
+
+impl<S, T> FromRequestParts<S> for ApiPath<T>
+where
+    S: Send + Sync,
+    Path<T>: FromRequestParts<S, Rejection = PathRejection>,
+{
+    type Rejection = (StatusCode, Json<ApiError>);
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Path(value) = Path::<T>::from_request_parts(parts, state)
+            .await
+            .map_err(|rejection| err(rejection.status(), &rejection.body_text()))?;
+        Ok(Self(value))
+    }
+}
+
+impl<S, T> FromRequestParts<S> for ApiQuery<T>
+where
+    S: Send + Sync,
+    Query<T>: FromRequestParts<S, Rejection = QueryRejection>,
+{
+    type Rejection = (StatusCode, Json<ApiError>);
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Query(value) = Query::<T>::from_request_parts(parts, state)
+            .await
+            .map_err(|rejection| err(rejection.status(), &rejection.body_text()))?;
+        Ok(Self(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::sync::{HistoryQuery, PushHistoryRequest};
+
+    fn error_code(rejection: (StatusCode, Json<ApiError>)) -> (StatusCode, serde_json::Value) {
+        let (status, Json(body)) = rejection;
+        (status, serde_json::to_value(body).unwrap()["code"].clone())
+    }
+
+    #[tokio::test]
+    async fn test_rejections_are_api_errors() {
+        let req = Request::builder()
+            .uri("/history?limit=lots")
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let (mut parts, _) = req.into_parts();
+        let Err(rejection) = ApiQuery::<HistoryQuery>::from_request_parts(&mut parts, &()).await
+        else {
+            panic!("invalid query accepted");
+        };
+        assert_eq!(
+            error_code(rejection),
+            (StatusCode::BAD_REQUEST, serde_json::json!("bad_request"))
+        );
+
+        let req = Request::builder()
+            .method("POST")
+            .uri("/history")
+            .body(axum::body::Body::from("{}"))
+            .unwrap();
+        let Err(rejection) = ApiJson::<PushHistoryRequest>::from_request(req, &()).await else {
+            panic!("body without a content type accepted");
+        };
+        assert_eq!(
+            error_code(rejection),
+            (
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                serde_json::json!("unsupported_media_type")
+            )
+        );
+    }
+}
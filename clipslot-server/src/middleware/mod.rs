@@ -1 +1,4 @@
 pub mod auth;
+pub mod extract;
+pub mod request_id;
+pub mod validate;
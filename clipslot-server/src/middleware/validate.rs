@@ -0,0 +1,172 @@
+use axum::{
+    extract::{rejection::JsonRejection, FromRequest, Request},
+    http::StatusCode,
+    Json,
+};
+
+use crate::errors::{err, ApiError};
+use crate::middleware::extract::json_rejection;
+use crate::models::attachment::{CreateAttachmentRequest, MAX_ATTACHMENT_BYTES};
+use crate::models::device::{DeviceMetadata, RegisterDeviceRequest, MAX_DEVICE_METADATA_LEN};
+use crate::models::share::{CreateShareRequest, MAX_SHARE_TTL_SECS};
+use crate::models::sync::{
+    check_blob_size, check_search_tokens, is_valid_content_hash, BlobError, PushHistoryRequest,
+    SlotSettings, UpdateSlotRequest, MAX_SLOT_COUNT,
+};
+use crate::models::telemetry::{
+    TelemetryReport, MAX_TELEMETRY_COUNT, MAX_TELEMETRY_DAYS, MAX_TELEMETRY_LABEL_LEN,
+};
+
+/// Largest request body accepted by any endpoint: a maximum-size blob once
+/// base64-encoded, plus room for the surrounding JSON.
+pub const MAX_BODY_BYTES: usize = 2 * 1024 * 1024;
+
+/// Request bodies that can be checked before they reach a handler.
+pub trait Validate {
+    fn validate(&self) -> Result<(), (StatusCode, &'static str)>;
+}
+
+/// Like `Json<T>`, but runs `T::validate` and reports every rejection as a JSON
+/// error: 413 for oversized bodies or blobs, 422 for malformed or invalid ones.
+pub struct ValidJson<T>(pub T);
+
+impl<S, T> FromRequest<S> for ValidJson<T>
+where
+    S: Send + Sync,
+    T: Validate,
+    Json<T>: FromRequest<S, Rejection = JsonRejection>,
+{
+    type Rejection = (StatusCode, Json<ApiError>);
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(value) = Json::<T>::from_request(req, state)
+            .await
+            .map_err(json_rejection)?;
+        value
+            .validate()
+            .map_err(|(status, message)| err(status, message))?;
+        Ok(Self(value))
+    }
+}
+
+/// Status for a blob that failed to decode in a handler.
+pub fn blob_error_status(error: BlobError) -> StatusCode {
+    match error {
+        BlobError::TooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+        BlobError::InvalidBase64 => StatusCode::UNPROCESSABLE_ENTITY,
+    }
+}
+
+impl Validate for UpdateSlotRequest {
+    fn validate(&self) -> Result<(), (StatusCode, &'static str)> {
+        check_blob_size(&self.encrypted_blob)
+            .map_err(|e| (blob_error_status(e), e.message()))
+    }
+}
+
+impl Validate for SlotSettings {
+    fn validate(&self) -> Result<(), (StatusCode, &'static str)> {
+        if !(1..=MAX_SLOT_COUNT).contains(&self.slot_count) {
+            return Err((StatusCode::UNPROCESSABLE_ENTITY, "slot_count must be 1-10"));
+        }
+        Ok(())
+    }
+}
+
+impl Validate for CreateAttachmentRequest {
+    fn validate(&self) -> Result<(), (StatusCode, &'static str)> {
+        if self.size <= 0 {
+            return Err((StatusCode::UNPROCESSABLE_ENTITY, "size must be positive"));
+        }
+        if self.size > MAX_ATTACHMENT_BYTES {
+            return Err((StatusCode::PAYLOAD_TOO_LARGE, "Attachment too large"));
+        }
+        Ok(())
+    }
+}
+
+impl Validate for CreateShareRequest {
+    fn validate(&self) -> Result<(), (StatusCode, &'static str)> {
+        if self
+            .expires_in_secs
+            .is_some_and(|secs| !(1..=MAX_SHARE_TTL_SECS).contains(&secs))
+        {
+            return Err((
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "expires_in_secs must be between 1 second and 7 days",
+            ));
+        }
+        check_blob_size(&self.encrypted_blob)
+            .map_err(|e| (blob_error_status(e), e.message()))
+    }
+}
+
+impl Validate for PushHistoryRequest {
+    fn validate(&self) -> Result<(), (StatusCode, &'static str)> {
+        if !is_valid_content_hash(&self.content_hash) {
+            return Err((
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "content_hash must be a hex SHA-256 digest",
+            ));
+        }
+        check_search_tokens(&self.search_tokens)
+            .map_err(|message| (StatusCode::UNPROCESSABLE_ENTITY, message))?;
+        check_blob_size(&self.encrypted_blob)
+            .map_err(|e| (blob_error_status(e), e.message()))
+    }
+}
+
+impl Validate for DeviceMetadata {
+    fn validate(&self) -> Result<(), (StatusCode, &'static str)> {
+        let fields = [&self.app_version, &self.os_version, &self.hostname];
+        if fields
+            .iter()
+            .any(|f| f.as_ref().is_some_and(|v| v.len() > MAX_DEVICE_METADATA_LEN))
+        {
+            return Err((
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "app_version, os_version and hostname must be at most 128 bytes",
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Validate for RegisterDeviceRequest {
+    fn validate(&self) -> Result<(), (StatusCode, &'static str)> {
+        self.metadata.validate()
+    }
+}
+
+impl Validate for TelemetryReport {
+    fn validate(&self) -> Result<(), (StatusCode, &'static str)> {
+        let is_label = |s: &str| {
+            !s.is_empty()
+                && s.len() <= MAX_TELEMETRY_LABEL_LEN
+                && s.bytes().all(|b| b.is_ascii_alphanumeric() || b"._-".contains(&b))
+        };
+        if !is_label(&self.app_version) || !is_label(&self.platform) {
+            return Err((
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "app_version and platform must be short plain labels",
+            ));
+        }
+        if self.days.is_empty() || self.days.len() > MAX_TELEMETRY_DAYS {
+            return Err((
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "days must hold between 1 and 31 entries",
+            ));
+        }
+        for day in &self.days {
+            let is_date = chrono::NaiveDate::parse_from_str(&day.day, "%Y-%m-%d").is_ok();
+            if !is_date || day.day.len() != 10 {
+                return Err((StatusCode::UNPROCESSABLE_ENTITY, "day must be YYYY-MM-DD"));
+            }
+            let counts = [day.captures, day.slot_saves, day.slot_pastes, day.sync_errors];
+            if counts.iter().any(|n| !(0..=MAX_TELEMETRY_COUNT).contains(n)) {
+                return Err((StatusCode::UNPROCESSABLE_ENTITY, "counts are out of range"));
+            }
+        }
+        Ok(())
+    }
+}
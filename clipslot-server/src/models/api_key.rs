@@ -0,0 +1,89 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Every key starts with this, so the extractor can tell keys from JWTs.
+pub const API_KEY_PREFIX: &str = "csk_";
+
+/// Scopes an API key can be granted.
+pub const SCOPE_SLOTS_READ: &str = "slots:read";
+pub const SCOPE_SLOTS_WRITE: &str = "slots:write";
+pub const SCOPE_HISTORY_READ: &str = "history:read";
+pub const SCOPE_HISTORY_WRITE: &str = "history:write";
+
+pub const ALL_SCOPES: &[&str] = &[
+    SCOPE_SLOTS_READ,
+    SCOPE_SLOTS_WRITE,
+    SCOPE_HISTORY_READ,
+    SCOPE_HISTORY_WRITE,
+];
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct ApiKey {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    pub prefix: String,
+    pub scopes: String,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateApiKeyRequest {
+    /// Label shown in the key list (e.g. "backup script")
+    pub name: String,
+    /// Scopes to grant: "slots:read", "slots:write", "history:read", "history:write"
+    pub scopes: Vec<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ApiKeyResponse {
+    pub id: Uuid,
+    pub name: String,
+    /// First characters of the key, to recognise it in the list
+    pub prefix: String,
+    pub scopes: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CreateApiKeyResponse {
+    /// The full key. It is only returned once and cannot be recovered.
+    pub key: String,
+    #[serde(flatten)]
+    pub api_key: ApiKeyResponse,
+}
+
+impl From<ApiKey> for ApiKeyResponse {
+    fn from(k: ApiKey) -> Self {
+        Self {
+            id: k.id,
+            name: k.name,
+            prefix: k.prefix,
+            scopes: split_scopes(&k.scopes),
+            created_at: k.created_at,
+            last_used_at: k.last_used_at,
+            revoked_at: k.revoked_at,
+        }
+    }
+}
+
+pub fn split_scopes(scopes: &str) -> Vec<String> {
+    scopes
+        .split(',')
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Hex SHA-256 of a key, as stored in `api_keys.key_hash`. Keys are random and
+/// high-entropy, so a fast hash is enough.
+pub fn hash_key(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
@@ -0,0 +1,73 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Size of every chunk but the last. Fits under the request body limit.
+pub const CHUNK_BYTES: i64 = 1024 * 1024;
+/// Largest attachment accepted, after encryption.
+pub const MAX_ATTACHMENT_BYTES: i64 = 50 * 1024 * 1024;
+/// Attachment storage per account, counting unfinished uploads.
+pub const MAX_USER_ATTACHMENT_BYTES: i64 = 1024 * 1024 * 1024;
+
+/// Number of chunks an attachment of `size` bytes is split into.
+pub fn chunk_count(size: i64) -> i32 {
+    ((size + CHUNK_BYTES - 1) / CHUNK_BYTES) as i32
+}
+
+/// Expected length of chunk `index` of an attachment of `size` bytes.
+pub fn chunk_len(size: i64, index: i32) -> i64 {
+    (size - index as i64 * CHUNK_BYTES).min(CHUNK_BYTES)
+}
+
+// ── Database rows ────────────────────────────────────────────────────────────
+
+#[derive(Debug, sqlx::FromRow)]
+#[allow(dead_code)]
+pub struct Attachment {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub size: i64,
+    pub chunk_count: i32,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+// ── API types ────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateAttachmentRequest {
+    /// Client-generated id; creating the same id again resumes the upload
+    pub id: Uuid,
+    /// Size of the encrypted attachment in bytes
+    pub size: i64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AttachmentResponse {
+    pub id: Uuid,
+    pub size: i64,
+    /// Every chunk but the last is exactly this many bytes
+    pub chunk_size: i64,
+    pub chunk_count: i32,
+    /// Chunks not uploaded yet; empty once the upload can be completed
+    pub missing_chunks: Vec<i32>,
+    /// True once completed; only complete attachments can be downloaded or
+    /// referenced from history
+    pub complete: bool,
+}
+
+impl AttachmentResponse {
+    pub fn new(attachment: &Attachment, uploaded: &[i32]) -> Self {
+        Self {
+            id: attachment.id,
+            size: attachment.size,
+            chunk_size: CHUNK_BYTES,
+            chunk_count: attachment.chunk_count,
+            missing_chunks: (0..attachment.chunk_count)
+                .filter(|i| !uploaded.contains(i))
+                .collect(),
+            complete: attachment.completed_at.is_some(),
+        }
+    }
+}
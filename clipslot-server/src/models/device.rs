@@ -10,16 +10,39 @@ pub struct Device {
     pub user_id: Uuid,
     pub name: String,
     pub device_type: String,
+    pub app_version: Option<String>,
+    pub os_version: Option<String>,
+    pub hostname: Option<String>,
     pub last_seen: DateTime<Utc>,
     pub created_at: DateTime<Utc>,
 }
 
+/// Longest app version, OS version or hostname accepted.
+pub const MAX_DEVICE_METADATA_LEN: usize = 128;
+
+/// Details that tell a user's devices of the same type apart. Sent at
+/// registration and again whenever the app starts syncing, so outdated
+/// installs show up in the device list.
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct DeviceMetadata {
+    /// ClipSlot version (e.g., "1.4.0")
+    #[serde(default)]
+    pub app_version: Option<String>,
+    /// Operating system release (e.g., "macOS 14.5")
+    #[serde(default)]
+    pub os_version: Option<String>,
+    #[serde(default)]
+    pub hostname: Option<String>,
+}
+
 #[derive(Debug, Deserialize, ToSchema)]
 pub struct RegisterDeviceRequest {
     /// Device name (e.g., "MacBook Pro")
     pub name: String,
     /// Device type: "macos", "windows", "linux"
     pub device_type: String,
+    #[serde(flatten)]
+    pub metadata: DeviceMetadata,
 }
 
 #[derive(Debug, Serialize, ToSchema)]
@@ -27,6 +50,9 @@ pub struct DeviceResponse {
     pub id: Uuid,
     pub name: String,
     pub device_type: String,
+    pub app_version: Option<String>,
+    pub os_version: Option<String>,
+    pub hostname: Option<String>,
     pub last_seen: DateTime<Utc>,
     pub created_at: DateTime<Utc>,
 }
@@ -37,8 +63,17 @@ impl From<Device> for DeviceResponse {
             id: d.id,
             name: d.name,
             device_type: d.device_type,
+            app_version: d.app_version,
+            os_version: d.os_version,
+            hostname: d.hostname,
             last_seen: d.last_seen,
             created_at: d.created_at,
         }
     }
 }
+
+/// Token from the revoke link in a "new device" email.
+#[derive(Debug, Deserialize, ToSchema, utoipa::IntoParams)]
+pub struct RevokeDeviceParams {
+    pub token: String,
+}
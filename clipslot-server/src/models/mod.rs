@@ -1,3 +1,9 @@
+pub mod api_key;
+pub mod attachment;
 pub mod device;
+pub mod share;
 pub mod sync;
+pub mod team;
+pub mod telemetry;
 pub mod user;
+pub mod webhook;
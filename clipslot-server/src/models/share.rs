@@ -0,0 +1,36 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Lifetime of a share link when the request doesn't set one.
+pub const DEFAULT_SHARE_TTL_SECS: i64 = 60 * 60;
+/// Longest a share link can stay valid.
+pub const MAX_SHARE_TTL_SECS: i64 = 7 * 24 * 60 * 60;
+/// Unopened share links a single user may have at once.
+pub const MAX_SHARES_PER_USER: i64 = 100;
+/// Length of the random id in a share link.
+pub const SHARE_ID_LEN: usize = 16;
+
+// ── API types ────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateShareRequest {
+    /// Base64 blob encrypted with a key only the link holds
+    pub encrypted_blob: String,
+    /// Seconds until the link stops working if nobody opens it (default 1 hour)
+    #[serde(default)]
+    pub expires_in_secs: Option<i64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CreateShareResponse {
+    pub id: String,
+    /// Path of the page that opens the share; append `#<key>` to make the link
+    pub path: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ShareResponse {
+    pub encrypted_blob: String,
+}
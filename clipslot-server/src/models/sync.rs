@@ -1,8 +1,84 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 use uuid::Uuid;
 
+/// Largest encrypted blob (after base64 decoding) accepted for a slot or history item.
+pub const MAX_BLOB_BYTES: usize = 1024 * 1024;
+/// Most permanent slots an account can have.
+pub const MAX_SLOT_COUNT: i32 = 10;
+/// Most search tokens stored for one history item.
+pub const MAX_SEARCH_TOKENS: usize = 256;
+/// Most search tokens in one query; every one of them must match.
+pub const MAX_QUERY_TOKENS: usize = 16;
+/// History items per user kept in `synced_history`; older ones are moved to
+/// `archived_history` by `archive_old_history`.
+pub const HOT_HISTORY_ITEMS: i64 = 10_000;
+/// Earlier versions kept per synced slot in `slot_versions`.
+pub const MAX_SLOT_VERSIONS: i64 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BlobError {
+    TooLarge,
+    InvalidBase64,
+}
+
+impl BlobError {
+    pub fn message(self) -> &'static str {
+        match self {
+            BlobError::TooLarge => "Blob too large",
+            BlobError::InvalidBase64 => "Invalid base64 blob",
+        }
+    }
+}
+
+/// Reject blobs that can't fit in `MAX_BLOB_BYTES` without decoding them.
+pub fn check_blob_size(encrypted_blob: &str) -> Result<(), BlobError> {
+    // 4 base64 chars per 3 bytes
+    if encrypted_blob.len() / 4 * 3 > MAX_BLOB_BYTES + 3 {
+        return Err(BlobError::TooLarge);
+    }
+    Ok(())
+}
+
+/// Decode a base64 blob from a client, enforcing `MAX_BLOB_BYTES`.
+pub fn decode_blob(encrypted_blob: &str) -> Result<Vec<u8>, BlobError> {
+    check_blob_size(encrypted_blob)?;
+    let blob = BASE64
+        .decode(encrypted_blob)
+        .map_err(|_| BlobError::InvalidBase64)?;
+    if blob.len() > MAX_BLOB_BYTES {
+        return Err(BlobError::TooLarge);
+    }
+    Ok(blob)
+}
+
+fn is_hex_digest(s: &str) -> bool {
+    s.len() == 64 && s.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// Content hashes are hex SHA-256 digests of the plaintext.
+pub fn is_valid_content_hash(hash: &str) -> bool {
+    is_hex_digest(hash)
+}
+
+/// Search tokens are hex HMAC-SHA256 digests of normalized words.
+pub fn is_valid_search_token(token: &str) -> bool {
+    is_hex_digest(token)
+}
+
+/// Validate the search tokens pushed with a history item.
+pub fn check_search_tokens(tokens: &[String]) -> Result<(), &'static str> {
+    if tokens.len() > MAX_SEARCH_TOKENS {
+        return Err("Too many search tokens");
+    }
+    if !tokens.iter().all(|t| is_valid_search_token(t)) {
+        return Err("search_tokens must be hex HMAC-SHA256 digests");
+    }
+    Ok(())
+}
+
 // ── Database rows ────────────────────────────────────────────────────────────
 
 #[derive(Debug, sqlx::FromRow)]
@@ -15,6 +91,16 @@ pub struct SyncedSlot {
     pub updated_by: Option<Uuid>,
 }
 
+#[derive(Debug, sqlx::FromRow)]
+pub struct SlotVersion {
+    pub id: Uuid,
+    pub slot_number: i32,
+    pub encrypted_blob: Vec<u8>,
+    pub updated_at: DateTime<Utc>,
+    pub updated_by: Option<Uuid>,
+    pub replaced_at: DateTime<Utc>,
+}
+
 #[derive(Debug, sqlx::FromRow)]
 #[allow(dead_code)]
 pub struct SyncedHistoryItem {
@@ -24,6 +110,17 @@ pub struct SyncedHistoryItem {
     pub content_hash: String,
     pub device_id: Option<Uuid>,
     pub created_at: DateTime<Utc>,
+    pub attachment_id: Option<Uuid>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+#[allow(dead_code)]
+pub struct DeletedHistoryItem {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub content_hash: String,
+    pub deleted_by: Option<Uuid>,
+    pub deleted_at: DateTime<Utc>,
 }
 
 // ── API types ────────────────────────────────────────────────────────────────
@@ -37,12 +134,46 @@ pub struct SlotResponse {
     pub updated_by: Option<Uuid>,
 }
 
+/// Content a slot held before it was overwritten.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SlotVersionResponse {
+    pub id: Uuid,
+    pub slot_number: i32,
+    /// Base64-encoded encrypted blob
+    pub encrypted_blob: String,
+    /// When this content was written, and by which device
+    pub updated_at: DateTime<Utc>,
+    pub updated_by: Option<Uuid>,
+    /// When it was overwritten
+    pub replaced_at: DateTime<Utc>,
+}
+
+impl From<SlotVersion> for SlotVersionResponse {
+    fn from(v: SlotVersion) -> Self {
+        Self {
+            id: v.id,
+            slot_number: v.slot_number,
+            encrypted_blob: BASE64.encode(&v.encrypted_blob),
+            updated_at: v.updated_at,
+            updated_by: v.updated_by,
+            replaced_at: v.replaced_at,
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, ToSchema)]
 pub struct UpdateSlotRequest {
     /// Base64-encoded encrypted blob
     pub encrypted_blob: String,
 }
 
+/// Account-wide slot settings, shared by every device.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SlotSettings {
+    /// Number of permanent slots, numbered from 1 (1-10)
+    pub slot_count: i32,
+}
+
 #[derive(Debug, Deserialize, ToSchema)]
 pub struct PushHistoryRequest {
     pub id: Uuid,
@@ -50,6 +181,13 @@ pub struct PushHistoryRequest {
     pub encrypted_blob: String,
     /// SHA-256 hash of the plaintext content (for dedup)
     pub content_hash: String,
+    /// A completed upload from `POST /api/sync/attachments`, for images and files
+    #[serde(default)]
+    pub attachment_id: Option<Uuid>,
+    /// Blind-index tokens for `GET /api/sync/history/search`: hex HMAC-SHA256 of each
+    /// normalized word, keyed with a secret only clients hold
+    #[serde(default)]
+    pub search_tokens: Vec<String>,
 }
 
 #[derive(Debug, Serialize, ToSchema)]
@@ -60,26 +198,163 @@ pub struct HistoryResponse {
     pub content_hash: String,
     pub device_id: Option<Uuid>,
     pub created_at: DateTime<Utc>,
+    /// Encrypted image or file, downloaded in chunks from `/api/sync/attachments/{id}`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attachment_id: Option<Uuid>,
+}
+
+impl From<SyncedHistoryItem> for HistoryResponse {
+    fn from(i: SyncedHistoryItem) -> Self {
+        Self {
+            id: i.id,
+            encrypted_blob: BASE64.encode(&i.encrypted_blob),
+            content_hash: i.content_hash,
+            device_id: i.device_id,
+            created_at: i.created_at,
+            attachment_id: i.attachment_id,
+        }
+    }
 }
 
 #[derive(Debug, Deserialize, ToSchema, utoipa::IntoParams)]
 pub struct HistoryQuery {
     /// Max items to return (default 50, max 200)
     pub limit: Option<i64>,
-    /// Offset for pagination
-    pub offset: Option<i64>,
+    /// Return the page after this one: the `next_cursor` from a previous page
+    pub cursor: Option<String>,
+    /// Delta mode: only return items newer than this cursor.
+    /// Accepts a `next_cursor` from a previous response, epoch millis, or an RFC 3339 timestamp.
+    pub since: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema, utoipa::IntoParams)]
+pub struct HistorySearchQuery {
+    /// Comma-separated search tokens; items must contain all of them
+    pub tokens: String,
+    /// Max items to return (default 50, max 200)
+    pub limit: Option<i64>,
+    /// Return the page after this one: the `next_cursor` from a previous page
+    pub cursor: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct HistoryTombstone {
+    pub id: Uuid,
+    pub content_hash: String,
+    pub deleted_at: DateTime<Utc>,
+}
+
+impl From<DeletedHistoryItem> for HistoryTombstone {
+    fn from(d: DeletedHistoryItem) -> Self {
+        Self {
+            id: d.id,
+            content_hash: d.content_hash,
+            deleted_at: d.deleted_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct HistoryDeltaResponse {
+    /// Items newer than `since`, oldest first
+    pub items: Vec<HistoryResponse>,
+    /// Items deleted since `since`; clients should remove them locally
+    pub deleted: Vec<HistoryTombstone>,
+    /// Cursor to pass as `since` on the next request
+    pub next_cursor: String,
+    /// True if more items are available after `next_cursor`
+    pub has_more: bool,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct HistoryPageResponse {
+    /// Newest first
+    pub items: Vec<HistoryResponse>,
+    /// Pass as `cursor` to fetch the next (older) page; absent on the last page
+    pub next_cursor: Option<String>,
+}
+
+/// `GET /api/sync/history` returns a page without `since`, or a delta with it.
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(untagged)]
+pub enum HistoryListResponse {
+    Page(HistoryPageResponse),
+    Delta(HistoryDeltaResponse),
+}
+
+/// Position in a user's history, ordered by (created_at, id).
+/// Encoded as "<created_at micros>:<id>" so it survives a round trip exactly.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HistoryCursor {
+    pub created_at: DateTime<Utc>,
+    pub id: Uuid,
+}
+
+impl HistoryCursor {
+    pub fn new(created_at: DateTime<Utc>, id: Uuid) -> Self {
+        Self { created_at, id }
+    }
+
+    pub fn encode(&self) -> String {
+        format!("{}:{}", self.created_at.timestamp_micros(), self.id)
+    }
+
+    /// Parse a cursor, epoch millis, or RFC 3339 timestamp.
+    /// Bare timestamps use the nil UUID so every item at that instant is included.
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.trim();
+        if let Some((micros, id)) = s.split_once(':').filter(|(m, _)| m.parse::<i64>().is_ok()) {
+            let created_at = DateTime::from_timestamp_micros(micros.parse().ok()?)?;
+            let id = Uuid::parse_str(id).ok()?;
+            return Some(Self::new(created_at, id));
+        }
+        if let Ok(millis) = s.parse::<i64>() {
+            return Some(Self::new(DateTime::from_timestamp_millis(millis)?, Uuid::nil()));
+        }
+        let created_at = DateTime::parse_from_rfc3339(s).ok()?.with_timezone(&Utc);
+        Some(Self::new(created_at, Uuid::nil()))
+    }
 }
 
 // ── WebSocket messages ───────────────────────────────────────────────────────
+//
+// Client-to-server messages may carry a client-generated `msg_id`. The server
+// answers with `ack` once the change is persisted (or permanently rejected);
+// messages without an ack are retried by the client.
+//
+// Both sides open with `hello`, giving their protocol version and the message
+// types they accept, so neither sends what the other can't handle. Clients
+// that never send `hello` (protocol version 1) receive everything.
+//
+// Server-to-client broadcasts also carry a per-user `seq`. A reconnecting client
+// passes the last seq it saw as `resume_from` and missed broadcasts are replayed;
+// if they are no longer buffered the server sends `resync_required` instead.
+
+/// Version of the WebSocket protocol spoken by this server.
+pub const PROTOCOL_VERSION: u32 = 2;
+/// Oldest client protocol version still served.
+pub const MIN_PROTOCOL_VERSION: u32 = 1;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum WsMessage {
+    #[serde(rename = "hello")]
+    Hello {
+        protocol_version: u32,
+        /// Oldest peer version supported; only sent by the server
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        min_protocol_version: Option<u32>,
+        /// Message types the sender accepts
+        #[serde(default)]
+        capabilities: Vec<String>,
+    },
     #[serde(rename = "slot_update")]
     SlotUpdate {
         slot_number: i32,
         encrypted_blob: String,
         timestamp: i64,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        msg_id: Option<Uuid>,
     },
     #[serde(rename = "slot_updated")]
     SlotUpdated {
@@ -88,11 +363,20 @@ pub enum WsMessage {
         updated_by: Uuid,
         timestamp: i64,
     },
+    /// Sent to every device of the account, including the one that changed it
+    #[serde(rename = "slot_settings_updated")]
+    SlotSettingsUpdated { slot_count: i32 },
     #[serde(rename = "history_push")]
     HistoryPush {
         id: Uuid,
         encrypted_blob: String,
         content_hash: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        attachment_id: Option<Uuid>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        search_tokens: Vec<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        msg_id: Option<Uuid>,
     },
     #[serde(rename = "history_new")]
     HistoryNew {
@@ -100,7 +384,106 @@ pub enum WsMessage {
         encrypted_blob: String,
         content_hash: String,
         device_id: Uuid,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        attachment_id: Option<Uuid>,
     },
+    #[serde(rename = "history_delete")]
+    HistoryDelete {
+        id: Uuid,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        msg_id: Option<Uuid>,
+    },
+    #[serde(rename = "history_deleted")]
+    HistoryDeleted {
+        id: Uuid,
+        content_hash: String,
+        deleted_by: Uuid,
+    },
+    #[serde(rename = "team_slot_update")]
+    TeamSlotUpdate {
+        team_id: Uuid,
+        slot_number: i32,
+        encrypted_blob: String,
+        timestamp: i64,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        msg_id: Option<Uuid>,
+    },
+    /// Sent to every member of the team, on all of their devices
+    #[serde(rename = "team_slot_updated")]
+    TeamSlotUpdated {
+        team_id: Uuid,
+        slot_number: i32,
+        encrypted_blob: String,
+        updated_by: Uuid,
+        timestamp: i64,
+    },
+    /// Content for one specific device of the same account
+    #[serde(rename = "send_to_device")]
+    SendToDevice {
+        target_device: Uuid,
+        encrypted_blob: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        msg_id: Option<Uuid>,
+    },
+    /// Delivered only to the connections of the target device
+    #[serde(rename = "sent_to_device")]
+    SentToDevice {
+        encrypted_blob: String,
+        from_device: Uuid,
+        timestamp: i64,
+    },
+    /// Ask another device of the same account for its current clipboard
+    #[serde(rename = "clipboard_request")]
+    ClipboardRequest {
+        target_device: Uuid,
+        request_id: Uuid,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        msg_id: Option<Uuid>,
+    },
+    /// Delivered only to the connections of the asked device
+    #[serde(rename = "clipboard_requested")]
+    ClipboardRequested { from_device: Uuid, request_id: Uuid },
+    /// Answer to a `clipboard_requested`; no blob if there is nothing to share
+    #[serde(rename = "clipboard_response")]
+    ClipboardResponse {
+        target_device: Uuid,
+        request_id: Uuid,
+        encrypted_blob: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        msg_id: Option<Uuid>,
+    },
+    /// Delivered only to the connections of the device that asked
+    #[serde(rename = "clipboard_responded")]
+    ClipboardResponded {
+        from_device: Uuid,
+        request_id: Uuid,
+        encrypted_blob: Option<String>,
+    },
+    /// Pause or resume capturing on all of the account's devices
+    #[serde(rename = "set_monitoring")]
+    SetMonitoring {
+        paused: bool,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        msg_id: Option<Uuid>,
+    },
+    /// Sent to the account's other devices
+    #[serde(rename = "monitoring_changed")]
+    MonitoringChanged { paused: bool, changed_by: Uuid },
+    #[serde(rename = "ack")]
+    Ack {
+        msg_id: Uuid,
+        /// False if the message was rejected and should not be retried
+        ok: bool,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+    #[serde(rename = "resync_required")]
+    ResyncRequired { reason: String },
     #[serde(rename = "error")]
-    Error { message: String },
+    Error {
+        /// Machine-readable reason, e.g. "invalid_message" or "payload_too_large"
+        #[serde(default)]
+        code: String,
+        message: String,
+    },
 }
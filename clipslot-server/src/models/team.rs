@@ -0,0 +1,133 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Slot numbers shared with a team. Personal slots keep the full 1-10 range.
+pub const TEAM_SLOTS: std::ops::RangeInclusive<i32> = 8..=10;
+
+pub const ROLE_OWNER: &str = "owner";
+pub const ROLE_MEMBER: &str = "member";
+
+// ── Database rows ────────────────────────────────────────────────────────────
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct Team {
+    pub id: Uuid,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    /// The current user's role (joined from team_members)
+    pub role: String,
+    /// The current user's wrapped copy of the team key
+    pub encrypted_team_key: Option<String>,
+    /// The team key a teammate wrapped for the current user
+    pub invite_key: Option<String>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct TeamMember {
+    pub user_id: Uuid,
+    pub email: String,
+    pub role: String,
+    pub encrypted_team_key: Option<String>,
+    pub joined_at: DateTime<Utc>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+#[allow(dead_code)]
+pub struct TeamSlot {
+    pub team_id: Uuid,
+    pub slot_number: i32,
+    pub encrypted_blob: Vec<u8>,
+    pub updated_at: DateTime<Utc>,
+    pub updated_by: Option<Uuid>,
+}
+
+// ── API types ────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateTeamRequest {
+    pub name: String,
+    /// The new team key wrapped with the creator's master key (optional; can be set later)
+    pub encrypted_team_key: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TeamResponse {
+    pub id: Uuid,
+    pub name: String,
+    /// "owner" or "member"
+    pub role: String,
+    /// Slot numbers shared with this team
+    pub slots: Vec<i32>,
+    /// The team key wrapped with your master key; null until you have received it
+    pub encrypted_team_key: Option<String>,
+    /// The team key wrapped for you by a teammate, with a code they gave you;
+    /// null once you have stored your own copy
+    pub invite_key: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<Team> for TeamResponse {
+    fn from(t: Team) -> Self {
+        Self {
+            id: t.id,
+            name: t.name,
+            role: t.role,
+            slots: TEAM_SLOTS.collect(),
+            encrypted_team_key: t.encrypted_team_key,
+            invite_key: t.invite_key,
+            created_at: t.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct AddMemberRequest {
+    /// Email of an existing ClipSlot account
+    pub email: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TeamMemberResponse {
+    pub user_id: Uuid,
+    pub email: String,
+    pub role: String,
+    /// Whether the member has stored a copy of the team key yet
+    pub has_key: bool,
+    pub joined_at: DateTime<Utc>,
+}
+
+impl From<TeamMember> for TeamMemberResponse {
+    fn from(m: TeamMember) -> Self {
+        Self {
+            user_id: m.user_id,
+            email: m.email,
+            role: m.role,
+            has_key: m.encrypted_team_key.is_some(),
+            joined_at: m.joined_at,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SetTeamKeyRequest {
+    /// The team key wrapped with your own master key
+    pub encrypted_team_key: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ShareTeamKeyRequest {
+    /// The team key wrapped with a code given to the member outside ClipSlot,
+    /// so the server can't unwrap it
+    pub invite_key: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TeamSlotResponse {
+    pub slot_number: i32,
+    /// Base64-encoded blob, encrypted with the team key
+    pub encrypted_blob: String,
+    pub updated_at: DateTime<Utc>,
+    pub updated_by: Option<Uuid>,
+}
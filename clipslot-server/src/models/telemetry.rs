@@ -0,0 +1,33 @@
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+/// Most days one report can carry; clients send finished days as they go.
+pub const MAX_TELEMETRY_DAYS: usize = 31;
+/// Longest `app_version` and `platform` accepted.
+pub const MAX_TELEMETRY_LABEL_LEN: usize = 32;
+/// Highest count accepted for one kind of event on one day.
+pub const MAX_TELEMETRY_COUNT: i64 = 1_000_000;
+
+// ── API types ────────────────────────────────────────────────────────────────
+
+/// Anonymous usage counts from a client that opted in. Carries no account,
+/// device or content; the server doesn't link reports to each other.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct TelemetryReport {
+    /// e.g. "0.2.11"
+    pub app_version: String,
+    /// e.g. "macos", "windows", "linux", "android", "ios"
+    pub platform: String,
+    pub days: Vec<TelemetryDay>,
+}
+
+/// How many times each counted thing happened on one UTC day.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct TelemetryDay {
+    /// `YYYY-MM-DD`, UTC
+    pub day: String,
+    pub captures: i64,
+    pub slot_saves: i64,
+    pub slot_pastes: i64,
+    pub sync_errors: i64,
+}
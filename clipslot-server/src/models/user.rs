@@ -33,10 +33,19 @@ pub struct AuthResponse {
     pub user_id: Uuid,
 }
 
+/// How long an issued session token stays valid.
+pub const TOKEN_LIFETIME_SECS: usize = 7 * 24 * 3600;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
     pub sub: Uuid,
     pub device_id: Option<Uuid>,
     pub exp: usize,
     pub iat: usize,
+    /// The issuing server's public URL, when it has one configured
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iss: Option<String>,
+    /// The server the token is for; the same URL as `iss`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aud: Option<String>,
 }
@@ -0,0 +1,77 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Sent when a slot is written, by any device or API key.
+pub const EVENT_SLOT_UPDATED: &str = "slot.updated";
+/// Sent when a new (non-duplicate) history item is stored.
+pub const EVENT_HISTORY_CREATED: &str = "history.created";
+
+pub const ALL_EVENTS: &[&str] = &[EVENT_SLOT_UPDATED, EVENT_HISTORY_CREATED];
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct Webhook {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub url: String,
+    pub secret: String,
+    pub events: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Webhook {
+    pub fn subscribes_to(&self, event: &str) -> bool {
+        self.events.split(',').any(|e| e == event)
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateWebhookRequest {
+    /// http(s) URL that receives a POST for each event
+    pub url: String,
+    /// Events to deliver: "slot.updated", "history.created"
+    pub events: Vec<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct WebhookResponse {
+    pub id: Uuid,
+    pub url: String,
+    pub events: Vec<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CreateWebhookResponse {
+    /// Secret for verifying the `X-ClipSlot-Signature` header. Only returned once.
+    pub secret: String,
+    #[serde(flatten)]
+    pub webhook: WebhookResponse,
+}
+
+impl From<Webhook> for WebhookResponse {
+    fn from(w: Webhook) -> Self {
+        Self {
+            id: w.id,
+            url: w.url,
+            events: w
+                .events
+                .split(',')
+                .filter(|e| !e.is_empty())
+                .map(str::to_string)
+                .collect(),
+            created_at: w.created_at,
+        }
+    }
+}
+
+/// Body of a webhook delivery.
+#[derive(Debug, Serialize)]
+pub struct WebhookPayload {
+    /// Unique per delivery; retries reuse it so receivers can deduplicate
+    pub id: Uuid,
+    pub event: String,
+    pub created_at: DateTime<Utc>,
+    pub data: serde_json::Value,
+}
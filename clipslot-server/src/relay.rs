@@ -0,0 +1,393 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{broadcast, mpsc};
+use uuid::Uuid;
+
+use crate::models::sync::WsMessage;
+
+/// Broadcasts kept per user for replay after a reconnect.
+const REPLAY_BUFFER_LEN: usize = 200;
+/// Broadcasts older than this are not replayed; the client must resync instead.
+const REPLAY_MAX_AGE: Duration = Duration::from_secs(300);
+/// Broadcasts queued per user before the slowest connection starts missing them.
+const CHANNEL_CAPACITY: usize = 100;
+/// Redis pub/sub channel shared by all server instances.
+const REDIS_CHANNEL: &str = "clipslot:relay";
+
+/// A broadcast as delivered to WebSocket connections.
+#[derive(Debug, Clone)]
+pub struct RelayEvent {
+    /// Device that caused the event (connections for that device skip it)
+    pub origin_device: Uuid,
+    /// The only device whose connections receive it; `None` for all of them
+    pub target_device: Option<Uuid>,
+    /// Per-user monotonic sequence number
+    pub seq: u64,
+    /// The message's `type`, so connections can skip types their client doesn't accept
+    pub kind: String,
+    /// JSON-encoded WsMessage, including its `seq` field
+    pub payload: String,
+}
+
+struct UserChannel {
+    tx: broadcast::Sender<RelayEvent>,
+    next_seq: AtomicU64,
+    buffer: Mutex<VecDeque<(RelayEvent, Instant)>>,
+}
+
+impl UserChannel {
+    fn new() -> Self {
+        // Seed from the clock so sequences keep increasing across server restarts;
+        // a client resuming from a pre-restart seq then falls outside the buffer.
+        let seed = chrono::Utc::now().timestamp_micros().max(0) as u64;
+        Self {
+            tx: broadcast::channel(CHANNEL_CAPACITY).0,
+            next_seq: AtomicU64::new(seed),
+            buffer: Mutex::new(VecDeque::with_capacity(REPLAY_BUFFER_LEN)),
+        }
+    }
+
+    /// See `UserChannels::deliver`.
+    fn deliver(&self, origin_device: Uuid, target_device: Option<Uuid>, msg: &WsMessage) {
+        // Hold the buffer lock so buffer order always matches seq order
+        let mut buffer = self.buffer.lock().unwrap();
+        let seq = self.next_seq.fetch_add(1, Ordering::SeqCst) + 1;
+
+        let mut value = serde_json::to_value(msg).unwrap();
+        let kind = value["type"].as_str().unwrap_or_default().to_string();
+        if let Some(obj) = value.as_object_mut() {
+            obj.insert("seq".to_string(), seq.into());
+        }
+        let event = RelayEvent {
+            origin_device,
+            target_device,
+            seq,
+            kind,
+            payload: value.to_string(),
+        };
+
+        drop_expired(&mut buffer);
+        if buffer.len() == REPLAY_BUFFER_LEN {
+            buffer.pop_front();
+        }
+        buffer.push_back((event.clone(), Instant::now()));
+
+        let _ = self.tx.send(event);
+    }
+
+    /// See `UserChannels::replay_since`.
+    fn replay_since(&self, after_seq: u64) -> Option<Vec<RelayEvent>> {
+        let buffer = self.buffer.lock().unwrap();
+        let current = self.next_seq.load(Ordering::SeqCst);
+
+        if after_seq > current {
+            // Sequence from a different channel lifetime
+            return None;
+        }
+        if after_seq == current {
+            return Some(Vec::new());
+        }
+
+        let oldest = buffer
+            .iter()
+            .find(|(_, at)| at.elapsed() < REPLAY_MAX_AGE)
+            .map(|(e, _)| e.seq)?;
+        if oldest > after_seq + 1 {
+            return None;
+        }
+
+        Some(
+            buffer
+                .iter()
+                .filter(|(e, _)| e.seq > after_seq)
+                .map(|(e, _)| e.clone())
+                .collect(),
+        )
+    }
+}
+
+/// Forget broadcasts too old to replay.
+fn drop_expired(buffer: &mut VecDeque<(RelayEvent, Instant)>) {
+    while buffer
+        .front()
+        .is_some_and(|(_, at)| at.elapsed() >= REPLAY_MAX_AGE)
+    {
+        buffer.pop_front();
+    }
+}
+
+/// A broadcast as published to Redis for the other instances.
+#[derive(Serialize, Deserialize)]
+struct FanoutEvent {
+    user_id: Uuid,
+    origin_device: Uuid,
+    #[serde(default)]
+    target_device: Option<Uuid>,
+    message: WsMessage,
+}
+
+/// Per-user broadcast channels for WebSocket relay, with a short replay buffer.
+///
+/// With a single instance broadcasts are delivered in-process. When a Redis
+/// fan-out is connected, every broadcast goes through Redis and each instance
+/// (this one included) delivers it to its own connections, buffering it only for
+/// users connected there. Sequence numbers are assigned per instance, so a device
+/// resuming on a different instance usually falls outside its buffer and resyncs.
+#[derive(Default)]
+pub struct UserChannels {
+    channels: DashMap<Uuid, UserChannel>,
+    fanout: OnceLock<mpsc::UnboundedSender<FanoutEvent>>,
+    /// Times a connection fell behind its channel and had to resync
+    lag_events: AtomicU64,
+    /// Broadcasts skipped by connections that fell behind
+    dropped_messages: AtomicU64,
+}
+
+/// Counters exported on `/metrics`.
+pub struct RelayStats {
+    pub lag_events: u64,
+    pub dropped_messages: u64,
+}
+
+impl UserChannels {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run `f` on the user's channel, creating it if needed. The map entry
+    /// stays locked meanwhile, so `prune` can't drop a channel in use.
+    fn with_channel<R>(&self, user_id: Uuid, f: impl FnOnce(&UserChannel) -> R) -> R {
+        let channel = self
+            .channels
+            .entry(user_id)
+            .or_insert_with(UserChannel::new);
+        f(&channel)
+    }
+
+    /// Drop expired broadcasts from every replay buffer, and the channels of
+    /// users with nothing buffered and no connections. Returns how many
+    /// channels were removed.
+    pub fn prune(&self) -> usize {
+        let before = self.channels.len();
+        self.channels.retain(|_, channel| {
+            let mut buffer = channel.buffer.lock().unwrap();
+            drop_expired(&mut buffer);
+            channel.tx.receiver_count() > 0 || !buffer.is_empty()
+        });
+        before - self.channels.len()
+    }
+
+    /// Broadcast `msg` to every connection of `user_id`, on all instances when a
+    /// Redis fan-out is connected.
+    pub fn publish(&self, user_id: Uuid, origin_device: Uuid, msg: &WsMessage) {
+        self.route(user_id, origin_device, None, msg);
+    }
+
+    /// Send `msg` to the connections of one of the user's devices only. Like
+    /// other broadcasts it is buffered, so the device gets it on resume.
+    pub fn send_to_device(
+        &self,
+        user_id: Uuid,
+        origin_device: Uuid,
+        target_device: Uuid,
+        msg: &WsMessage,
+    ) {
+        self.route(user_id, origin_device, Some(target_device), msg);
+    }
+
+    fn route(
+        &self,
+        user_id: Uuid,
+        origin_device: Uuid,
+        target_device: Option<Uuid>,
+        msg: &WsMessage,
+    ) {
+        if let Some(fanout) = self.fanout.get() {
+            let event = FanoutEvent {
+                user_id,
+                origin_device,
+                target_device,
+                message: msg.clone(),
+            };
+            if fanout.send(event).is_ok() {
+                return;
+            }
+        }
+        self.deliver(user_id, origin_device, target_device, msg);
+    }
+
+    /// Assign the next sequence number to `msg`, buffer it, and send it to this
+    /// instance's connections of `user_id`. Buffered even when nobody is
+    /// connected, so an offline device can catch up on reconnect.
+    fn deliver(
+        &self,
+        user_id: Uuid,
+        origin_device: Uuid,
+        target_device: Option<Uuid>,
+        msg: &WsMessage,
+    ) {
+        self.with_channel(user_id, |channel| {
+            channel.deliver(origin_device, target_device, msg)
+        });
+    }
+
+    /// Deliver a broadcast from another instance, but only to users with
+    /// connections here; others would just grow buffers nobody reads.
+    fn deliver_if_connected(
+        &self,
+        user_id: Uuid,
+        origin_device: Uuid,
+        target_device: Option<Uuid>,
+        msg: &WsMessage,
+    ) {
+        if let Some(channel) = self.channels.get(&user_id) {
+            if channel.tx.receiver_count() > 0 {
+                channel.deliver(origin_device, target_device, msg);
+            }
+        }
+    }
+
+    /// Tell every connection here to refetch current state, e.g. after
+    /// broadcasts from other instances may have been missed.
+    fn require_resync(&self, reason: &str) {
+        let msg = WsMessage::ResyncRequired {
+            reason: reason.to_string(),
+        };
+        for channel in self.channels.iter() {
+            if channel.tx.receiver_count() > 0 {
+                channel.deliver(Uuid::nil(), None, &msg);
+            }
+        }
+    }
+
+    pub fn subscribe(&self, user_id: Uuid) -> broadcast::Receiver<RelayEvent> {
+        self.with_channel(user_id, |channel| channel.tx.subscribe())
+    }
+
+    /// Count a connection that missed `skipped` broadcasts by falling behind.
+    pub fn record_lag(&self, skipped: u64) {
+        self.lag_events.fetch_add(1, Ordering::Relaxed);
+        self.dropped_messages.fetch_add(skipped, Ordering::Relaxed);
+    }
+
+    pub fn stats(&self) -> RelayStats {
+        RelayStats {
+            lag_events: self.lag_events.load(Ordering::Relaxed),
+            dropped_messages: self.dropped_messages.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Events published after `after_seq`, oldest first. Returns None if some of
+    /// them are no longer buffered, in which case the client has to resync.
+    pub fn replay_since(&self, user_id: Uuid, after_seq: u64) -> Option<Vec<RelayEvent>> {
+        self.with_channel(user_id, |channel| channel.replay_since(after_seq))
+    }
+}
+
+impl UserChannels {
+    /// Relay broadcasts through Redis pub/sub so WebSocket clients connected to
+    /// other instances receive them too.
+    pub async fn connect_redis(self: &Arc<Self>, url: &str) -> redis::RedisResult<()> {
+        let client = redis::Client::open(url)?;
+        let conn = client.get_multiplexed_async_connection().await?;
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        if self.fanout.set(tx).is_err() {
+            tracing::warn!("Redis fan-out already connected");
+            return Ok(());
+        }
+
+        tokio::spawn(run_publisher(self.clone(), client.clone(), conn, rx));
+        tokio::spawn(run_subscriber(self.clone(), client));
+        tracing::info!("WebSocket relay fan-out via Redis enabled");
+        Ok(())
+    }
+}
+
+/// Publish queued broadcasts to Redis in order. If Redis is unreachable the
+/// broadcast is delivered locally so this instance's clients still get it.
+async fn run_publisher(
+    channels: Arc<UserChannels>,
+    client: redis::Client,
+    conn: redis::aio::MultiplexedConnection,
+    mut rx: mpsc::UnboundedReceiver<FanoutEvent>,
+) {
+    let mut conn = Some(conn);
+    while let Some(event) = rx.recv().await {
+        if conn.is_none() {
+            conn = client.get_multiplexed_async_connection().await.ok();
+        }
+        let Some(c) = conn.as_mut() else {
+            channels.deliver(
+                event.user_id,
+                event.origin_device,
+                event.target_device,
+                &event.message,
+            );
+            continue;
+        };
+
+        let payload = serde_json::to_string(&event).unwrap();
+        let result: redis::RedisResult<()> = redis::cmd("PUBLISH")
+            .arg(REDIS_CHANNEL)
+            .arg(payload)
+            .query_async(c)
+            .await;
+        if let Err(e) = result {
+            tracing::error!("Redis publish failed, delivering locally: {}", e);
+            conn = None;
+            channels.deliver(
+                event.user_id,
+                event.origin_device,
+                event.target_device,
+                &event.message,
+            );
+        }
+    }
+}
+
+/// Deliver broadcasts published by any instance, reconnecting on failure.
+/// Broadcasts published while reconnecting are lost, so connections are
+/// then told to resync.
+async fn run_subscriber(channels: Arc<UserChannels>, client: redis::Client) {
+    let mut resubscribing = false;
+    loop {
+        match client.get_async_pubsub().await {
+            Ok(mut pubsub) => {
+                if let Err(e) = pubsub.subscribe(REDIS_CHANNEL).await {
+                    tracing::error!("Redis subscribe failed: {}", e);
+                } else {
+                    if resubscribing {
+                        channels.require_resync("Relay reconnected; updates may have been missed");
+                    }
+                    let mut messages = pubsub.on_message();
+                    while let Some(msg) = messages.next().await {
+                        let event = msg
+                            .get_payload::<String>()
+                            .ok()
+                            .and_then(|p| serde_json::from_str::<FanoutEvent>(&p).ok());
+                        match event {
+                            Some(e) => channels.deliver_if_connected(
+                                e.user_id,
+                                e.origin_device,
+                                e.target_device,
+                                &e.message,
+                            ),
+                            None => tracing::warn!("Ignoring malformed relay message from Redis"),
+                        }
+                    }
+                    tracing::warn!("Redis subscription closed, reconnecting");
+                }
+            }
+            Err(e) => tracing::error!("Redis connection failed: {}", e),
+        }
+        resubscribing = true;
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+}
@@ -0,0 +1,170 @@
+use axum::{
+    extract::State,
+    http::StatusCode,
+    routing::{delete, get},
+    Json, Router,
+};
+use rand::{distributions::Alphanumeric, Rng as _};
+use uuid::Uuid;
+
+use crate::errors::{err, ApiError};
+use crate::db::{self, with_db};
+use crate::middleware::extract::{ApiJson, ApiPath};
+use crate::middleware::auth::AuthUser;
+use crate::models::api_key::{
+    hash_key, ApiKey, ApiKeyResponse, CreateApiKeyRequest, CreateApiKeyResponse, ALL_SCOPES,
+    API_KEY_PREFIX,
+};
+use crate::AppState;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/api-keys", get(list_api_keys).post(create_api_key))
+        .route("/api-keys/{id}", delete(revoke_api_key))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/auth/api-keys",
+    request_body = CreateApiKeyRequest,
+    responses(
+        (status = 201, description = "API key created; the key is only shown once", body = CreateApiKeyResponse),
+        (status = 400, description = "Invalid name or scopes", body = ApiError),
+    ),
+    security(("bearer" = [])),
+    tag = "Auth"
+)]
+pub(crate) async fn create_api_key(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    ApiJson(req): ApiJson<CreateApiKeyRequest>,
+) -> Result<(StatusCode, Json<CreateApiKeyResponse>), (StatusCode, Json<ApiError>)> {
+    auth.require_session()?;
+
+    let name = req.name.trim().to_string();
+    if name.is_empty() || name.len() > 100 {
+        return Err(err(StatusCode::BAD_REQUEST, "Name must be 1-100 characters"));
+    }
+    if req.scopes.is_empty() {
+        return Err(err(StatusCode::BAD_REQUEST, "At least one scope is required"));
+    }
+    if let Some(unknown) = req.scopes.iter().find(|s| !ALL_SCOPES.contains(&s.as_str())) {
+        return Err(err(StatusCode::BAD_REQUEST, &format!("Unknown scope: {}", unknown)));
+    }
+
+    let mut scopes = req.scopes.clone();
+    scopes.sort();
+    scopes.dedup();
+
+    let secret: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(40)
+        .map(char::from)
+        .collect();
+    let key = format!("{}{}", API_KEY_PREFIX, secret);
+
+    let api_key = ApiKey {
+        id: Uuid::new_v4(),
+        user_id: auth.user_id,
+        name,
+        prefix: key[..API_KEY_PREFIX.len() + 8].to_string(),
+        scopes: scopes.join(","),
+        created_at: db::now(),
+        last_used_at: None,
+        revoked_at: None,
+    };
+
+    with_db!(&state.db, pool => {
+        sqlx::query(
+            "INSERT INTO api_keys (id, user_id, name, prefix, key_hash, scopes, created_at)
+             VALUES ($1, $2, $3, $4, $5, $6, $7)",
+        )
+        .bind(api_key.id)
+        .bind(api_key.user_id)
+        .bind(&api_key.name)
+        .bind(&api_key.prefix)
+        .bind(hash_key(&key))
+        .bind(&api_key.scopes)
+        .bind(api_key.created_at)
+        .execute(pool)
+        .await
+        .map(|_| ())
+    })
+    .map_err(|_| err(StatusCode::INTERNAL_SERVER_ERROR, "Failed to create API key"))?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(CreateApiKeyResponse {
+            key,
+            api_key: ApiKeyResponse::from(api_key),
+        }),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/auth/api-keys",
+    responses(
+        (status = 200, description = "API keys of the current user, including revoked ones", body = Vec<ApiKeyResponse>),
+    ),
+    security(("bearer" = [])),
+    tag = "Auth"
+)]
+pub(crate) async fn list_api_keys(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<Json<Vec<ApiKeyResponse>>, (StatusCode, Json<ApiError>)> {
+    auth.require_session()?;
+
+    let keys = with_db!(&state.db, pool => {
+        sqlx::query_as::<_, ApiKey>(
+            "SELECT id, user_id, name, prefix, scopes, created_at, last_used_at, revoked_at
+             FROM api_keys WHERE user_id = $1 ORDER BY created_at DESC",
+        )
+        .bind(auth.user_id)
+        .fetch_all(pool)
+        .await
+    })
+    .map_err(|_| err(StatusCode::INTERNAL_SERVER_ERROR, "Database error"))?;
+
+    Ok(Json(keys.into_iter().map(ApiKeyResponse::from).collect()))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/auth/api-keys/{id}",
+    params(("id" = Uuid, Path, description = "API key UUID")),
+    responses(
+        (status = 204, description = "API key revoked"),
+        (status = 404, description = "API key not found", body = ApiError),
+    ),
+    security(("bearer" = [])),
+    tag = "Auth"
+)]
+pub(crate) async fn revoke_api_key(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    ApiPath(key_id): ApiPath<Uuid>,
+) -> Result<StatusCode, (StatusCode, Json<ApiError>)> {
+    auth.require_session()?;
+
+    let revoked = with_db!(&state.db, pool => {
+        sqlx::query(
+            "UPDATE api_keys SET revoked_at = $1
+             WHERE id = $2 AND user_id = $3 AND revoked_at IS NULL",
+        )
+        .bind(db::now())
+        .bind(key_id)
+        .bind(auth.user_id)
+        .execute(pool)
+        .await
+        .map(|r| r.rows_affected())
+    })
+    .map_err(|_| err(StatusCode::INTERNAL_SERVER_ERROR, "Database error"))?;
+
+    if revoked == 0 {
+        return Err(err(StatusCode::NOT_FOUND, "API key not found"));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,364 @@
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::{header, StatusCode},
+    response::IntoResponse,
+    routing::{get, post, put},
+    Json, Router,
+};
+use uuid::Uuid;
+
+use crate::errors::{err, err_with_code, ApiError, ErrorCode};
+use crate::db::{self, with_db, Db};
+use crate::middleware::extract::ApiPath;
+use crate::middleware::auth::AuthUser;
+use crate::middleware::validate::ValidJson;
+use crate::models::api_key::{SCOPE_HISTORY_READ, SCOPE_HISTORY_WRITE};
+use crate::models::attachment::{
+    chunk_count, chunk_len, Attachment, AttachmentResponse, CreateAttachmentRequest,
+    MAX_USER_ATTACHMENT_BYTES,
+};
+use crate::AppState;
+
+/// Attachments not referenced by any history item are deleted after this long,
+/// which covers abandoned uploads and pushes that lost to a dedup.
+const UNREFERENCED_TTL: chrono::Duration = chrono::Duration::hours(24);
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/attachments", post(create_attachment))
+        .route("/attachments/{id}", get(get_attachment))
+        .route("/attachments/{id}/complete", post(complete_attachment))
+        .route(
+            "/attachments/{id}/chunks/{index}",
+            put(upload_chunk).get(download_chunk),
+        )
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/sync/attachments",
+    request_body = CreateAttachmentRequest,
+    responses(
+        (status = 201, description = "Upload started (or resumed)", body = AttachmentResponse),
+        (status = 409, description = "Id in use by another attachment", body = ApiError),
+        (status = 413, description = "Attachment too large", body = ApiError),
+        (status = 422, description = "Malformed body or invalid size", body = ApiError),
+    ),
+    security(("bearer" = [])),
+    tag = "Sync"
+)]
+pub(crate) async fn create_attachment(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    ValidJson(req): ValidJson<CreateAttachmentRequest>,
+) -> Result<(StatusCode, Json<AttachmentResponse>), (StatusCode, Json<ApiError>)> {
+    auth.require_scope(SCOPE_HISTORY_WRITE)?;
+
+    with_db!(&state.db, pool => {
+        sqlx::query(
+            "INSERT INTO attachments (id, user_id, size, chunk_count, created_at)
+             VALUES ($1, $2, $3, $4, $5)
+             ON CONFLICT (id) DO NOTHING",
+        )
+        .bind(req.id)
+        .bind(auth.user_id)
+        .bind(req.size)
+        .bind(chunk_count(req.size))
+        .bind(db::now())
+        .execute(pool)
+        .await
+        .map(|_| ())
+    })
+    .map_err(|_| err(StatusCode::INTERNAL_SERVER_ERROR, "Failed to create attachment"))?;
+
+    // An existing upload with the same id is resumed, as long as it matches
+    let attachment = match load(&state, auth.user_id, req.id).await {
+        Err((StatusCode::NOT_FOUND, _)) => {
+            return Err(err(StatusCode::CONFLICT, "Attachment id already in use"))
+        }
+        result => result?,
+    };
+    if attachment.size != req.size {
+        return Err(err(
+            StatusCode::CONFLICT,
+            "Attachment already exists with a different size",
+        ));
+    }
+
+    let uploaded = uploaded_chunks(&state, req.id).await?;
+    Ok((
+        StatusCode::CREATED,
+        Json(AttachmentResponse::new(&attachment, &uploaded)),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/sync/attachments/{id}",
+    params(("id" = Uuid, Path, description = "Attachment UUID")),
+    responses(
+        (status = 200, description = "Attachment metadata and upload progress", body = AttachmentResponse),
+        (status = 404, description = "Not found", body = ApiError),
+    ),
+    security(("bearer" = [])),
+    tag = "Sync"
+)]
+pub(crate) async fn get_attachment(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    ApiPath(id): ApiPath<Uuid>,
+) -> Result<Json<AttachmentResponse>, (StatusCode, Json<ApiError>)> {
+    auth.require_scope(SCOPE_HISTORY_READ)?;
+
+    let attachment = load(&state, auth.user_id, id).await?;
+    let uploaded = uploaded_chunks(&state, id).await?;
+    Ok(Json(AttachmentResponse::new(&attachment, &uploaded)))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/sync/attachments/{id}/chunks/{index}",
+    params(
+        ("id" = Uuid, Path, description = "Attachment UUID"),
+        ("index" = i32, Path, description = "Zero-based chunk index"),
+    ),
+    request_body(content = Vec<u8>, content_type = "application/octet-stream"),
+    responses(
+        (status = 204, description = "Chunk stored; uploading it again replaces it"),
+        (status = 400, description = "Invalid chunk index", body = ApiError),
+        (status = 404, description = "Not found", body = ApiError),
+        (status = 409, description = "Attachment already complete", body = ApiError),
+        (status = 413, description = "Account attachment quota exceeded (code quota_exceeded)", body = ApiError),
+        (status = 422, description = "Chunk has the wrong length", body = ApiError),
+    ),
+    security(("bearer" = [])),
+    tag = "Sync"
+)]
+pub(crate) async fn upload_chunk(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    ApiPath((id, index)): ApiPath<(Uuid, i32)>,
+    body: Bytes,
+) -> Result<StatusCode, (StatusCode, Json<ApiError>)> {
+    auth.require_scope(SCOPE_HISTORY_WRITE)?;
+
+    let attachment = load(&state, auth.user_id, id).await?;
+    if attachment.completed_at.is_some() {
+        return Err(err(StatusCode::CONFLICT, "Attachment already complete"));
+    }
+    if !(0..attachment.chunk_count).contains(&index) {
+        return Err(err(StatusCode::BAD_REQUEST, "Invalid chunk index"));
+    }
+    let expected = chunk_len(attachment.size, index);
+    if body.len() as i64 != expected {
+        return Err(err(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            &format!("Chunk {} must be {} bytes", index, expected),
+        ));
+    }
+
+    // Counted after the insert, so a replaced chunk isn't counted twice; the
+    // insert is rolled back if it goes over. Concurrent uploads can each get
+    // one chunk past the quota, no more.
+    let stored = with_db!(&state.db, pool => {
+        async {
+            let mut tx = pool.begin().await?;
+            sqlx::query(
+                "INSERT INTO attachment_chunks (attachment_id, chunk_index, data)
+                 VALUES ($1, $2, $3)
+                 ON CONFLICT (attachment_id, chunk_index) DO UPDATE SET data = $3",
+            )
+            .bind(id)
+            .bind(index)
+            .bind(body.as_ref())
+            .execute(&mut *tx)
+            .await?;
+            let used: i64 = sqlx::query_scalar(
+                "SELECT COALESCE(SUM(LENGTH(c.data)), 0) FROM attachment_chunks c
+                 JOIN attachments a ON a.id = c.attachment_id WHERE a.user_id = $1",
+            )
+            .bind(auth.user_id)
+            .fetch_one(&mut *tx)
+            .await?;
+            if used > MAX_USER_ATTACHMENT_BYTES {
+                return Ok(false);
+            }
+            tx.commit().await?;
+            Ok::<_, sqlx::Error>(true)
+        }
+        .await
+    })
+    .map_err(|_| err(StatusCode::INTERNAL_SERVER_ERROR, "Failed to store chunk"))?;
+
+    if !stored {
+        return Err(err_with_code(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            ErrorCode::QuotaExceeded,
+            &format!(
+                "Attachment storage is limited to {} MiB per account",
+                MAX_USER_ATTACHMENT_BYTES / (1024 * 1024)
+            ),
+        ));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/sync/attachments/{id}/complete",
+    params(("id" = Uuid, Path, description = "Attachment UUID")),
+    responses(
+        (status = 200, description = "Attachment complete; it can now be referenced from history", body = AttachmentResponse),
+        (status = 404, description = "Not found", body = ApiError),
+        (status = 409, description = "Chunks are missing", body = AttachmentResponse),
+    ),
+    security(("bearer" = [])),
+    tag = "Sync"
+)]
+pub(crate) async fn complete_attachment(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    ApiPath(id): ApiPath<Uuid>,
+) -> Result<(StatusCode, Json<AttachmentResponse>), (StatusCode, Json<ApiError>)> {
+    auth.require_scope(SCOPE_HISTORY_WRITE)?;
+
+    let mut attachment = load(&state, auth.user_id, id).await?;
+    let uploaded = uploaded_chunks(&state, id).await?;
+    if attachment.completed_at.is_none() {
+        if uploaded.len() as i32 != attachment.chunk_count {
+            // Tell the client what to upload rather than just that it can't finish
+            return Ok((
+                StatusCode::CONFLICT,
+                Json(AttachmentResponse::new(&attachment, &uploaded)),
+            ));
+        }
+
+        let now = db::now();
+        with_db!(&state.db, pool => {
+            sqlx::query("UPDATE attachments SET completed_at = $1 WHERE id = $2")
+                .bind(now)
+                .bind(id)
+                .execute(pool)
+                .await
+                .map(|_| ())
+        })
+        .map_err(|_| err(StatusCode::INTERNAL_SERVER_ERROR, "Database error"))?;
+        attachment.completed_at = Some(now);
+    }
+
+    Ok((
+        StatusCode::OK,
+        Json(AttachmentResponse::new(&attachment, &uploaded)),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/sync/attachments/{id}/chunks/{index}",
+    params(
+        ("id" = Uuid, Path, description = "Attachment UUID"),
+        ("index" = i32, Path, description = "Zero-based chunk index"),
+    ),
+    responses(
+        (status = 200, description = "Encrypted chunk", body = Vec<u8>, content_type = "application/octet-stream"),
+        (status = 404, description = "Not found", body = ApiError),
+        (status = 409, description = "Attachment not complete yet", body = ApiError),
+    ),
+    security(("bearer" = [])),
+    tag = "Sync"
+)]
+pub(crate) async fn download_chunk(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    ApiPath((id, index)): ApiPath<(Uuid, i32)>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ApiError>)> {
+    auth.require_scope(SCOPE_HISTORY_READ)?;
+
+    let attachment = load(&state, auth.user_id, id).await?;
+    if attachment.completed_at.is_none() {
+        return Err(err(StatusCode::CONFLICT, "Attachment upload is not complete"));
+    }
+
+    let data: Option<Vec<u8>> = with_db!(&state.db, pool => {
+        sqlx::query_scalar(
+            "SELECT data FROM attachment_chunks WHERE attachment_id = $1 AND chunk_index = $2",
+        )
+        .bind(id)
+        .bind(index)
+        .fetch_optional(pool)
+        .await
+    })
+    .map_err(|_| err(StatusCode::INTERNAL_SERVER_ERROR, "Database error"))?;
+
+    let data = data.ok_or_else(|| err(StatusCode::NOT_FOUND, "Chunk not found"))?;
+    Ok(([(header::CONTENT_TYPE, "application/octet-stream")], data))
+}
+
+/// The user's attachment, or 404.
+async fn load(
+    state: &AppState,
+    user_id: Uuid,
+    id: Uuid,
+) -> Result<Attachment, (StatusCode, Json<ApiError>)> {
+    with_db!(&state.db, pool => {
+        sqlx::query_as::<_, Attachment>(
+            "SELECT id, user_id, size, chunk_count, completed_at, created_at
+             FROM attachments WHERE id = $1 AND user_id = $2",
+        )
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+    })
+    .map_err(|_| err(StatusCode::INTERNAL_SERVER_ERROR, "Database error"))?
+    .ok_or_else(|| err(StatusCode::NOT_FOUND, "Attachment not found"))
+}
+
+async fn uploaded_chunks(
+    state: &AppState,
+    id: Uuid,
+) -> Result<Vec<i32>, (StatusCode, Json<ApiError>)> {
+    with_db!(&state.db, pool => {
+        sqlx::query_scalar(
+            "SELECT chunk_index FROM attachment_chunks WHERE attachment_id = $1
+             ORDER BY chunk_index",
+        )
+        .bind(id)
+        .fetch_all(pool)
+        .await
+    })
+    .map_err(|_| err(StatusCode::INTERNAL_SERVER_ERROR, "Database error"))
+}
+
+/// Whether the user has a completed attachment with this id, so a history item
+/// may reference it.
+pub(crate) async fn is_complete(db: &Db, user_id: Uuid, id: Uuid) -> Result<bool, sqlx::Error> {
+    with_db!(db, pool => {
+        sqlx::query_scalar(
+            "SELECT EXISTS (SELECT 1 FROM attachments
+             WHERE id = $1 AND user_id = $2 AND completed_at IS NOT NULL)",
+        )
+        .bind(id)
+        .bind(user_id)
+        .fetch_one(pool)
+        .await
+    })
+}
+
+/// Delete attachments older than `UNREFERENCED_TTL` that no history item
+/// points at. Returns how many were removed.
+pub async fn prune_unreferenced(db: &Db) -> Result<u64, sqlx::Error> {
+    with_db!(db, pool => {
+        sqlx::query(
+            "DELETE FROM attachments
+             WHERE created_at < $1
+               AND NOT EXISTS (SELECT 1 FROM all_history WHERE attachment_id = attachments.id)",
+        )
+        .bind(db::now() - UNREFERENCED_TTL)
+        .execute(pool)
+        .await
+        .map(|r| r.rows_affected())
+    })
+}
@@ -1,13 +1,27 @@
+use std::net::{IpAddr, SocketAddr};
+
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
+    extract::{ConnectInfo, Form, State},
+    http::{HeaderMap, StatusCode},
+    response::Html,
     routing::{delete, get, post},
     Json, Router,
 };
+use rand::{distributions::Alphanumeric, Rng as _};
 use uuid::Uuid;
 
+use crate::errors::{err, ApiError};
+use crate::audit::{self, EVENT_DEVICE_REVOKED, EVENT_LOGIN_LOCKED};
+use crate::db::{self, with_db};
+use crate::jwt_keys::Jwk;
+use crate::login_guard::{client_ip, Lockout, LOCKOUT_DURATION};
+use crate::middleware::extract::{ApiJson, ApiPath, ApiQuery};
 use crate::middleware::auth::{create_token, AuthUser};
-use crate::models::device::{DeviceResponse, RegisterDeviceRequest};
+use crate::middleware::validate::ValidJson;
+use crate::models::api_key::hash_key;
+use crate::models::device::{
+    DeviceMetadata, DeviceResponse, RegisterDeviceRequest, RevokeDeviceParams,
+};
 use crate::models::user::{AuthResponse, LoginRequest, RegisterRequest};
 use crate::AppState;
 
@@ -15,27 +29,15 @@ use argon2::Argon2;
 use password_hash::rand_core::OsRng;
 use password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
 
-#[derive(serde::Serialize, utoipa::ToSchema)]
-pub(crate) struct ApiError {
-    error: String,
-}
-
-fn err(status: StatusCode, msg: &str) -> (StatusCode, Json<ApiError>) {
-    (
-        status,
-        Json(ApiError {
-            error: msg.to_string(),
-        }),
-    )
-}
-
 pub fn router() -> Router<AppState> {
     Router::new()
         .route("/register", post(register))
         .route("/login", post(login))
-        .route("/device", post(register_device))
+        .route("/device", post(register_device).put(update_device))
         .route("/device/{id}", delete(delete_device))
+        .route("/device/revoke", get(revoke_device_page).post(revoke_device))
         .route("/devices", get(list_devices))
+        .route("/jwks", get(jwks))
 }
 
 #[utoipa::path(
@@ -51,7 +53,7 @@ pub fn router() -> Router<AppState> {
 )]
 pub(crate) async fn register(
     State(state): State<AppState>,
-    Json(req): Json<RegisterRequest>,
+    ApiJson(req): ApiJson<RegisterRequest>,
 ) -> Result<Json<AuthResponse>, (StatusCode, Json<ApiError>)> {
     let email = req.email.trim().to_lowercase();
     if !email.contains('@') || email.len() < 5 {
@@ -70,22 +72,31 @@ pub(crate) async fn register(
         .map_err(|_| err(StatusCode::INTERNAL_SERVER_ERROR, "Failed to hash password"))?
         .to_string();
 
-    let user_id: Uuid = sqlx::query_scalar(
-        "INSERT INTO users (email, password_hash) VALUES ($1, $2) RETURNING id",
-    )
-    .bind(&email)
-    .bind(&hash)
-    .fetch_one(&state.db)
-    .await
+    let user_id = Uuid::new_v4();
+    with_db!(&state.db, pool => {
+        sqlx::query(
+            "INSERT INTO users (id, email, password_hash, created_at) VALUES ($1, $2, $3, $4)",
+        )
+        .bind(user_id)
+        .bind(&email)
+        .bind(&hash)
+        .bind(db::now())
+        .execute(pool)
+        .await
+        .map(|_| ())
+    })
     .map_err(|e| {
-        if e.to_string().contains("unique") || e.to_string().contains("duplicate") {
+        let unique_violation = e
+            .as_database_error()
+            .is_some_and(|db_err| db_err.is_unique_violation());
+        if unique_violation {
             err(StatusCode::CONFLICT, "Email already registered")
         } else {
             err(StatusCode::INTERNAL_SERVER_ERROR, "Failed to create user")
         }
     })?;
 
-    let token = create_token(user_id, None, &state.jwt_secret)
+    let token = create_token(user_id, None, &state.jwt_keys)
         .map_err(|_| err(StatusCode::INTERNAL_SERVER_ERROR, "Failed to create token"))?;
 
     Ok(Json(AuthResponse { token, user_id }))
@@ -98,72 +109,241 @@ pub(crate) async fn register(
     responses(
         (status = 200, description = "Login successful", body = AuthResponse),
         (status = 401, description = "Invalid credentials", body = ApiError),
+        (status = 429, description = "Too many failed attempts; wait or account temporarily locked", body = ApiError),
     ),
     tag = "Auth"
 )]
 pub(crate) async fn login(
     State(state): State<AppState>,
-    Json(req): Json<LoginRequest>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    ApiJson(req): ApiJson<LoginRequest>,
 ) -> Result<Json<AuthResponse>, (StatusCode, Json<ApiError>)> {
     let email = req.email.trim().to_lowercase();
+    let ip = client_ip(&headers, peer, state.trust_forwarded_for);
+
+    if let Err(wait) = state.login_guard.check(&email, ip) {
+        return Err(err(
+            StatusCode::TOO_MANY_REQUESTS,
+            &format!(
+                "Too many failed login attempts, try again in {} seconds",
+                wait.as_secs()
+            ),
+        ));
+    }
 
-    let row =
+    let row = with_db!(&state.db, pool => {
         sqlx::query_as::<_, (Uuid, String)>("SELECT id, password_hash FROM users WHERE email = $1")
             .bind(&email)
-            .fetch_optional(&state.db)
+            .fetch_optional(pool)
             .await
-            .map_err(|_| err(StatusCode::INTERNAL_SERVER_ERROR, "Database error"))?
-            .ok_or_else(|| err(StatusCode::UNAUTHORIZED, "Invalid credentials"))?;
+    })
+    .map_err(|_| err(StatusCode::INTERNAL_SERVER_ERROR, "Database error"))?;
 
-    let (user_id, password_hash) = row;
+    let Some((user_id, password_hash)) = row else {
+        login_failed(&state, &email, None, ip).await;
+        return Err(err(StatusCode::UNAUTHORIZED, "Invalid credentials"));
+    };
 
     let parsed_hash = PasswordHash::new(&password_hash)
         .map_err(|_| err(StatusCode::INTERNAL_SERVER_ERROR, "Invalid stored hash"))?;
 
-    Argon2::default()
+    if Argon2::default()
         .verify_password(req.password.as_bytes(), &parsed_hash)
-        .map_err(|_| err(StatusCode::UNAUTHORIZED, "Invalid credentials"))?;
+        .is_err()
+    {
+        login_failed(&state, &email, Some(user_id), ip).await;
+        return Err(err(StatusCode::UNAUTHORIZED, "Invalid credentials"));
+    }
+
+    state.login_guard.record_success(&email);
 
-    let token = create_token(user_id, None, &state.jwt_secret)
+    let token = create_token(user_id, None, &state.jwt_keys)
         .map_err(|_| err(StatusCode::INTERNAL_SERVER_ERROR, "Failed to create token"))?;
 
     Ok(Json(AuthResponse { token, user_id }))
 }
 
+/// Count a failed login and write an audit event for any lockout it triggers.
+async fn login_failed(state: &AppState, email: &str, user_id: Option<Uuid>, ip: IpAddr) {
+    for lockout in state.login_guard.record_failure(email, ip) {
+        let (user_id, scope) = match lockout {
+            Lockout::Account => (user_id, "account"),
+            Lockout::Ip => (None, "ip"),
+        };
+        tracing::warn!("Login locked out for {} ({}, {})", scope, email, ip);
+        audit::record(
+            &state.db,
+            user_id,
+            EVENT_LOGIN_LOCKED,
+            Some(ip),
+            serde_json::json!({
+                "scope": scope,
+                "email": email,
+                "duration_secs": LOCKOUT_DURATION.as_secs(),
+            }),
+        )
+        .await;
+    }
+}
+
 #[utoipa::path(
     post,
     path = "/api/auth/device",
     request_body = RegisterDeviceRequest,
     responses(
         (status = 200, description = "Device registered, returns new JWT with device_id"),
+        (status = 422, description = "Device metadata too long", body = ApiError),
     ),
     security(("bearer" = [])),
     tag = "Auth"
 )]
 pub(crate) async fn register_device(
     State(state): State<AppState>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
     auth: AuthUser,
-    Json(req): Json<RegisterDeviceRequest>,
+    ValidJson(req): ValidJson<RegisterDeviceRequest>,
 ) -> Result<Json<serde_json::Value>, (StatusCode, Json<ApiError>)> {
-    let device_id: Uuid = sqlx::query_scalar(
-        "INSERT INTO devices (user_id, name, device_type) VALUES ($1, $2, $3) RETURNING id",
-    )
-    .bind(auth.user_id)
-    .bind(&req.name)
-    .bind(&req.device_type)
-    .fetch_one(&state.db)
-    .await
+    auth.require_session()?;
+
+    // Only needed for the notification email's revoke link
+    let revoke_token = state.mailer.is_enabled().then(|| {
+        rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(32)
+            .map(char::from)
+            .collect::<String>()
+    });
+
+    let device_id = Uuid::new_v4();
+    let now = db::now();
+    with_db!(&state.db, pool => {
+        sqlx::query(
+            "INSERT INTO devices (id, user_id, name, device_type, last_seen, created_at,
+                                  revoke_token_hash, app_version, os_version, hostname)
+             VALUES ($1, $2, $3, $4, $5, $5, $6, $7, $8, $9)",
+        )
+        .bind(device_id)
+        .bind(auth.user_id)
+        .bind(&req.name)
+        .bind(&req.device_type)
+        .bind(now)
+        .bind(revoke_token.as_deref().map(hash_key))
+        .bind(&req.metadata.app_version)
+        .bind(&req.metadata.os_version)
+        .bind(&req.metadata.hostname)
+        .execute(pool)
+        .await
+        .map(|_| ())
+    })
     .map_err(|_| err(StatusCode::INTERNAL_SERVER_ERROR, "Failed to register device"))?;
 
-    let token = create_token(auth.user_id, Some(device_id), &state.jwt_secret)
+    let token = create_token(auth.user_id, Some(device_id), &state.jwt_keys)
         .map_err(|_| err(StatusCode::INTERNAL_SERVER_ERROR, "Failed to create token"))?;
 
+    if let Some(revoke_token) = revoke_token {
+        let ip = client_ip(&headers, peer, state.trust_forwarded_for);
+        notify_new_device(&state, auth.user_id, &req, ip, &revoke_token).await;
+    }
+
     Ok(Json(serde_json::json!({
         "device_id": device_id,
         "token": token,
     })))
 }
 
+#[utoipa::path(
+    put,
+    path = "/api/auth/device",
+    request_body = DeviceMetadata,
+    responses(
+        (status = 204, description = "Metadata of the calling device updated"),
+        (status = 400, description = "Token is not bound to a device", body = ApiError),
+        (status = 404, description = "Device was removed", body = ApiError),
+        (status = 422, description = "Device metadata too long", body = ApiError),
+    ),
+    security(("bearer" = [])),
+    tag = "Auth"
+)]
+pub(crate) async fn update_device(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    ValidJson(req): ValidJson<DeviceMetadata>,
+) -> Result<StatusCode, (StatusCode, Json<ApiError>)> {
+    auth.require_session()?;
+    let device_id = auth
+        .device_id
+        .ok_or_else(|| err(StatusCode::BAD_REQUEST, "Token is not bound to a device"))?;
+
+    let updated = with_db!(&state.db, pool => {
+        sqlx::query(
+            "UPDATE devices SET app_version = $1, os_version = $2, hostname = $3
+             WHERE id = $4 AND user_id = $5",
+        )
+        .bind(&req.app_version)
+        .bind(&req.os_version)
+        .bind(&req.hostname)
+        .bind(device_id)
+        .bind(auth.user_id)
+        .execute(pool)
+        .await
+        .map(|r| r.rows_affected())
+    })
+    .map_err(|_| err(StatusCode::INTERNAL_SERVER_ERROR, "Failed to update device"))?;
+
+    if updated == 0 {
+        return Err(err(StatusCode::NOT_FOUND, "Device not found"));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Email the account owner about a newly registered device.
+async fn notify_new_device(
+    state: &AppState,
+    user_id: Uuid,
+    device: &RegisterDeviceRequest,
+    ip: IpAddr,
+    revoke_token: &str,
+) {
+    let email = with_db!(&state.db, pool => {
+        sqlx::query_scalar::<_, String>("SELECT email FROM users WHERE id = $1")
+            .bind(user_id)
+            .fetch_optional(pool)
+            .await
+    });
+    let email = match email {
+        Ok(Some(email)) => email,
+        Ok(None) => return,
+        Err(e) => {
+            tracing::error!("Failed to look up email for new device notification: {}", e);
+            return;
+        }
+    };
+
+    let Some(revoke_url) = state
+        .mailer
+        .link(&format!("/api/auth/device/revoke?token={}", revoke_token))
+    else {
+        return;
+    };
+    let body = format!(
+        "A new device was added to your ClipSlot account.\n\n\
+         Device: {} ({})\n\
+         IP address: {}\n\
+         Time: {}\n\n\
+         If this wasn't you, remove the device and change your password:\n{}\n",
+        device.name,
+        device.device_type,
+        ip,
+        chrono::Utc::now().format("%Y-%m-%d %H:%M UTC"),
+        revoke_url,
+    );
+    state
+        .mailer
+        .send(&email, "New device added to your ClipSlot account", body);
+}
+
 #[utoipa::path(
     delete,
     path = "/api/auth/device/{id}",
@@ -178,18 +358,24 @@ pub(crate) async fn register_device(
 pub(crate) async fn delete_device(
     State(state): State<AppState>,
     auth: AuthUser,
-    Path(device_id): Path<Uuid>,
+    ApiPath(device_id): ApiPath<Uuid>,
 ) -> Result<StatusCode, (StatusCode, Json<ApiError>)> {
-    let result = sqlx::query("DELETE FROM devices WHERE id = $1 AND user_id = $2")
-        .bind(device_id)
-        .bind(auth.user_id)
-        .execute(&state.db)
-        .await
-        .map_err(|_| err(StatusCode::INTERNAL_SERVER_ERROR, "Database error"))?;
+    auth.require_session()?;
 
-    if result.rows_affected() == 0 {
+    let deleted = with_db!(&state.db, pool => {
+        sqlx::query("DELETE FROM devices WHERE id = $1 AND user_id = $2")
+            .bind(device_id)
+            .bind(auth.user_id)
+            .execute(pool)
+            .await
+            .map(|r| r.rows_affected())
+    })
+    .map_err(|_| err(StatusCode::INTERNAL_SERVER_ERROR, "Database error"))?;
+
+    if deleted == 0 {
         return Err(err(StatusCode::NOT_FOUND, "Device not found"));
     }
+    state.known_devices.remove(device_id);
 
     Ok(StatusCode::NO_CONTENT)
 }
@@ -207,14 +393,141 @@ pub(crate) async fn list_devices(
     State(state): State<AppState>,
     auth: AuthUser,
 ) -> Result<Json<Vec<DeviceResponse>>, (StatusCode, Json<ApiError>)> {
-    let devices = sqlx::query_as::<_, crate::models::device::Device>(
-        "SELECT id, user_id, name, device_type, last_seen, created_at
-         FROM devices WHERE user_id = $1 ORDER BY created_at DESC",
-    )
-    .bind(auth.user_id)
-    .fetch_all(&state.db)
-    .await
+    auth.require_session()?;
+
+    let devices = with_db!(&state.db, pool => {
+        sqlx::query_as::<_, crate::models::device::Device>(
+            "SELECT id, user_id, name, device_type, app_version, os_version, hostname,
+                    last_seen, created_at
+             FROM devices WHERE user_id = $1 ORDER BY created_at DESC",
+        )
+        .bind(auth.user_id)
+        .fetch_all(pool)
+        .await
+    })
     .map_err(|_| err(StatusCode::INTERNAL_SERVER_ERROR, "Database error"))?;
 
     Ok(Json(devices.into_iter().map(DeviceResponse::from).collect()))
 }
+
+#[derive(serde::Serialize, utoipa::ToSchema)]
+pub(crate) struct JwksResponse {
+    keys: Vec<Jwk>,
+}
+
+/// Public keys that currently verify session tokens, matched by the token's `kid`.
+#[utoipa::path(
+    get,
+    path = "/api/auth/jwks",
+    responses(
+        (status = 200, description = "JSON Web Key Set of the token verification keys", body = JwksResponse),
+    ),
+    tag = "Auth"
+)]
+pub(crate) async fn jwks(State(state): State<AppState>) -> Json<JwksResponse> {
+    Json(JwksResponse {
+        keys: state.jwt_keys.jwks(),
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/auth/device/revoke",
+    params(RevokeDeviceParams),
+    responses(
+        (status = 200, description = "Confirmation page for removing the device", content_type = "text/html"),
+    ),
+    tag = "Auth"
+)]
+pub(crate) async fn revoke_device_page(ApiQuery(params): ApiQuery<RevokeDeviceParams>) -> Html<String> {
+    // Removing the device takes a POST so link scanners in mail clients can't trigger it
+    Html(page(
+        "Remove device?",
+        &format!(
+            "<p>This removes the device from your ClipSlot account.</p>\
+             <form method=\"post\" action=\"/api/auth/device/revoke\">\
+             <input type=\"hidden\" name=\"token\" value=\"{}\">\
+             <button type=\"submit\">Remove device</button></form>",
+            html_escape(&params.token)
+        ),
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/auth/device/revoke",
+    request_body(content = RevokeDeviceParams, content_type = "application/x-www-form-urlencoded"),
+    responses(
+        (status = 200, description = "Device removed", content_type = "text/html"),
+        (status = 404, description = "Invalid link or device already removed", content_type = "text/html"),
+    ),
+    tag = "Auth"
+)]
+pub(crate) async fn revoke_device(
+    State(state): State<AppState>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Form(params): Form<RevokeDeviceParams>,
+) -> (StatusCode, Html<String>) {
+    let deleted = with_db!(&state.db, pool => {
+        sqlx::query_as::<_, (Uuid, Uuid)>(
+            "DELETE FROM devices WHERE revoke_token_hash = $1 RETURNING id, user_id",
+        )
+        .bind(hash_key(&params.token))
+        .fetch_optional(pool)
+        .await
+    });
+
+    match deleted {
+        Ok(Some((device_id, user_id))) => {
+            state.known_devices.remove(device_id);
+            let ip = client_ip(&headers, peer, state.trust_forwarded_for);
+            tracing::warn!("Device {} removed via email revoke link", device_id);
+            audit::record(
+                &state.db,
+                Some(user_id),
+                EVENT_DEVICE_REVOKED,
+                Some(ip),
+                serde_json::json!({ "device_id": device_id }),
+            )
+            .await;
+            (
+                StatusCode::OK,
+                Html(page(
+                    "Device removed",
+                    "<p>The device has been removed. If you didn't add it, change your \
+                     password now.</p>",
+                )),
+            )
+        }
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Html(page(
+                "Link no longer valid",
+                "<p>This device has already been removed, or the link is invalid.</p>",
+            )),
+        ),
+        Err(e) => {
+            tracing::error!("Failed to revoke device: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Html(page("Something went wrong", "<p>Please try again later.</p>")),
+            )
+        }
+    }
+}
+
+pub(crate) fn page(title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>{0} - ClipSlot</title>\
+         </head><body><h1>{0}</h1>{1}</body></html>",
+        title, body
+    )
+}
+
+pub(crate) fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -6,6 +6,8 @@ use axum::{
 };
 use rand::Rng as _;
 
+use crate::errors::{err, ApiError};
+use crate::middleware::extract::ApiJson;
 use crate::middleware::auth::AuthUser;
 use crate::AppState;
 
@@ -29,20 +31,6 @@ pub struct RedeemCodeResponse {
     pub encrypted_key: String,
 }
 
-#[derive(serde::Serialize)]
-struct ApiError {
-    error: String,
-}
-
-fn err(status: StatusCode, msg: &str) -> (StatusCode, Json<ApiError>) {
-    (
-        status,
-        Json(ApiError {
-            error: msg.to_string(),
-        }),
-    )
-}
-
 pub fn router() -> Router<AppState> {
     Router::new()
         .route("/link-code", post(generate_link_code))
@@ -52,9 +40,10 @@ pub fn router() -> Router<AppState> {
 /// Generate a 6-digit link code that holds the encrypted master key for 5 minutes.
 async fn generate_link_code(
     State(state): State<AppState>,
-    _auth: AuthUser,
-    Json(req): Json<GenerateCodeRequest>,
+    auth: AuthUser,
+    ApiJson(req): ApiJson<GenerateCodeRequest>,
 ) -> Result<Json<GenerateCodeResponse>, (StatusCode, Json<ApiError>)> {
+    auth.require_session()?;
     if req.encrypted_key.is_empty() {
         return Err(err(StatusCode::BAD_REQUEST, "encrypted_key is required"));
     }
@@ -78,9 +67,10 @@ async fn generate_link_code(
 /// The code is deleted after retrieval (one-time use).
 async fn redeem_link_code(
     State(state): State<AppState>,
-    _auth: AuthUser,
-    Json(req): Json<RedeemCodeRequest>,
+    auth: AuthUser,
+    ApiJson(req): ApiJson<RedeemCodeRequest>,
 ) -> Result<Json<RedeemCodeResponse>, (StatusCode, Json<ApiError>)> {
+    auth.require_session()?;
     let code = req.code.trim().to_string();
 
     if code.len() != 6 || !code.chars().all(|c| c.is_ascii_digit()) {
@@ -0,0 +1,86 @@
+use std::fmt::Write as _;
+
+use axum::{
+    extract::State,
+    http::{header, HeaderMap, StatusCode},
+    response::IntoResponse,
+    routing::get,
+    Json, Router,
+};
+use sha2::{Digest, Sha256};
+
+use crate::errors::{err, ApiError};
+use crate::AppState;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/metrics", get(metrics))
+        .route("/api/health", get(health))
+}
+
+/// Lets clients tell an unreachable server from a dropped connection
+/// without signing in; touches nothing, so it stays cheap to poll.
+#[utoipa::path(
+    get,
+    path = "/api/health",
+    responses(
+        (status = 200, description = "The server is up", content_type = "text/plain"),
+    ),
+    tag = "Metrics"
+)]
+pub(crate) async fn health() -> &'static str {
+    "ok"
+}
+
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    responses(
+        (status = 200, description = "Counters in Prometheus text format", content_type = "text/plain"),
+        (status = 401, description = "Missing or wrong metrics token", body = ApiError),
+        (status = 404, description = "Metrics are disabled (no METRICS_TOKEN set)", body = ApiError),
+    ),
+    security(("bearer" = [])),
+    tag = "Metrics"
+)]
+pub(crate) async fn metrics(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, (StatusCode, Json<ApiError>)> {
+    let Some(expected) = state.metrics_token.as_deref() else {
+        return Err(err(StatusCode::NOT_FOUND, "Not found"));
+    };
+    let token = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .unwrap_or_default();
+    // Compare digests so the time taken doesn't reveal how much of the token matched
+    if Sha256::digest(token) != Sha256::digest(expected) {
+        return Err(err(StatusCode::UNAUTHORIZED, "Invalid metrics token"));
+    }
+
+    let relay = state.user_channels.stats();
+
+    let mut body = String::new();
+    counter(
+        &mut body,
+        "clipslot_ws_lag_events_total",
+        "WebSocket connections that fell behind their broadcast channel",
+        relay.lag_events,
+    );
+    counter(
+        &mut body,
+        "clipslot_ws_dropped_messages_total",
+        "Broadcasts skipped by WebSocket connections that fell behind",
+        relay.dropped_messages,
+    );
+
+    Ok(([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], body))
+}
+
+fn counter(out: &mut String, name: &str, help: &str, value: u64) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} counter", name);
+    let _ = writeln!(out, "{} {}", name, value);
+}
@@ -1,6 +1,13 @@
+pub mod api_keys;
+pub mod attachments;
 pub mod auth;
 pub mod key_exchange;
+pub mod metrics;
+pub mod shares;
 pub mod sync;
+pub mod teams;
+pub mod telemetry;
+pub mod webhooks;
 pub mod ws;
 
 use axum::Router;
@@ -10,7 +17,14 @@ pub fn api_router(state: AppState) -> Router {
     Router::new()
         .nest("/api/auth", auth::router())
         .nest("/api/auth", key_exchange::router())
+        .nest("/api/auth", api_keys::router())
         .nest("/api/sync", sync::router())
+        .nest("/api/sync", attachments::router())
+        .nest("/api", webhooks::router())
+        .nest("/api", teams::router())
+        .merge(shares::router())
         .merge(ws::router())
+        .merge(metrics::router())
+        .merge(telemetry::router())
         .with_state(state)
 }
@@ -0,0 +1,197 @@
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    response::{Html, IntoResponse},
+    routing::{get, post},
+    Json, Router,
+};
+use rand::{distributions::Alphanumeric, Rng as _};
+
+use crate::errors::{err, ApiError};
+use crate::db::{self, with_db, Db};
+use crate::middleware::extract::ApiPath;
+use crate::middleware::auth::AuthUser;
+use crate::middleware::validate::ValidJson;
+use crate::models::share::{
+    CreateShareRequest, CreateShareResponse, ShareResponse, DEFAULT_SHARE_TTL_SECS,
+    MAX_SHARES_PER_USER, SHARE_ID_LEN,
+};
+use crate::routes::auth::{html_escape, page};
+use crate::AppState;
+
+/// Fetches the share when asked to (so link previews don't use it up) and
+/// decrypts it with the key from the URL fragment, which never reaches the server.
+const SHARE_PAGE_SCRIPT: &str = r#"
+document.getElementById("open").onclick = async () => {
+  const out = document.getElementById("out");
+  const key = location.hash.slice(1);
+  if (!key) {
+    out.textContent = "This link is missing its key.";
+    return;
+  }
+  const res = await fetch("/api/shares/" + shareId);
+  if (!res.ok) {
+    out.textContent = "This link has already been opened or has expired.";
+    return;
+  }
+  document.getElementById("open").remove();
+  const bytes = (s) => Uint8Array.from(atob(s), (c) => c.charCodeAt(0));
+  try {
+    const { encrypted_blob } = await res.json();
+    const stored = new TextDecoder().decode(bytes(encrypted_blob));
+    const data = bytes(stored.replace(/^ENC:/, ""));
+    const raw = bytes(key.replace(/-/g, "+").replace(/_/g, "/"));
+    const aes = await crypto.subtle.importKey("raw", raw, "AES-GCM", false, ["decrypt"]);
+    const plain = await crypto.subtle.decrypt(
+      { name: "AES-GCM", iv: data.slice(0, 12) }, aes, data.slice(12));
+    out.textContent = new TextDecoder().decode(plain);
+  } catch (e) {
+    out.textContent = "The link's key doesn't match its content.";
+  }
+};
+"#;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/api/shares", post(create_share))
+        .route("/api/shares/{id}", get(open_share))
+        .route("/s/{id}", get(share_page))
+}
+
+fn is_share_id(id: &str) -> bool {
+    id.len() == SHARE_ID_LEN && id.bytes().all(|b| b.is_ascii_alphanumeric())
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/shares",
+    request_body = CreateShareRequest,
+    responses(
+        (status = 201, description = "Share created; it can be opened once", body = CreateShareResponse),
+        (status = 400, description = "Too many unopened shares", body = ApiError),
+        (status = 413, description = "Blob too large", body = ApiError),
+    ),
+    security(("bearer" = [])),
+    tag = "Shares"
+)]
+pub(crate) async fn create_share(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    ValidJson(req): ValidJson<CreateShareRequest>,
+) -> Result<(StatusCode, Json<CreateShareResponse>), (StatusCode, Json<ApiError>)> {
+    auth.require_session()?;
+    let now = db::now();
+
+    let count: i64 = with_db!(&state.db, pool => {
+        sqlx::query_scalar("SELECT COUNT(*) FROM shares WHERE user_id = $1 AND expires_at > $2")
+            .bind(auth.user_id)
+            .bind(now)
+            .fetch_one(pool)
+            .await
+    })
+    .map_err(|_| err(StatusCode::INTERNAL_SERVER_ERROR, "Database error"))?;
+    if count >= MAX_SHARES_PER_USER {
+        return Err(err(StatusCode::BAD_REQUEST, "Too many unopened shares"));
+    }
+
+    let id: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(SHARE_ID_LEN)
+        .map(char::from)
+        .collect();
+    let ttl = req.expires_in_secs.unwrap_or(DEFAULT_SHARE_TTL_SECS);
+    let expires_at = now + chrono::Duration::seconds(ttl);
+
+    with_db!(&state.db, pool => {
+        sqlx::query(
+            "INSERT INTO shares (id, user_id, encrypted_blob, expires_at, created_at)
+             VALUES ($1, $2, $3, $4, $5)",
+        )
+        .bind(&id)
+        .bind(auth.user_id)
+        .bind(&req.encrypted_blob)
+        .bind(expires_at)
+        .bind(now)
+        .execute(pool)
+        .await
+        .map(|_| ())
+    })
+    .map_err(|_| err(StatusCode::INTERNAL_SERVER_ERROR, "Failed to create share"))?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(CreateShareResponse {
+            path: format!("/s/{}", id),
+            id,
+            expires_at,
+        }),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/shares/{id}",
+    params(("id" = String, Path, description = "Share id")),
+    responses(
+        (status = 200, description = "The encrypted share; it is deleted as it is returned", body = ShareResponse),
+        (status = 404, description = "Unknown, expired or already opened", body = ApiError),
+    ),
+    tag = "Shares"
+)]
+pub(crate) async fn open_share(
+    State(state): State<AppState>,
+    ApiPath(id): ApiPath<String>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ApiError>)> {
+    let not_found = || err(StatusCode::NOT_FOUND, "Share not found or already opened");
+    if !is_share_id(&id) {
+        return Err(not_found());
+    }
+
+    // Deleting and reading in one statement, so two requests can't both get it
+    let blob: Option<String> = with_db!(&state.db, pool => {
+        sqlx::query_scalar(
+            "DELETE FROM shares WHERE id = $1 AND expires_at > $2 RETURNING encrypted_blob",
+        )
+        .bind(&id)
+        .bind(db::now())
+        .fetch_optional(pool)
+        .await
+    })
+    .map_err(|_| err(StatusCode::INTERNAL_SERVER_ERROR, "Database error"))?;
+
+    let encrypted_blob = blob.ok_or_else(not_found)?;
+    Ok((
+        [(header::CACHE_CONTROL, "no-store")],
+        Json(ShareResponse { encrypted_blob }),
+    ))
+}
+
+/// Page a share link opens in the browser.
+async fn share_page(ApiPath(id): ApiPath<String>) -> (StatusCode, Html<String>) {
+    if !is_share_id(&id) {
+        return (
+            StatusCode::NOT_FOUND,
+            Html(page("Link not found", "<p>This share link isn't valid.</p>")),
+        );
+    }
+    let body = format!(
+        "<p>Someone shared a snippet with you using ClipSlot. It can only be opened \
+         once, then the link stops working.</p>\
+         <button id=\"open\">Show snippet</button><pre id=\"out\"></pre>\
+         <script>const shareId = \"{}\";{}</script>",
+        html_escape(&id),
+        SHARE_PAGE_SCRIPT
+    );
+    (StatusCode::OK, Html(page("Shared snippet", &body)))
+}
+
+/// Delete shares that expired without being opened.
+pub async fn prune_expired(db: &Db) -> Result<u64, sqlx::Error> {
+    with_db!(db, pool => {
+        sqlx::query("DELETE FROM shares WHERE expires_at <= $1")
+            .bind(db::now())
+            .execute(pool)
+            .await
+            .map(|r| r.rows_affected())
+    })
+}
@@ -1,39 +1,42 @@
 use axum::{
-    extract::{Path, Query, State},
+    extract::State,
     http::StatusCode,
     routing::{delete, get, post, put},
     Json, Router,
 };
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chrono::{DateTime, Utc};
 use uuid::Uuid;
 
+use crate::errors::{err, ApiError};
+use crate::db::{self, with_db, Db};
+use crate::middleware::extract::{ApiPath, ApiQuery};
 use crate::middleware::auth::AuthUser;
+use crate::middleware::validate::{blob_error_status, ValidJson};
+use crate::routes::attachments;
+use crate::models::api_key::{
+    SCOPE_HISTORY_READ, SCOPE_HISTORY_WRITE, SCOPE_SLOTS_READ, SCOPE_SLOTS_WRITE,
+};
+use crate::models::webhook::{EVENT_HISTORY_CREATED, EVENT_SLOT_UPDATED};
 use crate::models::sync::{
-    HistoryQuery, HistoryResponse, PushHistoryRequest, SlotResponse, SyncedHistoryItem,
-    SyncedSlot, UpdateSlotRequest, WsMessage,
+    decode_blob, is_valid_search_token, DeletedHistoryItem, HistoryCursor, HistoryDeltaResponse,
+    HistoryListResponse, HistoryPageResponse, HistoryQuery, HistoryResponse, HistorySearchQuery,
+    HistoryTombstone, PushHistoryRequest, SlotResponse, SlotSettings, SlotVersion,
+    SlotVersionResponse, SyncedHistoryItem, SyncedSlot, UpdateSlotRequest, WsMessage,
+    HOT_HISTORY_ITEMS, MAX_QUERY_TOKENS, MAX_SLOT_VERSIONS,
 };
 use crate::AppState;
 
-#[derive(serde::Serialize, utoipa::ToSchema)]
-pub(crate) struct ApiError {
-    error: String,
-}
-
-fn err(status: StatusCode, msg: &str) -> (StatusCode, Json<ApiError>) {
-    (
-        status,
-        Json(ApiError {
-            error: msg.to_string(),
-        }),
-    )
-}
-
 pub fn router() -> Router<AppState> {
     Router::new()
         .route("/slots", get(get_slots))
         .route("/slots/{number}", put(update_slot))
+        .route("/slots/{number}/versions", get(list_slot_versions))
+        .route("/slots/{number}/versions/{id}/restore", post(restore_slot_version))
+        .route("/settings", get(get_slot_settings).put(update_slot_settings))
         .route("/history", get(get_history))
         .route("/history", post(push_history))
+        .route("/history/search", get(search_history))
         .route("/history/{id}", delete(delete_history))
 }
 
@@ -50,13 +53,17 @@ pub(crate) async fn get_slots(
     State(state): State<AppState>,
     auth: AuthUser,
 ) -> Result<Json<Vec<SlotResponse>>, (StatusCode, Json<ApiError>)> {
-    let slots = sqlx::query_as::<_, SyncedSlot>(
-        "SELECT user_id, slot_number, encrypted_blob, updated_at, updated_by
-         FROM synced_slots WHERE user_id = $1 ORDER BY slot_number",
-    )
-    .bind(auth.user_id)
-    .fetch_all(&state.db)
-    .await
+    auth.require_scope(SCOPE_SLOTS_READ)?;
+
+    let slots = with_db!(&state.db, pool => {
+        sqlx::query_as::<_, SyncedSlot>(
+            "SELECT user_id, slot_number, encrypted_blob, updated_at, updated_by
+             FROM synced_slots WHERE user_id = $1 ORDER BY slot_number",
+        )
+        .bind(auth.user_id)
+        .fetch_all(pool)
+        .await
+    })
     .map_err(|_| err(StatusCode::INTERNAL_SERVER_ERROR, "Database error"))?;
 
     let response: Vec<SlotResponse> = slots
@@ -75,11 +82,13 @@ pub(crate) async fn get_slots(
 #[utoipa::path(
     put,
     path = "/api/sync/slots/{number}",
-    params(("number" = i32, Path, description = "Slot number (1-10)")),
+    params(("number" = i32, Path, description = "Slot number, up to the account's slot_count")),
     request_body = UpdateSlotRequest,
     responses(
         (status = 200, description = "Slot updated"),
-        (status = 400, description = "Invalid slot number or blob"),
+        (status = 400, description = "Invalid slot number"),
+        (status = 413, description = "Blob too large"),
+        (status = 422, description = "Malformed body or invalid blob"),
     ),
     security(("bearer" = [])),
     tag = "Sync"
@@ -87,54 +96,287 @@ pub(crate) async fn get_slots(
 pub(crate) async fn update_slot(
     State(state): State<AppState>,
     auth: AuthUser,
-    Path(slot_number): Path<i32>,
-    Json(req): Json<UpdateSlotRequest>,
+    ApiPath(slot_number): ApiPath<i32>,
+    ValidJson(req): ValidJson<UpdateSlotRequest>,
 ) -> Result<StatusCode, (StatusCode, Json<ApiError>)> {
-    if !(1..=10).contains(&slot_number) {
-        return Err(err(StatusCode::BAD_REQUEST, "Invalid slot number (1-10)"));
-    }
+    auth.require_scope(SCOPE_SLOTS_WRITE)?;
+    check_slot_number(&state.db, auth.user_id, slot_number).await?;
 
-    let blob = BASE64
-        .decode(&req.encrypted_blob)
-        .map_err(|_| err(StatusCode::BAD_REQUEST, "Invalid base64 blob"))?;
+    let blob = decode_blob(&req.encrypted_blob)
+        .map_err(|e| err(blob_error_status(e), e.message()))?;
 
-    let device_id = auth.device_id;
-
-    sqlx::query(
-        "INSERT INTO synced_slots (user_id, slot_number, encrypted_blob, updated_at, updated_by)
-         VALUES ($1, $2, $3, NOW(), $4)
-         ON CONFLICT (user_id, slot_number)
-         DO UPDATE SET encrypted_blob = $3, updated_at = NOW(), updated_by = $4",
+    let timestamp = chrono::Utc::now().timestamp_millis();
+    write_slot(
+        &state,
+        auth.user_id,
+        auth.device_id,
+        slot_number,
+        blob,
+        req.encrypted_blob,
+        timestamp,
     )
-    .bind(auth.user_id)
-    .bind(slot_number)
-    .bind(&blob)
-    .bind(device_id)
-    .execute(&state.db)
     .await
     .map_err(|_| err(StatusCode::INTERNAL_SERVER_ERROR, "Failed to update slot"))?;
 
+    Ok(StatusCode::OK)
+}
+
+/// Reject slot numbers above the account's slot count.
+async fn check_slot_number(
+    db: &Db,
+    user_id: Uuid,
+    slot_number: i32,
+) -> Result<(), (StatusCode, Json<ApiError>)> {
+    let slot_count = slot_count(db, user_id)
+        .await
+        .map_err(|_| err(StatusCode::INTERNAL_SERVER_ERROR, "Database error"))?;
+    if !(1..=slot_count).contains(&slot_number) {
+        return Err(err(
+            StatusCode::BAD_REQUEST,
+            &format!("Invalid slot number (1-{})", slot_count),
+        ));
+    }
+    Ok(())
+}
+
+/// Overwrite a slot, keeping its previous content in `slot_versions`, then
+/// notify webhooks and the user's other devices. Used by the REST routes and
+/// WebSocket `slot_update` alike; `device_id` is None for API keys.
+pub(crate) async fn write_slot(
+    state: &AppState,
+    user_id: Uuid,
+    device_id: Option<Uuid>,
+    slot_number: i32,
+    blob: Vec<u8>,
+    encoded_blob: String,
+    timestamp: i64,
+) -> Result<(), sqlx::Error> {
+    let now = db::now();
+
+    with_db!(&state.db, pool => {
+        let mut tx = pool.begin().await?;
+
+        sqlx::query(
+            "INSERT INTO slot_versions
+                 (id, user_id, slot_number, encrypted_blob, updated_at, updated_by, replaced_at)
+             SELECT $1, user_id, slot_number, encrypted_blob, updated_at, updated_by, $4
+             FROM synced_slots WHERE user_id = $2 AND slot_number = $3",
+        )
+        .bind(Uuid::new_v4())
+        .bind(user_id)
+        .bind(slot_number)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            "DELETE FROM slot_versions
+             WHERE user_id = $1 AND slot_number = $2 AND id NOT IN (
+                 SELECT id FROM slot_versions WHERE user_id = $1 AND slot_number = $2
+                 ORDER BY replaced_at DESC LIMIT $3
+             )",
+        )
+        .bind(user_id)
+        .bind(slot_number)
+        .bind(MAX_SLOT_VERSIONS)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            "INSERT INTO synced_slots (user_id, slot_number, encrypted_blob, updated_at, updated_by)
+             VALUES ($1, $2, $3, $4, $5)
+             ON CONFLICT (user_id, slot_number)
+             DO UPDATE SET encrypted_blob = $3, updated_at = $4, updated_by = $5",
+        )
+        .bind(user_id)
+        .bind(slot_number)
+        .bind(&blob)
+        .bind(now)
+        .bind(device_id)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+    });
+
+    state.webhooks.dispatch(
+        user_id,
+        EVENT_SLOT_UPDATED,
+        serde_json::json!({
+            "slot_number": slot_number,
+            "encrypted_blob": encoded_blob,
+            "updated_by": device_id,
+        }),
+    );
+
     if let Some(device_id) = device_id {
-        if let Some(tx) = state.user_channels.get(&auth.user_id) {
-            let msg = WsMessage::SlotUpdated {
-                slot_number,
-                encrypted_blob: req.encrypted_blob,
-                updated_by: device_id,
-                timestamp: chrono::Utc::now().timestamp_millis(),
-            };
-            let _ = tx.send((device_id, serde_json::to_string(&msg).unwrap()));
-        }
+        let msg = WsMessage::SlotUpdated {
+            slot_number,
+            encrypted_blob: encoded_blob,
+            updated_by: device_id,
+            timestamp,
+        };
+        state.user_channels.publish(user_id, device_id, &msg);
     }
 
+    Ok(())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/sync/slots/{number}/versions",
+    params(("number" = i32, Path, description = "Slot number")),
+    responses(
+        (status = 200, description = "Earlier contents of the slot, newest first", body = Vec<SlotVersionResponse>),
+    ),
+    security(("bearer" = [])),
+    tag = "Sync"
+)]
+pub(crate) async fn list_slot_versions(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    ApiPath(slot_number): ApiPath<i32>,
+) -> Result<Json<Vec<SlotVersionResponse>>, (StatusCode, Json<ApiError>)> {
+    auth.require_scope(SCOPE_SLOTS_READ)?;
+
+    let versions = with_db!(&state.db, pool => {
+        sqlx::query_as::<_, SlotVersion>(
+            "SELECT id, slot_number, encrypted_blob, updated_at, updated_by, replaced_at
+             FROM slot_versions WHERE user_id = $1 AND slot_number = $2
+             ORDER BY replaced_at DESC",
+        )
+        .bind(auth.user_id)
+        .bind(slot_number)
+        .fetch_all(pool)
+        .await
+    })
+    .map_err(|_| err(StatusCode::INTERNAL_SERVER_ERROR, "Database error"))?;
+
+    Ok(Json(versions.into_iter().map(SlotVersionResponse::from).collect()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/sync/slots/{number}/versions/{id}/restore",
+    params(
+        ("number" = i32, Path, description = "Slot number, up to the account's slot_count"),
+        ("id" = Uuid, Path, description = "Version ID"),
+    ),
+    responses(
+        (status = 200, description = "Slot set back to the version; its current content becomes a version"),
+        (status = 400, description = "Invalid slot number", body = ApiError),
+        (status = 404, description = "Version not found", body = ApiError),
+    ),
+    security(("bearer" = [])),
+    tag = "Sync"
+)]
+pub(crate) async fn restore_slot_version(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    ApiPath((slot_number, version_id)): ApiPath<(i32, Uuid)>,
+) -> Result<StatusCode, (StatusCode, Json<ApiError>)> {
+    auth.require_scope(SCOPE_SLOTS_WRITE)?;
+    check_slot_number(&state.db, auth.user_id, slot_number).await?;
+
+    let blob = with_db!(&state.db, pool => {
+        sqlx::query_scalar::<_, Vec<u8>>(
+            "SELECT encrypted_blob FROM slot_versions
+             WHERE id = $1 AND user_id = $2 AND slot_number = $3",
+        )
+        .bind(version_id)
+        .bind(auth.user_id)
+        .bind(slot_number)
+        .fetch_optional(pool)
+        .await
+    })
+    .map_err(|_| err(StatusCode::INTERNAL_SERVER_ERROR, "Database error"))?
+    .ok_or_else(|| err(StatusCode::NOT_FOUND, "Version not found"))?;
+
+    let encoded = BASE64.encode(&blob);
+    let timestamp = chrono::Utc::now().timestamp_millis();
+    write_slot(&state, auth.user_id, auth.device_id, slot_number, blob, encoded, timestamp)
+        .await
+        .map_err(|_| err(StatusCode::INTERNAL_SERVER_ERROR, "Failed to restore slot"))?;
+
     Ok(StatusCode::OK)
 }
 
+/// Number of permanent slots on the user's account.
+pub(crate) async fn slot_count(db: &Db, user_id: Uuid) -> Result<i32, sqlx::Error> {
+    with_db!(db, pool => {
+        sqlx::query_scalar("SELECT slot_count FROM users WHERE id = $1")
+            .bind(user_id)
+            .fetch_one(pool)
+            .await
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/sync/settings",
+    responses(
+        (status = 200, description = "Account-wide slot settings", body = SlotSettings),
+    ),
+    security(("bearer" = [])),
+    tag = "Sync"
+)]
+pub(crate) async fn get_slot_settings(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<Json<SlotSettings>, (StatusCode, Json<ApiError>)> {
+    auth.require_scope(SCOPE_SLOTS_READ)?;
+
+    let slot_count = slot_count(&state.db, auth.user_id)
+        .await
+        .map_err(|_| err(StatusCode::INTERNAL_SERVER_ERROR, "Database error"))?;
+
+    Ok(Json(SlotSettings { slot_count }))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/sync/settings",
+    request_body = SlotSettings,
+    responses(
+        (status = 200, description = "Settings updated", body = SlotSettings),
+        (status = 422, description = "slot_count out of range"),
+    ),
+    security(("bearer" = [])),
+    tag = "Sync"
+)]
+pub(crate) async fn update_slot_settings(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    ValidJson(req): ValidJson<SlotSettings>,
+) -> Result<Json<SlotSettings>, (StatusCode, Json<ApiError>)> {
+    auth.require_scope(SCOPE_SLOTS_WRITE)?;
+
+    with_db!(&state.db, pool => {
+        sqlx::query("UPDATE users SET slot_count = $1 WHERE id = $2")
+            .bind(req.slot_count)
+            .bind(auth.user_id)
+            .execute(pool)
+            .await
+            .map(|_| ())
+    })
+    .map_err(|_| err(StatusCode::INTERNAL_SERVER_ERROR, "Failed to update settings"))?;
+
+    // Nil origin: the device that made the change gets it too, like the others
+    let msg = WsMessage::SlotSettingsUpdated {
+        slot_count: req.slot_count,
+    };
+    state.user_channels.publish(auth.user_id, Uuid::nil(), &msg);
+
+    Ok(Json(req))
+}
+
 #[utoipa::path(
     get,
     path = "/api/sync/history",
     params(HistoryQuery),
     responses(
-        (status = 200, description = "Paginated encrypted history", body = Vec<HistoryResponse>),
+        (status = 200, description = "Paginated encrypted history, or a delta when `since` is set", body = HistoryListResponse),
+        (status = 400, description = "Invalid cursor", body = ApiError),
     ),
     security(("bearer" = [])),
     tag = "Sync"
@@ -142,36 +384,144 @@ pub(crate) async fn update_slot(
 pub(crate) async fn get_history(
     State(state): State<AppState>,
     auth: AuthUser,
-    Query(query): Query<HistoryQuery>,
-) -> Result<Json<Vec<HistoryResponse>>, (StatusCode, Json<ApiError>)> {
-    let limit = query.limit.unwrap_or(50).min(200);
-    let offset = query.offset.unwrap_or(0);
-
-    let items = sqlx::query_as::<_, SyncedHistoryItem>(
-        "SELECT id, user_id, encrypted_blob, content_hash, device_id, created_at
-         FROM synced_history WHERE user_id = $1
-         ORDER BY created_at DESC
-         LIMIT $2 OFFSET $3",
-    )
-    .bind(auth.user_id)
-    .bind(limit)
-    .bind(offset)
-    .fetch_all(&state.db)
-    .await
+    ApiQuery(query): ApiQuery<HistoryQuery>,
+) -> Result<Json<HistoryListResponse>, (StatusCode, Json<ApiError>)> {
+    auth.require_scope(SCOPE_HISTORY_READ)?;
+
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+
+    if let Some(since) = query.since.as_deref() {
+        let cursor = HistoryCursor::parse(since)
+            .ok_or_else(|| err(StatusCode::BAD_REQUEST, "Invalid since cursor"))?;
+        return get_history_delta(&state, auth.user_id, cursor, limit)
+            .await
+            .map(|delta| Json(HistoryListResponse::Delta(delta)));
+    }
+
+    let cursor = match query.cursor.as_deref() {
+        Some(c) => Some(
+            HistoryCursor::parse(c).ok_or_else(|| err(StatusCode::BAD_REQUEST, "Invalid cursor"))?,
+        ),
+        None => None,
+    };
+
+    // Keyset pagination: items strictly before the cursor in (created_at, id)
+    // order, so inserts between requests never shift later pages. Fetch one
+    // extra row to learn whether another page exists.
+    let mut items = with_db!(&state.db, pool => {
+        let query = match cursor {
+            Some(cursor) => sqlx::query_as::<_, SyncedHistoryItem>(
+                "SELECT id, user_id, encrypted_blob, content_hash, device_id, created_at, attachment_id
+                 FROM all_history
+                 WHERE user_id = $1 AND (created_at, id) < ($2, $3)
+                 ORDER BY created_at DESC, id DESC
+                 LIMIT $4",
+            )
+            .bind(auth.user_id)
+            .bind(cursor.created_at)
+            .bind(cursor.id),
+            None => sqlx::query_as::<_, SyncedHistoryItem>(
+                "SELECT id, user_id, encrypted_blob, content_hash, device_id, created_at, attachment_id
+                 FROM all_history WHERE user_id = $1
+                 ORDER BY created_at DESC, id DESC
+                 LIMIT $2",
+            )
+            .bind(auth.user_id),
+        };
+        query.bind(limit + 1).fetch_all(pool).await
+    })
     .map_err(|_| err(StatusCode::INTERNAL_SERVER_ERROR, "Database error"))?;
 
-    let response: Vec<HistoryResponse> = items
-        .into_iter()
-        .map(|i| HistoryResponse {
-            id: i.id,
-            encrypted_blob: BASE64.encode(&i.encrypted_blob),
-            content_hash: i.content_hash,
-            device_id: i.device_id,
-            created_at: i.created_at,
-        })
-        .collect();
+    let next_cursor = if items.len() as i64 > limit {
+        items.truncate(limit as usize);
+        items.last().map(|i| HistoryCursor::new(i.created_at, i.id).encode())
+    } else {
+        None
+    };
 
-    Ok(Json(response))
+    Ok(Json(HistoryListResponse::Page(HistoryPageResponse {
+        items: items.into_iter().map(HistoryResponse::from).collect(),
+        next_cursor,
+    })))
+}
+
+/// Items and tombstones strictly after `cursor`, oldest first. Both are walked
+/// in one (timestamp, id) order, items by created_at and tombstones by
+/// deleted_at, so the next cursor never passes a row that wasn't returned.
+/// Writes take their timestamp under the user's history clock (see
+/// `next_history_time`), so a row committed later never sorts before one
+/// already returned.
+async fn get_history_delta(
+    state: &AppState,
+    user_id: Uuid,
+    cursor: HistoryCursor,
+    limit: i64,
+) -> Result<HistoryDeltaResponse, (StatusCode, Json<ApiError>)> {
+    // Fetch one extra row of each to learn whether another page exists
+    let items = with_db!(&state.db, pool => {
+        sqlx::query_as::<_, SyncedHistoryItem>(
+            "SELECT id, user_id, encrypted_blob, content_hash, device_id, created_at, attachment_id
+             FROM all_history
+             WHERE user_id = $1 AND (created_at, id) > ($2, $3)
+             ORDER BY created_at ASC, id ASC
+             LIMIT $4",
+        )
+        .bind(user_id)
+        .bind(cursor.created_at)
+        .bind(cursor.id)
+        .bind(limit + 1)
+        .fetch_all(pool)
+        .await
+    })
+    .map_err(|_| err(StatusCode::INTERNAL_SERVER_ERROR, "Database error"))?;
+
+    let tombstones = with_db!(&state.db, pool => {
+        sqlx::query_as::<_, DeletedHistoryItem>(
+            "SELECT id, user_id, content_hash, deleted_by, deleted_at
+             FROM deleted_history
+             WHERE user_id = $1 AND (deleted_at, id) > ($2, $3)
+             ORDER BY deleted_at ASC, id ASC
+             LIMIT $4",
+        )
+        .bind(user_id)
+        .bind(cursor.created_at)
+        .bind(cursor.id)
+        .bind(limit + 1)
+        .fetch_all(pool)
+        .await
+    })
+    .map_err(|_| err(StatusCode::INTERNAL_SERVER_ERROR, "Database error"))?;
+
+    let mut items = items.into_iter().peekable();
+    let mut tombstones = tombstones.into_iter().peekable();
+    let mut page_items = Vec::new();
+    let mut page_tombstones = Vec::new();
+    let mut next = cursor;
+    while ((page_items.len() + page_tombstones.len()) as i64) < limit {
+        let take_item = match (items.peek(), tombstones.peek()) {
+            (Some(i), Some(t)) => (i.created_at, i.id) <= (t.deleted_at, t.id),
+            (Some(_), None) => true,
+            (None, Some(_)) => false,
+            (None, None) => break,
+        };
+        if take_item {
+            let Some(item) = items.next() else { break };
+            next = HistoryCursor::new(item.created_at, item.id);
+            page_items.push(item);
+        } else {
+            let Some(tombstone) = tombstones.next() else { break };
+            next = HistoryCursor::new(tombstone.deleted_at, tombstone.id);
+            page_tombstones.push(tombstone);
+        }
+    }
+    let has_more = items.peek().is_some() || tombstones.peek().is_some();
+
+    Ok(HistoryDeltaResponse {
+        items: page_items.into_iter().map(HistoryResponse::from).collect(),
+        deleted: page_tombstones.into_iter().map(HistoryTombstone::from).collect(),
+        next_cursor: next.encode(),
+        has_more,
+    })
 }
 
 #[utoipa::path(
@@ -180,7 +530,8 @@ pub(crate) async fn get_history(
     request_body = PushHistoryRequest,
     responses(
         (status = 201, description = "History item created"),
-        (status = 400, description = "Invalid blob"),
+        (status = 413, description = "Blob too large"),
+        (status = 422, description = "Malformed body, invalid blob, content hash or attachment"),
     ),
     security(("bearer" = [])),
     tag = "Sync"
@@ -188,45 +539,243 @@ pub(crate) async fn get_history(
 pub(crate) async fn push_history(
     State(state): State<AppState>,
     auth: AuthUser,
-    Json(req): Json<PushHistoryRequest>,
+    ValidJson(req): ValidJson<PushHistoryRequest>,
 ) -> Result<StatusCode, (StatusCode, Json<ApiError>)> {
-    let blob = BASE64
-        .decode(&req.encrypted_blob)
-        .map_err(|_| err(StatusCode::BAD_REQUEST, "Invalid base64 blob"))?;
+    auth.require_scope(SCOPE_HISTORY_WRITE)?;
 
-    let device_id = auth.device_id;
+    let blob = decode_blob(&req.encrypted_blob)
+        .map_err(|e| err(blob_error_status(e), e.message()))?;
 
-    let result = sqlx::query(
-        "INSERT INTO synced_history (id, user_id, encrypted_blob, content_hash, device_id, created_at)
-         VALUES ($1, $2, $3, $4, $5, NOW())
-         ON CONFLICT (user_id, content_hash) DO NOTHING",
-    )
-    .bind(req.id)
-    .bind(auth.user_id)
-    .bind(&blob)
-    .bind(&req.content_hash)
-    .bind(device_id)
-    .execute(&state.db)
-    .await
-    .map_err(|_| err(StatusCode::INTERNAL_SERVER_ERROR, "Failed to push history"))?;
-
-    if result.rows_affected() > 0 {
-        if let Some(device_id) = device_id {
-            if let Some(tx) = state.user_channels.get(&auth.user_id) {
-                let msg = WsMessage::HistoryNew {
-                    id: req.id,
-                    encrypted_blob: req.encrypted_blob,
-                    content_hash: req.content_hash,
-                    device_id,
-                };
-                let _ = tx.send((device_id, serde_json::to_string(&msg).unwrap()));
-            }
+    if let Some(attachment_id) = req.attachment_id {
+        let complete = attachments::is_complete(&state.db, auth.user_id, attachment_id)
+            .await
+            .map_err(|_| err(StatusCode::INTERNAL_SERVER_ERROR, "Database error"))?;
+        if !complete {
+            return Err(err(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "Attachment not found or not complete",
+            ));
         }
     }
 
+    insert_history_item(&state, auth.user_id, auth.device_id, req, blob)
+        .await
+        .map_err(|_| err(StatusCode::INTERNAL_SERVER_ERROR, "Failed to push history"))?;
+
     Ok(StatusCode::CREATED)
 }
 
+/// The timestamp for a history write, given the user's history clock. Always
+/// after the previous write, even if the system clock has stepped back.
+fn next_history_time(clock: Option<DateTime<Utc>>) -> DateTime<Utc> {
+    let now = db::now();
+    match clock {
+        Some(last) if last >= now => last + chrono::Duration::microseconds(1),
+        _ => now,
+    }
+}
+
+/// Store a history item and its search tokens, then notify webhooks and broadcast
+/// `history_new` to the user's other devices. Returns false if it was a duplicate.
+pub(crate) async fn insert_history_item(
+    state: &AppState,
+    user_id: Uuid,
+    device_id: Option<Uuid>,
+    req: PushHistoryRequest,
+    blob: Vec<u8>,
+) -> Result<bool, sqlx::Error> {
+    let inserted = with_db!(&state.db, pool => {
+        let mut tx = pool.begin().await?;
+
+        // Hold the user's history clock until commit, so history writes commit
+        // in timestamp order and a delta cursor can't pass one still in flight
+        let clock = sqlx::query_scalar(
+            "UPDATE users SET history_clock = history_clock WHERE id = $1
+             RETURNING history_clock",
+        )
+        .bind(user_id)
+        .fetch_one(&mut *tx)
+        .await?;
+        let now = next_history_time(clock);
+
+        // Items that were deleted elsewhere are not resurrected by a stale device,
+        // and archived items count as duplicates like hot ones
+        let inserted = sqlx::query(
+            "INSERT INTO synced_history
+                 (id, user_id, encrypted_blob, content_hash, device_id, created_at, attachment_id)
+             SELECT $1, $2, $3, $4, $5, $6, $7
+             WHERE NOT EXISTS (SELECT 1 FROM deleted_history WHERE user_id = $2 AND id = $1)
+               AND NOT EXISTS (
+                   SELECT 1 FROM archived_history WHERE user_id = $2 AND content_hash = $4
+               )
+             ON CONFLICT (user_id, content_hash) DO NOTHING",
+        )
+        .bind(req.id)
+        .bind(user_id)
+        .bind(&blob)
+        .bind(&req.content_hash)
+        .bind(device_id)
+        .bind(now)
+        .bind(req.attachment_id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected()
+            > 0;
+
+        if inserted {
+            for token in &req.search_tokens {
+                sqlx::query(
+                    "INSERT INTO history_search_tokens (history_id, user_id, token)
+                     VALUES ($1, $2, $3)
+                     ON CONFLICT (history_id, token) DO NOTHING",
+                )
+                .bind(req.id)
+                .bind(user_id)
+                .bind(token.to_ascii_lowercase())
+                .execute(&mut *tx)
+                .await?;
+            }
+        }
+
+        sqlx::query("UPDATE users SET history_clock = $2 WHERE id = $1")
+            .bind(user_id)
+            .bind(now)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        inserted
+    });
+
+    if !inserted {
+        return Ok(false);
+    }
+
+    state.webhooks.dispatch(
+        user_id,
+        EVENT_HISTORY_CREATED,
+        serde_json::json!({
+            "id": req.id,
+            "encrypted_blob": req.encrypted_blob,
+            "content_hash": req.content_hash,
+            "device_id": device_id,
+            "attachment_id": req.attachment_id,
+        }),
+    );
+    if let Some(device_id) = device_id {
+        let msg = WsMessage::HistoryNew {
+            id: req.id,
+            encrypted_blob: req.encrypted_blob,
+            content_hash: req.content_hash,
+            device_id,
+            attachment_id: req.attachment_id,
+        };
+        state.user_channels.publish(user_id, device_id, &msg);
+    }
+
+    Ok(true)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/sync/history/search",
+    params(HistorySearchQuery),
+    responses(
+        (status = 200, description = "Items containing every token, newest first", body = HistoryPageResponse),
+        (status = 400, description = "Missing, invalid or too many tokens, or invalid cursor", body = ApiError),
+    ),
+    security(("bearer" = [])),
+    tag = "Sync"
+)]
+pub(crate) async fn search_history(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    ApiQuery(query): ApiQuery<HistorySearchQuery>,
+) -> Result<Json<HistoryPageResponse>, (StatusCode, Json<ApiError>)> {
+    auth.require_scope(SCOPE_HISTORY_READ)?;
+
+    let mut tokens: Vec<String> = query
+        .tokens
+        .split(',')
+        .map(|t| t.trim().to_ascii_lowercase())
+        .filter(|t| !t.is_empty())
+        .collect();
+    tokens.sort();
+    tokens.dedup();
+    if tokens.is_empty() {
+        return Err(err(StatusCode::BAD_REQUEST, "tokens is required"));
+    }
+    if tokens.len() > MAX_QUERY_TOKENS {
+        return Err(err(StatusCode::BAD_REQUEST, "Too many search tokens"));
+    }
+    if !tokens.iter().all(|t| is_valid_search_token(t)) {
+        return Err(err(StatusCode::BAD_REQUEST, "Invalid search token"));
+    }
+
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let cursor = match query.cursor.as_deref() {
+        Some(c) => Some(
+            HistoryCursor::parse(c).ok_or_else(|| err(StatusCode::BAD_REQUEST, "Invalid cursor"))?,
+        ),
+        None => None,
+    };
+
+    // Items with a row for every token, paginated like get_history
+    let mut param = 1;
+    let mut next_param = || {
+        param += 1;
+        format!("${}", param)
+    };
+    let cursor_clause = match cursor {
+        Some(_) => format!("AND (created_at, id) < ({}, {})", next_param(), next_param()),
+        None => String::new(),
+    };
+    let token_params = tokens.iter().map(|_| next_param()).collect::<Vec<_>>().join(", ");
+    let sql = format!(
+        "SELECT id, user_id, encrypted_blob, content_hash, device_id, created_at, attachment_id
+         FROM all_history
+         WHERE user_id = $1 {} AND id IN (
+             SELECT history_id FROM history_search_tokens
+             WHERE user_id = $1 AND token IN ({})
+             GROUP BY history_id HAVING COUNT(*) = {}
+         )
+         ORDER BY created_at DESC, id DESC
+         LIMIT {}",
+        cursor_clause,
+        token_params,
+        next_param(),
+        next_param(),
+    );
+
+    let mut items = with_db!(&state.db, pool => {
+        let mut query = sqlx::query_as::<_, SyncedHistoryItem>(&sql).bind(auth.user_id);
+        if let Some(cursor) = cursor {
+            query = query.bind(cursor.created_at).bind(cursor.id);
+        }
+        for token in &tokens {
+            query = query.bind(token);
+        }
+        query
+            .bind(tokens.len() as i64)
+            .bind(limit + 1)
+            .fetch_all(pool)
+            .await
+    })
+    .map_err(|_| err(StatusCode::INTERNAL_SERVER_ERROR, "Database error"))?;
+
+    let next_cursor = if items.len() as i64 > limit {
+        items.truncate(limit as usize);
+        items.last().map(|i| HistoryCursor::new(i.created_at, i.id).encode())
+    } else {
+        None
+    };
+
+    Ok(Json(HistoryPageResponse {
+        items: items.into_iter().map(HistoryResponse::from).collect(),
+        next_cursor,
+    }))
+}
+
 #[utoipa::path(
     delete,
     path = "/api/sync/history/{id}",
@@ -241,18 +790,222 @@ pub(crate) async fn push_history(
 pub(crate) async fn delete_history(
     State(state): State<AppState>,
     auth: AuthUser,
-    Path(item_id): Path<Uuid>,
+    ApiPath(item_id): ApiPath<Uuid>,
 ) -> Result<StatusCode, (StatusCode, Json<ApiError>)> {
-    let result = sqlx::query("DELETE FROM synced_history WHERE id = $1 AND user_id = $2")
-        .bind(item_id)
-        .bind(auth.user_id)
-        .execute(&state.db)
+    auth.require_scope(SCOPE_HISTORY_WRITE)?;
+
+    let deleted = delete_history_item(&state, auth.user_id, auth.device_id, item_id)
         .await
         .map_err(|_| err(StatusCode::INTERNAL_SERVER_ERROR, "Database error"))?;
 
-    if result.rows_affected() == 0 {
+    if !deleted {
         return Err(err(StatusCode::NOT_FOUND, "History item not found"));
     }
 
     Ok(StatusCode::NO_CONTENT)
 }
+
+/// Delete a history item, record a tombstone for delta sync, and broadcast
+/// `history_deleted` to the user's other devices. Returns false if the item didn't exist.
+pub(crate) async fn delete_history_item(
+    state: &AppState,
+    user_id: Uuid,
+    device_id: Option<Uuid>,
+    item_id: Uuid,
+) -> Result<bool, sqlx::Error> {
+    let content_hash = with_db!(&state.db, pool => {
+        let mut tx = pool.begin().await?;
+
+        // Ordered against inserts by the history clock, as in insert_history_item
+        let clock = sqlx::query_scalar(
+            "UPDATE users SET history_clock = history_clock WHERE id = $1
+             RETURNING history_clock",
+        )
+        .bind(user_id)
+        .fetch_one(&mut *tx)
+        .await?;
+        let now = next_history_time(clock);
+
+        let mut deleted: Option<(String, Option<Uuid>)> = None;
+        for table in ["synced_history", "archived_history"] {
+            deleted = sqlx::query_as(&format!(
+                "DELETE FROM {} WHERE id = $1 AND user_id = $2
+                 RETURNING content_hash, attachment_id",
+                table
+            ))
+            .bind(item_id)
+            .bind(user_id)
+            .fetch_optional(&mut *tx)
+            .await?;
+            if deleted.is_some() {
+                break;
+            }
+        }
+
+        let Some((content_hash, attachment_id)) = deleted else {
+            return Ok(false);
+        };
+
+        sqlx::query("DELETE FROM history_search_tokens WHERE history_id = $1")
+            .bind(item_id)
+            .execute(&mut *tx)
+            .await?;
+
+        if let Some(attachment_id) = attachment_id {
+            sqlx::query("DELETE FROM attachments WHERE id = $1")
+                .bind(attachment_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        sqlx::query(
+            "INSERT INTO deleted_history (id, user_id, content_hash, deleted_by, deleted_at)
+             VALUES ($1, $2, $3, $4, $5)
+             ON CONFLICT (user_id, id) DO UPDATE SET deleted_at = $5",
+        )
+        .bind(item_id)
+        .bind(user_id)
+        .bind(&content_hash)
+        .bind(device_id)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("UPDATE users SET history_clock = $2 WHERE id = $1")
+            .bind(user_id)
+            .bind(now)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        content_hash
+    });
+
+    if let Some(device_id) = device_id {
+        let msg = WsMessage::HistoryDeleted {
+            id: item_id,
+            content_hash,
+            deleted_by: device_id,
+        };
+        state.user_channels.publish(user_id, device_id, &msg);
+    }
+
+    Ok(true)
+}
+
+/// Move each user's history beyond their newest `HOT_HISTORY_ITEMS` items to
+/// `archived_history`, oldest first. Returns how many items were moved.
+pub async fn archive_old_history(db: &Db) -> Result<u64, sqlx::Error> {
+    let users: Vec<Uuid> = with_db!(db, pool => {
+        sqlx::query_scalar(
+            "SELECT user_id FROM synced_history GROUP BY user_id HAVING COUNT(*) > $1",
+        )
+        .bind(HOT_HISTORY_ITEMS)
+        .fetch_all(pool)
+        .await
+    })?;
+
+    let mut moved = 0;
+    for user_id in users {
+        moved += with_db!(db, pool => {
+            let mut tx = pool.begin().await?;
+
+            // The oldest item that stays; everything before it goes
+            let (created_at, id): (chrono::DateTime<chrono::Utc>, Uuid) = sqlx::query_as(
+                "SELECT created_at, id FROM synced_history WHERE user_id = $1
+                 ORDER BY created_at DESC, id DESC
+                 LIMIT 1 OFFSET $2",
+            )
+            .bind(user_id)
+            .bind(HOT_HISTORY_ITEMS - 1)
+            .fetch_one(&mut *tx)
+            .await?;
+
+            sqlx::query(
+                "INSERT INTO archived_history
+                     (id, user_id, encrypted_blob, content_hash, device_id, created_at, attachment_id)
+                 SELECT id, user_id, encrypted_blob, content_hash, device_id, created_at, attachment_id
+                 FROM synced_history
+                 WHERE user_id = $1 AND (created_at, id) < ($2, $3)",
+            )
+            .bind(user_id)
+            .bind(created_at)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+
+            let removed = sqlx::query(
+                "DELETE FROM synced_history WHERE user_id = $1 AND (created_at, id) < ($2, $3)",
+            )
+            .bind(user_id)
+            .bind(created_at)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+
+            tx.commit().await?;
+            removed
+        });
+    }
+    Ok(moved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn push(content_hash: &str) -> PushHistoryRequest {
+        PushHistoryRequest {
+            id: Uuid::new_v4(),
+            encrypted_blob: BASE64.encode(content_hash),
+            content_hash: content_hash.to_string(),
+            attachment_id: None,
+            search_tokens: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_history_writes_follow_the_history_clock() {
+        let (state, user_id, device_id) = AppState::for_test().await;
+
+        // A clock ahead of the system time, as after the system clock stepped back
+        let ahead = db::now() + chrono::Duration::hours(1);
+        with_db!(&state.db, pool => {
+            sqlx::query("UPDATE users SET history_clock = $2 WHERE id = $1")
+                .bind(user_id)
+                .bind(ahead)
+                .execute(pool)
+                .await
+                .unwrap();
+        });
+
+        let first = push("first");
+        let first_id = first.id;
+        insert_history_item(&state, user_id, Some(device_id), first, Vec::new())
+            .await
+            .unwrap();
+        assert!(delete_history_item(&state, user_id, Some(device_id), first_id).await.unwrap());
+
+        let delta = get_history_delta(&state, user_id, HistoryCursor::new(ahead, Uuid::max()), 10)
+            .await
+            .map_err(|(status, _)| status)
+            .unwrap();
+        assert_eq!(delta.items.len(), 0);
+        assert_eq!(delta.deleted.len(), 1);
+        assert!(!delta.has_more);
+
+        let second = push("second");
+        let second_id = second.id;
+        insert_history_item(&state, user_id, Some(device_id), second, Vec::new())
+            .await
+            .unwrap();
+        let next = HistoryCursor::parse(&delta.next_cursor).unwrap();
+        let delta = get_history_delta(&state, user_id, next, 10)
+            .await
+            .map_err(|(status, _)| status)
+            .unwrap();
+        assert_eq!(delta.items.len(), 1);
+        assert_eq!(delta.items[0].id, second_id);
+    }
+}
@@ -0,0 +1,585 @@
+use axum::{
+    extract::State,
+    http::StatusCode,
+    routing::{delete, get, put},
+    Json, Router,
+};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use uuid::Uuid;
+
+use crate::errors::{err, ApiError};
+use crate::db::{self, with_db};
+use crate::middleware::extract::{ApiJson, ApiPath};
+use crate::middleware::auth::AuthUser;
+use crate::middleware::validate::{blob_error_status, ValidJson};
+use crate::models::api_key::{SCOPE_SLOTS_READ, SCOPE_SLOTS_WRITE};
+use crate::models::sync::{decode_blob, UpdateSlotRequest, WsMessage};
+use crate::models::team::{
+    AddMemberRequest, CreateTeamRequest, SetTeamKeyRequest, ShareTeamKeyRequest, Team,
+    TeamMember, TeamMemberResponse, TeamResponse, TeamSlot, TeamSlotResponse, ROLE_MEMBER,
+    ROLE_OWNER, TEAM_SLOTS,
+};
+use crate::AppState;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/teams", get(list_teams).post(create_team))
+        .route("/teams/{id}", delete(delete_team))
+        .route("/teams/{id}/members", get(list_members).post(add_member))
+        .route("/teams/{id}/members/{user_id}", delete(remove_member))
+        .route("/teams/{id}/members/{user_id}/key", put(share_team_key))
+        .route("/teams/{id}/key", put(set_team_key))
+        .route("/teams/{id}/slots", get(get_team_slots))
+        .route("/teams/{id}/slots/{number}", put(update_team_slot))
+}
+
+/// The caller's role in the team. Non-members get a 404 so team ids can't be probed.
+async fn require_member(
+    state: &AppState,
+    team_id: Uuid,
+    user_id: Uuid,
+) -> Result<String, (StatusCode, Json<ApiError>)> {
+    let role: Option<String> = with_db!(&state.db, pool => {
+        sqlx::query_scalar("SELECT role FROM team_members WHERE team_id = $1 AND user_id = $2")
+            .bind(team_id)
+            .bind(user_id)
+            .fetch_optional(pool)
+            .await
+    })
+    .map_err(|_| err(StatusCode::INTERNAL_SERVER_ERROR, "Database error"))?;
+
+    role.ok_or_else(|| err(StatusCode::NOT_FOUND, "Team not found"))
+}
+
+async fn require_owner(
+    state: &AppState,
+    team_id: Uuid,
+    user_id: Uuid,
+) -> Result<(), (StatusCode, Json<ApiError>)> {
+    if require_member(state, team_id, user_id).await? != ROLE_OWNER {
+        return Err(err(StatusCode::FORBIDDEN, "Only the team owner can do this"));
+    }
+    Ok(())
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/teams",
+    request_body = CreateTeamRequest,
+    responses(
+        (status = 201, description = "Team created with the caller as owner", body = TeamResponse),
+        (status = 400, description = "Invalid name", body = ApiError),
+    ),
+    security(("bearer" = [])),
+    tag = "Teams"
+)]
+pub(crate) async fn create_team(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    ApiJson(req): ApiJson<CreateTeamRequest>,
+) -> Result<(StatusCode, Json<TeamResponse>), (StatusCode, Json<ApiError>)> {
+    auth.require_session()?;
+
+    let name = req.name.trim().to_string();
+    if name.is_empty() || name.len() > 100 {
+        return Err(err(StatusCode::BAD_REQUEST, "Name must be 1-100 characters"));
+    }
+
+    let team = Team {
+        id: Uuid::new_v4(),
+        name,
+        created_at: db::now(),
+        role: ROLE_OWNER.to_string(),
+        encrypted_team_key: req.encrypted_team_key.filter(|k| !k.is_empty()),
+        invite_key: None,
+    };
+
+    with_db!(&state.db, pool => {
+        async {
+            let mut tx = pool.begin().await?;
+            sqlx::query("INSERT INTO teams (id, name, created_by, created_at) VALUES ($1, $2, $3, $4)")
+                .bind(team.id)
+                .bind(&team.name)
+                .bind(auth.user_id)
+                .bind(team.created_at)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query(
+                "INSERT INTO team_members (team_id, user_id, role, encrypted_team_key, joined_at)
+                 VALUES ($1, $2, $3, $4, $5)",
+            )
+            .bind(team.id)
+            .bind(auth.user_id)
+            .bind(&team.role)
+            .bind(&team.encrypted_team_key)
+            .bind(team.created_at)
+            .execute(&mut *tx)
+            .await?;
+            tx.commit().await
+        }
+        .await
+    })
+    .map_err(|_| err(StatusCode::INTERNAL_SERVER_ERROR, "Failed to create team"))?;
+
+    Ok((StatusCode::CREATED, Json(TeamResponse::from(team))))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/teams",
+    responses(
+        (status = 200, description = "Teams the current user belongs to", body = Vec<TeamResponse>),
+    ),
+    security(("bearer" = [])),
+    tag = "Teams"
+)]
+pub(crate) async fn list_teams(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<Json<Vec<TeamResponse>>, (StatusCode, Json<ApiError>)> {
+    auth.require_session()?;
+
+    let teams = with_db!(&state.db, pool => {
+        sqlx::query_as::<_, Team>(
+            "SELECT t.id, t.name, t.created_at, m.role, m.encrypted_team_key, m.invite_key
+             FROM teams t JOIN team_members m ON m.team_id = t.id
+             WHERE m.user_id = $1 ORDER BY t.created_at",
+        )
+        .bind(auth.user_id)
+        .fetch_all(pool)
+        .await
+    })
+    .map_err(|_| err(StatusCode::INTERNAL_SERVER_ERROR, "Database error"))?;
+
+    Ok(Json(teams.into_iter().map(TeamResponse::from).collect()))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/teams/{id}",
+    params(("id" = Uuid, Path, description = "Team UUID")),
+    responses(
+        (status = 204, description = "Team and its shared slots deleted"),
+        (status = 403, description = "Caller is not the owner", body = ApiError),
+        (status = 404, description = "Team not found", body = ApiError),
+    ),
+    security(("bearer" = [])),
+    tag = "Teams"
+)]
+pub(crate) async fn delete_team(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    ApiPath(team_id): ApiPath<Uuid>,
+) -> Result<StatusCode, (StatusCode, Json<ApiError>)> {
+    auth.require_session()?;
+    require_owner(&state, team_id, auth.user_id).await?;
+
+    with_db!(&state.db, pool => {
+        sqlx::query("DELETE FROM teams WHERE id = $1")
+            .bind(team_id)
+            .execute(pool)
+            .await
+            .map(|_| ())
+    })
+    .map_err(|_| err(StatusCode::INTERNAL_SERVER_ERROR, "Database error"))?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/teams/{id}/members",
+    params(("id" = Uuid, Path, description = "Team UUID")),
+    responses(
+        (status = 200, description = "Team members", body = Vec<TeamMemberResponse>),
+        (status = 404, description = "Team not found", body = ApiError),
+    ),
+    security(("bearer" = [])),
+    tag = "Teams"
+)]
+pub(crate) async fn list_members(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    ApiPath(team_id): ApiPath<Uuid>,
+) -> Result<Json<Vec<TeamMemberResponse>>, (StatusCode, Json<ApiError>)> {
+    auth.require_session()?;
+    require_member(&state, team_id, auth.user_id).await?;
+
+    let members = with_db!(&state.db, pool => {
+        sqlx::query_as::<_, TeamMember>(
+            "SELECT m.user_id, u.email, m.role, m.encrypted_team_key, m.joined_at
+             FROM team_members m JOIN users u ON u.id = m.user_id
+             WHERE m.team_id = $1 ORDER BY m.joined_at",
+        )
+        .bind(team_id)
+        .fetch_all(pool)
+        .await
+    })
+    .map_err(|_| err(StatusCode::INTERNAL_SERVER_ERROR, "Database error"))?;
+
+    Ok(Json(members.into_iter().map(TeamMemberResponse::from).collect()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/teams/{id}/members",
+    params(("id" = Uuid, Path, description = "Team UUID")),
+    request_body = AddMemberRequest,
+    responses(
+        (status = 201, description = "Member added; they still need the team key from an existing member", body = TeamMemberResponse),
+        (status = 403, description = "Caller is not the owner", body = ApiError),
+        (status = 404, description = "Team or user not found", body = ApiError),
+        (status = 409, description = "Already a member", body = ApiError),
+    ),
+    security(("bearer" = [])),
+    tag = "Teams"
+)]
+pub(crate) async fn add_member(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    ApiPath(team_id): ApiPath<Uuid>,
+    ApiJson(req): ApiJson<AddMemberRequest>,
+) -> Result<(StatusCode, Json<TeamMemberResponse>), (StatusCode, Json<ApiError>)> {
+    auth.require_session()?;
+    require_owner(&state, team_id, auth.user_id).await?;
+
+    let email = req.email.trim().to_lowercase();
+    let user_id: Option<Uuid> = with_db!(&state.db, pool => {
+        sqlx::query_scalar("SELECT id FROM users WHERE email = $1")
+            .bind(&email)
+            .fetch_optional(pool)
+            .await
+    })
+    .map_err(|_| err(StatusCode::INTERNAL_SERVER_ERROR, "Database error"))?;
+    let user_id = user_id.ok_or_else(|| err(StatusCode::NOT_FOUND, "User not found"))?;
+
+    let member = TeamMember {
+        user_id,
+        email,
+        role: ROLE_MEMBER.to_string(),
+        encrypted_team_key: None,
+        joined_at: db::now(),
+    };
+
+    with_db!(&state.db, pool => {
+        sqlx::query(
+            "INSERT INTO team_members (team_id, user_id, role, joined_at) VALUES ($1, $2, $3, $4)",
+        )
+        .bind(team_id)
+        .bind(member.user_id)
+        .bind(&member.role)
+        .bind(member.joined_at)
+        .execute(pool)
+        .await
+        .map(|_| ())
+    })
+    .map_err(|e| {
+        if e.as_database_error().is_some_and(|d| d.is_unique_violation()) {
+            err(StatusCode::CONFLICT, "Already a member")
+        } else {
+            err(StatusCode::INTERNAL_SERVER_ERROR, "Failed to add member")
+        }
+    })?;
+
+    Ok((StatusCode::CREATED, Json(TeamMemberResponse::from(member))))
+}
+
+/// Owners can remove any member; members can remove themselves (leave). The
+/// remaining members should rotate the team key afterwards.
+#[utoipa::path(
+    delete,
+    path = "/api/teams/{id}/members/{user_id}",
+    params(
+        ("id" = Uuid, Path, description = "Team UUID"),
+        ("user_id" = Uuid, Path, description = "Member user UUID"),
+    ),
+    responses(
+        (status = 204, description = "Member removed"),
+        (status = 400, description = "The owner cannot leave; delete the team instead", body = ApiError),
+        (status = 403, description = "Only the owner can remove other members", body = ApiError),
+        (status = 404, description = "Team or member not found", body = ApiError),
+    ),
+    security(("bearer" = [])),
+    tag = "Teams"
+)]
+pub(crate) async fn remove_member(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    ApiPath((team_id, member_id)): ApiPath<(Uuid, Uuid)>,
+) -> Result<StatusCode, (StatusCode, Json<ApiError>)> {
+    auth.require_session()?;
+    let role = require_member(&state, team_id, auth.user_id).await?;
+
+    if member_id == auth.user_id {
+        if role == ROLE_OWNER {
+            return Err(err(
+                StatusCode::BAD_REQUEST,
+                "The owner cannot leave; delete the team instead",
+            ));
+        }
+    } else if role != ROLE_OWNER {
+        return Err(err(StatusCode::FORBIDDEN, "Only the team owner can do this"));
+    }
+
+    let removed = with_db!(&state.db, pool => {
+        sqlx::query("DELETE FROM team_members WHERE team_id = $1 AND user_id = $2")
+            .bind(team_id)
+            .bind(member_id)
+            .execute(pool)
+            .await
+            .map(|r| r.rows_affected())
+    })
+    .map_err(|_| err(StatusCode::INTERNAL_SERVER_ERROR, "Database error"))?;
+
+    if removed == 0 {
+        return Err(err(StatusCode::NOT_FOUND, "Member not found"));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/teams/{id}/key",
+    params(("id" = Uuid, Path, description = "Team UUID")),
+    request_body = SetTeamKeyRequest,
+    responses(
+        (status = 204, description = "Your wrapped copy of the team key was stored"),
+        (status = 404, description = "Team not found", body = ApiError),
+    ),
+    security(("bearer" = [])),
+    tag = "Teams"
+)]
+pub(crate) async fn set_team_key(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    ApiPath(team_id): ApiPath<Uuid>,
+    ApiJson(req): ApiJson<SetTeamKeyRequest>,
+) -> Result<StatusCode, (StatusCode, Json<ApiError>)> {
+    auth.require_session()?;
+
+    if req.encrypted_team_key.is_empty() {
+        return Err(err(StatusCode::BAD_REQUEST, "encrypted_team_key is required"));
+    }
+
+    let updated = with_db!(&state.db, pool => {
+        sqlx::query(
+            "UPDATE team_members SET encrypted_team_key = $1, invite_key = NULL
+             WHERE team_id = $2 AND user_id = $3",
+        )
+        .bind(&req.encrypted_team_key)
+        .bind(team_id)
+        .bind(auth.user_id)
+        .execute(pool)
+        .await
+        .map(|r| r.rows_affected())
+    })
+    .map_err(|_| err(StatusCode::INTERNAL_SERVER_ERROR, "Database error"))?;
+
+    if updated == 0 {
+        return Err(err(StatusCode::NOT_FOUND, "Team not found"));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Hand the team key to a member who doesn't have it yet. Only that member
+/// can read it back, from `GET /api/teams`, and only with the code it was
+/// wrapped with.
+#[utoipa::path(
+    put,
+    path = "/api/teams/{id}/members/{user_id}/key",
+    params(
+        ("id" = Uuid, Path, description = "Team UUID"),
+        ("user_id" = Uuid, Path, description = "Member user UUID"),
+    ),
+    request_body = ShareTeamKeyRequest,
+    responses(
+        (status = 204, description = "The wrapped key was stored for the member"),
+        (status = 404, description = "Team or member not found", body = ApiError),
+    ),
+    security(("bearer" = [])),
+    tag = "Teams"
+)]
+pub(crate) async fn share_team_key(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    ApiPath((team_id, member_id)): ApiPath<(Uuid, Uuid)>,
+    ApiJson(req): ApiJson<ShareTeamKeyRequest>,
+) -> Result<StatusCode, (StatusCode, Json<ApiError>)> {
+    auth.require_session()?;
+    require_member(&state, team_id, auth.user_id).await?;
+
+    if req.invite_key.is_empty() {
+        return Err(err(StatusCode::BAD_REQUEST, "invite_key is required"));
+    }
+
+    let updated = with_db!(&state.db, pool => {
+        sqlx::query("UPDATE team_members SET invite_key = $1 WHERE team_id = $2 AND user_id = $3")
+            .bind(&req.invite_key)
+            .bind(team_id)
+            .bind(member_id)
+            .execute(pool)
+            .await
+            .map(|r| r.rows_affected())
+    })
+    .map_err(|_| err(StatusCode::INTERNAL_SERVER_ERROR, "Database error"))?;
+
+    if updated == 0 {
+        return Err(err(StatusCode::NOT_FOUND, "Member not found"));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/teams/{id}/slots",
+    params(("id" = Uuid, Path, description = "Team UUID")),
+    responses(
+        (status = 200, description = "Shared slots, encrypted with the team key", body = Vec<TeamSlotResponse>),
+        (status = 404, description = "Team not found", body = ApiError),
+    ),
+    security(("bearer" = [])),
+    tag = "Teams"
+)]
+pub(crate) async fn get_team_slots(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    ApiPath(team_id): ApiPath<Uuid>,
+) -> Result<Json<Vec<TeamSlotResponse>>, (StatusCode, Json<ApiError>)> {
+    auth.require_scope(SCOPE_SLOTS_READ)?;
+    require_member(&state, team_id, auth.user_id).await?;
+
+    let slots = with_db!(&state.db, pool => {
+        sqlx::query_as::<_, TeamSlot>(
+            "SELECT team_id, slot_number, encrypted_blob, updated_at, updated_by
+             FROM team_slots WHERE team_id = $1 ORDER BY slot_number",
+        )
+        .bind(team_id)
+        .fetch_all(pool)
+        .await
+    })
+    .map_err(|_| err(StatusCode::INTERNAL_SERVER_ERROR, "Database error"))?;
+
+    let response = slots
+        .into_iter()
+        .map(|s| TeamSlotResponse {
+            slot_number: s.slot_number,
+            encrypted_blob: BASE64.encode(&s.encrypted_blob),
+            updated_at: s.updated_at,
+            updated_by: s.updated_by,
+        })
+        .collect();
+
+    Ok(Json(response))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/teams/{id}/slots/{number}",
+    params(
+        ("id" = Uuid, Path, description = "Team UUID"),
+        ("number" = i32, Path, description = "Shared slot number (8-10)"),
+    ),
+    request_body = UpdateSlotRequest,
+    responses(
+        (status = 200, description = "Slot updated and broadcast to all members"),
+        (status = 400, description = "Invalid slot number", body = ApiError),
+        (status = 413, description = "Blob too large", body = ApiError),
+        (status = 422, description = "Malformed body or invalid blob", body = ApiError),
+        (status = 404, description = "Team not found", body = ApiError),
+    ),
+    security(("bearer" = [])),
+    tag = "Teams"
+)]
+pub(crate) async fn update_team_slot(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    ApiPath((team_id, slot_number)): ApiPath<(Uuid, i32)>,
+    ValidJson(req): ValidJson<UpdateSlotRequest>,
+) -> Result<StatusCode, (StatusCode, Json<ApiError>)> {
+    auth.require_scope(SCOPE_SLOTS_WRITE)?;
+
+    if !TEAM_SLOTS.contains(&slot_number) {
+        return Err(err(StatusCode::BAD_REQUEST, "Invalid team slot number (8-10)"));
+    }
+
+    let blob = decode_blob(&req.encrypted_blob)
+        .map_err(|e| err(blob_error_status(e), e.message()))?;
+
+    let saved = save_team_slot(
+        &state,
+        auth.user_id,
+        auth.device_id,
+        team_id,
+        slot_number,
+        &blob,
+        req.encrypted_blob,
+        chrono::Utc::now().timestamp_millis(),
+    )
+    .await
+    .map_err(|_| err(StatusCode::INTERNAL_SERVER_ERROR, "Failed to update slot"))?;
+
+    if !saved {
+        return Err(err(StatusCode::NOT_FOUND, "Team not found"));
+    }
+
+    Ok(StatusCode::OK)
+}
+
+/// Store a shared slot and broadcast `team_slot_updated` to every member's
+/// devices (except the sender). Returns false if `user_id` is not a member.
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn save_team_slot(
+    state: &AppState,
+    user_id: Uuid,
+    device_id: Option<Uuid>,
+    team_id: Uuid,
+    slot_number: i32,
+    blob: &[u8],
+    encrypted_blob: String,
+    timestamp: i64,
+) -> Result<bool, sqlx::Error> {
+    let members: Vec<Uuid> = with_db!(&state.db, pool => {
+        sqlx::query_scalar("SELECT user_id FROM team_members WHERE team_id = $1")
+            .bind(team_id)
+            .fetch_all(pool)
+            .await
+    })?;
+    if !members.contains(&user_id) {
+        return Ok(false);
+    }
+
+    with_db!(&state.db, pool => {
+        sqlx::query(
+            "INSERT INTO team_slots (team_id, slot_number, encrypted_blob, updated_at, updated_by)
+             VALUES ($1, $2, $3, $4, $5)
+             ON CONFLICT (team_id, slot_number)
+             DO UPDATE SET encrypted_blob = $3, updated_at = $4, updated_by = $5",
+        )
+        .bind(team_id)
+        .bind(slot_number)
+        .bind(blob)
+        .bind(db::now())
+        .bind(device_id)
+        .execute(pool)
+        .await
+        .map(|_| ())
+    })?;
+
+    if let Some(device_id) = device_id {
+        let msg = WsMessage::TeamSlotUpdated {
+            team_id,
+            slot_number,
+            encrypted_blob,
+            updated_by: device_id,
+            timestamp,
+        };
+        for member in members {
+            state.user_channels.publish(member, device_id, &msg);
+        }
+    }
+
+    Ok(true)
+}
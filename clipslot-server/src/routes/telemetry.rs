@@ -0,0 +1,53 @@
+use axum::{extract::State, http::StatusCode, routing::post, Json, Router};
+use uuid::Uuid;
+
+use crate::db::{self, with_db};
+use crate::errors::{err, ApiError};
+use crate::middleware::validate::ValidJson;
+use crate::models::telemetry::TelemetryReport;
+use crate::AppState;
+
+pub fn router() -> Router<AppState> {
+    Router::new().route("/api/telemetry", post(report))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/telemetry",
+    request_body = TelemetryReport,
+    responses(
+        (status = 204, description = "Counts stored"),
+        (status = 422, description = "Invalid report", body = ApiError),
+    ),
+    tag = "Telemetry"
+)]
+pub(crate) async fn report(
+    State(state): State<AppState>,
+    ValidJson(req): ValidJson<TelemetryReport>,
+) -> Result<StatusCode, (StatusCode, Json<ApiError>)> {
+    let now = db::now();
+    for day in &req.days {
+        with_db!(&state.db, pool => {
+            sqlx::query(
+                "INSERT INTO telemetry_reports
+                     (id, day, app_version, platform, captures, slot_saves, slot_pastes,
+                      sync_errors, received_at)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)",
+            )
+            .bind(Uuid::new_v4())
+            .bind(&day.day)
+            .bind(&req.app_version)
+            .bind(&req.platform)
+            .bind(day.captures)
+            .bind(day.slot_saves)
+            .bind(day.slot_pastes)
+            .bind(day.sync_errors)
+            .bind(now)
+            .execute(pool)
+            .await
+            .map(|_| ())
+        })
+        .map_err(|_| err(StatusCode::INTERNAL_SERVER_ERROR, "Failed to store report"))?;
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,175 @@
+use axum::{
+    extract::State,
+    http::StatusCode,
+    routing::{delete, get},
+    Json, Router,
+};
+use rand::{distributions::Alphanumeric, Rng as _};
+use uuid::Uuid;
+
+use crate::errors::{err, ApiError};
+use crate::db::{self, with_db};
+use crate::middleware::extract::{ApiJson, ApiPath};
+use crate::middleware::auth::AuthUser;
+use crate::models::webhook::{
+    CreateWebhookRequest, CreateWebhookResponse, Webhook, WebhookResponse, ALL_EVENTS,
+};
+use crate::AppState;
+
+/// Webhooks a single user may register.
+const MAX_WEBHOOKS_PER_USER: i64 = 10;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/webhooks", get(list_webhooks).post(create_webhook))
+        .route("/webhooks/{id}", delete(delete_webhook))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/webhooks",
+    request_body = CreateWebhookRequest,
+    responses(
+        (status = 201, description = "Webhook registered; the signing secret is only shown once", body = CreateWebhookResponse),
+        (status = 400, description = "Invalid or non-public URL, or invalid events", body = ApiError),
+    ),
+    security(("bearer" = [])),
+    tag = "Webhooks"
+)]
+pub(crate) async fn create_webhook(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    ApiJson(req): ApiJson<CreateWebhookRequest>,
+) -> Result<(StatusCode, Json<CreateWebhookResponse>), (StatusCode, Json<ApiError>)> {
+    auth.require_session()?;
+
+    let url = req.url.trim().to_string();
+    crate::webhooks::resolve_target(&url)
+        .await
+        .map_err(|e| err(StatusCode::BAD_REQUEST, e))?;
+    if req.events.is_empty() {
+        return Err(err(StatusCode::BAD_REQUEST, "At least one event is required"));
+    }
+    if let Some(unknown) = req.events.iter().find(|e| !ALL_EVENTS.contains(&e.as_str())) {
+        return Err(err(StatusCode::BAD_REQUEST, &format!("Unknown event: {}", unknown)));
+    }
+
+    let count: i64 = with_db!(&state.db, pool => {
+        sqlx::query_scalar("SELECT COUNT(*) FROM webhooks WHERE user_id = $1")
+            .bind(auth.user_id)
+            .fetch_one(pool)
+            .await
+    })
+    .map_err(|_| err(StatusCode::INTERNAL_SERVER_ERROR, "Database error"))?;
+    if count >= MAX_WEBHOOKS_PER_USER {
+        return Err(err(StatusCode::BAD_REQUEST, "Webhook limit reached"));
+    }
+
+    let mut events = req.events.clone();
+    events.sort();
+    events.dedup();
+
+    let secret: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(32)
+        .map(char::from)
+        .collect();
+
+    let webhook = Webhook {
+        id: Uuid::new_v4(),
+        user_id: auth.user_id,
+        url,
+        secret: format!("whsec_{}", secret),
+        events: events.join(","),
+        created_at: db::now(),
+    };
+
+    with_db!(&state.db, pool => {
+        sqlx::query(
+            "INSERT INTO webhooks (id, user_id, url, secret, events, created_at)
+             VALUES ($1, $2, $3, $4, $5, $6)",
+        )
+        .bind(webhook.id)
+        .bind(webhook.user_id)
+        .bind(&webhook.url)
+        .bind(&webhook.secret)
+        .bind(&webhook.events)
+        .bind(webhook.created_at)
+        .execute(pool)
+        .await
+        .map(|_| ())
+    })
+    .map_err(|_| err(StatusCode::INTERNAL_SERVER_ERROR, "Failed to create webhook"))?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(CreateWebhookResponse {
+            secret: webhook.secret.clone(),
+            webhook: WebhookResponse::from(webhook),
+        }),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/webhooks",
+    responses(
+        (status = 200, description = "Registered webhooks", body = Vec<WebhookResponse>),
+    ),
+    security(("bearer" = [])),
+    tag = "Webhooks"
+)]
+pub(crate) async fn list_webhooks(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<Json<Vec<WebhookResponse>>, (StatusCode, Json<ApiError>)> {
+    auth.require_session()?;
+
+    let webhooks = with_db!(&state.db, pool => {
+        sqlx::query_as::<_, Webhook>(
+            "SELECT id, user_id, url, secret, events, created_at
+             FROM webhooks WHERE user_id = $1 ORDER BY created_at DESC",
+        )
+        .bind(auth.user_id)
+        .fetch_all(pool)
+        .await
+    })
+    .map_err(|_| err(StatusCode::INTERNAL_SERVER_ERROR, "Database error"))?;
+
+    Ok(Json(webhooks.into_iter().map(WebhookResponse::from).collect()))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/webhooks/{id}",
+    params(("id" = Uuid, Path, description = "Webhook UUID")),
+    responses(
+        (status = 204, description = "Webhook deleted"),
+        (status = 404, description = "Webhook not found", body = ApiError),
+    ),
+    security(("bearer" = [])),
+    tag = "Webhooks"
+)]
+pub(crate) async fn delete_webhook(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    ApiPath(webhook_id): ApiPath<Uuid>,
+) -> Result<StatusCode, (StatusCode, Json<ApiError>)> {
+    auth.require_session()?;
+
+    let deleted = with_db!(&state.db, pool => {
+        sqlx::query("DELETE FROM webhooks WHERE id = $1 AND user_id = $2")
+            .bind(webhook_id)
+            .bind(auth.user_id)
+            .execute(pool)
+            .await
+            .map(|r| r.rows_affected())
+    })
+    .map_err(|_| err(StatusCode::INTERNAL_SERVER_ERROR, "Database error"))?;
+
+    if deleted == 0 {
+        return Err(err(StatusCode::NOT_FOUND, "Webhook not found"));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -1,25 +1,123 @@
 use axum::{
     extract::{
-        ws::{Message, WebSocket, WebSocketUpgrade},
-        Query, State,
+        ws::{close_code, CloseFrame, Message, WebSocket, WebSocketUpgrade},
+        ConnectInfo, State,
     },
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
     response::IntoResponse,
     routing::get,
     Router,
 };
-use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use std::collections::HashSet;
+use std::io::{Read, Write};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression};
 use futures::{SinkExt, StreamExt};
 use tokio::sync::{broadcast, mpsc};
 use uuid::Uuid;
 
+use crate::db::{self, with_db};
+use crate::login_guard::client_ip;
+use crate::middleware::extract::ApiQuery;
 use crate::middleware::auth::validate_token;
-use crate::models::sync::WsMessage;
+use crate::models::sync::{
+    check_search_tokens, decode_blob, is_valid_content_hash, BlobError, PushHistoryRequest,
+    WsMessage, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION,
+};
+use crate::models::team::TEAM_SLOTS;
+use crate::routes::attachments;
+use crate::relay::RelayEvent;
+use crate::ws_limits::{ConnectionSlot, LimitExceeded};
 use crate::AppState;
 
+/// Largest text message handled; enough for a maximum-size blob once base64-encoded.
+/// Larger messages are answered with a `payload_too_large` error.
+const MAX_MESSAGE_BYTES: usize = 2 * 1024 * 1024;
+/// Hard transport limit: anything larger closes the connection outright.
+const MAX_FRAME_BYTES: usize = 2 * MAX_MESSAGE_BYTES;
+/// Invalid messages tolerated on one connection before it is closed.
+const MAX_VIOLATIONS: u32 = 5;
+/// Capability in `hello` for messages sent as binary frames holding the
+/// raw-deflated (RFC 1951) JSON. Both sides only send them to a peer that
+/// advertised it; the base64 blobs that make up most traffic shrink by about
+/// a quarter. (Neither axum nor tungstenite implement permessage-deflate.)
+const DEFLATE_CAPABILITY: &str = "deflate";
+/// Messages shorter than this go out as text; deflate wouldn't save much.
+const DEFLATE_MIN_BYTES: usize = 1024;
+/// Message types this server accepts from clients, advertised in `hello`.
+const ACCEPTED_TYPES: &[&str] = &[
+    "hello",
+    "slot_update",
+    "history_push",
+    "history_delete",
+    "team_slot_update",
+    "send_to_device",
+    "clipboard_request",
+    "clipboard_response",
+    "set_monitoring",
+];
+
+/// Per-connection state shared by its send and receive tasks.
+#[derive(Default)]
+struct Connection {
+    /// Message types the client accepts, from its `hello`. None until then, and
+    /// for clients that predate it, which get everything.
+    client_accepts: Mutex<Option<HashSet<String>>>,
+    /// Set by the receive task when it drops the connection; sent as the close frame
+    close_reason: Mutex<Option<CloseFrame>>,
+}
+
+impl Connection {
+    fn accepts(&self, kind: &str) -> bool {
+        self.client_accepts
+            .lock()
+            .unwrap()
+            .as_ref()
+            .is_none_or(|types| types.contains(kind))
+    }
+
+    /// Whether a broadcast goes out on this connection of `device_id`.
+    fn wants(&self, event: &RelayEvent, device_id: Uuid) -> bool {
+        event.origin_device != device_id
+            && event.target_device.is_none_or(|target| target == device_id)
+            && self.accepts(&event.kind)
+    }
+
+    /// `payload` as a frame for this connection: deflated if the client
+    /// takes that and it's worth it, text otherwise.
+    fn frame(&self, payload: String) -> Message {
+        let deflate = self
+            .client_accepts
+            .lock()
+            .unwrap()
+            .as_ref()
+            .is_some_and(|caps| caps.contains(DEFLATE_CAPABILITY));
+        if deflate && payload.len() >= DEFLATE_MIN_BYTES {
+            let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+            if encoder.write_all(payload.as_bytes()).is_ok() {
+                if let Ok(data) = encoder.finish() {
+                    return Message::Binary(data.into());
+                }
+            }
+        }
+        Message::Text(payload.into())
+    }
+
+    fn close(&self, code: u16, reason: &'static str) {
+        *self.close_reason.lock().unwrap() = Some(CloseFrame {
+            code,
+            reason: reason.into(),
+        });
+    }
+}
+
 #[derive(serde::Deserialize)]
 struct WsQuery {
     token: String,
+    /// Last broadcast seq the client received; missed broadcasts are replayed
+    resume_from: Option<u64>,
 }
 
 pub fn router() -> Router<AppState> {
@@ -29,64 +127,197 @@ pub fn router() -> Router<AppState> {
 async fn ws_handler(
     ws: WebSocketUpgrade,
     State(state): State<AppState>,
-    Query(query): Query<WsQuery>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    ApiQuery(query): ApiQuery<WsQuery>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let claims =
-        validate_token(&query.token, &state.jwt_secret).map_err(|_| StatusCode::UNAUTHORIZED)?;
+    let claims = validate_token(&query.token, &state.jwt_keys)
+        .await
+        .map_err(|_| StatusCode::UNAUTHORIZED)?;
 
     let user_id = claims.sub;
     let device_id = claims.device_id.ok_or(StatusCode::UNAUTHORIZED)?;
+    let exists = state
+        .known_devices
+        .exists(device_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if !exists {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let ip = client_ip(&headers, peer, state.trust_forwarded_for);
+    let slot = state.ws_limits.acquire(user_id, ip);
 
-    Ok(ws.on_upgrade(move |socket| handle_socket(socket, state, user_id, device_id)))
+    let resume_from = query.resume_from;
+    Ok(ws
+        .max_message_size(MAX_FRAME_BYTES)
+        .max_frame_size(MAX_FRAME_BYTES)
+        .on_upgrade(move |socket| async move {
+            match slot {
+                Ok(slot) => {
+                    handle_socket(socket, state, user_id, device_id, resume_from, slot).await
+                }
+                Err(limit) => {
+                    tracing::warn!(
+                        "Refused WebSocket: {} (user={}, ip={})",
+                        limit.message(),
+                        user_id,
+                        ip
+                    );
+                    refuse_socket(socket, limit).await
+                }
+            }
+        }))
 }
 
-fn get_or_create_channel(
-    state: &AppState,
-    user_id: Uuid,
-) -> broadcast::Sender<(Uuid, String)> {
-    state
-        .user_channels
-        .entry(user_id)
-        .or_insert_with(|| broadcast::channel(100).0)
-        .clone()
+/// Tell a client over its limit why, then close. Done after the upgrade so the
+/// client gets a structured error rather than a failed handshake.
+async fn refuse_socket(mut socket: WebSocket, limit: LimitExceeded) {
+    let msg = WsMessage::Error {
+        code: limit.code().to_string(),
+        message: limit.message().to_string(),
+    };
+    let _ = socket
+        .send(Message::Text(serde_json::to_string(&msg).unwrap().into()))
+        .await;
+    let frame = CloseFrame {
+        code: close_code::POLICY,
+        reason: limit.message().into(),
+    };
+    let _ = socket.send(Message::Close(Some(frame))).await;
 }
 
-async fn handle_socket(socket: WebSocket, state: AppState, user_id: Uuid, device_id: Uuid) {
+async fn handle_socket(
+    socket: WebSocket,
+    state: AppState,
+    user_id: Uuid,
+    device_id: Uuid,
+    resume_from: Option<u64>,
+    slot: ConnectionSlot,
+) {
+    // Keeps graceful shutdown waiting until this connection has wound down
+    let _guard = state.shutdown.track();
     let (mut sender, mut receiver) = socket.split();
 
-    let tx = get_or_create_channel(&state, user_id);
-    let mut rx = tx.subscribe();
+    // Subscribe before reading the replay buffer so nothing published in between
+    // is lost; live events already covered by the replay are skipped below.
+    let mut rx = state.user_channels.subscribe(user_id);
+    let replay = resume_from.map(|seq| state.user_channels.replay_since(user_id, seq));
 
     // Direct channel for messages targeted at this specific connection (errors, acks)
     let (direct_tx, mut direct_rx) = mpsc::channel::<String>(32);
+    let conn = Arc::new(Connection::default());
 
     // Update device last_seen
-    let _ = sqlx::query("UPDATE devices SET last_seen = NOW() WHERE id = $1")
-        .bind(device_id)
-        .execute(&state.db)
-        .await;
+    let _ = with_db!(&state.db, pool => {
+        sqlx::query("UPDATE devices SET last_seen = $1 WHERE id = $2")
+            .bind(db::now())
+            .bind(device_id)
+            .execute(pool)
+            .await
+            .map(|_| ())
+    });
 
     tracing::info!("WebSocket connected: user={}, device={}", user_id, device_id);
 
     // Task: forward broadcast messages and direct messages to this client
+    let shutdown = state.shutdown.clone();
+    let user_channels = state.user_channels.clone();
+    let send_conn = conn.clone();
     let send_task = tokio::spawn(async move {
+        let hello = WsMessage::Hello {
+            protocol_version: PROTOCOL_VERSION,
+            min_protocol_version: Some(MIN_PROTOCOL_VERSION),
+            capabilities: ACCEPTED_TYPES
+                .iter()
+                .chain([&DEFLATE_CAPABILITY])
+                .map(|t| t.to_string())
+                .collect(),
+        };
+        let payload = serde_json::to_string(&hello).unwrap();
+        if sender.send(Message::Text(payload.into())).await.is_err() {
+            return;
+        }
+
+        let mut replayed_up_to = 0;
+        match replay {
+            Some(Some(events)) => {
+                for event in events {
+                    replayed_up_to = event.seq;
+                    if !send_conn.wants(&event, device_id) {
+                        continue;
+                    }
+                    if sender.send(send_conn.frame(event.payload)).await.is_err() {
+                        return;
+                    }
+                }
+            }
+            Some(None) => {
+                let msg = WsMessage::ResyncRequired {
+                    reason: "Missed events are no longer available".to_string(),
+                };
+                let payload = serde_json::to_string(&msg).unwrap();
+                if sender.send(Message::Text(payload.into())).await.is_err() {
+                    return;
+                }
+            }
+            None => {}
+        }
+
         loop {
             tokio::select! {
                 result = rx.recv() => {
                     match result {
-                        Ok((origin_device, payload)) => {
-                            if origin_device == device_id {
+                        Ok(event) => {
+                            if event.seq <= replayed_up_to || !send_conn.wants(&event, device_id) {
                                 continue;
                             }
+                            if sender.send(send_conn.frame(event.payload)).await.is_err() {
+                                break;
+                            }
+                        }
+                        Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                            // Too slow to keep up: the skipped broadcasts are gone, so
+                            // the client has to fetch current state itself
+                            tracing::warn!(
+                                "WebSocket lagged, {} broadcasts dropped: user={}, device={}",
+                                skipped,
+                                user_id,
+                                device_id
+                            );
+                            user_channels.record_lag(skipped);
+                            let msg = WsMessage::ResyncRequired {
+                                reason: format!(
+                                    "Connection fell behind; {} updates missed",
+                                    skipped
+                                ),
+                            };
+                            let payload = serde_json::to_string(&msg).unwrap();
                             if sender.send(Message::Text(payload.into())).await.is_err() {
                                 break;
                             }
                         }
-                        Err(_) => break,
+                        Err(broadcast::error::RecvError::Closed) => break,
                     }
                 }
-                Some(payload) = direct_rx.recv() => {
-                    if sender.send(Message::Text(payload.into())).await.is_err() {
+                payload = direct_rx.recv() => {
+                    let Some(payload) = payload else {
+                        // The receive task has finished and its last replies have been
+                        // flushed above. Tell the client why if we're closing on it; on
+                        // shutdown, ask it to reconnect.
+                        let frame = send_conn.close_reason.lock().unwrap().take().or_else(|| {
+                            shutdown.is_triggered().then(|| CloseFrame {
+                                code: close_code::RESTART,
+                                reason: "Server restarting, please reconnect".into(),
+                            })
+                        });
+                        if let Some(frame) = frame {
+                            let _ = sender.send(Message::Close(Some(frame))).await;
+                        }
+                        break;
+                    };
+                    if sender.send(send_conn.frame(payload)).await.is_err() {
                         break;
                     }
                 }
@@ -96,23 +327,75 @@ async fn handle_socket(socket: WebSocket, state: AppState, user_id: Uuid, device
 
     // Task: process incoming messages from this client
     let state_clone = state.clone();
-    let tx_clone = tx.clone();
     let recv_task = tokio::spawn(async move {
-        while let Some(Ok(msg)) = receiver.next().await {
-            match msg {
-                Message::Text(text) => {
-                    handle_ws_message(&state_clone, user_id, device_id, &text, &tx_clone, &direct_tx).await;
+        let mut violations = 0;
+        let removed = state_clone.known_devices.removed(device_id);
+        tokio::pin!(removed);
+        loop {
+            // Stop reading on shutdown, but only between messages so a write in
+            // progress is never cut off
+            let msg = tokio::select! {
+                msg = receiver.next() => msg,
+                _ = state_clone.shutdown.triggered() => break,
+                _ = &mut removed => {
+                    tracing::info!("Closing WebSocket of removed device {}", device_id);
+                    conn.close(close_code::POLICY, "Device removed from the account");
+                    break;
+                }
+                _ = slot.evicted() => {
+                    tracing::info!(
+                        "Closing oldest WebSocket for user {} to stay within the limit",
+                        user_id
+                    );
+                    conn.close(
+                        close_code::POLICY,
+                        "Too many connections; replaced by a newer one",
+                    );
+                    break;
                 }
+            };
+            let Some(Ok(msg)) = msg else {
+                break;
+            };
+            let text = match msg {
+                Message::Text(text) => Some(text.as_str().to_owned()),
+                // Deflated text, from clients that saw DEFLATE_CAPABILITY
+                Message::Binary(data) => inflate(&data),
                 Message::Close(_) => break,
-                _ => {}
+                _ => continue,
+            };
+            let valid = match text {
+                Some(text) => {
+                    handle_ws_message(&state_clone, user_id, device_id, &text, &direct_tx, &conn)
+                        .await
+                }
+                None => {
+                    send_error(&direct_tx, "invalid_message", "Invalid deflated message").await;
+                    false
+                }
+            };
+            if conn.close_reason.lock().unwrap().is_some() {
+                break;
+            }
+            if !valid {
+                violations += 1;
+                if violations >= MAX_VIOLATIONS {
+                    tracing::warn!(
+                        "Closing WebSocket after {} invalid messages: user={}, device={}",
+                        violations,
+                        user_id,
+                        device_id
+                    );
+                    conn.close(close_code::POLICY, "Too many invalid messages");
+                    break;
+                }
             }
         }
     });
 
-    tokio::select! {
-        _ = send_task => {},
-        _ = recv_task => {},
-    }
+    // The send task ends once the receive task drops direct_tx, so waiting for
+    // both lets in-flight messages finish and their acks go out.
+    let _ = tokio::join!(send_task, recv_task);
 
     tracing::info!(
         "WebSocket disconnected: user={}, device={}",
@@ -121,132 +404,446 @@ async fn handle_socket(socket: WebSocket, state: AppState, user_id: Uuid, device
     );
 }
 
+/// Handle one client message. Returns false if it was malformed or failed
+/// validation, which counts towards closing the connection.
 async fn handle_ws_message(
     state: &AppState,
     user_id: Uuid,
     device_id: Uuid,
     text: &str,
-    tx: &broadcast::Sender<(Uuid, String)>,
     direct_tx: &mpsc::Sender<String>,
-) {
+    conn: &Connection,
+) -> bool {
+    if text.len() > MAX_MESSAGE_BYTES {
+        send_error(direct_tx, "payload_too_large", "Message too large").await;
+        return false;
+    }
+
     let msg: WsMessage = match serde_json::from_str(text) {
         Ok(m) => m,
         Err(e) => {
-            let err_msg = WsMessage::Error {
-                message: format!("Invalid message: {}", e),
-            };
-            let _ = direct_tx.send(serde_json::to_string(&err_msg).unwrap()).await;
-            return;
+            // A newer client may send types this server doesn't know yet; tell it
+            // (and nack it, so it stops retrying) rather than treating it as abuse
+            let value = serde_json::from_str::<serde_json::Value>(text).unwrap_or_default();
+            if let Some(kind) = value["type"].as_str().filter(|k| !ACCEPTED_TYPES.contains(k)) {
+                let msg_id = value["msg_id"].as_str().and_then(|id| id.parse().ok());
+                let message = format!("Unsupported message type: {}", kind);
+                reject(direct_tx, msg_id, "unsupported_message_type", &message).await;
+                return true;
+            }
+            send_error(direct_tx, "invalid_message", &format!("Invalid message: {}", e)).await;
+            return false;
         }
     };
 
     match msg {
+        WsMessage::Hello {
+            protocol_version,
+            capabilities,
+            ..
+        } => {
+            if protocol_version < MIN_PROTOCOL_VERSION {
+                let message = format!(
+                    "Protocol version {} is no longer supported; please update (minimum {})",
+                    protocol_version, MIN_PROTOCOL_VERSION
+                );
+                send_error(direct_tx, "unsupported_protocol_version", &message).await;
+                conn.close(close_code::PROTOCOL, "Unsupported protocol version");
+                return true;
+            }
+            tracing::debug!(
+                "WebSocket hello: user={}, device={}, protocol={}",
+                user_id,
+                device_id,
+                protocol_version
+            );
+            *conn.client_accepts.lock().unwrap() = Some(capabilities.into_iter().collect());
+        }
+
         WsMessage::SlotUpdate {
             slot_number,
             encrypted_blob,
             timestamp,
+            msg_id,
         } => {
-            if !(1..=10).contains(&slot_number) {
-                let err_msg = WsMessage::Error {
-                    message: "Invalid slot number".to_string(),
-                };
-                let _ = direct_tx.send(serde_json::to_string(&err_msg).unwrap()).await;
-                return;
+            let slot_count = match crate::routes::sync::slot_count(&state.db, user_id).await {
+                Ok(n) => n,
+                Err(e) => {
+                    tracing::error!("Failed to load slot count: {}", e);
+                    let message = format!("Failed to save slot update: {}", e);
+                    send_error(direct_tx, "save_failed", &message).await;
+                    return true;
+                }
+            };
+            if !(1..=slot_count).contains(&slot_number) {
+                reject(direct_tx, msg_id, "invalid_slot", "Invalid slot number").await;
+                return false;
             }
 
-            let blob = match BASE64.decode(&encrypted_blob) {
+            let blob = match decode_blob(&encrypted_blob) {
                 Ok(b) => b,
-                Err(_) => {
-                    let err_msg = WsMessage::Error {
-                        message: "Invalid base64 blob".to_string(),
-                    };
-                    let _ = direct_tx.send(serde_json::to_string(&err_msg).unwrap()).await;
-                    return;
+                Err(e) => {
+                    reject(direct_tx, msg_id, blob_error_code(e), e.message()).await;
+                    return false;
                 }
             };
 
-            let result = sqlx::query(
-                "INSERT INTO synced_slots (user_id, slot_number, encrypted_blob, updated_at, updated_by)
-                 VALUES ($1, $2, $3, NOW(), $4)
-                 ON CONFLICT (user_id, slot_number)
-                 DO UPDATE SET encrypted_blob = $3, updated_at = NOW(), updated_by = $4",
+            let result = crate::routes::sync::write_slot(
+                state,
+                user_id,
+                Some(device_id),
+                slot_number,
+                blob,
+                encrypted_blob,
+                timestamp,
             )
-            .bind(user_id)
-            .bind(slot_number)
-            .bind(&blob)
-            .bind(device_id)
-            .execute(&state.db)
             .await;
 
+            // No ack on failure: the client retries until the write is persisted
             if let Err(e) = result {
                 tracing::error!("Failed to save slot update: {}", e);
-                let err_msg = WsMessage::Error {
-                    message: format!("Failed to save slot update: {}", e),
-                };
-                let _ = direct_tx.send(serde_json::to_string(&err_msg).unwrap()).await;
-                return;
+                let message = format!("Failed to save slot update: {}", e);
+                send_error(direct_tx, "save_failed", &message).await;
+                return true;
+            }
+
+            ack(direct_tx, msg_id).await;
+        }
+
+        WsMessage::TeamSlotUpdate {
+            team_id,
+            slot_number,
+            encrypted_blob,
+            timestamp,
+            msg_id,
+        } => {
+            if !TEAM_SLOTS.contains(&slot_number) {
+                reject(direct_tx, msg_id, "invalid_slot", "Invalid team slot number").await;
+                return false;
             }
 
-            let response = WsMessage::SlotUpdated {
+            let blob = match decode_blob(&encrypted_blob) {
+                Ok(b) => b,
+                Err(e) => {
+                    reject(direct_tx, msg_id, blob_error_code(e), e.message()).await;
+                    return false;
+                }
+            };
+
+            // Broadcast to all members happens inside save_team_slot
+            let result = crate::routes::teams::save_team_slot(
+                state,
+                user_id,
+                Some(device_id),
+                team_id,
                 slot_number,
+                &blob,
                 encrypted_blob,
-                updated_by: device_id,
                 timestamp,
-            };
-            let _ = tx.send((device_id, serde_json::to_string(&response).unwrap()));
+            )
+            .await;
+
+            match result {
+                Ok(true) => ack(direct_tx, msg_id).await,
+                Ok(false) => {
+                    reject(direct_tx, msg_id, "not_a_member", "Not a member of this team").await
+                }
+                Err(e) => {
+                    tracing::error!("Failed to save team slot update: {}", e);
+                    let message = format!("Failed to save team slot update: {}", e);
+                    send_error(direct_tx, "save_failed", &message).await;
+                }
+            }
         }
 
         WsMessage::HistoryPush {
             id,
             encrypted_blob,
             content_hash,
+            attachment_id,
+            search_tokens,
+            msg_id,
         } => {
-            let blob = match BASE64.decode(&encrypted_blob) {
+            if !is_valid_content_hash(&content_hash) {
+                let message = "content_hash must be a hex SHA-256 digest";
+                reject(direct_tx, msg_id, "invalid_content_hash", message).await;
+                return false;
+            }
+            if let Err(message) = check_search_tokens(&search_tokens) {
+                reject(direct_tx, msg_id, "invalid_search_tokens", message).await;
+                return false;
+            }
+
+            let blob = match decode_blob(&encrypted_blob) {
                 Ok(b) => b,
-                Err(_) => {
-                    let err_msg = WsMessage::Error {
-                        message: "Invalid base64 blob".to_string(),
-                    };
-                    let _ = direct_tx.send(serde_json::to_string(&err_msg).unwrap()).await;
-                    return;
+                Err(e) => {
+                    reject(direct_tx, msg_id, blob_error_code(e), e.message()).await;
+                    return false;
                 }
             };
 
-            let result = sqlx::query(
-                "INSERT INTO synced_history (id, user_id, encrypted_blob, content_hash, device_id, created_at)
-                 VALUES ($1, $2, $3, $4, $5, NOW())
-                 ON CONFLICT (user_id, content_hash) DO NOTHING",
-            )
-            .bind(id)
-            .bind(user_id)
-            .bind(&blob)
-            .bind(&content_hash)
-            .bind(device_id)
-            .execute(&state.db)
-            .await;
-
-            match result {
-                Ok(r) if r.rows_affected() > 0 => {
-                    let response = WsMessage::HistoryNew {
-                        id,
-                        encrypted_blob,
-                        content_hash,
-                        device_id,
-                    };
-                    let _ = tx.send((device_id, serde_json::to_string(&response).unwrap()));
+            if let Some(attachment_id) = attachment_id {
+                match attachments::is_complete(&state.db, user_id, attachment_id).await {
+                    Ok(true) => {}
+                    Ok(false) => {
+                        let message = "Attachment not found or not complete";
+                        reject(direct_tx, msg_id, "invalid_attachment", message).await;
+                        return false;
+                    }
+                    Err(e) => {
+                        tracing::error!("Failed to look up attachment: {}", e);
+                        let message = format!("Failed to save history: {}", e);
+                        send_error(direct_tx, "save_failed", &message).await;
+                        return true;
+                    }
                 }
+            }
+
+            let req = PushHistoryRequest {
+                id,
+                encrypted_blob,
+                content_hash,
+                attachment_id,
+                search_tokens,
+            };
+            // Broadcast happens inside insert_history_item
+            match crate::routes::sync::insert_history_item(
+                state,
+                user_id,
+                Some(device_id),
+                req,
+                blob,
+            )
+            .await
+            {
+                // Dedup hits are acked too — the item is already persisted
+                Ok(_) => ack(direct_tx, msg_id).await,
                 Err(e) => {
                     tracing::error!("Failed to save history push: {}", e);
-                    let err_msg = WsMessage::Error {
-                        message: format!("Failed to save history: {}", e),
-                    };
-                    let _ = direct_tx.send(serde_json::to_string(&err_msg).unwrap()).await;
+                    let message = format!("Failed to save history: {}", e);
+                    send_error(direct_tx, "save_failed", &message).await;
                 }
-                _ => {} // Dedup — item already exists
             }
         }
 
+        WsMessage::HistoryDelete { id, msg_id } => {
+            // Broadcast happens inside delete_history_item; unknown ids are a no-op
+            match crate::routes::sync::delete_history_item(state, user_id, Some(device_id), id)
+                .await
+            {
+                Ok(_) => ack(direct_tx, msg_id).await,
+                Err(e) => {
+                    tracing::error!("Failed to delete history item: {}", e);
+                    let message = format!("Failed to delete history item: {}", e);
+                    send_error(direct_tx, "save_failed", &message).await;
+                }
+            }
+        }
+
+        WsMessage::SendToDevice {
+            target_device,
+            encrypted_blob,
+            msg_id,
+        } => {
+            if let Err(e) = decode_blob(&encrypted_blob) {
+                reject(direct_tx, msg_id, blob_error_code(e), e.message()).await;
+                return false;
+            }
+            let msg = WsMessage::SentToDevice {
+                encrypted_blob,
+                from_device: device_id,
+                timestamp: chrono::Utc::now().timestamp_millis(),
+            };
+            return route_to_device(state, direct_tx, user_id, device_id, target_device, msg_id, msg)
+                .await;
+        }
+
+        WsMessage::ClipboardRequest {
+            target_device,
+            request_id,
+            msg_id,
+        } => {
+            let msg = WsMessage::ClipboardRequested {
+                from_device: device_id,
+                request_id,
+            };
+            return route_to_device(state, direct_tx, user_id, device_id, target_device, msg_id, msg)
+                .await;
+        }
+
+        WsMessage::ClipboardResponse {
+            target_device,
+            request_id,
+            encrypted_blob,
+            msg_id,
+        } => {
+            if let Some(Err(e)) = encrypted_blob.as_deref().map(decode_blob) {
+                reject(direct_tx, msg_id, blob_error_code(e), e.message()).await;
+                return false;
+            }
+            let msg = WsMessage::ClipboardResponded {
+                from_device: device_id,
+                request_id,
+                encrypted_blob,
+            };
+            return route_to_device(state, direct_tx, user_id, device_id, target_device, msg_id, msg)
+                .await;
+        }
+
+        WsMessage::SetMonitoring { paused, msg_id } => {
+            ack(direct_tx, msg_id).await;
+            let msg = WsMessage::MonitoringChanged {
+                paused,
+                changed_by: device_id,
+            };
+            state.user_channels.publish(user_id, device_id, &msg);
+        }
+
         // Ignore server-to-client message types
         _ => {}
     }
+    true
+}
+
+/// Check that `target_device` is another device on the account, then ack and
+/// deliver `msg` to it only. Returns false if it targets the sending device.
+async fn route_to_device(
+    state: &AppState,
+    direct_tx: &mpsc::Sender<String>,
+    user_id: Uuid,
+    device_id: Uuid,
+    target_device: Uuid,
+    msg_id: Option<Uuid>,
+    msg: WsMessage,
+) -> bool {
+    if target_device == device_id {
+        reject(direct_tx, msg_id, "invalid_target", "Can't send to the same device").await;
+        return false;
+    }
+
+    let found: Result<i64, sqlx::Error> = with_db!(&state.db, pool => {
+        sqlx::query_scalar("SELECT COUNT(*) FROM devices WHERE id = $1 AND user_id = $2")
+            .bind(target_device)
+            .bind(user_id)
+            .fetch_one(pool)
+            .await
+    });
+    match found {
+        Ok(0) => {
+            let message = "No such device on this account";
+            reject(direct_tx, msg_id, "unknown_device", message).await;
+        }
+        Ok(_) => {
+            ack(direct_tx, msg_id).await;
+            state
+                .user_channels
+                .send_to_device(user_id, device_id, target_device, &msg);
+        }
+        Err(e) => {
+            tracing::error!("Failed to look up target device: {}", e);
+            let message = format!("Failed to send to device: {}", e);
+            send_error(direct_tx, "save_failed", &message).await;
+        }
+    }
+    true
+}
+
+fn blob_error_code(error: BlobError) -> &'static str {
+    match error {
+        BlobError::TooLarge => "payload_too_large",
+        BlobError::InvalidBase64 => "invalid_blob",
+    }
+}
+
+/// Acknowledge a persisted client message (no-op for clients that don't send msg_id).
+async fn ack(direct_tx: &mpsc::Sender<String>, msg_id: Option<Uuid>) {
+    if let Some(msg_id) = msg_id {
+        let msg = WsMessage::Ack {
+            msg_id,
+            ok: true,
+            error: None,
+        };
+        let _ = direct_tx.send(serde_json::to_string(&msg).unwrap()).await;
+    }
+}
+
+/// Permanently reject a client message. Clients that sent a msg_id get a
+/// negative ack so they stop retrying; older clients get a plain error.
+async fn reject(
+    direct_tx: &mpsc::Sender<String>,
+    msg_id: Option<Uuid>,
+    code: &str,
+    message: &str,
+) {
+    match msg_id {
+        Some(msg_id) => {
+            let msg = WsMessage::Ack {
+                msg_id,
+                ok: false,
+                error: Some(message.to_string()),
+            };
+            let _ = direct_tx.send(serde_json::to_string(&msg).unwrap()).await;
+        }
+        None => send_error(direct_tx, code, message).await,
+    }
+}
+
+/// The text in a deflated message; None if it isn't valid deflate or UTF-8.
+/// Reads one byte past the message limit, so oversized messages are still
+/// answered with `payload_too_large` rather than inflated in full.
+fn inflate(data: &[u8]) -> Option<String> {
+    let mut text = String::new();
+    DeflateDecoder::new(data)
+        .take(MAX_MESSAGE_BYTES as u64 + 1)
+        .read_to_string(&mut text)
+        .ok()?;
+    Some(text)
+}
+
+async fn send_error(direct_tx: &mpsc::Sender<String>, code: &str, message: &str) {
+    let msg = WsMessage::Error {
+        code: code.to_string(),
+        message: message.to_string(),
+    };
+    let _ = direct_tx.send(serde_json::to_string(&msg).unwrap()).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn version_count(state: &AppState, user_id: Uuid) -> i64 {
+        with_db!(&state.db, pool => {
+            sqlx::query_scalar("SELECT COUNT(*) FROM slot_versions WHERE user_id = $1")
+                .bind(user_id)
+                .fetch_one(pool)
+                .await
+                .unwrap()
+        })
+    }
+
+    #[tokio::test]
+    async fn test_slot_update_keeps_previous_version() {
+        let (state, user_id, device_id) = AppState::for_test().await;
+        let (direct_tx, mut direct_rx) = mpsc::channel(16);
+        let conn = Connection::default();
+
+        for content in ["first", "second"] {
+            let msg = WsMessage::SlotUpdate {
+                slot_number: 1,
+                encrypted_blob: base64::Engine::encode(
+                    &base64::engine::general_purpose::STANDARD,
+                    content,
+                ),
+                timestamp: 0,
+                msg_id: Some(Uuid::new_v4()),
+            };
+            let text = serde_json::to_string(&msg).unwrap();
+            assert!(handle_ws_message(&state, user_id, device_id, &text, &direct_tx, &conn).await);
+            let reply = direct_rx.recv().await.unwrap();
+            assert!(reply.contains("\"ok\":true"), "{}", reply);
+        }
+
+        assert_eq!(version_count(&state, user_id).await, 1);
+    }
 }
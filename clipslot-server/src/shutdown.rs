@@ -0,0 +1,86 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::watch;
+
+/// Coordinates graceful shutdown: signals long-lived WebSocket handlers to
+/// wind down and tracks how many are still running.
+#[derive(Clone)]
+pub struct Shutdown {
+    triggered: Arc<watch::Sender<bool>>,
+    active: Arc<watch::Sender<usize>>,
+}
+
+/// Held by a WebSocket handler for as long as it runs.
+pub struct ConnectionGuard {
+    active: Arc<watch::Sender<usize>>,
+}
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        self.active.send_modify(|n| *n -= 1);
+    }
+}
+
+impl Shutdown {
+    pub fn new() -> Self {
+        Self {
+            triggered: Arc::new(watch::channel(false).0),
+            active: Arc::new(watch::channel(0).0),
+        }
+    }
+
+    pub fn trigger(&self) {
+        self.triggered.send_replace(true);
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        *self.triggered.borrow()
+    }
+
+    /// Resolves once shutdown has been triggered.
+    pub async fn triggered(&self) {
+        let mut rx = self.triggered.subscribe();
+        let _ = rx.wait_for(|&t| t).await;
+    }
+
+    pub fn track(&self) -> ConnectionGuard {
+        self.active.send_modify(|n| *n += 1);
+        ConnectionGuard {
+            active: self.active.clone(),
+        }
+    }
+
+    /// Wait for tracked connections to finish. Returns how many were still
+    /// running when `timeout` elapsed.
+    pub async fn drained(&self, timeout: Duration) -> usize {
+        let mut rx = self.active.subscribe();
+        let _ = tokio::time::timeout(timeout, rx.wait_for(|&n| n == 0)).await;
+        *self.active.borrow()
+    }
+}
+
+/// Resolves on Ctrl+C or SIGTERM.
+pub async fn signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Failed to install Ctrl+C handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Failed to install SIGTERM handler")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+}
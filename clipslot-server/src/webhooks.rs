@@ -0,0 +1,250 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::Duration;
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use uuid::Uuid;
+
+use crate::db::{self, with_db, Db};
+use crate::models::webhook::{Webhook, WebhookPayload};
+
+/// Delivery attempts per webhook before giving up.
+const MAX_ATTEMPTS: u32 = 3;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Delivers sync events to user-registered webhooks in the background.
+///
+/// Each delivery is a JSON POST signed with the webhook's secret:
+/// `X-ClipSlot-Signature: t=<unix seconds>,v1=<hex HMAC-SHA256 of "<t>.<body>">`.
+pub struct WebhookDispatcher {
+    db: Db,
+}
+
+impl WebhookDispatcher {
+    pub fn new(db: Db) -> Self {
+        Self { db }
+    }
+
+    /// Queue `event` for every webhook of `user_id` subscribed to it. Never blocks
+    /// the caller; delivery failures are only logged.
+    pub fn dispatch(&self, user_id: Uuid, event: &'static str, data: serde_json::Value) {
+        let db = self.db.clone();
+
+        tokio::spawn(async move {
+            let hooks = with_db!(&db, pool => {
+                sqlx::query_as::<_, Webhook>(
+                    "SELECT id, user_id, url, secret, events, created_at
+                     FROM webhooks WHERE user_id = $1",
+                )
+                .bind(user_id)
+                .fetch_all(pool)
+                .await
+            });
+            let hooks = match hooks {
+                Ok(hooks) => hooks,
+                Err(e) => {
+                    tracing::error!("Failed to load webhooks: {}", e);
+                    return;
+                }
+            };
+
+            let payload = WebhookPayload {
+                id: Uuid::new_v4(),
+                event: event.to_string(),
+                created_at: db::now(),
+                data,
+            };
+            let body = serde_json::to_string(&payload).unwrap();
+
+            for hook in hooks.into_iter().filter(|h| h.subscribes_to(event)) {
+                let body = body.clone();
+                tokio::spawn(async move { deliver(&hook, event, payload.id, &body).await });
+            }
+        });
+    }
+}
+
+/// Resolve a webhook URL to the address deliveries go to. Fails unless it's
+/// an http(s) URL whose host resolves only to public addresses, so webhooks
+/// can't reach the server's own network.
+pub async fn resolve_target(url: &str) -> Result<SocketAddr, &'static str> {
+    let url = reqwest::Url::parse(url).map_err(|_| "URL must be an absolute http(s) URL")?;
+    let (Some(host), Some(port)) = (url.host_str(), url.port_or_known_default()) else {
+        return Err("URL must be an absolute http(s) URL");
+    };
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err("URL must be an absolute http(s) URL");
+    }
+
+    // IPv6 literals keep their brackets in the host string
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    let addrs: Vec<SocketAddr> = match host.parse::<IpAddr>() {
+        Ok(ip) => vec![SocketAddr::new(ip, port)],
+        Err(_) => tokio::net::lookup_host((host, port))
+            .await
+            .map_err(|_| "URL host could not be resolved")?
+            .collect(),
+    };
+    // Every address must be public, or a host with one public and one
+    // private record could still reach the private one
+    if addrs.iter().any(|addr| !is_public(addr.ip())) {
+        return Err("URL must not point to a private or local address");
+    }
+    addrs.first().copied().ok_or("URL host could not be resolved")
+}
+
+/// Whether `ip` is reachable on the internet, rather than the server itself
+/// or its local network (loopback, private, link-local, unique-local,
+/// unspecified and the like).
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => {
+            let [a, b, ..] = v4.octets();
+            !(v4.is_loopback()
+                || v4.is_private()
+                || v4.is_link_local()
+                || v4.is_unspecified()
+                || v4.is_broadcast()
+                || v4.is_multicast()
+                // Shared address space (carrier-grade NAT)
+                || (a == 100 && (64..128).contains(&b))
+                || a == 0)
+        }
+        IpAddr::V6(v6) => {
+            if let Some(v4) = v6.to_ipv4_mapped() {
+                return is_public(IpAddr::V4(v4));
+            }
+            // Translation and tunnel prefixes route to the IPv4 address they
+            // carry, so that address has to be public too
+            if embedded_ipv4(v6)
+                .into_iter()
+                .any(|v4| !is_public(IpAddr::V4(v4)))
+            {
+                return false;
+            }
+            !(v6.is_loopback()
+                || v6.is_unspecified()
+                || v6.is_multicast()
+                || v6.is_unique_local()
+                || v6.is_unicast_link_local())
+        }
+    }
+}
+
+/// The IPv4 addresses carried by a NAT64 (64:ff9b::/96), 6to4 (2002::/16)
+/// or Teredo (2001::/32) address; empty for any other IPv6 address.
+fn embedded_ipv4(v6: Ipv6Addr) -> Vec<Ipv4Addr> {
+    let bits = u128::from(v6);
+    let v4_at = |shift: u32| Ipv4Addr::from((bits >> shift) as u32);
+    match v6.segments() {
+        [0x64, 0xff9b, 0, 0, 0, 0, ..] => vec![v4_at(0)],
+        [0x2002, ..] => vec![v4_at(80)],
+        // Teredo: the server in bits 32-63, the client inverted in the last 32
+        [0x2001, 0, ..] => vec![v4_at(64), Ipv4Addr::from(!(bits as u32))],
+        _ => Vec::new(),
+    }
+}
+
+/// A client that connects to `addr` whatever the URL's host resolves to by
+/// then, so a DNS change after the check can't redirect the delivery.
+fn pinned_client(url: &str, addr: SocketAddr) -> reqwest::Result<reqwest::Client> {
+    let mut builder = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .redirect(reqwest::redirect::Policy::none());
+    if let Some(host) = reqwest::Url::parse(url).ok().and_then(|u| u.domain().map(str::to_string)) {
+        builder = builder.resolve(&host, addr);
+    }
+    builder.build()
+}
+
+async fn deliver(hook: &Webhook, event: &str, delivery: Uuid, body: &str) {
+    for attempt in 1..=MAX_ATTEMPTS {
+        // Checked again on every attempt: the URL was public at registration,
+        // but its DNS may have changed since
+        let http = match resolve_target(&hook.url).await {
+            Ok(addr) => pinned_client(&hook.url, addr),
+            Err(e) => {
+                tracing::warn!("Not delivering to webhook {}: {}", hook.id, e);
+                return;
+            }
+        };
+        let http = match http {
+            Ok(http) => http,
+            Err(e) => {
+                tracing::error!("Failed to build webhook HTTP client: {}", e);
+                return;
+            }
+        };
+
+        let timestamp = chrono::Utc::now().timestamp();
+        let result = http
+            .post(&hook.url)
+            .header("Content-Type", "application/json")
+            .header("User-Agent", "ClipSlot-Webhook/1")
+            .header("X-ClipSlot-Event", event)
+            .header("X-ClipSlot-Delivery", delivery.to_string())
+            .header(
+                "X-ClipSlot-Signature",
+                format!("t={},v1={}", timestamp, sign(&hook.secret, timestamp, body)),
+            )
+            .body(body.to_string())
+            .send()
+            .await;
+
+        match result {
+            Ok(resp) if resp.status().is_success() => return,
+            Ok(resp) => tracing::warn!(
+                "Webhook {} returned {} (attempt {}/{})",
+                hook.id,
+                resp.status(),
+                attempt,
+                MAX_ATTEMPTS
+            ),
+            Err(e) => tracing::warn!(
+                "Webhook {} failed: {} (attempt {}/{})",
+                hook.id,
+                e,
+                attempt,
+                MAX_ATTEMPTS
+            ),
+        }
+
+        if attempt < MAX_ATTEMPTS {
+            tokio::time::sleep(Duration::from_secs(2u64.pow(attempt))).await;
+        }
+    }
+    tracing::error!("Giving up on webhook {} delivery {}", hook.id, delivery);
+}
+
+fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(format!("{}.{}", timestamp, body).as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn public(ip: &str) -> bool {
+        is_public(ip.parse().unwrap())
+    }
+
+    #[test]
+    fn test_is_public_checks_embedded_ipv4() {
+        // NAT64 of 127.0.0.1 and 8.8.8.8
+        assert!(!public("64:ff9b::7f00:1"));
+        assert!(public("64:ff9b::808:808"));
+        // 6to4 of 10.0.0.1 and 8.8.8.8
+        assert!(!public("2002:a00:1::1"));
+        assert!(public("2002:808:808::1"));
+        // Teredo with a public server and client 127.0.0.1 (inverted)
+        assert!(!public("2001:0:808:808::80ff:fffe"));
+        // Teredo with server 192.168.0.1
+        assert!(!public("2001:0:c0a8:1::f7f7:f7f7"));
+        // Teredo with server 8.8.8.8 and client 8.8.8.8
+        assert!(public("2001:0:808:808::f7f7:f7f7"));
+        assert!(public("2606:4700::1111"));
+    }
+}
@@ -0,0 +1,145 @@
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use dashmap::DashMap;
+use tokio::sync::Notify;
+use uuid::Uuid;
+
+/// Concurrent WebSocket connections per user. Each device normally holds one,
+/// so this only bites on runaway reconnect loops; the oldest connection is
+/// closed to make room, since it is the likeliest to be a dead half-open socket.
+const MAX_PER_USER: usize = 10;
+/// Concurrent WebSocket connections per client IP. New connections beyond this
+/// are refused. Higher than the per-user cap for shared IPs (offices, NAT).
+const MAX_PER_IP: usize = 50;
+/// Connections per user, counting ones told to close that haven't yet. Past
+/// this, closing the oldest isn't keeping up and new ones are refused.
+const MAX_PER_USER_CLOSING: usize = MAX_PER_USER * 2;
+
+/// Which limit refused a connection.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LimitExceeded {
+    PerIp,
+    PerUser,
+}
+
+impl LimitExceeded {
+    /// Machine-readable code for the `error` message.
+    pub fn code(self) -> &'static str {
+        match self {
+            LimitExceeded::PerIp => "too_many_connections",
+            LimitExceeded::PerUser => "too_many_account_connections",
+        }
+    }
+
+    pub fn message(self) -> &'static str {
+        match self {
+            LimitExceeded::PerIp => "Too many connections from this address",
+            LimitExceeded::PerUser => "Too many connections for this account",
+        }
+    }
+}
+
+struct Connection {
+    id: u64,
+    evict: Arc<Notify>,
+    /// Told to close, but its handler hasn't exited yet
+    evicted: bool,
+}
+
+/// Tracks open WebSocket connections per user and per IP. State is in memory
+/// and per instance.
+#[derive(Default)]
+pub struct ConnectionLimits {
+    by_user: DashMap<Uuid, Vec<Connection>>,
+    by_ip: DashMap<IpAddr, usize>,
+    next_id: AtomicU64,
+}
+
+impl ConnectionLimits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a new connection. Fails if the IP is at its limit; if the user
+    /// is, their oldest connection is told to close.
+    pub fn acquire(
+        self: &Arc<Self>,
+        user_id: Uuid,
+        ip: IpAddr,
+    ) -> Result<ConnectionSlot, LimitExceeded> {
+        {
+            let mut count = self.by_ip.entry(ip).or_insert(0);
+            if *count >= MAX_PER_IP {
+                return Err(LimitExceeded::PerIp);
+            }
+            *count += 1;
+        }
+
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let evict = Arc::new(Notify::new());
+        let mut conns = self.by_user.entry(user_id).or_default();
+        if conns.len() >= MAX_PER_USER_CLOSING {
+            drop(conns);
+            self.by_ip.remove_if_mut(&ip, |_, count| {
+                *count -= 1;
+                *count == 0
+            });
+            return Err(LimitExceeded::PerUser);
+        }
+        conns.push(Connection {
+            id,
+            evict: evict.clone(),
+            evicted: false,
+        });
+        if conns.iter().filter(|c| !c.evicted).count() > MAX_PER_USER {
+            // Oldest first; it stays registered until its handler exits
+            if let Some(oldest) = conns.iter_mut().find(|c| !c.evicted) {
+                oldest.evicted = true;
+                oldest.evict.notify_one();
+            }
+        }
+
+        Ok(ConnectionSlot {
+            limits: self.clone(),
+            user_id,
+            ip,
+            id,
+            evict,
+        })
+    }
+
+    fn release(&self, user_id: Uuid, ip: IpAddr, id: u64) {
+        self.by_ip.remove_if_mut(&ip, |_, count| {
+            *count -= 1;
+            *count == 0
+        });
+        self.by_user.remove_if_mut(&user_id, |_, conns| {
+            conns.retain(|c| c.id != id);
+            conns.is_empty()
+        });
+    }
+}
+
+/// An open connection's place in the limits, released on drop.
+pub struct ConnectionSlot {
+    limits: Arc<ConnectionLimits>,
+    user_id: Uuid,
+    ip: IpAddr,
+    id: u64,
+    evict: Arc<Notify>,
+}
+
+impl ConnectionSlot {
+    /// Resolves when a newer connection has pushed this one over the user's limit.
+    pub async fn evicted(&self) {
+        self.evict.notified().await
+    }
+}
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        self.limits.release(self.user_id, self.ip, self.id);
+    }
+}
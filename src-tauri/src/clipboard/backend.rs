@@ -0,0 +1,109 @@
+/// The system clipboard, as the monitor and paste flow use it. The app's
+/// `AppHandle` is the real one (see `monitor.rs`).
+pub trait ClipboardBackend {
+    fn read_text(&self) -> Result<String, String>;
+    fn write_text(&self, text: &str) -> Result<(), String>;
+}
+
+/// Keys the app presses in the focused app: between pasted items to move
+/// between fields, or to erase a typed snippet keyword.
+#[cfg(desktop)]
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Key {
+    Tab,
+    Enter,
+    Backspace,
+}
+
+/// Synthetic keystrokes sent to the focused app.
+#[cfg(desktop)]
+pub trait InputBackend {
+    /// Send the platform's paste keystroke (Cmd+V / Ctrl+V).
+    fn paste(&self) -> Result<(), String>;
+    /// Press and release `key`, without modifiers.
+    fn press(&self, key: Key) -> Result<(), String>;
+}
+
+/// In-memory backends for tests.
+#[cfg(test)]
+pub mod fake {
+    use std::sync::Mutex;
+
+    use super::ClipboardBackend;
+    #[cfg(desktop)]
+    use super::{InputBackend, Key};
+
+    #[derive(Default)]
+    pub struct FakeClipboard {
+        text: Mutex<String>,
+        /// Everything written through `write_text`, in order
+        pub writes: Mutex<Vec<String>>,
+    }
+
+    impl FakeClipboard {
+        pub fn with_text(text: &str) -> Self {
+            let clipboard = FakeClipboard::default();
+            clipboard.copy(text);
+            clipboard
+        }
+
+        /// Change the content like another app copying would.
+        pub fn copy(&self, text: &str) {
+            *self.text.lock().unwrap() = text.to_string();
+        }
+
+        pub fn text(&self) -> String {
+            self.text.lock().unwrap().clone()
+        }
+    }
+
+    impl ClipboardBackend for FakeClipboard {
+        fn read_text(&self) -> Result<String, String> {
+            Ok(self.text())
+        }
+
+        fn write_text(&self, text: &str) -> Result<(), String> {
+            self.writes.lock().unwrap().push(text.to_string());
+            self.copy(text);
+            Ok(())
+        }
+    }
+
+    /// Pastes into a pretend focused app, which reads `clipboard` when the
+    /// keystroke arrives.
+    #[cfg(desktop)]
+    pub struct FakeInput<'a> {
+        clipboard: &'a FakeClipboard,
+        pub pasted: Mutex<Vec<String>>,
+        /// Everything the app received, pastes as their text and keys as
+        /// `<Tab>` or `<Enter>`
+        pub typed: Mutex<Vec<String>>,
+    }
+
+    #[cfg(desktop)]
+    impl<'a> FakeInput<'a> {
+        pub fn new(clipboard: &'a FakeClipboard) -> Self {
+            FakeInput {
+                clipboard,
+                pasted: Mutex::new(Vec::new()),
+                typed: Mutex::new(Vec::new()),
+            }
+        }
+    }
+
+    #[cfg(desktop)]
+    impl InputBackend for FakeInput<'_> {
+        fn paste(&self) -> Result<(), String> {
+            let text = self.clipboard.text();
+            self.typed.lock().unwrap().push(text.clone());
+            self.pasted.lock().unwrap().push(text);
+            Ok(())
+        }
+
+        fn press(&self, key: Key) -> Result<(), String> {
+            self.typed.lock().unwrap().push(format!("<{:?}>", key));
+            Ok(())
+        }
+    }
+}
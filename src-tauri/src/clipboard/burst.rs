@@ -0,0 +1,97 @@
+use std::time::{Duration, Instant};
+
+use super::item::ClipboardItem;
+
+/// A burst still changing after this long is captured anyway, so content
+/// that never stops changing still reaches history now and then.
+pub const MAX_BURST: Duration = Duration::from_secs(10);
+
+/// Holds back clipboard changes that come one poll after another from the
+/// same app (e.g. an editor writing a selection as it grows), so a burst is
+/// captured once, as its final content.
+#[derive(Default)]
+pub struct BurstCoalescer {
+    pending: Option<Pending>,
+}
+
+struct Pending {
+    item: ClipboardItem,
+    started: Instant,
+}
+
+impl BurstCoalescer {
+    /// A change seen at `now`. Returns an earlier change to capture if this
+    /// one doesn't continue its burst.
+    pub fn push(&mut self, item: ClipboardItem, now: Instant) -> Option<ClipboardItem> {
+        match self.pending.take() {
+            Some(pending)
+                if pending.item.source_app == item.source_app
+                    && now.duration_since(pending.started) < MAX_BURST =>
+            {
+                self.pending = Some(Pending {
+                    item,
+                    started: pending.started,
+                });
+                None
+            }
+            previous => {
+                self.pending = Some(Pending { item, started: now });
+                previous.map(|p| p.item)
+            }
+        }
+    }
+
+    /// A poll without a change: the burst is over, and its last change is
+    /// the one to capture.
+    pub fn settle(&mut self) -> Option<ClipboardItem> {
+        self.pending.take().map(|p| p.item)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(content: &str, app: Option<&str>) -> ClipboardItem {
+        let mut item = ClipboardItem::new(content.to_string(), "device");
+        item.source_app = app.map(String::from);
+        item
+    }
+
+    fn content(item: Option<ClipboardItem>) -> Option<String> {
+        item.map(|i| i.content)
+    }
+
+    #[test]
+    fn test_burst_captured_as_final_content() {
+        let mut bursts = BurstCoalescer::default();
+        let start = Instant::now();
+        for (i, text) in ["h", "he", "hel", "hello"].iter().enumerate() {
+            let at = start + Duration::from_millis(500 * i as u64);
+            assert_eq!(content(bursts.push(item(text, Some("Editor")), at)), None);
+        }
+        assert_eq!(content(bursts.settle()).as_deref(), Some("hello"));
+        assert_eq!(content(bursts.settle()), None);
+    }
+
+    #[test]
+    fn test_other_app_ends_burst() {
+        let mut bursts = BurstCoalescer::default();
+        let now = Instant::now();
+        bursts.push(item("one", Some("Editor")), now);
+        let ended = bursts.push(item("two", Some("Browser")), now);
+        assert_eq!(content(ended).as_deref(), Some("one"));
+        assert_eq!(content(bursts.settle()).as_deref(), Some("two"));
+    }
+
+    #[test]
+    fn test_long_burst_captured_periodically() {
+        let mut bursts = BurstCoalescer::default();
+        let start = Instant::now();
+        bursts.push(item("a", None), start);
+        bursts.push(item("ab", None), start + MAX_BURST / 2);
+        let ended = bursts.push(item("abc", None), start + MAX_BURST);
+        assert_eq!(content(ended).as_deref(), Some("ab"));
+        assert_eq!(content(bursts.settle()).as_deref(), Some("abc"));
+    }
+}
@@ -0,0 +1,97 @@
+use std::sync::atomic::{AtomicU8, Ordering};
+
+const WATCHING: u8 = 0;
+/// The app wrote to the clipboard itself; the next change isn't captured
+const SKIP_NEXT: u8 = 1;
+/// Nothing is captured, including what is copied meanwhile
+const PAUSED: u8 = 2;
+
+/// Whether clipboard changes are captured, shared by the monitor and
+/// everything that pauses it or writes to the clipboard.
+pub struct CaptureState(AtomicU8);
+
+impl Default for CaptureState {
+    fn default() -> Self {
+        CaptureState(AtomicU8::new(WATCHING))
+    }
+}
+
+impl CaptureState {
+    pub fn is_paused(&self) -> bool {
+        self.0.load(Ordering::Acquire) == PAUSED
+    }
+
+    pub fn pause(&self) {
+        self.0.store(PAUSED, Ordering::Release);
+    }
+
+    pub fn resume(&self) {
+        let _ = self
+            .0
+            .compare_exchange(PAUSED, WATCHING, Ordering::AcqRel, Ordering::Acquire);
+    }
+
+    /// Pause or resume; returns whether capturing is now paused.
+    pub fn toggle_pause(&self) -> bool {
+        let previous = self
+            .0
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |state| {
+                Some(if state == PAUSED { WATCHING } else { PAUSED })
+            })
+            .unwrap_or(WATCHING);
+        previous != PAUSED
+    }
+
+    /// Don't capture the next change. Has no effect while paused, since the
+    /// monitor forgets changes made during a pause.
+    pub fn skip_next(&self) {
+        let _ = self
+            .0
+            .compare_exchange(WATCHING, SKIP_NEXT, Ordering::AcqRel, Ordering::Acquire);
+    }
+
+    /// Called by the monitor for each clipboard change; whether to capture it.
+    pub fn take_change(&self) -> bool {
+        match self
+            .0
+            .compare_exchange(SKIP_NEXT, WATCHING, Ordering::AcqRel, Ordering::Acquire)
+        {
+            Ok(_) => false,
+            Err(state) => state == WATCHING,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_skip_next_skips_one_change() {
+        let state = CaptureState::default();
+        assert!(state.take_change());
+        state.skip_next();
+        state.skip_next();
+        assert!(!state.take_change());
+        assert!(state.take_change());
+    }
+
+    #[test]
+    fn test_pause() {
+        let state = CaptureState::default();
+        state.skip_next();
+        assert!(state.toggle_pause());
+        assert!(state.is_paused());
+        // Writes during a pause don't carry over to after it
+        state.skip_next();
+        assert!(!state.take_change());
+        assert!(!state.toggle_pause());
+        assert!(state.take_change());
+
+        state.pause();
+        state.resume();
+        state.resume();
+        assert!(!state.is_paused());
+        assert!(state.take_change());
+    }
+}
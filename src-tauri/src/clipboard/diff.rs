@@ -0,0 +1,161 @@
+use std::fmt::Write;
+
+/// Unchanged lines shown around each change.
+const CONTEXT_LINES: usize = 3;
+/// Most line pairs compared once the common start and end are set aside;
+/// the table takes 4 bytes per pair.
+const MAX_COMPARED_PAIRS: usize = 4_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// Unified diff (as `diff -u` prints it) turning `old` into `new`, with
+/// `old_name` and `new_name` in the header. Empty if they're the same.
+pub fn unified_diff(
+    old: &str,
+    new: &str,
+    old_name: &str,
+    new_name: &str,
+) -> Result<String, String> {
+    let a: Vec<&str> = old.lines().collect();
+    let b: Vec<&str> = new.lines().collect();
+    let ops = line_ops(&a, &b)?;
+    let changes: Vec<usize> = (0..ops.len()).filter(|&k| ops[k] != Op::Equal).collect();
+    if changes.is_empty() {
+        return Ok(String::new());
+    }
+
+    // Lines of `a` and `b` before each op
+    let mut positions = Vec::with_capacity(ops.len() + 1);
+    let (mut i, mut j) = (0, 0);
+    for op in &ops {
+        positions.push((i, j));
+        match op {
+            Op::Equal => (i, j) = (i + 1, j + 1),
+            Op::Delete => i += 1,
+            Op::Insert => j += 1,
+        }
+    }
+    positions.push((i, j));
+
+    let mut out = String::new();
+    let _ = writeln!(out, "--- {}\n+++ {}", old_name, new_name);
+    let mut first = 0;
+    while first < changes.len() {
+        // Changes close enough to share their context go in one hunk
+        let mut last = first;
+        while last + 1 < changes.len()
+            && changes[last + 1] - changes[last] <= 2 * CONTEXT_LINES + 1
+        {
+            last += 1;
+        }
+        let start = changes[first].saturating_sub(CONTEXT_LINES);
+        let end = (changes[last] + CONTEXT_LINES + 1).min(ops.len());
+        let ((a0, b0), (a1, b1)) = (positions[start], positions[end]);
+        let _ = writeln!(
+            out,
+            "@@ -{} +{} @@",
+            hunk_range(a0, a1 - a0),
+            hunk_range(b0, b1 - b0)
+        );
+        for (op, &(i, j)) in ops[start..end].iter().zip(&positions[start..end]) {
+            let _ = match op {
+                Op::Equal => writeln!(out, " {}", a[i]),
+                Op::Delete => writeln!(out, "-{}", a[i]),
+                Op::Insert => writeln!(out, "+{}", b[j]),
+            };
+        }
+        first = last + 1;
+    }
+    Ok(out)
+}
+
+/// "start,len" with 1-based lines; an empty range names the line before it.
+fn hunk_range(start: usize, len: usize) -> String {
+    match len {
+        0 => format!("{},0", start),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{}", start + 1, len),
+    }
+}
+
+/// Edits turning `a` into `b`, keeping the longest common subsequence of lines.
+fn line_ops(a: &[&str], b: &[&str]) -> Result<Vec<Op>, String> {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let a_mid = &a[prefix..a.len() - suffix];
+    let b_mid = &b[prefix..b.len() - suffix];
+    let (n, m) = (a_mid.len(), b_mid.len());
+    if n.saturating_mul(m) > MAX_COMPARED_PAIRS {
+        return Err("The items differ in too many lines to compare".to_string());
+    }
+
+    // lcs[i * width + j]: common subsequence length of a_mid[i..] and b_mid[j..]
+    let width = m + 1;
+    let mut lcs = vec![0u32; (n + 1) * width];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i * width + j] = if a_mid[i] == b_mid[j] {
+                lcs[(i + 1) * width + j + 1] + 1
+            } else {
+                lcs[(i + 1) * width + j].max(lcs[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut ops = vec![Op::Equal; prefix];
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m && a_mid[i] == b_mid[j] {
+            ops.push(Op::Equal);
+            (i, j) = (i + 1, j + 1);
+        } else if j == m || (i < n && lcs[(i + 1) * width + j] >= lcs[i * width + j + 1]) {
+            ops.push(Op::Delete);
+            i += 1;
+        } else {
+            ops.push(Op::Insert);
+            j += 1;
+        }
+    }
+    ops.extend(std::iter::repeat_n(Op::Equal, suffix));
+    Ok(ops)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unified_diff() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl\n";
+        let new = "a\nB\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl\nm\n";
+        let expected = "--- old\n+++ new\n\
+            @@ -1,5 +1,5 @@\n a\n-b\n+B\n c\n d\n e\n\
+            @@ -10,3 +10,4 @@\n j\n k\n l\n+m\n";
+        assert_eq!(unified_diff(old, new, "old", "new").unwrap(), expected);
+    }
+
+    #[test]
+    fn test_close_changes_share_a_hunk() {
+        let diff = unified_diff("x\na\nb\nc\ny", "a\nb\nc", "old", "new").unwrap();
+        assert_eq!(diff, "--- old\n+++ new\n@@ -1,5 +1,3 @@\n-x\n a\n b\n c\n-y\n");
+        assert_eq!(
+            unified_diff("", "one", "old", "new").unwrap(),
+            "--- old\n+++ new\n@@ -0,0 +1 @@\n+one\n"
+        );
+    }
+
+    #[test]
+    fn test_same_text_has_no_diff() {
+        assert_eq!(unified_diff("same\ntext", "same\ntext", "a", "b").unwrap(), "");
+    }
+}
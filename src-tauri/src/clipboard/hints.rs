@@ -0,0 +1,144 @@
+/// What the app that copied says about keeping the clipboard content.
+/// Windows apps (password managers, enterprise apps with data loss
+/// prevention) put extra formats next to the text to keep it out of
+/// clipboard history and cloud sync; ClipSlot is both, so any of them
+/// keeps the content out of history.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct StorageHints {
+    /// `ExcludeClipboardContentFromHistory` is present; its value is unused
+    pub exclude_from_history: bool,
+    /// The DWORD in `CanIncludeInClipboardHistory`, if present
+    pub can_include_in_history: Option<u32>,
+    /// The DWORD in `CanUploadToCloudClipboard`, if present
+    pub can_upload_to_cloud: Option<u32>,
+}
+
+impl StorageHints {
+    pub fn allow_storing(&self) -> bool {
+        !self.exclude_from_history
+            && self.can_include_in_history != Some(0)
+            && self.can_upload_to_cloud != Some(0)
+    }
+}
+
+/// Whether the content on the clipboard now may be stored in history.
+#[cfg(target_os = "windows")]
+pub fn clipboard_allows_storing() -> bool {
+    win_clipboard::read_hints().allow_storing()
+}
+
+/// Only Windows has these hints.
+#[cfg(not(target_os = "windows"))]
+pub fn clipboard_allows_storing() -> bool {
+    true
+}
+
+#[cfg(target_os = "windows")]
+mod win_clipboard {
+    use std::ffi::c_void;
+
+    use super::StorageHints;
+
+    extern "system" {
+        fn RegisterClipboardFormatW(name: *const u16) -> u32;
+        fn IsClipboardFormatAvailable(format: u32) -> i32;
+        fn OpenClipboard(owner: *mut c_void) -> i32;
+        fn CloseClipboard() -> i32;
+        fn GetClipboardData(format: u32) -> *mut c_void;
+        fn GlobalLock(mem: *mut c_void) -> *mut c_void;
+        fn GlobalUnlock(mem: *mut c_void) -> i32;
+        fn GlobalSize(mem: *mut c_void) -> usize;
+    }
+
+    /// The format's id; registering an existing name returns the same one.
+    fn format(name: &str) -> u32 {
+        let wide: Vec<u16> = name.encode_utf16().chain(std::iter::once(0)).collect();
+        unsafe { RegisterClipboardFormatW(wide.as_ptr()) }
+    }
+
+    fn available(format: u32) -> bool {
+        format != 0 && unsafe { IsClipboardFormatAvailable(format) } != 0
+    }
+
+    pub fn read_hints() -> StorageHints {
+        let exclude = format("ExcludeClipboardContentFromHistory");
+        let include = format("CanIncludeInClipboardHistory");
+        let upload = format("CanUploadToCloudClipboard");
+
+        let mut hints = StorageHints {
+            exclude_from_history: available(exclude),
+            ..StorageHints::default()
+        };
+        if !available(include) && !available(upload) {
+            return hints;
+        }
+
+        // Another app may hold the clipboard open. A hint that can't be read
+        // is treated as a no, since apps add these formats to say no.
+        if unsafe { OpenClipboard(std::ptr::null_mut()) } == 0 {
+            hints.can_include_in_history = available(include).then_some(0);
+            hints.can_upload_to_cloud = available(upload).then_some(0);
+            return hints;
+        }
+        hints.can_include_in_history = available(include).then(|| read_dword(include));
+        hints.can_upload_to_cloud = available(upload).then(|| read_dword(upload));
+        unsafe { CloseClipboard() };
+        hints
+    }
+
+    /// The DWORD stored in `format`; 0 if it can't be read. The clipboard
+    /// must be open.
+    fn read_dword(format: u32) -> u32 {
+        unsafe {
+            let handle = GetClipboardData(format);
+            if handle.is_null() || GlobalSize(handle) < 4 {
+                return 0;
+            }
+            let data = GlobalLock(handle) as *const u8;
+            if data.is_null() {
+                return 0;
+            }
+            let value = u32::from_le_bytes(std::ptr::read_unaligned(data as *const [u8; 4]));
+            GlobalUnlock(handle);
+            value
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_no_hints_allow_storing() {
+        assert!(StorageHints::default().allow_storing());
+        let allowed = StorageHints {
+            exclude_from_history: false,
+            can_include_in_history: Some(1),
+            can_upload_to_cloud: Some(1),
+        };
+        assert!(allowed.allow_storing());
+    }
+
+    #[test]
+    fn test_any_opt_out_prevents_storing() {
+        let excluded = StorageHints {
+            exclude_from_history: true,
+            ..StorageHints::default()
+        };
+        assert!(!excluded.allow_storing());
+
+        let no_history = StorageHints {
+            can_include_in_history: Some(0),
+            ..StorageHints::default()
+        };
+        assert!(!no_history.allow_storing());
+
+        let no_cloud = StorageHints {
+            can_include_in_history: Some(1),
+            can_upload_to_cloud: Some(0),
+            ..StorageHints::default()
+        };
+        assert!(!no_cloud.allow_storing());
+    }
+}
@@ -0,0 +1,153 @@
+use std::time::Duration;
+
+use url::Url;
+
+/// How long fetching a page title may take before it is given up.
+const FETCH_TIMEOUT: Duration = Duration::from_secs(5);
+/// Most of a page read while looking for its `<title>`.
+const MAX_PAGE_BYTES: usize = 64 * 1024;
+/// Longest title kept.
+const MAX_TITLE_CHARS: usize = 200;
+
+/// The http(s) link an item consists of, if it is just one link.
+pub fn web_url(content: &str) -> Option<Url> {
+    let text = content.trim();
+    if text.contains(char::is_whitespace) {
+        return None;
+    }
+    let url = Url::parse(text).ok()?;
+    (matches!(url.scheme(), "http" | "https") && url.host().is_some()).then_some(url)
+}
+
+/// Fetch `url` and return its page title, `None` if it has none.
+pub async fn fetch_title(url: &Url) -> Result<Option<String>, String> {
+    let client = reqwest::Client::builder()
+        .timeout(FETCH_TIMEOUT)
+        .user_agent(concat!("ClipSlot/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .map_err(|e| e.to_string())?;
+    let mut resp = client
+        .get(url.clone())
+        .send()
+        .await
+        .map_err(|e| format!("Network error: {}", e))?;
+    if !resp.status().is_success() {
+        return Err(format!("HTTP {}", resp.status()));
+    }
+    let is_html = resp
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.contains("html"));
+    if !is_html {
+        return Ok(None);
+    }
+
+    // The title is near the top; don't download the whole page
+    let mut page = Vec::new();
+    while let Some(chunk) = resp.chunk().await.map_err(|e| format!("Network error: {}", e))? {
+        page.extend_from_slice(&chunk);
+        if page.len() >= MAX_PAGE_BYTES || contains_ignore_case(&page, b"</title") {
+            break;
+        }
+    }
+    page.truncate(MAX_PAGE_BYTES);
+    Ok(extract_title(&String::from_utf8_lossy(&page)))
+}
+
+/// The text of the first `<title>` element in `html`, tidied for display.
+pub fn extract_title(html: &str) -> Option<String> {
+    let lower = html.to_ascii_lowercase();
+    let open = lower.find("<title")?;
+    let start = open + lower[open..].find('>')? + 1;
+    let end = start + lower[start..].find("</title")?;
+
+    let title = decode_entities(&html[start..end])
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    if title.is_empty() {
+        return None;
+    }
+    Some(title.chars().take(MAX_TITLE_CHARS).collect())
+}
+
+fn contains_ignore_case(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack
+        .windows(needle.len())
+        .any(|w| w.eq_ignore_ascii_case(needle))
+}
+
+/// Decode the character references titles commonly use.
+fn decode_entities(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let decoded = rest.find(';').filter(|&semi| semi <= 10).and_then(|semi| {
+            let c = match &rest[1..semi] {
+                "amp" => '&',
+                "lt" => '<',
+                "gt" => '>',
+                "quot" => '"',
+                "apos" => '\'',
+                "nbsp" => ' ',
+                "ndash" => '–',
+                "mdash" => '—',
+                code => {
+                    let n = match code.strip_prefix("#x").or_else(|| code.strip_prefix("#X")) {
+                        Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                        None => code.strip_prefix('#')?.parse().ok()?,
+                    };
+                    char::from_u32(n)?
+                }
+            };
+            Some((c, semi))
+        });
+        match decoded {
+            Some((c, semi)) => {
+                out.push(c);
+                rest = &rest[semi + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_web_url() {
+        assert!(web_url("  https://github.com/tokio-rs/axum/issues\n").is_some());
+        assert!(web_url("http://localhost:3000").is_some());
+        assert!(web_url("ftp://example.com").is_none());
+        assert!(web_url("mailto:someone@example.com").is_none());
+        assert!(web_url("see https://example.com").is_none());
+        assert!(web_url("not a link").is_none());
+    }
+
+    #[test]
+    fn test_extract_title() {
+        let html = "<html><head><TITLE lang=\"en\">\n  GitHub &ndash; axum   issues &amp; PRs\n\
+                    </TITLE></head></html>";
+        assert_eq!(extract_title(html).as_deref(), Some("GitHub – axum issues & PRs"));
+        assert_eq!(
+            extract_title("<title>Caf&#233; &#x26; &bogus; A&B</title>").as_deref(),
+            Some("Café & &bogus; A&B")
+        );
+        assert_eq!(extract_title("<title>  </title>"), None);
+        assert_eq!(extract_title("<title>never closed"), None);
+        assert_eq!(extract_title("<p>no title</p>"), None);
+
+        let long = format!("<title>{}</title>", "x".repeat(500));
+        assert_eq!(extract_title(&long).unwrap().chars().count(), MAX_TITLE_CHARS);
+    }
+}
@@ -0,0 +1,91 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// How far back clipboard changes are remembered.
+pub const LOOP_WINDOW: Duration = Duration::from_secs(10);
+/// Content seen this many times within `LOOP_WINDOW`, having been written by
+/// the app itself, means something is writing it back.
+pub const LOOP_REPEATS: usize = 3;
+/// How long nothing is captured once a loop is found.
+pub const COOLDOWN: Duration = Duration::from_secs(30);
+
+/// Notices when the clipboard ping-pongs with another clipboard tool (or a
+/// synced copy of ClipSlot in a VM): what the app writes keeps coming back.
+/// Capturing then stops for a while, which breaks the loop.
+#[derive(Default)]
+pub struct LoopGuard {
+    /// Recent changes, oldest first: content hash, when, whether the app
+    /// wrote it
+    recent: VecDeque<(String, Instant, bool)>,
+    cooldown_until: Option<Instant>,
+}
+
+impl LoopGuard {
+    /// A clipboard change at `now`; whether it's part of a loop and
+    /// shouldn't be captured.
+    pub fn observe(&mut self, hash: &str, ours: bool, now: Instant) -> bool {
+        while let Some((_, at, _)) = self.recent.front() {
+            if now.duration_since(*at) < LOOP_WINDOW {
+                break;
+            }
+            self.recent.pop_front();
+        }
+        self.recent.push_back((hash.to_string(), now, ours));
+
+        if self.cooldown_until.is_some_and(|until| now < until) {
+            return true;
+        }
+
+        let mut repeats = self.recent.iter().filter(|(h, _, _)| h == hash);
+        let written = repeats.clone().any(|(_, _, ours)| *ours);
+        if written && repeats.nth(LOOP_REPEATS - 1).is_some() {
+            tracing::warn!(
+                "Clipboard loop with another app detected; not capturing for {}s",
+                COOLDOWN.as_secs()
+            );
+            self.cooldown_until = Some(now + COOLDOWN);
+            return true;
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_content_written_back_starts_cooldown() {
+        let mut guard = LoopGuard::default();
+        let start = Instant::now();
+        let at = |ms: u64| start + Duration::from_millis(ms);
+
+        // The app writes "x", another tool answers with "y" and writes "x"
+        // back, over and over
+        assert!(!guard.observe("x", true, at(0)));
+        assert!(!guard.observe("y", false, at(500)));
+        assert!(!guard.observe("x", false, at(1000)));
+        assert!(!guard.observe("y", false, at(1500)));
+        assert!(guard.observe("x", false, at(2000)));
+
+        // Everything waits for the cooldown
+        assert!(guard.observe("new", false, at(2500)));
+        assert!(!guard.observe("new", false, at(2000) + COOLDOWN));
+    }
+
+    #[test]
+    fn test_repeats_alone_are_not_a_loop() {
+        let mut guard = LoopGuard::default();
+        let start = Instant::now();
+        // The user copying the same things back and forth
+        for i in 0..4 {
+            let at = start + Duration::from_millis(500 * i);
+            assert!(!guard.observe(if i % 2 == 0 { "a" } else { "b" }, false, at));
+        }
+
+        // The app's write, repeated but spread out
+        assert!(!guard.observe("x", true, start));
+        assert!(!guard.observe("x", false, start + LOOP_WINDOW));
+        assert!(!guard.observe("x", false, start + LOOP_WINDOW * 2));
+    }
+}
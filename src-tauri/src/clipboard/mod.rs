@@ -1,2 +1,22 @@
-pub mod item;
+pub mod backend;
+#[cfg(desktop)]
+pub mod burst;
+pub mod capture_state;
+pub mod diff;
+#[cfg(desktop)]
+pub mod hints;
+pub mod link;
+#[cfg(desktop)]
+pub mod loop_guard;
 pub mod monitor;
+#[cfg(desktop)]
+pub mod native_history;
+pub mod qr;
+pub mod schedule;
+pub mod sensitive;
+pub mod transform;
+#[cfg(desktop)]
+pub mod watcher;
+pub mod wipe;
+
+pub use clipslot_core::clipboard::{item, language, timestamp};
@@ -1,57 +1,76 @@
-use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use std::time::Duration;
 
-use tauri::{AppHandle, Emitter, Runtime};
+use tauri::{AppHandle, Runtime};
 use tauri_plugin_clipboard_manager::ClipboardExt;
 
-use super::item::ClipboardItem;
-use crate::storage::database::Database;
-use crate::sync::manager::SyncManager;
-
+use super::backend::ClipboardBackend;
+use super::capture_state::CaptureState;
+#[cfg(desktop)]
+use {
+    super::burst::BurstCoalescer,
+    super::hints,
+    super::item::ClipboardItem,
+    super::link,
+    super::watcher::ChangeWatcher,
+    super::wipe,
+    crate::storage::database::Database,
+    crate::storage::settings::Settings,
+    crate::sync::manager::SyncManager,
+    std::time::{Duration, Instant},
+    tauri::Emitter,
+};
+
+#[cfg(desktop)]
 const POLL_INTERVAL_MS: u64 = 500;
 
+/// Watches the clipboard on a polling thread; slot shortcuts are OS hotkeys
+/// (see `slots::manager::register_shortcuts`).
 pub struct ClipboardMonitor {
-    paused: Arc<AtomicBool>,
-    skip_next: Arc<AtomicBool>,
+    state: Arc<CaptureState>,
 }
 
 impl ClipboardMonitor {
     pub fn new() -> Self {
         Self {
-            paused: Arc::new(AtomicBool::new(false)),
-            skip_next: Arc::new(AtomicBool::new(false)),
+            state: Arc::new(CaptureState::default()),
         }
     }
 
     pub fn is_paused(&self) -> bool {
-        self.paused.load(Ordering::Relaxed)
+        self.state.is_paused()
     }
 
     pub fn pause(&self) {
-        self.paused.store(true, Ordering::Relaxed);
+        self.state.pause();
     }
 
     pub fn resume(&self) {
-        self.paused.store(false, Ordering::Relaxed);
+        self.state.resume();
     }
 
     pub fn toggle_pause(&self) -> bool {
-        let was_paused = self.paused.fetch_xor(true, Ordering::Relaxed);
-        let now_paused = !was_paused;
-        println!(
-            "[ClipSlot] Monitoring {}",
+        let now_paused = self.state.toggle_pause();
+        tracing::info!(
+            "Monitoring {}",
             if now_paused { "PAUSED" } else { "RESUMED" }
         );
         now_paused
     }
 
+    /// Shared with whatever pauses capturing while it uses the clipboard.
+    pub fn capture_state(&self) -> Arc<CaptureState> {
+        self.state.clone()
+    }
+
     /// Tell the monitor to ignore the next clipboard change.
     /// Used when the app itself writes to the clipboard (e.g., click-to-copy).
     pub fn set_skip_next(&self) {
-        self.skip_next.store(true, Ordering::Relaxed);
+        self.state.skip_next();
     }
 
+    /// Start watching. Only desktops can: phones don't let apps read the
+    /// clipboard in the background.
+    #[cfg(desktop)]
     pub fn start<R: Runtime>(
         &self,
         app_handle: AppHandle<R>,
@@ -59,8 +78,7 @@ impl ClipboardMonitor {
         db: Arc<Database>,
         sync_manager: Option<Arc<SyncManager>>,
     ) {
-        let paused = self.paused.clone();
-        let skip_next = self.skip_next.clone();
+        let state = self.state.clone();
 
         std::thread::spawn(move || {
             // Create a dedicated tokio runtime for async sync operations.
@@ -70,91 +88,155 @@ impl ClipboardMonitor {
                 tokio::runtime::Runtime::new().expect("Failed to create tokio runtime")
             });
 
-            let mut last_hash: Option<String> = None;
-
             // Read initial clipboard content to avoid capturing pre-existing content
-            if let Ok(text) = app_handle.clipboard().read_text() {
-                if !text.is_empty() {
-                    last_hash = Some(ClipboardItem::hash_content(&text));
-                    println!("[ClipSlot] Monitor started (existing clipboard content ignored)");
-                }
+            let initial = app_handle.read_text().ok().filter(|t| !t.is_empty());
+            if initial.is_some() {
+                tracing::info!("Monitor started (existing clipboard content ignored)");
             } else {
-                println!("[ClipSlot] Monitor started (clipboard empty)");
+                tracing::info!("Monitor started (clipboard empty)");
             }
+            let mut watcher = ChangeWatcher::new(state, initial.as_deref());
+            let mut bursts = BurstCoalescer::default();
 
             loop {
                 std::thread::sleep(Duration::from_millis(POLL_INTERVAL_MS));
 
-                if paused.load(Ordering::Relaxed) {
+                let Some(text) = watcher.poll(&app_handle) else {
+                    // A burst of changes is captured once it stops
+                    if let Some(item) = bursts.settle() {
+                        capture(&app_handle, &db, &sync_manager, rt.as_ref(), item);
+                    }
                     continue;
-                }
-
-                let text = match app_handle.clipboard().read_text() {
-                    Ok(t) => t,
-                    Err(_) => continue,
                 };
 
-                if text.is_empty() {
-                    continue;
-                }
+                let settings = Settings::load(&db);
+                let item = ClipboardItem::new(text, &device_id);
 
-                let hash = ClipboardItem::hash_content(&text);
+                // Secrets are cleared later whether or not they're captured
+                let source_app = item.source_app.as_deref();
+                if let Some(delay) = settings.clipboard_clear_delay(&item.content, source_app) {
+                    wipe::clear_after(app_handle.clone(), item.content.clone(), delay);
+                }
 
-                if last_hash.as_ref() == Some(&hash) {
+                // The copying app asked for its content to stay out of
+                // clipboard history (Windows only)
+                if !hints::clipboard_allows_storing() {
+                    tracing::info!("Skipped content marked not for clipboard history");
                     continue;
                 }
 
-                last_hash = Some(hash);
-
-                // If the app itself wrote to the clipboard, skip this capture
-                if skip_next.swap(false, Ordering::Relaxed) {
+                // Outside the times capturing is allowed; the change is still
+                // remembered so it isn't captured once they start
+                if !settings.capture_schedule.allows(chrono::Local::now().naive_local()) {
                     continue;
                 }
 
-                let item = ClipboardItem::new(text, &device_id);
+                // Excluded apps, or apps outside the allowed list. Always
+                // passes until the source app is detected.
+                if !settings.captures_from(item.source_app.as_deref()) {
+                    continue;
+                }
 
-                println!(
-                    "[ClipSlot] Captured: id={} hash={}.. len={} at={}",
-                    item.id,
-                    &item.content_hash[..12],
-                    item.content.len(),
-                    item.created_at
-                );
-
-                // Persist to database (with dedup check)
-                match db.insert_item(&item) {
-                    Ok(true) => {
-                        // Enforce history limit
-                        if let Err(e) = db.enforce_history_limit() {
-                            eprintln!("[ClipSlot] Failed to enforce limit: {}", e);
-                        }
-                        // Emit event to frontend
-                        let _ = app_handle.emit("clipboard-changed", &item);
-
-                        // Push to sync if enabled
-                        if let (Some(ref sync), Some(ref rt)) = (&sync_manager, &rt) {
-                            let item_id = item.id.clone();
-                            let db_ref = db.clone();
-                            let sync_ref = sync.clone();
-                            rt.spawn(async move {
-                                if let Ok(Some((encrypted, hash))) =
-                                    db_ref.get_item_encrypted(&item_id)
-                                {
-                                    sync_ref
-                                        .notify_history_push(&item_id, &encrypted, &hash)
-                                        .await;
-                                }
-                            });
-                        }
-                    }
-                    Ok(false) => {
-                        // Duplicate detected, skip
-                    }
-                    Err(e) => {
-                        eprintln!("[ClipSlot] Failed to persist item: {}", e);
-                    }
+                if let Some(item) = bursts.push(item, Instant::now()) {
+                    capture(&app_handle, &db, &sync_manager, rt.as_ref(), item);
                 }
             }
         });
     }
 }
+
+/// Store a captured change, tell the history window and sync it.
+#[cfg(desktop)]
+fn capture<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    db: &Arc<Database>,
+    sync_manager: &Option<Arc<SyncManager>>,
+    rt: Option<&tokio::runtime::Runtime>,
+    item: ClipboardItem,
+) {
+    tracing::info!(
+        id = %item.id,
+        content = %item.content,
+        at = item.created_at,
+        "Captured"
+    );
+
+    // Persist to database (with dedup check)
+    match db.insert_item(&item) {
+        Ok(true) => {
+            crate::telemetry::record(crate::telemetry::Event::Capture);
+            // Enforce history limit
+            if let Err(e) = db.enforce_history_limit() {
+                tracing::error!("Failed to enforce limit: {}", e);
+            }
+            // Emit event to frontend
+            let _ = app_handle.emit("clipboard-changed", &item);
+
+            // Show links by their page title, if allowed to look it up
+            if let Some(url) = link::web_url(&item.content) {
+                if db.get_setting("fetch_link_titles").as_deref() == Some("true") {
+                    spawn_title_fetch(app_handle.clone(), db.clone(), item.id.clone(), url);
+                }
+            }
+
+            // Push to sync if enabled
+            if let (Some(sync), Some(rt)) = (sync_manager, rt) {
+                let item_id = item.id.clone();
+                let db_ref = db.clone();
+                let sync_ref = sync.clone();
+                rt.spawn(async move {
+                    if let Ok(Some((encrypted, hash))) = db_ref.get_item_encrypted(&item_id) {
+                        sync_ref
+                            .notify_history_push(&item_id, &encrypted, &hash)
+                            .await;
+                    }
+                });
+            }
+        }
+        Ok(false) => {
+            // Duplicate detected, skip
+        }
+        Err(e) => {
+            tracing::error!("Failed to persist item: {}", e);
+        }
+    }
+}
+
+impl<R: Runtime> ClipboardBackend for AppHandle<R> {
+    fn read_text(&self) -> Result<String, String> {
+        self.clipboard().read_text().map_err(|e| e.to_string())
+    }
+
+    fn write_text(&self, text: &str) -> Result<(), String> {
+        self.clipboard().write_text(text).map_err(|e| e.to_string())
+    }
+}
+
+/// Fetch a link's page title in the background, store it and tell the
+/// history window.
+#[cfg(desktop)]
+fn spawn_title_fetch<R: Runtime>(
+    app_handle: AppHandle<R>,
+    db: Arc<Database>,
+    item_id: String,
+    url: url::Url,
+) {
+    tauri::async_runtime::spawn(async move {
+        let title = match link::fetch_title(&url).await {
+            Ok(Some(title)) => title,
+            Ok(None) => return,
+            Err(e) => {
+                tracing::error!("Failed to fetch link title: {}", e);
+                return;
+            }
+        };
+        let (id, stored) = (item_id.clone(), title.clone());
+        match db.call(move |db| db.set_link_title(&id, &stored)).await {
+            Ok(()) => {
+                let payload = serde_json::json!({ "id": item_id, "title": title });
+                let _ = app_handle.emit("link-title-fetched", payload);
+            }
+            Err(e) => tracing::error!("Failed to save link title: {}", e),
+        }
+    });
+}
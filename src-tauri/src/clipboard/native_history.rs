@@ -0,0 +1,130 @@
+use rusqlite::Result as SqliteResult;
+
+use super::item::ClipboardItem;
+use crate::storage::database::Database;
+
+/// A text entry of the operating system's own clipboard history.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NativeEntry {
+    pub text: String,
+    /// When it was copied, ms since epoch
+    pub copied_at: i64,
+}
+
+/// Ticks (100ns) between 1601-01-01, where Windows times start, and the
+/// Unix epoch.
+const WINDOWS_EPOCH_OFFSET: i64 = 116_444_736_000_000_000;
+
+/// A Windows `DateTime` as ms since the Unix epoch.
+pub fn windows_time_to_unix_ms(ticks: i64) -> i64 {
+    (ticks - WINDOWS_EPOCH_OFFSET) / 10_000
+}
+
+/// Text entries of the Windows clipboard history (Win+V). `None` when
+/// there's none to read: history is turned off in Windows settings, or
+/// the system doesn't keep one.
+#[cfg(target_os = "windows")]
+pub fn read_entries() -> Option<Vec<NativeEntry>> {
+    use windows::ApplicationModel::DataTransfer::{
+        Clipboard, ClipboardHistoryItemsResultStatus, StandardDataFormats,
+    };
+
+    if !Clipboard::IsHistoryEnabled().ok()? {
+        return None;
+    }
+    let result = Clipboard::GetHistoryItemsAsync().ok()?.get().ok()?;
+    if result.Status().ok()? != ClipboardHistoryItemsResultStatus::Success {
+        return None;
+    }
+    let text_format = StandardDataFormats::Text().ok()?;
+
+    let mut entries = Vec::new();
+    for item in result.Items().ok()? {
+        // Images and files are skipped, as the monitor only captures text
+        let Ok(content) = item.Content() else {
+            continue;
+        };
+        if !content.Contains(&text_format).unwrap_or(false) {
+            continue;
+        }
+        let Ok(text) = content.GetTextAsync().and_then(|op| op.get()) else {
+            continue;
+        };
+        let copied_at = item
+            .Timestamp()
+            .map(|t| windows_time_to_unix_ms(t.UniversalTime))
+            .unwrap_or_else(|_| chrono::Utc::now().timestamp_millis());
+        entries.push(NativeEntry {
+            text: text.to_string(),
+            copied_at,
+        });
+    }
+    Some(entries)
+}
+
+/// Only Windows has a clipboard history apps can read.
+#[cfg(not(target_os = "windows"))]
+pub fn read_entries() -> Option<Vec<NativeEntry>> {
+    None
+}
+
+/// History items for `entries`, oldest first, keeping when each was copied.
+pub fn to_items(entries: Vec<NativeEntry>, device_id: &str) -> Vec<ClipboardItem> {
+    let mut items: Vec<ClipboardItem> = entries
+        .into_iter()
+        .filter(|entry| !entry.text.trim().is_empty())
+        .map(|entry| {
+            let mut item = ClipboardItem::new(entry.text, device_id);
+            item.created_at = entry.copied_at;
+            item
+        })
+        .collect();
+    items.sort_by_key(|item| item.created_at);
+    items
+}
+
+/// Add the Windows clipboard history to ClipSlot's, skipping content it
+/// already has. Returns how many items were added.
+pub fn import(db: &Database, device_id: &str) -> SqliteResult<usize> {
+    let Some(entries) = read_entries() else {
+        return Ok(0);
+    };
+    let mut imported = 0;
+    for item in to_items(entries, device_id) {
+        if db.has_item_with_hash(&item.content_hash)? {
+            continue;
+        }
+        if db.insert_item(&item)? {
+            imported += 1;
+        }
+    }
+    db.enforce_history_limit()?;
+    tracing::info!("Imported {} items from the system clipboard history", imported);
+    Ok(imported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_windows_time() {
+        // 2026-01-01T00:00:00Z
+        assert_eq!(windows_time_to_unix_ms(134_116_992_000_000_000), 1_767_225_600_000);
+        assert_eq!(windows_time_to_unix_ms(WINDOWS_EPOCH_OFFSET), 0);
+    }
+
+    #[test]
+    fn test_to_items_oldest_first() {
+        let entry = |text: &str, copied_at| NativeEntry {
+            text: text.to_string(),
+            copied_at,
+        };
+        let entries = vec![entry("newer", 2_000), entry("  \n", 1_500), entry("older", 1_000)];
+        let items = to_items(entries, "device");
+        let texts: Vec<&str> = items.iter().map(|i| i.content.as_str()).collect();
+        assert_eq!(texts, vec!["older", "newer"]);
+        assert_eq!(items[0].created_at, 1_000);
+        assert_eq!(items[0].device_id, "device");
+    }
+}
@@ -0,0 +1,69 @@
+use qrcode::types::QrError;
+use qrcode::{Color, QrCode};
+
+/// Pixels per QR module.
+const MODULE_PX: usize = 8;
+/// Light border around the code, in modules. Scanners expect at least 4.
+const QUIET_ZONE: usize = 4;
+
+/// Render `text` as a black-on-white QR code PNG.
+pub fn render_png(text: &str) -> Result<Vec<u8>, String> {
+    let code = QrCode::new(text.as_bytes()).map_err(|e| match e {
+        QrError::DataTooLong => "Too long to fit in a QR code".to_string(),
+        e => format!("Can't make a QR code: {}", e),
+    })?;
+    let modules = code.width();
+    let size = (modules + 2 * QUIET_ZONE) * MODULE_PX;
+
+    let mut pixels = vec![255u8; size * size];
+    for (i, color) in code.to_colors().into_iter().enumerate() {
+        if color != Color::Dark {
+            continue;
+        }
+        let x = (i % modules + QUIET_ZONE) * MODULE_PX;
+        let y = (i / modules + QUIET_ZONE) * MODULE_PX;
+        for row in y..y + MODULE_PX {
+            pixels[row * size + x..row * size + x + MODULE_PX].fill(0);
+        }
+    }
+
+    let mut png = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png, size as u32, size as u32);
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .and_then(|mut writer| {
+            writer.write_image_data(&pixels)?;
+            writer.finish()
+        })
+        .map_err(|e| format!("PNG encode error: {}", e))?;
+    Ok(png)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_png() {
+        let png = render_png("https://example.com").unwrap();
+        let decoder = png::Decoder::new(png.as_slice());
+        let mut reader = decoder.read_info().unwrap();
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut pixels).unwrap();
+
+        let modules = QrCode::new("https://example.com").unwrap().width();
+        let size = (modules + 2 * QUIET_ZONE) * MODULE_PX;
+        assert_eq!((info.width as usize, info.height as usize), (size, size));
+        // Quiet zone is light, the top-left finder pattern starts dark
+        assert_eq!(pixels[0], 255);
+        let corner = QUIET_ZONE * MODULE_PX;
+        assert_eq!(pixels[corner * size + corner], 0);
+    }
+
+    #[test]
+    fn test_render_png_too_long() {
+        assert!(render_png(&"x".repeat(5000)).is_err());
+    }
+}
@@ -0,0 +1,142 @@
+use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime};
+use serde::{Deserialize, Serialize};
+
+/// What the capture schedule does during its time window.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScheduleMode {
+    /// Capture at any time
+    Off,
+    /// Don't capture during the window
+    Skip,
+    /// Capture only during the window
+    Only,
+}
+
+/// A weekly time window in local time that turns capturing off, or limits it,
+/// e.g. for employers that don't allow clipboard history at work.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CaptureSchedule {
+    pub mode: ScheduleMode,
+    /// Days the window starts on, 1 (Monday) to 7 (Sunday)
+    pub days: Vec<u8>,
+    /// Start of the window as `HH:MM`
+    pub start: String,
+    /// End of the window as `HH:MM`. Before `start`, the window runs past
+    /// midnight into the next day.
+    pub end: String,
+}
+
+impl Default for CaptureSchedule {
+    fn default() -> Self {
+        CaptureSchedule {
+            mode: ScheduleMode::Off,
+            days: vec![1, 2, 3, 4, 5],
+            start: "09:00".to_string(),
+            end: "17:00".to_string(),
+        }
+    }
+}
+
+impl CaptureSchedule {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(day) = self.days.iter().find(|d| !(1..=7).contains(*d)) {
+            return Err(format!("Invalid schedule day: {} (use 1 to 7)", day));
+        }
+        let start = parse_time(&self.start)?;
+        if start == parse_time(&self.end)? {
+            return Err("The schedule must start and end at different times".to_string());
+        }
+        Ok(())
+    }
+
+    /// Whether clipboard changes at local time `now` may be captured.
+    pub fn allows(&self, now: NaiveDateTime) -> bool {
+        match self.mode {
+            ScheduleMode::Off => true,
+            ScheduleMode::Skip => !self.in_window(now),
+            ScheduleMode::Only => self.in_window(now),
+        }
+    }
+
+    fn in_window(&self, now: NaiveDateTime) -> bool {
+        let (Ok(start), Ok(end)) = (parse_time(&self.start), parse_time(&self.end)) else {
+            return false;
+        };
+        let starts_on = |date: NaiveDate| {
+            self.days.contains(&(date.weekday().number_from_monday() as u8))
+        };
+        let time = now.time();
+        if start < end {
+            starts_on(now.date()) && start <= time && time < end
+        } else {
+            // Past midnight: the evening part, or the morning after a start day
+            (starts_on(now.date()) && time >= start)
+                || (time < end && now.date().pred_opt().is_some_and(starts_on))
+        }
+    }
+}
+
+fn parse_time(s: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(s, "%H:%M").map_err(|_| format!("Invalid time: {} (use HH:MM)", s))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> NaiveDateTime {
+        // 2026-03-02 is a Monday
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    #[test]
+    fn test_work_hours() {
+        let skip = CaptureSchedule {
+            mode: ScheduleMode::Skip,
+            ..Default::default()
+        };
+        assert!(!skip.allows(at("2026-03-02 09:00")));
+        assert!(!skip.allows(at("2026-03-06 16:59")));
+        assert!(skip.allows(at("2026-03-02 17:00")));
+        assert!(skip.allows(at("2026-03-02 08:59")));
+        // Saturday
+        assert!(skip.allows(at("2026-03-07 12:00")));
+
+        let only = CaptureSchedule {
+            mode: ScheduleMode::Only,
+            ..skip
+        };
+        assert!(only.allows(at("2026-03-02 12:00")));
+        assert!(!only.allows(at("2026-03-07 12:00")));
+        assert!(CaptureSchedule::default().allows(at("2026-03-02 12:00")));
+    }
+
+    #[test]
+    fn test_window_past_midnight() {
+        // Friday night into Saturday morning
+        let schedule = CaptureSchedule {
+            mode: ScheduleMode::Skip,
+            days: vec![5],
+            start: "22:00".to_string(),
+            end: "06:00".to_string(),
+        };
+        assert!(!schedule.allows(at("2026-03-06 23:00")));
+        assert!(!schedule.allows(at("2026-03-07 05:00")));
+        assert!(schedule.allows(at("2026-03-07 23:00")));
+        assert!(schedule.allows(at("2026-03-06 05:00")));
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(CaptureSchedule::default().validate().is_ok());
+        for bad in [
+            CaptureSchedule { days: vec![0], ..Default::default() },
+            CaptureSchedule { start: "9am".to_string(), ..Default::default() },
+            CaptureSchedule { end: "09:00".to_string(), ..Default::default() },
+        ] {
+            assert!(bad.validate().is_err(), "{:?}", bad);
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde::Deserialize;
+
+/// Text conversions offered as "Copy as..." in the history window.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Transform {
+    Uppercase,
+    Lowercase,
+    Slugify,
+    JsonPretty,
+    JsonMinify,
+    Base64Encode,
+    Base64Decode,
+    UrlEncode,
+    UrlDecode,
+}
+
+impl Transform {
+    pub fn apply(self, text: &str) -> Result<String, String> {
+        match self {
+            Transform::Uppercase => Ok(text.to_uppercase()),
+            Transform::Lowercase => Ok(text.to_lowercase()),
+            Transform::Slugify => Ok(slugify(text)),
+            Transform::JsonPretty => parse_json(text)
+                .and_then(|v| serde_json::to_string_pretty(&v).map_err(|e| e.to_string())),
+            Transform::JsonMinify => {
+                parse_json(text).and_then(|v| serde_json::to_string(&v).map_err(|e| e.to_string()))
+            }
+            Transform::Base64Encode => Ok(BASE64.encode(text.as_bytes())),
+            Transform::Base64Decode => {
+                let bytes = BASE64
+                    .decode(text.trim())
+                    .map_err(|e| format!("Not valid base64: {}", e))?;
+                String::from_utf8(bytes).map_err(|_| "Decoded data is not text".to_string())
+            }
+            Transform::UrlEncode => Ok(url_encode(text)),
+            Transform::UrlDecode => url_decode(text.trim()),
+        }
+    }
+}
+
+fn parse_json(text: &str) -> Result<serde_json::Value, String> {
+    serde_json::from_str(text).map_err(|e| format!("Not valid JSON: {}", e))
+}
+
+/// "Hello, World!" -> "hello-world"
+fn slugify(text: &str) -> String {
+    let mut slug = String::new();
+    for c in text.chars().flat_map(char::to_lowercase) {
+        if c.is_alphanumeric() {
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.trim_end_matches('-').to_string()
+}
+
+/// Percent-encode everything but unreserved characters (RFC 3986), so the
+/// result is safe anywhere in a URL.
+fn url_encode(text: &str) -> String {
+    let mut out = String::new();
+    for b in text.bytes() {
+        if b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.' | b'~') {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{:02X}", b));
+        }
+    }
+    out
+}
+
+fn url_decode(text: &str) -> Result<String, String> {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = text
+                .get(i + 1..i + 3)
+                .and_then(|h| u8::from_str_radix(h, 16).ok())
+                .ok_or_else(|| format!("Invalid escape at position {}", i))?;
+            out.push(hex);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(out).map_err(|_| "Decoded data is not text".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_case_and_slug() {
+        assert_eq!(Transform::Uppercase.apply("Straße").unwrap(), "STRASSE");
+        assert_eq!(Transform::Slugify.apply("  Hello, World! 2025 ").unwrap(), "hello-world-2025");
+    }
+
+    #[test]
+    fn test_json_keeps_key_order() {
+        let minified = Transform::JsonMinify.apply("{ \"b\": 1,\n \"a\": [1, 2] }").unwrap();
+        assert_eq!(minified, r#"{"b":1,"a":[1,2]}"#);
+        let pretty = Transform::JsonPretty.apply(&minified).unwrap();
+        assert!(pretty.starts_with("{\n  \"b\": 1"));
+        assert!(Transform::JsonPretty.apply("not json").is_err());
+    }
+
+    #[test]
+    fn test_round_trips() {
+        let text = "a b/c?d=é&e";
+        for (encode, decode) in [
+            (Transform::Base64Encode, Transform::Base64Decode),
+            (Transform::UrlEncode, Transform::UrlDecode),
+        ] {
+            let encoded = encode.apply(text).unwrap();
+            assert_eq!(decode.apply(&encoded).unwrap(), text);
+        }
+        assert_eq!(Transform::UrlEncode.apply(text).unwrap(), "a%20b%2Fc%3Fd%3D%C3%A9%26e");
+        assert!(Transform::UrlDecode.apply("%zz").is_err());
+        assert!(Transform::Base64Decode.apply("!!!").is_err());
+    }
+}
@@ -0,0 +1,154 @@
+use std::sync::Arc;
+use std::time::Instant;
+
+use super::backend::ClipboardBackend;
+use super::capture_state::CaptureState;
+use super::item::ClipboardItem;
+use super::loop_guard::LoopGuard;
+
+/// Decides which clipboard changes the monitor captures: new content only,
+/// and not while paused, right after the app wrote to the clipboard or while
+/// another app keeps writing back what the app wrote.
+pub struct ChangeWatcher {
+    state: Arc<CaptureState>,
+    last_hash: Option<String>,
+    was_paused: bool,
+    loops: LoopGuard,
+}
+
+impl ChangeWatcher {
+    /// `initial` is what's on the clipboard at startup, which isn't captured.
+    pub fn new(state: Arc<CaptureState>, initial: Option<&str>) -> Self {
+        ChangeWatcher {
+            state,
+            last_hash: initial
+                .filter(|text| !text.is_empty())
+                .map(ClipboardItem::hash_content),
+            was_paused: false,
+            loops: LoopGuard::default(),
+        }
+    }
+
+    /// Look at the clipboard once; the text to capture, if it changed.
+    pub fn poll(&mut self, clipboard: &dyn ClipboardBackend) -> Option<String> {
+        // The clipboard isn't read during a pause
+        if self.state.is_paused() {
+            self.was_paused = true;
+            return None;
+        }
+
+        let text = clipboard.read_text().ok().filter(|t| !t.is_empty())?;
+        let hash = ClipboardItem::hash_content(&text);
+
+        if self.last_hash.as_ref() == Some(&hash) {
+            self.was_paused = false;
+            return None;
+        }
+
+        self.last_hash = Some(hash.clone());
+
+        // What was copied during a pause stays uncaptured after it,
+        // and so does anything the app wrote since
+        if std::mem::take(&mut self.was_paused) {
+            self.state.take_change();
+            return None;
+        }
+
+        // Paused meanwhile, or the app itself wrote to the clipboard
+        let ours = !self.state.take_change();
+        let looping = self.loops.observe(&hash, ours, Instant::now());
+        if ours || looping {
+            return None;
+        }
+        Some(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clipboard::backend::fake::FakeClipboard;
+
+    fn watcher(clipboard: &FakeClipboard) -> (ChangeWatcher, Arc<CaptureState>) {
+        let state = Arc::new(CaptureState::default());
+        let text = clipboard.read_text().ok();
+        (ChangeWatcher::new(state.clone(), text.as_deref()), state)
+    }
+
+    #[test]
+    fn test_captures_new_content_once() {
+        let clipboard = FakeClipboard::with_text("already there");
+        let (mut watcher, _) = watcher(&clipboard);
+        assert_eq!(watcher.poll(&clipboard), None);
+
+        clipboard.copy("first");
+        assert_eq!(watcher.poll(&clipboard).as_deref(), Some("first"));
+        assert_eq!(watcher.poll(&clipboard), None);
+
+        clipboard.copy("");
+        assert_eq!(watcher.poll(&clipboard), None);
+        clipboard.copy("second");
+        assert_eq!(watcher.poll(&clipboard).as_deref(), Some("second"));
+        // Copying the previous content again is a change
+        clipboard.copy("first");
+        assert_eq!(watcher.poll(&clipboard).as_deref(), Some("first"));
+    }
+
+    #[test]
+    fn test_skip_next() {
+        let clipboard = FakeClipboard::default();
+        let (mut watcher, state) = watcher(&clipboard);
+
+        state.skip_next();
+        clipboard.write_text("written by the app").unwrap();
+        assert_eq!(watcher.poll(&clipboard), None);
+
+        clipboard.copy("copied by the user");
+        assert_eq!(watcher.poll(&clipboard).as_deref(), Some("copied by the user"));
+    }
+
+    #[test]
+    fn test_content_written_back_is_not_captured() {
+        let clipboard = FakeClipboard::default();
+        let (mut watcher, state) = watcher(&clipboard);
+
+        state.skip_next();
+        clipboard.write_text("synced").unwrap();
+        assert_eq!(watcher.poll(&clipboard), None);
+        clipboard.copy("other manager");
+        assert_eq!(watcher.poll(&clipboard).as_deref(), Some("other manager"));
+        clipboard.copy("synced");
+        assert_eq!(watcher.poll(&clipboard).as_deref(), Some("synced"));
+        clipboard.copy("other manager");
+        assert_eq!(watcher.poll(&clipboard).as_deref(), Some("other manager"));
+
+        // The third time round is a loop
+        clipboard.copy("synced");
+        assert_eq!(watcher.poll(&clipboard), None);
+        clipboard.copy("copied by the user");
+        assert_eq!(watcher.poll(&clipboard), None);
+    }
+
+    #[test]
+    fn test_changes_during_pause_are_not_captured() {
+        let clipboard = FakeClipboard::with_text("before");
+        let (mut watcher, state) = watcher(&clipboard);
+
+        state.pause();
+        clipboard.copy("during");
+        assert_eq!(watcher.poll(&clipboard), None);
+        state.resume();
+        assert_eq!(watcher.poll(&clipboard), None);
+
+        // Content restored during the pause isn't a change either
+        state.pause();
+        clipboard.copy("temporary");
+        assert_eq!(watcher.poll(&clipboard), None);
+        clipboard.copy("during");
+        state.resume();
+        assert_eq!(watcher.poll(&clipboard), None);
+
+        clipboard.copy("after");
+        assert_eq!(watcher.poll(&clipboard).as_deref(), Some("after"));
+    }
+}
@@ -0,0 +1,71 @@
+use std::time::Duration;
+
+use super::backend::ClipboardBackend;
+
+/// Empty the clipboard. Nothing is skipped: the monitor never captures empty
+/// content, so a skip would swallow the user's next copy instead.
+pub fn clear(clipboard: &dyn ClipboardBackend) -> Result<(), String> {
+    clipboard.write_text("")
+}
+
+/// Clear the clipboard if it still holds `text`, leaving anything copied
+/// since alone. Whether it was cleared.
+pub fn clear_if_unchanged(clipboard: &dyn ClipboardBackend, text: &str) -> Result<bool, String> {
+    if clipboard.read_text()? != text {
+        return Ok(false);
+    }
+    clear(clipboard)?;
+    Ok(true)
+}
+
+/// Clear `text` from the clipboard after `delay`, on a thread of its own.
+pub fn clear_after<B>(clipboard: B, text: String, delay: Duration)
+where
+    B: ClipboardBackend + Send + 'static,
+{
+    std::thread::spawn(move || {
+        std::thread::sleep(delay);
+        match clear_if_unchanged(&clipboard, &text) {
+            Ok(true) => tracing::info!("Cleared clipboard after {}s", delay.as_secs()),
+            Ok(false) => {}
+            Err(e) => tracing::error!("Failed to clear clipboard: {}", e),
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clipboard::backend::fake::FakeClipboard;
+
+    #[test]
+    fn test_clear_if_unchanged() {
+        let clipboard = FakeClipboard::with_text("hunter2");
+        assert_eq!(clear_if_unchanged(&clipboard, "hunter2"), Ok(true));
+        assert_eq!(clipboard.text(), "");
+
+        clipboard.copy("copied since");
+        assert_eq!(clear_if_unchanged(&clipboard, "hunter2"), Ok(false));
+        assert_eq!(clipboard.text(), "copied since");
+    }
+
+    #[cfg(desktop)]
+    #[test]
+    fn test_clear_is_not_captured() {
+        use std::sync::Arc;
+
+        use crate::clipboard::capture_state::CaptureState;
+        use crate::clipboard::watcher::ChangeWatcher;
+
+        let clipboard = FakeClipboard::with_text("hunter2");
+        let mut watcher =
+            ChangeWatcher::new(Arc::new(CaptureState::default()), Some("hunter2"));
+
+        clear(&clipboard).unwrap();
+        assert_eq!(watcher.poll(&clipboard), None);
+
+        // The next copy is still captured
+        clipboard.copy("copied later");
+        assert_eq!(watcher.poll(&clipboard).as_deref(), Some("copied later"));
+    }
+}
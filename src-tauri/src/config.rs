@@ -1,6 +1,7 @@
-/// Default sync server URL.
-/// Override at build time: SYNC_SERVER_URL=https://example.com cargo build
-pub const SYNC_SERVER_URL: &str = match option_env!("SYNC_SERVER_URL") {
-    Some(url) => url,
-    None => "https://clipslot-production.up.railway.app",
-};
+pub use clipslot_core::config::SYNC_SERVER_URL;
+
+/// Public key update bundles are signed with (from `tauri signer generate`).
+/// Set at build time: UPDATER_PUBKEY=... cargo build. Without it, builds
+/// don't look for updates.
+#[cfg(desktop)]
+pub const UPDATER_PUBKEY: Option<&str> = option_env!("UPDATER_PUBKEY");
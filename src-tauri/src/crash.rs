@@ -0,0 +1,221 @@
+//! A tray app that crashes just disappears, so panics are written to
+//! `<data dir>/crashes` for the user to share from the Debug tab.
+
+use std::panic::PanicHookInfo;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use serde::Serialize;
+
+/// Reports kept; older ones are deleted as new ones are written.
+const MAX_REPORTS: usize = 10;
+
+/// Where reports go, and the OS details for them. Worked out up front, since
+/// the hook shouldn't run commands.
+struct Context {
+    dir: PathBuf,
+    os: String,
+}
+
+static CONTEXT: OnceLock<Context> = OnceLock::new();
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CrashReport {
+    pub file_name: String,
+    /// Milliseconds since the epoch
+    pub created_at: i64,
+    /// The panic message, e.g. `index out of bounds: ...`
+    pub message: String,
+}
+
+/// Write a report for every panic from now on, then carry on as before
+/// (printing it, and aborting or unwinding).
+pub fn install(data_dir: &Path) {
+    let os =
+        crate::sync::device_info::os_version().unwrap_or_else(|| std::env::consts::OS.to_string());
+    let context = Context {
+        dir: crashes_dir(data_dir),
+        os: format!("{} ({})", os, std::env::consts::ARCH),
+    };
+    if CONTEXT.set(context).is_err() {
+        return;
+    }
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        // No logging here: the panic may have happened while the log was
+        // locked
+        if let Some(path) = write_report(info) {
+            tracing::error!("Crash report saved to {}", path.display());
+        }
+        previous(info);
+    }));
+}
+
+pub fn crashes_dir(data_dir: &Path) -> PathBuf {
+    data_dir.join("crashes")
+}
+
+fn write_report(info: &PanicHookInfo) -> Option<PathBuf> {
+    let context = CONTEXT.get()?;
+    let now = chrono::Local::now();
+    let thread = std::thread::current();
+    let report = format_report(
+        &panic_message(info.payload()),
+        &info.location().map(|l| l.to_string()).unwrap_or_default(),
+        thread.name().unwrap_or("unnamed"),
+        &context.os,
+        &now.to_rfc3339(),
+        &std::backtrace::Backtrace::force_capture().to_string(),
+    );
+    std::fs::create_dir_all(&context.dir).ok()?;
+    let path = context
+        .dir
+        .join(format!("crash-{}.txt", now.format("%Y%m%d-%H%M%S-%3f")));
+    std::fs::write(&path, report).ok()?;
+    prune(&context.dir, MAX_REPORTS);
+    Some(path)
+}
+
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "(no message)".to_string()
+    }
+}
+
+fn format_report(
+    message: &str,
+    location: &str,
+    thread: &str,
+    os: &str,
+    time: &str,
+    backtrace: &str,
+) -> String {
+    format!(
+        "ClipSlot crash report\n\
+         Version: {}\n\
+         OS: {}\n\
+         Time: {}\n\
+         Thread: {}\n\
+         \n\
+         Panic: {}\n\
+         At: {}\n\
+         \n\
+         Backtrace:\n{}\n",
+        env!("CARGO_PKG_VERSION"),
+        os,
+        time,
+        thread,
+        message,
+        location,
+        backtrace
+    )
+}
+
+/// The panic message line of a report.
+fn message_of(report: &str) -> String {
+    report
+        .lines()
+        .find_map(|line| line.strip_prefix("Panic: "))
+        .unwrap_or("")
+        .to_string()
+}
+
+/// Reports in `dir`, newest first.
+pub fn list(dir: &Path) -> Vec<CrashReport> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut reports: Vec<CrashReport> = entries
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let file_name = entry.file_name().into_string().ok()?;
+            if !is_report_name(&file_name) {
+                return None;
+            }
+            let created_at = entry
+                .metadata()
+                .and_then(|m| m.modified())
+                .ok()
+                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                .map_or(0, |d| d.as_millis() as i64);
+            let text = std::fs::read_to_string(entry.path()).unwrap_or_default();
+            Some(CrashReport {
+                file_name,
+                created_at,
+                message: message_of(&text),
+            })
+        })
+        .collect();
+    // Names sort by the time they were written
+    reports.sort_by(|a, b| b.file_name.cmp(&a.file_name));
+    reports
+}
+
+/// Text of the report called `file_name` in `dir`.
+pub fn read(dir: &Path, file_name: &str) -> Result<String, String> {
+    if !is_report_name(file_name) {
+        return Err(format!("Not a crash report: {}", file_name));
+    }
+    std::fs::read_to_string(dir.join(file_name))
+        .map_err(|e| format!("Failed to read crash report: {}", e))
+}
+
+fn is_report_name(name: &str) -> bool {
+    name.starts_with("crash-") && name.ends_with(".txt") && !name.contains(['/', '\\'])
+}
+
+fn prune(dir: &Path, keep: usize) {
+    for report in list(dir).into_iter().skip(keep) {
+        let _ = std::fs::remove_file(dir.join(report.file_name));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_has_message_and_version() {
+        let report = format_report(
+            "index out of bounds",
+            "src/lib.rs:10:5",
+            "main",
+            "macOS 15.1 (aarch64)",
+            "2026-10-16T14:00:00+02:00",
+            "0: clipslot::run",
+        );
+        assert!(report.contains(&format!("Version: {}", env!("CARGO_PKG_VERSION"))));
+        assert!(report.contains("OS: macOS 15.1 (aarch64)"));
+        assert_eq!(message_of(&report), "index out of bounds");
+        assert_eq!(panic_message(&"boom"), "boom");
+        assert_eq!(panic_message(&String::from("boom")), "boom");
+        assert_eq!(panic_message(&42), "(no message)");
+    }
+
+    #[test]
+    fn test_list_and_prune_reports() {
+        let dir = std::env::temp_dir().join(format!("clipslot-crash-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for i in 0..4 {
+            std::fs::write(
+                dir.join(format!("crash-20261016-14000{}-000.txt", i)),
+                format!("Panic: boom {}\n", i),
+            )
+            .unwrap();
+        }
+        std::fs::write(dir.join("notes.txt"), "not a report").unwrap();
+
+        prune(&dir, 3);
+        let reports = list(&dir);
+        assert_eq!(reports.len(), 3);
+        assert_eq!(reports[0].message, "boom 3");
+        assert_eq!(reports[2].file_name, "crash-20261016-140001-000.txt");
+        assert!(read(&dir, "../notes.txt").is_err());
+        assert!(dir.join("notes.txt").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
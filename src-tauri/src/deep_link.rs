@@ -0,0 +1,90 @@
+use url::Url;
+
+/// URL scheme registered for the app (see `plugins.deep-link` in tauri.conf.json).
+pub const SCHEME: &str = "clipslot";
+
+/// What a `clipslot://` link asks the app to do.
+#[derive(Debug, PartialEq)]
+pub enum DeepLinkAction {
+    /// `clipslot://paste-slot/3`
+    PasteSlot(u32),
+    /// `clipslot://save-slot/1`: saves the current clipboard
+    SaveSlot(u32),
+    /// `clipslot://paste-item/<id>`: pastes a history item
+    PasteItem(String),
+    /// `clipslot://search?q=...`: opens history filtered by the query
+    Search(String),
+}
+
+impl DeepLinkAction {
+    pub fn parse(url: &Url) -> Result<Self, String> {
+        if url.scheme() != SCHEME {
+            return Err(format!("Not a {}:// link: {}", SCHEME, url));
+        }
+        let arg = url.path().trim_matches('/');
+        match url.host_str().unwrap_or_default() {
+            "paste-slot" => Ok(DeepLinkAction::PasteSlot(parse_slot(arg)?)),
+            "save-slot" => Ok(DeepLinkAction::SaveSlot(parse_slot(arg)?)),
+            "paste-item" if !arg.is_empty() => Ok(DeepLinkAction::PasteItem(arg.to_string())),
+            "search" => {
+                let query = url
+                    .query_pairs()
+                    .find(|(key, _)| key == "q")
+                    .map(|(_, value)| value.into_owned())
+                    .unwrap_or_default();
+                Ok(DeepLinkAction::Search(query))
+            }
+            action => Err(format!("Unknown link action: {}", action)),
+        }
+    }
+}
+
+/// A slot number within the largest slot count; the configured count is
+/// checked by the caller.
+fn parse_slot(arg: &str) -> Result<u32, String> {
+    use crate::storage::database::MAX_SLOT_COUNT;
+    arg.parse::<u32>()
+        .ok()
+        .filter(|n| (1..=MAX_SLOT_COUNT).contains(n))
+        .ok_or_else(|| format!("Invalid slot number: {}", arg))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(link: &str) -> Result<DeepLinkAction, String> {
+        DeepLinkAction::parse(&Url::parse(link).unwrap())
+    }
+
+    #[test]
+    fn test_parse_slot_actions() {
+        assert_eq!(parse("clipslot://paste-slot/3"), Ok(DeepLinkAction::PasteSlot(3)));
+        assert_eq!(parse("clipslot://save-slot/1/"), Ok(DeepLinkAction::SaveSlot(1)));
+        assert!(parse("clipslot://paste-slot/0").is_err());
+        assert!(parse("clipslot://paste-slot/11").is_err());
+        assert!(parse("clipslot://save-slot/").is_err());
+    }
+
+    #[test]
+    fn test_parse_paste_item() {
+        assert_eq!(
+            parse("clipslot://paste-item/4b1e2c3d-0000-4000-8000-000000000001"),
+            Ok(DeepLinkAction::PasteItem(
+                "4b1e2c3d-0000-4000-8000-000000000001".to_string()
+            ))
+        );
+        assert!(parse("clipslot://paste-item/").is_err());
+    }
+
+    #[test]
+    fn test_parse_search() {
+        assert_eq!(
+            parse("clipslot://search?q=hello%20world"),
+            Ok(DeepLinkAction::Search("hello world".to_string()))
+        );
+        assert_eq!(parse("clipslot://search"), Ok(DeepLinkAction::Search(String::new())));
+        assert!(parse("clipslot://unknown/1").is_err());
+        assert!(parse("https://search?q=x").is_err());
+    }
+}
@@ -0,0 +1,460 @@
+use std::fmt::Display;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use serde::{Deserialize, Serialize};
+
+/// Language of the tray menu and notifications.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    /// The operating system's language, if translated; English otherwise
+    System,
+    En,
+    De,
+    Fr,
+    Es,
+}
+
+/// Translated languages, in the order of each `STRINGS` row.
+const LOCALES: [(Language, &str); 4] = [
+    (Language::En, "en"),
+    (Language::De, "de"),
+    (Language::Fr, "fr"),
+    (Language::Es, "es"),
+];
+
+/// Index into `LOCALES` of the language in use.
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+
+/// Every string by key, in each of `LOCALES`. `{0}`, `{1}`... are filled in
+/// by `text_with`.
+const STRINGS: &[(&str, [&str; 4])] = &[
+    // ── Tray ──
+    (
+        "update_available",
+        [
+            "Update Available: v{0} — Install",
+            "Update verfügbar: v{0} — Installieren",
+            "Mise à jour disponible : v{0} — Installer",
+            "Actualización disponible: v{0} — Instalar",
+        ],
+    ),
+    (
+        "quit",
+        ["Quit ClipSlot", "ClipSlot beenden", "Quitter ClipSlot", "Salir de ClipSlot"],
+    ),
+    ("one_item", ["1 item", "1 Eintrag", "1 élément", "1 elemento"]),
+    ("items", ["{0} items", "{0} Einträge", "{0} éléments", "{0} elementos"]),
+    (
+        "last_capture",
+        [
+            "{0} · last capture {1}",
+            "{0} · zuletzt kopiert {1}",
+            "{0} · dernière copie {1}",
+            "{0} · última copia {1}",
+        ],
+    ),
+    ("connected", ["Connected", "Verbunden", "Connecté", "Conectado"]),
+    ("connecting", ["Connecting...", "Verbinde...", "Connexion...", "Conectando..."]),
+    (
+        "syncing",
+        ["Syncing...", "Synchronisiere...", "Synchronisation...", "Sincronizando..."],
+    ),
+    ("offline", ["Offline", "Offline", "Hors ligne", "Sin conexión"]),
+    (
+        "working_offline",
+        [
+            "Working offline",
+            "Offline-Modus",
+            "Travail hors ligne",
+            "Trabajando sin conexión",
+        ],
+    ),
+    (
+        "working_offline_one_queued",
+        [
+            "Working offline, 1 change queued",
+            "Offline-Modus, 1 Änderung ausstehend",
+            "Travail hors ligne, 1 modification en attente",
+            "Trabajando sin conexión, 1 cambio pendiente",
+        ],
+    ),
+    (
+        "working_offline_queued",
+        [
+            "Working offline, {0} changes queued",
+            "Offline-Modus, {0} Änderungen ausstehend",
+            "Travail hors ligne, {0} modifications en attente",
+            "Trabajando sin conexión, {0} cambios pendientes",
+        ],
+    ),
+    (
+        "signed_in_as",
+        [
+            "Signed in as {0} — {1}",
+            "Angemeldet als {0} — {1}",
+            "Connecté en tant que {0} — {1}",
+            "Sesión iniciada como {0} — {1}",
+        ],
+    ),
+    (
+        "sync_now",
+        [
+            "Sync Now",
+            "Jetzt synchronisieren",
+            "Synchroniser maintenant",
+            "Sincronizar ahora",
+        ],
+    ),
+    ("slot_empty", ["{0}: (empty)", "{0}: (leer)", "{0} : (vide)", "{0}: (vacío)"]),
+    ("paste", ["Paste", "Einfügen", "Coller", "Pegar"]),
+    (
+        "copy_to_clipboard",
+        [
+            "Copy to Clipboard",
+            "In die Zwischenablage kopieren",
+            "Copier dans le presse-papiers",
+            "Copiar al portapapeles",
+        ],
+    ),
+    (
+        "clear_slot",
+        ["Clear Slot", "Slot leeren", "Vider l'emplacement", "Vaciar ranura"],
+    ),
+    ("recent", ["Recent", "Zuletzt", "Récents", "Recientes"]),
+    (
+        "paste_delete_newest",
+        [
+            "Paste and Delete Newest",
+            "Neuesten einfügen und löschen",
+            "Coller et supprimer le plus récent",
+            "Pegar y eliminar el más reciente",
+        ],
+    ),
+    (
+        "show_history",
+        [
+            "Show History",
+            "Verlauf anzeigen",
+            "Afficher l'historique",
+            "Mostrar historial",
+        ],
+    ),
+    (
+        "resume_monitoring",
+        [
+            "Resume Monitoring",
+            "Überwachung fortsetzen",
+            "Reprendre la surveillance",
+            "Reanudar supervisión",
+        ],
+    ),
+    (
+        "pause_monitoring",
+        [
+            "Pause Monitoring",
+            "Überwachung pausieren",
+            "Suspendre la surveillance",
+            "Pausar supervisión",
+        ],
+    ),
+    (
+        "pause_everywhere",
+        [
+            "Pause on All Devices",
+            "Auf allen Geräten pausieren",
+            "Suspendre sur tous les appareils",
+            "Pausar en todos los dispositivos",
+        ],
+    ),
+    (
+        "resume_everywhere",
+        [
+            "Resume on All Devices",
+            "Auf allen Geräten fortsetzen",
+            "Reprendre sur tous les appareils",
+            "Reanudar en todos los dispositivos",
+        ],
+    ),
+    (
+        "clear_clipboard",
+        [
+            "Clear Clipboard",
+            "Zwischenablage leeren",
+            "Vider le presse-papiers",
+            "Vaciar portapapeles",
+        ],
+    ),
+    ("settings", ["Settings...", "Einstellungen...", "Réglages...", "Ajustes..."]),
+    // ── Notifications ──
+    (
+        "clipboard_empty",
+        [
+            "Clipboard is empty",
+            "Die Zwischenablage ist leer",
+            "Le presse-papiers est vide",
+            "El portapapeles está vacío",
+        ],
+    ),
+    (
+        "saved_to_slot",
+        [
+            "Saved to {0}: {1}",
+            "In {0} gespeichert: {1}",
+            "Enregistré dans {0} : {1}",
+            "Guardado en {0}: {1}",
+        ],
+    ),
+    (
+        "save_to_slot_failed",
+        [
+            "Failed to save to Slot {0}",
+            "Speichern in Slot {0} fehlgeschlagen",
+            "Échec de l'enregistrement dans l'emplacement {0}",
+            "No se pudo guardar en la ranura {0}",
+        ],
+    ),
+    ("slot_is_empty", ["{0} is empty", "{0} ist leer", "{0} est vide", "{0} está vacío"]),
+    (
+        "deep_link_paste_off",
+        [
+            "A link asked to paste. Allow it in Settings > Privacy",
+            "Ein Link wollte einfügen. Erlauben unter Settings > Privacy",
+            "Un lien a demandé à coller. Autorisez-le dans Settings > Privacy",
+            "Un enlace pidió pegar. Permítelo en Settings > Privacy",
+        ],
+    ),
+    (
+        "copied_from",
+        ["Copied from {0}", "Kopiert von {0}", "Copié depuis {0}", "Copiado desde {0}"],
+    ),
+    (
+        "another_device",
+        ["another device", "einem anderen Gerät", "un autre appareil", "otro dispositivo"],
+    ),
+    (
+        "paused_from",
+        [
+            "Monitoring paused from {0}",
+            "Überwachung von {0} pausiert",
+            "Surveillance suspendue depuis {0}",
+            "Supervisión pausada desde {0}",
+        ],
+    ),
+    (
+        "resumed_from",
+        [
+            "Monitoring resumed from {0}",
+            "Überwachung von {0} fortgesetzt",
+            "Surveillance reprise depuis {0}",
+            "Supervisión reanudada desde {0}",
+        ],
+    ),
+    (
+        "update_ready",
+        [
+            "Version {0} is available. Install it from the tray menu.",
+            "Version {0} ist verfügbar. Installiere sie über das Tray-Menü.",
+            "La version {0} est disponible. Installez-la depuis le menu de la barre d'état.",
+            "La versión {0} está disponible. Instálala desde el menú de la bandeja.",
+        ],
+    ),
+    (
+        "database_salvaged",
+        [
+            "The history database was damaged. {0} records were recovered; the damaged \
+             file was kept at {1}.",
+            "Die Verlaufsdatenbank war beschädigt. {0} Datensätze wurden \
+             wiederhergestellt; die beschädigte Datei liegt unter {1}.",
+            "La base de données de l'historique était endommagée. {0} enregistrements \
+             ont été récupérés ; le fichier endommagé a été conservé dans {1}.",
+            "La base de datos del historial estaba dañada. Se recuperaron {0} \
+             registros; el archivo dañado se guardó en {1}.",
+        ],
+    ),
+    (
+        "database_reset",
+        [
+            "The history database was damaged and couldn't be recovered, so ClipSlot \
+             started a new one. The damaged file was kept at {0}.",
+            "Die Verlaufsdatenbank war beschädigt und konnte nicht wiederhergestellt \
+             werden, daher hat ClipSlot eine neue angelegt. Die beschädigte Datei liegt \
+             unter {0}.",
+            "La base de données de l'historique était endommagée et n'a pas pu être \
+             récupérée ; ClipSlot en a créé une nouvelle. Le fichier endommagé a été \
+             conservé dans {0}.",
+            "La base de datos del historial estaba dañada y no se pudo recuperar, así \
+             que ClipSlot creó una nueva. El archivo dañado se guardó en {0}.",
+        ],
+    ),
+    (
+        "key_unavailable",
+        [
+            "ClipSlot couldn't get its encryption key from the keychain, so history \
+             isn't being saved. Open ClipSlot to try again.",
+            "ClipSlot konnte seinen Schlüssel nicht aus dem Schlüsselbund lesen, daher \
+             wird der Verlauf nicht gespeichert. Öffne ClipSlot, um es erneut zu \
+             versuchen.",
+            "ClipSlot n'a pas pu obtenir sa clé de chiffrement depuis le trousseau ; \
+             l'historique n'est donc pas enregistré. Ouvrez ClipSlot pour réessayer.",
+            "ClipSlot no pudo obtener su clave de cifrado del llavero, así que el \
+             historial no se está guardando. Abre ClipSlot para volver a intentarlo.",
+        ],
+    ),
+    (
+        "crash_report_saved",
+        [
+            "ClipSlot ran into a problem last time and saved a crash report. You can \
+             share it from Settings → Debug.",
+            "ClipSlot ist beim letzten Mal auf ein Problem gestoßen und hat einen \
+             Absturzbericht gespeichert. Du kannst ihn unter Einstellungen → Debug teilen.",
+            "ClipSlot a rencontré un problème la dernière fois et a enregistré un rapport \
+             de plantage. Vous pouvez le partager depuis Réglages → Débogage.",
+            "ClipSlot tuvo un problema la última vez y guardó un informe de fallo. Puedes \
+             compartirlo desde Ajustes → Depuración.",
+        ],
+    ),    (
+        "system_history_found",
+        [
+            "Your Windows clipboard history has {0} items. Import them into ClipSlot \
+             from Settings → General.",
+            "Dein Windows-Zwischenablageverlauf enthält {0} Einträge. Du kannst sie unter \
+             Einstellungen → Allgemein in ClipSlot importieren.",
+            "Votre historique du presse-papiers Windows contient {0} éléments. Importez-les \
+             dans ClipSlot depuis Réglages → Général.",
+            "Tu historial del portapapeles de Windows tiene {0} elementos. Impórtalos en \
+             ClipSlot desde Ajustes → General.",
+        ],
+    ),
+];
+
+/// Use `language` for strings from now on.
+pub fn set_language(language: Language) {
+    let language = match language {
+        Language::System => system_locale()
+            .and_then(|locale| language_of(&locale))
+            .unwrap_or(Language::En),
+        language => language,
+    };
+    let index = LOCALES.iter().position(|(l, _)| *l == language).unwrap_or(0);
+    CURRENT.store(index, Ordering::Relaxed);
+}
+
+/// The string for `key` in the current language.
+pub fn text(key: &str) -> &'static str {
+    text_in(CURRENT.load(Ordering::Relaxed), key)
+}
+
+/// The string for `key` in the current language, with `{0}`, `{1}`...
+/// replaced by `args`.
+pub fn text_with(key: &str, args: &[&dyn Display]) -> String {
+    fill(text(key), args)
+}
+
+fn text_in(locale: usize, key: &str) -> &'static str {
+    match STRINGS.iter().find(|(k, _)| *k == key) {
+        Some((_, translations)) => translations[locale],
+        None => {
+            debug_assert!(false, "missing string {:?}", key);
+            ""
+        }
+    }
+}
+
+fn fill(template: &str, args: &[&dyn Display]) -> String {
+    args.iter()
+        .enumerate()
+        .fold(template.to_string(), |text, (i, arg)| {
+            text.replace(&format!("{{{}}}", i), &arg.to_string())
+        })
+}
+
+/// The translated language of a locale like `de_DE.UTF-8` or `fr-CA`.
+fn language_of(locale: &str) -> Option<Language> {
+    let code = locale
+        .split(['_', '-', '.', '@'])
+        .next()?
+        .to_ascii_lowercase();
+    LOCALES.iter().find(|(_, c)| *c == code).map(|(l, _)| *l)
+}
+
+/// The user's locale as the OS reports it, e.g. `de_DE.UTF-8`.
+fn system_locale() -> Option<String> {
+    for var in ["LC_ALL", "LC_MESSAGES", "LANG"] {
+        match std::env::var(var) {
+            Ok(value) if !value.is_empty() && value != "C" && value != "POSIX" => {
+                return Some(value)
+            }
+            _ => {}
+        }
+    }
+    platform_locale()
+}
+
+/// Apps started from Finder get no `LANG`.
+#[cfg(target_os = "macos")]
+fn platform_locale() -> Option<String> {
+    let output = std::process::Command::new("defaults")
+        .args(["read", "-g", "AppleLocale"])
+        .output()
+        .ok()?;
+    let locale = String::from_utf8(output.stdout).ok()?.trim().to_string();
+    (output.status.success() && !locale.is_empty()).then_some(locale)
+}
+
+#[cfg(target_os = "windows")]
+fn platform_locale() -> Option<String> {
+    extern "system" {
+        fn GetUserDefaultLocaleName(name: *mut u16, len: i32) -> i32;
+    }
+    // LOCALE_NAME_MAX_LENGTH
+    let mut name = [0u16; 85];
+    let len = unsafe { GetUserDefaultLocaleName(name.as_mut_ptr(), name.len() as i32) };
+    if len <= 1 {
+        return None;
+    }
+    // `len` counts the terminating null
+    Some(String::from_utf16_lossy(&name[..len as usize - 1]))
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn platform_locale() -> Option<String> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn placeholders(text: &str) -> Vec<usize> {
+        (0..10).filter(|i| text.contains(&format!("{{{}}}", i))).collect()
+    }
+
+    #[test]
+    fn test_translations_keep_placeholders() {
+        for (key, translations) in STRINGS {
+            let expected = placeholders(translations[0]);
+            for (translation, (_, code)) in translations.iter().zip(LOCALES) {
+                assert_eq!(placeholders(translation), expected, "{} in {}", key, code);
+            }
+            assert_eq!(STRINGS.iter().filter(|(k, _)| k == key).count(), 1, "{}", key);
+        }
+    }
+
+    #[test]
+    fn test_text_in_language() {
+        assert_eq!(
+            fill(text_in(1, "saved_to_slot"), &[&"Slot 2", &"hi"]),
+            "In Slot 2 gespeichert: hi"
+        );
+        assert_eq!(fill(text_in(0, "items"), &[&3]), "3 items");
+    }
+
+    #[test]
+    fn test_language_of_locale() {
+        assert_eq!(language_of("de_DE.UTF-8"), Some(Language::De));
+        assert_eq!(language_of("fr-CA"), Some(Language::Fr));
+        assert_eq!(language_of("ES"), Some(Language::Es));
+        assert_eq!(language_of("pt_BR"), None);
+    }
+}
@@ -1,144 +1,859 @@
 mod clipboard;
 mod config;
-mod crypto;
-#[macro_use]
+mod crash;
+#[cfg(desktop)]
+mod deep_link;
+mod diagnostics;
+mod i18n;
+#[cfg(desktop)]
+mod local_api;
 mod logging;
+#[cfg(mobile)]
+mod mobile;
 mod slots;
 mod storage;
 mod sync;
+mod telemetry;
+#[cfg(desktop)]
+mod updater;
 
+#[cfg(desktop)]
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+#[cfg(desktop)]
+use std::sync::Mutex;
+use std::time::Duration;
 
-use clipboard::item::ClipboardItem;
+use clipslot_core::crypto;
+use clipboard::item::{ClipboardItem, ClipboardItemDetails, ClipboardItemPreview};
 use clipboard::monitor::ClipboardMonitor;
 use crypto::cipher::CryptoEngine;
-use slots::SlotInfo;
-use storage::database::Database;
-use sync::manager::SyncManager;
-use tauri::menu::{Menu, MenuItemBuilder, PredefinedMenuItem};
-use tauri::tray::{TrayIcon, TrayIconBuilder};
-use tauri::{AppHandle, Listener, Manager, WebviewUrl, WebviewWindowBuilder, Wry};
-
-fn get_or_create_device_id() -> String {
-    let hostname = hostname::get()
+use slots::{SlotInfo, SlotSummary, TeamSlot};
+use storage::database::{Database, Recovery};
+use storage::integrity::IntegrityReport;
+use storage::settings::Settings;
+#[cfg(desktop)]
+use storage::settings::TrayLeftClick;
+use sync::key_exchange::LinkStatus;
+#[cfg(desktop)]
+use sync::manager::{ClipboardRequest, MonitoringChange};
+use sync::manager::{DeviceDelivery, SyncManager};
+use tauri::{AppHandle, Emitter, Manager};
+#[cfg(desktop)]
+use {
+    tauri::menu::{IsMenuItem, Menu, MenuItemBuilder, PredefinedMenuItem, SubmenuBuilder},
+    tauri::tray::{MouseButton, MouseButtonState, TrayIcon, TrayIconBuilder, TrayIconEvent},
+    tauri::{Listener, PhysicalPosition, WebviewUrl, WebviewWindowBuilder, Wry},
+    tauri_plugin_deep_link::DeepLinkExt,
+};
+
+/// This install's device ID, which its captures are tagged with. Earlier
+/// versions derived it from the hostname; their items move to the new one.
+fn get_or_create_device_id(db: &Database) -> String {
+    let legacy_id = uuid::Uuid::new_v5(&uuid::Uuid::NAMESPACE_DNS, machine_name().as_bytes());
+    let legacy_id = legacy_id.to_string();
+    db.get_or_create_device_id(&legacy_id).unwrap_or_else(|e| {
+        tracing::error!("Failed to store device ID: {}", e);
+        legacy_id
+    })
+}
+
+/// This machine's name, for showing it; not unique, so not an ID.
+fn machine_name() -> String {
+    hostname::get()
         .map(|h| h.to_string_lossy().to_string())
-        .unwrap_or_else(|_| "unknown".to_string());
-    let id = uuid::Uuid::new_v5(&uuid::Uuid::NAMESPACE_DNS, hostname.as_bytes());
-    id.to_string()
+        .unwrap_or_else(|_| "unknown".to_string())
 }
 
 /// Stored in Tauri managed state so we can update the tray menu dynamically.
+#[cfg(desktop)]
 struct TrayIconHandle(TrayIcon);
 
 // ── Tray Menu ────────────────────────────────────────────────────────────────
 
-fn build_tray_menu(app: &AppHandle, slots: &[SlotInfo], is_paused: bool) -> tauri::Result<Menu<Wry>> {
-    let mut items: Vec<Box<dyn tauri::menu::IsMenuItem<Wry>>> = Vec::new();
+/// Coalesces tray rebuilds (bursts of captures or synced slot updates) and
+/// remembers what the menu shows, so unchanged menus aren't replaced.
+#[cfg(desktop)]
+struct TrayRefresh {
+    pending: AtomicBool,
+    shown: Mutex<Vec<TrayEntry>>,
+}
+
+#[cfg(desktop)]
+const TRAY_REFRESH_DELAY: Duration = Duration::from_millis(250);
+
+/// Sections of the tray menu, shown in the order chosen in settings (see
+/// `TRAY_SECTION_KEYS`). Quit is always last.
+#[cfg(desktop)]
+#[derive(Clone, Copy, PartialEq)]
+enum TraySection {
+    Status,
+    Sync,
+    Slots,
+    Recent,
+    History,
+    Pause,
+    Settings,
+}
+
+#[cfg(desktop)]
+impl TraySection {
+    fn from_key(key: &str) -> Option<Self> {
+        match key {
+            "status" => Some(TraySection::Status),
+            "sync" => Some(TraySection::Sync),
+            "slots" => Some(TraySection::Slots),
+            "recent" => Some(TraySection::Recent),
+            "history" => Some(TraySection::History),
+            "pause" => Some(TraySection::Pause),
+            "settings" => Some(TraySection::Settings),
+            _ => None,
+        }
+    }
+
+    /// Single actions are grouped together rather than separated
+    fn is_action(self) -> bool {
+        matches!(
+            self,
+            TraySection::History | TraySection::Pause | TraySection::Settings
+        )
+    }
+}
+
+/// The sections chosen in settings, in order.
+#[cfg(desktop)]
+fn tray_sections(db: &Database) -> Vec<TraySection> {
+    Settings::load(db)
+        .tray_sections
+        .iter()
+        .filter_map(|k| TraySection::from_key(k))
+        .collect()
+}
+
+/// One row of the tray menu. The menu is described as entries first, so a
+/// refresh can skip rebuilding it when nothing visible changed.
+#[cfg(desktop)]
+#[derive(Clone, PartialEq)]
+enum TrayEntry {
+    Item {
+        id: String,
+        label: String,
+        enabled: bool,
+    },
+    Submenu {
+        id: String,
+        label: String,
+        enabled: bool,
+        items: Vec<TrayEntry>,
+    },
+    Separator,
+}
+
+#[cfg(desktop)]
+impl TrayEntry {
+    fn item(id: impl Into<String>, label: impl Into<String>) -> Self {
+        TrayEntry::Item {
+            id: id.into(),
+            label: label.into(),
+            enabled: true,
+        }
+    }
+
+    /// Greyed-out informational row
+    fn label(id: impl Into<String>, label: impl Into<String>) -> Self {
+        TrayEntry::Item {
+            id: id.into(),
+            label: label.into(),
+            enabled: false,
+        }
+    }
+
+    fn build(&self, app: &AppHandle) -> tauri::Result<Box<dyn IsMenuItem<Wry>>> {
+        Ok(match self {
+            TrayEntry::Item { id, label, enabled } => Box::new(
+                MenuItemBuilder::with_id(id.as_str(), label)
+                    .enabled(*enabled)
+                    .build(app)?,
+            ),
+            TrayEntry::Submenu { id, label, enabled, items } => {
+                let children = items
+                    .iter()
+                    .map(|item| item.build(app))
+                    .collect::<tauri::Result<Vec<_>>>()?;
+                let refs: Vec<&dyn IsMenuItem<Wry>> = children.iter().map(|b| b.as_ref()).collect();
+                Box::new(
+                    SubmenuBuilder::with_id(app, id.as_str(), label)
+                        .items(&refs)
+                        .enabled(*enabled)
+                        .build()?,
+                )
+            }
+            TrayEntry::Separator => Box::new(PredefinedMenuItem::separator(app)?),
+        })
+    }
+}
+
+#[cfg(desktop)]
+fn build_menu(app: &AppHandle, entries: &[TrayEntry]) -> tauri::Result<Menu<Wry>> {
+    let items = entries
+        .iter()
+        .map(|entry| entry.build(app))
+        .collect::<tauri::Result<Vec<_>>>()?;
+    let refs: Vec<&dyn IsMenuItem<Wry>> = items.iter().map(|b| b.as_ref()).collect();
+    Menu::with_items(app, &refs)
+}
+
+#[cfg(desktop)]
+fn tray_entries(
+    app: &AppHandle,
+    sections: &[TraySection],
+    summary: &StatusSummary,
+    slots: &[SlotInfo],
+    recent: &[ClipboardItemPreview],
+) -> Vec<TrayEntry> {
+    let mut entries = Vec::new();
+    let mut previous: Option<TraySection> = None;
+
+    // Pending update notice, above everything else
+    let update = app
+        .try_state::<updater::AvailableUpdate>()
+        .and_then(|u| u.0.lock().unwrap().clone());
+    if let Some(update) = update {
+        let label = i18n::text_with("update_available", &[&update.version]);
+        entries.push(TrayEntry::item("install_update", label));
+        entries.push(TrayEntry::Separator);
+    }
+
+    for &section in sections {
+        let section_entries = tray_section_entries(app, section, summary, slots, recent);
+        if section_entries.is_empty() {
+            continue;
+        }
+        if previous.is_some_and(|p| !(p.is_action() && section.is_action())) {
+            entries.push(TrayEntry::Separator);
+        }
+        entries.extend(section_entries);
+        previous = Some(section);
+    }
+
+    if previous.is_some() {
+        entries.push(TrayEntry::Separator);
+    }
+    entries.push(TrayEntry::item("quit", i18n::text("quit")));
+    entries
+}
+
+#[cfg(desktop)]
+fn tray_section_entries(
+    app: &AppHandle,
+    section: TraySection,
+    summary: &StatusSummary,
+    slots: &[SlotInfo],
+    recent: &[ClipboardItemPreview],
+) -> Vec<TrayEntry> {
+    let mut items = Vec::new();
+
+    match section {
+        // e.g. "142 items · last capture 14:32", to show monitoring is alive
+        TraySection::Status => {
+            let count = match summary.history_count {
+                1 => i18n::text("one_item").to_string(),
+                n => i18n::text_with("items", &[&n]),
+            };
+            let label = match summary.last_capture_at.and_then(format_capture_time) {
+                Some(time) => i18n::text_with("last_capture", &[&count, &time]),
+                None => count,
+            };
+            items.push(TrayEntry::label("status_header", label));
+        }
 
-    // Sync status line (if logged in)
-    if let Some(sync_manager) = app.try_state::<Arc<SyncManager>>() {
-        let has_auth = sync_manager.has_auth();
-        if has_auth {
+        // Account and sync status (if logged in)
+        TraySection::Sync => {
+            let Some(sync_manager) = app.try_state::<Arc<SyncManager>>() else {
+                return items;
+            };
+            let Some(email) = sync_manager.account_email_blocking() else {
+                return items;
+            };
             let status = sync_manager.get_status_blocking();
-            let label = match status {
-                sync::types::SyncStatus::Connected => "Sync: Connected",
-                sync::types::SyncStatus::Connecting => "Sync: Connecting...",
-                sync::types::SyncStatus::Syncing => "Sync: Syncing...",
-                sync::types::SyncStatus::Disconnected => "Sync: Offline",
+            let status_label = match status {
+                sync::types::SyncStatus::Connected => i18n::text("connected").to_string(),
+                sync::types::SyncStatus::Connecting => i18n::text("connecting").to_string(),
+                sync::types::SyncStatus::Syncing => i18n::text("syncing").to_string(),
+                sync::types::SyncStatus::Disconnected => i18n::text("offline").to_string(),
+                // e.g. "Working offline, 3 changes queued"
+                sync::types::SyncStatus::Offline => match sync_manager.pending_changes() {
+                    0 => i18n::text("working_offline").to_string(),
+                    1 => i18n::text("working_offline_one_queued").to_string(),
+                    n => i18n::text_with("working_offline_queued", &[&n]),
+                },
             };
-            let status_item = MenuItemBuilder::with_id("sync_status", label)
-                .enabled(false)
-                .build(app)?;
-            items.push(Box::new(status_item));
-            items.push(Box::new(PredefinedMenuItem::separator(app)?));
+            let label = i18n::text_with("signed_in_as", &[&email, &status_label]);
+            items.push(TrayEntry::label("sync_status", label));
+            items.push(TrayEntry::Item {
+                id: "sync_now".to_string(),
+                label: i18n::text("sync_now").to_string(),
+                enabled: status != sync::types::SyncStatus::Syncing,
+            });
         }
-    }
 
-    // Slot items
-    for slot in slots {
-        let label = if slot.is_empty {
-            format!("{}: (empty)", slot.name)
-        } else {
-            let preview = slot.content_preview.as_deref().unwrap_or("");
-            let short: String = preview.chars().take(30).collect();
-            if preview.chars().count() > 30 {
-                format!("{}: {}...", slot.name, short)
+        TraySection::Slots => {
+            for slot in slots {
+                // e.g. "Slot 2 (⌘⌥2)", so the paste shortcuts are discoverable
+                let name = match slots::manager::paste_shortcut_label(slot.slot_number) {
+                    Some(shortcut) => format!("{} ({})", slot.name, shortcut),
+                    None => slot.name.clone(),
+                };
+                let label = if slot.is_empty {
+                    i18n::text_with("slot_empty", &[&name])
+                } else {
+                    let preview = slot.content_preview.as_deref().unwrap_or("");
+                    let short: String = preview.chars().take(30).collect();
+                    if preview.chars().count() > 30 {
+                        format!("{}: {}...", name, short)
+                    } else {
+                        format!("{}: {}", name, short)
+                    }
+                };
+                // Pasting goes to the focused app, so also offer a plain copy
+                let n = slot.slot_number;
+                items.push(TrayEntry::Submenu {
+                    id: format!("slot_{}", n),
+                    label,
+                    enabled: !slot.is_empty,
+                    items: vec![
+                        TrayEntry::item(format!("paste_slot_{}", n), i18n::text("paste")),
+                        TrayEntry::item(
+                            format!("copy_slot_{}", n),
+                            i18n::text("copy_to_clipboard"),
+                        ),
+                        TrayEntry::Separator,
+                        TrayEntry::item(format!("clear_slot_{}", n), i18n::text("clear_slot")),
+                    ],
+                });
+            }
+        }
+
+        // Recent history items, click to copy
+        TraySection::Recent => {
+            if recent.is_empty() {
+                return items;
+            }
+            items.push(TrayEntry::label("recent_header", i18n::text("recent")));
+            for item in recent {
+                // Menu labels are a single line
+                let preview = item.item.content.split_whitespace().collect::<Vec<_>>().join(" ");
+                let short: String = preview.chars().take(40).collect();
+                let label = if preview.chars().count() > 40 || item.is_truncated {
+                    format!("{}...", short)
+                } else {
+                    short
+                };
+                let id = format!("copy_history_{}", item.item.id);
+                items.push(TrayEntry::item(id, label));
+            }
+            // One-time codes are usually the newest item
+            let id = format!("paste_remove_history_{}", recent[0].item.id);
+            items.push(TrayEntry::item(id, i18n::text("paste_delete_newest")));
+        }
+
+        TraySection::History => {
+            items.push(TrayEntry::item("show_history", i18n::text("show_history")));
+        }
+
+        TraySection::Pause => {
+            let pause_label = if summary.is_paused {
+                i18n::text("resume_monitoring")
             } else {
-                format!("{}: {}", slot.name, short)
+                i18n::text("pause_monitoring")
+            };
+            items.push(TrayEntry::item("pause", pause_label));
+            // The same on all of the account's devices, when signed in
+            if let Some(sync_manager) = app.try_state::<Arc<SyncManager>>() {
+                if sync_manager.account_email_blocking().is_some() {
+                    let label = if summary.is_paused {
+                        i18n::text("resume_everywhere")
+                    } else {
+                        i18n::text("pause_everywhere")
+                    };
+                    items.push(TrayEntry::Item {
+                        id: "pause_everywhere".to_string(),
+                        label: label.to_string(),
+                        enabled: !matches!(
+                            sync_manager.get_status_blocking(),
+                            sync::types::SyncStatus::Disconnected
+                                | sync::types::SyncStatus::Offline
+                        ),
+                    });
+                }
             }
+            items.push(TrayEntry::item("clear_clipboard", i18n::text("clear_clipboard")));
+        }
+
+        TraySection::Settings => {
+            items.push(TrayEntry::item("settings", i18n::text("settings")));
+        }
+    }
+
+    items
+}
+
+/// History size and activity, shown at the top of the tray menu.
+#[derive(serde::Serialize)]
+struct StatusSummary {
+    history_count: u32,
+    /// Newest history item's capture time, ms since epoch
+    last_capture_at: Option<i64>,
+    is_paused: bool,
+}
+
+fn status_summary(app: &AppHandle) -> Result<StatusSummary, String> {
+    let db = app.state::<Arc<Database>>();
+    Ok(StatusSummary {
+        history_count: db.get_count().map_err(|e| e.to_string())?,
+        last_capture_at: db.get_last_capture_at().map_err(|e| e.to_string())?,
+        is_paused: app.state::<Arc<ClipboardMonitor>>().is_paused(),
+    })
+}
+
+/// Local time of a capture: "14:32" today, "Oct 3, 14:32" before that.
+#[cfg(desktop)]
+fn format_capture_time(ms: i64) -> Option<String> {
+    let time = chrono::DateTime::from_timestamp_millis(ms)?.with_timezone(&chrono::Local);
+    if time.date_naive() == chrono::Local::now().date_naive() {
+        Some(time.format("%H:%M").to_string())
+    } else {
+        Some(time.format("%b %-d, %H:%M").to_string())
+    }
+}
+
+/// The tray menu for the current settings, slots and history.
+#[cfg(desktop)]
+fn current_tray_entries(app: &AppHandle) -> Vec<TrayEntry> {
+    let db = app.state::<Arc<Database>>();
+    let summary = status_summary(app).unwrap_or(StatusSummary {
+        history_count: 0,
+        last_capture_at: None,
+        is_paused: app.state::<Arc<ClipboardMonitor>>().is_paused(),
+    });
+
+    let sections = tray_sections(&db);
+    let slots = if sections.contains(&TraySection::Slots) {
+        db.get_all_slots().unwrap_or_default()
+    } else {
+        Vec::new()
+    };
+    let recent = if sections.contains(&TraySection::Recent) {
+        db.get_history_previews(db.get_tray_history_count(), 0)
+            .unwrap_or_default()
+    } else {
+        Vec::new()
+    };
+    tray_entries(app, &sections, &summary, &slots, &recent)
+}
+
+/// Rebuild the tray menu shortly, folding in any other requests made meanwhile.
+#[cfg(desktop)]
+fn refresh_tray_menu(app: &AppHandle) {
+    let refresh = app.state::<TrayRefresh>();
+    if refresh.pending.swap(true, Ordering::AcqRel) {
+        return;
+    }
+    let app = app.clone();
+    std::thread::spawn(move || {
+        std::thread::sleep(TRAY_REFRESH_DELAY);
+        let refresh = app.state::<TrayRefresh>();
+        refresh.pending.store(false, Ordering::Release);
+
+        let entries = current_tray_entries(&app);
+        let mut shown = refresh.shown.lock().unwrap();
+        if *shown == entries {
+            return;
+        }
+        match build_menu(&app, &entries) {
+            Ok(menu) => {
+                let tray = app.state::<TrayIconHandle>();
+                let _ = tray.0.set_menu(Some(menu));
+                *shown = entries;
+            }
+            Err(e) => tracing::error!("Failed to rebuild tray menu: {}", e),
+        }
+    });
+}
+
+/// Phones have no tray.
+#[cfg(mobile)]
+fn refresh_tray_menu(_app: &AppHandle) {}
+
+#[cfg(desktop)]
+const PICKER_WIDTH: f64 = 360.0;
+#[cfg(desktop)]
+const PICKER_HEIGHT: f64 = 420.0;
+
+/// Whether a left click on the tray icon opens the quick picker instead of the
+/// menu (`tray_left_click` = "picker"). Right-click always shows the menu.
+#[cfg(desktop)]
+fn picker_on_left_click(db: &Database) -> bool {
+    Settings::load(db).tray_left_click == TrayLeftClick::Picker
+}
+
+/// Show the quick picker next to the tray icon that was clicked at `click`:
+/// below it when the tray is at the top of the screen, above it otherwise.
+#[cfg(desktop)]
+fn show_quick_picker(app: &AppHandle, click: PhysicalPosition<f64>) {
+    let window = match app.get_webview_window("picker") {
+        Some(window) => window,
+        None => match WebviewWindowBuilder::new(
+            app,
+            "picker",
+            WebviewUrl::App("index.html?page=picker".into()),
+        )
+        .title("ClipSlot")
+        .inner_size(PICKER_WIDTH, PICKER_HEIGHT)
+        .decorations(false)
+        .resizable(false)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .visible(false)
+        .content_protected(hide_from_screen_capture(app))
+        .build()
+        {
+            Ok(window) => window,
+            Err(e) => {
+                tracing::error!("Failed to create quick picker: {}", e);
+                return;
+            }
+        },
+    };
+
+    if let Ok(Some(monitor)) = app.monitor_from_point(click.x, click.y) {
+        let scale = monitor.scale_factor();
+        let (width, height) = (PICKER_WIDTH * scale, PICKER_HEIGHT * scale);
+        let left = monitor.position().x as f64;
+        let top = monitor.position().y as f64;
+        let right = left + monitor.size().width as f64;
+        let bottom = top + monitor.size().height as f64;
+
+        let x = (click.x - width / 2.0).clamp(left, (right - width).max(left));
+        let y = if click.y < (top + bottom) / 2.0 { click.y } else { click.y - height };
+        let _ = window.set_position(PhysicalPosition::new(x, y));
+    }
+    let _ = window.show();
+    let _ = window.set_focus();
+}
+
+/// Whether new windows are kept out of screenshots and screen sharing.
+#[cfg(desktop)]
+fn hide_from_screen_capture(app: &AppHandle) -> bool {
+    Settings::load(&app.state::<Arc<Database>>()).hide_from_screen_capture
+}
+
+/// Show the history window, optionally with its search box set to `query`.
+#[cfg(desktop)]
+fn show_history_window(app: &AppHandle, query: Option<&str>) {
+    if let Some(window) = app.get_webview_window("history") {
+        let _ = window.show();
+        let _ = window.set_focus();
+        if let Some(query) = query {
+            let _ = app.emit_to("history", "history-search", query);
+        }
+    } else {
+        let url = match query {
+            Some(query) => format!(
+                "index.html?q={}",
+                url::form_urlencoded::byte_serialize(query.as_bytes()).collect::<String>()
+            ),
+            None => "index.html".to_string(),
         };
-        let id = format!("paste_slot_{}", slot.slot_number);
-        let item = MenuItemBuilder::with_id(id, label)
-            .enabled(!slot.is_empty)
-            .build(app)?;
-        items.push(Box::new(item));
+        let _ = WebviewWindowBuilder::new(app, "history", WebviewUrl::App(url.into()))
+            .title("ClipSlot History")
+            .inner_size(480.0, 600.0)
+            .resizable(true)
+            .center()
+            .content_protected(hide_from_screen_capture(app))
+            .build();
     }
+}
+
+/// Tell the user their database was damaged and what was kept of it.
+fn notify_database_recovery(app: &AppHandle, recovery: &Recovery) {
+    use tauri_plugin_notification::NotificationExt;
 
-    items.push(Box::new(PredefinedMenuItem::separator(app)?));
+    let body = match recovery {
+        Recovery::None => return,
+        Recovery::Salvaged { backup, rows } => {
+            i18n::text_with("database_salvaged", &[rows, &backup.display()])
+        }
+        Recovery::Reset { backup } => i18n::text_with("database_reset", &[&backup.display()]),
+    };
+    let _ = app
+        .notification()
+        .builder()
+        .title("ClipSlot")
+        .body(body)
+        .show();
+}
 
-    let show_history = MenuItemBuilder::with_id("show_history", "Show History").build(app)?;
-    items.push(Box::new(show_history));
+/// Point the user at crash reports written since they were last told about one.
+fn notify_new_crash_reports(app: &AppHandle, db: &Database) {
+    use tauri_plugin_notification::NotificationExt;
+
+    let Ok(data_dir) = app.path().app_data_dir() else {
+        return;
+    };
+    let reports = crash::list(&crash::crashes_dir(&data_dir));
+    let Some(newest) = reports.first() else {
+        return;
+    };
+    let seen = db
+        .get_setting("crash_report_seen")
+        .and_then(|v| v.parse::<i64>().ok())
+        .unwrap_or(0);
+    if newest.created_at <= seen {
+        return;
+    }
+    tracing::info!("Crash report from a previous run: {}", newest.message);
+    let _ = db.set_setting("crash_report_seen", &newest.created_at.to_string());
+    let _ = app
+        .notification()
+        .builder()
+        .title("ClipSlot")
+        .body(i18n::text("crash_report_saved"))
+        .show();
+}
 
-    items.push(Box::new(PredefinedMenuItem::separator(app)?));
+/// Tell the user history won't be saved until the keychain gives up the key.
+fn notify_key_unavailable(app: &AppHandle) {
+    use tauri_plugin_notification::NotificationExt;
 
-    let pause_label = if is_paused { "Resume Monitoring" } else { "Pause Monitoring" };
-    let pause = MenuItemBuilder::with_id("pause", pause_label).build(app)?;
-    items.push(Box::new(pause));
+    let _ = app
+        .notification()
+        .builder()
+        .title("ClipSlot")
+        .body(i18n::text("key_unavailable"))
+        .show();
+}
 
-    let settings = MenuItemBuilder::with_id("settings", "Settings...").build(app)?;
-    items.push(Box::new(settings));
+/// Point out the Windows clipboard history once, the first time ClipSlot
+/// runs, so history doesn't have to start from nothing.
+#[cfg(desktop)]
+fn offer_system_history_import(app: &AppHandle, db: &Database) {
+    use tauri_plugin_notification::NotificationExt;
 
-    items.push(Box::new(PredefinedMenuItem::separator(app)?));
+    if db.get_setting("system_history_offered").is_some() {
+        return;
+    }
+    if let Err(e) = db.set_setting("system_history_offered", "true") {
+        tracing::error!("Failed to save setting: {}", e);
+        return;
+    }
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let count = clipboard::native_history::read_entries().map_or(0, |e| e.len());
+        if count > 0 {
+            let _ = app
+                .notification()
+                .builder()
+                .title("ClipSlot")
+                .body(i18n::text_with("system_history_found", &[&count]))
+                .show();
+        }
+    });
+}
 
-    let quit = MenuItemBuilder::with_id("quit", "Quit ClipSlot").build(app)?;
-    items.push(Box::new(quit));
+/// Copy content another device sent here, and say where it came from.
+async fn receive_from_device(app: &AppHandle, delivery: DeviceDelivery) {
+    use tauri_plugin_clipboard_manager::ClipboardExt;
+    use tauri_plugin_notification::NotificationExt;
 
-    let refs: Vec<&dyn tauri::menu::IsMenuItem<Wry>> = items.iter().map(|b| b.as_ref()).collect();
-    Menu::with_items(app, &refs)
+    if let Err(e) = app.clipboard().write_text(delivery.text.clone()) {
+        tracing::error!("Failed to copy content sent from another device: {}", e);
+        return;
+    }
+    let sync = app.state::<Arc<SyncManager>>().inner().clone();
+    let from = sync
+        .get_linked_devices()
+        .await
+        .ok()
+        .and_then(|devices| devices.into_iter().find(|d| d.id == delivery.from_device))
+        .map(|d| d.name)
+        .unwrap_or_else(|| i18n::text("another_device").to_string());
+    tracing::info!("Copied content sent from {}", from);
+    let _ = app
+        .notification()
+        .builder()
+        .title(i18n::text_with("copied_from", &[&from]))
+        .body(ClipboardItem::preview(&delivery.text))
+        .show();
 }
 
-fn refresh_tray_menu(app: &AppHandle) {
-    let db = app.state::<Arc<Database>>();
+/// Answer another device asking for this one's clipboard. Nothing is shared
+/// while capture is paused.
+#[cfg(desktop)]
+async fn answer_clipboard_request(app: &AppHandle, request: ClipboardRequest) {
+    use tauri_plugin_clipboard_manager::ClipboardExt;
+
+    let text = if app.state::<Arc<ClipboardMonitor>>().is_paused() {
+        tracing::info!(
+            "Declined clipboard request from {}: capture is paused",
+            request.from_device
+        );
+        None
+    } else {
+        app.clipboard().read_text().ok().filter(|t| !t.is_empty())
+    };
+    let sync = app.state::<Arc<SyncManager>>().inner().clone();
+    if let Err(e) = sync.answer_clipboard_request(request, text.as_deref()).await {
+        tracing::error!("Failed to answer clipboard request: {}", e);
+    }
+}
+
+/// Pause or resume capture here and on the account's other devices. Done
+/// here first, so it happens even if the others can't be reached.
+#[cfg(desktop)]
+async fn set_monitoring_everywhere(app: &AppHandle, paused: bool) -> Result<(), String> {
     let monitor = app.state::<Arc<ClipboardMonitor>>();
-    let is_paused = monitor.is_paused();
+    if paused {
+        monitor.pause();
+    } else {
+        monitor.resume();
+    }
+    refresh_tray_menu(app);
+    let sync = app.state::<Arc<SyncManager>>().inner().clone();
+    sync.set_monitoring_everywhere(paused).await
+}
+
+/// Follow another device pausing or resuming capture everywhere, and say so.
+#[cfg(desktop)]
+async fn apply_monitoring_change(app: &AppHandle, change: MonitoringChange) {
+    use tauri_plugin_notification::NotificationExt;
 
-    let slots = db.get_all_slots().unwrap_or_default();
-    match build_tray_menu(app, &slots, is_paused) {
-        Ok(menu) => {
-            let tray = app.state::<TrayIconHandle>();
-            let _ = tray.0.set_menu(Some(menu));
+    let monitor = app.state::<Arc<ClipboardMonitor>>();
+    if change.paused == monitor.is_paused() {
+        return;
+    }
+    if change.paused {
+        monitor.pause();
+    } else {
+        monitor.resume();
+    }
+    refresh_tray_menu(app);
+
+    let sync = app.state::<Arc<SyncManager>>().inner().clone();
+    let from = sync
+        .get_linked_devices()
+        .await
+        .ok()
+        .and_then(|devices| devices.into_iter().find(|d| d.id == change.changed_by))
+        .map(|d| d.name)
+        .unwrap_or_else(|| i18n::text("another_device").to_string());
+    tracing::info!("Capture {} from {}", if change.paused { "paused" } else { "resumed" }, from);
+    let key = if change.paused { "paused_from" } else { "resumed_from" };
+    let _ = app
+        .notification()
+        .builder()
+        .title("ClipSlot")
+        .body(i18n::text_with(key, &[&from]))
+        .show();
+}
+
+/// Run the action of a `clipslot://` link opened by another app.
+#[cfg(desktop)]
+fn handle_deep_link(app: &AppHandle, url: &url::Url) {
+    tracing::info!("Deep link: {}", url);
+    let action = match deep_link::DeepLinkAction::parse(url) {
+        Ok(action) => action,
+        Err(e) => {
+            tracing::error!("Ignoring deep link: {}", e);
+            return;
+        }
+    };
+    let db = app.state::<Arc<Database>>();
+    let slot_count = db.get_slot_count();
+    match action {
+        deep_link::DeepLinkAction::PasteSlot(_) | deep_link::DeepLinkAction::PasteItem(_)
+            if !Settings::load(&db).deep_link_paste =>
+        {
+            use tauri_plugin_notification::NotificationExt;
+            tracing::warn!("Ignoring deep link: pasting from links is turned off");
+            let _ = app
+                .notification()
+                .builder()
+                .title("ClipSlot")
+                .body(i18n::text("deep_link_paste_off"))
+                .show();
+        }
+        deep_link::DeepLinkAction::PasteSlot(n) | deep_link::DeepLinkAction::SaveSlot(n)
+            if n > slot_count =>
+        {
+            tracing::error!("Ignoring deep link: only {} slots are in use", slot_count);
+        }
+        deep_link::DeepLinkAction::PasteSlot(n) => {
+            slots::manager::handle_paste_from_slot(app, n);
+        }
+        deep_link::DeepLinkAction::SaveSlot(n) => {
+            slots::manager::handle_save_to_slot(app, n);
+        }
+        deep_link::DeepLinkAction::PasteItem(id) => {
+            match db.get_item_content(&id) {
+                Ok(Some(content)) => {
+                    slots::manager::paste_text(app, &content);
+                    record_item_use(&db, &id);
+                }
+                Ok(None) => tracing::error!("Ignoring deep link: no history item {}", id),
+                Err(e) => tracing::error!("Failed to read history item {}: {}", id, e),
+            }
+        }
+        deep_link::DeepLinkAction::Search(query) => {
+            show_history_window(app, Some(&query));
         }
-        Err(e) => eprintln!("[ClipSlot] Failed to rebuild tray menu: {}", e),
     }
 }
 
+#[cfg(desktop)]
 fn handle_tray_menu_event(app: &AppHandle, event_id: &str) {
     match event_id {
         "quit" => {
             app.exit(0);
         }
-        "show_history" => {
-            if let Some(window) = app.get_webview_window("history") {
-                let _ = window.show();
-                let _ = window.set_focus();
-            } else {
-                let _ = WebviewWindowBuilder::new(
-                    app,
-                    "history",
-                    WebviewUrl::App("index.html".into()),
-                )
-                .title("ClipSlot History")
-                .inner_size(480.0, 600.0)
-                .resizable(true)
-                .center()
-                .build();
-            }
+        "show_history" => show_history_window(app, None),
+        "sync_now" => {
+            let sync = app.state::<Arc<SyncManager>>().inner().clone();
+            tauri::async_runtime::spawn(async move {
+                match sync_now(sync).await {
+                    Ok(msg) => tracing::info!("Tray sync completed: {}", msg),
+                    Err(e) => tracing::error!("Tray sync failed: {}", e),
+                }
+            });
+        }
+        "install_update" => {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = updater::install(&app).await {
+                    tracing::error!("Update install failed: {}", e);
+                }
+            });
         }
         "pause" => {
             let monitor = app.state::<Arc<ClipboardMonitor>>();
             monitor.toggle_pause();
             refresh_tray_menu(app);
         }
+        "pause_everywhere" => {
+            let paused = !app.state::<Arc<ClipboardMonitor>>().is_paused();
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = set_monitoring_everywhere(&app, paused).await {
+                    tracing::error!("Failed to pause other devices: {}", e);
+                }
+            });
+        }
+        "clear_clipboard" => {
+            if let Err(e) = clipboard::wipe::clear(app) {
+                tracing::error!("Failed to clear clipboard: {}", e);
+            }
+        }
         "settings" => {
             if let Some(window) = app.get_webview_window("settings") {
                 let _ = window.show();
@@ -153,9 +868,70 @@ fn handle_tray_menu_event(app: &AppHandle, event_id: &str) {
                 .inner_size(560.0, 480.0)
                 .resizable(true)
                 .center()
+                .content_protected(hide_from_screen_capture(app))
                 .build();
             }
         }
+        id if id.starts_with("copy_history_") => {
+            use tauri_plugin_clipboard_manager::ClipboardExt;
+            let item_id = id.strip_prefix("copy_history_").unwrap();
+            let db = app.state::<Arc<Database>>();
+            match db.get_item_content(item_id) {
+                Ok(Some(content)) => {
+                    app.state::<Arc<ClipboardMonitor>>().set_skip_next();
+                    if let Err(e) = app.clipboard().write_text(&content) {
+                        tracing::error!("Failed to copy history item from tray: {}", e);
+                    } else {
+                        record_item_use(&db, item_id);
+                    }
+                }
+                Ok(None) => tracing::info!("Tray history item {} no longer exists", item_id),
+                Err(e) => tracing::error!("Failed to read history item {}: {}", item_id, e),
+            }
+        }
+        id if id.starts_with("paste_remove_history_") => {
+            let item_id = id.strip_prefix("paste_remove_history_").unwrap().to_string();
+            let app = app.clone();
+            // Pasting waits for the keystroke to land; keep that off the main thread
+            std::thread::spawn(move || {
+                if let Err(e) = paste_and_remove_item(&app, &item_id) {
+                    tracing::error!("Failed to paste and delete history item {}: {}", item_id, e);
+                }
+            });
+        }
+        id if id.starts_with("copy_slot_") => {
+            use tauri_plugin_clipboard_manager::ClipboardExt;
+            let Ok(slot_num) = id.strip_prefix("copy_slot_").unwrap().parse::<u32>() else {
+                return;
+            };
+            let db = app.state::<Arc<Database>>();
+            match db.get_slot(slot_num) {
+                Ok(slot) => {
+                    if let Some(content) = slot.content {
+                        app.state::<Arc<ClipboardMonitor>>().set_skip_next();
+                        if let Err(e) = app.clipboard().write_text(&content) {
+                            tracing::error!("Failed to copy slot {} from tray: {}", slot_num, e);
+                        } else {
+                            telemetry::record(telemetry::Event::SlotPaste);
+                            if let Err(e) = db.record_slot_use(slot_num) {
+                                tracing::error!("Failed to record use of slot {}: {}", slot_num, e);
+                            }
+                        }
+                    }
+                }
+                Err(e) => tracing::error!("Failed to read slot {}: {}", slot_num, e),
+            }
+        }
+        id if id.starts_with("clear_slot_") => {
+            let Ok(slot_num) = id.strip_prefix("clear_slot_").unwrap().parse::<u32>() else {
+                return;
+            };
+            let db = app.state::<Arc<Database>>();
+            if let Err(e) = db.clear_slot(slot_num) {
+                tracing::error!("Failed to clear slot {}: {}", slot_num, e);
+            }
+            refresh_tray_menu(app);
+        }
         id if id.starts_with("paste_slot_") => {
             if let Ok(slot_num) = id.strip_prefix("paste_slot_").unwrap().parse::<u32>() {
                 slots::manager::handle_paste_from_slot(app, slot_num);
@@ -167,35 +943,324 @@ fn handle_tray_menu_event(app: &AppHandle, event_id: &str) {
 
 // ── Tauri Commands ──────────────────────────────────────────────────────────
 
+// History and search decrypt many rows, so they run on the database thread
+// rather than the main thread.
+
 #[tauri::command]
-fn get_clipboard_history(
+async fn get_clipboard_history(
     db: tauri::State<'_, Arc<Database>>,
     limit: Option<u32>,
     offset: Option<u32>,
 ) -> Result<Vec<ClipboardItem>, String> {
-    db.get_history(limit.unwrap_or(50), offset.unwrap_or(0))
+    db.call(move |db| db.get_history(limit.unwrap_or(50), offset.unwrap_or(0)))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// History for list views: each item's `content` is only its preview. Use
+/// `get_history_item` or `copy_history_item` for the full content.
+#[tauri::command]
+async fn get_history_previews(
+    db: tauri::State<'_, Arc<Database>>,
+    limit: Option<u32>,
+    offset: Option<u32>,
+) -> Result<Vec<ClipboardItemPreview>, String> {
+    db.call(move |db| db.get_history_previews(limit.unwrap_or(50), offset.unwrap_or(0)))
+        .await
         .map_err(|e| e.to_string())
 }
 
+/// One page of history previews for infinite scroll, only items copied on
+/// `device_id` if given (see `get_known_devices`). Pass the returned
+/// `next_cursor` to get the following page; new captures don't shift pages.
 #[tauri::command]
-fn search_history(
+async fn get_history_page(
+    db: tauri::State<'_, Arc<Database>>,
+    cursor: Option<String>,
+    page_size: Option<u32>,
+    device_id: Option<String>,
+) -> Result<storage::page::HistoryPage, String> {
+    let cursor = cursor
+        .map(|c| c.parse::<storage::page::HistoryCursor>())
+        .transpose()?;
+    let page_size = page_size.unwrap_or(50).clamp(1, storage::page::MAX_PAGE_SIZE);
+    db.call(move |db| db.get_history_page(cursor.as_ref(), page_size, device_id.as_deref()))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// A device that history items were copied on.
+#[derive(serde::Serialize)]
+struct KnownDevice {
+    device_id: String,
+    /// Its name in sync, or this machine's hostname; `None` for other
+    /// devices that aren't linked
+    name: Option<String>,
+    is_this_device: bool,
+    item_count: u32,
+}
+
+/// Devices that history items were copied on, most items first, for
+/// filtering history by device. Named after the linked devices when signed
+/// in and online.
+#[tauri::command]
+async fn get_known_devices(
+    db: tauri::State<'_, Arc<Database>>,
+    sync: tauri::State<'_, Arc<SyncManager>>,
+) -> Result<Vec<KnownDevice>, String> {
+    let counts = db
+        .call(|db| db.get_history_device_counts())
+        .await
+        .map_err(|e| e.to_string())?;
+    let linked = sync.get_linked_devices().await.unwrap_or_default();
+    // Captures here use the install's ID; synced ones the account's
+    let local_id = get_or_create_device_id(&db);
+    let sync_id = sync.get_sync_status().await.device_id.map(|id| id.to_string());
+
+    Ok(counts
+        .into_iter()
+        .map(|(device_id, item_count)| {
+            let is_this_device = device_id == local_id || Some(&device_id) == sync_id.as_ref();
+            KnownDevice {
+                name: linked
+                    .iter()
+                    .find(|d| d.id.to_string() == device_id)
+                    .map(|d| d.name.clone())
+                    .or_else(|| is_this_device.then(machine_name)),
+                is_this_device,
+                device_id,
+                item_count,
+            }
+        })
+        .collect())
+}
+
+/// History previews bucketed by `group_by` ("day" or "source_app"), with each
+/// group's total count and its newest `items_per_group` items.
+#[tauri::command]
+async fn get_history_grouped(
+    db: tauri::State<'_, Arc<Database>>,
+    group_by: storage::page::HistoryGroupBy,
+    items_per_group: Option<u32>,
+) -> Result<Vec<storage::page::HistoryGroup>, String> {
+    let items_per_group = items_per_group.unwrap_or(20).clamp(1, storage::page::MAX_PAGE_SIZE);
+    db.call(move |db| db.get_history_grouped(group_by, items_per_group))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Send a history item or a slot straight to another of the account's
+/// devices, which copies it and shows a notification.
+#[tauri::command]
+async fn send_to_device(
+    db: tauri::State<'_, Arc<Database>>,
+    sync: tauri::State<'_, Arc<SyncManager>>,
+    device_id: uuid::Uuid,
+    item_id: Option<String>,
+    slot_number: Option<u32>,
+) -> Result<(), String> {
+    let text = match (item_id, slot_number) {
+        (Some(id), None) => db
+            .call(move |db| db.get_item_content(&id))
+            .await
+            .map_err(|e| e.to_string())?
+            .ok_or("Item not found")?,
+        (None, Some(n)) => db
+            .call(move |db| db.get_slot(n))
+            .await
+            .map_err(|e| e.to_string())?
+            .content
+            .ok_or("Slot is empty")?,
+        _ => return Err("Provide exactly one of item_id or slot_number".to_string()),
+    };
+    sync.send_to_device(device_id, &text).await
+}
+
+/// Upload a history item as a one-time link anyone can open in a browser.
+/// The link expires after `expires_in_secs` (1 hour by default) if unopened.
+#[tauri::command]
+async fn create_share_link(
+    db: tauri::State<'_, Arc<Database>>,
+    sync: tauri::State<'_, Arc<SyncManager>>,
+    item_id: String,
+    expires_in_secs: Option<i64>,
+) -> Result<sync::types::ShareLink, String> {
+    let text = db
+        .call(move |db| db.get_item_content(&item_id))
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or("Item not found")?;
+    sync.create_share_link(&text, expires_in_secs).await
+}
+
+/// Fetch another device's current clipboard and copy it here. Returns the
+/// text, or `None` if that device had nothing to share.
+#[tauri::command]
+async fn pull_device_clipboard(
+    app: AppHandle,
+    sync: tauri::State<'_, Arc<SyncManager>>,
+    device_id: uuid::Uuid,
+) -> Result<Option<String>, String> {
+    use tauri_plugin_clipboard_manager::ClipboardExt;
+
+    let text = sync.pull_clipboard(device_id).await?;
+    if let Some(text) = &text {
+        app.clipboard()
+            .write_text(text.clone())
+            .map_err(|e| format!("Failed to copy: {}", e))?;
+    }
+    Ok(text)
+}
+
+/// Search history text, optionally as a regex and narrowed by `filters`
+/// (date range, source app, content type, language, device, pinned only).
+/// Each result carries the byte ranges of its content that matched.
+#[tauri::command]
+async fn search_history(
     db: tauri::State<'_, Arc<Database>>,
     query: String,
-) -> Result<Vec<ClipboardItem>, String> {
-    db.search(&query).map_err(|e| e.to_string())
+    filters: Option<storage::search::SearchFilters>,
+) -> Result<Vec<storage::search::SearchHit>, String> {
+    use storage::search::SearchHit;
+
+    let filters = filters.unwrap_or_default();
+    let matcher = filters.matcher(&query)?;
+    db.call(move |db| {
+        db.search_filtered(&filters, &matcher).map(|items| {
+            items
+                .into_iter()
+                .map(|item| SearchHit::new(item, &matcher))
+                .collect()
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
 fn delete_history_item(
+    app: tauri::AppHandle,
     db: tauri::State<'_, Arc<Database>>,
+    sync: tauri::State<'_, Arc<SyncManager>>,
     id: String,
 ) -> Result<bool, String> {
-    db.delete_item(&id).map_err(|e| e.to_string())
+    let deleted = db.delete_item(&id).map_err(|e| e.to_string())?;
+
+    // Propagate the deletion so other devices don't keep (or resurrect) the item
+    if deleted {
+        refresh_tray_menu(&app);
+        let sync = sync.inner().clone();
+        tokio::spawn(async move {
+            sync.notify_history_deleted(&id).await;
+        });
+    }
+
+    Ok(deleted)
+}
+
+/// Paste a history item into the focused app, then delete it here and on
+/// other devices, e.g. for one-time codes. Blocks while pasting.
+#[cfg(desktop)]
+fn paste_and_remove_item(app: &AppHandle, id: &str) -> Result<(), String> {
+    let db = app.state::<Arc<Database>>();
+    let content = db
+        .get_item_content(id)
+        .map_err(|e| e.to_string())?
+        .ok_or("Item not found")?;
+    slots::manager::paste_text(app, &content);
+    if db.delete_item(id).map_err(|e| e.to_string())? {
+        let _ = app.emit("history-item-deleted", id);
+        refresh_tray_menu(app);
+        let sync = app.state::<Arc<SyncManager>>().inner().clone();
+        let id = id.to_string();
+        tauri::async_runtime::spawn(async move {
+            sync.notify_history_deleted(&id).await;
+        });
+    }
+    Ok(())
+}
+
+/// Paste a history item and delete it (see `paste_and_remove_item`). The
+/// quick picker is closed first so the paste reaches the app behind it.
+#[cfg(desktop)]
+#[tauri::command]
+async fn paste_and_remove(app: tauri::AppHandle, id: String) -> Result<(), String> {
+    if let Some(picker) = app.get_webview_window("picker") {
+        let _ = picker.close();
+    }
+    tokio::task::spawn_blocking(move || paste_and_remove_item(&app, &id))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Paste a large slot after the user confirmed it (see
+/// `slots::manager::handle_confirmed_paste`). The confirmation window is
+/// closed first, as the picker is for `paste_and_remove`.
+#[cfg(desktop)]
+#[tauri::command]
+async fn confirm_slot_paste(app: tauri::AppHandle, slot_number: u32) -> Result<(), String> {
+    if let Some(window) = app.get_webview_window("paste-confirm") {
+        let _ = window.close();
+    }
+    tokio::task::spawn_blocking(move || slots::manager::handle_confirmed_paste(&app, slot_number))
+        .await
+        .map_err(|e| e.to_string())
 }
 
+/// Paste several history items into the focused app one after another,
+/// pressing `separator_key` and waiting `delay_ms` between them, e.g. to fill
+/// a form's fields. The picker is closed first, as for `paste_and_remove`.
+#[cfg(desktop)]
 #[tauri::command]
-fn clear_history(db: tauri::State<'_, Arc<Database>>) -> Result<u32, String> {
-    db.clear_history().map_err(|e| e.to_string())
+async fn paste_items_sequentially(
+    app: tauri::AppHandle,
+    ids: Vec<String>,
+    delay_ms: u64,
+    separator_key: Option<clipboard::backend::Key>,
+) -> Result<(), String> {
+    if ids.is_empty() {
+        return Err("No items to paste".to_string());
+    }
+    if delay_ms > slots::paste::MAX_SEQUENCE_DELAY_MS {
+        return Err(format!(
+            "delay_ms must be at most {}",
+            slots::paste::MAX_SEQUENCE_DELAY_MS
+        ));
+    }
+    let db = app.state::<Arc<Database>>().inner().clone();
+    let contents = ids
+        .iter()
+        .map(|id| {
+            db.get_item_content(id)
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| format!("Item not found: {}", id))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    if let Some(picker) = app.get_webview_window("picker") {
+        let _ = picker.close();
+    }
+    tokio::task::spawn_blocking(move || {
+        let texts: Vec<&str> = contents.iter().map(String::as_str).collect();
+        let delay = Duration::from_millis(delay_ms);
+        slots::manager::paste_texts(&app, &texts, separator_key, delay)?;
+        for id in &ids {
+            record_item_use(&db, id);
+        }
+        Ok(())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+fn clear_history(
+    app: tauri::AppHandle,
+    db: tauri::State<'_, Arc<Database>>,
+) -> Result<u32, String> {
+    let cleared = db.clear_history().map_err(|e| e.to_string())?;
+    refresh_tray_menu(&app);
+    Ok(cleared)
 }
 
 #[tauri::command]
@@ -203,6 +1268,173 @@ fn get_history_count(db: tauri::State<'_, Arc<Database>>) -> Result<u32, String>
     db.get_count().map_err(|e| e.to_string())
 }
 
+/// How many text items the Windows clipboard history has to import; `None`
+/// where there's none to read (other systems, or history turned off).
+#[cfg(desktop)]
+#[tauri::command]
+async fn get_system_history_count() -> Option<usize> {
+    tokio::task::spawn_blocking(|| clipboard::native_history::read_entries().map(|e| e.len()))
+        .await
+        .ok()
+        .flatten()
+}
+
+/// Add the Windows clipboard history to ClipSlot's; returns how many items
+/// were added.
+#[cfg(desktop)]
+#[tauri::command]
+async fn import_system_history(
+    app: tauri::AppHandle,
+    db: tauri::State<'_, Arc<Database>>,
+) -> Result<usize, String> {
+    let db = db.inner().clone();
+    let device_id = get_or_create_device_id(&db);
+    let imported = tokio::task::spawn_blocking(move || {
+        clipboard::native_history::import(&db, &device_id)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| e.to_string())?;
+    if imported > 0 {
+        let _ = app.emit("clipboard-changed", ());
+        refresh_tray_menu(&app);
+    }
+    Ok(imported)
+}
+
+/// Items ranked by how often and how recently they were copied or pasted.
+#[tauri::command]
+async fn get_frequent_items(
+    db: tauri::State<'_, Arc<Database>>,
+    limit: u32,
+) -> Result<Vec<ClipboardItemPreview>, String> {
+    db.call(move |db| db.get_frequent_items(limit))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Programming languages of code snippets in history, for the language filter.
+#[tauri::command]
+fn get_history_languages(db: tauri::State<'_, Arc<Database>>) -> Result<Vec<String>, String> {
+    db.get_languages().map_err(|e| e.to_string())
+}
+
+/// Convert a history item (by id) or some text and copy the result, for
+/// "Copy as..." actions. Returns the converted text.
+#[tauri::command]
+fn transform_and_copy(
+    app: tauri::AppHandle,
+    db: tauri::State<'_, Arc<Database>>,
+    monitor: tauri::State<'_, Arc<ClipboardMonitor>>,
+    id_or_text: String,
+    transform: clipboard::transform::Transform,
+) -> Result<String, String> {
+    use tauri_plugin_clipboard_manager::ClipboardExt;
+    let text = db
+        .get_item_content(&id_or_text)
+        .map_err(|e| e.to_string())?
+        .unwrap_or(id_or_text);
+    let result = transform.apply(&text)?;
+    monitor.set_skip_next();
+    app.clipboard()
+        .write_text(&result)
+        .map_err(|e| e.to_string())?;
+    Ok(result)
+}
+
+/// Unified diff of two history items' text, e.g. two copied versions of a
+/// config file. Empty if they're the same.
+#[tauri::command]
+async fn diff_items(
+    db: tauri::State<'_, Arc<Database>>,
+    id_a: String,
+    id_b: String,
+) -> Result<String, String> {
+    let (a, b) = (id_a.clone(), id_b.clone());
+    let (old, new) = db
+        .call(move |db| {
+            Ok::<_, rusqlite::Error>((db.get_item_content(&a)?, db.get_item_content(&b)?))
+        })
+        .await
+        .map_err(|e| e.to_string())?;
+    let (Some(old), Some(new)) = (old, new) else {
+        return Err("Item not found".to_string());
+    };
+    clipboard::diff::unified_diff(&old, &new, &id_a, &id_b)
+}
+
+/// One item with its full content and metadata, for a detail view.
+#[tauri::command]
+fn get_history_item(
+    db: tauri::State<'_, Arc<Database>>,
+    id: String,
+) -> Result<ClipboardItemDetails, String> {
+    db.get_item_details(&id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Item not found".to_string())
+}
+
+/// The item's content as a QR code, as a `data:image/png` URL, so short
+/// texts and links can be scanned with a phone.
+#[tauri::command]
+fn get_item_qr(db: tauri::State<'_, Arc<Database>>, id: String) -> Result<String, String> {
+    use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+
+    let text = db
+        .get_item_content(&id)
+        .map_err(|e| e.to_string())?
+        .ok_or("Item not found")?;
+    let png = clipboard::qr::render_png(&text)?;
+    Ok(format!("data:image/png;base64,{}", BASE64.encode(png)))
+}
+
+/// Pin an item so it is kept when history is cleared or trimmed.
+#[tauri::command]
+fn pin_item(db: tauri::State<'_, Arc<Database>>, id: String) -> Result<bool, String> {
+    db.pin_item(&id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn unpin_item(db: tauri::State<'_, Arc<Database>>, id: String) -> Result<bool, String> {
+    db.unpin_item(&id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn get_pinned_items(db: tauri::State<'_, Arc<Database>>) -> Result<Vec<ClipboardItem>, String> {
+    db.get_pinned_items().map_err(|e| e.to_string())
+}
+
+/// History count, last capture time and whether monitoring is paused.
+#[tauri::command]
+fn get_status_summary(app: tauri::AppHandle) -> Result<StatusSummary, String> {
+    status_summary(&app)
+}
+
+/// Copy a history item's full content by ID.
+#[tauri::command]
+fn copy_history_item(
+    app: tauri::AppHandle,
+    db: tauri::State<'_, Arc<Database>>,
+    monitor: tauri::State<'_, Arc<ClipboardMonitor>>,
+    id: String,
+) -> Result<(), String> {
+    let text = db
+        .get_item_content(&id)
+        .map_err(|e| e.to_string())?
+        .ok_or("Item not found")?;
+    copy_to_clipboard(app, monitor, text)?;
+    record_item_use(&db, &id);
+    Ok(())
+}
+
+/// Count a copy or paste of a history item for frequency ranking. Failing to
+/// count is only logged.
+fn record_item_use(db: &Database, id: &str) {
+    if let Err(e) = db.record_item_use(id) {
+        tracing::error!("Failed to record use of item {}: {}", id, e);
+    }
+}
+
 #[tauri::command]
 fn copy_to_clipboard(
     app: tauri::AppHandle,
@@ -213,18 +1445,26 @@ fn copy_to_clipboard(
     monitor.set_skip_next();
     app.clipboard()
         .write_text(&text)
-        .map_err(|e| e.to_string())
+        .map_err(|e| e.to_string())?;
+    // The monitor skips the app's own writes, so secrets copied from here
+    // are timed here
+    let settings = Settings::load(&app.state::<Arc<Database>>());
+    if let Some(delay) = settings.clipboard_clear_delay(&text, None) {
+        clipboard::wipe::clear_after(app, text, delay);
+    }
+    Ok(())
+}
+
+/// Empty the system clipboard, e.g. right after pasting a password.
+#[tauri::command]
+fn clear_system_clipboard(app: tauri::AppHandle) -> Result<(), String> {
+    clipboard::wipe::clear(&app)
 }
 
 // ── Slot Commands ────────────────────────────────────────────────────────────
 
 #[tauri::command]
-fn save_to_slot(
-    app: tauri::AppHandle,
-    db: tauri::State<'_, Arc<Database>>,
-    sync: tauri::State<'_, Arc<SyncManager>>,
-    slot_number: u32,
-) -> Result<SlotInfo, String> {
+fn save_to_slot(app: tauri::AppHandle, slot_number: u32) -> Result<SlotInfo, String> {
     use tauri_plugin_clipboard_manager::ClipboardExt;
     let text = app
         .clipboard()
@@ -233,20 +1473,31 @@ fn save_to_slot(
     if text.is_empty() {
         return Err("Clipboard is empty".to_string());
     }
-    let device_id = get_or_create_device_id();
-    let item = ClipboardItem::new(text, &device_id);
-    let result = db
+    save_text_to_slot(&app, slot_number, text)
+}
+
+/// Save `text` to a slot (from the app's commands or local API), refresh the
+/// tray and push the change to other devices.
+fn save_text_to_slot(
+    app: &AppHandle,
+    slot_number: u32,
+    text: String,
+) -> Result<SlotInfo, String> {
+    let db = app.state::<Arc<Database>>();
+    let item = ClipboardItem::new(text, &get_or_create_device_id(&db));
+    let slot_info = db
         .save_to_slot(slot_number, &item)
         .map_err(|e| e.to_string())?;
-    refresh_tray_menu(&app);
-
-    // Notify sync manager of slot change
-    let sync = sync.inner().clone();
-    tokio::spawn(async move {
-        sync.notify_slot_changed(slot_number).await;
-    });
-
-    Ok(result)
+    telemetry::record(telemetry::Event::SlotSave);
+    let _ = app.emit("slot-changed", ());
+
+    if let Some(sync) = app.try_state::<Arc<SyncManager>>() {
+        let sync = sync.inner().clone();
+        tauri::async_runtime::spawn(async move {
+            sync.notify_slot_changed(slot_number).await;
+        });
+    }
+    Ok(slot_info)
 }
 
 #[tauri::command]
@@ -257,9 +1508,37 @@ fn get_slot(
     db.get_slot(slot_number).map_err(|e| e.to_string())
 }
 
+/// Every slot without its content; `get_slot` fetches one slot's content.
 #[tauri::command]
-fn get_all_slots(db: tauri::State<'_, Arc<Database>>) -> Result<Vec<SlotInfo>, String> {
-    db.get_all_slots().map_err(|e| e.to_string())
+fn get_all_slots(db: tauri::State<'_, Arc<Database>>) -> Result<Vec<SlotSummary>, String> {
+    db.get_slot_summaries().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn get_slot_count(db: tauri::State<'_, Arc<Database>>) -> u32 {
+    db.get_slot_count()
+}
+
+/// Set how many slots are in use. When signed in the count is stored on the
+/// account, and the server passes it on to the other devices.
+#[tauri::command]
+async fn set_slot_count(
+    app: tauri::AppHandle,
+    db: tauri::State<'_, Arc<Database>>,
+    sync: tauri::State<'_, Arc<SyncManager>>,
+    slot_count: u32,
+) -> Result<u32, String> {
+    let settings = sync::types::SlotSettings {
+        slot_count: slot_count as i32,
+    };
+    if let Some(token) = sync.get_token().await {
+        let api = sync.get_api().await;
+        api.update_slot_settings(&token, &settings).await?;
+    }
+    db.call(move |db| sync::slot_sync::apply_slot_count(db, settings.slot_count))
+        .await?;
+    refresh_tray_menu(&app);
+    Ok(slot_count)
 }
 
 #[tauri::command]
@@ -290,40 +1569,104 @@ fn rename_slot(
 // ── Settings Commands ────────────────────────────────────────────────────────
 
 #[tauri::command]
-fn get_settings(
-    db: tauri::State<'_, Arc<Database>>,
-) -> Result<std::collections::HashMap<String, String>, String> {
-    let keys = ["history_limit", "auto_clear_on_quit", "excluded_apps"];
-    let mut map = std::collections::HashMap::new();
-    for key in keys {
-        if let Some(val) = db.get_setting(key) {
-            map.insert(key.to_string(), val);
+fn get_settings(db: tauri::State<'_, Arc<Database>>) -> Settings {
+    Settings::load(&db)
+}
+
+/// Apply a partial settings object, e.g. `{"history_limit": 1000}`. Nothing is
+/// saved unless the whole result is valid. Emits `settings-changed`.
+#[tauri::command]
+async fn update_settings(
+    app: tauri::AppHandle,
+    changes: serde_json::Map<String, serde_json::Value>,
+) -> Result<Settings, String> {
+    apply_settings(&app, changes).await
+}
+
+async fn apply_settings(
+    app: &AppHandle,
+    changes: serde_json::Map<String, serde_json::Value>,
+) -> Result<Settings, String> {
+    let db = app.state::<Arc<Database>>().inner().clone();
+    let (settings, changed) = db
+        .call(move |db| {
+            let previous = Settings::load(db);
+            let settings = previous.with_changes(changes)?;
+            let changed = settings.save(db, &previous).map_err(|e| e.to_string())?;
+            Ok::<_, String>((settings, changed))
+        })
+        .await?;
+    if changed.is_empty() {
+        return Ok(settings);
+    }
+    tracing::info!("Settings changed: {}", changed.join(", "));
+    let is_changed = |key: &str| changed.iter().any(|k| k == key);
+
+    #[cfg(desktop)]
+    if is_changed("tray_left_click") {
+        let tray = app.state::<TrayIconHandle>();
+        tray.0
+            .set_show_menu_on_left_click(settings.tray_left_click == TrayLeftClick::Menu)
+            .map_err(|e| e.to_string())?;
+    }
+    if is_changed("log_level") {
+        logging::set_level(settings.log_level);
+    }
+    if is_changed("log_redaction") {
+        logging::set_redaction(settings.log_redaction);
+    }
+    #[cfg(desktop)]
+    if is_changed("hide_from_screen_capture") {
+        for window in app.webview_windows().values() {
+            let _ = window.set_content_protected(settings.hide_from_screen_capture);
+        }
+    }
+    if is_changed("history_integrity_enabled") {
+        let enabled = settings.history_integrity_enabled;
+        db.call(move |db| db.set_history_integrity(enabled))
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    if is_changed("telemetry_enabled") {
+        telemetry::set_enabled(&db, settings.telemetry_enabled);
+    }
+    if is_changed("language") {
+        i18n::set_language(settings.language);
+        refresh_tray_menu(app);
+    }
+    if is_changed("history_limit") {
+        if let Err(e) = db.call(|db| db.enforce_history_limit()).await {
+            tracing::error!("Failed to apply history limit: {}", e);
+        }
+    }
+    if is_changed("tray_history_count") || is_changed("tray_sections") {
+        refresh_tray_menu(app);
+    }
+    #[cfg(desktop)]
+    if is_changed("snippet_expansion_enabled") || is_changed("snippet_triggers") {
+        slots::manager::configure_snippets(app, &settings);
+    }
+    #[cfg(desktop)]
+    if is_changed("local_api_enabled") || is_changed("local_api_port") {
+        if settings.local_api_enabled {
+            local_api::start(app).await?;
+        } else {
+            local_api::stop(app);
         }
     }
-    Ok(map)
+    let _ = app.emit("settings-changed", &settings);
+    Ok(settings)
 }
 
-const ALLOWED_SETTING_KEYS: &[&str] = &[
-    "history_limit",
-    "auto_clear_on_quit",
-    "excluded_apps",
-    "sync_server_url",
-    "history_sync_enabled",
-];
-
+/// Pause or resume capture on every device of the account, e.g. before
+/// sharing the screen.
+#[cfg(desktop)]
 #[tauri::command]
-fn update_setting(
-    db: tauri::State<'_, Arc<Database>>,
-    key: String,
-    value: String,
-) -> Result<bool, String> {
-    if !ALLOWED_SETTING_KEYS.contains(&key.as_str()) {
-        return Err(format!("Unknown setting key: {}", key));
-    }
-    db.set_setting(&key, &value).map_err(|e| e.to_string())?;
-    Ok(true)
+async fn pause_everywhere(app: tauri::AppHandle, paused: bool) -> Result<(), String> {
+    set_monitoring_everywhere(&app, paused).await
 }
 
+#[cfg(desktop)]
 #[tauri::command]
 fn toggle_monitoring(
     app: tauri::AppHandle,
@@ -345,6 +1688,7 @@ fn save_item_to_slot(
     let result = db
         .save_existing_item_to_slot(slot_number, &item_id)
         .map_err(|e| e.to_string())?;
+    telemetry::record(telemetry::Event::SlotSave);
     refresh_tray_menu(&app);
 
     // Notify sync manager of slot change
@@ -363,6 +1707,24 @@ fn is_encryption_enabled() -> bool {
     true
 }
 
+/// Why the encryption key couldn't be read at startup, if it couldn't. History
+/// is then only kept in memory until the app restarts.
+struct KeyUnavailable(Option<String>);
+
+#[tauri::command]
+fn get_key_error(state: tauri::State<'_, KeyUnavailable>) -> Option<String> {
+    state.0.clone()
+}
+
+/// Ask the keychain for the encryption key again, restarting the app with
+/// saved history once it can be read.
+#[tauri::command]
+fn retry_keychain(app: AppHandle) -> Result<(), String> {
+    crypto::keychain::get_or_create_master_key()?;
+    tracing::info!("Encryption key available, restarting");
+    app.restart()
+}
+
 // ── Debug Commands ───────────────────────────────────────────────────────────
 
 #[tauri::command]
@@ -370,6 +1732,205 @@ fn get_log_path() -> Option<String> {
     logging::log_path()
 }
 
+/// The end of the log, for the debug console. New lines follow as
+/// `log-appended` events.
+#[tauri::command]
+fn get_log_tail(lines: usize) -> Vec<String> {
+    logging::tail(lines)
+}
+
+#[tauri::command]
+async fn set_log_level(app: tauri::AppHandle, level: logging::LogLevel) -> Result<(), String> {
+    let mut changes = serde_json::Map::new();
+    changes.insert(
+        "log_level".to_string(),
+        serde_json::to_value(level).map_err(|e| e.to_string())?,
+    );
+    apply_settings(&app, changes).await?;
+    Ok(())
+}
+
+/// Zip up everything a bug report needs, with personal data scrubbed. Saved
+/// to `path`, or the Downloads folder; returns where it was written.
+#[tauri::command]
+async fn export_diagnostics(
+    app: tauri::AppHandle,
+    db: tauri::State<'_, Arc<Database>>,
+    sync: tauri::State<'_, Arc<SyncManager>>,
+    path: Option<String>,
+) -> Result<String, String> {
+    let path = match path {
+        Some(path) => std::path::PathBuf::from(path),
+        None => {
+            let dir = app.path().download_dir().map_err(|e| e.to_string())?;
+            let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+            dir.join(format!("clipslot-diagnostics-{}.zip", stamp))
+        }
+    };
+
+    let mut files: Vec<(&str, Vec<u8>)> = Vec::new();
+    if let Some(log_path) = logging::log_path().map(std::path::PathBuf::from) {
+        let old_path = log_path.with_file_name("clipslot.old.log");
+        for (name, path) in [("clipslot.log", log_path), ("clipslot.old.log", old_path)] {
+            if let Ok(text) = std::fs::read_to_string(&path) {
+                files.push((name, diagnostics::scrub(&text).into_bytes()));
+            }
+        }
+    }
+
+    let crashes_dir = crash::crashes_dir(&app.path().app_data_dir().map_err(|e| e.to_string())?);
+    let crash_reports: Vec<(String, Vec<u8>)> = crash::list(&crashes_dir)
+        .into_iter()
+        .filter_map(|report| {
+            let text = crash::read(&crashes_dir, &report.file_name).ok()?;
+            Some((
+                format!("crashes/{}", report.file_name),
+                diagnostics::scrub(&text).into_bytes(),
+            ))
+        })
+        .collect();
+    for (name, text) in &crash_reports {
+        files.push((name.as_str(), text.clone()));
+    }
+
+    let (settings, schema, server_url) = db
+        .call(|db| {
+            let settings = db.get_all_settings()?;
+            let schema = db.get_schema()?;
+            Ok::<_, rusqlite::Error>((settings, schema, db.get_setting("sync_server_url")))
+        })
+        .await
+        .map_err(|e| e.to_string())?;
+    let settings = diagnostics::redact_settings(&settings);
+    files.push(("settings.json", to_pretty_json(&settings)?));
+
+    let (version, statements) = schema;
+    let schema = format!("-- user_version = {}\n\n{};\n", version, statements.join(";\n\n"));
+    files.push(("schema.sql", schema.into_bytes()));
+
+    let sync_state = serde_json::to_value(sync.get_sync_status().await)
+        .map_err(|e| e.to_string())?;
+    let sync_info = serde_json::json!({
+        "state": sync_state,
+        "server_url": server_url,
+    });
+    let sync_info = diagnostics::scrub(&String::from_utf8_lossy(&to_pretty_json(&sync_info)?));
+    files.push(("sync.json", sync_info.into_bytes()));
+
+    let platform = serde_json::json!({
+        "app_version": env!("CARGO_PKG_VERSION"),
+        "os": std::env::consts::OS,
+        "os_family": std::env::consts::FAMILY,
+        "arch": std::env::consts::ARCH,
+        "exported_at": chrono::Local::now().to_rfc3339(),
+    });
+    files.push(("platform.json", to_pretty_json(&platform)?));
+
+    diagnostics::write_bundle(&path, &files)?;
+    tracing::info!("Diagnostics exported to {}", path.display());
+    Ok(path.display().to_string())
+}
+
+/// Check history for items added, changed or deleted outside ClipSlot.
+#[tauri::command]
+async fn verify_history_integrity(
+    db: tauri::State<'_, Arc<Database>>,
+) -> Result<IntegrityReport, String> {
+    let report = db
+        .call(|db| db.verify_history_integrity())
+        .await
+        .map_err(|e| e.to_string())?;
+    if !report.problems.is_empty() {
+        tracing::warn!("History integrity check: {}", report.problems.join("; "));
+    }
+    Ok(report)
+}
+
+/// Crash reports saved by earlier runs, newest first.
+#[tauri::command]
+fn get_crash_reports(app: tauri::AppHandle) -> Result<Vec<crash::CrashReport>, String> {
+    let data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    Ok(crash::list(&crash::crashes_dir(&data_dir)))
+}
+
+/// Save a copy of a crash report, with personal data scrubbed, to the
+/// Downloads folder for attaching to a bug report; returns where it went.
+#[tauri::command]
+fn share_crash_report(app: tauri::AppHandle, file_name: String) -> Result<String, String> {
+    let data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    let text = crash::read(&crash::crashes_dir(&data_dir), &file_name)?;
+    let dir = app.path().download_dir().map_err(|e| e.to_string())?;
+    let path = dir.join(format!("clipslot-{}", file_name));
+    std::fs::write(&path, diagnostics::scrub(&text))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    tracing::info!("Crash report saved to {}", path.display());
+    Ok(path.display().to_string())
+}
+
+fn to_pretty_json(value: &serde_json::Value) -> Result<Vec<u8>, String> {
+    serde_json::to_vec_pretty(value).map_err(|e| e.to_string())
+}
+
+// ── Update Commands ──────────────────────────────────────────────────────────
+
+/// Check for a newer release. A found update is also offered in the tray.
+#[cfg(desktop)]
+#[tauri::command]
+async fn check_for_updates(
+    app: tauri::AppHandle,
+) -> Result<Option<updater::UpdateInfo>, String> {
+    let update = updater::check(&app).await?;
+    *app.state::<updater::AvailableUpdate>().0.lock().unwrap() = update.clone();
+    refresh_tray_menu(&app);
+    Ok(update)
+}
+
+#[cfg(desktop)]
+#[tauri::command]
+async fn install_update(app: tauri::AppHandle) -> Result<(), String> {
+    updater::install(&app).await
+}
+
+// ── Local API Commands ───────────────────────────────────────────────────────
+
+#[cfg(desktop)]
+#[tauri::command]
+fn get_local_api_info(app: tauri::AppHandle) -> local_api::LocalApiInfo {
+    local_api::info(&app)
+}
+
+/// Turn the local API on or off and pick its port; restarts it if running.
+#[cfg(desktop)]
+#[tauri::command]
+async fn configure_local_api(
+    app: tauri::AppHandle,
+    enabled: bool,
+    port: u16,
+) -> Result<local_api::LocalApiInfo, String> {
+    let mut changes = serde_json::Map::new();
+    changes.insert("local_api_enabled".to_string(), enabled.into());
+    changes.insert("local_api_port".to_string(), port.into());
+    apply_settings(&app, changes).await?;
+    // Unchanged settings, but it may have failed to start earlier
+    if enabled && !local_api::info(&app).running {
+        local_api::start(&app).await?;
+    }
+    Ok(local_api::info(&app))
+}
+
+/// New token for the local API; restarts it so the old token stops working.
+#[cfg(desktop)]
+#[tauri::command]
+async fn regenerate_local_api_token(
+    app: tauri::AppHandle,
+) -> Result<local_api::LocalApiInfo, String> {
+    local_api::regenerate_token(&app.state::<Arc<Database>>());
+    if local_api::info(&app).running {
+        local_api::start(&app).await?;
+    }
+    Ok(local_api::info(&app))
+}
+
 // ── Sync Commands ────────────────────────────────────────────────────────────
 
 #[tauri::command]
@@ -378,18 +1939,18 @@ async fn sync_login(
     email: String,
     password: String,
 ) -> Result<sync::types::SyncState, String> {
-    clog!("Login attempt for {}", email);
+    tracing::info!("Login attempt for {}", email);
     let state = sync.login(&email, &password).await?;
-    clog!("Login successful, starting background sync...");
+    tracing::info!("Login successful, starting background sync...");
     let sync_bg = sync.inner().clone();
     tokio::spawn(async move {
         match sync_bg.start_sync().await {
-            Ok(msg) => clog!("Post-login sync: {}", msg),
-            Err(e) => clog!("ERROR: Post-login sync failed: {}", e),
+            Ok(msg) => tracing::info!("Post-login sync: {}", msg),
+            Err(e) => tracing::error!("Post-login sync failed: {}", e),
         }
         match sync_bg.connect_ws().await {
-            Ok(()) => clog!("Post-login WS connected"),
-            Err(e) => clog!("ERROR: Post-login WS connect failed: {}", e),
+            Ok(()) => tracing::info!("Post-login WS connected"),
+            Err(e) => tracing::error!("Post-login WS connect failed: {}", e),
         }
         sync_bg.spawn_ws_reconnect_loop();
     });
@@ -402,18 +1963,18 @@ async fn sync_register(
     email: String,
     password: String,
 ) -> Result<sync::types::SyncState, String> {
-    clog!("Register attempt for {}", email);
+    tracing::info!("Register attempt for {}", email);
     let state = sync.register(&email, &password).await?;
-    clog!("Register successful, starting background sync...");
+    tracing::info!("Register successful, starting background sync...");
     let sync_bg = sync.inner().clone();
     tokio::spawn(async move {
         match sync_bg.start_sync().await {
-            Ok(msg) => clog!("Post-register sync: {}", msg),
-            Err(e) => clog!("ERROR: Post-register sync failed: {}", e),
+            Ok(msg) => tracing::info!("Post-register sync: {}", msg),
+            Err(e) => tracing::error!("Post-register sync failed: {}", e),
         }
         match sync_bg.connect_ws().await {
-            Ok(()) => clog!("Post-register WS connected"),
-            Err(e) => clog!("ERROR: Post-register WS connect failed: {}", e),
+            Ok(()) => tracing::info!("Post-register WS connected"),
+            Err(e) => tracing::error!("Post-register WS connect failed: {}", e),
         }
         sync_bg.spawn_ws_reconnect_loop();
     });
@@ -441,14 +2002,19 @@ async fn get_linked_devices(
 
 #[tauri::command]
 async fn force_sync(sync: tauri::State<'_, Arc<SyncManager>>) -> Result<String, String> {
-    clog!("Force sync requested");
+    tracing::info!("Force sync requested");
+    sync_now(sync.inner().clone()).await
+}
+
+/// Full sync, then (re)connect the WebSocket. Used by the UI and the tray.
+async fn sync_now(sync: Arc<SyncManager>) -> Result<String, String> {
     let result = sync.start_sync().await?;
-    clog!("Force sync result: {}", result);
+    tracing::info!("Force sync result: {}", result);
     match sync.connect_ws().await {
-        Ok(()) => clog!("Force sync: WS connected"),
-        Err(e) => clog!("ERROR: Force sync WS connect failed: {}", e),
+        Ok(()) => tracing::info!("Force sync: WS connected"),
+        Err(e) => tracing::error!("Force sync WS connect failed: {}", e),
     }
-    sync.inner().clone().spawn_ws_reconnect_loop();
+    sync.spawn_ws_reconnect_loop();
     Ok(result)
 }
 
@@ -463,21 +2029,154 @@ fn toggle_history_sync(
     Ok(enabled)
 }
 
+/// Upload blind-index tokens with history so it can be searched on the server.
+/// Only items synced from now on are indexed.
+#[tauri::command]
+fn toggle_search_index(
+    db: tauri::State<'_, Arc<Database>>,
+    enabled: bool,
+) -> Result<bool, String> {
+    let value = if enabled { "true" } else { "false" };
+    db.set_setting(sync::history_sync::SEARCH_INDEX_KEY, value)
+        .map_err(|e| e.to_string())?;
+    Ok(enabled)
+}
+
+/// Bumped for every new link code, so the previous code's countdown stops.
+static LINK_CODE_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+fn emit_link_status(app: &AppHandle, status: &LinkStatus) {
+    let _ = app.emit("device-link-status", status);
+}
+
+/// Generate a code another device can enter to get this one's encryption
+/// key. `device-link-status` counts down every second until it expires.
+#[tauri::command]
+async fn generate_device_link_code(
+    app: tauri::AppHandle,
+    sync: tauri::State<'_, Arc<SyncManager>>,
+) -> Result<String, String> {
+    let token = sync
+        .get_token()
+        .await
+        .ok_or_else(|| "Not logged in".to_string())?;
+    let api = sync.get_api().await;
+    let code = match sync::key_exchange::generate_link_code(&api, &token).await {
+        Ok(code) => code,
+        Err(error) => {
+            emit_link_status(&app, &LinkStatus::Failed { error: error.clone() });
+            return Err(error);
+        }
+    };
+
+    let generation = LINK_CODE_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    let countdown_code = code.clone();
+    tauri::async_runtime::spawn(async move {
+        let started = std::time::Instant::now();
+        while LINK_CODE_GENERATION.load(Ordering::SeqCst) == generation {
+            let status = LinkStatus::of_code(&countdown_code, started.elapsed());
+            emit_link_status(&app, &status);
+            if status == LinkStatus::Expired {
+                break;
+            }
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        }
+    });
+    Ok(code)
+}
+
+/// Redeem a code generated on another device, importing its encryption key.
+/// `device-link-status` says whether it worked.
+#[tauri::command]
+async fn redeem_device_link_code(
+    app: tauri::AppHandle,
+    sync: tauri::State<'_, Arc<SyncManager>>,
+    code: String,
+) -> Result<(), String> {
+    let token = sync
+        .get_token()
+        .await
+        .ok_or_else(|| "Not logged in".to_string())?;
+    let api = sync.get_api().await;
+    emit_link_status(&app, &LinkStatus::Redeeming);
+    match sync::key_exchange::redeem_link_code(&api, &token, &code).await {
+        Ok(()) => {
+            emit_link_status(&app, &LinkStatus::Linked);
+            Ok(())
+        }
+        Err(error) => {
+            emit_link_status(&app, &LinkStatus::Failed { error: error.clone() });
+            Err(error)
+        }
+    }
+}
+
+// ── Teams ───────────────────────────────────────────────────────────────────
+
+fn parse_team_id(team_id: &str) -> Result<uuid::Uuid, String> {
+    uuid::Uuid::parse_str(team_id).map_err(|e| format!("Invalid team id: {}", e))
+}
+
+#[tauri::command]
+async fn list_teams(
+    sync: tauri::State<'_, Arc<SyncManager>>,
+) -> Result<Vec<sync::types::TeamResponse>, String> {
+    let token = sync
+        .get_token()
+        .await
+        .ok_or_else(|| "Not logged in".to_string())?;
+    let api = sync.get_api().await;
+    api.list_teams(&token).await
+}
+
+#[tauri::command]
+async fn create_team(
+    sync: tauri::State<'_, Arc<SyncManager>>,
+    name: String,
+) -> Result<sync::types::TeamResponse, String> {
+    let token = sync
+        .get_token()
+        .await
+        .ok_or_else(|| "Not logged in".to_string())?;
+    let api = sync.get_api().await;
+    sync::team_sync::create_team(&api, &token, &name).await
+}
+
+#[tauri::command]
+async fn add_team_member(
+    sync: tauri::State<'_, Arc<SyncManager>>,
+    team_id: String,
+    email: String,
+) -> Result<(), String> {
+    let token = sync
+        .get_token()
+        .await
+        .ok_or_else(|| "Not logged in".to_string())?;
+    let api = sync.get_api().await;
+    api.add_team_member(&token, parse_team_id(&team_id)?, &email)
+        .await
+}
+
 #[tauri::command]
-async fn generate_link_code(
+async fn share_team_key(
     sync: tauri::State<'_, Arc<SyncManager>>,
+    team_id: String,
+    member_id: String,
 ) -> Result<String, String> {
     let token = sync
         .get_token()
         .await
         .ok_or_else(|| "Not logged in".to_string())?;
+    let member_id =
+        uuid::Uuid::parse_str(&member_id).map_err(|e| format!("Invalid member id: {}", e))?;
     let api = sync.get_api().await;
-    sync::key_exchange::generate_link_code(&api, &token).await
+    sync::team_sync::share_team_key(&api, &token, parse_team_id(&team_id)?, member_id).await
 }
 
 #[tauri::command]
-async fn enter_link_code(
+async fn redeem_team_key(
     sync: tauri::State<'_, Arc<SyncManager>>,
+    team_id: String,
     code: String,
 ) -> Result<(), String> {
     let token = sync
@@ -485,34 +2184,90 @@ async fn enter_link_code(
         .await
         .ok_or_else(|| "Not logged in".to_string())?;
     let api = sync.get_api().await;
-    sync::key_exchange::redeem_link_code(&api, &token, &code).await
+    sync::team_sync::redeem_team_key(&api, &token, parse_team_id(&team_id)?, &code).await
+}
+
+/// A team's shared slots as last received, kept apart from the personal slots.
+#[tauri::command]
+fn get_team_slots(
+    db: tauri::State<'_, Arc<Database>>,
+    team_id: String,
+) -> Result<Vec<TeamSlot>, String> {
+    db.get_team_slots(&parse_team_id(&team_id)?.to_string())
+        .map_err(|e| e.to_string())
 }
 
 // ── App Entry ───────────────────────────────────────────────────────────────
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    tauri::Builder::default()
+    let builder = tauri::Builder::default();
+
+    // Must come first: a second launch (e.g. to open a clipslot:// link)
+    // hands its link to this instance and exits
+    #[cfg(desktop)]
+    let builder = builder.plugin(tauri_plugin_single_instance::init(|_app, _argv, _cwd| {}));
+
+    let builder = builder
+        .plugin(tauri_plugin_deep_link::init())
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_clipboard_manager::init())
-        .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_notification::init());
+
+    #[cfg(desktop)]
+    let builder = builder
+        .plugin(slots::manager::shortcut_plugin())
+        .plugin(
+            tauri_plugin_updater::Builder::new()
+                .pubkey(config::UPDATER_PUBKEY.unwrap_or_default())
+                .build(),
+        )
         .invoke_handler(tauri::generate_handler![
             get_clipboard_history,
             search_history,
             delete_history_item,
             clear_history,
             get_history_count,
+            get_system_history_count,
+            import_system_history,
+            get_history_languages,
+            get_frequent_items,
+            get_history_previews,
+            get_history_page,
+            get_known_devices,
+            get_history_grouped,
+            send_to_device,
+            pull_device_clipboard,
+            create_share_link,
+            get_status_summary,
+            get_history_item,
+            get_item_qr,
+            pin_item,
+            unpin_item,
+            get_pinned_items,
             copy_to_clipboard,
+            copy_history_item,
+        clear_system_clipboard,
+            transform_and_copy,
+            diff_items,
             save_to_slot,
             get_slot,
             get_all_slots,
+            get_slot_count,
+            set_slot_count,
             clear_slot,
             rename_slot,
             get_settings,
-            update_setting,
+            update_settings,
             toggle_monitoring,
+            pause_everywhere,
+            paste_and_remove,
+            paste_items_sequentially,
+            confirm_slot_paste,
             save_item_to_slot,
             is_encryption_enabled,
+            get_key_error,
+            retry_keychain,
             sync_login,
             sync_register,
             sync_logout,
@@ -520,10 +2275,106 @@ pub fn run() {
             get_linked_devices,
             force_sync,
             toggle_history_sync,
-            generate_link_code,
-            enter_link_code,
+            toggle_search_index,
+            generate_device_link_code,
+            redeem_device_link_code,
+            list_teams,
+            create_team,
+            add_team_member,
+            share_team_key,
+            redeem_team_key,
+            get_team_slots,
             get_log_path,
+            get_log_tail,
+            set_log_level,
+            export_diagnostics,
+            verify_history_integrity,
+            get_crash_reports,
+            share_crash_report,
+            check_for_updates,
+            install_update,
+            get_local_api_info,
+            configure_local_api,
+            regenerate_local_api_token,
         ])
+        .on_window_event(|_window, event| {
+            // Prevent app from quitting when windows are closed — it's a tray app
+            if let tauri::WindowEvent::CloseRequested { api, .. } = event {
+                api.prevent_close();
+                let _ = _window.hide();
+            }
+        });
+
+    // Phones can't watch the clipboard or take global shortcuts, and have no
+    // tray; the app views and copies history and slots
+    #[cfg(mobile)]
+    let builder = builder.invoke_handler(tauri::generate_handler![
+        get_clipboard_history,
+        search_history,
+        delete_history_item,
+        clear_history,
+        get_history_count,
+        get_history_languages,
+        get_frequent_items,
+        get_history_previews,
+        get_history_page,
+        get_known_devices,
+        get_history_grouped,
+        send_to_device,
+        pull_device_clipboard,
+        create_share_link,
+        get_status_summary,
+        get_history_item,
+        get_item_qr,
+        pin_item,
+        unpin_item,
+        get_pinned_items,
+        copy_to_clipboard,
+        copy_history_item,
+        clear_system_clipboard,
+        transform_and_copy,
+        diff_items,
+        save_to_slot,
+        get_slot,
+        get_all_slots,
+        get_slot_count,
+        set_slot_count,
+        clear_slot,
+        rename_slot,
+        get_settings,
+        update_settings,
+        save_item_to_slot,
+        is_encryption_enabled,
+        get_key_error,
+        retry_keychain,
+        sync_login,
+        sync_register,
+        sync_logout,
+        get_sync_status,
+        get_linked_devices,
+        force_sync,
+        toggle_history_sync,
+        toggle_search_index,
+        generate_device_link_code,
+        redeem_device_link_code,
+        list_teams,
+        create_team,
+        add_team_member,
+        share_team_key,
+        redeem_team_key,
+        get_team_slots,
+        get_log_path,
+        get_log_tail,
+        set_log_level,
+        export_diagnostics,
+        verify_history_integrity,
+        get_crash_reports,
+        share_crash_report,
+        mobile::capture_clipboard,
+        mobile::save_shared_text,
+    ]);
+
+    builder
         .setup(|app| {
             // Initialize file logging first
             let data_dir = app
@@ -531,6 +2382,11 @@ pub fn run() {
                 .app_data_dir()
                 .expect("failed to resolve app data dir");
             logging::init(&data_dir);
+            crash::install(&data_dir);
+            let log_handle = app.handle().clone();
+            logging::set_listener(move |line| {
+                let _ = log_handle.emit("log-appended", line);
+            });
 
             #[cfg(target_os = "macos")]
             {
@@ -541,61 +2397,88 @@ pub fn run() {
                 }
                 let trusted = unsafe { AXIsProcessTrusted() };
                 if trusted {
-                    clog!("Accessibility: granted");
+                    tracing::info!("Accessibility: granted");
                 } else {
-                    clog!("WARNING: Accessibility not granted — shortcuts won't work");
-                    clog!("Grant access in: System Settings > Privacy & Security > Accessibility");
+                    tracing::warn!("Accessibility not granted — shortcuts won't work");
+                    tracing::info!(
+                        "Grant access in: System Settings > Privacy & Security > Accessibility"
+                    );
                 }
             }
 
             // Initialize encryption (set data dir first so file fallback works)
-            clog!("Initializing encryption...");
+            tracing::info!("Initializing encryption...");
             let data_dir_enc = app
                 .path()
                 .app_data_dir()
                 .expect("failed to resolve app data dir");
             crypto::keychain::set_app_data_dir(data_dir_enc);
-            let master_key = crypto::keychain::get_or_create_master_key()
-                .map_err(|e| Box::<dyn std::error::Error>::from(e))?;
-            let crypto_engine = Arc::new(CryptoEngine::new(&master_key));
-            clog!("Encryption initialized");
-
-            // Initialize database
-            let data_dir = app
-                .path()
-                .app_data_dir()
-                .expect("failed to resolve app data dir");
-            let db = Arc::new(
-                Database::new(data_dir, crypto_engine).expect("failed to initialize database"),
-            );
-            app.manage(db.clone());
-            clog!("Database initialized");
+            let key_error = match crypto::keychain::get_or_create_master_key() {
+                Ok(master_key) => {
+                    let crypto_engine = Arc::new(CryptoEngine::new(&master_key));
+                    tracing::info!("Encryption initialized");
+
+                    // Initialize database
+                    let data_dir = app
+                        .path()
+                        .app_data_dir()
+                        .expect("failed to resolve app data dir");
+                    let (db, recovery) = Database::open(data_dir, crypto_engine)
+                        .map_err(Box::<dyn std::error::Error>::from)?;
+                    app.manage(Arc::new(db));
+                    notify_database_recovery(app.handle(), &recovery);
+                    None
+                }
+                Err(e) => {
+                    // Saved history can't be read without the key; run on an
+                    // empty one in memory until `retry_keychain` gets it
+                    tracing::error!("Encryption key unavailable: {}", e);
+                    tracing::info!("History will not be saved this session");
+                    use rand::RngCore;
+                    let mut temp_key = [0u8; 32];
+                    rand::thread_rng().fill_bytes(&mut temp_key);
+                    let crypto_engine = Arc::new(CryptoEngine::new(&temp_key));
+                    app.manage(Arc::new(Database::in_memory(crypto_engine)?));
+                    notify_key_unavailable(app.handle());
+                    Some(e)
+                }
+            };
+            app.manage(KeyUnavailable(key_error));
+            let db = app.state::<Arc<Database>>().inner().clone();
+            let settings = Settings::load(&db);
+            logging::set_level(settings.log_level);
+            logging::set_hash_key(&db);
+            logging::set_redaction(settings.log_redaction);
+            i18n::set_language(settings.language);
+            telemetry::init(&db, settings.telemetry_enabled);
+            notify_new_crash_reports(app.handle(), &db);
+            tracing::info!("Database initialized");
 
             // Initialize sync manager
             let server_url = db
                 .get_setting("sync_server_url")
                 .unwrap_or_else(|| "not set".to_string());
-            clog!("Sync server URL: {}", server_url);
+            tracing::info!("Sync server URL: {}", server_url);
             let sync_manager = Arc::new(SyncManager::new(db.clone()));
             app.manage(sync_manager.clone());
-            clog!("SyncManager initialized, has_auth={}", sync_manager.has_auth());
+            tracing::info!("SyncManager initialized, has_auth={}", sync_manager.has_auth());
 
             // Auto-sync + connect WebSocket if already authenticated.
             // The thread + runtime must stay alive to keep the WS connection open.
             if sync_manager.has_auth() {
-                clog!("Auth found, starting auto-sync...");
+                tracing::info!("Auth found, starting auto-sync...");
                 let sm = sync_manager.clone();
                 std::thread::spawn(move || {
                     let rt = tokio::runtime::Runtime::new()
                         .expect("Failed to create sync runtime");
                     rt.block_on(async {
                         match sm.start_sync().await {
-                            Ok(msg) => clog!("Auto-sync completed: {}", msg),
-                            Err(e) => clog!("ERROR: Auto-sync failed: {}", e),
+                            Ok(msg) => tracing::info!("Auto-sync completed: {}", msg),
+                            Err(e) => tracing::error!("Auto-sync failed: {}", e),
                         }
                         match sm.connect_ws().await {
-                            Ok(()) => clog!("WebSocket connected"),
-                            Err(e) => clog!("ERROR: WS connect failed: {}", e),
+                            Ok(()) => tracing::info!("WebSocket connected"),
+                            Err(e) => tracing::error!("WS connect failed: {}", e),
                         }
                         sm.clone().spawn_ws_reconnect_loop();
                         // Keep runtime alive so WS tasks continue running
@@ -603,50 +2486,214 @@ pub fn run() {
                     });
                 });
             } else {
-                clog!("No auth found, skipping auto-sync");
+                tracing::info!("No auth found, skipping auto-sync");
             }
 
-            // Start clipboard monitoring
-            let device_id = get_or_create_device_id();
-            println!("[ClipSlot] Device ID: {}", device_id);
-
-            let monitor = Arc::new(ClipboardMonitor::new());
-            monitor.start(app.handle().clone(), device_id, db.clone(), Some(sync_manager));
-            app.manage(monitor);
-
-            // Start keyboard listener for slot shortcuts
-            slots::manager::start_shortcut_listener(app.handle().clone());
-
-            // Build initial tray menu with slot previews
-            let slots = db.get_all_slots().unwrap_or_default();
-            let menu = build_tray_menu(app.handle(), &slots, false)?;
+            #[cfg(desktop)]
+            setup_desktop(app, &db, sync_manager)?;
+            #[cfg(mobile)]
+            mobile::setup(app)?;
 
-            let tray = TrayIconBuilder::with_id("main")
-                .icon(app.default_window_icon().unwrap().clone())
-                .menu(&menu)
-                .show_menu_on_left_click(true)
-                .on_menu_event(|app, event| {
-                    handle_tray_menu_event(app, event.id.as_ref());
-                })
-                .build(app)?;
+            // Keep the tray's sync status current, and tell the UI
+            let handle = app.handle().clone();
+            let mut status_rx = app.state::<Arc<SyncManager>>().subscribe_status();
+            tauri::async_runtime::spawn(async move {
+                while status_rx.changed().await.is_ok() {
+                    let status = status_rx.borrow_and_update().clone();
+                    let _ = handle.emit("sync-status", &status);
+                    refresh_tray_menu(&handle);
+                }
+            });
 
-            app.manage(TrayIconHandle(tray));
+            // Changes waiting for the server, e.g. "3 changes queued" while offline
+            let handle = app.handle().clone();
+            let sync_manager = app.state::<Arc<SyncManager>>().inner().clone();
+            let mut pending_rx = sync_manager.subscribe_pending_changes();
+            tauri::async_runtime::spawn(async move {
+                while pending_rx.changed().await.is_ok() {
+                    let pending = *pending_rx.borrow_and_update();
+                    let _ = handle.emit("sync-pending-changes", pending);
+                    if sync_manager.get_status_blocking() == sync::types::SyncStatus::Offline {
+                        refresh_tray_menu(&handle);
+                    }
+                }
+            });
 
-            // Listen for slot changes from the shortcut listener thread
+            // Content sent here from another device is copied right away
             let handle = app.handle().clone();
-            app.listen("slot-changed", move |_| {
-                refresh_tray_menu(&handle);
+            let mut deliveries = app.state::<Arc<SyncManager>>().subscribe_deliveries();
+            tauri::async_runtime::spawn(async move {
+                loop {
+                    match deliveries.recv().await {
+                        Ok(delivery) => receive_from_device(&handle, delivery).await,
+                        Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                        Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                    }
+                }
+            });
+
+            // Send finished days of usage counts, if opted in
+            let telemetry_db = db.clone();
+            let app_version = app.package_info().version.to_string();
+            tauri::async_runtime::spawn(async move {
+                loop {
+                    let server_url = Settings::load(&telemetry_db).sync_server_url;
+                    telemetry::flush(&telemetry_db, &server_url, &app_version).await;
+                    tokio::time::sleep(std::time::Duration::from_secs(60 * 60)).await;
+                }
             });
 
             Ok(())
         })
-        .on_window_event(|_window, event| {
-            // Prevent app from quitting when windows are closed — it's a tray app
-            if let tauri::WindowEvent::CloseRequested { api, .. } = event {
-                api.prevent_close();
-                let _ = _window.hide();
-            }
-        })
         .run(tauri::generate_context!())
         .expect("error while running ClipSlot");
 }
+
+/// Capture, shortcuts, the tray and everything else only the desktop app has.
+#[cfg(desktop)]
+fn setup_desktop(
+    app: &mut tauri::App,
+    db: &Arc<Database>,
+    sync_manager: Arc<SyncManager>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Start clipboard monitoring
+    let device_id = get_or_create_device_id(db);
+    tracing::info!("Device ID: {}", device_id);
+
+    let monitor = Arc::new(ClipboardMonitor::new());
+    monitor.start(app.handle().clone(), device_id, db.clone(), Some(sync_manager));
+    app.manage(monitor);
+    offer_system_history_import(app.handle(), db);
+
+    // Slot shortcuts are OS hotkeys; besides the monitor, only the opt-in
+    // snippet listener polls
+    slots::manager::register_shortcuts(app.handle());
+    app.manage(Arc::new(slots::manager::SnippetListener::default()));
+    slots::manager::configure_snippets(app.handle(), &Settings::load(db));
+
+    // Build initial tray menu with slot and recent history previews
+    let entries = current_tray_entries(app.handle());
+    let menu = build_menu(app.handle(), &entries)?;
+
+    let tray = TrayIconBuilder::with_id("main")
+        .icon(app.default_window_icon().unwrap().clone())
+        .menu(&menu)
+        .show_menu_on_left_click(!picker_on_left_click(&db))
+        .on_menu_event(|app, event| {
+            handle_tray_menu_event(app, event.id.as_ref());
+        })
+        .on_tray_icon_event(|tray, event| {
+            if let TrayIconEvent::Click {
+                button: MouseButton::Left,
+                button_state: MouseButtonState::Up,
+                position,
+                ..
+            } = event
+            {
+                let app = tray.app_handle();
+                if picker_on_left_click(&app.state::<Arc<Database>>()) {
+                    show_quick_picker(app, position);
+                }
+            }
+        })
+        .build(app)?;
+
+    app.manage(TrayIconHandle(tray));
+    app.manage(TrayRefresh {
+        pending: AtomicBool::new(false),
+        shown: Mutex::new(entries),
+    });
+    app.manage(updater::AvailableUpdate::default());
+    app.manage(local_api::LocalApi::default());
+
+    // Listen for slot changes from the shortcut listener thread
+    let handle = app.handle().clone();
+    app.listen("slot-changed", move |_| {
+        refresh_tray_menu(&handle);
+    });
+
+    // Other devices can fetch what is on this clipboard
+    let handle = app.handle().clone();
+    let mut requests = app.state::<Arc<SyncManager>>().subscribe_clipboard_requests();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match requests.recv().await {
+                Ok(request) => answer_clipboard_request(&handle, request).await,
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+
+    // Other devices can pause capture here too
+    let handle = app.handle().clone();
+    let mut changes = app.state::<Arc<SyncManager>>().subscribe_monitoring_changes();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match changes.recv().await {
+                Ok(change) => apply_monitoring_change(&handle, change).await,
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+
+    // New captures show up under "Recent"; bursts rebuild the menu once
+    let handle = app.handle().clone();
+    app.listen("clipboard-changed", move |_| {
+        refresh_tray_menu(&handle);
+    });
+
+    if db.get_setting("local_api_enabled").as_deref() == Some("true") {
+        let handle = app.handle().clone();
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = local_api::start(&handle).await {
+                tracing::error!("{}", e);
+            }
+        });
+    }
+
+    // clipslot:// links, including one the app was launched with
+    #[cfg(any(target_os = "linux", all(debug_assertions, windows)))]
+    if let Err(e) = app.deep_link().register_all() {
+        tracing::error!("Failed to register {}:// links: {}", deep_link::SCHEME, e);
+    }
+    let handle = app.handle().clone();
+    app.deep_link().on_open_url(move |event| {
+        for url in event.urls() {
+            handle_deep_link(&handle, &url);
+        }
+    });
+    if let Ok(Some(urls)) = app.deep_link().get_current() {
+        for url in urls {
+            handle_deep_link(app.handle(), &url);
+        }
+    }
+
+    if updater::is_enabled()
+        && db.get_setting("check_updates_on_startup").as_deref() != Some("false")
+    {
+        let handle = app.handle().clone();
+        tauri::async_runtime::spawn(async move {
+            match updater::check(&handle).await {
+                Ok(Some(update)) => {
+                    use tauri_plugin_notification::NotificationExt;
+                    tracing::info!("Update available: {}", update.version);
+                    let _ = handle
+                        .notification()
+                        .builder()
+                        .title("ClipSlot")
+                        .body(i18n::text_with("update_ready", &[&update.version]))
+                        .show();
+                    *handle.state::<updater::AvailableUpdate>().0.lock().unwrap() =
+                        Some(update);
+                    refresh_tray_menu(&handle);
+                }
+                Ok(None) => tracing::info!("ClipSlot is up to date"),
+                Err(e) => tracing::error!("{}", e),
+            }
+        });
+    }
+
+    Ok(())
+}
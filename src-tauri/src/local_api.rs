@@ -0,0 +1,505 @@
+use std::sync::{Arc, Mutex};
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Path, Query, Request, State,
+    },
+    http::{header, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use tokio::sync::{broadcast, oneshot};
+
+use crate::clipboard::item::ClipboardItem;
+use crate::clipboard::monitor::ClipboardMonitor;
+use crate::slots::SlotInfo;
+use crate::storage::database::Database;
+use crate::storage::settings::DEFAULT_LOCAL_API_PORT;
+
+/// Most history items returned in one page.
+const MAX_PAGE: u32 = 500;
+
+/// Localhost-only REST API for scripts (Keyboard Maestro, AutoHotkey, curl).
+/// Off by default; every request needs the `local_api_token` as a bearer token.
+#[derive(Default)]
+pub struct LocalApi {
+    shutdown: Mutex<Option<oneshot::Sender<()>>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LocalApiInfo {
+    pub enabled: bool,
+    pub port: u16,
+    pub token: String,
+    /// Whether the server is currently listening
+    pub running: bool,
+}
+
+pub fn port(db: &Database) -> u16 {
+    db.get_setting("local_api_port")
+        .and_then(|v| v.parse().ok())
+        .filter(|&p| p != 0)
+        .unwrap_or(DEFAULT_LOCAL_API_PORT)
+}
+
+/// The API token, created on first use.
+pub fn token(db: &Database) -> String {
+    match db.get_setting("local_api_token") {
+        Some(token) if !token.is_empty() => token,
+        _ => regenerate_token(db),
+    }
+}
+
+/// Replace the API token; scripts using the old one stop working.
+pub fn regenerate_token(db: &Database) -> String {
+    use rand::RngCore;
+    let mut bytes = [0u8; 24];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    let token: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    if let Err(e) = db.set_setting("local_api_token", &token) {
+        tracing::error!("Failed to save local API token: {}", e);
+    }
+    token
+}
+
+pub fn info(app: &AppHandle) -> LocalApiInfo {
+    let db = app.state::<Arc<Database>>();
+    LocalApiInfo {
+        enabled: db.get_setting("local_api_enabled").as_deref() == Some("true"),
+        port: port(&db),
+        token: token(&db),
+        running: app.state::<LocalApi>().shutdown.lock().unwrap().is_some(),
+    }
+}
+
+/// (Re)start the server on the configured port.
+pub async fn start(app: &AppHandle) -> Result<(), String> {
+    stop(app);
+    let db = app.state::<Arc<Database>>().inner().clone();
+    let (port, token) = db.call(|db| (port(db), token(db))).await;
+
+    // Loopback only: never reachable from other machines
+    let listener = tokio::net::TcpListener::bind(("127.0.0.1", port))
+        .await
+        .map_err(|e| format!("Failed to listen on port {}: {}", port, e))?;
+
+    let state = ApiState {
+        app: app.clone(),
+        token: Arc::new(token),
+    };
+    let router = router(state);
+    let (tx, rx) = oneshot::channel();
+    *app.state::<LocalApi>().shutdown.lock().unwrap() = Some(tx);
+
+    tauri::async_runtime::spawn(async move {
+        let server = axum::serve(listener, router).with_graceful_shutdown(async {
+            let _ = rx.await;
+        });
+        if let Err(e) = server.await {
+            tracing::error!("Local API stopped: {}", e);
+        }
+    });
+    tracing::info!("Local API listening on 127.0.0.1:{}", port);
+    Ok(())
+}
+
+pub fn stop(app: &AppHandle) {
+    if let Some(tx) = app.state::<LocalApi>().shutdown.lock().unwrap().take() {
+        let _ = tx.send(());
+        tracing::info!("Local API stopped");
+    }
+}
+
+// ── Routes ───────────────────────────────────────────────────────────────────
+
+#[derive(Clone)]
+struct ApiState {
+    app: AppHandle,
+    token: Arc<String>,
+}
+
+#[derive(Serialize)]
+struct ApiError {
+    error: String,
+}
+
+fn err(status: StatusCode, msg: &str) -> (StatusCode, Json<ApiError>) {
+    (
+        status,
+        Json(ApiError {
+            error: msg.to_string(),
+        }),
+    )
+}
+
+fn internal(e: impl std::fmt::Display) -> (StatusCode, Json<ApiError>) {
+    err(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string())
+}
+
+type ApiResult<T> = Result<Json<T>, (StatusCode, Json<ApiError>)>;
+
+fn router(state: ApiState) -> Router {
+    Router::new()
+        .route("/v1", get(get_info))
+        .route("/v1/history", get(get_history))
+        .route("/v1/search", get(search))
+        .route("/v1/copy", post(copy))
+        .route("/v1/paste", post(paste))
+        .route("/v1/slots", get(get_slots))
+        .route("/v1/slots/{n}", get(get_slot).put(set_slot))
+        .route("/v1/ws", get(controller_ws))
+        .layer(middleware::from_fn_with_state(state.clone(), require_token))
+        .with_state(state)
+}
+
+/// Bearer token, or `?token=` for WebSocket clients that can't set headers.
+async fn require_token(State(state): State<ApiState>, req: Request, next: Next) -> Response {
+    let header_token = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(str::to_string);
+    let query_token = || {
+        url::form_urlencoded::parse(req.uri().query().unwrap_or_default().as_bytes())
+            .find(|(key, _)| key == "token")
+            .map(|(_, value)| value.into_owned())
+    };
+    let authorized = header_token
+        .or_else(query_token)
+        .is_some_and(|t| t == state.token.as_str());
+    if !authorized {
+        return err(StatusCode::UNAUTHORIZED, "Missing or invalid token").into_response();
+    }
+    next.run(req).await
+}
+
+/// Version of the API; v1 only ever gains fields and endpoints.
+const API_VERSION: u32 = 1;
+
+#[derive(Serialize)]
+struct ApiInfo {
+    api_version: u32,
+    app_version: String,
+    slot_count: u32,
+}
+
+/// GET /v1: lets extensions check what they're talking to
+async fn get_info(State(state): State<ApiState>) -> Json<ApiInfo> {
+    let db = state.app.state::<Arc<Database>>().inner().clone();
+    Json(ApiInfo {
+        api_version: API_VERSION,
+        app_version: state.app.package_info().version.to_string(),
+        slot_count: db.call(|db| db.get_slot_count()).await,
+    })
+}
+
+#[derive(Deserialize)]
+struct HistoryQuery {
+    limit: Option<u32>,
+    offset: Option<u32>,
+}
+
+/// GET /v1/history?limit=50&offset=0: newest first
+async fn get_history(
+    State(state): State<ApiState>,
+    Query(q): Query<HistoryQuery>,
+) -> ApiResult<Vec<ClipboardItem>> {
+    let db = state.app.state::<Arc<Database>>().inner().clone();
+    let limit = q.limit.unwrap_or(50).min(MAX_PAGE);
+    db.call(move |db| db.get_history(limit, q.offset.unwrap_or(0)))
+        .await
+        .map(Json)
+        .map_err(internal)
+}
+
+#[derive(Deserialize)]
+struct SearchQuery {
+    q: String,
+    limit: Option<usize>,
+}
+
+/// GET /v1/search?q=text&limit=20
+async fn search(
+    State(state): State<ApiState>,
+    Query(q): Query<SearchQuery>,
+) -> ApiResult<Vec<ClipboardItem>> {
+    let db = state.app.state::<Arc<Database>>().inner().clone();
+    let mut items = db.call(move |db| db.search(&q.q)).await.map_err(internal)?;
+    if let Some(limit) = q.limit {
+        items.truncate(limit);
+    }
+    Ok(Json(items))
+}
+
+#[derive(Deserialize)]
+struct CopyRequest {
+    /// Copy an existing history item...
+    id: Option<String>,
+    /// ...or new text, which is captured into history like any other copy
+    text: Option<String>,
+}
+
+#[derive(Serialize)]
+struct Copied {
+    copied: bool,
+}
+
+/// POST /v1/copy {"id": "..."} or {"text": "..."}
+async fn copy(State(state): State<ApiState>, Json(req): Json<CopyRequest>) -> ApiResult<Copied> {
+    let text = match (req.id, req.text) {
+        (Some(id), None) => {
+            let db = state.app.state::<Arc<Database>>().inner().clone();
+            let content = db
+                .call(move |db| {
+                    let content = db.get_item_content(&id)?;
+                    if content.is_some() {
+                        db.record_item_use(&id)?;
+                    }
+                    Ok::<_, rusqlite::Error>(content)
+                })
+                .await
+                .map_err(internal)?
+                .ok_or_else(|| err(StatusCode::NOT_FOUND, "Item not found"))?;
+            state.app.state::<Arc<ClipboardMonitor>>().set_skip_next();
+            content
+        }
+        (None, Some(text)) => text,
+        _ => return Err(err(StatusCode::BAD_REQUEST, "Provide exactly one of id or text")),
+    };
+    state.app.clipboard().write_text(&text).map_err(internal)?;
+    Ok(Json(Copied { copied: true }))
+}
+
+#[derive(Deserialize)]
+struct PasteRequest {
+    /// Paste a history item...
+    id: Option<String>,
+    /// ...or a slot
+    slot: Option<u32>,
+}
+
+#[derive(Serialize)]
+struct Pasted {
+    pasted: bool,
+}
+
+/// POST /v1/paste {"id": "..."} or {"slot": 2}: pastes into the focused app,
+/// e.g. after a launcher closes its window
+async fn paste(State(state): State<ApiState>, Json(req): Json<PasteRequest>) -> ApiResult<Pasted> {
+    let text = {
+        let db = state.app.state::<Arc<Database>>().inner().clone();
+        match (req.id, req.slot) {
+            (Some(id), None) => db
+                .call(move |db| {
+                    let content = db.get_item_content(&id)?;
+                    if content.is_some() {
+                        db.record_item_use(&id)?;
+                    }
+                    Ok::<_, rusqlite::Error>(content)
+                })
+                .await
+                .map_err(internal)?
+                .ok_or_else(|| err(StatusCode::NOT_FOUND, "Item not found"))?,
+            (None, Some(n)) => {
+                check_slot(&db, n).await?;
+                db.call(move |db| {
+                    let slot = db.get_slot(n)?;
+                    db.record_slot_use(n)?;
+                    crate::telemetry::record(crate::telemetry::Event::SlotPaste);
+                    Ok::<_, rusqlite::Error>(slot)
+                })
+                .await
+                .map_err(internal)?
+                .content
+                .ok_or_else(|| err(StatusCode::NOT_FOUND, "Slot is empty"))?
+            }
+            _ => return Err(err(StatusCode::BAD_REQUEST, "Provide exactly one of id or slot")),
+        }
+    };
+    let app = state.app.clone();
+    tokio::task::spawn_blocking(move || crate::slots::manager::paste_text(&app, &text))
+        .await
+        .map_err(internal)?;
+    Ok(Json(Pasted { pasted: true }))
+}
+
+/// A slot number in use, or 404.
+async fn check_slot(db: &Arc<Database>, n: u32) -> Result<(), (StatusCode, Json<ApiError>)> {
+    let count = db.call(|db| db.get_slot_count()).await;
+    if !(1..=count).contains(&n) {
+        return Err(err(
+            StatusCode::NOT_FOUND,
+            &format!("Invalid slot number (1-{})", count),
+        ));
+    }
+    Ok(())
+}
+
+/// GET /v1/slots
+async fn get_slots(State(state): State<ApiState>) -> ApiResult<Vec<SlotInfo>> {
+    let db = state.app.state::<Arc<Database>>().inner().clone();
+    db.call(|db| db.get_all_slots()).await.map(Json).map_err(internal)
+}
+
+/// GET /v1/slots/{n}
+async fn get_slot(State(state): State<ApiState>, Path(n): Path<u32>) -> ApiResult<SlotInfo> {
+    let db = state.app.state::<Arc<Database>>().inner().clone();
+    check_slot(&db, n).await?;
+    db.call(move |db| db.get_slot(n)).await.map(Json).map_err(internal)
+}
+
+#[derive(Deserialize)]
+struct SetSlotRequest {
+    text: String,
+}
+
+/// PUT /v1/slots/{n} {"text": "..."}
+async fn set_slot(
+    State(state): State<ApiState>,
+    Path(n): Path<u32>,
+    Json(req): Json<SetSlotRequest>,
+) -> ApiResult<SlotInfo> {
+    check_slot(state.app.state::<Arc<Database>>().inner(), n).await?;
+    if req.text.is_empty() {
+        return Err(err(StatusCode::BAD_REQUEST, "Text is empty"));
+    }
+    crate::save_text_to_slot(&state.app, n, req.text)
+        .map(Json)
+        .map_err(internal)
+}
+
+// ── Controller WebSocket ─────────────────────────────────────────────────────
+
+/// Messages from a controller such as a Stream Deck plugin.
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ControllerRequest {
+    GetSlots,
+    /// Paste a slot into the focused app
+    Paste { slot: u32 },
+    /// Save the current clipboard to a slot
+    Save { slot: u32 },
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ControllerEvent {
+    /// Every slot in use: sent on connect and in reply to `get_slots`
+    Slots { slots: Vec<SlotInfo> },
+    SlotChanged { slot: SlotInfo },
+    Error { error: String },
+}
+
+/// GET /v1/ws: pushes slot changes and accepts paste/save commands
+async fn controller_ws(State(state): State<ApiState>, ws: WebSocketUpgrade) -> Response {
+    ws.on_upgrade(move |socket| controller_session(state, socket))
+}
+
+async fn controller_session(state: ApiState, mut socket: WebSocket) {
+    let db = state.app.state::<Arc<Database>>().inner().clone();
+    let mut changes = db.subscribe_slot_changes();
+    if send_event(&mut socket, &all_slots(&db).await).await.is_err() {
+        return;
+    }
+
+    loop {
+        let event = tokio::select! {
+            change = changes.recv() => match change {
+                Ok(n) => match slot_changed_event(&db, n).await {
+                    Some(event) => event,
+                    None => continue,
+                },
+                // Missed some changes: resend everything
+                Err(broadcast::error::RecvError::Lagged(_)) => all_slots(&db).await,
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            msg = socket.recv() => match msg {
+                Some(Ok(Message::Text(text))) => match handle_request(&state, &text).await {
+                    Some(event) => event,
+                    None => continue,
+                },
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => continue,
+            },
+        };
+        if send_event(&mut socket, &event).await.is_err() {
+            break;
+        }
+    }
+}
+
+/// Carry out a controller request; changes are reported as `slot_changed`.
+async fn handle_request(state: &ApiState, text: &str) -> Option<ControllerEvent> {
+    let request = match serde_json::from_str::<ControllerRequest>(text) {
+        Ok(request) => request,
+        Err(e) => {
+            return Some(ControllerEvent::Error {
+                error: format!("Invalid message: {}", e),
+            })
+        }
+    };
+    let db = state.app.state::<Arc<Database>>().inner().clone();
+    let slot = match request {
+        ControllerRequest::GetSlots => return Some(all_slots(&db).await),
+        ControllerRequest::Paste { slot } | ControllerRequest::Save { slot } => slot,
+    };
+    if let Err((_, Json(e))) = check_slot(&db, slot).await {
+        return Some(ControllerEvent::Error { error: e.error });
+    }
+
+    let app = state.app.clone();
+    let result = match request {
+        ControllerRequest::Paste { .. } => {
+            let content = match db.call(move |db| db.get_slot(slot)).await {
+                Ok(info) => info.content,
+                Err(e) => return Some(ControllerEvent::Error { error: e.to_string() }),
+            };
+            let Some(content) = content else {
+                return Some(ControllerEvent::Error {
+                    error: format!("Slot {} is empty", slot),
+                });
+            };
+            tokio::task::spawn_blocking(move || {
+                crate::slots::manager::paste_text(&app, &content)
+            })
+            .await
+        }
+        _ => {
+            tokio::task::spawn_blocking(move || {
+                crate::slots::manager::handle_save_to_slot(&app, slot)
+            })
+            .await
+        }
+    };
+    result.err().map(|e| ControllerEvent::Error { error: e.to_string() })
+}
+
+async fn all_slots(db: &Arc<Database>) -> ControllerEvent {
+    match db.call(|db| db.get_all_slots()).await {
+        Ok(slots) => ControllerEvent::Slots { slots },
+        Err(e) => ControllerEvent::Error { error: e.to_string() },
+    }
+}
+
+/// `slot_changed` for slot `n`, or nothing if the slot isn't in use.
+async fn slot_changed_event(db: &Arc<Database>, n: u32) -> Option<ControllerEvent> {
+    let slot = db
+        .call(move |db| (n <= db.get_slot_count()).then(|| db.get_slot(n)))
+        .await?;
+    Some(match slot {
+        Ok(slot) => ControllerEvent::SlotChanged { slot },
+        Err(e) => ControllerEvent::Error { error: e.to_string() },
+    })
+}
+
+async fn send_event(socket: &mut WebSocket, event: &ControllerEvent) -> Result<(), axum::Error> {
+    let json = serde_json::to_string(event).expect("controller events serialize");
+    socket.send(Message::Text(json.into())).await
+}
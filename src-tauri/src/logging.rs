@@ -1,10 +1,172 @@
+use std::cell::Cell;
+use std::fmt::{self, Write as _};
 use std::fs::{File, OpenOptions};
 use std::io::Write;
 use std::path::PathBuf;
-use std::sync::Mutex;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Metadata, Subscriber};
+use tracing_subscriber::filter::filter_fn;
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+use tracing_subscriber::util::SubscriberInitExt;
+
+use crate::storage::database::Database;
 
 static LOG_FILE: Mutex<Option<File>> = Mutex::new(None);
 static LOG_PATH: Mutex<Option<PathBuf>> = Mutex::new(None);
+static LOG_LEVEL: AtomicU8 = AtomicU8::new(LogLevel::Info as u8);
+static REDACTION: AtomicU8 = AtomicU8::new(LogRedaction::Lengths as u8);
+/// Key for `LogRedaction::Hashes`, from `set_hash_key`.
+static HASH_KEY: OnceLock<[u8; 32]> = OnceLock::new();
+/// Called with every line written, e.g. to stream it to the settings window.
+type Listener = Box<dyn Fn(&str) + Send>;
+static LISTENER: Mutex<Option<Listener>> = Mutex::new(None);
+
+thread_local! {
+    /// Set while the listener runs, so anything it logs isn't fed back to it
+    static IN_LISTENER: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Crates whose lines are the app's own.
+const APP_TARGETS: [&str; 2] = [env!("CARGO_CRATE_NAME"), "clipslot_core"];
+
+/// Most lines `tail` returns.
+pub const MAX_TAIL_LINES: usize = 5000;
+
+/// Field for clipboard content (item text, slot content, previews) in a
+/// log line, e.g. `tracing::info!(content = %text, "Saved to slot")`.
+/// Written as `LogRedaction` says; content never goes in the message.
+pub const CONTENT_FIELD: &str = "content";
+
+/// Characters of content written with redaction off.
+const CONTENT_PREVIEW_CHARS: usize = 50;
+
+/// Setting holding the install's `HASH_KEY`, hex-encoded.
+const HASH_KEY_SETTING: &str = "log_hash_key";
+
+/// Lines below the configured level are dropped.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Error = 0,
+    Warning = 1,
+    Info = 2,
+    Debug = 3,
+}
+
+/// How clipboard content in `CONTENT_FIELD` is written.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogRedaction {
+    /// The start of the content
+    Off = 0,
+    /// How many characters it has
+    Lengths = 1,
+    /// A short hash, enough to tell whether two lines are about the same
+    /// content. Keyed with a secret random to each install, so a shared log
+    /// can't be checked against guesses at what was copied.
+    Hashes = 2,
+}
+
+impl LogRedaction {
+    fn from_u8(value: u8) -> LogRedaction {
+        match value {
+            0 => LogRedaction::Off,
+            2 => LogRedaction::Hashes,
+            _ => LogRedaction::Lengths,
+        }
+    }
+
+    pub fn apply(self, content: &str) -> String {
+        match self {
+            LogRedaction::Off => match content.char_indices().nth(CONTENT_PREVIEW_CHARS) {
+                Some((end, _)) => format!("{:?}...", &content[..end]),
+                None => format!("{:?}", content),
+            },
+            LogRedaction::Lengths => format!("<{} chars>", content.chars().count()),
+            LogRedaction::Hashes => {
+                // Before `set_hash_key`, hashes only match within this run
+                let key = HASH_KEY.get_or_init(|| {
+                    let mut key = [0u8; 32];
+                    rand::thread_rng().fill_bytes(&mut key);
+                    key
+                });
+                format!("hmac:{}", content_hash(key, content))
+            }
+        }
+    }
+}
+
+/// The first 12 hex digits of HMAC-SHA256 of `content` under `key`.
+fn content_hash(key: &[u8], content: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(content.as_bytes());
+    let hash: String = mac
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    hash[..12].to_string()
+}
+
+impl From<&Level> for LogLevel {
+    fn from(level: &Level) -> Self {
+        match *level {
+            Level::ERROR => LogLevel::Error,
+            Level::WARN => LogLevel::Warning,
+            Level::INFO => LogLevel::Info,
+            _ => LogLevel::Debug,
+        }
+    }
+}
+
+pub fn set_level(level: LogLevel) {
+    LOG_LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+pub fn set_redaction(redaction: LogRedaction) {
+    REDACTION.store(redaction as u8, Ordering::Relaxed);
+}
+
+/// Use this install's key for `LogRedaction::Hashes`, creating it on first
+/// run. Call before anything is logged with hashes on.
+pub fn set_hash_key(db: &Database) {
+    let stored = db
+        .get_setting(HASH_KEY_SETTING)
+        .and_then(|hex| decode_key(&hex));
+    let key = stored.unwrap_or_else(|| {
+        let mut key = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut key);
+        let hex: String = key.iter().map(|b| format!("{:02x}", b)).collect();
+        if let Err(e) = db.set_setting(HASH_KEY_SETTING, &hex) {
+            tracing::error!("Failed to save log hash key: {}", e);
+        }
+        key
+    });
+    let _ = HASH_KEY.set(key);
+}
+
+fn decode_key(hex: &str) -> Option<[u8; 32]> {
+    if hex.len() != 64 || !hex.is_ascii() {
+        return None;
+    }
+    let mut key = [0u8; 32];
+    for (i, byte) in key.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).ok()?;
+    }
+    Some(key)
+}
+
+pub fn set_listener(listener: impl Fn(&str) + Send + 'static) {
+    *LISTENER.lock().unwrap() = Some(Box::new(listener));
+}
 
 pub fn init(data_dir: &PathBuf) {
     let _ = std::fs::create_dir_all(data_dir);
@@ -25,19 +187,87 @@ pub fn init(data_dir: &PathBuf) {
     {
         *LOG_FILE.lock().unwrap() = Some(file);
         *LOG_PATH.lock().unwrap() = Some(log_path.clone());
-        log(&format!(
-            "=== ClipSlot v{} started ===",
-            env!("CARGO_PKG_VERSION")
-        ));
-        log(&format!("OS: {}", std::env::consts::OS));
-        log(&format!("Arch: {}", std::env::consts::ARCH));
-        log(&format!("Log file: {}", log_path.display()));
+    }
+
+    // Also picks up dependencies logging through the `log` crate
+    let _ = tracing_subscriber::registry()
+        .with(LineLayer(write_line).with_filter(filter_fn(enabled)))
+        .try_init();
+
+    tracing::info!("=== ClipSlot v{} started ===", env!("CARGO_PKG_VERSION"));
+    tracing::info!("OS: {}", std::env::consts::OS);
+    tracing::info!("Arch: {}", std::env::consts::ARCH);
+    tracing::info!("Log file: {}", log_path.display());
+}
+
+/// The app's own lines down to the configured level; other crates' only
+/// when something went wrong.
+fn enabled(meta: &Metadata<'_>) -> bool {
+    let level = LogLevel::from(meta.level());
+    let ours = APP_TARGETS
+        .iter()
+        .any(|crate_name| meta.target().starts_with(crate_name));
+    level as u8 <= LOG_LEVEL.load(Ordering::Relaxed) && (ours || level <= LogLevel::Warning)
+}
+
+/// Formats each event as a line and hands it to `.0`.
+struct LineLayer<F>(F);
+
+impl<S: Subscriber, F: Fn(&str) + 'static> Layer<S> for LineLayer<F> {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let meta = event.metadata();
+        let mut fields = Fields::default();
+        event.record(&mut fields);
+        let line = format!(
+            "[{}] {} {}: {}{}",
+            chrono::Local::now().format("%H:%M:%S%.3f"),
+            meta.level(),
+            meta.target(),
+            fields.message,
+            fields.rest
+        );
+        (self.0)(&line);
+    }
+}
+
+#[derive(Default)]
+struct Fields {
+    message: String,
+    /// The other fields, as ` name=value`
+    rest: String,
+}
+
+impl Visit for Fields {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == CONTENT_FIELD {
+            self.record_content(value);
+        } else {
+            self.record_debug(field, &value);
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        match field.name() {
+            "message" => self.message = format!("{:?}", value),
+            // Where a `log` crate record came from; the target says enough
+            name if name.starts_with("log.") => {}
+            // `content = %text` arrives here, and its Debug is the text's Display
+            CONTENT_FIELD => self.record_content(&format!("{:?}", value)),
+            name => {
+                let _ = write!(self.rest, " {}={:?}", name, value);
+            }
+        }
+    }
+}
+
+impl Fields {
+    fn record_content(&mut self, content: &str) {
+        let redaction = LogRedaction::from_u8(REDACTION.load(Ordering::Relaxed));
+        let _ = write!(self.rest, " {}={}", CONTENT_FIELD, redaction.apply(content));
     }
 }
 
-pub fn log(msg: &str) {
-    let timestamp = chrono::Local::now().format("%H:%M:%S%.3f");
-    let line = format!("[{}] {}", timestamp, msg);
+fn write_line(line: &str) {
     eprintln!("{}", line);
     if let Ok(mut guard) = LOG_FILE.lock() {
         if let Some(ref mut file) = *guard {
@@ -45,6 +275,15 @@ pub fn log(msg: &str) {
             let _ = file.flush();
         }
     }
+    if IN_LISTENER.with(|busy| busy.replace(true)) {
+        return;
+    }
+    if let Ok(guard) = LISTENER.lock() {
+        if let Some(listener) = guard.as_ref() {
+            listener(line);
+        }
+    }
+    IN_LISTENER.with(|busy| busy.set(false));
 }
 
 pub fn log_path() -> Option<String> {
@@ -54,10 +293,97 @@ pub fn log_path() -> Option<String> {
         .and_then(|g| g.as_ref().map(|p| p.display().to_string()))
 }
 
-/// Convenience macro for logging with format args.
-#[macro_export]
-macro_rules! clog {
-    ($($arg:tt)*) => {
-        $crate::logging::log(&format!($($arg)*))
+/// The last `lines` lines of the log file (at most `MAX_TAIL_LINES`).
+pub fn tail(lines: usize) -> Vec<String> {
+    let Some(path) = LOG_PATH.lock().ok().and_then(|g| g.clone()) else {
+        return Vec::new();
     };
+    let text = std::fs::read_to_string(path).unwrap_or_default();
+    last_lines(&text, lines.min(MAX_TAIL_LINES))
+}
+
+fn last_lines(text: &str, n: usize) -> Vec<String> {
+    let lines: Vec<&str> = text.lines().collect();
+    lines[lines.len().saturating_sub(n)..]
+        .iter()
+        .map(|l| l.to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    /// Lines logged while `f` runs, without the timestamp.
+    fn capture(f: impl FnOnce()) -> Vec<String> {
+        let lines = Arc::new(Mutex::new(Vec::new()));
+        let sink = lines.clone();
+        let layer = LineLayer(move |line: &str| {
+            let (_, rest) = line.split_once("] ").unwrap();
+            sink.lock().unwrap().push(rest.to_string());
+        });
+        let subscriber = tracing_subscriber::registry().with(layer.with_filter(filter_fn(enabled)));
+        tracing::subscriber::with_default(subscriber, f);
+        let lines = lines.lock().unwrap().clone();
+        lines
+    }
+
+    #[test]
+    fn test_levels_and_targets() {
+        assert!(LogLevel::Debug > LogLevel::Info);
+        assert_eq!(LogLevel::from(&Level::WARN), LogLevel::Warning);
+
+        let lines = capture(|| {
+            tracing::error!("Sync failed: {}", "timeout");
+            tracing::debug!("Not at the default level");
+            tracing::info!(target: "tauri::manager", "Other crates' info is dropped");
+            tracing::warn!(target: "tauri::manager", "but not their warnings");
+        });
+        assert_eq!(
+            lines,
+            vec![
+                format!("ERROR {}: Sync failed: timeout", module_path!()),
+                "WARN tauri::manager: but not their warnings".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_content_redacted() {
+        // Only lengths, by default
+        let lines = capture(|| tracing::info!(slot = 2, content = %"secret", "Saved to slot"));
+        let expected = format!(
+            "INFO {}: Saved to slot slot=2 content=<6 chars>",
+            module_path!()
+        );
+        assert_eq!(lines, vec![expected]);
+
+        let long = "x".repeat(60);
+        assert_eq!(LogRedaction::Off.apply("hi"), "\"hi\"");
+        assert_eq!(
+            LogRedaction::Off.apply(&long),
+            format!("{:?}...", &long[..50])
+        );
+        assert_eq!(LogRedaction::Lengths.apply("héllo"), "<5 chars>");
+        let hash = LogRedaction::Hashes.apply("secret");
+        assert!(hash.starts_with("hmac:") && hash.len() == 17, "{}", hash);
+        assert_eq!(hash, LogRedaction::Hashes.apply("secret"));
+        assert_ne!(hash, LogRedaction::Hashes.apply("other"));
+
+        // Keyed: the same content hashes differently on another install
+        assert_eq!(content_hash(b"key", "secret"), content_hash(b"key", "secret"));
+        assert_ne!(content_hash(b"key", "secret"), content_hash(b"other key", "secret"));
+
+        let hex = "00ff".repeat(16);
+        assert_eq!(decode_key(&hex).unwrap()[..2], [0x00, 0xff]);
+        assert!(decode_key("00ff").is_none());
+    }
+
+    #[test]
+    fn test_last_lines() {
+        assert_eq!(last_lines("a\nb\nc\n", 2), vec!["b", "c"]);
+        assert_eq!(last_lines("a\nb", 5), vec!["a", "b"]);
+        assert!(last_lines("", 3).is_empty());
+    }
 }
@@ -0,0 +1,74 @@
+use std::sync::Arc;
+
+use tauri::{App, AppHandle, Emitter, Manager, WebviewUrl, WebviewWindowBuilder};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+use crate::clipboard::item::ClipboardItem;
+use crate::clipboard::monitor::ClipboardMonitor;
+use crate::storage::database::Database;
+use crate::sync::manager::SyncManager;
+
+/// Open the app's only window, which shows slots and history.
+pub fn setup(app: &mut App) -> Result<(), Box<dyn std::error::Error>> {
+    // Never started, since phones don't let apps watch the clipboard; the
+    // copy commands use it to skip their own writes, as on desktop
+    app.manage(Arc::new(ClipboardMonitor::new()));
+
+    WebviewWindowBuilder::new(app, "main", WebviewUrl::App("index.html?page=mobile".into()))
+        .build()?;
+    Ok(())
+}
+
+/// Add what is on the clipboard to history. Phones only allow reading it
+/// when the user asks, so this stands in for the clipboard monitor.
+#[tauri::command]
+pub async fn capture_clipboard(app: AppHandle) -> Result<Option<ClipboardItem>, String> {
+    let text = app.clipboard().read_text().map_err(|e| e.to_string())?;
+    add_to_history(&app, text).await
+}
+
+/// Add text another app shared with ClipSlot through the share sheet.
+#[tauri::command]
+pub async fn save_shared_text(
+    app: AppHandle,
+    text: String,
+) -> Result<Option<ClipboardItem>, String> {
+    add_to_history(&app, text).await
+}
+
+/// Store `text` as a history item and push it to other devices. `None` if
+/// it's blank or was just added.
+async fn add_to_history(app: &AppHandle, text: String) -> Result<Option<ClipboardItem>, String> {
+    if text.trim().is_empty() {
+        return Ok(None);
+    }
+    let db = app.state::<Arc<Database>>().inner().clone();
+    let item = ClipboardItem::new(text, &crate::get_or_create_device_id(&db));
+    let stored = item.clone();
+    let inserted = db
+        .call(move |db| {
+            let inserted = db.insert_item(&stored)?;
+            if inserted {
+                db.enforce_history_limit()?;
+            }
+            Ok::<_, rusqlite::Error>(inserted)
+        })
+        .await
+        .map_err(|e| e.to_string())?;
+    if !inserted {
+        return Ok(None);
+    }
+    crate::telemetry::record(crate::telemetry::Event::Capture);
+    let _ = app.emit("clipboard-changed", &item);
+
+    if let Some(sync) = app.try_state::<Arc<SyncManager>>() {
+        let sync = sync.inner().clone();
+        let id = item.id.clone();
+        tauri::async_runtime::spawn(async move {
+            if let Ok(Some((encrypted, hash))) = db.get_item_encrypted(&id) {
+                sync.notify_history_push(&id, &encrypted, &hash).await;
+            }
+        });
+    }
+    Ok(Some(item))
+}
@@ -0,0 +1,159 @@
+use serde::{Deserialize, Serialize};
+
+use crate::storage::database::MAX_SLOT_COUNT;
+
+pub const MAX_KEYWORD_LEN: usize = 32;
+
+/// A keyword that, once typed, is replaced by a slot's content.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SnippetTrigger {
+    /// e.g. `;addr`
+    pub keyword: String,
+    pub slot: u32,
+}
+
+/// Characters keywords are made of: those the listener recognises, typed
+/// without Shift.
+pub fn is_keyword_char(c: char) -> bool {
+    c.is_ascii_lowercase() || c.is_ascii_digit() || ";,./-='`".contains(c)
+}
+
+pub fn validate_triggers(triggers: &[SnippetTrigger]) -> Result<(), String> {
+    for (i, trigger) in triggers.iter().enumerate() {
+        let keyword = &trigger.keyword;
+        if keyword.len() < 2 || keyword.len() > MAX_KEYWORD_LEN {
+            return Err(format!(
+                "Snippet keywords must be 2 to {} characters: {:?}",
+                MAX_KEYWORD_LEN, keyword
+            ));
+        }
+        if !keyword.chars().all(is_keyword_char) {
+            return Err(format!(
+                "Snippet keywords can only use a-z, 0-9 and ;,./-='`: {:?}",
+                keyword
+            ));
+        }
+        if triggers[..i].iter().any(|t| t.keyword == *keyword) {
+            return Err(format!("Duplicate snippet keyword: {}", keyword));
+        }
+        if !(1..=MAX_SLOT_COUNT).contains(&trigger.slot) {
+            return Err(format!(
+                "Snippet slot must be between 1 and {}",
+                MAX_SLOT_COUNT
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// The last few characters typed, to spot keywords in.
+#[derive(Default)]
+pub struct KeywordMatcher {
+    typed: String,
+}
+
+impl KeywordMatcher {
+    /// Note a typed character; the trigger whose keyword it completes, if
+    /// any. What was typed is forgotten after a match.
+    pub fn push<'a>(
+        &mut self,
+        c: char,
+        triggers: &'a [SnippetTrigger],
+    ) -> Option<&'a SnippetTrigger> {
+        if self.typed.len() == MAX_KEYWORD_LEN {
+            self.typed.remove(0);
+        }
+        self.typed.push(c);
+        let found = triggers.iter().find(|t| self.typed.ends_with(&t.keyword))?;
+        self.reset();
+        Some(found)
+    }
+
+    /// Whether what was typed last starts some keyword, so the next keys
+    /// could complete it.
+    pub fn in_progress(&self, triggers: &[SnippetTrigger]) -> bool {
+        triggers.iter().any(|t| {
+            // Keywords are ASCII, so any length is a char boundary
+            (1..t.keyword.len()).any(|n| self.typed.ends_with(&t.keyword[..n]))
+        })
+    }
+
+    pub fn backspace(&mut self) {
+        self.typed.pop();
+    }
+
+    /// Forget what was typed, e.g. after a key that moves the cursor.
+    pub fn reset(&mut self) {
+        self.typed.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trigger(keyword: &str, slot: u32) -> SnippetTrigger {
+        SnippetTrigger {
+            keyword: keyword.to_string(),
+            slot,
+        }
+    }
+
+    fn type_text<'a>(
+        matcher: &mut KeywordMatcher,
+        text: &str,
+        triggers: &'a [SnippetTrigger],
+    ) -> Option<&'a SnippetTrigger> {
+        text.chars().fold(None, |_, c| matcher.push(c, triggers))
+    }
+
+    #[test]
+    fn test_matches_typed_keyword() {
+        let triggers = [trigger(";addr", 1), trigger(";sig", 2)];
+        let mut matcher = KeywordMatcher::default();
+
+        assert_eq!(type_text(&mut matcher, "hello ;ad", &triggers), None);
+        assert_eq!(type_text(&mut matcher, "dr", &triggers), Some(&triggers[0]));
+
+        // Corrected typos still match; text after a reset doesn't
+        assert_eq!(type_text(&mut matcher, ";sx", &triggers), None);
+        matcher.backspace();
+        assert_eq!(type_text(&mut matcher, "ig", &triggers), Some(&triggers[1]));
+        type_text(&mut matcher, ";s", &triggers);
+        matcher.reset();
+        assert_eq!(type_text(&mut matcher, "ig", &triggers), None);
+    }
+
+    #[test]
+    fn test_in_progress() {
+        let triggers = [trigger(";addr", 1)];
+        let mut matcher = KeywordMatcher::default();
+        assert!(!matcher.in_progress(&triggers));
+
+        type_text(&mut matcher, "hello", &triggers);
+        assert!(!matcher.in_progress(&triggers));
+        type_text(&mut matcher, ";a", &triggers);
+        assert!(matcher.in_progress(&triggers));
+        type_text(&mut matcher, "x", &triggers);
+        assert!(!matcher.in_progress(&triggers));
+        matcher.backspace();
+        assert!(matcher.in_progress(&triggers));
+        type_text(&mut matcher, "ddr", &triggers);
+        assert!(!matcher.in_progress(&triggers));
+    }
+
+    #[test]
+    fn test_validate_triggers() {
+        assert!(validate_triggers(&[trigger(";addr", 1), trigger("..sig", 10)]).is_ok());
+        for bad in [
+            vec![trigger(";", 1)],
+            vec![trigger(";Addr", 1)],
+            vec![trigger("; addr", 1)],
+            vec![trigger(";addr", 0)],
+            vec![trigger(";addr", 1), trigger(";addr", 2)],
+        ] {
+            assert!(validate_triggers(&bad).is_err(), "{:?}", bad);
+        }
+    }
+}
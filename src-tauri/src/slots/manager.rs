@@ -1,127 +1,281 @@
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
-use tauri::{AppHandle, Emitter, Manager, Wry};
+use tauri::plugin::TauriPlugin;
+use tauri::{AppHandle, Emitter, Manager, WebviewUrl, WebviewWindowBuilder, Wry};
 use tauri_plugin_clipboard_manager::ClipboardExt;
+use tauri_plugin_global_shortcut::{Code, GlobalShortcutExt, Modifiers, Shortcut, ShortcutState};
 use tauri_plugin_notification::NotificationExt;
 
+use crate::clipboard::backend::{InputBackend, Key};
+use crate::clipboard::capture_state::CaptureState;
 use crate::clipboard::item::ClipboardItem;
 use crate::clipboard::monitor::ClipboardMonitor;
+use crate::i18n;
+use crate::slots::expander::{self, KeywordMatcher, SnippetTrigger};
+use crate::slots::paste;
 use crate::storage::database::Database;
+use crate::storage::settings::Settings;
 use crate::sync::manager::SyncManager;
+use crate::telemetry;
+
+/// Slots reachable with the number-key shortcuts (1-5).
+pub const SHORTCUT_SLOTS: u32 = 5;
+
+/// How often the snippet listener looks at the keyboard while nothing typed
+/// could be a keyword. Slow enough to cost next to nothing, quick enough to
+/// catch a key press.
+const SNIPPET_IDLE_POLL: Duration = Duration::from_millis(50);
+/// How often it looks once a keyword has been started, so the rest of it
+/// isn't missed however fast it's typed.
+const SNIPPET_ACTIVE_POLL: Duration = Duration::from_millis(10);
+
+/// Paste shortcut for a slot as shown in menus, e.g. "⌘⌥2", if it has one.
+pub fn paste_shortcut_label(slot_number: u32) -> Option<String> {
+    if !(1..=SHORTCUT_SLOTS).contains(&slot_number) {
+        return None;
+    }
+    #[cfg(target_os = "macos")]
+    let label = format!("⌘⌥{}", slot_number);
+    #[cfg(not(target_os = "macos"))]
+    let label = format!("Alt+Shift+{}", slot_number);
+    Some(label)
+}
+
+/// What a slot shortcut does.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ShortcutAction {
+    Save(u32),
+    Paste(u32),
+}
 
-/// Start keyboard polling for slot shortcuts.
+/// Slot shortcuts and what they do.
 /// macOS:   Save = Cmd+Ctrl+1-5,    Paste = Cmd+Option+1-5
 /// Windows: Save = Ctrl+Shift+1-5,  Paste = Alt+Shift+1-5
-pub fn start_shortcut_listener(app_handle: AppHandle<Wry>) {
-    std::thread::spawn(move || {
-        use device_query::{DeviceQuery, DeviceState, Keycode};
-
-        let device_state = DeviceState::new();
-        let mut last_save_slot: Option<u32> = None;
-        let mut last_paste_slot: Option<u32> = None;
-
-        clog!("Shortcut listener started (polling)");
-        #[cfg(target_os = "macos")]
-        clog!("Shortcuts: Save=Cmd+Ctrl+1-5, Paste=Cmd+Option+1-5");
-        #[cfg(not(target_os = "macos"))]
-        clog!("Shortcuts: Save=Ctrl+Shift+1-5, Paste=Alt+Shift+1-5");
-
-        let mut log_keys_once = true;
-
-        loop {
-            std::thread::sleep(Duration::from_millis(50));
-
-            let keys = device_state.get_keys();
-
-            // Log detected keys once when any modifier is held (for debugging)
-            if !keys.is_empty() && log_keys_once {
-                let has_modifier = keys.iter().any(|k| matches!(k,
-                    Keycode::LControl | Keycode::RControl |
-                    Keycode::LShift | Keycode::RShift |
-                    Keycode::LAlt | Keycode::RAlt |
-                    Keycode::Command | Keycode::LOption
-                ));
-                if has_modifier {
-                    clog!("Keys detected: {:?}", keys);
-                    log_keys_once = false;
-                }
-            }
-            if keys.is_empty() {
-                log_keys_once = true;
-            }
+fn slot_shortcuts() -> Vec<(Shortcut, ShortcutAction)> {
+    #[cfg(target_os = "macos")]
+    let (save, paste) = (
+        Modifiers::SUPER | Modifiers::CONTROL,
+        Modifiers::SUPER | Modifiers::ALT,
+    );
+    #[cfg(not(target_os = "macos"))]
+    let (save, paste) = (
+        Modifiers::CONTROL | Modifiers::SHIFT,
+        Modifiers::ALT | Modifiers::SHIFT,
+    );
+    let keys = [Code::Digit1, Code::Digit2, Code::Digit3, Code::Digit4, Code::Digit5];
+    (1..=SHORTCUT_SLOTS)
+        .zip(keys)
+        .flat_map(|(n, key)| {
+            [
+                (Shortcut::new(Some(save), key), ShortcutAction::Save(n)),
+                (Shortcut::new(Some(paste), key), ShortcutAction::Paste(n)),
+            ]
+        })
+        .collect()
+}
 
-            let ctrl_held =
-                keys.contains(&Keycode::LControl) || keys.contains(&Keycode::RControl);
-            #[allow(unused_variables)]
-            let shift_held =
-                keys.contains(&Keycode::LShift) || keys.contains(&Keycode::RShift);
-            #[allow(unused_variables)]
-            let alt_held = keys.contains(&Keycode::LAlt) || keys.contains(&Keycode::RAlt);
-
-            // Determine which number key (1-5) is pressed
-            let slot_number = if keys.contains(&Keycode::Key1) {
-                Some(1u32)
-            } else if keys.contains(&Keycode::Key2) {
-                Some(2)
-            } else if keys.contains(&Keycode::Key3) {
-                Some(3)
-            } else if keys.contains(&Keycode::Key4) {
-                Some(4)
-            } else if keys.contains(&Keycode::Key5) {
-                Some(5)
-            } else {
-                None
+/// The global shortcut plugin, handling the slot shortcuts. The OS reports
+/// key presses to it, so nothing polls the keyboard.
+pub fn shortcut_plugin() -> TauriPlugin<Wry> {
+    tauri_plugin_global_shortcut::Builder::new()
+        .with_handler(|app, shortcut, event| {
+            if event.state() != ShortcutState::Pressed {
+                return;
+            }
+            let Some((_, action)) = slot_shortcuts().into_iter().find(|(s, _)| s == shortcut)
+            else {
+                return;
             };
+            let app = app.clone();
+            // Pasting waits for the keystroke to land; keep that off the main thread
+            std::thread::spawn(move || handle_shortcut(&app, action));
+        })
+        .build()
+}
 
-            // Platform-specific modifier detection
-            #[cfg(target_os = "macos")]
-            let (save_combo, paste_combo) = {
-                let cmd_held = keys.contains(&Keycode::Command);
-                let option_held = keys.contains(&Keycode::LOption) || keys.contains(&Keycode::RAlt);
-                // Save: Cmd+Ctrl+N (without Option)
-                let save = cmd_held && ctrl_held && !option_held;
-                // Paste: Cmd+Option+N (without Ctrl)
-                let paste = cmd_held && option_held && !ctrl_held;
-                (save, paste)
-            };
+/// Register the slot shortcuts with the OS.
+pub fn register_shortcuts(app: &AppHandle<Wry>) {
+    for (shortcut, _) in slot_shortcuts() {
+        if let Err(e) = app.global_shortcut().register(shortcut) {
+            tracing::info!("Failed to register shortcut {:?}: {}", shortcut, e);
+        }
+    }
+    #[cfg(target_os = "macos")]
+    tracing::info!("Shortcuts: Save=Cmd+Ctrl+1-5, Paste=Cmd+Option+1-5");
+    #[cfg(not(target_os = "macos"))]
+    tracing::info!("Shortcuts: Save=Ctrl+Shift+1-5, Paste=Alt+Shift+1-5");
+}
 
-            #[cfg(not(target_os = "macos"))]
-            let (save_combo, paste_combo) = {
-                // Save: Ctrl+Shift+N (without Alt)
-                let save = ctrl_held && shift_held && !alt_held;
-                // Paste: Alt+Shift+N (without Ctrl)
-                let paste = alt_held && shift_held && !ctrl_held;
-                (save, paste)
-            };
+/// The keyboard polling behind snippet expansion. Unlike the OS hotkeys it
+/// sees every key typed, so it only runs while expansion is on and has
+/// triggers (see `configure_snippets`).
+#[derive(Default)]
+pub struct SnippetListener {
+    /// Empty while expansion is off, which stops the thread
+    triggers: Mutex<Vec<SnippetTrigger>>,
+    running: AtomicBool,
+}
+
+/// Start or stop the snippet listener to match `settings`.
+pub fn configure_snippets(app: &AppHandle<Wry>, settings: &Settings) {
+    let listener = app.state::<Arc<SnippetListener>>().inner().clone();
+    let triggers = if settings.snippet_expansion_enabled {
+        settings.snippet_triggers.clone()
+    } else {
+        Vec::new()
+    };
+    let start = !triggers.is_empty();
+    *listener.triggers.lock().unwrap() = triggers;
+    if start && !listener.running.swap(true, Ordering::AcqRel) {
+        let app = app.clone();
+        std::thread::spawn(move || run_snippet_listener(app, listener));
+    }
+}
 
-            // Save to slot
-            if save_combo {
-                if slot_number != last_save_slot {
-                    if let Some(n) = slot_number {
-                        clog!("Shortcut: SAVE to slot {}", n);
-                        handle_save_to_slot(&app_handle, n);
-                    }
-                    last_save_slot = slot_number;
+fn run_snippet_listener(app: AppHandle<Wry>, listener: Arc<SnippetListener>) {
+    use device_query::{DeviceQuery, DeviceState, Keycode};
+
+    tracing::info!("Snippet listener started");
+    let device_state = DeviceState::new();
+    let mut matcher = KeywordMatcher::default();
+    let mut held: Vec<Keycode> = device_state.get_keys();
+    let mut poll = SNIPPET_IDLE_POLL;
+
+    loop {
+        std::thread::sleep(poll);
+
+        let keys = device_state.get_keys();
+        let pressed: Vec<&Keycode> = keys.iter().filter(|k| !held.contains(*k)).collect();
+        let modifier_held = keys.iter().any(|k| {
+            matches!(
+                k,
+                Keycode::LControl
+                    | Keycode::RControl
+                    | Keycode::LShift
+                    | Keycode::RShift
+                    | Keycode::LAlt
+                    | Keycode::RAlt
+                    | Keycode::Command
+                    | Keycode::LOption
+            )
+        });
+
+        let mut found = None;
+        {
+            let triggers = listener.triggers.lock().unwrap();
+            // Stopped; flagged under the lock so a restart can't be missed
+            if triggers.is_empty() {
+                listener.running.store(false, Ordering::Release);
+                tracing::info!("Snippet listener stopped");
+                return;
+            }
+            for key in pressed {
+                if modifier_held {
+                    // A shortcut or a shifted character, not part of a keyword
+                    matcher.reset();
+                } else if *key == Keycode::Backspace {
+                    matcher.backspace();
+                } else if let Some(c) = keyword_char(key) {
+                    found = matcher.push(c, &triggers).cloned().or(found);
+                } else {
+                    matcher.reset();
                 }
+            }
+            poll = if matcher.in_progress(&triggers) {
+                SNIPPET_ACTIVE_POLL
             } else {
-                last_save_slot = None;
+                SNIPPET_IDLE_POLL
+            };
+        }
+        held = keys;
+
+        if let Some(trigger) = found {
+            expand_snippet(&app, &trigger);
+            // Ignore the keys sent while expanding
+            held = device_state.get_keys();
+        }
+    }
+}
+
+/// The character a key types when pressed on its own, if keywords use it.
+/// Keys are told apart by their `Debug` names ("A", "Key1", "Semicolon").
+fn keyword_char(key: &device_query::Keycode) -> Option<char> {
+    let name = format!("{:?}", key);
+    let c = match name.as_str() {
+        "Semicolon" => ';',
+        "Comma" => ',',
+        "Dot" => '.',
+        "Slash" => '/',
+        "Minus" => '-',
+        "Equal" => '=',
+        "Apostrophe" => '\'',
+        "Grave" => '`',
+        _ => {
+            let mut chars = name.strip_prefix("Key").unwrap_or(&name).chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => c.to_ascii_lowercase(),
+                _ => return None,
             }
+        }
+    };
+    expander::is_keyword_char(c).then_some(c)
+}
 
-            // Paste from slot
-            if paste_combo {
-                if slot_number != last_paste_slot {
-                    if let Some(n) = slot_number {
-                        clog!("Shortcut: PASTE from slot {}", n);
-                        handle_paste_from_slot(&app_handle, n);
-                    }
-                    last_paste_slot = slot_number;
-                }
-            } else {
-                last_paste_slot = None;
+/// Replace the just-typed keyword of `trigger` with its slot's content.
+fn expand_snippet(app: &AppHandle<Wry>, trigger: &SnippetTrigger) {
+    let db = app.state::<Arc<Database>>();
+    let text = match db.get_slot(trigger.slot) {
+        Ok(slot) => match slot.content {
+            Some(text) => text,
+            None => {
+                tracing::info!(
+                    "Snippet {} is bound to empty slot {}",
+                    trigger.keyword,
+                    trigger.slot
+                );
+                return;
             }
+        },
+        Err(e) => {
+            tracing::error!("Failed to read slot {}: {}", trigger.slot, e);
+            return;
         }
-    });
+    };
+    tracing::info!("Expanding snippet {} from slot {}", trigger.keyword, trigger.slot);
+    let state = capture_state(app);
+    let erase = trigger.keyword.len();
+    if let Err(e) = paste::replace_typed_with(app, &SystemInput, &state, erase, &text) {
+        tracing::error!("Failed to expand snippet {}: {}", trigger.keyword, e);
+    } else {
+        telemetry::record(telemetry::Event::SlotPaste);
+        if let Err(e) = db.record_slot_use(trigger.slot) {
+            tracing::error!(
+                "Failed to record use of slot {}: {}",
+                trigger.slot,
+                e
+            );
+        }
+    }
+}
+
+fn handle_shortcut(app: &AppHandle<Wry>, action: ShortcutAction) {
+    let (ShortcutAction::Save(n) | ShortcutAction::Paste(n)) = action;
+    // Slots above the account's slot count are not in use
+    if n > app.state::<Arc<Database>>().get_slot_count() {
+        return;
+    }
+    match action {
+        ShortcutAction::Save(n) => {
+            tracing::info!("Shortcut: SAVE to slot {}", n);
+            handle_save_to_slot(app, n);
+        }
+        ShortcutAction::Paste(n) => {
+            tracing::info!("Shortcut: PASTE from slot {}", n);
+            handle_paste_from_slot(app, n);
+        }
+    }
 }
 
 pub fn handle_save_to_slot(app: &AppHandle<Wry>, slot_number: u32) {
@@ -129,30 +283,23 @@ pub fn handle_save_to_slot(app: &AppHandle<Wry>, slot_number: u32) {
     let text = match app.clipboard().read_text() {
         Ok(t) if !t.is_empty() => t,
         Ok(_) => {
-            println!("[ClipSlot] Clipboard is empty, nothing to save");
+            tracing::info!("Clipboard is empty, nothing to save");
             let _ = app
                 .notification()
                 .builder()
                 .title("ClipSlot")
-                .body("Clipboard is empty")
+                .body(i18n::text("clipboard_empty"))
                 .show();
             return;
         }
         Err(e) => {
-            eprintln!("[ClipSlot] Failed to read clipboard: {}", e);
+            tracing::error!("Failed to read clipboard: {}", e);
             return;
         }
     };
 
     let db = app.state::<Arc<Database>>();
-    let device_id = {
-        let hostname = hostname::get()
-            .map(|h| h.to_string_lossy().to_string())
-            .unwrap_or_else(|_| "unknown".to_string());
-        uuid::Uuid::new_v5(&uuid::Uuid::NAMESPACE_DNS, hostname.as_bytes()).to_string()
-    };
-
-    let item = ClipboardItem::new(text, &device_id);
+    let item = ClipboardItem::new(text, &crate::get_or_create_device_id(&db));
 
     // Tell the monitor to skip the next change
     if let Some(monitor) = app.try_state::<Arc<ClipboardMonitor>>() {
@@ -161,13 +308,15 @@ pub fn handle_save_to_slot(app: &AppHandle<Wry>, slot_number: u32) {
 
     match db.save_to_slot(slot_number, &item) {
         Ok(slot_info) => {
+            telemetry::record(telemetry::Event::SlotSave);
             let preview = slot_info
                 .content_preview
                 .as_deref()
                 .unwrap_or("(empty)");
-            let body = format!("Saved to {}: {}", slot_info.name, truncate(preview, 50));
+            let body =
+                i18n::text_with("saved_to_slot", &[&slot_info.name, &truncate(preview, 50)]);
 
-            println!("[ClipSlot] {}", body);
+            tracing::info!(slot = slot_number, content = %item.content, "Saved to slot");
 
             match app
                 .notification()
@@ -176,8 +325,8 @@ pub fn handle_save_to_slot(app: &AppHandle<Wry>, slot_number: u32) {
                 .body(&body)
                 .show()
             {
-                Ok(_) => println!("[ClipSlot] Notification sent"),
-                Err(e) => eprintln!("[ClipSlot] Notification failed: {}", e),
+                Ok(_) => tracing::info!("Notification sent"),
+                Err(e) => tracing::error!("Notification failed: {}", e),
             }
 
             // Signal tray menu to refresh
@@ -196,41 +345,57 @@ pub fn handle_save_to_slot(app: &AppHandle<Wry>, slot_number: u32) {
             }
         }
         Err(e) => {
-            eprintln!("[ClipSlot] Failed to save to slot {}: {}", slot_number, e);
+            tracing::error!("Failed to save to slot {}: {}", slot_number, e);
             let _ = app
                 .notification()
                 .builder()
                 .title("ClipSlot")
-                .body(&format!("Failed to save to Slot {}", slot_number))
+                .body(i18n::text_with("save_to_slot_failed", &[&slot_number]))
                 .show();
         }
     }
 }
 
 pub fn handle_paste_from_slot(app: &AppHandle<Wry>, slot_number: u32) {
-    clog!("handle_paste_from_slot: slot {}", slot_number);
+    paste_from_slot(app, slot_number, false);
+}
+
+/// Paste a slot the user confirmed in the confirmation window.
+pub fn handle_confirmed_paste(app: &AppHandle<Wry>, slot_number: u32) {
+    paste_from_slot(app, slot_number, true);
+}
+
+/// Paste a slot into the focused app. Content over the size in
+/// `paste_confirm_kb` opens the confirmation window instead, unless it was
+/// `confirmed` there.
+fn paste_from_slot(app: &AppHandle<Wry>, slot_number: u32, confirmed: bool) {
+    tracing::info!("handle_paste_from_slot: slot {}", slot_number);
     let db = app.state::<Arc<Database>>();
 
     // Read slot content from DB
     let slot_info = match db.get_slot(slot_number) {
         Ok(info) => {
-            clog!("handle_paste_from_slot: got slot info, is_empty={}, has_content={}, name={}",
-                info.is_empty, info.content.is_some(), info.name);
+            tracing::info!(
+                "handle_paste_from_slot: got slot info, is_empty={}, has_content={}, name={}",
+                info.is_empty,
+                info.content.is_some(),
+                info.name
+            );
             info
         }
         Err(e) => {
-            clog!("ERROR: handle_paste_from_slot: get_slot failed: {}", e);
+            tracing::error!("handle_paste_from_slot: get_slot failed: {}", e);
             return;
         }
     };
 
     if slot_info.is_empty {
-        clog!("handle_paste_from_slot: slot {} is empty", slot_number);
+        tracing::info!("handle_paste_from_slot: slot {} is empty", slot_number);
         let _ = app
             .notification()
             .builder()
             .title("ClipSlot")
-            .body(&format!("{} is empty", slot_info.name))
+            .body(i18n::text_with("slot_is_empty", &[&slot_info.name]))
             .show();
         return;
     }
@@ -238,66 +403,113 @@ pub fn handle_paste_from_slot(app: &AppHandle<Wry>, slot_number: u32) {
     let slot_content = match slot_info.content {
         Some(c) => c,
         None => {
-            clog!("ERROR: Slot {} content is None despite not being empty", slot_number);
+            tracing::error!("Slot {} content is None despite not being empty", slot_number);
             return;
         }
     };
-    clog!(
-        "Pasting from {} ({} chars)",
-        slot_info.name,
-        slot_content.len()
-    );
-
-    // 1. Pause clipboard monitoring
-    if let Some(monitor) = app.try_state::<Arc<ClipboardMonitor>>() {
-        monitor.pause();
+    if !confirmed && Settings::load(&db).paste_needs_confirmation(&slot_content) {
+        tracing::info!("Slot {} is large, asking before pasting", slot_number);
+        show_paste_confirmation(app, slot_number, &slot_info.name, slot_content.len());
+        return;
     }
+    tracing::info!(content = %slot_content, "Pasting from {}", slot_info.name);
+    paste_text(app, &slot_content);
+    tracing::info!("Paste from {} complete", slot_info.name);
+    telemetry::record(telemetry::Event::SlotPaste);
+    if let Err(e) = db.record_slot_use(slot_number) {
+        tracing::error!("Failed to record use of slot {}: {}", slot_number, e);
+    }
+}
 
-    // 2. Save current clipboard content
-    let original_clipboard = app.clipboard().read_text().ok();
-
-    // 3. Write slot content to system clipboard
-    if let Err(e) = app.clipboard().write_text(&slot_content) {
-        clog!("ERROR: Failed to write slot content to clipboard: {}", e);
-        if let Some(monitor) = app.try_state::<Arc<ClipboardMonitor>>() {
-            monitor.resume();
-        }
-        return;
+/// Ask whether to paste `size` bytes from a slot, in a small window over the
+/// app the paste would go to. Its Paste button calls `confirm_slot_paste`.
+fn show_paste_confirmation(app: &AppHandle<Wry>, slot_number: u32, name: &str, size: usize) {
+    if let Some(window) = app.get_webview_window("paste-confirm") {
+        let _ = window.close();
+    }
+    let url = format!(
+        "index.html?page=confirm-paste&slot={}&size={}&name={}",
+        slot_number,
+        size,
+        url::form_urlencoded::byte_serialize(name.as_bytes()).collect::<String>()
+    );
+    let built = WebviewWindowBuilder::new(app, "paste-confirm", WebviewUrl::App(url.into()))
+        .title("ClipSlot")
+        .inner_size(340.0, 140.0)
+        .decorations(false)
+        .resizable(false)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .center()
+        .content_protected(crate::hide_from_screen_capture(app))
+        .build();
+    if let Err(e) = built {
+        tracing::error!("Failed to show paste confirmation: {}", e);
     }
+}
 
-    // 4. Small delay for clipboard to propagate
-    std::thread::sleep(Duration::from_millis(50));
+/// Paste `text` into the focused app, leaving the clipboard as it was.
+/// Blocks for a few hundred milliseconds.
+pub fn paste_text(app: &AppHandle<Wry>, text: &str) {
+    paste::paste_with(app, &SystemInput, &capture_state(app), text);
+}
 
-    // 5. Simulate Cmd+V paste keystroke (uses CGEvent with explicit flags,
-    //    so physical Cmd+Option being held won't interfere)
-    clog!("Simulating Ctrl+V paste...");
-    if let Err(e) = simulate_paste() {
-        clog!("ERROR: Failed to simulate paste: {}", e);
-    } else {
-        clog!("Paste simulation sent");
+/// Paste each of `texts` into the focused app, pressing `separator` between
+/// them (see `paste::paste_sequence_with`). Blocks until done.
+pub fn paste_texts(
+    app: &AppHandle<Wry>,
+    texts: &[&str],
+    separator: Option<Key>,
+    delay: Duration,
+) -> Result<(), String> {
+    let state = capture_state(app);
+    paste::paste_sequence_with(app, &SystemInput, &state, texts, separator, delay)
+}
+
+fn capture_state(app: &AppHandle<Wry>) -> Arc<CaptureState> {
+    match app.try_state::<Arc<ClipboardMonitor>>() {
+        Some(monitor) => monitor.capture_state(),
+        None => Arc::new(CaptureState::default()),
     }
+}
 
-    // 6. Wait for the target app to process the paste
-    std::thread::sleep(Duration::from_millis(200));
+/// Keystrokes sent through the OS.
+struct SystemInput;
 
-    // 7. Restore original clipboard content
-    if let Some(original) = original_clipboard {
-        let _ = app.clipboard().write_text(&original);
+impl InputBackend for SystemInput {
+    fn paste(&self) -> Result<(), String> {
+        simulate_paste()
     }
 
-    // 8. Resume clipboard monitoring
-    if let Some(monitor) = app.try_state::<Arc<ClipboardMonitor>>() {
-        monitor.resume();
+    fn press(&self, key: Key) -> Result<(), String> {
+        simulate_key(key)
     }
-
-    clog!("Paste from {} complete", slot_info.name);
 }
 
 /// Simulate Cmd+V using CoreGraphics CGEvent with explicit flags.
-/// This works even while physical modifier keys are held because we use
-/// a private event source and set only the Command flag on the event.
 #[cfg(target_os = "macos")]
 fn simulate_paste() -> Result<(), String> {
+    // Virtual key code 9 = 'v' on macOS
+    // kCGEventFlagMaskCommand = 0x00100000
+    post_key(9, 0x00100000)
+}
+
+#[cfg(target_os = "macos")]
+fn simulate_key(key: Key) -> Result<(), String> {
+    // Virtual key codes: 48 = Tab, 36 = Return, 51 = Delete (backspace)
+    let virtual_key = match key {
+        Key::Tab => 48,
+        Key::Enter => 36,
+        Key::Backspace => 51,
+    };
+    post_key(virtual_key, 0)
+}
+
+/// Press and release `virtual_key` with exactly `flags` held.
+/// This works even while physical modifier keys are held because we use
+/// a private event source and set only the given flags on the event.
+#[cfg(target_os = "macos")]
+fn post_key(virtual_key: u16, flags: u64) -> Result<(), String> {
     extern "C" {
         fn CGEventSourceCreate(state_id: i32) -> *mut std::ffi::c_void;
         fn CGEventCreateKeyboardEvent(
@@ -317,29 +529,25 @@ fn simulate_paste() -> Result<(), String> {
             return Err("Failed to create CGEventSource".to_string());
         }
 
-        // Virtual key code 9 = 'v' on macOS
-        // kCGEventFlagMaskCommand = 0x00100000
-        let cmd_flag: u64 = 0x00100000;
-
         // Key down
-        let key_down = CGEventCreateKeyboardEvent(source, 9, true);
+        let key_down = CGEventCreateKeyboardEvent(source, virtual_key, true);
         if key_down.is_null() {
             CFRelease(source);
             return Err("Failed to create key down event".to_string());
         }
-        CGEventSetFlags(key_down, cmd_flag);
+        CGEventSetFlags(key_down, flags);
         CGEventPost(0, key_down); // kCGHIDEventTap = 0
         CFRelease(key_down);
 
-        std::thread::sleep(Duration::from_millis(10));
+        std::thread::sleep(std::time::Duration::from_millis(10));
 
         // Key up
-        let key_up = CGEventCreateKeyboardEvent(source, 9, false);
+        let key_up = CGEventCreateKeyboardEvent(source, virtual_key, false);
         if key_up.is_null() {
             CFRelease(source);
             return Err("Failed to create key up event".to_string());
         }
-        CGEventSetFlags(key_up, cmd_flag);
+        CGEventSetFlags(key_up, flags);
         CGEventPost(0, key_up);
         CFRelease(key_up);
 
@@ -350,7 +558,7 @@ fn simulate_paste() -> Result<(), String> {
 }
 
 #[cfg(target_os = "windows")]
-fn simulate_paste() -> Result<(), String> {
+mod win_input {
     // Windows INPUT struct layout on 64-bit:
     //   offset 0:  type (u32, 4 bytes)
     //   offset 4:  _align (u32, 4 bytes padding for union alignment)
@@ -380,39 +588,74 @@ fn simulate_paste() -> Result<(), String> {
     }
 
     const INPUT_KEYBOARD: u32 = 1;
-    const KEYEVENTF_KEYUP: u32 = 0x0002;
+    pub const KEYEVENTF_KEYUP: u32 = 0x0002;
+
+    /// Send `(virtual key, flags)` keyboard events in order.
+    pub fn send(keys: &[(u16, u32)]) -> Result<(), String> {
+        let size = std::mem::size_of::<Input>() as i32;
+        let inputs: Vec<Input> = keys
+            .iter()
+            .map(|&(vk, flags)| Input {
+                type_: INPUT_KEYBOARD,
+                _align: 0,
+                vk,
+                scan: 0,
+                flags,
+                time: 0,
+                extra_info: 0,
+                _union_pad: [0; 8],
+            })
+            .collect();
+
+        let sent = unsafe { SendInput(inputs.len() as u32, inputs.as_ptr(), size) };
+
+        if sent as usize == inputs.len() {
+            Ok(())
+        } else {
+            Err(format!(
+                "SendInput returned {} (expected {}), size={}",
+                sent,
+                inputs.len(),
+                size
+            ))
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn simulate_paste() -> Result<(), String> {
+    use win_input::KEYEVENTF_KEYUP;
+
     const VK_CONTROL: u16 = 0x11;
     const VK_V: u16 = 0x56;
 
     const VK_SHIFT: u16 = 0x10;
     const VK_MENU: u16 = 0x12; // Alt
 
-    let size = std::mem::size_of::<Input>() as i32;
-
-    let make = |vk: u16, flags: u32| -> Input {
-        Input { type_: INPUT_KEYBOARD, _align: 0, vk, scan: 0, flags, time: 0, extra_info: 0, _union_pad: [0; 8] }
-    };
-
     // Release physical Alt+Shift first (they're still held from the shortcut),
     // then send Ctrl+V, so the target app sees only Ctrl+V.
-    let inputs = [
-        make(VK_MENU, KEYEVENTF_KEYUP),     // Alt up
-        make(VK_SHIFT, KEYEVENTF_KEYUP),     // Shift up
-        make(VK_CONTROL, 0),                  // Ctrl down
-        make(VK_V, 0),                        // V down
-        make(VK_V, KEYEVENTF_KEYUP),          // V up
-        make(VK_CONTROL, KEYEVENTF_KEYUP),    // Ctrl up
-    ];
-
-    let sent = unsafe {
-        SendInput(6, inputs.as_ptr(), size)
-    };
+    win_input::send(&[
+        (VK_MENU, KEYEVENTF_KEYUP),    // Alt up
+        (VK_SHIFT, KEYEVENTF_KEYUP),   // Shift up
+        (VK_CONTROL, 0),               // Ctrl down
+        (VK_V, 0),                     // V down
+        (VK_V, KEYEVENTF_KEYUP),       // V up
+        (VK_CONTROL, KEYEVENTF_KEYUP), // Ctrl up
+    ])
+}
 
-    if sent == 6 {
-        Ok(())
-    } else {
-        Err(format!("SendInput returned {} (expected 6), size={}", sent, size))
-    }
+#[cfg(target_os = "windows")]
+fn simulate_key(key: Key) -> Result<(), String> {
+    const VK_BACK: u16 = 0x08;
+    const VK_TAB: u16 = 0x09;
+    const VK_RETURN: u16 = 0x0D;
+
+    let vk = match key {
+        Key::Tab => VK_TAB,
+        Key::Enter => VK_RETURN,
+        Key::Backspace => VK_BACK,
+    };
+    win_input::send(&[(vk, 0), (vk, win_input::KEYEVENTF_KEYUP)])
 }
 
 #[cfg(not(any(target_os = "macos", target_os = "windows")))]
@@ -421,6 +664,12 @@ fn simulate_paste() -> Result<(), String> {
     Ok(())
 }
 
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn simulate_key(_key: Key) -> Result<(), String> {
+    // Linux: xdotool or similar would be needed
+    Ok(())
+}
+
 fn truncate(s: &str, max_len: usize) -> &str {
     match s.char_indices().nth(max_len) {
         Some((byte_idx, _)) => &s[..byte_idx],
@@ -0,0 +1,178 @@
+use std::time::Duration;
+
+use crate::clipboard::backend::{ClipboardBackend, InputBackend, Key};
+use crate::clipboard::capture_state::CaptureState;
+
+/// Most time allowed between sequentially pasted items.
+pub const MAX_SEQUENCE_DELAY_MS: u64 = 5000;
+
+/// Paste `text` into the focused app through `clipboard` and `input`,
+/// leaving the clipboard as it was. Capturing is paused meanwhile, so
+/// neither the pasted text nor the restored content is captured. Blocks for
+/// a few hundred milliseconds.
+pub fn paste_with(
+    clipboard: &dyn ClipboardBackend,
+    input: &dyn InputBackend,
+    state: &CaptureState,
+    text: &str,
+) {
+    if let Err(e) = paste_sequence_with(clipboard, input, state, &[text], None, Duration::ZERO) {
+        tracing::error!("{}", e);
+    }
+}
+
+/// Paste each of `texts` in turn like `paste_with`, pressing `separator`
+/// (e.g. Tab to reach the next form field) and waiting `delay` between
+/// them. Stops at the first failure; the clipboard is restored either way.
+pub fn paste_sequence_with(
+    clipboard: &dyn ClipboardBackend,
+    input: &dyn InputBackend,
+    state: &CaptureState,
+    texts: &[&str],
+    separator: Option<Key>,
+    delay: Duration,
+) -> Result<(), String> {
+    // 1. Pause clipboard monitoring
+    state.pause();
+
+    // 2. Save current clipboard content
+    let original_clipboard = clipboard.read_text().ok();
+
+    let result = texts.iter().enumerate().try_for_each(|(i, text)| {
+        if i > 0 {
+            if let Some(key) = separator {
+                input
+                    .press(key)
+                    .map_err(|e| format!("Failed to press {:?}: {}", key, e))?;
+            }
+            std::thread::sleep(delay);
+        }
+
+        // 3. Write the text to the system clipboard
+        clipboard
+            .write_text(text)
+            .map_err(|e| format!("Failed to write paste content to clipboard: {}", e))?;
+
+        // 4. Small delay for clipboard to propagate
+        std::thread::sleep(Duration::from_millis(50));
+
+        // 5. Simulate the paste keystroke
+        tracing::info!("Simulating Ctrl+V paste...");
+        input
+            .paste()
+            .map_err(|e| format!("Failed to simulate paste: {}", e))?;
+        tracing::info!("Paste simulation sent");
+
+        // 6. Wait for the target app to process the paste
+        std::thread::sleep(Duration::from_millis(200));
+        Ok(())
+    });
+
+    // 7. Restore original clipboard content
+    if let Some(original) = original_clipboard {
+        let _ = clipboard.write_text(&original);
+    }
+
+    // 8. Resume clipboard monitoring
+    state.resume();
+    result
+}
+
+/// Erase the `erase` characters just typed with Backspace, then paste
+/// `text` in their place like `paste_with`.
+pub fn replace_typed_with(
+    clipboard: &dyn ClipboardBackend,
+    input: &dyn InputBackend,
+    state: &CaptureState,
+    erase: usize,
+    text: &str,
+) -> Result<(), String> {
+    for _ in 0..erase {
+        input
+            .press(Key::Backspace)
+            .map_err(|e| format!("Failed to press Backspace: {}", e))?;
+    }
+    paste_sequence_with(clipboard, input, state, &[text], None, Duration::ZERO)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::clipboard::backend::fake::{FakeClipboard, FakeInput};
+    use crate::clipboard::watcher::ChangeWatcher;
+
+    #[test]
+    fn test_paste_restores_clipboard() {
+        let clipboard = FakeClipboard::with_text("the user's copy");
+        let input = FakeInput::new(&clipboard);
+        let state = CaptureState::default();
+
+        paste_with(&clipboard, &input, &state, "slot content");
+
+        assert_eq!(*input.pasted.lock().unwrap(), vec!["slot content"]);
+        assert_eq!(clipboard.text(), "the user's copy");
+        assert!(!state.is_paused());
+    }
+
+    #[test]
+    fn test_paste_is_not_captured() {
+        let clipboard = FakeClipboard::with_text("the user's copy");
+        let input = FakeInput::new(&clipboard);
+        let state = Arc::new(CaptureState::default());
+        let mut watcher = ChangeWatcher::new(state.clone(), Some("the user's copy"));
+
+        std::thread::scope(|s| {
+            s.spawn(|| paste_with(&clipboard, &input, &state, "slot content"));
+            // The monitor polls while the paste is under way
+            for _ in 0..30 {
+                assert_eq!(watcher.poll(&clipboard), None);
+                std::thread::sleep(Duration::from_millis(10));
+            }
+        });
+        assert_eq!(watcher.poll(&clipboard), None);
+
+        clipboard.copy("copied later");
+        assert_eq!(watcher.poll(&clipboard).as_deref(), Some("copied later"));
+    }
+
+    #[test]
+    fn test_paste_sequence() {
+        let clipboard = FakeClipboard::with_text("the user's copy");
+        let input = FakeInput::new(&clipboard);
+        let state = CaptureState::default();
+
+        let texts = ["Ada", "Lovelace", "ada@example.com"];
+        paste_sequence_with(
+            &clipboard,
+            &input,
+            &state,
+            &texts,
+            Some(Key::Tab),
+            Duration::ZERO,
+        )
+        .unwrap();
+
+        let typed = input.typed.lock().unwrap();
+        assert_eq!(
+            *typed,
+            ["Ada", "<Tab>", "Lovelace", "<Tab>", "ada@example.com"]
+        );
+        assert_eq!(clipboard.text(), "the user's copy");
+        assert!(!state.is_paused());
+    }
+
+    #[test]
+    fn test_replace_typed() {
+        let clipboard = FakeClipboard::with_text("the user's copy");
+        let input = FakeInput::new(&clipboard);
+        let state = CaptureState::default();
+
+        replace_typed_with(&clipboard, &input, &state, 3, "1 Main St").unwrap();
+
+        let typed = input.typed.lock().unwrap();
+        assert_eq!(*typed, ["<Backspace>", "<Backspace>", "<Backspace>", "1 Main St"]);
+        assert_eq!(clipboard.text(), "the user's copy");
+    }
+}
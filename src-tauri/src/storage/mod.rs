@@ -1 +1,3 @@
-pub mod database;
+pub use clipslot_core::storage::{database, integrity, page, search, slot_store};
+
+pub mod settings;
@@ -0,0 +1,421 @@
+use std::time::Duration;
+
+use rusqlite::Result as SqliteResult;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::clipboard::schedule::CaptureSchedule;
+use crate::clipboard::sensitive;
+use crate::i18n::Language;
+use crate::logging::{LogLevel, LogRedaction};
+use crate::slots::expander::{self, SnippetTrigger};
+use crate::storage::database::{
+    Database, DEFAULT_DEDUP_WINDOW_SECS, DEFAULT_HISTORY_LIMIT, DEFAULT_TRAY_HISTORY_COUNT,
+    MAX_TRAY_HISTORY_COUNT,
+};
+use crate::sync::filter::HistorySyncFilter;
+
+pub const MIN_HISTORY_LIMIT: u32 = 10;
+pub const MAX_HISTORY_LIMIT: u32 = 10_000;
+pub const MAX_DEDUP_WINDOW_SECS: u32 = 3600;
+pub const MAX_DEDUP_RECENT_ITEMS: u32 = 100;
+/// Local API port used until one is chosen in settings.
+pub const DEFAULT_LOCAL_API_PORT: u16 = 19847;
+pub const MAX_CLIPBOARD_CLEAR_SECS: u32 = 3600;
+pub const DEFAULT_PASTE_CONFIRM_KB: u32 = 512;
+pub const MAX_PASTE_CONFIRM_KB: u32 = 100 * 1024;
+/// Tray menu sections, in their default order.
+pub const TRAY_SECTION_KEYS: [&str; 7] =
+    ["status", "sync", "slots", "recent", "history", "pause", "settings"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrayLeftClick {
+    Menu,
+    Picker,
+}
+
+/// Which of `excluded_apps` and `allowed_apps` decides what is captured.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AppFilterMode {
+    /// Capture from every app except `excluded_apps`
+    Exclude,
+    /// Capture only from `allowed_apps`
+    Only,
+}
+
+/// Order of items in the quick picker.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PickerOrder {
+    Recent,
+    /// Most reused first, see `Database::get_frequent_items`
+    Frequent,
+}
+
+/// User-editable settings. Each field is stored as its own `app_config` row
+/// (strings as-is, everything else as JSON), so the sync and startup code can
+/// keep reading single keys.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    pub history_limit: u32,
+    /// Seconds in which the same content isn't captured twice; 0 for none
+    pub dedup_window_secs: u32,
+    /// Also skip content matching any of this many latest items; 0 for none
+    pub dedup_recent_items: u32,
+    pub auto_clear_on_quit: bool,
+    /// App names whose clipboard changes are never captured
+    pub excluded_apps: Vec<String>,
+    pub app_filter_mode: AppFilterMode,
+    /// App names that are the only ones captured in `AppFilterMode::Only`
+    pub allowed_apps: Vec<String>,
+    /// Times of the week when clipboard changes are (or aren't) captured
+    pub capture_schedule: CaptureSchedule,
+    /// Look up the page title of copied links. Contacts the linked site.
+    pub fetch_link_titles: bool,
+    /// Seconds before a copied secret is cleared from the clipboard; 0 to
+    /// leave it
+    pub clipboard_clear_secs: u32,
+    /// Slot content larger than this many KB is only pasted once confirmed;
+    /// 0 to never ask
+    pub paste_confirm_kb: u32,
+    /// Let `clipslot://paste-slot` and `paste-item` links paste. Off by
+    /// default, since any web page can open one.
+    pub deep_link_paste: bool,
+    /// Keep the app's windows out of screenshots and screen sharing
+    pub hide_from_screen_capture: bool,
+    pub sync_server_url: String,
+    pub history_sync_enabled: bool,
+    /// Which local items history sync uploads
+    pub history_sync_filter: HistorySyncFilter,
+    pub search_index_enabled: bool,
+    /// Chain history items with a keyed MAC to spot tampering with the file
+    pub history_integrity_enabled: bool,
+    pub tray_history_count: u32,
+    /// Tray sections shown, in order
+    pub tray_sections: Vec<String>,
+    pub tray_left_click: TrayLeftClick,
+    pub picker_order: PickerOrder,
+    /// Language of the tray menu and notifications
+    pub language: Language,
+    /// Replace typed keywords with slot content. Off by default, since it
+    /// means watching every key typed.
+    pub snippet_expansion_enabled: bool,
+    pub snippet_triggers: Vec<SnippetTrigger>,
+    pub check_updates_on_startup: bool,
+    /// Send anonymous daily usage counts to the sync server
+    pub telemetry_enabled: bool,
+    pub local_api_enabled: bool,
+    pub local_api_port: u16,
+    pub log_level: LogLevel,
+    /// How much of clipboard content the log shows
+    pub log_redaction: LogRedaction,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            history_limit: DEFAULT_HISTORY_LIMIT,
+            dedup_window_secs: DEFAULT_DEDUP_WINDOW_SECS,
+            dedup_recent_items: 0,
+            auto_clear_on_quit: false,
+            excluded_apps: Vec::new(),
+            app_filter_mode: AppFilterMode::Exclude,
+            allowed_apps: Vec::new(),
+            capture_schedule: CaptureSchedule::default(),
+            fetch_link_titles: false,
+            clipboard_clear_secs: 0,
+            paste_confirm_kb: DEFAULT_PASTE_CONFIRM_KB,
+            deep_link_paste: false,
+            hide_from_screen_capture: false,
+            sync_server_url: crate::config::SYNC_SERVER_URL.to_string(),
+            history_sync_enabled: false,
+            history_sync_filter: HistorySyncFilter::default(),
+            search_index_enabled: false,
+            history_integrity_enabled: false,
+            tray_history_count: DEFAULT_TRAY_HISTORY_COUNT,
+            tray_sections: TRAY_SECTION_KEYS.iter().map(|k| k.to_string()).collect(),
+            tray_left_click: TrayLeftClick::Menu,
+            picker_order: PickerOrder::Recent,
+            language: Language::System,
+            snippet_expansion_enabled: false,
+            snippet_triggers: Vec::new(),
+            check_updates_on_startup: true,
+            telemetry_enabled: false,
+            local_api_enabled: false,
+            local_api_port: DEFAULT_LOCAL_API_PORT,
+            log_level: LogLevel::Info,
+            log_redaction: LogRedaction::Lengths,
+        }
+    }
+}
+
+impl Settings {
+    /// Current settings. A stored value that is missing, malformed or out of
+    /// range falls back to its default.
+    pub fn load(db: &Database) -> Settings {
+        Self::from_stored(|key| db.get_setting(key))
+    }
+
+    fn from_stored(get: impl Fn(&str) -> Option<String>) -> Settings {
+        let defaults = to_map(&Settings::default());
+        let mut settings = Settings::default();
+        for (key, default) in &defaults {
+            let Some(raw) = get(key) else {
+                continue;
+            };
+            let value = match default {
+                Value::String(_) => Value::String(raw.clone()),
+                _ => serde_json::from_str(&raw).unwrap_or(Value::Null),
+            };
+            let mut changes = Map::new();
+            changes.insert(key.clone(), value);
+            match settings.with_changes(changes) {
+                Ok(updated) => settings = updated,
+                Err(e) => tracing::info!("Ignoring stored setting {}={:?}: {}", key, raw, e),
+            }
+        }
+        settings
+    }
+
+    /// A copy with `changes` (a partial settings object) applied, or why the
+    /// result would be invalid.
+    pub fn with_changes(&self, changes: Map<String, Value>) -> Result<Settings, String> {
+        let mut map = to_map(self);
+        for (key, value) in changes {
+            if !map.contains_key(&key) {
+                return Err(format!("Unknown setting: {}", key));
+            }
+            map.insert(key, value);
+        }
+        let settings: Settings =
+            serde_json::from_value(Value::Object(map)).map_err(|e| e.to_string())?;
+        settings.validate()?;
+        Ok(settings)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if !(MIN_HISTORY_LIMIT..=MAX_HISTORY_LIMIT).contains(&self.history_limit) {
+            return Err(format!(
+                "history_limit must be between {} and {}",
+                MIN_HISTORY_LIMIT, MAX_HISTORY_LIMIT
+            ));
+        }
+        if self.dedup_window_secs > MAX_DEDUP_WINDOW_SECS {
+            return Err(format!(
+                "dedup_window_secs must be at most {}",
+                MAX_DEDUP_WINDOW_SECS
+            ));
+        }
+        if self.dedup_recent_items > MAX_DEDUP_RECENT_ITEMS {
+            return Err(format!(
+                "dedup_recent_items must be at most {}",
+                MAX_DEDUP_RECENT_ITEMS
+            ));
+        }
+        if self.tray_history_count > MAX_TRAY_HISTORY_COUNT {
+            return Err(format!(
+                "tray_history_count must be at most {}",
+                MAX_TRAY_HISTORY_COUNT
+            ));
+        }
+        for (i, key) in self.tray_sections.iter().enumerate() {
+            if !TRAY_SECTION_KEYS.contains(&key.as_str()) {
+                return Err(format!("Unknown tray section: {}", key));
+            }
+            if self.tray_sections[..i].contains(key) {
+                return Err(format!("Duplicate tray section: {}", key));
+            }
+        }
+        if self.excluded_apps.iter().any(|app| app.trim().is_empty()) {
+            return Err("Excluded app names can't be empty".to_string());
+        }
+        if self.allowed_apps.iter().any(|app| app.trim().is_empty()) {
+            return Err("Allowed app names can't be empty".to_string());
+        }
+        if self.clipboard_clear_secs > MAX_CLIPBOARD_CLEAR_SECS {
+            return Err(format!(
+                "clipboard_clear_secs must be at most {}",
+                MAX_CLIPBOARD_CLEAR_SECS
+            ));
+        }
+        if self.paste_confirm_kb > MAX_PASTE_CONFIRM_KB {
+            return Err(format!(
+                "paste_confirm_kb must be at most {}",
+                MAX_PASTE_CONFIRM_KB
+            ));
+        }
+        self.capture_schedule.validate()?;
+        expander::validate_triggers(&self.snippet_triggers)?;
+        self.history_sync_filter.validate()?;
+        let url = url::Url::parse(&self.sync_server_url)
+            .map_err(|e| format!("Invalid sync_server_url: {}", e))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err("sync_server_url must be an http(s) URL".to_string());
+        }
+        if self.local_api_port < 1024 {
+            return Err("local_api_port must be between 1024 and 65535".to_string());
+        }
+        Ok(())
+    }
+
+    /// Whether clipboard content copied in `app` may be captured. Content from
+    /// an unknown app is, since there is nothing to match.
+    pub fn captures_from(&self, app: Option<&str>) -> bool {
+        let Some(app) = app else {
+            return true;
+        };
+        let listed = |apps: &[String]| apps.iter().any(|a| a.eq_ignore_ascii_case(app));
+        match self.app_filter_mode {
+            AppFilterMode::Exclude => !listed(&self.excluded_apps),
+            AppFilterMode::Only => listed(&self.allowed_apps),
+        }
+    }
+
+    /// How long `text`, copied in `app`, stays on the clipboard before it is
+    /// cleared, if it is. That is content that looks like a secret, or comes
+    /// from an app that isn't captured, such as a password manager.
+    pub fn clipboard_clear_delay(&self, text: &str, app: Option<&str>) -> Option<Duration> {
+        if self.clipboard_clear_secs == 0 {
+            return None;
+        }
+        let from_hidden_app = app.is_some() && !self.captures_from(app);
+        if !from_hidden_app && !sensitive::looks_sensitive(text) {
+            return None;
+        }
+        Some(Duration::from_secs(self.clipboard_clear_secs.into()))
+    }
+
+    /// Whether pasting `text` from a slot has to be confirmed first.
+    pub fn paste_needs_confirmation(&self, text: &str) -> bool {
+        self.paste_confirm_kb > 0 && text.len() > self.paste_confirm_kb as usize * 1024
+    }
+
+    /// Write the fields that differ from `previous`; returns their keys.
+    pub fn save(&self, db: &Database, previous: &Settings) -> SqliteResult<Vec<String>> {
+        let old = to_map(previous);
+        let mut changed = Vec::new();
+        for (key, value) in to_map(self) {
+            if old.get(&key) == Some(&value) {
+                continue;
+            }
+            let stored = match value {
+                Value::String(s) => s,
+                other => other.to_string(),
+            };
+            db.set_setting(&key, &stored)?;
+            changed.push(key);
+        }
+        Ok(changed)
+    }
+}
+
+fn to_map(settings: &Settings) -> Map<String, Value> {
+    match serde_json::to_value(settings) {
+        Ok(Value::Object(map)) => map,
+        _ => unreachable!("Settings serializes to an object"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn changes(value: Value) -> Map<String, Value> {
+        value.as_object().unwrap().clone()
+    }
+
+    #[test]
+    fn test_stored_garbage_falls_back_to_default() {
+        let settings = Settings::from_stored(|key| match key {
+            "history_limit" => Some("abc".to_string()),
+            "tray_history_count" => Some("3".to_string()),
+            "tray_left_click" => Some("picker".to_string()),
+            "picker_order" => Some("frequent".to_string()),
+            "excluded_apps" => Some(r#"["Keychain Access"]"#.to_string()),
+            "tray_sections" => Some(r#"["slots", "bogus"]"#.to_string()),
+            _ => None,
+        });
+        assert_eq!(settings.history_limit, DEFAULT_HISTORY_LIMIT);
+        assert_eq!(settings.tray_history_count, 3);
+        assert_eq!(settings.tray_left_click, TrayLeftClick::Picker);
+        assert_eq!(settings.picker_order, PickerOrder::Frequent);
+        assert_eq!(settings.excluded_apps, vec!["Keychain Access"]);
+        assert_eq!(settings.tray_sections, Settings::default().tray_sections);
+    }
+
+    #[test]
+    fn test_captures_from() {
+        let mut settings = Settings {
+            excluded_apps: vec!["1Password".to_string()],
+            allowed_apps: vec!["Code".to_string()],
+            ..Default::default()
+        };
+        assert!(!settings.captures_from(Some("1password")));
+        assert!(settings.captures_from(Some("Safari")));
+
+        settings.app_filter_mode = AppFilterMode::Only;
+        assert!(settings.captures_from(Some("code")));
+        assert!(!settings.captures_from(Some("Safari")));
+        assert!(settings.captures_from(None));
+    }
+
+    #[test]
+    fn test_clipboard_clear_delay() {
+        let secret = "password: correct-horse";
+        let mut settings = Settings {
+            excluded_apps: vec!["1Password".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(settings.clipboard_clear_delay(secret, None), None);
+
+        settings.clipboard_clear_secs = 30;
+        let delay = Some(Duration::from_secs(30));
+        assert_eq!(settings.clipboard_clear_delay(secret, None), delay);
+        assert_eq!(settings.clipboard_clear_delay("hunter2", Some("1Password")), delay);
+        assert_eq!(settings.clipboard_clear_delay("hunter2", Some("Safari")), None);
+        assert_eq!(settings.clipboard_clear_delay("hunter2", None), None);
+    }
+
+    #[test]
+    fn test_paste_needs_confirmation() {
+        let mut settings = Settings {
+            paste_confirm_kb: 1,
+            ..Default::default()
+        };
+        assert!(!settings.paste_needs_confirmation(&"x".repeat(1024)));
+        assert!(settings.paste_needs_confirmation(&"x".repeat(1025)));
+
+        settings.paste_confirm_kb = 0;
+        assert!(!settings.paste_needs_confirmation(&"x".repeat(1024 * 1024)));
+    }
+
+    #[test]
+    fn test_with_changes_validates() {
+        let settings = Settings::default();
+        let updated = settings
+            .with_changes(changes(json!({"history_limit": 1000, "auto_clear_on_quit": true})))
+            .unwrap();
+        assert_eq!(updated.history_limit, 1000);
+        assert!(updated.auto_clear_on_quit);
+
+        for bad in [
+            json!({"history_limit": "abc"}),
+            json!({"history_limit": 5}),
+            json!({"dedup_recent_items": 1000}),
+            json!({"tray_left_click": "double"}),
+            json!({"tray_sections": ["slots", "slots"]}),
+            json!({"sync_server_url": "ftp://example.com"}),
+            json!({"local_api_port": 80}),
+            json!({"clipboard_clear_secs": 86400}),
+            json!({"log_level": "verbose"}),
+            json!({"snippet_triggers": [{"keyword": "x", "slot": 1}]}),
+            json!({"no_such_key": true}),
+        ] {
+            assert!(settings.with_changes(changes(bad.clone())).is_err(), "{}", bad);
+        }
+    }
+}
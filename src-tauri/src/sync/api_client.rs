@@ -1,4 +1,5 @@
 use reqwest::Client;
+use serde::Deserialize;
 use uuid::Uuid;
 
 use super::types::*;
@@ -11,8 +12,14 @@ pub struct ApiClient {
 
 impl ApiClient {
     pub fn new(base_url: &str) -> Self {
+        // History pages are mostly base64 and compress well
+        let client = Client::builder()
+            .gzip(true)
+            .brotli(true)
+            .build()
+            .expect("Failed to build HTTP client");
         Self {
-            client: Client::new(),
+            client,
             base_url: base_url.trim_end_matches('/').to_string(),
         }
     }
@@ -21,9 +28,26 @@ impl ApiClient {
         &self.base_url
     }
 
+    /// Whether the server answers its health check. A server error (e.g. a
+    /// proxy whose backend is down) counts as unreachable; any other answer,
+    /// including 404 from servers without the health route, as reachable.
+    pub async fn health(&self, timeout: std::time::Duration) -> Result<(), ApiError> {
+        let resp = self
+            .client
+            .get(format!("{}/api/health", self.base_url))
+            .timeout(timeout)
+            .send()
+            .await
+            .map_err(|e| format!("Network error: {}", e))?;
+        if health_status_unreachable(resp.status()) {
+            return Err(format!("Health check failed: {}", resp.status()).into());
+        }
+        Ok(())
+    }
+
     // ── Auth ────────────────────────────────────────────────────────────
 
-    pub async fn register(&self, email: &str, password: &str) -> Result<AuthResponse, String> {
+    pub async fn register(&self, email: &str, password: &str) -> Result<AuthResponse, ApiError> {
         let resp = self
             .client
             .post(format!("{}/api/auth/register", self.base_url))
@@ -39,10 +63,10 @@ impl ApiClient {
 
         resp.json::<AuthResponse>()
             .await
-            .map_err(|e| format!("Parse error: {}", e))
+            .map_err(|e| format!("Parse error: {}", e).into())
     }
 
-    pub async fn login(&self, email: &str, password: &str) -> Result<AuthResponse, String> {
+    pub async fn login(&self, email: &str, password: &str) -> Result<AuthResponse, ApiError> {
         let resp = self
             .client
             .post(format!("{}/api/auth/login", self.base_url))
@@ -58,7 +82,7 @@ impl ApiClient {
 
         resp.json::<AuthResponse>()
             .await
-            .map_err(|e| format!("Parse error: {}", e))
+            .map_err(|e| format!("Parse error: {}", e).into())
     }
 
     pub async fn register_device(
@@ -66,12 +90,19 @@ impl ApiClient {
         token: &str,
         name: &str,
         device_type: &str,
-    ) -> Result<DeviceRegistrationResponse, String> {
+        metadata: &DeviceMetadata,
+    ) -> Result<DeviceRegistrationResponse, ApiError> {
         let resp = self
             .client
             .post(format!("{}/api/auth/device", self.base_url))
             .bearer_auth(token)
-            .json(&serde_json::json!({ "name": name, "device_type": device_type }))
+            .json(&serde_json::json!({
+                "name": name,
+                "device_type": device_type,
+                "app_version": metadata.app_version,
+                "os_version": metadata.os_version,
+                "hostname": metadata.hostname,
+            }))
             .send()
             .await
             .map_err(|e| format!("Network error: {}", e))?;
@@ -83,10 +114,33 @@ impl ApiClient {
 
         resp.json::<DeviceRegistrationResponse>()
             .await
-            .map_err(|e| format!("Parse error: {}", e))
+            .map_err(|e| format!("Parse error: {}", e).into())
     }
 
-    pub async fn list_devices(&self, token: &str) -> Result<Vec<DeviceInfo>, String> {
+    /// Refresh the metadata of the device the token belongs to.
+    pub async fn update_device(
+        &self,
+        token: &str,
+        metadata: &DeviceMetadata,
+    ) -> Result<(), ApiError> {
+        let resp = self
+            .client
+            .put(format!("{}/api/auth/device", self.base_url))
+            .bearer_auth(token)
+            .json(metadata)
+            .send()
+            .await
+            .map_err(|e| format!("Network error: {}", e))?;
+
+        if !resp.status().is_success() {
+            let body = resp.text().await.unwrap_or_default();
+            return Err(extract_error(&body));
+        }
+
+        Ok(())
+    }
+
+    pub async fn list_devices(&self, token: &str) -> Result<Vec<DeviceInfo>, ApiError> {
         let resp = self
             .client
             .get(format!("{}/api/auth/devices", self.base_url))
@@ -102,10 +156,10 @@ impl ApiClient {
 
         resp.json::<Vec<DeviceInfo>>()
             .await
-            .map_err(|e| format!("Parse error: {}", e))
+            .map_err(|e| format!("Parse error: {}", e).into())
     }
 
-    pub async fn delete_device(&self, token: &str, device_id: Uuid) -> Result<(), String> {
+    pub async fn delete_device(&self, token: &str, device_id: Uuid) -> Result<(), ApiError> {
         let resp = self
             .client
             .delete(format!("{}/api/auth/device/{}", self.base_url, device_id))
@@ -124,7 +178,7 @@ impl ApiClient {
 
     // ── Slots ───────────────────────────────────────────────────────────
 
-    pub async fn get_slots(&self, token: &str) -> Result<Vec<SlotResponse>, String> {
+    pub async fn get_slots(&self, token: &str) -> Result<Vec<SlotResponse>, ApiError> {
         let resp = self
             .client
             .get(format!("{}/api/sync/slots", self.base_url))
@@ -140,7 +194,7 @@ impl ApiClient {
 
         resp.json::<Vec<SlotResponse>>()
             .await
-            .map_err(|e| format!("Parse error: {}", e))
+            .map_err(|e| format!("Parse error: {}", e).into())
     }
 
     pub async fn update_slot(
@@ -148,7 +202,7 @@ impl ApiClient {
         token: &str,
         slot_number: i32,
         encrypted_blob: &str,
-    ) -> Result<(), String> {
+    ) -> Result<(), ApiError> {
         let resp = self
             .client
             .put(format!(
@@ -171,19 +225,94 @@ impl ApiClient {
         Ok(())
     }
 
+    pub async fn get_slot_settings(&self, token: &str) -> Result<SlotSettings, ApiError> {
+        let resp = self
+            .client
+            .get(format!("{}/api/sync/settings", self.base_url))
+            .bearer_auth(token)
+            .send()
+            .await
+            .map_err(|e| format!("Network error: {}", e))?;
+
+        if !resp.status().is_success() {
+            let body = resp.text().await.unwrap_or_default();
+            return Err(extract_error(&body));
+        }
+
+        resp.json::<SlotSettings>()
+            .await
+            .map_err(|e| format!("Parse error: {}", e).into())
+    }
+
+    pub async fn update_slot_settings(
+        &self,
+        token: &str,
+        settings: &SlotSettings,
+    ) -> Result<SlotSettings, ApiError> {
+        let resp = self
+            .client
+            .put(format!("{}/api/sync/settings", self.base_url))
+            .bearer_auth(token)
+            .json(settings)
+            .send()
+            .await
+            .map_err(|e| format!("Network error: {}", e))?;
+
+        if !resp.status().is_success() {
+            let body = resp.text().await.unwrap_or_default();
+            return Err(extract_error(&body));
+        }
+
+        resp.json::<SlotSettings>()
+            .await
+            .map_err(|e| format!("Parse error: {}", e).into())
+    }
+
     // ── History ─────────────────────────────────────────────────────────
 
+    /// Fetch a page of history, newest first. Pass the previous page's
+    /// `next_cursor` to continue.
     pub async fn get_history(
         &self,
         token: &str,
         limit: i64,
-        offset: i64,
-    ) -> Result<Vec<HistoryResponse>, String> {
+        cursor: Option<&str>,
+    ) -> Result<HistoryPageResponse, ApiError> {
+        let mut query = vec![("limit", limit.to_string())];
+        if let Some(cursor) = cursor {
+            query.push(("cursor", cursor.to_string()));
+        }
+        let resp = self
+            .client
+            .get(format!("{}/api/sync/history", self.base_url))
+            .bearer_auth(token)
+            .query(&query)
+            .send()
+            .await
+            .map_err(|e| format!("Network error: {}", e))?;
+
+        if !resp.status().is_success() {
+            let body = resp.text().await.unwrap_or_default();
+            return Err(extract_error(&body));
+        }
+
+        resp.json::<HistoryPageResponse>()
+            .await
+            .map_err(|e| format!("Parse error: {}", e).into())
+    }
+
+    /// Fetch items newer than `since` (a previous `next_cursor`, or "0" for everything).
+    pub async fn get_history_since(
+        &self,
+        token: &str,
+        since: &str,
+        limit: i64,
+    ) -> Result<HistoryDeltaResponse, ApiError> {
         let resp = self
             .client
             .get(format!("{}/api/sync/history", self.base_url))
             .bearer_auth(token)
-            .query(&[("limit", limit), ("offset", offset)])
+            .query(&[("since", since.to_string()), ("limit", limit.to_string())])
             .send()
             .await
             .map_err(|e| format!("Network error: {}", e))?;
@@ -193,12 +322,12 @@ impl ApiClient {
             return Err(extract_error(&body));
         }
 
-        resp.json::<Vec<HistoryResponse>>()
+        resp.json::<HistoryDeltaResponse>()
             .await
-            .map_err(|e| format!("Parse error: {}", e))
+            .map_err(|e| format!("Parse error: {}", e).into())
     }
 
-    pub async fn push_history(&self, token: &str, req: &PushHistoryRequest) -> Result<(), String> {
+    pub async fn push_history(&self, token: &str, req: &PushHistoryRequest) -> Result<(), ApiError> {
         let resp = self
             .client
             .post(format!("{}/api/sync/history", self.base_url))
@@ -216,14 +345,343 @@ impl ApiClient {
         Ok(())
     }
 
+    // ── Attachments ─────────────────────────────────────────────────────
+
+    /// Start (or resume) a chunked upload of an encrypted attachment.
+    pub async fn create_attachment(
+        &self,
+        token: &str,
+        req: &CreateAttachmentRequest,
+    ) -> Result<AttachmentResponse, ApiError> {
+        let resp = self
+            .client
+            .post(format!("{}/api/sync/attachments", self.base_url))
+            .bearer_auth(token)
+            .json(req)
+            .send()
+            .await
+            .map_err(|e| format!("Network error: {}", e))?;
+
+        if !resp.status().is_success() {
+            let body = resp.text().await.unwrap_or_default();
+            return Err(extract_error(&body));
+        }
+
+        resp.json::<AttachmentResponse>()
+            .await
+            .map_err(|e| format!("Parse error: {}", e).into())
+    }
+
+    pub async fn get_attachment(
+        &self,
+        token: &str,
+        id: Uuid,
+    ) -> Result<AttachmentResponse, ApiError> {
+        let resp = self
+            .client
+            .get(format!("{}/api/sync/attachments/{}", self.base_url, id))
+            .bearer_auth(token)
+            .send()
+            .await
+            .map_err(|e| format!("Network error: {}", e))?;
+
+        if !resp.status().is_success() {
+            let body = resp.text().await.unwrap_or_default();
+            return Err(extract_error(&body));
+        }
+
+        resp.json::<AttachmentResponse>()
+            .await
+            .map_err(|e| format!("Parse error: {}", e).into())
+    }
+
+    pub async fn upload_attachment_chunk(
+        &self,
+        token: &str,
+        id: Uuid,
+        index: i32,
+        data: Vec<u8>,
+    ) -> Result<(), ApiError> {
+        let resp = self
+            .client
+            .put(format!("{}/api/sync/attachments/{}/chunks/{}", self.base_url, id, index))
+            .bearer_auth(token)
+            .header(reqwest::header::CONTENT_TYPE, "application/octet-stream")
+            .body(data)
+            .send()
+            .await
+            .map_err(|e| format!("Network error: {}", e))?;
+
+        if !resp.status().is_success() {
+            let body = resp.text().await.unwrap_or_default();
+            return Err(extract_error(&body));
+        }
+
+        Ok(())
+    }
+
+    /// Finish an upload. Fails if chunks are still missing.
+    pub async fn complete_attachment(&self, token: &str, id: Uuid) -> Result<(), ApiError> {
+        let resp = self
+            .client
+            .post(format!("{}/api/sync/attachments/{}/complete", self.base_url, id))
+            .bearer_auth(token)
+            .send()
+            .await
+            .map_err(|e| format!("Network error: {}", e))?;
+
+        if resp.status() == reqwest::StatusCode::CONFLICT {
+            let body = resp.text().await.unwrap_or_default();
+            let missing = serde_json::from_str::<AttachmentResponse>(&body)
+                .map(|a| a.missing_chunks.len())
+                .unwrap_or_default();
+            return Err(format!("Attachment upload is missing {} chunk(s)", missing).into());
+        }
+        if !resp.status().is_success() {
+            let body = resp.text().await.unwrap_or_default();
+            return Err(extract_error(&body));
+        }
+
+        Ok(())
+    }
+
+    pub async fn download_attachment_chunk(
+        &self,
+        token: &str,
+        id: Uuid,
+        index: i32,
+    ) -> Result<Vec<u8>, ApiError> {
+        let resp = self
+            .client
+            .get(format!("{}/api/sync/attachments/{}/chunks/{}", self.base_url, id, index))
+            .bearer_auth(token)
+            .send()
+            .await
+            .map_err(|e| format!("Network error: {}", e))?;
+
+        if !resp.status().is_success() {
+            let body = resp.text().await.unwrap_or_default();
+            return Err(extract_error(&body));
+        }
+
+        resp.bytes()
+            .await
+            .map(|b| b.to_vec())
+            .map_err(|e| format!("Network error: {}", e).into())
+    }
+
+    // ── Shares ──────────────────────────────────────────────────────────
+
+    /// Upload a blob encrypted with its own key as a one-time share.
+    pub async fn create_share(
+        &self,
+        token: &str,
+        req: &CreateShareRequest,
+    ) -> Result<CreateShareResponse, ApiError> {
+        let resp = self
+            .client
+            .post(format!("{}/api/shares", self.base_url))
+            .bearer_auth(token)
+            .json(req)
+            .send()
+            .await
+            .map_err(|e| format!("Network error: {}", e))?;
+
+        if !resp.status().is_success() {
+            let body = resp.text().await.unwrap_or_default();
+            return Err(extract_error(&body));
+        }
+
+        resp.json::<CreateShareResponse>()
+            .await
+            .map_err(|e| format!("Parse error: {}", e).into())
+    }
+
+    // ── Teams ───────────────────────────────────────────────────────────
+
+    pub async fn list_teams(&self, token: &str) -> Result<Vec<TeamResponse>, ApiError> {
+        let resp = self
+            .client
+            .get(format!("{}/api/teams", self.base_url))
+            .bearer_auth(token)
+            .send()
+            .await
+            .map_err(|e| format!("Network error: {}", e))?;
+
+        if !resp.status().is_success() {
+            let body = resp.text().await.unwrap_or_default();
+            return Err(extract_error(&body));
+        }
+
+        resp.json::<Vec<TeamResponse>>()
+            .await
+            .map_err(|e| format!("Parse error: {}", e).into())
+    }
+
+    pub async fn create_team(
+        &self,
+        token: &str,
+        name: &str,
+        encrypted_team_key: &str,
+    ) -> Result<TeamResponse, ApiError> {
+        let resp = self
+            .client
+            .post(format!("{}/api/teams", self.base_url))
+            .bearer_auth(token)
+            .json(&serde_json::json!({ "name": name, "encrypted_team_key": encrypted_team_key }))
+            .send()
+            .await
+            .map_err(|e| format!("Network error: {}", e))?;
+
+        if !resp.status().is_success() {
+            let body = resp.text().await.unwrap_or_default();
+            return Err(extract_error(&body));
+        }
+
+        resp.json::<TeamResponse>()
+            .await
+            .map_err(|e| format!("Parse error: {}", e).into())
+    }
+
+    pub async fn add_team_member(
+        &self,
+        token: &str,
+        team_id: Uuid,
+        email: &str,
+    ) -> Result<(), ApiError> {
+        let resp = self
+            .client
+            .post(format!("{}/api/teams/{}/members", self.base_url, team_id))
+            .bearer_auth(token)
+            .json(&serde_json::json!({ "email": email }))
+            .send()
+            .await
+            .map_err(|e| format!("Network error: {}", e))?;
+
+        if !resp.status().is_success() {
+            let body = resp.text().await.unwrap_or_default();
+            return Err(extract_error(&body));
+        }
+
+        Ok(())
+    }
+
+    /// Store our own wrapped copy of the team key.
+    pub async fn set_team_key(
+        &self,
+        token: &str,
+        team_id: Uuid,
+        encrypted_team_key: &str,
+    ) -> Result<(), ApiError> {
+        let resp = self
+            .client
+            .put(format!("{}/api/teams/{}/key", self.base_url, team_id))
+            .bearer_auth(token)
+            .json(&serde_json::json!({ "encrypted_team_key": encrypted_team_key }))
+            .send()
+            .await
+            .map_err(|e| format!("Network error: {}", e))?;
+
+        if !resp.status().is_success() {
+            let body = resp.text().await.unwrap_or_default();
+            return Err(extract_error(&body));
+        }
+
+        Ok(())
+    }
+
+    /// Hand the team key, wrapped with an invite code, to a member.
+    pub async fn share_team_key(
+        &self,
+        token: &str,
+        team_id: Uuid,
+        member_id: Uuid,
+        invite_key: &str,
+    ) -> Result<(), ApiError> {
+        let resp = self
+            .client
+            .put(format!(
+                "{}/api/teams/{}/members/{}/key",
+                self.base_url, team_id, member_id
+            ))
+            .bearer_auth(token)
+            .json(&serde_json::json!({ "invite_key": invite_key }))
+            .send()
+            .await
+            .map_err(|e| format!("Network error: {}", e))?;
+
+        if !resp.status().is_success() {
+            let body = resp.text().await.unwrap_or_default();
+            return Err(extract_error(&body));
+        }
+
+        Ok(())
+    }
+
+    pub async fn get_team_slots(
+        &self,
+        token: &str,
+        team_id: Uuid,
+    ) -> Result<Vec<TeamSlotResponse>, ApiError> {
+        let resp = self
+            .client
+            .get(format!("{}/api/teams/{}/slots", self.base_url, team_id))
+            .bearer_auth(token)
+            .send()
+            .await
+            .map_err(|e| format!("Network error: {}", e))?;
+
+        if !resp.status().is_success() {
+            let body = resp.text().await.unwrap_or_default();
+            return Err(extract_error(&body));
+        }
+
+        resp.json::<Vec<TeamSlotResponse>>()
+            .await
+            .map_err(|e| format!("Parse error: {}", e).into())
+    }
+
+    pub async fn update_team_slot(
+        &self,
+        token: &str,
+        team_id: Uuid,
+        slot_number: i32,
+        encrypted_blob: &str,
+    ) -> Result<(), ApiError> {
+        let resp = self
+            .client
+            .put(format!(
+                "{}/api/teams/{}/slots/{}",
+                self.base_url, team_id, slot_number
+            ))
+            .bearer_auth(token)
+            .json(&UpdateSlotRequest {
+                encrypted_blob: encrypted_blob.to_string(),
+            })
+            .send()
+            .await
+            .map_err(|e| format!("Network error: {}", e))?;
+
+        if !resp.status().is_success() {
+            let body = resp.text().await.unwrap_or_default();
+            return Err(extract_error(&body));
+        }
+
+        Ok(())
+    }
+
     // ── WebSocket ───────────────────────────────────────────────────────
 
-    pub fn ws_url(&self, token: &str) -> String {
+    /// WebSocket URL; `resume_from` asks the server to replay broadcasts after that seq.
+    pub fn ws_url(&self, token: &str, resume_from: Option<u64>) -> String {
         let ws_base = self
             .base_url
             .replace("http://", "ws://")
             .replace("https://", "wss://");
-        format!("{}/api/sync/ws?token={}", ws_base, token)
+        match resume_from {
+            Some(seq) => format!("{}/api/sync/ws?token={}&resume_from={}", ws_base, token, seq),
+            None => format!("{}/api/sync/ws?token={}", ws_base, token),
+        }
     }
 
     // ── Key Exchange ─────────────────────────────────────────────────────
@@ -232,7 +690,7 @@ impl ApiClient {
         &self,
         token: &str,
         encrypted_key: &str,
-    ) -> Result<String, String> {
+    ) -> Result<String, ApiError> {
         let resp = self
             .client
             .post(format!("{}/api/auth/link-code", self.base_url))
@@ -255,14 +713,14 @@ impl ApiClient {
         data.get("code")
             .and_then(|c| c.as_str())
             .map(|s| s.to_string())
-            .ok_or_else(|| "Missing code in response".to_string())
+            .ok_or_else(|| "Missing code in response".to_string().into())
     }
 
     pub async fn redeem_link_code(
         &self,
         token: &str,
         code: &str,
-    ) -> Result<String, String> {
+    ) -> Result<String, ApiError> {
         let resp = self
             .client
             .post(format!("{}/api/auth/redeem-code", self.base_url))
@@ -285,13 +743,51 @@ impl ApiClient {
         data.get("encrypted_key")
             .and_then(|k| k.as_str())
             .map(|s| s.to_string())
-            .ok_or_else(|| "Missing encrypted_key in response".to_string())
+            .ok_or_else(|| "Missing encrypted_key in response".to_string().into())
     }
 }
 
-fn extract_error(body: &str) -> String {
-    serde_json::from_str::<serde_json::Value>(body)
-        .ok()
-        .and_then(|v| v.get("error")?.as_str().map(|s| s.to_string()))
-        .unwrap_or_else(|| body.to_string())
+#[derive(Deserialize)]
+struct ErrorBody {
+    error: String,
+    code: Option<ErrorCode>,
+    request_id: Option<String>,
+}
+
+/// Whether a health check answer means the server can't be used right now.
+fn health_status_unreachable(status: reqwest::StatusCode) -> bool {
+    status.is_server_error()
+}
+
+fn extract_error(body: &str) -> ApiError {
+    match serde_json::from_str::<ErrorBody>(body) {
+        Ok(e) => ApiError {
+            code: e.code,
+            message: e.error,
+            request_id: e.request_id,
+        },
+        Err(_) => ApiError::from(body.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::StatusCode;
+
+    #[test]
+    fn test_health_status_unreachable() {
+        for status in [
+            StatusCode::INTERNAL_SERVER_ERROR,
+            StatusCode::BAD_GATEWAY,
+            StatusCode::SERVICE_UNAVAILABLE,
+            StatusCode::GATEWAY_TIMEOUT,
+        ] {
+            assert!(health_status_unreachable(status), "{}", status);
+        }
+        // Servers from before the health route answer 404, but are up
+        for status in [StatusCode::OK, StatusCode::NOT_FOUND, StatusCode::UNAUTHORIZED] {
+            assert!(!health_status_unreachable(status), "{}", status);
+        }
+    }
 }
@@ -0,0 +1,65 @@
+use std::process::Command;
+
+use super::types::DeviceMetadata;
+
+/// Version, OS release and hostname of this install, as shown in the
+/// account's device list.
+pub fn metadata() -> DeviceMetadata {
+    DeviceMetadata {
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        os_version: os_version(),
+        hostname: hostname::get()
+            .ok()
+            .map(|h| h.to_string_lossy().to_string()),
+    }
+}
+
+/// e.g. "macOS 15.1" or "Ubuntu 24.04 LTS".
+pub fn os_version() -> Option<String> {
+    if cfg!(target_os = "macos") {
+        command_output("sw_vers", &["-productVersion"]).map(|v| format!("macOS {}", v))
+    } else if cfg!(target_os = "windows") {
+        // e.g. "Microsoft Windows [Version 10.0.22631.3737]"
+        command_output("cmd", &["/C", "ver"])
+    } else {
+        std::fs::read_to_string("/etc/os-release")
+            .ok()
+            .and_then(|s| pretty_name(&s))
+    }
+}
+
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let mut command = Command::new(program);
+    command.args(args);
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        // CREATE_NO_WINDOW, so no console flashes up
+        command.creation_flags(0x0800_0000);
+    }
+    let output = command.output().ok()?;
+    let text = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !text.is_empty()).then_some(text)
+}
+
+/// `PRETTY_NAME` from an os-release file, e.g. "Ubuntu 24.04 LTS".
+fn pretty_name(os_release: &str) -> Option<String> {
+    os_release
+        .lines()
+        .find_map(|line| line.strip_prefix("PRETTY_NAME="))
+        .map(|v| v.trim().trim_matches(|c| c == '"' || c == '\'').to_string())
+        .filter(|v| !v.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pretty_name() {
+        let os_release = "NAME=\"Ubuntu\"\nPRETTY_NAME=\"Ubuntu 24.04 LTS\"\nID=ubuntu\n";
+        assert_eq!(pretty_name(os_release).as_deref(), Some("Ubuntu 24.04 LTS"));
+        assert_eq!(pretty_name("PRETTY_NAME=Arch Linux").as_deref(), Some("Arch Linux"));
+        assert_eq!(pretty_name("NAME=Ubuntu\nPRETTY_NAME=\"\"\n"), None);
+    }
+}
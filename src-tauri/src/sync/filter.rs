@@ -0,0 +1,134 @@
+use serde::{Deserialize, Serialize};
+
+use crate::clipboard::item::ClipboardItem;
+use crate::clipboard::{language, link, sensitive};
+
+/// Kind of history item, for choosing which ones history sync uploads.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncContentKind {
+    Text,
+    /// Content that is just one http(s) link
+    Link,
+    /// Content that `language::detect` recognises as code
+    Code,
+    /// Images and files, stored as attachments
+    File,
+}
+
+impl SyncContentKind {
+    pub fn of(item: &ClipboardItem) -> Self {
+        if item.content_type != "text/plain" {
+            SyncContentKind::File
+        } else if link::web_url(&item.content).is_some() {
+            SyncContentKind::Link
+        } else if language::detect(&item.content).is_some() {
+            SyncContentKind::Code
+        } else {
+            SyncContentKind::Text
+        }
+    }
+}
+
+/// Which local history items history sync uploads. Items left out stay on
+/// this device; pulling from the server is not affected.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HistorySyncFilter {
+    /// Keep items that look like secrets or card numbers local
+    pub exclude_sensitive: bool,
+    /// Largest item uploaded, in KB of content (or attachment); 0 for no limit
+    pub max_size_kb: u32,
+    pub content_kinds: Vec<SyncContentKind>,
+}
+
+impl Default for HistorySyncFilter {
+    fn default() -> Self {
+        HistorySyncFilter {
+            exclude_sensitive: false,
+            max_size_kb: 0,
+            content_kinds: vec![
+                SyncContentKind::Text,
+                SyncContentKind::Link,
+                SyncContentKind::Code,
+                SyncContentKind::File,
+            ],
+        }
+    }
+}
+
+impl HistorySyncFilter {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.content_kinds.is_empty() {
+            return Err(
+                "Choose at least one content type to sync, or turn history sync off".to_string(),
+            );
+        }
+        Ok(())
+    }
+
+    /// Whether every item is uploaded, so items needn't be looked at.
+    pub fn allows_all(&self) -> bool {
+        *self == HistorySyncFilter::default()
+    }
+
+    /// Whether `item` (decrypted) may be uploaded. `attachment_size` is the
+    /// size of its attachment, for images and files.
+    pub fn allows(&self, item: &ClipboardItem, attachment_size: Option<i64>) -> bool {
+        if !self.content_kinds.contains(&SyncContentKind::of(item)) {
+            return false;
+        }
+        let size = attachment_size.unwrap_or(item.content.len() as i64);
+        if self.max_size_kb > 0 && size > i64::from(self.max_size_kb) * 1024 {
+            return false;
+        }
+        !(self.exclude_sensitive && sensitive::looks_sensitive(&item.content))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(content: &str) -> ClipboardItem {
+        ClipboardItem::new(content.to_string(), "device")
+    }
+
+    #[test]
+    fn test_default_allows_everything() {
+        let filter = HistorySyncFilter::default();
+        assert!(filter.allows_all());
+        assert!(filter.allows(&item("password=hunter2hunter2"), None));
+        assert!(filter.allows(&item(&"x".repeat(1 << 20)), None));
+    }
+
+    #[test]
+    fn test_filters_items() {
+        let filter = HistorySyncFilter {
+            exclude_sensitive: true,
+            max_size_kb: 1,
+            content_kinds: vec![SyncContentKind::Text, SyncContentKind::Link],
+        };
+        assert!(!filter.allows_all());
+        assert!(filter.allows(&item("Meeting moved to 3pm"), None));
+        assert!(filter.allows(&item("https://example.com/docs"), None));
+        assert!(!filter.allows(&item("SELECT id, name FROM users WHERE id = 4"), None));
+        assert!(!filter.allows(&item("password=hunter2hunter2"), None));
+        assert!(!filter.allows(&item(&"word ".repeat(300)), None));
+
+        let mut file = item("photo.png");
+        file.content_type = "application/octet-stream".to_string();
+        assert_eq!(SyncContentKind::of(&file), SyncContentKind::File);
+        assert!(!filter.allows(&file, Some(10)));
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(HistorySyncFilter::default().validate().is_ok());
+        let none = HistorySyncFilter {
+            content_kinds: Vec::new(),
+            ..Default::default()
+        };
+        assert!(none.validate().is_err());
+    }
+}
@@ -2,13 +2,30 @@ use std::sync::Arc;
 
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 
-use crate::storage::database::Database;
+use crate::crypto::blind_index::BlindIndex;
+use crate::crypto::cipher::CryptoEngine;
+use crate::crypto::keychain;
+use crate::storage::database::{Database, SyncedItem};
+use crate::storage::settings::Settings;
 
 use super::api_client::ApiClient;
-use super::types::PushHistoryRequest;
+use super::types::{CreateAttachmentRequest, PushHistoryRequest};
 
-/// Perform initial history sync between local and remote.
-/// Pulls remote items missing locally, pushes local items missing remotely.
+/// Settings key holding the server cursor of the last pulled history item.
+pub const HISTORY_CURSOR_KEY: &str = "history_sync_cursor";
+/// Settings key holding the newest local item already pushed, as
+/// "created_at:id" (created_at in millis).
+pub const HISTORY_PUSH_WATERMARK_KEY: &str = "history_push_watermark";
+
+/// Settings key: "true" to upload blind-index tokens so other clients can search
+/// history on the server.
+pub const SEARCH_INDEX_KEY: &str = "search_index_enabled";
+
+const PAGE_SIZE: i64 = 200;
+
+/// Perform history sync between local and remote.
+/// Pulls remote items newer than the stored cursor, then pushes local items
+/// created since the last push that the server doesn't have yet.
 /// Returns (pulled, pushed) counts.
 pub async fn perform_initial_history_sync(
     api: &ApiClient,
@@ -19,77 +36,311 @@ pub async fn perform_initial_history_sync(
     let mut pulled = 0u32;
     let mut pushed = 0u32;
 
-    // Pull remote history (first 200 items)
-    let remote_items = api.get_history(token, 200, 0).await?;
+    // Pull remote history incrementally; a missing cursor means "from the beginning"
+    let mut cursor = db
+        .call(|db| db.get_setting(HISTORY_CURSOR_KEY))
+        .await
+        .filter(|c| !c.is_empty())
+        .unwrap_or_else(|| "0".to_string());
+    let mut remote_hashes = std::collections::HashSet::new();
 
-    for item in &remote_items {
-        // Check if we already have this item locally (by content_hash)
-        let exists = db
-            .has_item_with_hash(&item.content_hash)
-            .map_err(|e| format!("DB error: {}", e))?;
+    loop {
+        let delta = api.get_history_since(token, &cursor, PAGE_SIZE).await?;
 
-        if !exists {
-            // Decode base64 blob to get the encrypted string
-            let blob_bytes = BASE64
-                .decode(&item.encrypted_blob)
-                .map_err(|e| format!("Base64 decode error: {}", e))?;
-            let enc_str = String::from_utf8(blob_bytes)
-                .map_err(|e| format!("UTF-8 error: {}", e))?;
-
-            let created_at = parse_timestamp(&item.created_at);
-
-            db.insert_synced_item(
-                &item.id.to_string(),
-                &enc_str,
-                &item.content_hash,
-                device_id,
-                created_at,
-            )
+        // The page is stored in one transaction, then attachments are fetched
+        let mut new_items = Vec::new();
+        let mut page_hashes = std::collections::HashSet::new();
+
+        // Check which items we already have locally (by content_hash)
+        let hashes: Vec<String> = delta.items.iter().map(|i| i.content_hash.clone()).collect();
+        let known = db
+            .call(move |db| {
+                hashes
+                    .iter()
+                    .map(|hash| db.has_item_with_hash(hash))
+                    .collect::<Result<Vec<bool>, _>>()
+            })
+            .await
             .map_err(|e| format!("DB error: {}", e))?;
-            pulled += 1;
+
+        for (item, exists) in delta.items.iter().zip(known) {
+            remote_hashes.insert(item.content_hash.clone());
+
+            if !exists && page_hashes.insert(item.content_hash.clone()) {
+                // Decode base64 blob to get the encrypted string
+                let blob_bytes = BASE64
+                    .decode(&item.encrypted_blob)
+                    .map_err(|e| format!("Base64 decode error: {}", e))?;
+                let enc_str = String::from_utf8(blob_bytes)
+                    .map_err(|e| format!("UTF-8 error: {}", e))?;
+
+                new_items.push(SyncedItem {
+                    id: item.id.to_string(),
+                    encrypted_content: enc_str,
+                    content_hash: item.content_hash.clone(),
+                    // Older servers don't say which device pushed it
+                    device_id: item
+                        .device_id
+                        .map_or_else(|| device_id.to_string(), |id| id.to_string()),
+                    created_at: parse_timestamp(&item.created_at),
+                });
+            }
+        }
+
+        let new_ids: Vec<String> = new_items.iter().map(|item| item.id.clone()).collect();
+        if !new_items.is_empty() {
+            pulled += db
+                .call(move |db| db.insert_synced_items_batch(&new_items))
+                .await
+                .map_err(|e| format!("DB error: {}", e))? as u32;
+        }
+
+        // The items are kept even if their attachments can't be fetched right now
+        for item in &delta.items {
+            let Some(attachment_id) = item.attachment_id else {
+                continue;
+            };
+            let id = item.id.to_string();
+            if !new_ids.contains(&id) {
+                continue;
+            }
+            if let Err(e) = pull_attachment(api, token, db, &id, attachment_id).await {
+                tracing::error!("Failed to download attachment for {}: {}", id, e);
+            }
+        }
+
+        // Apply deletions made on other devices
+        let tombstones: Vec<(String, String)> = delta
+            .deleted
+            .iter()
+            .map(|t| (t.id.to_string(), t.content_hash.clone()))
+            .collect();
+        for (_, content_hash) in &tombstones {
+            remote_hashes.remove(content_hash);
+        }
+        // Persist after every page so an interrupted sync resumes where it stopped
+        cursor = delta.next_cursor;
+        let next_cursor = cursor.clone();
+        db.call(move |db| {
+            for (id, content_hash) in &tombstones {
+                db.delete_synced_item(id, content_hash)?;
+            }
+            let _ = db.set_setting(HISTORY_CURSOR_KEY, &next_cursor);
+            Ok::<_, rusqlite::Error>(())
+        })
+        .await
+        .map_err(|e| format!("DB error: {}", e))?;
+
+        if !delta.has_more {
+            break;
         }
     }
 
-    // Push local history items that the server might not have
-    // We collect content hashes from remote for quick lookup
-    let remote_hashes: std::collections::HashSet<&str> =
-        remote_items.iter().map(|r| r.content_hash.as_str()).collect();
+    // Push local history items created since the last successful push
+    let watermark = db
+        .call(|db| db.get_setting(HISTORY_PUSH_WATERMARK_KEY))
+        .await
+        .map(|v| parse_push_watermark(&v))
+        .unwrap_or_default();
 
+    let after = watermark.clone();
     let local_items = db
-        .get_unpromoted_encrypted_items(200)
+        .call(move |db| db.get_unpromoted_encrypted_items(200, (after.0, &after.1)))
+        .await
         .map_err(|e| format!("DB error: {}", e))?;
 
-    for (id, encrypted, content_hash) in &local_items {
-        if remote_hashes.contains(content_hash.as_str()) {
+    // Items come oldest first; stop advancing the watermark at the first failure
+    // so that item is retried on the next sync.
+    let mut new_watermark = watermark.clone();
+    let mut push_failed = false;
+    for (id, encrypted, content_hash, created_at) in &local_items {
+        // Filtered-out items count as handled, so loosening the filter later
+        // doesn't upload old ones
+        if remote_hashes.contains(content_hash) || !sync_filter_allows(db, id).await {
+            if !push_failed {
+                new_watermark = (*created_at, id.clone());
+            }
             continue;
         }
 
         // Base64-encode the encrypted content for the server
         let blob = BASE64.encode(encrypted.as_bytes());
+        let item_id = uuid::Uuid::parse_str(id).unwrap_or_else(|_| uuid::Uuid::new_v4());
+
+        // Images and files go up first; the item itself only references them
+        let attachment_of = id.clone();
+        let attachment = db
+            .call(move |db| db.get_attachment(&attachment_of))
+            .await
+            .map_err(|e| format!("DB error: {}", e))?;
+        let attachment_id = match attachment {
+            Some(data) => match push_attachment(api, token, item_id, &data).await {
+                Ok(()) => Some(item_id),
+                Err(e) => {
+                    tracing::error!("Failed to upload attachment for {}: {}", id, e);
+                    push_failed = true;
+                    continue;
+                }
+            },
+            None => None,
+        };
 
         let req = PushHistoryRequest {
-            id: uuid::Uuid::parse_str(id).unwrap_or_else(|_| uuid::Uuid::new_v4()),
+            id: item_id,
             encrypted_blob: blob,
             content_hash: content_hash.clone(),
+            attachment_id,
+            search_tokens: search_tokens(db, encrypted).await,
         };
 
         if let Err(e) = api.push_history(token, &req).await {
-            eprintln!("[ClipSlot] Failed to push history item {}: {}", id, e);
+            tracing::error!("Failed to push history item {}: {}", id, e);
+            push_failed = true;
         } else {
             pushed += 1;
+            if !push_failed {
+                new_watermark = (*created_at, id.clone());
+            }
         }
     }
 
-    println!(
-        "[ClipSlot] History sync: pulled {}, pushed {}",
+    if new_watermark > watermark {
+        let value = format!("{}:{}", new_watermark.0, new_watermark.1);
+        db.call(move |db| {
+            let _ = db.set_setting(HISTORY_PUSH_WATERMARK_KEY, &value);
+        })
+        .await;
+    }
+
+    tracing::info!(
+        "History sync: pulled {}, pushed {}",
         pulled, pushed
     );
     Ok((pulled, pushed))
 }
 
+/// Whether the history sync filter in settings lets item `id` be uploaded.
+/// Items that can't be read are kept local.
+pub async fn sync_filter_allows(db: &Arc<Database>, id: &str) -> bool {
+    let id = id.to_string();
+    db.call(move |db| {
+        let filter = Settings::load(db).history_sync_filter;
+        if filter.allows_all() {
+            return true;
+        }
+        match db.get_item_details(&id) {
+            Ok(Some(details)) => filter.allows(&details.item, details.attachment_size),
+            _ => false,
+        }
+    })
+    .await
+}
+
+/// Blind-index tokens for an item's encrypted content, or none if the search
+/// index is disabled or the content can't be decrypted.
+pub async fn search_tokens(db: &Arc<Database>, encrypted: &str) -> Vec<String> {
+    if db.call(|db| db.get_setting(SEARCH_INDEX_KEY)).await.as_deref() != Some("true") {
+        return Vec::new();
+    }
+    let master_key = match keychain::get_or_create_master_key() {
+        Ok(key) => key,
+        Err(e) => {
+            tracing::error!("Search index: no master key: {}", e);
+            return Vec::new();
+        }
+    };
+    match CryptoEngine::new(&master_key).decrypt(encrypted) {
+        Ok(plaintext) => BlindIndex::new(&master_key).tokens(&plaintext),
+        Err(e) => {
+            tracing::error!("Search index: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// Upload an item's encrypted attachment in chunks, under the item's own id.
+/// Chunks the server already has (from an interrupted attempt) are skipped.
+pub async fn push_attachment(
+    api: &ApiClient,
+    token: &str,
+    id: uuid::Uuid,
+    data: &[u8],
+) -> Result<(), String> {
+    let req = CreateAttachmentRequest {
+        id,
+        size: data.len() as i64,
+    };
+    let upload = api.create_attachment(token, &req).await?;
+    if upload.complete {
+        return Ok(());
+    }
+
+    let chunk_size = upload.chunk_size as usize;
+    for index in upload.missing_chunks {
+        let start = index as usize * chunk_size;
+        let chunk = data
+            .get(start..(start + chunk_size).min(data.len()))
+            .ok_or_else(|| format!("Server asked for chunk {} past the end", index))?;
+        api.upload_attachment_chunk(token, id, index, chunk.to_vec()).await?;
+    }
+    Ok(api.complete_attachment(token, id).await?)
+}
+
+/// Download an item's encrypted attachment and store it locally.
+pub async fn pull_attachment(
+    api: &ApiClient,
+    token: &str,
+    db: &Arc<Database>,
+    item_id: &str,
+    attachment_id: uuid::Uuid,
+) -> Result<(), String> {
+    let attachment = api.get_attachment(token, attachment_id).await?;
+    let mut data = Vec::with_capacity(attachment.size.max(0) as usize);
+    for index in 0..attachment.chunk_count {
+        let chunk = api.download_attachment_chunk(token, attachment_id, index).await?;
+        data.extend_from_slice(&chunk);
+    }
+    if data.len() as i64 != attachment.size {
+        return Err(format!(
+            "Attachment {} is {} bytes, expected {}",
+            attachment_id,
+            data.len(),
+            attachment.size
+        ));
+    }
+
+    let item_id = item_id.to_string();
+    db.call(move |db| db.save_attachment(&item_id, &data))
+        .await
+        .map_err(|e| format!("DB error: {}", e))
+}
+
 /// Parse an ISO 8601 timestamp string to epoch millis, falling back to 0.
 fn parse_timestamp(ts: &str) -> i64 {
     chrono::DateTime::parse_from_rfc3339(ts)
         .map(|dt| dt.timestamp_millis())
         .unwrap_or(0)
 }
+
+/// Parse a stored push watermark, "created_at:id". A bare created_at, as
+/// stored by older versions, resumes with every item of that millisecond.
+fn parse_push_watermark(value: &str) -> (i64, String) {
+    match value.split_once(':') {
+        Some((created_at, id)) => (created_at.parse().unwrap_or(0), id.to_string()),
+        None => (value.parse().unwrap_or(0), String::new()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_push_watermark() {
+        assert_eq!(parse_push_watermark("1000:abc"), (1000, "abc".to_string()));
+        // Items created in that millisecond are pushed again; the server
+        // skips the ones it already has
+        assert_eq!(parse_push_watermark("1000"), (1000, String::new()));
+        assert_eq!(parse_push_watermark(""), (0, String::new()));
+    }
+}
@@ -1,13 +1,48 @@
+use std::time::Duration;
+
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde::Serialize;
 
 use super::api_client::ApiClient;
 
+/// How long the server keeps a link code.
+pub const LINK_CODE_TTL: Duration = Duration::from_secs(300);
+
+/// Progress of linking a device, for the settings window.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum LinkStatus {
+    /// A code is waiting to be entered on the other device
+    Waiting { code: String, expires_in_secs: u64 },
+    /// The code ran out; a new one has to be generated
+    Expired,
+    /// A code from another device is being redeemed here
+    Redeeming,
+    /// The key was received; ClipSlot has to restart to use it
+    Linked,
+    Failed { error: String },
+}
+
+impl LinkStatus {
+    /// Status of `code`, generated `elapsed` ago.
+    pub fn of_code(code: &str, elapsed: Duration) -> LinkStatus {
+        match LINK_CODE_TTL.checked_sub(elapsed) {
+            Some(left) if !left.is_zero() => LinkStatus::Waiting {
+                code: code.to_string(),
+                // Rounded up, so the countdown doesn't show 0 too early
+                expires_in_secs: left.as_millis().div_ceil(1000) as u64,
+            },
+            _ => LinkStatus::Expired,
+        }
+    }
+}
+
 /// Read the master key from the OS keychain and upload it to the server,
 /// receiving a 6-digit link code in return.
 pub async fn generate_link_code(api: &ApiClient, token: &str) -> Result<String, String> {
     let master_key = crate::crypto::keychain::get_or_create_master_key()?;
     let encoded = BASE64.encode(master_key);
-    api.generate_link_code(token, &encoded).await
+    Ok(api.generate_link_code(token, &encoded).await?)
 }
 
 /// Redeem a 6-digit link code, receive the master key, and store it in the OS keychain
@@ -30,6 +65,29 @@ pub async fn redeem_link_code(api: &ApiClient, token: &str, code: &str) -> Resul
     key.copy_from_slice(&key_bytes);
 
     crate::crypto::keychain::import_master_key(&key)?;
-    println!("[ClipSlot] Master key imported from link code — restart required");
+    tracing::info!("Master key imported from link code — restart required");
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_code_counts_down_then_expires() {
+        assert_eq!(
+            LinkStatus::of_code("123456", Duration::from_millis(1500)),
+            LinkStatus::Waiting {
+                code: "123456".to_string(),
+                expires_in_secs: 299
+            }
+        );
+        assert_eq!(LinkStatus::of_code("123456", LINK_CODE_TTL), LinkStatus::Expired);
+
+        let json = serde_json::to_value(LinkStatus::Failed {
+            error: "Code has expired".to_string(),
+        })
+        .unwrap();
+        assert_eq!(json["state"], "failed");
+    }
+}
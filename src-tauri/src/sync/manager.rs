@@ -1,18 +1,57 @@
-use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::time::Duration;
 
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
-use tokio::sync::RwLock;
+use tokio::sync::{broadcast, oneshot, RwLock};
 use uuid::Uuid;
 
-use crate::storage::database::Database;
+use crate::storage::database::{Database, SyncedItem};
+use crate::telemetry;
 
 use super::api_client::ApiClient;
 use super::offline_queue::OfflineQueue;
+use super::team_sync;
 use super::types::*;
 use super::ws_client::WsClient;
 
+/// How long to wait for a server ack before resending a message.
+const ACK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long to wait for another device to answer a clipboard pull.
+const PULL_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// How often to check whether the server is reachable, or back while
+/// working offline.
+const PROBE_INTERVAL: Duration = Duration::from_secs(10);
+/// How long a health check may take before the server counts as unreachable.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Answers to clipboard pulls still waiting, by request id.
+type PendingPulls = Arc<Mutex<HashMap<Uuid, oneshot::Sender<Option<String>>>>>;
+
+/// Content another of the account's devices sent straight to this one.
+#[derive(Debug, Clone)]
+pub struct DeviceDelivery {
+    pub text: String,
+    pub from_device: Uuid,
+}
+
+/// Another of the account's devices asking for this one's clipboard.
+#[derive(Debug, Clone)]
+pub struct ClipboardRequest {
+    pub from_device: Uuid,
+    pub request_id: Uuid,
+}
+
+/// Another of the account's devices pausing or resuming capture everywhere.
+#[derive(Debug, Clone)]
+pub struct MonitoringChange {
+    pub paused: bool,
+    pub changed_by: Uuid,
+}
+
 struct AuthState {
     token: String,
     user_id: Uuid,
@@ -25,10 +64,17 @@ pub struct SyncManager {
     db: Arc<Database>,
     auth: RwLock<Option<AuthState>>,
     ws: RwLock<Option<WsClient>>,
-    status: RwLock<SyncStatus>,
-    offline_queue: OfflineQueue,
+    /// Current status; subscribers are told about every change
+    status: tokio::sync::watch::Sender<SyncStatus>,
+    offline_queue: Arc<OfflineQueue>,
+    /// Highest broadcast seq received; 0 until the first broadcast arrives
+    last_seq: Arc<AtomicU64>,
     ws_alive: Arc<tokio::sync::watch::Sender<bool>>,
     reconnect_active: AtomicBool,
+    deliveries: broadcast::Sender<DeviceDelivery>,
+    clipboard_requests: broadcast::Sender<ClipboardRequest>,
+    monitoring_changes: broadcast::Sender<MonitoringChange>,
+    pending_pulls: PendingPulls,
 }
 
 impl SyncManager {
@@ -43,10 +89,15 @@ impl SyncManager {
             db,
             auth: RwLock::new(None),
             ws: RwLock::new(None),
-            status: RwLock::new(SyncStatus::Disconnected),
-            offline_queue: OfflineQueue::new(),
+            status: tokio::sync::watch::Sender::new(SyncStatus::Disconnected),
+            offline_queue: Arc::new(OfflineQueue::new()),
+            last_seq: Arc::new(AtomicU64::new(0)),
             ws_alive: Arc::new(ws_alive_tx),
             reconnect_active: AtomicBool::new(false),
+            deliveries: broadcast::channel(16).0,
+            clipboard_requests: broadcast::channel(16).0,
+            monitoring_changes: broadcast::channel(16).0,
+            pending_pulls: Arc::default(),
         };
 
         // Try to restore auth from persisted settings
@@ -78,27 +129,40 @@ impl SyncManager {
                     email: email.clone(),
                 };
                 *self.auth.blocking_write() = Some(auth);
-                println!("[ClipSlot] Restored auth session for {}", email);
+                tracing::info!("Restored auth session for {}", email);
             }
         }
     }
 
-    fn persist_auth(&self, state: &AuthState) {
-        let _ = self.db.set_setting("auth_token", &state.token);
-        let _ = self
-            .db
-            .set_setting("auth_user_id", &state.user_id.to_string());
-        let _ = self
-            .db
-            .set_setting("auth_device_id", &state.device_id.to_string());
-        let _ = self.db.set_setting("auth_email", &state.email);
+    async fn persist_auth(&self, state: &AuthState) {
+        let settings = [
+            ("auth_token", state.token.clone()),
+            ("auth_user_id", state.user_id.to_string()),
+            ("auth_device_id", state.device_id.to_string()),
+            ("auth_email", state.email.clone()),
+        ];
+        self.db
+            .call(move |db| {
+                for (key, value) in &settings {
+                    let _ = db.set_setting(key, value);
+                }
+            })
+            .await;
     }
 
-    fn clear_auth_settings(&self) {
-        let _ = self.db.set_setting("auth_token", "");
-        let _ = self.db.set_setting("auth_user_id", "");
-        let _ = self.db.set_setting("auth_device_id", "");
-        let _ = self.db.set_setting("auth_email", "");
+    async fn clear_auth_settings(&self) {
+        self.db
+            .call(|db| {
+                let _ = db.set_setting("auth_token", "");
+                let _ = db.set_setting("auth_user_id", "");
+                let _ = db.set_setting("auth_device_id", "");
+                let _ = db.set_setting("auth_email", "");
+                // Sync cursors belong to the account that was signed in
+                let _ = db.set_setting(super::history_sync::HISTORY_CURSOR_KEY, "");
+                let _ = db.set_setting(super::history_sync::HISTORY_PUSH_WATERMARK_KEY, "");
+            })
+            .await;
+        self.last_seq.store(0, Ordering::SeqCst);
     }
 
     fn get_device_name() -> String {
@@ -127,6 +191,7 @@ impl SyncManager {
                 &auth_resp.token,
                 &Self::get_device_name(),
                 &Self::get_device_type(),
+                &super::device_info::metadata(),
             )
             .await?;
 
@@ -137,7 +202,7 @@ impl SyncManager {
             email: email.to_string(),
         };
 
-        self.persist_auth(&state);
+        self.persist_auth(&state).await;
         *self.auth.write().await = Some(state);
 
         Ok(self.build_sync_state().await)
@@ -153,6 +218,7 @@ impl SyncManager {
                 &auth_resp.token,
                 &Self::get_device_name(),
                 &Self::get_device_type(),
+                &super::device_info::metadata(),
             )
             .await?;
 
@@ -163,7 +229,7 @@ impl SyncManager {
             email: email.to_string(),
         };
 
-        self.persist_auth(&state);
+        self.persist_auth(&state).await;
         *self.auth.write().await = Some(state);
 
         Ok(self.build_sync_state().await)
@@ -174,14 +240,15 @@ impl SyncManager {
         if let Some(ws) = self.ws.write().await.take() {
             ws.disconnect().await;
         }
-        *self.status.write().await = SyncStatus::Disconnected;
-        self.clear_auth_settings();
+        self.set_status(SyncStatus::Disconnected);
+        self.clear_auth_settings().await;
         *self.auth.write().await = None;
-        println!("[ClipSlot] Logged out");
+        tracing::info!("Logged out");
         Ok(())
     }
 
-    /// Check if an error indicates an expired/invalid token.
+    /// Check if a WebSocket connect error indicates an expired/invalid token.
+    /// API calls report this as `ApiError::is_unauthorized` instead.
     fn is_auth_error(err: &str) -> bool {
         err.contains("401") || err.contains("Unauthorized")
             || err.contains("expired") || err.contains("Invalid or expired token")
@@ -189,12 +256,12 @@ impl SyncManager {
 
     /// Force-logout when token is expired so the UI shows the login screen.
     pub async fn force_logout_expired(&self) {
-        clog!("Token expired — auto-logging out");
+        tracing::info!("Token expired — auto-logging out");
         if let Some(ws) = self.ws.write().await.take() {
             ws.disconnect().await;
         }
-        *self.status.write().await = SyncStatus::Disconnected;
-        self.clear_auth_settings();
+        self.set_status(SyncStatus::Disconnected);
+        self.clear_auth_settings().await;
         *self.auth.write().await = None;
     }
 
@@ -206,23 +273,23 @@ impl SyncManager {
         let auth = self.auth.read().await;
         let auth = auth.as_ref().ok_or("Not logged in")?;
         let api = self.api.read().await;
-        api.list_devices(&auth.token).await
+        Ok(api.list_devices(&auth.token).await?)
     }
 
     pub async fn start_sync(&self) -> Result<String, String> {
-        clog!("start_sync: beginning...");
+        tracing::info!("start_sync: beginning...");
         let auth_guard = self.auth.read().await;
         let auth = auth_guard.as_ref().ok_or("Not logged in")?;
         let token = auth.token.clone();
         let device_id = auth.device_id.to_string();
-        clog!("start_sync: device_id={}", device_id);
+        tracing::info!("start_sync: device_id={}", device_id);
         drop(auth_guard);
         let api = self.api.read().await;
-        clog!("start_sync: API base_url={}", api.base_url());
+        tracing::info!("start_sync: API base_url={}", api.base_url());
 
-        *self.status.write().await = SyncStatus::Syncing;
+        let previous_status = self.status.send_replace(SyncStatus::Syncing);
 
-        clog!("start_sync: performing slot sync...");
+        tracing::info!("start_sync: performing slot sync...");
         let slot_synced = match super::slot_sync::perform_full_slot_sync(
             &api,
             &token,
@@ -233,22 +300,26 @@ impl SyncManager {
         {
             Ok(n) => n,
             Err(e) => {
-                if Self::is_auth_error(&e) {
+                telemetry::record(telemetry::Event::SyncError);
+                if e.is_unauthorized() {
                     drop(api);
                     self.force_logout_expired().await;
+                } else {
+                    self.set_status(previous_status);
                 }
-                return Err(e);
+                return Err(e.into());
             }
         };
-        clog!("start_sync: slot sync done, synced {} slots", slot_synced);
+        tracing::info!("start_sync: slot sync done, synced {} slots", slot_synced);
+
+        // Keeps the version shown in other devices' lists current after updates
+        if let Err(e) = api.update_device(&token, &super::device_info::metadata()).await {
+            tracing::info!("start_sync: device metadata update failed: {}", e);
+        }
 
         // History sync (opt-in)
-        let history_sync_enabled = self
-            .db
-            .get_setting("history_sync_enabled")
-            .map(|v| v == "true")
-            .unwrap_or(false);
-        clog!("start_sync: history_sync_enabled={}", history_sync_enabled);
+        let history_sync_enabled = history_sync_enabled(&self.db).await;
+        tracing::info!("start_sync: history_sync_enabled={}", history_sync_enabled);
 
         let mut history_msg = String::new();
         if history_sync_enabled {
@@ -262,15 +333,16 @@ impl SyncManager {
             {
                 Ok((pulled, pushed)) => {
                     history_msg = format!(", history: pulled {}, pushed {}", pulled, pushed);
-                    clog!("start_sync: history pulled={}, pushed={}", pulled, pushed);
+                    tracing::info!("start_sync: history pulled={}, pushed={}", pulled, pushed);
                 }
                 Err(e) => {
-                    clog!("ERROR: History sync failed: {}", e);
+                    telemetry::record(telemetry::Event::SyncError);
+                    tracing::error!("History sync failed: {}", e);
                 }
             }
         }
 
-        *self.status.write().await = SyncStatus::Connected;
+        self.set_status(SyncStatus::Connected);
 
         Ok(format!("Synced {} slots{}", slot_synced, history_msg))
     }
@@ -278,11 +350,11 @@ impl SyncManager {
     // ── WebSocket ───────────────────────────────────────────────────────
 
     pub async fn connect_ws(&self) -> Result<(), String> {
-        clog!("connect_ws: starting...");
+        tracing::info!("connect_ws: starting...");
 
         // Disconnect any existing WS connection first
         if let Some(old_ws) = self.ws.write().await.take() {
-            clog!("connect_ws: disconnecting old WS connection");
+            tracing::info!("connect_ws: disconnecting old WS connection");
             old_ws.disconnect().await;
         }
 
@@ -290,15 +362,26 @@ impl SyncManager {
         let auth = auth_guard.as_ref().ok_or("Not logged in")?;
         let api = self.api.read().await;
 
-        let ws_url = api.ws_url(&auth.token);
-        clog!("connect_ws: URL={}", ws_url.split('?').next().unwrap_or(&ws_url));
+        // Resume from the last broadcast seen so missed updates are replayed
+        let resume_from = match self.last_seq.load(Ordering::SeqCst) {
+            0 => None,
+            seq => Some(seq),
+        };
+        let ws_url = api.ws_url(&auth.token, resume_from);
+        tracing::info!(
+            "connect_ws: URL={} resume_from={:?}",
+            ws_url.split('?').next().unwrap_or(&ws_url),
+            resume_from
+        );
+        let resync_api = api.clone();
+        let resync_token = auth.token.clone();
         drop(api);
         drop(auth_guard);
 
-        *self.status.write().await = SyncStatus::Connecting;
+        self.set_status(SyncStatus::Connecting);
 
-        let client = WsClient::connect(&ws_url).await?;
-        clog!("connect_ws: WebSocket connected successfully");
+        let client = WsClient::connect(&ws_url, self.last_seq.clone()).await?;
+        tracing::info!("connect_ws: WebSocket connected successfully");
 
         // Spawn a task to handle incoming WS messages
         let mut rx = client.subscribe();
@@ -312,10 +395,25 @@ impl SyncManager {
             .unwrap_or_default();
 
         let ws_alive = self.ws_alive.clone();
+        let offline_queue = self.offline_queue.clone();
+        let deliveries = self.deliveries.clone();
+        let clipboard_requests = self.clipboard_requests.clone();
+        let monitoring_changes = self.monitoring_changes.clone();
+        let pending_pulls = self.pending_pulls.clone();
         tokio::spawn(async move {
-            clog!("WS message handler started, listening for broadcasts...");
-            while let Ok(msg) = rx.recv().await {
-                clog!("WS handler: received broadcast message");
+            tracing::info!("WS message handler started, listening for broadcasts...");
+            // A message read ahead while batching, handled before waiting again
+            let mut next = None;
+            let mut ended = false;
+            while !ended {
+                let msg = match next.take() {
+                    Some(msg) => msg,
+                    None => match rx.recv().await {
+                        Ok(msg) => msg,
+                        Err(_) => break,
+                    },
+                };
+                tracing::debug!("WS handler: received broadcast message");
                 match msg {
                     WsMessage::SlotUpdated {
                         slot_number,
@@ -323,27 +421,63 @@ impl SyncManager {
                         timestamp,
                         ..
                     } => {
-                        clog!("WS handler: SlotUpdated slot={}", slot_number);
+                        tracing::info!("WS handler: SlotUpdated slot={}", slot_number);
                         if let Ok(blob_bytes) = BASE64.decode(&encrypted_blob) {
                             if let Ok(enc_str) = String::from_utf8(blob_bytes) {
-                                if let Err(e) = db.save_encrypted_to_slot(
-                                    slot_number as u32,
-                                    &enc_str,
-                                    timestamp,
-                                    &device_id_str,
-                                ) {
-                                    clog!(
-                                        "ERROR: Failed to save synced slot {}: {}",
+                                let device_id = device_id_str.clone();
+                                let saved = db
+                                    .call(move |db| {
+                                        db.save_encrypted_to_slot(
+                                            slot_number as u32,
+                                            &enc_str,
+                                            timestamp,
+                                            &device_id,
+                                        )
+                                    })
+                                    .await;
+                                if let Err(e) = saved {
+                                    tracing::error!(
+                                        "Failed to save synced slot {}: {}",
                                         slot_number, e
                                     );
                                 } else {
-                                    clog!("Slot {} updated from remote", slot_number);
+                                    tracing::info!("Slot {} updated from remote", slot_number);
                                 }
                             } else {
-                                clog!("ERROR: SlotUpdated blob is not valid UTF-8");
+                                tracing::error!("SlotUpdated blob is not valid UTF-8");
                             }
                         } else {
-                            clog!("ERROR: SlotUpdated blob is not valid base64");
+                            tracing::error!("SlotUpdated blob is not valid base64");
+                        }
+                    }
+                    WsMessage::SlotSettingsUpdated { slot_count } => {
+                        tracing::info!("WS handler: SlotSettingsUpdated slot_count={}", slot_count);
+                        let (grew, applied) = db
+                            .call(move |db| {
+                                let grew = slot_count > db.get_slot_count() as i32;
+                                (grew, super::slot_sync::apply_slot_count(db, slot_count))
+                            })
+                            .await;
+                        if let Err(e) = applied {
+                            tracing::error!("Failed to apply slot count: {}", e);
+                        } else if grew {
+                            // Newly enabled slots may already hold content on the server
+                            let api = resync_api.clone();
+                            let token = resync_token.clone();
+                            let db = db.clone();
+                            let device_id = device_id_str.clone();
+                            tokio::spawn(async move {
+                                if let Err(e) = super::slot_sync::perform_full_slot_sync(
+                                    &api, &token, &db, &device_id,
+                                )
+                                .await
+                                {
+                                    tracing::error!(
+                                        "Slot sync after slot count change failed: {}",
+                                        e
+                                    );
+                                }
+                            });
                         }
                     }
                     WsMessage::HistoryNew {
@@ -351,41 +485,237 @@ impl SyncManager {
                         encrypted_blob,
                         content_hash,
                         device_id,
+                        attachment_id,
                     } => {
-                        clog!("WS handler: HistoryNew id={}", id);
-                        if let Ok(blob_bytes) = BASE64.decode(&encrypted_blob) {
-                            if let Ok(enc_str) = String::from_utf8(blob_bytes) {
-                                let now = chrono::Utc::now().timestamp_millis();
-                                if let Err(e) = db.insert_synced_item(
-                                    &id.to_string(),
-                                    &enc_str,
-                                    &content_hash,
-                                    &device_id.to_string(),
-                                    now,
-                                ) {
-                                    clog!("ERROR: Failed to save synced history item: {}", e);
-                                } else {
-                                    clog!("History item received from remote");
+                        // A resumed connection replays its backlog all at once;
+                        // consecutive history items are stored in one transaction
+                        let mut batch =
+                            vec![(id, encrypted_blob, content_hash, device_id, attachment_id)];
+                        loop {
+                            match rx.try_recv() {
+                                Ok(WsMessage::HistoryNew {
+                                    id,
+                                    encrypted_blob,
+                                    content_hash,
+                                    device_id,
+                                    attachment_id,
+                                }) => batch.push((
+                                    id,
+                                    encrypted_blob,
+                                    content_hash,
+                                    device_id,
+                                    attachment_id,
+                                )),
+                                Ok(other) => {
+                                    next = Some(other);
+                                    break;
+                                }
+                                Err(broadcast::error::TryRecvError::Empty) => break,
+                                Err(_) => {
+                                    ended = true;
+                                    break;
                                 }
                             }
                         }
+                        tracing::info!("WS handler: HistoryNew x{}", batch.len());
+
+                        let now = chrono::Utc::now().timestamp_millis();
+                        let mut items = Vec::with_capacity(batch.len());
+                        let mut attachments = Vec::new();
+                        for (id, blob, content_hash, device_id, attachment_id) in batch {
+                            let Some(enc_str) = BASE64
+                                .decode(&blob)
+                                .ok()
+                                .and_then(|bytes| String::from_utf8(bytes).ok())
+                            else {
+                                tracing::error!("HistoryNew blob for {} is not valid", id);
+                                continue;
+                            };
+                            if let Some(attachment_id) = attachment_id {
+                                attachments.push((id.to_string(), attachment_id));
+                            }
+                            items.push(SyncedItem {
+                                id: id.to_string(),
+                                encrypted_content: enc_str,
+                                content_hash,
+                                device_id: device_id.to_string(),
+                                created_at: now,
+                            });
+                        }
+
+                        match db.call(move |db| db.insert_synced_items_batch(&items)).await {
+                            Err(e) => tracing::error!("Failed to save synced history items: {}", e),
+                            Ok(n) => {
+                                tracing::info!("{} history item(s) received from remote", n);
+                                for (item_id, attachment_id) in attachments {
+                                    let api = resync_api.clone();
+                                    let token = resync_token.clone();
+                                    let db = db.clone();
+                                    tokio::spawn(async move {
+                                        let result = super::history_sync::pull_attachment(
+                                            &api,
+                                            &token,
+                                            &db,
+                                            &item_id,
+                                            attachment_id,
+                                        )
+                                        .await;
+                                        if let Err(e) = result {
+                                            tracing::error!("Attachment download failed: {}", e);
+                                        }
+                                    });
+                                }
+                            }
+                        }
+                    }
+                    WsMessage::HistoryDeleted { id, content_hash, .. } => {
+                        tracing::info!("WS handler: HistoryDeleted id={}", id);
+                        let deleted = db
+                            .call(move |db| db.delete_synced_item(&id.to_string(), &content_hash))
+                            .await;
+                        match deleted {
+                            Ok(n) => {
+                                tracing::info!("Removed {} history item(s) deleted remotely", n)
+                            }
+                            Err(e) => tracing::error!("Failed to apply remote deletion: {}", e),
+                        }
                     }
-                    WsMessage::Error { message } => {
-                        clog!("WS handler: server error: {}", message);
+                    WsMessage::TeamSlotUpdated {
+                        team_id,
+                        slot_number,
+                        encrypted_blob,
+                        timestamp,
+                        ..
+                    } => {
+                        tracing::info!(
+                            "WS handler: TeamSlotUpdated team={} slot={}",
+                            team_id,
+                            slot_number
+                        );
+                        let api = resync_api.clone();
+                        let token = resync_token.clone();
+                        let db = db.clone();
+                        let device_id = device_id_str.clone();
+                        tokio::spawn(async move {
+                            match team_sync::apply_team_slot(
+                                &api,
+                                &token,
+                                &db,
+                                &device_id,
+                                team_id,
+                                slot_number,
+                                &encrypted_blob,
+                                timestamp,
+                            )
+                            .await
+                            {
+                                Ok(()) => {
+                                    tracing::info!("Team slot {} updated from remote", slot_number)
+                                }
+                                Err(e) => tracing::error!(
+                                    "Failed to apply team slot {}: {}",
+                                    slot_number, e
+                                ),
+                            }
+                        });
+                    }
+                    WsMessage::SentToDevice {
+                        encrypted_blob,
+                        from_device,
+                        ..
+                    } => {
+                        tracing::info!("WS handler: SentToDevice from={}", from_device);
+                        match decrypt_blob(&encrypted_blob) {
+                            Ok(text) => {
+                                let _ = deliveries.send(DeviceDelivery { text, from_device });
+                            }
+                            Err(e) => {
+                                tracing::error!("Can't read content sent to this device: {}", e)
+                            }
+                        }
+                    }
+                    WsMessage::ClipboardRequested {
+                        from_device,
+                        request_id,
+                    } => {
+                        tracing::info!("WS handler: ClipboardRequested from={}", from_device);
+                        let request = ClipboardRequest {
+                            from_device,
+                            request_id,
+                        };
+                        if clipboard_requests.send(request).is_err() {
+                            tracing::warn!("Nothing is answering clipboard requests");
+                        }
+                    }
+                    WsMessage::ClipboardResponded {
+                        from_device,
+                        request_id,
+                        encrypted_blob,
+                    } => {
+                        tracing::info!("WS handler: ClipboardResponded from={}", from_device);
+                        // Answers that come after the pull gave up are dropped
+                        let Some(reply) = pending_pulls.lock().unwrap().remove(&request_id) else {
+                            continue;
+                        };
+                        let text = match encrypted_blob.as_deref().map(decrypt_blob) {
+                            Some(Ok(text)) => Some(text),
+                            Some(Err(e)) => {
+                                tracing::error!("Can't read clipboard from {}: {}", from_device, e);
+                                None
+                            }
+                            None => None,
+                        };
+                        let _ = reply.send(text);
+                    }
+                    WsMessage::MonitoringChanged { paused, changed_by } => {
+                        tracing::info!(
+                            "WS handler: MonitoringChanged paused={} by={}",
+                            paused,
+                            changed_by
+                        );
+                        let _ = monitoring_changes.send(MonitoringChange { paused, changed_by });
+                    }
+                    WsMessage::Ack { msg_id, ok, error } => {
+                        offline_queue.ack(&msg_id);
+                        if !ok {
+                            tracing::error!(
+                                "Server rejected message {}: {}",
+                                msg_id,
+                                error.unwrap_or_default()
+                            );
+                        }
+                    }
+                    WsMessage::ResyncRequired { reason } => {
+                        tracing::info!("WS handler: resync required: {}", reason);
+                        let api = resync_api.clone();
+                        let token = resync_token.clone();
+                        let db = db.clone();
+                        let device_id = device_id_str.clone();
+                        tokio::spawn(async move {
+                            resync(&api, &token, &db, &device_id).await;
+                        });
+                    }
+                    WsMessage::Error { code, message } => {
+                        tracing::info!("WS handler: server error ({}): {}", code, message);
+                    }
+                    WsMessage::Hello { .. } => {
+                        // Capabilities are recorded by the client itself
                     }
                     _ => {
-                        clog!("WS handler: ignoring message type");
+                        tracing::info!("WS handler: ignoring message type");
                     }
                 }
             }
+            // Anything still unacked is resent after reconnecting
+            offline_queue.requeue_in_flight();
             ws_alive.send_replace(false);
-            clog!("WS message handler ended (broadcast channel closed)");
+            tracing::info!("WS message handler ended (broadcast channel closed)");
         });
 
         *self.ws.write().await = Some(client);
         self.ws_alive.send_replace(true);
-        *self.status.write().await = SyncStatus::Connected;
-        println!("[ClipSlot] WebSocket connected and listening");
+        self.set_status(SyncStatus::Connected);
+        tracing::info!("WebSocket connected and listening");
 
         // Flush any messages queued while offline
         self.flush_offline_queue().await;
@@ -396,16 +726,16 @@ impl SyncManager {
     /// Notify the server of a local slot change via WebSocket.
     /// If WS is disconnected, queues the message for later.
     pub async fn notify_slot_changed(&self, slot_number: u32) {
-        clog!("notify_slot_changed: slot {}", slot_number);
+        tracing::info!("notify_slot_changed: slot {}", slot_number);
         let auth = self.auth.read().await;
         if auth.is_none() {
-            clog!("notify_slot_changed: no auth, skipping");
+            tracing::info!("notify_slot_changed: no auth, skipping");
             return;
         }
         drop(auth);
 
         // Get the raw encrypted content for this slot
-        let (encrypted, _) = match self.db.get_slot_raw(slot_number) {
+        let (encrypted, _) = match self.db.call(move |db| db.get_slot_raw(slot_number)).await {
             Ok(r) => r,
             Err(_) => return,
         };
@@ -423,6 +753,7 @@ impl SyncManager {
             slot_number: slot_number as i32,
             encrypted_blob: blob,
             timestamp,
+            msg_id: Some(Uuid::new_v4()),
         };
 
         self.send_or_queue(msg).await;
@@ -437,13 +768,9 @@ impl SyncManager {
         }
         drop(auth);
 
-        let history_enabled = self
-            .db
-            .get_setting("history_sync_enabled")
-            .map(|v| v == "true")
-            .unwrap_or(false);
-
-        if !history_enabled {
+        if !history_sync_enabled(&self.db).await
+            || !super::history_sync::sync_filter_allows(&self.db, id).await
+        {
             return;
         }
 
@@ -453,22 +780,187 @@ impl SyncManager {
             id: uuid::Uuid::parse_str(id).unwrap_or_else(|_| uuid::Uuid::new_v4()),
             encrypted_blob: blob,
             content_hash: content_hash.to_string(),
+            attachment_id: None,
+            search_tokens: super::history_sync::search_tokens(&self.db, encrypted).await,
+            msg_id: Some(Uuid::new_v4()),
         };
 
         self.send_or_queue(msg).await;
     }
 
+    /// Send `text` straight to another of the account's devices, which copies
+    /// it and shows a notification. Needs a live connection: the point is to
+    /// get it there now.
+    pub async fn send_to_device(&self, target_device: Uuid, text: &str) -> Result<(), String> {
+        if self.auth.read().await.is_none() {
+            return Err("Not logged in".to_string());
+        }
+        let msg = WsMessage::SendToDevice {
+            target_device,
+            encrypted_blob: encrypt_blob(text)?,
+            msg_id: Some(Uuid::new_v4()),
+        };
+        self.send_live(msg, "sending to a device").await
+    }
+
+    /// Content sent to this device by the account's other devices.
+    pub fn subscribe_deliveries(&self) -> broadcast::Receiver<DeviceDelivery> {
+        self.deliveries.subscribe()
+    }
+
+    /// Fetch the current clipboard of another of the account's devices.
+    /// `None` if it had nothing to share; an error if it didn't answer,
+    /// e.g. because it is offline.
+    pub async fn pull_clipboard(&self, target_device: Uuid) -> Result<Option<String>, String> {
+        if self.auth.read().await.is_none() {
+            return Err("Not logged in".to_string());
+        }
+        let request_id = Uuid::new_v4();
+        let msg = WsMessage::ClipboardRequest {
+            target_device,
+            request_id,
+            msg_id: Some(Uuid::new_v4()),
+        };
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.pending_pulls.lock().unwrap().insert(request_id, reply_tx);
+
+        let result = match self.send_live(msg, "fetching another device's clipboard").await {
+            Ok(()) => tokio::time::timeout(PULL_TIMEOUT, reply_rx)
+                .await
+                .map_err(|_| "The device didn't answer; it may be offline".to_string())
+                .and_then(|reply| reply.map_err(|_| "Sync stopped".to_string())),
+            Err(e) => Err(e),
+        };
+        self.pending_pulls.lock().unwrap().remove(&request_id);
+        result
+    }
+
+    /// Requests from the account's other devices for this one's clipboard.
+    /// Each must be answered with `answer_clipboard_request`.
+    pub fn subscribe_clipboard_requests(&self) -> broadcast::Receiver<ClipboardRequest> {
+        self.clipboard_requests.subscribe()
+    }
+
+    /// Send this device's clipboard, or `None` to decline, to the device that
+    /// asked for it.
+    pub async fn answer_clipboard_request(
+        &self,
+        request: ClipboardRequest,
+        text: Option<&str>,
+    ) -> Result<(), String> {
+        let msg = WsMessage::ClipboardResponse {
+            target_device: request.from_device,
+            request_id: request.request_id,
+            encrypted_blob: text.map(encrypt_blob).transpose()?,
+            msg_id: Some(Uuid::new_v4()),
+        };
+        self.send_live(msg, "fetching another device's clipboard").await
+    }
+
+    /// Pause or resume capture on all of the account's other devices, e.g.
+    /// before sharing the screen. Needs a live connection, so it isn't
+    /// applied later on by surprise.
+    pub async fn set_monitoring_everywhere(&self, paused: bool) -> Result<(), String> {
+        if self.auth.read().await.is_none() {
+            return Err("Not logged in".to_string());
+        }
+        let msg = WsMessage::SetMonitoring {
+            paused,
+            msg_id: Some(Uuid::new_v4()),
+        };
+        self.send_live(msg, "pausing other devices").await
+    }
+
+    /// Other devices pausing or resuming capture everywhere.
+    pub fn subscribe_monitoring_changes(&self) -> broadcast::Receiver<MonitoringChange> {
+        self.monitoring_changes.subscribe()
+    }
+
+    /// Upload `text` as a one-time share link that anyone can open in a
+    /// browser. It is encrypted with a fresh key that only the link's
+    /// fragment holds, so neither the server nor the account key can read it.
+    pub async fn create_share_link(
+        &self,
+        text: &str,
+        expires_in_secs: Option<i64>,
+    ) -> Result<ShareLink, String> {
+        use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+        use rand::RngCore;
+
+        let token = {
+            let auth = self.auth.read().await;
+            auth.as_ref().ok_or("Not logged in")?.token.clone()
+        };
+        let mut key = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut key);
+        let encrypted = crate::crypto::cipher::CryptoEngine::new(&key).encrypt(text)?;
+        let req = CreateShareRequest {
+            encrypted_blob: BASE64.encode(encrypted.as_bytes()),
+            expires_in_secs,
+        };
+
+        let api = self.api.read().await;
+        let share = api.create_share(&token, &req).await?;
+        Ok(ShareLink {
+            url: format!("{}{}#{}", api.base_url(), share.path, URL_SAFE_NO_PAD.encode(key)),
+            expires_at: share.expires_at,
+        })
+    }
+
+    /// Send a message meant for another device right now, rather than
+    /// queueing it for whenever the connection comes back.
+    async fn send_live(&self, msg: WsMessage, feature: &str) -> Result<(), String> {
+        let ws = self.ws.read().await;
+        let client = ws.as_ref().ok_or("Not connected to the sync server")?;
+        if !client.server_accepts(&msg) {
+            return Err(format!("The sync server doesn't support {}", feature));
+        }
+        drop(ws);
+        self.send_or_queue(msg).await;
+        Ok(())
+    }
+
+    /// Propagate a local history deletion so other devices remove the item too.
+    /// If WS is disconnected, queues the message for later.
+    pub async fn notify_history_deleted(&self, id: &str) {
+        if self.auth.read().await.is_none() {
+            return;
+        }
+
+        if !history_sync_enabled(&self.db).await {
+            return;
+        }
+
+        // Items that never came from or went to the server have no remote counterpart
+        let Ok(id) = uuid::Uuid::parse_str(id) else {
+            return;
+        };
+
+        self.send_or_queue(WsMessage::HistoryDelete {
+            id,
+            msg_id: Some(Uuid::new_v4()),
+        })
+        .await;
+    }
+
     /// Send a message via WS if connected, otherwise enqueue for later.
     async fn send_or_queue(&self, msg: WsMessage) {
         let ws = self.ws.read().await;
         if let Some(client) = ws.as_ref() {
-            clog!("send_or_queue: sending via WS");
-            if let Err(e) = client.send(&msg).await {
-                clog!("ERROR: WS send failed, queuing: {}", e);
-                self.offline_queue.enqueue(msg);
+            if !client.server_accepts(&msg) {
+                tracing::info!("send_or_queue: server doesn't support {}, dropping", msg.kind());
+                return;
+            }
+            tracing::info!("send_or_queue: sending via WS");
+            match client.send(&msg).await {
+                Ok(()) => self.offline_queue.track_sent(&msg),
+                Err(e) => {
+                    tracing::error!("WS send failed, queuing: {}", e);
+                    self.offline_queue.enqueue(msg);
+                }
             }
         } else {
-            clog!("send_or_queue: WS not connected, queuing message");
+            tracing::info!("send_or_queue: WS not connected, queuing message");
             self.offline_queue.enqueue(msg);
         }
     }
@@ -480,16 +972,52 @@ impl SyncManager {
             return;
         }
 
-        println!("[ClipSlot] Flushing {} queued messages", messages.len());
+        tracing::info!("Flushing {} queued messages", messages.len());
         let ws = self.ws.read().await;
         if let Some(client) = ws.as_ref() {
-            for msg in messages {
+            let mut remaining = messages.into_iter();
+            for msg in remaining.by_ref() {
+                if !client.server_accepts(&msg) {
+                    tracing::info!("Dropping queued {}: not supported by server", msg.kind());
+                    continue;
+                }
                 if let Err(e) = client.send(&msg).await {
-                    eprintln!("[ClipSlot] Failed to flush queued message: {}", e);
+                    tracing::error!("Failed to flush queued message: {}", e);
                     // Re-queue failed messages
                     self.offline_queue.enqueue(msg);
                     break;
                 }
+                self.offline_queue.track_sent(&msg);
+            }
+            for msg in remaining {
+                self.offline_queue.enqueue(msg);
+            }
+        }
+    }
+
+    /// Resend messages the server hasn't acknowledged within ACK_TIMEOUT.
+    async fn resend_unacked(&self) {
+        let due = self.offline_queue.due_for_retry(ACK_TIMEOUT);
+        if due.is_empty() {
+            return;
+        }
+
+        tracing::info!("Resending {} unacknowledged message(s)", due.len());
+        let ws = self.ws.read().await;
+        let Some(client) = ws.as_ref() else {
+            // Disconnected: the handler re-queues in-flight messages itself
+            return;
+        };
+        for msg in due {
+            if !client.server_accepts(&msg) {
+                tracing::info!("Dropping unacked {}: not supported by server", msg.kind());
+                if let Some(msg_id) = msg.msg_id() {
+                    self.offline_queue.ack(&msg_id);
+                }
+                continue;
+            }
+            if client.send(&msg).await.is_ok() {
+                self.offline_queue.track_sent(&msg);
             }
         }
     }
@@ -498,10 +1026,41 @@ impl SyncManager {
     /// Safe to call multiple times — only one loop runs at a time.
     pub fn spawn_ws_reconnect_loop(self: Arc<Self>) {
         if self.reconnect_active.swap(true, Ordering::AcqRel) {
-            clog!("WS reconnect loop already active");
+            tracing::info!("WS reconnect loop already active");
             return;
         }
 
+        // Retry unacked messages for as long as the reconnect loop runs
+        let retry = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(ACK_TIMEOUT / 2);
+            while retry.reconnect_active.load(Ordering::Acquire) {
+                interval.tick().await;
+                retry.resend_unacked().await;
+            }
+        });
+
+        // Probe the server while connected too: a connection can look open
+        // long after the server stopped answering
+        let probe = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(PROBE_INTERVAL);
+            interval.tick().await; // skip first immediate tick
+            while probe.reconnect_active.load(Ordering::Acquire) {
+                interval.tick().await;
+                let connected = *probe.ws_alive.borrow();
+                if !connected || probe.server_reachable().await {
+                    continue;
+                }
+                tracing::info!("Health check failed while connected, dropping the connection");
+                if let Some(ws) = probe.ws.write().await.take() {
+                    ws.disconnect().await;
+                }
+                // The reconnect loop takes over, and reports Offline
+                probe.ws_alive.send_replace(false);
+            }
+        });
+
         let this = self;
         tokio::spawn(async move {
             let mut rx = this.ws_alive.subscribe();
@@ -515,45 +1074,60 @@ impl SyncManager {
                 }
 
                 if this.auth.read().await.is_none() {
-                    clog!("WS reconnect: not authenticated, stopping");
+                    tracing::info!("WS reconnect: not authenticated, stopping");
                     break;
                 }
 
-                clog!("WS reconnect: connection lost, retrying in {}s...", backoff);
-                *this.status.write().await = SyncStatus::Disconnected;
-                tokio::time::sleep(Duration::from_secs(backoff)).await;
+                // While the server can't be reached, changes are queued and
+                // only the cheap health check repeats
+                let reachable = this.server_reachable().await;
+                let wait = if reachable {
+                    tracing::info!("WS reconnect: connection lost, retrying in {}s...", backoff);
+                    this.set_status(SyncStatus::Disconnected);
+                    Duration::from_secs(backoff)
+                } else {
+                    if this.set_status(SyncStatus::Offline) != SyncStatus::Offline {
+                        tracing::info!("WS reconnect: server unreachable, working offline");
+                    }
+                    backoff = 3;
+                    PROBE_INTERVAL
+                };
+                tokio::time::sleep(wait).await;
 
                 // Check if someone else already reconnected (e.g. force_sync)
                 if *this.ws_alive.borrow() {
-                    clog!("WS reconnect: already reconnected, skipping");
+                    tracing::info!("WS reconnect: already reconnected, skipping");
                     backoff = 3;
                     continue;
                 }
 
                 if this.auth.read().await.is_none() {
-                    clog!("WS reconnect: logged out during backoff, stopping");
+                    tracing::info!("WS reconnect: logged out during backoff, stopping");
                     break;
                 }
+                if !reachable {
+                    continue;
+                }
 
                 match this.connect_ws().await {
                     Ok(()) => {
-                        clog!("WS reconnect: success");
+                        tracing::info!("WS reconnect: success");
                         backoff = 3;
                     }
                     Err(e) => {
                         if Self::is_auth_error(&e) {
-                            clog!("WS reconnect: auth error, forcing logout: {}", e);
+                            tracing::info!("WS reconnect: auth error, forcing logout: {}", e);
                             this.force_logout_expired().await;
                             break;
                         }
-                        clog!("WS reconnect: failed: {}", e);
+                        tracing::info!("WS reconnect: failed: {}", e);
                         backoff = (backoff * 2).min(30);
                     }
                 }
             }
 
             this.reconnect_active.store(false, Ordering::Release);
-            clog!("WS reconnect loop stopped");
+            tracing::info!("WS reconnect loop stopped");
         });
     }
 
@@ -575,19 +1149,52 @@ impl SyncManager {
         self.auth.blocking_read().is_some()
     }
 
+    /// Synchronous read of the signed-in account's email (for tray menu).
+    pub fn account_email_blocking(&self) -> Option<String> {
+        self.auth.blocking_read().as_ref().map(|a| a.email.clone())
+    }
+
+    /// Returns the previous status.
+    fn set_status(&self, status: SyncStatus) -> SyncStatus {
+        self.status.send_replace(status)
+    }
+
+    /// A quick health check, telling "server unreachable" apart from a
+    /// connection that merely dropped.
+    async fn server_reachable(&self) -> bool {
+        let api = self.api.read().await.clone();
+        api.health(PROBE_TIMEOUT).await.is_ok()
+    }
+
+    /// Changes the server hasn't confirmed yet, e.g. queued while offline.
+    pub fn pending_changes(&self) -> usize {
+        self.offline_queue.pending()
+    }
+
+    /// Receives `pending_changes()` whenever it changes.
+    pub fn subscribe_pending_changes(&self) -> tokio::sync::watch::Receiver<usize> {
+        self.offline_queue.subscribe_pending()
+    }
+
+    /// Receives every status change, e.g. to keep the tray menu current.
+    pub fn subscribe_status(&self) -> tokio::sync::watch::Receiver<SyncStatus> {
+        self.status.subscribe()
+    }
+
     /// Synchronous status read (for tray menu).
     pub fn get_status_blocking(&self) -> SyncStatus {
-        self.status.blocking_read().clone()
+        self.status.borrow().clone()
     }
 
     async fn build_sync_state(&self) -> SyncState {
         let auth = self.auth.read().await;
-        let status = self.status.read().await.clone();
-        let history_sync = self
+        let status = self.status.borrow().clone();
+        let history_sync = history_sync_enabled(&self.db).await;
+        let search_index = self
             .db
-            .get_setting("history_sync_enabled")
-            .map(|v| v == "true")
-            .unwrap_or(false);
+            .call(|db| db.get_setting(super::history_sync::SEARCH_INDEX_KEY))
+            .await
+            .is_some_and(|v| v == "true");
 
         match auth.as_ref() {
             Some(a) => SyncState {
@@ -596,6 +1203,8 @@ impl SyncManager {
                 email: Some(a.email.clone()),
                 device_id: Some(a.device_id),
                 history_sync_enabled: history_sync,
+                search_index_enabled: search_index,
+                pending_changes: self.offline_queue.pending(),
             },
             None => SyncState {
                 status: SyncStatus::Disconnected,
@@ -603,7 +1212,53 @@ impl SyncManager {
                 email: None,
                 device_id: None,
                 history_sync_enabled: history_sync,
+                search_index_enabled: search_index,
+                pending_changes: self.offline_queue.pending(),
             },
         }
     }
 }
+
+/// Encrypt `text` with the account's master key as a base64 blob.
+fn encrypt_blob(text: &str) -> Result<String, String> {
+    let master_key = crate::crypto::keychain::get_or_create_master_key()?;
+    let encrypted = crate::crypto::cipher::CryptoEngine::new(&master_key).encrypt(text)?;
+    Ok(BASE64.encode(encrypted.as_bytes()))
+}
+
+/// Decrypt a base64 blob encrypted with the account's master key.
+fn decrypt_blob(encrypted_blob: &str) -> Result<String, String> {
+    let bytes = BASE64
+        .decode(encrypted_blob)
+        .map_err(|e| format!("Base64 decode error: {}", e))?;
+    let encrypted = String::from_utf8(bytes).map_err(|e| format!("UTF-8 error: {}", e))?;
+    let master_key = crate::crypto::keychain::get_or_create_master_key()?;
+    crate::crypto::cipher::CryptoEngine::new(&master_key).decrypt(&encrypted)
+}
+
+async fn history_sync_enabled(db: &Arc<Database>) -> bool {
+    db.call(|db| db.get_setting("history_sync_enabled"))
+        .await
+        .is_some_and(|v| v == "true")
+}
+
+/// Catch up with the server after it could not replay missed broadcasts.
+/// Slots are fetched in full; history resumes from the stored cursor.
+async fn resync(api: &ApiClient, token: &str, db: &Arc<Database>, device_id: &str) {
+    match super::slot_sync::perform_full_slot_sync(api, token, db, device_id).await {
+        Ok(n) => tracing::info!("Resync: synced {} slots", n),
+        Err(e) => {
+            telemetry::record(telemetry::Event::SyncError);
+            tracing::error!("Resync slot sync failed: {}", e);
+        }
+    }
+
+    if history_sync_enabled(db).await {
+        if let Err(e) =
+            super::history_sync::perform_initial_history_sync(api, token, db, device_id).await
+        {
+            telemetry::record(telemetry::Event::SyncError);
+            tracing::error!("Resync history sync failed: {}", e);
+        }
+    }
+}
@@ -1,8 +1,11 @@
 pub mod api_client;
+pub mod device_info;
+pub mod filter;
 pub mod history_sync;
 pub mod key_exchange;
 pub mod manager;
 pub mod offline_queue;
 pub mod slot_sync;
+pub mod team_sync;
 pub mod types;
 pub mod ws_client;
@@ -1,21 +1,60 @@
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use tokio::sync::watch;
+use uuid::Uuid;
 
 use super::types::WsMessage;
 
+/// Give up on a message after this many unacknowledged sends.
+const MAX_SEND_ATTEMPTS: u32 = 5;
+
+struct InFlight {
+    msg: WsMessage,
+    sent_at: Instant,
+    attempts: u32,
+}
+
 /// In-memory queue for messages that couldn't be sent while offline.
 /// Deduplicates slot updates by keeping only the latest per slot_number.
+///
+/// Also tracks messages that were sent but not yet acknowledged by the server,
+/// so they can be retried after a timeout or re-queued when the connection drops.
 pub struct OfflineQueue {
     queue: Mutex<VecDeque<WsMessage>>,
+    in_flight: Mutex<HashMap<Uuid, InFlight>>,
+    /// `pending()`, sent to subscribers whenever it changes
+    pending: watch::Sender<usize>,
 }
 
 impl OfflineQueue {
     pub fn new() -> Self {
         Self {
             queue: Mutex::new(VecDeque::new()),
+            in_flight: Mutex::new(HashMap::new()),
+            pending: watch::Sender::new(0),
         }
     }
 
+    /// Messages the server hasn't acknowledged yet, queued or in flight.
+    pub fn pending(&self) -> usize {
+        let queued = self.queue.lock().unwrap().len();
+        queued + self.in_flight.lock().unwrap().len()
+    }
+
+    /// Receives `pending()` whenever it changes, e.g. to show how many changes
+    /// wait for the connection to come back.
+    pub fn subscribe_pending(&self) -> watch::Receiver<usize> {
+        self.pending.subscribe()
+    }
+
+    /// Called after every change, with neither lock held.
+    fn pending_changed(&self) {
+        let pending = self.pending();
+        self.pending.send_if_modified(|current| std::mem::replace(current, pending) != pending);
+    }
+
     /// Enqueue a message. For SlotUpdate messages, replaces any existing
     /// entry for the same slot_number (keeping only the latest).
     pub fn enqueue(&self, msg: WsMessage) {
@@ -29,15 +68,189 @@ impl OfflineQueue {
         }
 
         q.push_back(msg);
+        drop(q);
+        self.pending_changed();
     }
 
     /// Drain all queued messages for sending.
     pub fn drain(&self) -> Vec<WsMessage> {
-        let mut q = self.queue.lock().unwrap();
-        q.drain(..).collect()
+        let drained = self.queue.lock().unwrap().drain(..).collect();
+        self.pending_changed();
+        drained
     }
 
     pub fn is_empty(&self) -> bool {
         self.queue.lock().unwrap().is_empty()
     }
+
+    /// Record a message that was just sent and awaits an ack.
+    /// A newer SlotUpdate supersedes any unacked one for the same slot.
+    pub fn track_sent(&self, msg: &WsMessage) {
+        let Some(msg_id) = msg.msg_id() else {
+            return;
+        };
+        let mut in_flight = self.in_flight.lock().unwrap();
+
+        if let WsMessage::SlotUpdate { slot_number, .. } = msg {
+            in_flight.retain(|id, entry| {
+                *id == msg_id
+                    || !matches!(&entry.msg, WsMessage::SlotUpdate { slot_number: n, .. } if n == slot_number)
+            });
+        }
+
+        let attempts = in_flight.get(&msg_id).map(|e| e.attempts).unwrap_or(0) + 1;
+        in_flight.insert(
+            msg_id,
+            InFlight {
+                msg: msg.clone(),
+                sent_at: Instant::now(),
+                attempts,
+            },
+        );
+        drop(in_flight);
+        self.pending_changed();
+    }
+
+    /// Mark a message as acknowledged. Returns false if it wasn't being tracked.
+    pub fn ack(&self, msg_id: &Uuid) -> bool {
+        let acked = self.in_flight.lock().unwrap().remove(msg_id).is_some();
+        self.pending_changed();
+        acked
+    }
+
+    /// Messages whose ack is overdue and should be sent again.
+    /// Messages that exhausted their attempts are dropped.
+    pub fn due_for_retry(&self, timeout: Duration) -> Vec<WsMessage> {
+        let mut in_flight = self.in_flight.lock().unwrap();
+        let mut due = Vec::new();
+
+        in_flight.retain(|id, entry| {
+            if entry.sent_at.elapsed() < timeout {
+                return true;
+            }
+            if entry.attempts >= MAX_SEND_ATTEMPTS {
+                tracing::info!(
+                    "Dropping unacknowledged message {} after {} attempts",
+                    id,
+                    entry.attempts
+                );
+                return false;
+            }
+            due.push(entry.msg.clone());
+            true
+        });
+        drop(in_flight);
+        self.pending_changed();
+
+        due
+    }
+
+    /// Move every unacked message back to the front of the queue (e.g. after a
+    /// disconnect), so it is resent first on the next connection. An unacked
+    /// SlotUpdate is dropped if a newer one for the same slot is already queued.
+    pub fn requeue_in_flight(&self) {
+        let mut pending: Vec<InFlight> = self
+            .in_flight
+            .lock()
+            .unwrap()
+            .drain()
+            .map(|(_, entry)| entry)
+            .collect();
+        pending.sort_by_key(|entry| std::cmp::Reverse(entry.sent_at));
+
+        let mut q = self.queue.lock().unwrap();
+        for entry in pending {
+            if let WsMessage::SlotUpdate { slot_number, .. } = &entry.msg {
+                let superseded = q.iter().any(|existing| {
+                    matches!(existing, WsMessage::SlotUpdate { slot_number: n, .. } if n == slot_number)
+                });
+                if superseded {
+                    continue;
+                }
+            }
+            q.push_front(entry.msg);
+        }
+        drop(q);
+        self.pending_changed();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn slot_update(slot_number: i32, blob: &str) -> WsMessage {
+        WsMessage::SlotUpdate {
+            slot_number,
+            encrypted_blob: blob.to_string(),
+            timestamp: 0,
+            msg_id: Some(Uuid::new_v4()),
+        }
+    }
+
+    #[test]
+    fn test_ack_clears_in_flight() {
+        let queue = OfflineQueue::new();
+        let msg = slot_update(1, "a");
+        queue.track_sent(&msg);
+
+        assert!(queue.ack(&msg.msg_id().unwrap()));
+        queue.requeue_in_flight();
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_requeue_skips_superseded_slot_update() {
+        let queue = OfflineQueue::new();
+        queue.track_sent(&slot_update(1, "old"));
+        queue.track_sent(&slot_update(2, "other"));
+        queue.enqueue(slot_update(1, "new"));
+
+        queue.requeue_in_flight();
+        let drained = queue.drain();
+
+        assert_eq!(drained.len(), 2);
+        let slot1: Vec<_> = drained
+            .iter()
+            .filter_map(|m| match m {
+                WsMessage::SlotUpdate {
+                    slot_number: 1,
+                    encrypted_blob,
+                    ..
+                } => Some(encrypted_blob.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(slot1, vec!["new"]);
+    }
+
+    #[test]
+    fn test_retry_after_timeout() {
+        let queue = OfflineQueue::new();
+        queue.track_sent(&slot_update(3, "a"));
+
+        assert!(queue.due_for_retry(Duration::from_secs(60)).is_empty());
+        assert_eq!(queue.due_for_retry(Duration::ZERO).len(), 1);
+    }
+
+    #[test]
+    fn test_pending_counts_queued_and_in_flight() {
+        let queue = OfflineQueue::new();
+        let mut pending_rx = queue.subscribe_pending();
+        let sent = slot_update(1, "a");
+        queue.track_sent(&sent);
+        queue.enqueue(slot_update(2, "b"));
+        queue.enqueue(slot_update(2, "c"));
+        assert_eq!(queue.pending(), 2);
+        assert!(pending_rx.has_changed().unwrap());
+        assert_eq!(*pending_rx.borrow_and_update(), 2);
+
+        // Still unacked after the connection drops
+        queue.requeue_in_flight();
+        assert_eq!(queue.pending(), 2);
+        assert!(!pending_rx.has_changed().unwrap());
+
+        queue.drain();
+        assert_eq!(*pending_rx.borrow_and_update(), 0);
+    }
 }
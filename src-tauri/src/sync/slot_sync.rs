@@ -2,24 +2,30 @@ use std::sync::Arc;
 
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 
-use crate::storage::database::Database;
+use crate::storage::database::{Database, MAX_SLOT_COUNT};
 
 use super::api_client::ApiClient;
+use super::types::ApiError;
 
-/// Perform a full slot sync between local and remote.
-/// Returns the number of slots synced.
+/// Perform a full slot sync between local and remote, after adopting the
+/// account's slot count. Returns the number of slots synced.
 pub async fn perform_full_slot_sync(
     api: &ApiClient,
     token: &str,
     db: &Arc<Database>,
     device_id: &str,
-) -> Result<u32, String> {
+) -> Result<u32, ApiError> {
+    let settings = api.get_slot_settings(token).await?;
+    let slot_count = settings.slot_count;
+    db.call(move |db| apply_slot_count(db, slot_count)).await?;
+
     let remote_slots = api.get_slots(token).await?;
     let mut synced = 0u32;
 
-    for slot_num in 1..=10 {
+    for slot_num in 1..=db.call(|db| db.get_slot_count()).await {
         let (local_encrypted, local_updated_at) = db
-            .get_slot_raw(slot_num)
+            .call(move |db| db.get_slot_raw(slot_num))
+            .await
             .map_err(|e| format!("DB error: {}", e))?;
 
         // Find the matching remote slot
@@ -40,11 +46,10 @@ pub async fn perform_full_slot_sync(
                     let enc_str = String::from_utf8(blob_bytes)
                         .map_err(|e| format!("UTF-8 error: {}", e))?;
 
-                    db.save_encrypted_to_slot(slot_num, &enc_str, remote_ts, device_id)
-                        .map_err(|e| format!("DB error: {}", e))?;
+                    save_pulled_slot(db, slot_num, enc_str, remote_ts, device_id).await?;
                     synced += 1;
-                    println!(
-                        "[ClipSlot] Slot {} pulled from server (remote newer)",
+                    tracing::info!(
+                        "Slot {} pulled from server (remote newer)",
                         slot_num
                     );
                 } else if local_updated_at > remote_ts {
@@ -52,8 +57,8 @@ pub async fn perform_full_slot_sync(
                     let blob = BASE64.encode(local_enc.as_bytes());
                     api.update_slot(token, slot_num as i32, &blob).await?;
                     synced += 1;
-                    println!(
-                        "[ClipSlot] Slot {} pushed to server (local newer)",
+                    tracing::info!(
+                        "Slot {} pushed to server (local newer)",
                         slot_num
                     );
                 }
@@ -65,7 +70,7 @@ pub async fn perform_full_slot_sync(
                 let blob = BASE64.encode(local_enc.as_bytes());
                 api.update_slot(token, slot_num as i32, &blob).await?;
                 synced += 1;
-                println!("[ClipSlot] Slot {} pushed to server (new)", slot_num);
+                tracing::info!("Slot {} pushed to server (new)", slot_num);
             }
 
             // Only remote exists — pull to local
@@ -77,10 +82,9 @@ pub async fn perform_full_slot_sync(
                     .map_err(|e| format!("UTF-8 error: {}", e))?;
 
                 let remote_ts = parse_timestamp(&remote_slot.updated_at);
-                db.save_encrypted_to_slot(slot_num, &enc_str, remote_ts, device_id)
-                    .map_err(|e| format!("DB error: {}", e))?;
+                save_pulled_slot(db, slot_num, enc_str, remote_ts, device_id).await?;
                 synced += 1;
-                println!("[ClipSlot] Slot {} pulled from server (new)", slot_num);
+                tracing::info!("Slot {} pulled from server (new)", slot_num);
             }
 
             // Neither exists — nothing to do
@@ -91,6 +95,28 @@ pub async fn perform_full_slot_sync(
     Ok(synced)
 }
 
+async fn save_pulled_slot(
+    db: &Arc<Database>,
+    slot_number: u32,
+    encrypted: String,
+    updated_at: i64,
+    device_id: &str,
+) -> Result<(), String> {
+    let device_id = device_id.to_string();
+    db.call(move |db| db.save_encrypted_to_slot(slot_number, &encrypted, updated_at, &device_id))
+        .await
+        .map_err(|e| format!("DB error: {}", e))
+}
+
+/// Store the account's slot count locally; slots above it are hidden, not cleared.
+pub fn apply_slot_count(db: &Database, slot_count: i32) -> Result<(), String> {
+    if !(1..=MAX_SLOT_COUNT as i32).contains(&slot_count) {
+        return Err(format!("Invalid slot count: {} (1-{})", slot_count, MAX_SLOT_COUNT));
+    }
+    db.set_setting("slot_count", &slot_count.to_string())
+        .map_err(|e| format!("DB error: {}", e))
+}
+
 /// Parse an ISO 8601 timestamp string to epoch millis, falling back to 0.
 fn parse_timestamp(ts: &str) -> i64 {
     chrono::DateTime::parse_from_rfc3339(ts)
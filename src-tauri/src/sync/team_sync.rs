@@ -0,0 +1,189 @@
+use std::sync::Arc;
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use rand::{Rng, RngCore};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::crypto::cipher::CryptoEngine;
+use crate::storage::database::Database;
+
+use super::api_client::ApiClient;
+use super::types::TeamResponse;
+
+/// Characters of invite codes: capitals and digits, without look-alikes (0/O, 1/I/L).
+const INVITE_ALPHABET: &[u8] = b"23456789ABCDEFGHJKMNPQRSTUVWXYZ";
+/// Characters in an invite code; about 99 bits, so the server can't guess it.
+const INVITE_CODE_LEN: usize = 20;
+
+/// Create a team with a fresh random team key. The server only ever sees the
+/// key wrapped: with a member's master key, or for a new member with an
+/// invite code that is passed on outside ClipSlot.
+pub async fn create_team(api: &ApiClient, token: &str, name: &str) -> Result<TeamResponse, String> {
+    let mut team_key = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut team_key);
+    Ok(api.create_team(token, name, &wrap_team_key(&team_key)?).await?)
+}
+
+/// Hand the team key to a newly added member, wrapped with a fresh invite
+/// code. Returns the code, for the member to enter on their side.
+pub async fn share_team_key(
+    api: &ApiClient,
+    token: &str,
+    team_id: Uuid,
+    member_id: Uuid,
+) -> Result<String, String> {
+    let team_key = get_team_key(api, token, team_id).await?;
+    let code = new_invite_code();
+    let invite_key =
+        CryptoEngine::new(&invite_wrapping_key(&code)).encrypt(&BASE64.encode(team_key))?;
+    api.share_team_key(token, team_id, member_id, &invite_key)
+        .await?;
+    Ok(code)
+}
+
+/// Unwrap the team key shared with us using its invite code, and store our
+/// own wrapped copy on the server, so our other devices can use it without
+/// another code.
+pub async fn redeem_team_key(
+    api: &ApiClient,
+    token: &str,
+    team_id: Uuid,
+    code: &str,
+) -> Result<(), String> {
+    let invite_key = find_team(api, token, team_id)
+        .await?
+        .invite_key
+        .ok_or("The team key hasn't been shared with you yet")?;
+    let encoded = CryptoEngine::new(&invite_wrapping_key(code))
+        .decrypt(&invite_key)
+        .map_err(|_| "Wrong invite code".to_string())?;
+    let team_key = decode_key(&encoded)?;
+    api.set_team_key(token, team_id, &wrap_team_key(&team_key)?)
+        .await?;
+    tracing::info!("Team key received for team {}", team_id);
+    Ok(())
+}
+
+/// Apply a `team_slot_updated` broadcast: decrypt with the team key and store the
+/// content in the team's local copy of the slot, re-encrypted with our master
+/// key. The personal slot with the same number is left alone.
+#[allow(clippy::too_many_arguments)]
+pub async fn apply_team_slot(
+    api: &ApiClient,
+    token: &str,
+    db: &Arc<Database>,
+    device_id: &str,
+    team_id: Uuid,
+    slot_number: i32,
+    encrypted_blob: &str,
+    timestamp: i64,
+) -> Result<(), String> {
+    let team_key = get_team_key(api, token, team_id).await?;
+
+    let blob_bytes = BASE64
+        .decode(encrypted_blob)
+        .map_err(|e| format!("Base64 decode error: {}", e))?;
+    let enc_str = String::from_utf8(blob_bytes).map_err(|e| format!("UTF-8 error: {}", e))?;
+    let plaintext = CryptoEngine::new(&team_key).decrypt(&enc_str)?;
+
+    let master_key = crate::crypto::keychain::get_or_create_master_key()?;
+    let local_enc = CryptoEngine::new(&master_key).encrypt(&plaintext)?;
+    let team_id = team_id.to_string();
+    let device_id = device_id.to_string();
+    db.call(move |db| {
+        db.save_encrypted_to_team_slot(
+            &team_id,
+            slot_number as u32,
+            &local_enc,
+            timestamp,
+            &device_id,
+        )
+    })
+    .await
+    .map_err(|e| format!("DB error: {}", e))
+}
+
+async fn find_team(api: &ApiClient, token: &str, team_id: Uuid) -> Result<TeamResponse, String> {
+    api.list_teams(token)
+        .await?
+        .into_iter()
+        .find(|t| t.id == team_id)
+        .ok_or_else(|| "Not a member of this team".to_string())
+}
+
+/// Fetch and unwrap our copy of a team's key.
+async fn get_team_key(api: &ApiClient, token: &str, team_id: Uuid) -> Result<[u8; 32], String> {
+    let wrapped = find_team(api, token, team_id)
+        .await?
+        .encrypted_team_key
+        .ok_or("Team key not received yet — ask a member for an invite code")?;
+
+    let master_key = crate::crypto::keychain::get_or_create_master_key()?;
+    let encoded = CryptoEngine::new(&master_key).decrypt(&wrapped)?;
+    decode_key(&encoded)
+}
+
+fn wrap_team_key(team_key: &[u8; 32]) -> Result<String, String> {
+    let master_key = crate::crypto::keychain::get_or_create_master_key()?;
+    CryptoEngine::new(&master_key).encrypt(&BASE64.encode(team_key))
+}
+
+/// A random invite code, e.g. `7KQM-2ZXC-...`, in groups of four.
+fn new_invite_code() -> String {
+    let mut rng = rand::thread_rng();
+    let chars: Vec<char> = (0..INVITE_CODE_LEN)
+        .map(|_| INVITE_ALPHABET[rng.gen_range(0..INVITE_ALPHABET.len())] as char)
+        .collect();
+    chars
+        .chunks(4)
+        .map(|group| group.iter().collect::<String>())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// The key an invite code wraps the team key with. Case, dashes and spaces
+/// don't matter, so the code can be typed however it was read out.
+fn invite_wrapping_key(code: &str) -> [u8; 32] {
+    let normalized: String = code
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+    Sha256::new()
+        .chain_update(b"clipslot-team-invite:")
+        .chain_update(normalized.as_bytes())
+        .finalize()
+        .into()
+}
+
+fn decode_key(encoded: &str) -> Result<[u8; 32], String> {
+    let bytes = BASE64
+        .decode(encoded)
+        .map_err(|e| format!("Failed to decode key: {}", e))?;
+    bytes
+        .try_into()
+        .map_err(|b: Vec<u8>| format!("Invalid key length: {} (expected 32)", b.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invite_code_format() {
+        let code = new_invite_code();
+        assert_eq!(code.len(), INVITE_CODE_LEN + INVITE_CODE_LEN / 4 - 1);
+        assert!(code
+            .split('-')
+            .all(|group| group.len() == 4 && group.bytes().all(|b| INVITE_ALPHABET.contains(&b))));
+        assert_ne!(code, new_invite_code());
+    }
+
+    #[test]
+    fn test_invite_wrapping_key_ignores_formatting() {
+        let key = invite_wrapping_key("7KQM-2ZXC-ABCD-EFGH-JKMN");
+        assert_eq!(key, invite_wrapping_key("7kqm 2zxc abcd efgh jkmn"));
+        assert_ne!(key, invite_wrapping_key("7KQM-2ZXC-ABCD-EFGH-JKMP"));
+    }
+}
@@ -1,3 +1,5 @@
+use std::fmt;
+
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -20,10 +22,25 @@ pub struct DeviceInfo {
     pub id: Uuid,
     pub name: String,
     pub device_type: String,
+    #[serde(default)]
+    pub app_version: Option<String>,
+    #[serde(default)]
+    pub os_version: Option<String>,
+    #[serde(default)]
+    pub hostname: Option<String>,
     pub last_seen: String,
     pub created_at: String,
 }
 
+/// Sent at registration and on each sync start, so the device list shows
+/// which version each install runs.
+#[derive(Debug, Clone, Serialize)]
+pub struct DeviceMetadata {
+    pub app_version: String,
+    pub os_version: Option<String>,
+    pub hostname: Option<String>,
+}
+
 // ── Sync types ──────────────────────────────────────────────────────────────
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -39,11 +56,23 @@ pub struct UpdateSlotRequest {
     pub encrypted_blob: String,
 }
 
+/// Account-wide slot settings, shared by every device.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SlotSettings {
+    pub slot_count: i32,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PushHistoryRequest {
     pub id: Uuid,
     pub encrypted_blob: String,
     pub content_hash: String,
+    /// A completed attachment upload, for images and files
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attachment_id: Option<Uuid>,
+    /// Blind-index tokens so the server can search without seeing the words
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub search_tokens: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -53,13 +82,171 @@ pub struct HistoryResponse {
     pub content_hash: String,
     pub device_id: Option<Uuid>,
     pub created_at: String,
+    #[serde(default)]
+    pub attachment_id: Option<Uuid>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryTombstone {
+    pub id: Uuid,
+    pub content_hash: String,
+    pub deleted_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryPageResponse {
+    pub items: Vec<HistoryResponse>,
+    #[serde(default)]
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryDeltaResponse {
+    pub items: Vec<HistoryResponse>,
+    #[serde(default)]
+    pub deleted: Vec<HistoryTombstone>,
+    pub next_cursor: String,
+    pub has_more: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateShareRequest {
+    pub encrypted_blob: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_in_secs: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateShareResponse {
+    pub id: String,
+    /// Page that opens the share, relative to the server URL
+    pub path: String,
+    pub expires_at: String,
+}
+
+/// A one-time link to an item, usable without an account.
+#[derive(Debug, Clone, Serialize)]
+pub struct ShareLink {
+    /// Includes the decryption key as its `#` fragment
+    pub url: String,
+    pub expires_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateAttachmentRequest {
+    pub id: Uuid,
+    pub size: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttachmentResponse {
+    pub id: Uuid,
+    pub size: i64,
+    /// Every chunk but the last is exactly this many bytes
+    pub chunk_size: i64,
+    pub chunk_count: i32,
+    #[serde(default)]
+    pub missing_chunks: Vec<i32>,
+    pub complete: bool,
+}
+
+// ── Team types ──────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TeamResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub role: String,
+    /// Slot numbers shared with this team
+    pub slots: Vec<i32>,
+    /// The team key wrapped with our master key; None until it has been received
+    pub encrypted_team_key: Option<String>,
+    /// The team key a teammate wrapped for us with an invite code
+    #[serde(default)]
+    pub invite_key: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TeamSlotResponse {
+    pub slot_number: i32,
+    pub encrypted_blob: String,
+    pub updated_at: String,
+    pub updated_by: Option<Uuid>,
+}
+
+// ── Error types ─────────────────────────────────────────────────────────────
+
+/// Kind of error the server reported (mirrors server's ErrorCode).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    BadRequest,
+    Unauthorized,
+    Forbidden,
+    NotFound,
+    Conflict,
+    Gone,
+    PayloadTooLarge,
+    UnsupportedMediaType,
+    Unprocessable,
+    RateLimited,
+    QuotaExceeded,
+    Internal,
+    /// A code added to the server after this client was built
+    #[serde(other)]
+    Unknown,
+}
+
+/// A failed API call. `code` is `None` for network and parse errors, and for
+/// error bodies without one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiError {
+    pub code: Option<ErrorCode>,
+    pub message: String,
+    pub request_id: Option<String>,
+}
+
+impl ApiError {
+    /// The session token was missing, invalid or expired.
+    pub fn is_unauthorized(&self) -> bool {
+        self.code == Some(ErrorCode::Unauthorized)
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.request_id {
+            Some(id) => write!(f, "{} (request {})", self.message, id),
+            None => f.write_str(&self.message),
+        }
+    }
+}
+
+impl From<String> for ApiError {
+    fn from(message: String) -> Self {
+        ApiError {
+            code: None,
+            message,
+            request_id: None,
+        }
+    }
+}
+
+impl From<ApiError> for String {
+    fn from(error: ApiError) -> Self {
+        error.to_string()
+    }
 }
 
 // ── Status types ────────────────────────────────────────────────────────────
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum SyncStatus {
+    /// Signed out, or not connected yet
     Disconnected,
+    /// Signed in, but the server can't be reached; changes are queued
+    Offline,
     Connecting,
     Connected,
     Syncing,
@@ -72,18 +259,51 @@ pub struct SyncState {
     pub email: Option<String>,
     pub device_id: Option<Uuid>,
     pub history_sync_enabled: bool,
+    pub search_index_enabled: bool,
+    /// Changes the server hasn't confirmed yet, e.g. queued while offline
+    pub pending_changes: usize,
 }
 
 // ── WebSocket messages (mirrors server's WsMessage) ─────────────────────────
 
+/// Version of the WebSocket protocol spoken by this client, sent in `hello`.
+pub const PROTOCOL_VERSION: u32 = 2;
+
+/// Message types this client accepts from the server, sent in `hello`.
+pub const ACCEPTED_TYPES: &[&str] = &[
+    "hello",
+    "slot_updated",
+    "slot_settings_updated",
+    "history_new",
+    "history_deleted",
+    "team_slot_updated",
+    "sent_to_device",
+    "clipboard_requested",
+    "clipboard_responded",
+    "monitoring_changed",
+    "ack",
+    "resync_required",
+    "error",
+];
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum WsMessage {
+    #[serde(rename = "hello")]
+    Hello {
+        protocol_version: u32,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        min_protocol_version: Option<u32>,
+        #[serde(default)]
+        capabilities: Vec<String>,
+    },
     #[serde(rename = "slot_update")]
     SlotUpdate {
         slot_number: i32,
         encrypted_blob: String,
         timestamp: i64,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        msg_id: Option<Uuid>,
     },
     #[serde(rename = "slot_updated")]
     SlotUpdated {
@@ -92,11 +312,19 @@ pub enum WsMessage {
         updated_by: Uuid,
         timestamp: i64,
     },
+    #[serde(rename = "slot_settings_updated")]
+    SlotSettingsUpdated { slot_count: i32 },
     #[serde(rename = "history_push")]
     HistoryPush {
         id: Uuid,
         encrypted_blob: String,
         content_hash: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        attachment_id: Option<Uuid>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        search_tokens: Vec<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        msg_id: Option<Uuid>,
     },
     #[serde(rename = "history_new")]
     HistoryNew {
@@ -104,9 +332,141 @@ pub enum WsMessage {
         encrypted_blob: String,
         content_hash: String,
         device_id: Uuid,
+        #[serde(default)]
+        attachment_id: Option<Uuid>,
+    },
+    #[serde(rename = "history_delete")]
+    HistoryDelete {
+        id: Uuid,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        msg_id: Option<Uuid>,
+    },
+    #[serde(rename = "history_deleted")]
+    HistoryDeleted {
+        id: Uuid,
+        content_hash: String,
+        deleted_by: Uuid,
+    },
+    #[serde(rename = "team_slot_update")]
+    TeamSlotUpdate {
+        team_id: Uuid,
+        slot_number: i32,
+        encrypted_blob: String,
+        timestamp: i64,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        msg_id: Option<Uuid>,
+    },
+    #[serde(rename = "team_slot_updated")]
+    TeamSlotUpdated {
+        team_id: Uuid,
+        slot_number: i32,
+        encrypted_blob: String,
+        updated_by: Uuid,
+        timestamp: i64,
+    },
+    #[serde(rename = "send_to_device")]
+    SendToDevice {
+        target_device: Uuid,
+        encrypted_blob: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        msg_id: Option<Uuid>,
+    },
+    #[serde(rename = "sent_to_device")]
+    SentToDevice {
+        encrypted_blob: String,
+        from_device: Uuid,
+        timestamp: i64,
+    },
+    #[serde(rename = "clipboard_request")]
+    ClipboardRequest {
+        target_device: Uuid,
+        request_id: Uuid,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        msg_id: Option<Uuid>,
+    },
+    #[serde(rename = "clipboard_requested")]
+    ClipboardRequested { from_device: Uuid, request_id: Uuid },
+    #[serde(rename = "clipboard_response")]
+    ClipboardResponse {
+        target_device: Uuid,
+        request_id: Uuid,
+        encrypted_blob: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        msg_id: Option<Uuid>,
+    },
+    #[serde(rename = "clipboard_responded")]
+    ClipboardResponded {
+        from_device: Uuid,
+        request_id: Uuid,
+        encrypted_blob: Option<String>,
+    },
+    #[serde(rename = "set_monitoring")]
+    SetMonitoring {
+        paused: bool,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        msg_id: Option<Uuid>,
+    },
+    #[serde(rename = "monitoring_changed")]
+    MonitoringChanged { paused: bool, changed_by: Uuid },
+    #[serde(rename = "ack")]
+    Ack {
+        msg_id: Uuid,
+        ok: bool,
+        #[serde(default)]
+        error: Option<String>,
+    },
+    #[serde(rename = "resync_required")]
+    ResyncRequired {
+        reason: String,
     },
     #[serde(rename = "error")]
     Error {
+        #[serde(default)]
+        code: String,
         message: String,
     },
 }
+
+impl WsMessage {
+    /// The `type` tag this message is sent with.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            WsMessage::Hello { .. } => "hello",
+            WsMessage::SlotUpdate { .. } => "slot_update",
+            WsMessage::SlotUpdated { .. } => "slot_updated",
+            WsMessage::SlotSettingsUpdated { .. } => "slot_settings_updated",
+            WsMessage::HistoryPush { .. } => "history_push",
+            WsMessage::HistoryNew { .. } => "history_new",
+            WsMessage::HistoryDelete { .. } => "history_delete",
+            WsMessage::HistoryDeleted { .. } => "history_deleted",
+            WsMessage::TeamSlotUpdate { .. } => "team_slot_update",
+            WsMessage::TeamSlotUpdated { .. } => "team_slot_updated",
+            WsMessage::SendToDevice { .. } => "send_to_device",
+            WsMessage::SentToDevice { .. } => "sent_to_device",
+            WsMessage::ClipboardRequest { .. } => "clipboard_request",
+            WsMessage::ClipboardRequested { .. } => "clipboard_requested",
+            WsMessage::ClipboardResponse { .. } => "clipboard_response",
+            WsMessage::ClipboardResponded { .. } => "clipboard_responded",
+            WsMessage::SetMonitoring { .. } => "set_monitoring",
+            WsMessage::MonitoringChanged { .. } => "monitoring_changed",
+            WsMessage::Ack { .. } => "ack",
+            WsMessage::ResyncRequired { .. } => "resync_required",
+            WsMessage::Error { .. } => "error",
+        }
+    }
+
+    /// Client-generated ID of an outgoing message that expects an ack.
+    pub fn msg_id(&self) -> Option<Uuid> {
+        match self {
+            WsMessage::SlotUpdate { msg_id, .. }
+            | WsMessage::HistoryPush { msg_id, .. }
+            | WsMessage::HistoryDelete { msg_id, .. }
+            | WsMessage::TeamSlotUpdate { msg_id, .. }
+            | WsMessage::SendToDevice { msg_id, .. }
+            | WsMessage::ClipboardRequest { msg_id, .. }
+            | WsMessage::ClipboardResponse { msg_id, .. }
+            | WsMessage::SetMonitoring { msg_id, .. } => *msg_id,
+            _ => None,
+        }
+    }
+}
@@ -1,22 +1,39 @@
+use std::collections::HashSet;
+use std::io::{Read, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
+use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression};
 use futures_util::{SinkExt, StreamExt};
 use tokio::sync::{broadcast, mpsc};
 use tokio_tungstenite::{connect_async, tungstenite::Message};
 
-use super::types::WsMessage;
+use super::types::{WsMessage, ACCEPTED_TYPES, PROTOCOL_VERSION};
 
 /// Interval for sending WebSocket ping frames to keep the connection alive.
 const PING_INTERVAL: Duration = Duration::from_secs(30);
+/// Hello capability for binary frames of raw-deflated JSON, which both sides
+/// only send to a peer that advertised it.
+const DEFLATE_CAPABILITY: &str = "deflate";
+/// Messages shorter than this go out as text; deflate wouldn't save much.
+const DEFLATE_MIN_BYTES: usize = 1024;
+/// Largest message inflated; the server never sends more than its 4 MiB frame limit.
+const MAX_INFLATED_BYTES: u64 = 4 * 1024 * 1024;
 
 pub struct WsClient {
     outgoing_tx: mpsc::Sender<String>,
     incoming_tx: broadcast::Sender<WsMessage>,
     shutdown_tx: mpsc::Sender<()>,
+    /// Message types the server accepts, from its `hello`. None until then, and
+    /// for servers that predate it, which are assumed to accept everything.
+    server_accepts: Arc<Mutex<Option<HashSet<String>>>>,
 }
 
 impl WsClient {
-    pub async fn connect(ws_url: &str) -> Result<Self, String> {
+    /// Connect to the server. `last_seq` is advanced to the `seq` of every
+    /// broadcast received, so a later connection can resume from it.
+    pub async fn connect(ws_url: &str, last_seq: Arc<AtomicU64>) -> Result<Self, String> {
         let url = url::Url::parse(ws_url).map_err(|e| format!("Invalid WS URL: {}", e))?;
 
         let (ws_stream, _) = connect_async(url.as_str())
@@ -30,6 +47,25 @@ impl WsClient {
         let (shutdown_tx, mut shutdown_rx) = mpsc::channel::<()>(1);
 
         let incoming_tx_clone = incoming_tx.clone();
+        let server_accepts: Arc<Mutex<Option<HashSet<String>>>> = Arc::new(Mutex::new(None));
+        let server_accepts_clone = server_accepts.clone();
+        let server_accepts_send = server_accepts.clone();
+
+        // Introduce ourselves before anything else goes out
+        let hello = WsMessage::Hello {
+            protocol_version: PROTOCOL_VERSION,
+            min_protocol_version: None,
+            capabilities: ACCEPTED_TYPES
+                .iter()
+                .chain([&DEFLATE_CAPABILITY])
+                .map(|t| t.to_string())
+                .collect(),
+        };
+        let hello = serde_json::to_string(&hello).map_err(|e| e.to_string())?;
+        ws_sink
+            .send(Message::Text(hello.into()))
+            .await
+            .map_err(|e| format!("WebSocket hello failed: {}", e))?;
 
         // Send task: forwards outgoing messages and pings to the WebSocket
         tokio::spawn(async move {
@@ -39,20 +75,25 @@ impl WsClient {
             loop {
                 tokio::select! {
                     Some(msg) = outgoing_rx.recv() => {
-                        if ws_sink.send(Message::Text(msg.into())).await.is_err() {
-                            clog!("WS send task: send failed, breaking");
+                        let deflate = server_accepts_send
+                            .lock()
+                            .unwrap()
+                            .as_ref()
+                            .is_some_and(|caps| caps.contains(DEFLATE_CAPABILITY));
+                        if ws_sink.send(frame(msg, deflate)).await.is_err() {
+                            tracing::info!("WS send task: send failed, breaking");
                             break;
                         }
                     }
                     _ = ping_interval.tick() => {
                         if ws_sink.send(Message::Ping(vec![].into())).await.is_err() {
-                            clog!("WS send task: ping failed, breaking");
+                            tracing::info!("WS send task: ping failed, breaking");
                             break;
                         }
                     }
                     _ = shutdown_rx.recv() => {
                         let _ = ws_sink.close().await;
-                        clog!("WS send task: shutdown received");
+                        tracing::info!("WS send task: shutdown received");
                         break;
                     }
                 }
@@ -62,43 +103,85 @@ impl WsClient {
         // Receive task: reads from WebSocket and broadcasts parsed messages
         tokio::spawn(async move {
             while let Some(result) = ws_stream_rx.next().await {
-                match result {
-                    Ok(Message::Text(text)) => {
-                        clog!("WS recv: got message ({}B)", text.len());
-                        match serde_json::from_str::<WsMessage>(&text) {
-                            Ok(msg) => {
-                                clog!("WS recv: parsed message type={}", ws_msg_type(&msg));
-                                let _ = incoming_tx_clone.send(msg);
-                            }
-                            Err(e) => {
-                                clog!("WS recv: parse error: {}", e);
-                            }
+                let text = match result {
+                    Ok(Message::Text(text)) => text.as_str().to_owned(),
+                    Ok(Message::Binary(data)) => match inflate(&data) {
+                        Some(text) => text,
+                        None => {
+                            tracing::info!("WS recv: invalid deflated message");
+                            continue;
                         }
-                    }
+                    },
                     Ok(Message::Pong(_)) => {
                         // Expected response to our pings, ignore
+                        continue;
                     }
                     Ok(Message::Close(frame)) => {
-                        clog!("WS recv: server closed connection: {:?}", frame);
+                        tracing::info!("WS recv: server closed connection: {:?}", frame);
                         break;
                     }
                     Err(e) => {
-                        clog!("WS recv: error: {}", e);
+                        tracing::info!("WS recv: error: {}", e);
                         break;
                     }
-                    _ => {}
+                    _ => continue,
+                };
+                tracing::debug!("WS recv: got message ({}B)", text.len());
+                let parsed = serde_json::from_str::<serde_json::Value>(&text).and_then(|value| {
+                    if let Some(seq) = value.get("seq").and_then(|s| s.as_u64()) {
+                        last_seq.fetch_max(seq, Ordering::SeqCst);
+                    }
+                    serde_json::from_value::<WsMessage>(value)
+                });
+                match parsed {
+                    Ok(msg) => {
+                        tracing::debug!("WS recv: parsed message type={}", ws_msg_type(&msg));
+                        if let WsMessage::Hello {
+                            protocol_version,
+                            min_protocol_version,
+                            capabilities,
+                        } = &msg
+                        {
+                            tracing::info!("WS recv: server protocol version {}", protocol_version);
+                            if min_protocol_version.is_some_and(|v| v > PROTOCOL_VERSION) {
+                                tracing::error!(
+                                    "Server requires protocol version {} \
+                                     (this build speaks {}); please update",
+                                    min_protocol_version.unwrap_or_default(),
+                                    PROTOCOL_VERSION
+                                );
+                            }
+                            *server_accepts_clone.lock().unwrap() =
+                                Some(capabilities.iter().cloned().collect());
+                        }
+                        let _ = incoming_tx_clone.send(msg);
+                    }
+                    Err(e) => {
+                        tracing::info!("WS recv: parse error: {}", e);
+                    }
                 }
             }
-            clog!("WS receive loop ended");
+            tracing::info!("WS receive loop ended");
         });
 
         Ok(Self {
             outgoing_tx,
             incoming_tx,
             shutdown_tx,
+            server_accepts,
         })
     }
 
+    /// Whether the server handles messages of this type. Sending one it doesn't
+    /// would only get it rejected.
+    pub fn server_accepts(&self, msg: &WsMessage) -> bool {
+        self.server_accepts
+            .lock()
+            .unwrap()
+            .as_ref()
+            .is_none_or(|types| types.contains(msg.kind()))
+    }
+
     pub async fn send(&self, msg: &WsMessage) -> Result<(), String> {
         let json = serde_json::to_string(msg).map_err(|e| e.to_string())?;
         self.outgoing_tx
@@ -116,12 +199,76 @@ impl WsClient {
     }
 }
 
+/// `payload` as a frame: deflated if the server takes that and it's worth it.
+fn frame(payload: String, deflate: bool) -> Message {
+    if deflate && payload.len() >= DEFLATE_MIN_BYTES {
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        if encoder.write_all(payload.as_bytes()).is_ok() {
+            if let Ok(data) = encoder.finish() {
+                return Message::Binary(data.into());
+            }
+        }
+    }
+    Message::Text(payload.into())
+}
+
+/// The text in a deflated message; None if it isn't valid deflate or UTF-8,
+/// or inflates past `MAX_INFLATED_BYTES`.
+fn inflate(data: &[u8]) -> Option<String> {
+    let mut text = String::new();
+    let read = DeflateDecoder::new(data)
+        .take(MAX_INFLATED_BYTES + 1)
+        .read_to_string(&mut text)
+        .ok()?;
+    (read as u64 <= MAX_INFLATED_BYTES).then_some(text)
+}
+
 fn ws_msg_type(msg: &WsMessage) -> &'static str {
     match msg {
+        WsMessage::Hello { .. } => "Hello",
         WsMessage::SlotUpdate { .. } => "SlotUpdate",
         WsMessage::SlotUpdated { .. } => "SlotUpdated",
+        WsMessage::SlotSettingsUpdated { .. } => "SlotSettingsUpdated",
         WsMessage::HistoryPush { .. } => "HistoryPush",
         WsMessage::HistoryNew { .. } => "HistoryNew",
+        WsMessage::HistoryDelete { .. } => "HistoryDelete",
+        WsMessage::HistoryDeleted { .. } => "HistoryDeleted",
+        WsMessage::TeamSlotUpdate { .. } => "TeamSlotUpdate",
+        WsMessage::TeamSlotUpdated { .. } => "TeamSlotUpdated",
+        WsMessage::SendToDevice { .. } => "SendToDevice",
+        WsMessage::SentToDevice { .. } => "SentToDevice",
+        WsMessage::ClipboardRequest { .. } => "ClipboardRequest",
+        WsMessage::ClipboardRequested { .. } => "ClipboardRequested",
+        WsMessage::ClipboardResponse { .. } => "ClipboardResponse",
+        WsMessage::ClipboardResponded { .. } => "ClipboardResponded",
+        WsMessage::SetMonitoring { .. } => "SetMonitoring",
+        WsMessage::MonitoringChanged { .. } => "MonitoringChanged",
+        WsMessage::Ack { .. } => "Ack",
+        WsMessage::ResyncRequired { .. } => "ResyncRequired",
         WsMessage::Error { .. } => "Error",
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_deflates_only_large_messages() {
+        let short = "{\"type\":\"ack\"}".to_string();
+        assert!(matches!(frame(short.clone(), true), Message::Text(_)));
+
+        let long = format!("{{\"data\":\"{}\"}}", "QUJD".repeat(1024));
+        assert!(matches!(frame(long.clone(), false), Message::Text(_)));
+        let Message::Binary(data) = frame(long.clone(), true) else {
+            panic!("expected a deflated frame");
+        };
+        assert!(data.len() < long.len());
+        assert_eq!(inflate(&data).as_deref(), Some(long.as_str()));
+    }
+
+    #[test]
+    fn test_inflate_rejects_garbage() {
+        assert_eq!(inflate(&[0xff, 0x00, 0x12]), None);
+    }
+}
@@ -0,0 +1,219 @@
+//! Opt-in anonymous usage counts (`telemetry_enabled`). Only how many times a
+//! few things happened each day is kept, never content, names or ids. Days
+//! are queued locally and sent to `POST {sync_server_url}/api/telemetry` once
+//! they're over; see docs/telemetry.md.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use crate::storage::database::Database;
+
+/// `app_config` key of the days not sent yet.
+const QUEUE_KEY: &str = "telemetry_queue";
+/// Days kept while the server can't be reached; older ones are dropped.
+pub const MAX_QUEUED_DAYS: usize = 31;
+/// Highest count the server takes for one day.
+const MAX_COUNT: u32 = 1_000_000;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static QUEUE: Mutex<TelemetryQueue> = Mutex::new(TelemetryQueue { days: Vec::new() });
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Event {
+    Capture,
+    SlotSave,
+    SlotPaste,
+    SyncError,
+}
+
+/// Counts for one UTC day, as the server takes them.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DayCounts {
+    /// `YYYY-MM-DD`
+    pub day: String,
+    pub captures: u32,
+    pub slot_saves: u32,
+    pub slot_pastes: u32,
+    pub sync_errors: u32,
+}
+
+/// Days counted but not sent yet, oldest first.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct TelemetryQueue {
+    days: Vec<DayCounts>,
+}
+
+impl TelemetryQueue {
+    pub fn record(&mut self, day: &str, event: Event) {
+        if self.days.last().is_none_or(|d| d.day != day) {
+            self.days.push(DayCounts {
+                day: day.to_string(),
+                ..DayCounts::default()
+            });
+            if self.days.len() > MAX_QUEUED_DAYS {
+                self.days.remove(0);
+            }
+        }
+        let counts = self.days.last_mut().unwrap();
+        let count = match event {
+            Event::Capture => &mut counts.captures,
+            Event::SlotSave => &mut counts.slot_saves,
+            Event::SlotPaste => &mut counts.slot_pastes,
+            Event::SyncError => &mut counts.sync_errors,
+        };
+        *count = (*count + 1).min(MAX_COUNT);
+    }
+
+    /// Days before `today`, which won't change any more.
+    pub fn finished(&self, today: &str) -> Vec<DayCounts> {
+        self.days
+            .iter()
+            .filter(|d| d.day.as_str() < today)
+            .cloned()
+            .collect()
+    }
+
+    /// Forget days up to and including `day`, once sent.
+    pub fn remove_through(&mut self, day: &str) {
+        self.days.retain(|d| d.day.as_str() > day);
+    }
+}
+
+/// What is sent: the counts plus which version and OS they came from.
+#[derive(Debug, Serialize)]
+struct Report<'a> {
+    app_version: &'a str,
+    platform: &'a str,
+    days: &'a [DayCounts],
+}
+
+fn today() -> String {
+    chrono::Utc::now().format("%Y-%m-%d").to_string()
+}
+
+/// Count `event` for today, if telemetry is on.
+pub fn record(event: Event) {
+    if ENABLED.load(Ordering::Relaxed) {
+        QUEUE.lock().unwrap().record(&today(), event);
+    }
+}
+
+/// Pick up the days left from last time and start (or stop) counting.
+pub fn init(db: &Database, enabled: bool) {
+    let stored = db
+        .get_setting(QUEUE_KEY)
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default();
+    *QUEUE.lock().unwrap() = stored;
+    set_enabled(db, enabled);
+}
+
+/// Turning telemetry off also drops whatever wasn't sent.
+pub fn set_enabled(db: &Database, enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+    if !enabled {
+        *QUEUE.lock().unwrap() = TelemetryQueue::default();
+        if let Err(e) = db.set_setting(QUEUE_KEY, "{\"days\":[]}") {
+            tracing::error!("Failed to clear telemetry queue: {}", e);
+        }
+    }
+}
+
+/// Save the queue, then send the days that are over. Unsent days stay queued
+/// for the next try.
+pub async fn flush(db: &Database, server_url: &str, app_version: &str) {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    let (stored, days) = {
+        let queue = QUEUE.lock().unwrap();
+        (serde_json::to_string(&*queue), queue.finished(&today()))
+    };
+    if let Ok(stored) = stored {
+        if let Err(e) = db.set_setting(QUEUE_KEY, &stored) {
+            tracing::error!("Failed to save telemetry queue: {}", e);
+        }
+    }
+    let Some(last) = days.last() else {
+        return;
+    };
+
+    let report = Report {
+        app_version,
+        platform: std::env::consts::OS,
+        days: &days,
+    };
+    let url = format!("{}/api/telemetry", server_url.trim_end_matches('/'));
+    let sent = reqwest::Client::new()
+        .post(&url)
+        .json(&report)
+        .timeout(std::time::Duration::from_secs(30))
+        .send()
+        .await;
+    match sent {
+        Ok(response) if response.status().is_success() => {
+            tracing::info!("Sent usage counts for {} day(s)", days.len());
+        }
+        // Sending the same report again won't help
+        Ok(response) if response.status().is_client_error() => {
+            tracing::warn!(
+                "Server rejected usage counts: {}",
+                response.status()
+            );
+        }
+        Ok(response) => {
+            tracing::warn!(
+                "Failed to send usage counts: {}",
+                response.status()
+            );
+            return;
+        }
+        Err(e) => {
+            tracing::warn!("Failed to send usage counts: {}", e);
+            return;
+        }
+    }
+    let stored = {
+        let mut queue = QUEUE.lock().unwrap();
+        queue.remove_through(&last.day);
+        serde_json::to_string(&*queue)
+    };
+    if let Ok(stored) = stored {
+        let _ = db.set_setting(QUEUE_KEY, &stored);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_queue_counts_by_day() {
+        let mut queue = TelemetryQueue::default();
+        queue.record("2026-10-14", Event::Capture);
+        queue.record("2026-10-14", Event::Capture);
+        queue.record("2026-10-14", Event::SyncError);
+        queue.record("2026-10-15", Event::SlotPaste);
+
+        let finished = queue.finished("2026-10-15");
+        assert_eq!(finished.len(), 1);
+        assert_eq!(finished[0].captures, 2);
+        assert_eq!(finished[0].sync_errors, 1);
+
+        queue.remove_through("2026-10-14");
+        assert_eq!(queue.finished("2026-10-16")[0].slot_pastes, 1);
+    }
+
+    #[test]
+    fn test_queue_drops_oldest_days() {
+        let mut queue = TelemetryQueue::default();
+        for day in 1..=MAX_QUEUED_DAYS + 5 {
+            let day = format!("2026-{:02}-{:02}", day / 28 + 1, day % 28 + 1);
+            queue.record(&day, Event::Capture);
+        }
+        assert_eq!(queue.days.len(), MAX_QUEUED_DAYS);
+        assert_eq!(queue.days[0].day, "2026-01-07");
+    }
+}
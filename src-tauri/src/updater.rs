@@ -0,0 +1,64 @@
+use std::sync::Mutex;
+
+use serde::Serialize;
+use tauri::AppHandle;
+use tauri_plugin_updater::UpdaterExt;
+
+use crate::config::UPDATER_PUBKEY;
+
+/// A newer release than the running build.
+#[derive(Debug, Clone, Serialize)]
+pub struct UpdateInfo {
+    pub version: String,
+    pub current_version: String,
+    /// Release notes, if the release has any
+    pub notes: Option<String>,
+}
+
+/// The update found by the last check, shown in the tray until installed.
+#[derive(Default)]
+pub struct AvailableUpdate(pub Mutex<Option<UpdateInfo>>);
+
+/// Whether this build was given a key to verify updates with.
+pub fn is_enabled() -> bool {
+    UPDATER_PUBKEY.is_some_and(|key| !key.is_empty())
+}
+
+/// Ask the release endpoint for a newer version.
+pub async fn check(app: &AppHandle) -> Result<Option<UpdateInfo>, String> {
+    if !is_enabled() {
+        return Err("Updates are not enabled in this build".to_string());
+    }
+    let update = app
+        .updater()
+        .map_err(|e| e.to_string())?
+        .check()
+        .await
+        .map_err(|e| format!("Update check failed: {}", e))?;
+    Ok(update.map(|u| UpdateInfo {
+        version: u.version.clone(),
+        current_version: u.current_version.clone(),
+        notes: u.body.clone(),
+    }))
+}
+
+/// Download and install the latest release, then restart into it.
+pub async fn install(app: &AppHandle) -> Result<(), String> {
+    if !is_enabled() {
+        return Err("Updates are not enabled in this build".to_string());
+    }
+    let update = app
+        .updater()
+        .map_err(|e| e.to_string())?
+        .check()
+        .await
+        .map_err(|e| format!("Update check failed: {}", e))?
+        .ok_or_else(|| "ClipSlot is up to date".to_string())?;
+
+    tracing::info!("Installing update {}", update.version);
+    update
+        .download_and_install(|_, _| {}, || tracing::info!("Update downloaded"))
+        .await
+        .map_err(|e| format!("Update failed: {}", e))?;
+    app.restart()
+}
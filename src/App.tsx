@@ -1,4 +1,4 @@
-import { useEffect, useState, useCallback, useRef } from "react";
+import { Fragment, useEffect, useState, useCallback, useRef, type ReactNode } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
 import { getCurrentWebviewWindow } from "@tauri-apps/api/webviewWindow";
@@ -12,7 +12,84 @@ interface ClipboardItem {
   source_app: string | null;
   device_id: string;
   created_at: number;
+  // created_at in the user's time zone, e.g. 2026-10-16T13:57:52.123+02:00
+  created_at_iso: string;
   is_promoted: boolean;
+  // Set on list previews, where `content` is only the start of the item
+  content_length?: number;
+  is_truncated?: boolean;
+  // Page title of a link, when looking titles up is enabled
+  link_title?: string;
+  // Programming language, for items that look like code
+  language?: string;
+  // Search results only: UTF-8 byte ranges [start, end) of content that matched
+  matches?: [number, number][];
+}
+
+interface HistoryPage {
+  items: ClipboardItem[];
+  next_cursor: string | null;
+}
+
+// Items fetched per page as the history list scrolls
+const PAGE_SIZE = 100;
+
+// The first `maxLen` characters of `text` with search hits marked. The
+// backend's ranges are UTF-8 byte offsets; JS strings index UTF-16 units.
+function highlightMatches(text: string, matches: [number, number][], maxLen: number) {
+  const bytes = new TextEncoder().encode(text);
+  const decoder = new TextDecoder();
+  const charOffset = (byte: number) => decoder.decode(bytes.subarray(0, byte)).length;
+  const shown = Math.min(text.length, maxLen);
+  const parts: ReactNode[] = [];
+  let last = 0;
+  for (const [startByte, endByte] of matches) {
+    const start = charOffset(startByte);
+    if (start >= shown) break;
+    const end = Math.min(charOffset(endByte), shown);
+    parts.push(text.substring(last, start));
+    parts.push(
+      <mark key={start} className="search-hit">
+        {text.substring(start, end)}
+      </mark>
+    );
+    last = end;
+  }
+  parts.push(text.substring(last, shown));
+  if (text.length > maxLen) parts.push("...");
+  return parts;
+}
+
+const TRANSFORMS: { key: string; label: string }[] = [
+  { key: "uppercase", label: "UPPERCASE" },
+  { key: "lowercase", label: "lowercase" },
+  { key: "slugify", label: "slug-case" },
+  { key: "json_pretty", label: "Pretty JSON" },
+  { key: "json_minify", label: "Minified JSON" },
+  { key: "base64_encode", label: "Base64 Encoded" },
+  { key: "base64_decode", label: "Base64 Decoded" },
+  { key: "url_encode", label: "URL Encoded" },
+  { key: "url_decode", label: "URL Decoded" },
+];
+
+interface DeviceInfo {
+  id: string;
+  name: string;
+}
+
+/** Mirrors `KnownDevice` in src-tauri/src/lib.rs. */
+interface KnownDevice {
+  device_id: string;
+  name: string | null;
+  is_this_device: boolean;
+  item_count: number;
+}
+
+function deviceLabel(device: KnownDevice): string {
+  if (device.is_this_device) {
+    return device.name ? `${device.name} (this device)` : "This device";
+  }
+  return device.name ?? `Device ${device.device_id.slice(0, 8)}`;
 }
 
 interface ContextMenuState {
@@ -24,9 +101,22 @@ interface ContextMenuState {
 
 function App() {
   const [items, setItems] = useState<ClipboardItem[]>([]);
-  const [searchQuery, setSearchQuery] = useState("");
+  // clipslot://search links open the window with a query
+  const [searchQuery, setSearchQuery] = useState(
+    () => new URLSearchParams(window.location.search).get("q") ?? ""
+  );
+  const [regexSearch, setRegexSearch] = useState(false);
+  // Languages of code snippets in history, and the one being filtered on
+  const [languages, setLanguages] = useState<string[]>([]);
+  const [languageFilter, setLanguageFilter] = useState("");
+  // Devices history was copied on, and the one being filtered on
+  const [knownDevices, setKnownDevices] = useState<KnownDevice[]>([]);
+  const [deviceFilter, setDeviceFilter] = useState("");
+  const [searchError, setSearchError] = useState<string | null>(null);
   const [copiedId, setCopiedId] = useState<string | null>(null);
   const [count, setCount] = useState(0);
+  const [nextCursor, setNextCursor] = useState<string | null>(null);
+  const loadingMore = useRef(false);
   const [selectedIndex, setSelectedIndex] = useState(-1);
   const [contextMenu, setContextMenu] = useState<ContextMenuState>({
     visible: false,
@@ -35,46 +125,165 @@ function App() {
     item: null,
   });
 
+  // Sharing needs an account; other devices on it are listed for "Send to Device"
+  const [loggedIn, setLoggedIn] = useState(false);
+  // data: URL of the QR code being shown, if any
+  const [qrImage, setQrImage] = useState<string | null>(null);
+  // "Compare with...": the item picked first, then the diff against the second
+  const [compareFrom, setCompareFrom] = useState<ClipboardItem | null>(null);
+  const [diffText, setDiffText] = useState<string | null>(null);
+  const [devices, setDevices] = useState<DeviceInfo[]>([]);
+  // Set when the keychain withheld the encryption key, so history isn't saved
+  const [keyError, setKeyError] = useState<string | null>(null);
+
   const listRef = useRef<HTMLDivElement>(null);
   const itemRefs = useRef<(HTMLDivElement | null)[]>([]);
 
   const loadHistory = useCallback(async () => {
     try {
-      if (searchQuery.trim()) {
+      if (searchQuery.trim() || languageFilter) {
         const results = await invoke<ClipboardItem[]>("search_history", {
           query: searchQuery,
+          filters: {
+            regex: regexSearch,
+            language: languageFilter || null,
+            device_id: deviceFilter || null,
+          },
         });
         setItems(results);
+        setNextCursor(null);
       } else {
-        const results = await invoke<ClipboardItem[]>(
-          "get_clipboard_history",
-          { limit: 100, offset: 0 }
-        );
-        setItems(results);
+        const page = await invoke<HistoryPage>("get_history_page", {
+          cursor: null,
+          pageSize: PAGE_SIZE,
+          deviceId: deviceFilter || null,
+        });
+        setItems(page.items);
+        setNextCursor(page.next_cursor);
       }
+      setSearchError(null);
       const c = await invoke<number>("get_history_count");
       setCount(c);
+      setLanguages(await invoke<string[]>("get_history_languages"));
+      setKnownDevices(await invoke<KnownDevice[]>("get_known_devices"));
     } catch (e) {
+      // e.g. an invalid regex while it is being typed
+      setSearchError(String(e));
       console.error("Failed to load history:", e);
     }
-  }, [searchQuery]);
+  }, [searchQuery, regexSearch, languageFilter, deviceFilter]);
+
+  // Add new captures to the top without reloading, so the scroll position
+  // and the pages already loaded stay put
+  const loadNewest = useCallback(async () => {
+    if (searchQuery.trim() || languageFilter) {
+      return loadHistory();
+    }
+    try {
+      const page = await invoke<HistoryPage>("get_history_page", {
+        cursor: null,
+        pageSize: PAGE_SIZE,
+        deviceId: deviceFilter || null,
+      });
+      setItems((current) => {
+        const seen = new Set(current.map((i) => i.id));
+        return [...page.items.filter((i) => !seen.has(i.id)), ...current];
+      });
+      setCount(await invoke<number>("get_history_count"));
+    } catch (e) {
+      console.error("Failed to load new history:", e);
+    }
+  }, [searchQuery, languageFilter, deviceFilter, loadHistory]);
+
+  const loadMore = useCallback(async () => {
+    if (!nextCursor || loadingMore.current || searchQuery.trim() || languageFilter) return;
+    loadingMore.current = true;
+    try {
+      const page = await invoke<HistoryPage>("get_history_page", {
+        cursor: nextCursor,
+        pageSize: PAGE_SIZE,
+        deviceId: deviceFilter || null,
+      });
+      setItems((current) => {
+        const seen = new Set(current.map((i) => i.id));
+        return [...current, ...page.items.filter((i) => !seen.has(i.id))];
+      });
+      setNextCursor(page.next_cursor);
+    } catch (e) {
+      console.error("Failed to load more history:", e);
+    } finally {
+      loadingMore.current = false;
+    }
+  }, [nextCursor, searchQuery, languageFilter, deviceFilter]);
+
+  const handleScroll = (e: React.UIEvent<HTMLDivElement>) => {
+    const el = e.currentTarget;
+    if (el.scrollHeight - el.scrollTop - el.clientHeight < 200) {
+      loadMore();
+    }
+  };
 
   useEffect(() => {
     loadHistory();
   }, [loadHistory]);
 
+  useEffect(() => {
+    invoke<string | null>("get_key_error").then(setKeyError);
+  }, []);
+
+  const retryKeychain = async () => {
+    try {
+      // Restarts the app once the key can be read
+      await invoke("retry_keychain");
+    } catch (e) {
+      setKeyError(String(e));
+    }
+  };
+
   useEffect(() => {
     setSelectedIndex(-1);
   }, [searchQuery]);
 
   useEffect(() => {
     const unlisten = listen("clipboard-changed", () => {
-      loadHistory();
+      loadNewest();
     });
     return () => {
       unlisten.then((fn) => fn());
     };
-  }, [loadHistory]);
+  }, [loadNewest]);
+
+  // Pasted and deleted from the tray or quick picker
+  useEffect(() => {
+    const unlisten = listen<string>("history-item-deleted", (event) => {
+      setItems((current) => current.filter((item) => item.id !== event.payload));
+      invoke<number>("get_history_count").then(setCount);
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  useEffect(() => {
+    const unlisten = listen<{ id: string; title: string }>("link-title-fetched", (event) => {
+      const { id, title } = event.payload;
+      setItems((current) =>
+        current.map((i) => (i.id === id ? { ...i, link_title: title } : i))
+      );
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  useEffect(() => {
+    const unlisten = listen<string>("history-search", (event) => {
+      setSearchQuery(event.payload);
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
 
   // Close context menu on click outside
   useEffect(() => {
@@ -85,7 +294,7 @@ function App() {
 
   const handleCopy = async (item: ClipboardItem) => {
     try {
-      await invoke("copy_to_clipboard", { text: item.content });
+      await invoke("copy_history_item", { id: item.id });
       setCopiedId(item.id);
       setTimeout(() => setCopiedId(null), 1500);
     } catch (e) {
@@ -93,10 +302,21 @@ function App() {
     }
   };
 
+  const handleCopyAs = async (item: ClipboardItem, transform: string) => {
+    try {
+      await invoke("transform_and_copy", { idOrText: item.id, transform });
+      setCopiedId(item.id);
+      setTimeout(() => setCopiedId(null), 1500);
+    } catch (e) {
+      console.error("Failed to convert:", e);
+    }
+  };
+
   const handleDelete = async (id: string) => {
     try {
       await invoke("delete_history_item", { id });
-      await loadHistory();
+      setItems((current) => current.filter((i) => i.id !== id));
+      setCount(await invoke<number>("get_history_count"));
     } catch (e) {
       console.error("Failed to delete:", e);
     }
@@ -120,13 +340,87 @@ function App() {
     }
   };
 
+  const loadDevices = async () => {
+    try {
+      const status = await invoke<{ logged_in: boolean; device_id: string | null }>(
+        "get_sync_status"
+      );
+      setLoggedIn(status.logged_in);
+      if (!status.logged_in) {
+        setDevices([]);
+        return;
+      }
+      const result = await invoke<DeviceInfo[]>("get_linked_devices");
+      setDevices(result.filter((d) => d.id !== status.device_id));
+    } catch (e) {
+      console.error("Failed to load devices:", e);
+      setDevices([]);
+    }
+  };
+
+  const handleSendToDevice = async (itemId: string, deviceId: string) => {
+    try {
+      await invoke("send_to_device", { deviceId, itemId });
+      setContextMenu((prev) => ({ ...prev, visible: false }));
+    } catch (e) {
+      console.error("Failed to send to device:", e);
+    }
+  };
+
+  const handleShowQr = async (item: ClipboardItem) => {
+    setContextMenu((prev) => ({ ...prev, visible: false }));
+    try {
+      setQrImage(await invoke<string>("get_item_qr", { id: item.id }));
+    } catch (e) {
+      console.error("Failed to make QR code:", e);
+    }
+  };
+
+  const handleCompare = async (item: ClipboardItem) => {
+    setContextMenu((prev) => ({ ...prev, visible: false }));
+    if (!compareFrom) {
+      setCompareFrom(item);
+      return;
+    }
+    setCompareFrom(null);
+    try {
+      const diff = await invoke<string>("diff_items", { idA: compareFrom.id, idB: item.id });
+      setDiffText(diff || "The items are the same.");
+    } catch (e) {
+      setDiffText(String(e));
+    }
+  };
+
+  const handleShareLink = async (item: ClipboardItem) => {
+    setContextMenu((prev) => ({ ...prev, visible: false }));
+    try {
+      const link = await invoke<{ url: string; expires_at: string }>("create_share_link", {
+        itemId: item.id,
+      });
+      await invoke("copy_to_clipboard", { text: link.url });
+      setCopiedId(item.id);
+      setTimeout(() => setCopiedId(null), 1500);
+    } catch (e) {
+      console.error("Failed to create share link:", e);
+    }
+  };
+
   const handleContextMenu = (e: React.MouseEvent, item: ClipboardItem) => {
     e.preventDefault();
     e.stopPropagation();
     setContextMenu({ visible: true, x: e.clientX, y: e.clientY, item });
+    loadDevices();
   };
 
   const handleKeyDown = (e: React.KeyboardEvent) => {
+    if (qrImage) {
+      if (e.key === "Escape") setQrImage(null);
+      return;
+    }
+    if (diffText !== null) {
+      if (e.key === "Escape") setDiffText(null);
+      return;
+    }
     if (contextMenu.visible) {
       if (e.key === "Escape") {
         setContextMenu((prev) => ({ ...prev, visible: false }));
@@ -175,11 +469,26 @@ function App() {
     return date.toLocaleDateString();
   };
 
+  // The history list is divided at local midnight
+  const dayLabel = (timestamp: number) => {
+    const date = new Date(timestamp);
+    const today = new Date();
+    const yesterday = new Date(today.getFullYear(), today.getMonth(), today.getDate() - 1);
+    if (date.toDateString() === today.toDateString()) return "Today";
+    if (date.toDateString() === yesterday.toDateString()) return "Yesterday";
+    return date.toLocaleDateString();
+  };
+
   const truncate = (text: string, maxLen: number) => {
     if (text.length <= maxLen) return text;
     return text.substring(0, maxLen) + "...";
   };
 
+  const preview = (item: ClipboardItem, maxLen: number) =>
+    item.matches?.length
+      ? highlightMatches(item.content, item.matches, maxLen)
+      : truncate(item.content, maxLen);
+
   return (
     <div className="history-container" onKeyDown={handleKeyDown} tabIndex={0}>
       <div className="history-header">
@@ -187,6 +496,15 @@ function App() {
         <span className="count">{count} items</span>
       </div>
 
+      {keyError && (
+        <div className="key-error">
+          <span>
+            History isn't being saved: ClipSlot couldn't get its encryption key. {keyError}
+          </span>
+          <button onClick={retryKeychain}>Try Again</button>
+        </div>
+      )}
+
       <div className="search-bar">
         <input
           type="text"
@@ -195,50 +513,106 @@ function App() {
           onChange={(e) => setSearchQuery(e.target.value)}
           autoFocus
         />
+        <button
+          className={`regex-toggle${regexSearch ? " active" : ""}`}
+          onClick={() => setRegexSearch((r) => !r)}
+          title="Regular expression"
+        >
+          .*
+        </button>
+        {languages.length > 0 && (
+          <select
+            className="language-filter"
+            value={languageFilter}
+            onChange={(e) => setLanguageFilter(e.target.value)}
+            title="Language"
+          >
+            <option value="">All</option>
+            {languages.map((language) => (
+              <option key={language} value={language}>
+                {language}
+              </option>
+            ))}
+          </select>
+        )}
+        {knownDevices.length > 1 && (
+          <select
+            className="language-filter"
+            value={deviceFilter}
+            onChange={(e) => setDeviceFilter(e.target.value)}
+            title="Copied on"
+          >
+            <option value="">All devices</option>
+            {knownDevices.map((device) => (
+              <option key={device.device_id} value={device.device_id}>
+                {deviceLabel(device)}
+              </option>
+            ))}
+          </select>
+        )}
       </div>
+      {searchError && <div className="search-error">{searchError}</div>}
 
-      <div className="history-list" ref={listRef}>
+      <div className="history-list" ref={listRef} onScroll={handleScroll}>
         {items.length === 0 ? (
           <div className="empty-state">
-            {searchQuery ? "No matching items" : "No clipboard history yet"}
+            {searchQuery || languageFilter || deviceFilter
+              ? "No matching items"
+              : "No clipboard history yet"}
           </div>
         ) : (
           items.map((item, index) => (
-            <div
-              key={item.id}
-              ref={(el) => { itemRefs.current[index] = el; }}
-              className={`history-item${copiedId === item.id ? " copied" : ""}${
-                selectedIndex === index ? " selected" : ""
-              }`}
-              onClick={() => {
-                setSelectedIndex(index);
-                handleCopy(item);
-              }}
-              onContextMenu={(e) => handleContextMenu(e, item)}
-            >
-              <div className="item-content">
-                {truncate(item.content, 120)}
-              </div>
-              <div className="item-meta">
-                <span className="item-time">{formatTime(item.created_at)}</span>
-                <span className="item-size">
-                  {item.content.length} chars
-                </span>
-                {copiedId === item.id && (
-                  <span className="copied-badge">Copied!</span>
+            <Fragment key={item.id}>
+              {!searchQuery &&
+                (index === 0 ||
+                  dayLabel(item.created_at) !== dayLabel(items[index - 1].created_at)) && (
+                  <div className="day-divider">{dayLabel(item.created_at)}</div>
                 )}
-                <button
-                  className="delete-btn"
-                  onClick={(e) => {
-                    e.stopPropagation();
-                    handleDelete(item.id);
-                  }}
-                  title="Delete"
-                >
-                  x
-                </button>
+              <div
+                ref={(el) => { itemRefs.current[index] = el; }}
+                className={`history-item${copiedId === item.id ? " copied" : ""}${
+                  selectedIndex === index ? " selected" : ""
+                }`}
+                onClick={() => {
+                  setSelectedIndex(index);
+                  handleCopy(item);
+                }}
+                onContextMenu={(e) => handleContextMenu(e, item)}
+              >
+                {item.link_title ? (
+                  <div className="item-content" title={item.content}>
+                    <span className="item-link-title">{item.link_title}</span>
+                    <span className="item-link-url">{preview(item, 80)}</span>
+                  </div>
+                ) : (
+                  <div className={`item-content${item.language ? " item-code" : ""}`}>
+                    {preview(item, 120)}
+                  </div>
+                )}
+                <div className="item-meta">
+                  <span className="item-time" title={item.created_at_iso}>
+                    {formatTime(item.created_at)}
+                  </span>
+                  <span className="item-size">
+                    {item.content_length ?? item.content.length} chars
+                  </span>
+                  {item.language && <span className="item-language">{item.language}</span>}
+                  {copiedId === item.id && (
+                    <span className="copied-badge">Copied!</span>
+                  )}
+                  <button
+                    className="delete-btn"
+                    onClick={(e) => {
+                      e.stopPropagation();
+                      handleDelete(item.id);
+                    }}
+                    title="Delete"
+                  >
+                    x
+                  </button>
+                </div>
               </div>
-            </div>
+            </Fragment>
           ))
         )}
       </div>
@@ -251,6 +625,29 @@ function App() {
         </div>
       )}
 
+      {diffText !== null && (
+        <div className="qr-overlay" onClick={() => setDiffText(null)}>
+          <pre className="diff-view" onClick={(e) => e.stopPropagation()}>
+            {diffText.split("\n").map((line, i) => (
+              <div
+                key={i}
+                className={
+                  line.startsWith("+") ? "diff-added" : line.startsWith("-") ? "diff-removed" : ""
+                }
+              >
+                {line}
+              </div>
+            ))}
+          </pre>
+        </div>
+      )}
+
+      {qrImage && (
+        <div className="qr-overlay" onClick={() => setQrImage(null)}>
+          <img className="qr-image" src={qrImage} alt="QR code of the item" />
+        </div>
+      )}
+
       {/* Context Menu */}
       {contextMenu.visible && contextMenu.item && (
         <div
@@ -267,6 +664,31 @@ function App() {
           >
             Copy
           </div>
+          <div className="context-menu-item context-submenu">
+            Copy As
+            <div className="context-menu">
+              {TRANSFORMS.map((t) => (
+                <div
+                  key={t.key}
+                  className="context-menu-item"
+                  onClick={() => {
+                    handleCopyAs(contextMenu.item!, t.key);
+                    setContextMenu((prev) => ({ ...prev, visible: false }));
+                  }}
+                >
+                  {t.label}
+                </div>
+              ))}
+            </div>
+          </div>
+          <div className="context-menu-item" onClick={() => handleShowQr(contextMenu.item!)}>
+            Show QR Code
+          </div>
+          {compareFrom?.id !== contextMenu.item.id && (
+            <div className="context-menu-item" onClick={() => handleCompare(contextMenu.item!)}>
+              {compareFrom ? "Compare with Chosen Item" : "Compare with..."}
+            </div>
+          )}
           <div className="context-menu-separator" />
           {[1, 2, 3, 4, 5].map((n) => (
             <div
@@ -277,6 +699,35 @@ function App() {
               Save to Slot {n}
             </div>
           ))}
+          {loggedIn && (
+            <>
+              <div className="context-menu-separator" />
+              <div
+                className="context-menu-item"
+                onClick={() => handleShareLink(contextMenu.item!)}
+              >
+                Copy One-Time Link
+              </div>
+            </>
+          )}
+          {devices.length > 0 && (
+            <>
+              <div className="context-menu-item context-submenu">
+                Send to Device
+                <div className="context-menu">
+                  {devices.map((d) => (
+                    <div
+                      key={d.id}
+                      className="context-menu-item"
+                      onClick={() => handleSendToDevice(contextMenu.item!.id, d.id)}
+                    >
+                      {d.name}
+                    </div>
+                  ))}
+                </div>
+              </div>
+            </>
+          )}
           <div className="context-menu-separator" />
           <div
             className="context-menu-item danger"
@@ -0,0 +1,58 @@
+import { useEffect } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { getCurrentWebviewWindow } from "@tauri-apps/api/webviewWindow";
+import "../App.css";
+
+function formatSize(bytes: number): string {
+  if (bytes >= 1024 * 1024) return `${(bytes / (1024 * 1024)).toFixed(1)} MB`;
+  return `${Math.round(bytes / 1024)} KB`;
+}
+
+// Asks before a large slot is pasted by its shortcut. Enter pastes into the
+// app behind the window, which the backend closes first; Escape or clicking
+// elsewhere cancels.
+export default function ConfirmPaste() {
+  const params = new URLSearchParams(window.location.search);
+  const slotNumber = Number(params.get("slot"));
+  const name = params.get("name") ?? `Slot ${slotNumber}`;
+  const size = Number(params.get("size"));
+
+  const cancel = () => getCurrentWebviewWindow().close();
+
+  const paste = async () => {
+    try {
+      await invoke("confirm_slot_paste", { slotNumber });
+    } catch (e) {
+      console.error("Failed to paste slot:", e);
+      cancel();
+    }
+  };
+
+  useEffect(() => {
+    const unlisten = getCurrentWebviewWindow().onFocusChanged(({ payload: focused }) => {
+      if (!focused) cancel();
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  const handleKeyDown = (e: React.KeyboardEvent) => {
+    if (e.key === "Enter") paste();
+    if (e.key === "Escape") cancel();
+  };
+
+  return (
+    <div className="confirm-paste" onKeyDown={handleKeyDown}>
+      <p>
+        {name} holds {formatSize(size)}. Paste it?
+      </p>
+      <div className="confirm-paste-actions">
+        <button onClick={cancel}>Cancel</button>
+        <button className="primary" onClick={paste} autoFocus>
+          Paste
+        </button>
+      </div>
+    </div>
+  );
+}
@@ -0,0 +1,115 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import App from "../App";
+import "../App.css";
+import "./mobile.css";
+
+interface SlotSummary {
+  slot_number: number;
+  name: string;
+  content_preview: string | null;
+  updated_at: number;
+  is_empty: boolean;
+}
+
+interface SlotInfo extends SlotSummary {
+  content: string | null;
+}
+
+type Tab = "slots" | "history";
+
+// Phones can't watch the clipboard or take shortcuts, so the app is for
+// looking at synced slots and history, copying from them, and adding the
+// clipboard by hand.
+export default function MobileApp() {
+  const [tab, setTab] = useState<Tab>("slots");
+
+  return (
+    <div className="mobile-container">
+      <div className="mobile-content">{tab === "slots" ? <SlotList /> : <App />}</div>
+      <nav className="mobile-tabs">
+        <button className={tab === "slots" ? "active" : ""} onClick={() => setTab("slots")}>
+          Slots
+        </button>
+        <button className={tab === "history" ? "active" : ""} onClick={() => setTab("history")}>
+          History
+        </button>
+      </nav>
+    </div>
+  );
+}
+
+function SlotList() {
+  const [slots, setSlots] = useState<SlotSummary[]>([]);
+  const [copied, setCopied] = useState<number | null>(null);
+  const [message, setMessage] = useState<string | null>(null);
+
+  const loadSlots = async () => {
+    try {
+      setSlots(await invoke<SlotSummary[]>("get_all_slots"));
+    } catch (e) {
+      console.error("Failed to load slots:", e);
+    }
+  };
+
+  useEffect(() => {
+    loadSlots();
+    const unlisten = listen("slot-changed", loadSlots);
+    return () => {
+      unlisten.then((f) => f());
+    };
+  }, []);
+
+  const copySlot = async (slot: SlotSummary) => {
+    if (slot.is_empty) return;
+    try {
+      const { content } = await invoke<SlotInfo>("get_slot", { slotNumber: slot.slot_number });
+      if (content === null) return;
+      await invoke("copy_to_clipboard", { text: content });
+      setCopied(slot.slot_number);
+      setTimeout(() => setCopied(null), 1500);
+    } catch (e) {
+      console.error("Failed to copy slot:", e);
+    }
+  };
+
+  const addClipboard = async () => {
+    try {
+      const item = await invoke<unknown | null>("capture_clipboard");
+      setMessage(item ? "Added to history" : "Nothing new on the clipboard");
+    } catch (e) {
+      setMessage(String(e));
+    }
+    setTimeout(() => setMessage(null), 2000);
+  };
+
+  return (
+    <div className="history-container">
+      <div className="history-header">
+        <h2>Slots</h2>
+        <button className="mobile-capture-btn" onClick={addClipboard}>
+          Add clipboard
+        </button>
+      </div>
+      {message && <div className="mobile-message">{message}</div>}
+      <div className="history-list">
+        {slots.map((slot) => (
+          <div
+            key={slot.slot_number}
+            className={`history-item ${copied === slot.slot_number ? "copied" : ""}`}
+            onClick={() => copySlot(slot)}
+          >
+            <div className="item-content">
+              {slot.is_empty ? "(empty)" : slot.content_preview}
+            </div>
+            <div className="item-meta">
+              <span>{slot.name}</span>
+              {copied === slot.slot_number && <span className="copied-badge">Copied!</span>}
+            </div>
+          </div>
+        ))}
+      </div>
+    </div>
+  );
+}
@@ -0,0 +1,189 @@
+import { useEffect, useState, useCallback, useRef } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { getCurrentWebviewWindow } from "@tauri-apps/api/webviewWindow";
+import { getSettings } from "./Settings/settingsApi";
+import "../App.css";
+
+interface ClipboardItem {
+  id: string;
+  content: string;
+  created_at: number;
+}
+
+// Compact history picker opened from the tray icon. Picking an item copies
+// it and hides the window; so does losing focus. Tab marks items to paste in
+// turn, e.g. into a form's fields.
+export default function QuickPicker() {
+  const [items, setItems] = useState<ClipboardItem[]>([]);
+  const [query, setQuery] = useState("");
+  const [selectedIndex, setSelectedIndex] = useState(0);
+  // IDs of marked items, in the order they'll be pasted
+  const [marked, setMarked] = useState<string[]>([]);
+
+  const inputRef = useRef<HTMLInputElement>(null);
+  const itemRefs = useRef<(HTMLDivElement | null)[]>([]);
+
+  const loadItems = useCallback(async () => {
+    try {
+      let results: ClipboardItem[];
+      if (query.trim()) {
+        results = await invoke<ClipboardItem[]>("search_history", { query });
+      } else if ((await getSettings()).picker_order === "frequent") {
+        results = await invoke<ClipboardItem[]>("get_frequent_items", { limit: 50 });
+      } else {
+        results = await invoke<ClipboardItem[]>("get_history_previews", { limit: 50, offset: 0 });
+      }
+      setItems(results);
+      setSelectedIndex(0);
+    } catch (e) {
+      console.error("Failed to load history:", e);
+    }
+  }, [query]);
+
+  useEffect(() => {
+    loadItems();
+  }, [loadItems]);
+
+  // The window is reused: start fresh each time it is shown, hide when left
+  useEffect(() => {
+    const picker = getCurrentWebviewWindow();
+    const unlisten = picker.onFocusChanged(({ payload: focused }) => {
+      if (focused) {
+        setQuery("");
+        setMarked([]);
+        loadItems();
+        inputRef.current?.focus();
+      } else {
+        picker.close();
+      }
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, [loadItems]);
+
+  const pick = async (item: ClipboardItem) => {
+    try {
+      await invoke("copy_history_item", { id: item.id });
+    } catch (e) {
+      console.error("Failed to copy:", e);
+    }
+    getCurrentWebviewWindow().close();
+  };
+
+  // Pastes into the app behind the picker and forgets the item, e.g. for
+  // one-time codes; the picker is closed by the backend before pasting
+  const pasteAndRemove = async (item: ClipboardItem) => {
+    try {
+      await invoke("paste_and_remove", { id: item.id });
+    } catch (e) {
+      console.error("Failed to paste and delete:", e);
+    }
+  };
+
+  const toggleMarked = (item: ClipboardItem) => {
+    setMarked((prev) =>
+      prev.includes(item.id) ? prev.filter((id) => id !== item.id) : [...prev, item.id]
+    );
+  };
+
+  // Pastes the marked items into the app behind the picker, pressing Tab
+  // between them; the backend closes the picker first
+  const pasteMarked = async () => {
+    try {
+      await invoke("paste_items_sequentially", {
+        ids: marked,
+        delayMs: 100,
+        separatorKey: "tab",
+      });
+    } catch (e) {
+      console.error("Failed to paste items:", e);
+    }
+  };
+
+  const handleKeyDown = (e: React.KeyboardEvent) => {
+    switch (e.key) {
+      case "ArrowDown":
+        e.preventDefault();
+        setSelectedIndex((prev) => {
+          const next = Math.min(prev + 1, items.length - 1);
+          itemRefs.current[next]?.scrollIntoView({ block: "nearest" });
+          return next;
+        });
+        break;
+      case "ArrowUp":
+        e.preventDefault();
+        setSelectedIndex((prev) => {
+          const next = Math.max(prev - 1, 0);
+          itemRefs.current[next]?.scrollIntoView({ block: "nearest" });
+          return next;
+        });
+        break;
+      case "Tab":
+        e.preventDefault();
+        if (items[selectedIndex]) toggleMarked(items[selectedIndex]);
+        break;
+      case "Enter":
+        if (marked.length > 0) {
+          pasteMarked();
+        } else if (items[selectedIndex]) {
+          if (e.shiftKey) {
+            pasteAndRemove(items[selectedIndex]);
+          } else {
+            pick(items[selectedIndex]);
+          }
+        }
+        break;
+      case "Escape":
+        getCurrentWebviewWindow().close();
+        break;
+    }
+  };
+
+  const truncate = (text: string, maxLen: number) => {
+    if (text.length <= maxLen) return text;
+    return text.substring(0, maxLen) + "...";
+  };
+
+  return (
+    <div className="history-container" onKeyDown={handleKeyDown}>
+      <div className="search-bar">
+        <input
+          ref={inputRef}
+          type="text"
+          placeholder={
+            marked.length > 0
+              ? `${marked.length} marked (Enter pastes them in turn)`
+              : "Search history (Shift+Enter pastes and deletes, Tab marks)"
+          }
+          value={query}
+          onChange={(e) => setQuery(e.target.value)}
+          autoFocus
+        />
+      </div>
+
+      <div className="history-list">
+        {items.length === 0 ? (
+          <div className="empty-state">
+            {query ? "No matching items" : "No clipboard history yet"}
+          </div>
+        ) : (
+          items.map((item, index) => (
+            <div
+              key={item.id}
+              ref={(el) => { itemRefs.current[index] = el; }}
+              className={`history-item${selectedIndex === index ? " selected" : ""}`}
+              onClick={() => pick(item)}
+              onMouseEnter={() => setSelectedIndex(index)}
+            >
+              {marked.includes(item.id) && (
+                <span className="mark-badge">{marked.indexOf(item.id) + 1}</span>
+              )}
+              <div className="item-content">{truncate(item.content, 80)}</div>
+            </div>
+          ))
+        )}
+      </div>
+    </div>
+  );
+}
@@ -1,5 +1,19 @@
 import { useEffect, useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import {
+  getSettings,
+  updateSettings,
+  type HistorySyncFilter,
+  type SyncContentKind,
+} from "./settingsApi";
+
+const CONTENT_KINDS: [SyncContentKind, string][] = [
+  ["text", "Text"],
+  ["link", "Links"],
+  ["code", "Code"],
+  ["file", "Images & files"],
+];
 
 interface SyncState {
   status: string;
@@ -7,16 +21,32 @@ interface SyncState {
   email: string | null;
   device_id: string | null;
   history_sync_enabled: boolean;
+  search_index_enabled: boolean;
+  pending_changes: number;
 }
 
 interface DeviceInfo {
   id: string;
   name: string;
   device_type: string;
+  app_version: string | null;
+  os_version: string | null;
+  hostname: string | null;
   last_seen: string;
   created_at: string;
 }
 
+// Sent as `device-link-status` while linking a device
+type LinkStatus =
+  | { state: "waiting"; code: string; expires_in_secs: number }
+  | { state: "expired" }
+  | { state: "redeeming" }
+  | { state: "linked" }
+  | { state: "failed"; error: string };
+
+const formatCountdown = (secs: number) =>
+  `${Math.floor(secs / 60)}:${String(secs % 60).padStart(2, "0")}`;
+
 export default function AccountTab() {
   const [syncState, setSyncState] = useState<SyncState | null>(null);
   const [devices, setDevices] = useState<DeviceInfo[]>([]);
@@ -26,10 +56,16 @@ export default function AccountTab() {
   const [error, setError] = useState("");
   const [loading, setLoading] = useState(false);
   const [linkCode, setLinkCode] = useState("");
+  const [linkExpiresIn, setLinkExpiresIn] = useState(0);
   const [linkCodeInput, setLinkCodeInput] = useState("");
   const [linkLoading, setLinkLoading] = useState(false);
   const [linkError, setLinkError] = useState("");
   const [linkSuccess, setLinkSuccess] = useState("");
+  const [pullingId, setPullingId] = useState<string | null>(null);
+  const [pullStatus, setPullStatus] = useState("");
+  const [pauseStatus, setPauseStatus] = useState("");
+  const [syncFilter, setSyncFilter] = useState<HistorySyncFilter | null>(null);
+  const [syncFilterError, setSyncFilterError] = useState<string | null>(null);
 
   const loadStatus = async () => {
     try {
@@ -54,6 +90,98 @@ export default function AccountTab() {
 
   useEffect(() => {
     loadStatus();
+    getSettings().then((settings) => setSyncFilter(settings.history_sync_filter));
+  }, []);
+
+  const saveSyncFilter = async (changes: Partial<HistorySyncFilter>) => {
+    if (!syncFilter) return;
+    const prev = syncFilter;
+    const next = { ...syncFilter, ...changes };
+    setSyncFilter(next);
+    setSyncFilterError(null);
+    try {
+      await updateSettings({ history_sync_filter: next });
+    } catch (e) {
+      setSyncFilterError(String(e));
+      setSyncFilter(prev);
+    }
+  };
+
+  const toggleContentKind = (kind: SyncContentKind) => {
+    if (!syncFilter) return;
+    const content_kinds = syncFilter.content_kinds.includes(kind)
+      ? syncFilter.content_kinds.filter((k) => k !== kind)
+      : [...syncFilter.content_kinds, kind];
+    saveSyncFilter({ content_kinds });
+  };
+
+  const pauseEverywhere = async (paused: boolean) => {
+    setPauseStatus("");
+    try {
+      await invoke("pause_everywhere", { paused });
+      setPauseStatus(
+        paused ? "Monitoring paused on all devices." : "Monitoring resumed on all devices."
+      );
+    } catch (err) {
+      setPauseStatus(`Changed on this device only: ${err}`);
+    }
+  };
+
+  const pullClipboard = async (device: DeviceInfo) => {
+    setPullingId(device.id);
+    setPullStatus("");
+    try {
+      const text = await invoke<string | null>("pull_device_clipboard", {
+        deviceId: device.id,
+      });
+      setPullStatus(
+        text === null
+          ? `${device.name} has nothing to share right now.`
+          : `Copied from ${device.name}: ${text.length > 80 ? text.slice(0, 80) + "..." : text}`
+      );
+    } catch (err) {
+      setPullStatus(String(err));
+    } finally {
+      setPullingId(null);
+    }
+  };
+
+  useEffect(() => {
+    const unlisten = listen<string>("sync-status", (event) => {
+      setSyncState((prev) => (prev ? { ...prev, status: event.payload } : prev));
+    });
+    const unlistenPending = listen<number>("sync-pending-changes", (event) => {
+      setSyncState((prev) => (prev ? { ...prev, pending_changes: event.payload } : prev));
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+      unlistenPending.then((fn) => fn());
+    };
+  }, []);
+
+  useEffect(() => {
+    const unlisten = listen<LinkStatus>("device-link-status", (event) => {
+      const status = event.payload;
+      switch (status.state) {
+        case "waiting":
+          setLinkCode(status.code);
+          setLinkExpiresIn(status.expires_in_secs);
+          break;
+        case "expired":
+          setLinkCode("");
+          setLinkError("The link code expired. Generate a new one.");
+          break;
+        case "linked":
+          setLinkSuccess("Key imported successfully. Please restart ClipSlot.");
+          break;
+        case "failed":
+          setLinkError(status.error);
+          break;
+      }
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
   }, []);
 
   const handleSubmit = async (e: React.FormEvent) => {
@@ -154,7 +282,19 @@ export default function AccountTab() {
       ? "#4caf50"
       : syncState.status === "Syncing" || syncState.status === "Connecting"
         ? "#ff9800"
-        : "#999";
+        : syncState.status === "Offline"
+          ? "#f44336"
+          : "#999";
+
+  // e.g. "Working offline, 3 changes queued"
+  const statusText =
+    syncState.status !== "Offline"
+      ? syncState.status
+      : syncState.pending_changes === 0
+        ? "Working offline"
+        : `Working offline, ${syncState.pending_changes} ${
+            syncState.pending_changes === 1 ? "change" : "changes"
+          } queued`;
 
   return (
     <div className="settings-tab">
@@ -165,7 +305,7 @@ export default function AccountTab() {
         </p>
         <div className="sync-status-row">
           <span className="sync-dot" style={{ background: statusColor }} />
-          <span className="setting-hint">{syncState.status}</span>
+          <span className="setting-hint">{statusText}</span>
           <button
             className="setting-btn"
             style={{ marginLeft: "auto", fontSize: 12 }}
@@ -214,6 +354,82 @@ export default function AccountTab() {
             Enable history sync
           </label>
         </div>
+        <div className="setting-row">
+          <label className="toggle-label">
+            <input
+              type="checkbox"
+              checked={syncState.search_index_enabled}
+              disabled={!syncState.history_sync_enabled}
+              onChange={async (e) => {
+                const enabled = e.target.checked;
+                try {
+                  await invoke("toggle_search_index", { enabled });
+                  setSyncState((prev) =>
+                    prev ? { ...prev, search_index_enabled: enabled } : prev
+                  );
+                } catch (err) {
+                  console.error("Failed to toggle search index:", err);
+                }
+              }}
+            />
+            Make synced history searchable on the server
+          </label>
+        </div>
+        <p className="setting-description">
+          Uploads keyed word hashes so other clients can search without the server
+          learning your text. It can tell which items share a word.
+        </p>
+        {syncFilter && (
+          <>
+            <p className="setting-description">
+              Only upload these items. Others stay on this device; items already
+              skipped aren't uploaded if you loosen the filter later.
+            </p>
+            <div className="setting-row">
+              {CONTENT_KINDS.map(([kind, label]) => (
+                <label key={kind} className="schedule-day">
+                  <input
+                    type="checkbox"
+                    checked={syncFilter.content_kinds.includes(kind)}
+                    disabled={!syncState.history_sync_enabled}
+                    onChange={() => toggleContentKind(kind)}
+                  />
+                  {label}
+                </label>
+              ))}
+            </div>
+            <div className="setting-row">
+              <label className="toggle-label">
+                <input
+                  type="checkbox"
+                  checked={syncFilter.exclude_sensitive}
+                  disabled={!syncState.history_sync_enabled}
+                  onChange={(e) => saveSyncFilter({ exclude_sensitive: e.target.checked })}
+                />
+                Keep items that look like passwords, keys or card numbers local
+              </label>
+            </div>
+            <div className="setting-row">
+              <span className="setting-hint">Largest item</span>
+              <input
+                type="number"
+                className="setting-input number-input"
+                value={syncFilter.max_size_kb}
+                min={0}
+                disabled={!syncState.history_sync_enabled}
+                onChange={(e) =>
+                  setSyncFilter({
+                    ...syncFilter,
+                    max_size_kb: Math.max(0, parseInt(e.target.value, 10) || 0),
+                  })
+                }
+                onBlur={() => saveSyncFilter({ max_size_kb: syncFilter.max_size_kb })}
+              />
+              <span className="setting-hint">KB (0 for no limit)</span>
+            </div>
+            {syncFilterError && <p className="auth-error">{syncFilterError}</p>}
+          </>
+        )}
       </div>
 
       <div className="setting-group">
@@ -226,12 +442,47 @@ export default function AccountTab() {
               <li key={device.id} className="app-list-item">
                 <span>
                   {device.name}{" "}
-                  <span className="setting-hint">({device.device_type})</span>
+                  <span className="setting-hint">
+                    ({[
+                      device.os_version ?? device.device_type,
+                      device.hostname !== device.name ? device.hostname : null,
+                      device.app_version && `v${device.app_version}`,
+                    ]
+                      .filter(Boolean)
+                      .join(" · ")})
+                  </span>
                 </span>
+                {device.id !== syncState.device_id && (
+                  <button
+                    className="setting-btn"
+                    disabled={pullingId !== null}
+                    onClick={() => pullClipboard(device)}
+                  >
+                    {pullingId === device.id ? "Fetching..." : "Fetch Clipboard"}
+                  </button>
+                )}
               </li>
             ))}
           </ul>
         )}
+        {pullStatus && <p className="setting-hint">{pullStatus}</p>}
+      </div>
+
+      <div className="setting-group">
+        <label className="setting-label">Pause Everywhere</label>
+        <p className="setting-description">
+          Stop capturing on all your devices at once, e.g. before sharing your
+          screen or handling sensitive data.
+        </p>
+        <div className="setting-row">
+          <button className="setting-btn" onClick={() => pauseEverywhere(true)}>
+            Pause All Devices
+          </button>
+          <button className="setting-btn" onClick={() => pauseEverywhere(false)}>
+            Resume All Devices
+          </button>
+        </div>
+        {pauseStatus && <p className="setting-hint">{pauseStatus}</p>}
       </div>
 
       <div className="setting-group">
@@ -249,7 +500,7 @@ export default function AccountTab() {
               setLinkError("");
               setLinkCode("");
               try {
-                const code = await invoke<string>("generate_link_code");
+                const code = await invoke<string>("generate_device_link_code");
                 setLinkCode(code);
               } catch (err) {
                 setLinkError(String(err));
@@ -265,7 +516,8 @@ export default function AccountTab() {
             <div className="link-code-display">
               <span className="link-code">{linkCode}</span>
               <p className="setting-hint">
-                Enter this code on your other device within 5 minutes.
+                Enter this code on your other device. It expires in{" "}
+                {formatCountdown(linkExpiresIn)}.
               </p>
             </div>
           )}
@@ -293,7 +545,7 @@ export default function AccountTab() {
                 setLinkError("");
                 setLinkSuccess("");
                 try {
-                  await invoke("enter_link_code", { code: linkCodeInput });
+                  await invoke("redeem_device_link_code", { code: linkCodeInput });
                   setLinkSuccess(
                     "Key imported successfully. Please restart ClipSlot."
                   );
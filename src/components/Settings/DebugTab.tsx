@@ -0,0 +1,202 @@
+import { useEffect, useRef, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import {
+  getSettings,
+  updateSettings,
+  type LogLevel,
+  type LogRedaction,
+} from "./settingsApi";
+
+// Lines kept in the console; older ones scroll away
+const MAX_LINES = 1000;
+
+const LOG_LEVELS: { value: LogLevel; label: string }[] = [
+  { value: "error", label: "Errors only" },
+  { value: "warning", label: "Warnings and errors" },
+  { value: "info", label: "Normal" },
+  { value: "debug", label: "Debug (verbose)" },
+];
+
+const LOG_REDACTIONS: { value: LogRedaction; label: string }[] = [
+  { value: "lengths", label: "Clipboard content: length only" },
+  { value: "hashes", label: "Clipboard content: hash only" },
+  { value: "off", label: "Clipboard content: first 50 characters" },
+];
+
+interface CrashReport {
+  file_name: string;
+  created_at: number;
+  message: string;
+}
+
+function lineClass(line: string): string {
+  if (line.includes("] ERROR ")) return "log-line log-error";
+  if (line.includes("] WARN ")) return "log-line log-warning";
+  return "log-line";
+}
+
+export default function DebugTab() {
+  const [lines, setLines] = useState<string[]>([]);
+  const [logLevel, setLogLevel] = useState<LogLevel>("info");
+  const [logPath, setLogPath] = useState<string | null>(null);
+  const [redaction, setRedaction] = useState<LogRedaction>("lengths");
+  const [diagnosticsStatus, setDiagnosticsStatus] = useState<string | null>(null);
+  const [crashReports, setCrashReports] = useState<CrashReport[]>([]);
+  const [crashStatus, setCrashStatus] = useState<string | null>(null);
+  const consoleRef = useRef<HTMLDivElement>(null);
+
+  useEffect(() => {
+    getSettings().then((settings) => {
+      setLogLevel(settings.log_level);
+      setRedaction(settings.log_redaction);
+    });
+    invoke<string | null>("get_log_path").then(setLogPath);
+    invoke<CrashReport[]>("get_crash_reports").then(setCrashReports);
+    invoke<string[]>("get_log_tail", { lines: 500 }).then((tail) =>
+      setLines((current) => [...tail, ...current].slice(-MAX_LINES))
+    );
+    const unlisten = listen<string>("log-appended", (event) => {
+      setLines((current) => [...current, event.payload].slice(-MAX_LINES));
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  useEffect(() => {
+    const el = consoleRef.current;
+    if (el) el.scrollTop = el.scrollHeight;
+  }, [lines]);
+
+  const changeLogLevel = async (level: LogLevel) => {
+    const prev = logLevel;
+    setLogLevel(level);
+    try {
+      await invoke("set_log_level", { level });
+    } catch (e) {
+      console.error("Failed to set log level:", e);
+      setLogLevel(prev);
+    }
+  };
+
+  const changeRedaction = async (next: LogRedaction) => {
+    const prev = redaction;
+    setRedaction(next);
+    try {
+      await updateSettings({ log_redaction: next });
+    } catch (e) {
+      console.error("Failed to save log redaction setting:", e);
+      setRedaction(prev);
+    }
+  };
+
+  const exportDiagnostics = async () => {
+    setDiagnosticsStatus("Exporting...");
+    try {
+      const path = await invoke<string>("export_diagnostics");
+      setDiagnosticsStatus(`Saved to ${path}`);
+    } catch (e) {
+      setDiagnosticsStatus(String(e));
+    }
+  };
+
+  const shareCrashReport = async (fileName: string) => {
+    try {
+      const path = await invoke<string>("share_crash_report", { fileName });
+      setCrashStatus(`Saved to ${path}`);
+    } catch (e) {
+      setCrashStatus(String(e));
+    }
+  };
+
+  return (
+    <div className="settings-tab">
+      <div className="setting-group">
+        <label className="setting-label">Log</label>
+        <p className="setting-description">
+          What ClipSlot is doing, as it happens.
+          {logPath && <> Also saved to <span className="api-token">{logPath}</span>.</>}
+        </p>
+        <div className="setting-row">
+          <select
+            className="setting-input"
+            value={logLevel}
+            onChange={(e) => changeLogLevel(e.target.value as LogLevel)}
+          >
+            {LOG_LEVELS.map((level) => (
+              <option key={level.value} value={level.value}>
+                {level.label}
+              </option>
+            ))}
+          </select>
+          <select
+            className="setting-input"
+            value={redaction}
+            onChange={(e) => changeRedaction(e.target.value as LogRedaction)}
+          >
+            {LOG_REDACTIONS.map((option) => (
+              <option key={option.value} value={option.value}>
+                {option.label}
+              </option>
+            ))}
+          </select>
+          <button className="setting-btn" onClick={() => setLines([])}>
+            Clear
+          </button>
+        </div>
+        <div className="log-console" ref={consoleRef}>
+          {lines.length === 0 ? (
+            <div className="setting-empty">No log lines yet</div>
+          ) : (
+            lines.map((line, i) => (
+              <div key={i} className={lineClass(line)}>
+                {line}
+              </div>
+            ))
+          )}
+        </div>
+      </div>
+
+      <div className="setting-group">
+        <label className="setting-label">Diagnostics</label>
+        <p className="setting-description">
+          Save a zip of the log, settings and system details to attach to a bug report.
+          Email addresses, tokens and your user name are removed first.
+        </p>
+        <div className="setting-row">
+          <button className="setting-btn" onClick={exportDiagnostics}>
+            Export Diagnostics
+          </button>
+          {diagnosticsStatus && <span className="setting-hint">{diagnosticsStatus}</span>}
+        </div>
+      </div>
+
+      <div className="setting-group">
+        <label className="setting-label">Crash Reports</label>
+        <p className="setting-description">
+          Saved when ClipSlot crashes. Share one with a bug report to help find the cause;
+          diagnostics include them too.
+        </p>
+        {crashReports.length === 0 ? (
+          <p className="setting-empty">No crashes recorded</p>
+        ) : (
+          crashReports.map((report) => (
+            <div key={report.file_name} className="setting-row">
+              <span className="setting-hint">
+                {new Date(report.created_at).toLocaleString()}: {report.message}
+              </span>
+              <button
+                className="setting-btn"
+                onClick={() => shareCrashReport(report.file_name)}
+              >
+                Share
+              </button>
+            </div>
+          ))
+        )}
+        {crashStatus && <p className="setting-hint">{crashStatus}</p>}
+      </div>
+    </div>
+  );
+}
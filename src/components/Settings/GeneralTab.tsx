@@ -1,29 +1,150 @@
 import { useEffect, useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
+import { getSettings, updateSettings, type Language, type Settings } from "./settingsApi";
+
+const TRAY_SECTIONS: { key: string; label: string }[] = [
+  { key: "status", label: "History count and last capture" },
+  { key: "sync", label: "Account and sync status" },
+  { key: "slots", label: "Slots" },
+  { key: "recent", label: "Recent history" },
+  { key: "history", label: "Show History" },
+  { key: "pause", label: "Pause Monitoring" },
+  { key: "settings", label: "Settings" },
+];
+
+const DEFAULT_TRAY_SECTIONS = TRAY_SECTIONS.map((s) => s.key);
+
+// Each in its own language
+const LANGUAGES: { value: Language; label: string }[] = [
+  { value: "system", label: "System" },
+  { value: "en", label: "English" },
+  { value: "de", label: "Deutsch" },
+  { value: "fr", label: "Français" },
+  { value: "es", label: "Español" },
+];
+
+interface UpdateInfo {
+  version: string;
+  current_version: string;
+  notes: string | null;
+}
 
 export default function GeneralTab() {
   const [historyLimit, setHistoryLimit] = useState(500);
+  const [dedupWindowSecs, setDedupWindowSecs] = useState(2);
+  const [dedupRecentItems, setDedupRecentItems] = useState(0);
+  const [trayHistoryCount, setTrayHistoryCount] = useState(5);
   const [autoClearOnQuit, setAutoClearOnQuit] = useState(false);
+  const [pickerOnLeftClick, setPickerOnLeftClick] = useState(false);
+  const [pickerFrequentFirst, setPickerFrequentFirst] = useState(false);
+  const [language, setLanguage] = useState<Language>("system");
+  // Every section in display order, with the hidden ones after the shown ones
+  const [trayOrder, setTrayOrder] = useState<string[]>(DEFAULT_TRAY_SECTIONS);
+  const [trayShown, setTrayShown] = useState<string[]>(DEFAULT_TRAY_SECTIONS);
+  const [checkUpdatesOnStartup, setCheckUpdatesOnStartup] = useState(true);
+  const [update, setUpdate] = useState<UpdateInfo | null>(null);
+  const [updateStatus, setUpdateStatus] = useState<string | null>(null);
+  const [updateBusy, setUpdateBusy] = useState(false);
+  // Items in the Windows clipboard history; null where there's none to import
+  const [systemHistoryCount, setSystemHistoryCount] = useState<number | null>(null);
+  const [importStatus, setImportStatus] = useState<string | null>(null);
+  const [importing, setImporting] = useState(false);
   const [saved, setSaved] = useState(false);
 
+  const [saveError, setSaveError] = useState<string | null>(null);
+
+  const applySettings = (settings: Settings) => {
+    setHistoryLimit(settings.history_limit);
+    setDedupWindowSecs(settings.dedup_window_secs);
+    setDedupRecentItems(settings.dedup_recent_items);
+    setTrayHistoryCount(settings.tray_history_count);
+    const shown = settings.tray_sections;
+    setTrayShown(shown);
+    setTrayOrder([...shown, ...DEFAULT_TRAY_SECTIONS.filter((k) => !shown.includes(k))]);
+    setPickerOnLeftClick(settings.tray_left_click === "picker");
+    setPickerFrequentFirst(settings.picker_order === "frequent");
+    setLanguage(settings.language);
+    setCheckUpdatesOnStartup(settings.check_updates_on_startup);
+    setAutoClearOnQuit(settings.auto_clear_on_quit);
+  };
+
   useEffect(() => {
-    invoke<Record<string, string>>("get_settings").then((settings) => {
-      if (settings.history_limit) {
-        setHistoryLimit(parseInt(settings.history_limit, 10) || 500);
-      }
-      if (settings.auto_clear_on_quit) {
-        setAutoClearOnQuit(settings.auto_clear_on_quit === "true");
-      }
-    });
+    getSettings().then(applySettings);
+    invoke<number | null>("get_system_history_count").then(setSystemHistoryCount);
   }, []);
 
-  const saveSetting = async (key: string, value: string) => {
+  const saveSetting = async (changes: Partial<Settings>) => {
+    setSaveError(null);
     try {
-      await invoke("update_setting", { key, value });
+      await updateSettings(changes);
       setSaved(true);
       setTimeout(() => setSaved(false), 1500);
     } catch (e) {
-      console.error("Failed to save setting:", e);
+      setSaveError(String(e));
+      // Show what is actually stored again
+      getSettings().then(applySettings);
+    }
+  };
+
+  const saveTraySections = (order: string[], shown: string[]) => {
+    setTrayOrder(order);
+    setTrayShown(shown);
+    saveSetting({ tray_sections: order.filter((k) => shown.includes(k)) });
+  };
+
+  const toggleTraySection = (key: string, enabled: boolean) => {
+    const shown = enabled ? [...trayShown, key] : trayShown.filter((k) => k !== key);
+    saveTraySections(trayOrder, shown);
+  };
+
+  const moveTraySection = (index: number, delta: number) => {
+    const target = index + delta;
+    if (target < 0 || target >= trayOrder.length) return;
+    const order = [...trayOrder];
+    [order[index], order[target]] = [order[target], order[index]];
+    saveTraySections(order, trayShown);
+  };
+
+  const importSystemHistory = async () => {
+    setImporting(true);
+    setImportStatus(null);
+    try {
+      const imported = await invoke<number>("import_system_history");
+      setImportStatus(
+        imported === 0
+          ? "Nothing new to import."
+          : `Imported ${imported} item${imported === 1 ? "" : "s"}.`
+      );
+    } catch (e) {
+      setImportStatus(String(e));
+    } finally {
+      setImporting(false);
+    }
+  };
+
+  const checkForUpdates = async () => {
+    setUpdateBusy(true);
+    setUpdateStatus(null);
+    try {
+      const found = await invoke<UpdateInfo | null>("check_for_updates");
+      setUpdate(found);
+      if (!found) setUpdateStatus("ClipSlot is up to date.");
+    } catch (e) {
+      setUpdateStatus(String(e));
+    } finally {
+      setUpdateBusy(false);
+    }
+  };
+
+  const installUpdate = async () => {
+    setUpdateBusy(true);
+    setUpdateStatus("Downloading update...");
+    try {
+      // Restarts the app once installed
+      await invoke("install_update");
+    } catch (e) {
+      setUpdateStatus(String(e));
+      setUpdateBusy(false);
     }
   };
 
@@ -42,12 +163,183 @@ export default function GeneralTab() {
             min={10}
             max={10000}
             onChange={(e) => setHistoryLimit(parseInt(e.target.value, 10) || 500)}
-            onBlur={() => saveSetting("history_limit", historyLimit.toString())}
+            onBlur={() => saveSetting({ history_limit: historyLimit })}
+          />
+          <span className="setting-hint">items</span>
+        </div>
+      </div>
+
+      {systemHistoryCount !== null && (
+        <div className="setting-group">
+          <label className="setting-label">Windows Clipboard History</label>
+          <p className="setting-description">
+            Add the {systemHistoryCount} text items in the Windows clipboard
+            history (Win+V) to ClipSlot's, with when they were copied. Content
+            ClipSlot already has is skipped.
+          </p>
+          <div className="setting-row">
+            <button className="setting-btn" disabled={importing} onClick={importSystemHistory}>
+              {importing ? "Importing..." : "Import"}
+            </button>
+            {importStatus && <span className="setting-hint">{importStatus}</span>}
+          </div>
+        </div>
+      )}
+
+      <div className="setting-group">
+        <label className="setting-label">Duplicates</label>
+        <p className="setting-description">
+          Copying the same thing again soon after is kept once. Some apps set
+          the clipboard over and over; skipping content that matches any of
+          the latest items keeps them from filling history.
+        </p>
+        <div className="setting-row">
+          <input
+            type="number"
+            className="setting-input number-input"
+            value={dedupWindowSecs}
+            min={0}
+            max={3600}
+            onChange={(e) => setDedupWindowSecs(Math.max(0, parseInt(e.target.value, 10) || 0))}
+            onBlur={() => saveSetting({ dedup_window_secs: dedupWindowSecs })}
+          />
+          <span className="setting-hint">seconds between identical copies</span>
+        </div>
+        <div className="setting-row">
+          <input
+            type="number"
+            className="setting-input number-input"
+            value={dedupRecentItems}
+            min={0}
+            max={100}
+            onChange={(e) => setDedupRecentItems(Math.max(0, parseInt(e.target.value, 10) || 0))}
+            onBlur={() => saveSetting({ dedup_recent_items: dedupRecentItems })}
+          />
+          <span className="setting-hint">latest items to check (0 for none)</span>
+        </div>
+      </div>
+
+      <div className="setting-group">
+        <label className="setting-label">Recent Items in Tray</label>
+        <p className="setting-description">
+          Latest history items listed in the tray menu; click one to copy it.
+          Set to 0 to hide them.
+        </p>
+        <div className="setting-row">
+          <input
+            type="number"
+            className="setting-input number-input"
+            value={trayHistoryCount}
+            min={0}
+            max={10}
+            onChange={(e) =>
+              setTrayHistoryCount(Math.min(10, Math.max(0, parseInt(e.target.value, 10) || 0)))
+            }
+            onBlur={() => saveSetting({ tray_history_count: trayHistoryCount })}
           />
           <span className="setting-hint">items</span>
         </div>
       </div>
 
+      <div className="setting-group">
+        <label className="setting-label">Tray Menu</label>
+        <p className="setting-description">
+          Choose which sections the tray menu shows, and in what order. Quit is
+          always last.
+        </p>
+        <ul className="app-list">
+          {trayOrder.map((key, index) => (
+            <li key={key} className="app-list-item">
+              <label>
+                <input
+                  type="checkbox"
+                  checked={trayShown.includes(key)}
+                  onChange={(e) => toggleTraySection(key, e.target.checked)}
+                />
+                {TRAY_SECTIONS.find((s) => s.key === key)?.label}
+              </label>
+              <div className="tray-section-actions">
+                <button
+                  className="setting-btn"
+                  disabled={index === 0}
+                  onClick={() => moveTraySection(index, -1)}
+                  aria-label="Move up"
+                >
+                  ↑
+                </button>
+                <button
+                  className="setting-btn"
+                  disabled={index === trayOrder.length - 1}
+                  onClick={() => moveTraySection(index, 1)}
+                  aria-label="Move down"
+                >
+                  ↓
+                </button>
+              </div>
+            </li>
+          ))}
+        </ul>
+      </div>
+
+      <div className="setting-group">
+        <label className="setting-label">Tray and Notification Language</label>
+        <p className="setting-description">
+          Language of the tray menu and notifications. System uses your
+          computer's language when ClipSlot is translated into it.
+        </p>
+        <select
+          className="setting-input"
+          value={language}
+          onChange={(e) => {
+            const value = e.target.value as Language;
+            setLanguage(value);
+            saveSetting({ language: value });
+          }}
+        >
+          {LANGUAGES.map((l) => (
+            <option key={l.value} value={l.value}>
+              {l.label}
+            </option>
+          ))}
+        </select>
+      </div>
+
+      <div className="setting-group">
+        <label className="setting-label">
+          <input
+            type="checkbox"
+            checked={pickerOnLeftClick}
+            onChange={(e) => {
+              setPickerOnLeftClick(e.target.checked);
+              saveSetting({ tray_left_click: e.target.checked ? "picker" : "menu" });
+            }}
+          />
+          Left-click opens quick picker
+        </label>
+        <p className="setting-description">
+          Clicking the tray icon opens a searchable list of recent items instead
+          of the menu; right-click still shows the menu. Not available on Linux.
+        </p>
+      </div>
+
+      <div className="setting-group">
+        <label className="setting-label">
+          <input
+            type="checkbox"
+            checked={pickerFrequentFirst}
+            onChange={(e) => {
+              setPickerFrequentFirst(e.target.checked);
+              saveSetting({ picker_order: e.target.checked ? "frequent" : "recent" });
+            }}
+          />
+          Quick picker shows most-used items first
+        </label>
+        <p className="setting-description">
+          Items you copy or paste again often rise to the top, while recent
+          ones stay near it.
+        </p>
+      </div>
+
       <div className="setting-group">
         <label className="setting-label">
           <input
@@ -55,7 +347,7 @@ export default function GeneralTab() {
             checked={autoClearOnQuit}
             onChange={(e) => {
               setAutoClearOnQuit(e.target.checked);
-              saveSetting("auto_clear_on_quit", e.target.checked.toString());
+              saveSetting({ auto_clear_on_quit: e.target.checked });
             }}
           />
           Clear history on quit
@@ -65,6 +357,36 @@ export default function GeneralTab() {
         </p>
       </div>
 
+      <div className="setting-group">
+        <label className="setting-label">
+          <input
+            type="checkbox"
+            checked={checkUpdatesOnStartup}
+            onChange={(e) => {
+              setCheckUpdatesOnStartup(e.target.checked);
+              saveSetting({ check_updates_on_startup: e.target.checked });
+            }}
+          />
+          Check for updates on startup
+        </label>
+        <p className="setting-description">
+          When a new version is out, it can be installed from the tray menu.
+        </p>
+        <div className="setting-row">
+          {update ? (
+            <button className="setting-btn" disabled={updateBusy} onClick={installUpdate}>
+              Install v{update.version} and Restart
+            </button>
+          ) : (
+            <button className="setting-btn" disabled={updateBusy} onClick={checkForUpdates}>
+              {updateBusy ? "Checking..." : "Check for Updates"}
+            </button>
+          )}
+          {updateStatus && <span className="setting-hint">{updateStatus}</span>}
+        </div>
+      </div>
+
+      {saveError && <div className="auth-error">{saveError}</div>}
       {saved && <div className="save-indicator">Settings saved</div>}
     </div>
   );
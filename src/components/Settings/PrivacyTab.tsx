@@ -1,57 +1,252 @@
 import { useEffect, useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
+import {
+  getSettings,
+  updateSettings,
+  type CaptureSchedule,
+  type Settings,
+} from "./settingsApi";
+
+const WEEKDAYS = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+interface LocalApiInfo {
+  enabled: boolean;
+  port: number;
+  token: string;
+  running: boolean;
+}
+
+interface IntegrityReport {
+  enabled: boolean;
+  checked: number;
+  problems: string[];
+}
 
 export default function PrivacyTab() {
   const [excludedApps, setExcludedApps] = useState<string[]>([]);
+  // "only": capture from the allowed apps alone, instead of all but the excluded ones
+  const [appFilterMode, setAppFilterMode] = useState<Settings["app_filter_mode"]>("exclude");
+  const [allowedApps, setAllowedApps] = useState<string[]>([]);
   const [newApp, setNewApp] = useState("");
+  const [fetchLinkTitles, setFetchLinkTitles] = useState(false);
+  const [deepLinkPaste, setDeepLinkPaste] = useState(false);
+  const [telemetry, setTelemetry] = useState(false);
+  const [hideFromCapture, setHideFromCapture] = useState(false);
+  const [integrity, setIntegrity] = useState(false);
+  const [integrityReport, setIntegrityReport] = useState<IntegrityReport | null>(null);
+  const [integrityError, setIntegrityError] = useState<string | null>(null);
+  const [clipboardClearSecs, setClipboardClearSecs] = useState(0);
+  const [clipboardCleared, setClipboardCleared] = useState(false);
+  const [schedule, setSchedule] = useState<CaptureSchedule | null>(null);
+  const [scheduleError, setScheduleError] = useState<string | null>(null);
+  const [localApi, setLocalApi] = useState<LocalApiInfo | null>(null);
+  const [localApiPort, setLocalApiPort] = useState(19847);
+  const [localApiError, setLocalApiError] = useState<string | null>(null);
 
   useEffect(() => {
-    invoke<Record<string, string>>("get_settings").then((settings) => {
-      if (settings.excluded_apps) {
-        try {
-          setExcludedApps(JSON.parse(settings.excluded_apps));
-        } catch {
-          setExcludedApps([]);
-        }
-      }
+    getSettings().then((settings) => {
+      setExcludedApps(settings.excluded_apps);
+      setAppFilterMode(settings.app_filter_mode);
+      setAllowedApps(settings.allowed_apps);
+      setFetchLinkTitles(settings.fetch_link_titles);
+      setDeepLinkPaste(settings.deep_link_paste);
+      setTelemetry(settings.telemetry_enabled);
+      setHideFromCapture(settings.hide_from_screen_capture);
+      setIntegrity(settings.history_integrity_enabled);
+      setClipboardClearSecs(settings.clipboard_clear_secs);
+      setSchedule(settings.capture_schedule);
+    });
+    invoke<LocalApiInfo>("get_local_api_info").then((info) => {
+      setLocalApi(info);
+      setLocalApiPort(info.port);
     });
   }, []);
 
-  const saveExcludedApps = async (apps: string[]) => {
-    const prev = excludedApps;
-    setExcludedApps(apps);
+  const configureLocalApi = async (enabled: boolean, port: number) => {
+    setLocalApiError(null);
+    try {
+      setLocalApi(await invoke<LocalApiInfo>("configure_local_api", { enabled, port }));
+    } catch (e) {
+      setLocalApiError(String(e));
+    }
+  };
+
+  const regenerateLocalApiToken = async () => {
+    setLocalApiError(null);
+    try {
+      setLocalApi(await invoke<LocalApiInfo>("regenerate_local_api_token"));
+    } catch (e) {
+      setLocalApiError(String(e));
+    }
+  };
+
+  // The list being edited: allowed apps in "only" mode, excluded apps otherwise
+  const appList = appFilterMode === "only" ? allowedApps : excludedApps;
+
+  const saveAppList = async (apps: string[]) => {
+    const only = appFilterMode === "only";
+    const setApps = only ? setAllowedApps : setExcludedApps;
+    const prev = appList;
+    setApps(apps);
+    try {
+      await updateSettings(only ? { allowed_apps: apps } : { excluded_apps: apps });
+    } catch (e) {
+      console.error("Failed to save app list:", e);
+      setApps(prev);
+    }
+  };
+
+  const changeAppFilterMode = async (mode: Settings["app_filter_mode"]) => {
+    const prev = appFilterMode;
+    setAppFilterMode(mode);
+    try {
+      await updateSettings({ app_filter_mode: mode });
+    } catch (e) {
+      console.error("Failed to save app filter mode:", e);
+      setAppFilterMode(prev);
+    }
+  };
+
+  const toggleFetchLinkTitles = async (enabled: boolean) => {
+    setFetchLinkTitles(enabled);
+    try {
+      await updateSettings({ fetch_link_titles: enabled });
+    } catch (e) {
+      console.error("Failed to save link title setting:", e);
+      setFetchLinkTitles(!enabled);
+    }
+  };
+
+  const toggleDeepLinkPaste = async (enabled: boolean) => {
+    setDeepLinkPaste(enabled);
+    try {
+      await updateSettings({ deep_link_paste: enabled });
+    } catch (e) {
+      console.error("Failed to save link paste setting:", e);
+      setDeepLinkPaste(!enabled);
+    }
+  };
+
+  const toggleHideFromCapture = async (enabled: boolean) => {
+    setHideFromCapture(enabled);
+    try {
+      await updateSettings({ hide_from_screen_capture: enabled });
+    } catch (e) {
+      console.error("Failed to save screen capture setting:", e);
+      setHideFromCapture(!enabled);
+    }
+  };
+
+  const toggleTelemetry = async (enabled: boolean) => {
+    setTelemetry(enabled);
     try {
-      await invoke("update_setting", {
-        key: "excluded_apps",
-        value: JSON.stringify(apps),
-      });
+      await updateSettings({ telemetry_enabled: enabled });
     } catch (e) {
-      console.error("Failed to save excluded apps:", e);
-      setExcludedApps(prev);
+      console.error("Failed to save usage statistics setting:", e);
+      setTelemetry(!enabled);
     }
   };
 
+  const toggleIntegrity = async (enabled: boolean) => {
+    setIntegrity(enabled);
+    setIntegrityReport(null);
+    setIntegrityError(null);
+    try {
+      await updateSettings({ history_integrity_enabled: enabled });
+    } catch (e) {
+      setIntegrityError(String(e));
+      setIntegrity(!enabled);
+    }
+  };
+
+  const verifyIntegrity = async () => {
+    setIntegrityError(null);
+    try {
+      setIntegrityReport(await invoke<IntegrityReport>("verify_history_integrity"));
+    } catch (e) {
+      setIntegrityError(String(e));
+    }
+  };
+
+  const saveClipboardClearSecs = async (secs: number) => {
+    const prev = clipboardClearSecs;
+    setClipboardClearSecs(secs);
+    try {
+      await updateSettings({ clipboard_clear_secs: secs });
+    } catch (e) {
+      console.error("Failed to save clipboard clear delay:", e);
+      setClipboardClearSecs(prev);
+    }
+  };
+
+  const clearClipboard = async () => {
+    try {
+      await invoke("clear_system_clipboard");
+      setClipboardCleared(true);
+      setTimeout(() => setClipboardCleared(false), 2000);
+    } catch (e) {
+      console.error("Failed to clear clipboard:", e);
+    }
+  };
+
+  const saveSchedule = async (changes: Partial<CaptureSchedule>) => {
+    if (!schedule) return;
+    const prev = schedule;
+    const next = { ...schedule, ...changes };
+    setSchedule(next);
+    setScheduleError(null);
+    try {
+      await updateSettings({ capture_schedule: next });
+    } catch (e) {
+      setScheduleError(String(e));
+      setSchedule(prev);
+    }
+  };
+
+  const toggleDay = (day: number) => {
+    if (!schedule) return;
+    const days = schedule.days.includes(day)
+      ? schedule.days.filter((d) => d !== day)
+      : [...schedule.days, day].sort();
+    saveSchedule({ days });
+  };
+
   const handleAdd = () => {
     const trimmed = newApp.trim();
-    if (trimmed && !excludedApps.includes(trimmed)) {
-      saveExcludedApps([...excludedApps, trimmed]);
+    if (trimmed && !appList.includes(trimmed)) {
+      saveAppList([...appList, trimmed]);
       setNewApp("");
     }
   };
 
   const handleRemove = (app: string) => {
-    saveExcludedApps(excludedApps.filter((a) => a !== app));
+    saveAppList(appList.filter((a) => a !== app));
   };
 
   return (
     <div className="settings-tab">
       <div className="setting-group">
-        <label className="setting-label">Excluded Applications</label>
+        <label className="setting-label">
+          {appFilterMode === "only" ? "Allowed Applications" : "Excluded Applications"}
+        </label>
         <p className="setting-description">
-          ClipSlot will not capture clipboard content copied from these apps.
+          {appFilterMode === "only"
+            ? "ClipSlot will only capture clipboard content copied from these apps."
+            : "ClipSlot will not capture clipboard content copied from these apps."}{" "}
           Enter the application bundle identifier (e.g., com.1password.app).
         </p>
 
+        <div className="setting-row">
+          <select
+            className="setting-input"
+            value={appFilterMode}
+            onChange={(e) => changeAppFilterMode(e.target.value as Settings["app_filter_mode"])}
+          >
+            <option value="exclude">Capture from all apps except these</option>
+            <option value="only">Only capture from these apps</option>
+          </select>
+        </div>
+
         <div className="setting-row">
           <input
             type="text"
@@ -66,9 +261,9 @@ export default function PrivacyTab() {
           </button>
         </div>
 
-        {excludedApps.length > 0 ? (
+        {appList.length > 0 ? (
           <ul className="app-list">
-            {excludedApps.map((app) => (
+            {appList.map((app) => (
               <li key={app} className="app-list-item">
                 <span>{app}</span>
                 <button
@@ -81,8 +276,241 @@ export default function PrivacyTab() {
             ))}
           </ul>
         ) : (
-          <p className="setting-empty">No excluded apps.</p>
+          <p className="setting-empty">
+            {appFilterMode === "only"
+              ? "No allowed apps yet. Nothing will be captured until you add one."
+              : "No excluded apps."}
+          </p>
+        )}
+      </div>
+
+      {schedule && (
+        <div className="setting-group">
+          <label className="setting-label">Capture Schedule</label>
+          <p className="setting-description">
+            Stop capturing at certain times, such as work hours, or capture only
+            then. Uses this computer's clock.
+          </p>
+          <div className="setting-row">
+            <select
+              className="setting-input"
+              value={schedule.mode}
+              onChange={(e) => saveSchedule({ mode: e.target.value as CaptureSchedule["mode"] })}
+            >
+              <option value="off">Always capture</option>
+              <option value="skip">Don't capture during these hours</option>
+              <option value="only">Only capture during these hours</option>
+            </select>
+          </div>
+          {schedule.mode !== "off" && (
+            <>
+              <div className="setting-row">
+                {WEEKDAYS.map((name, i) => (
+                  <label key={name} className="schedule-day">
+                    <input
+                      type="checkbox"
+                      checked={schedule.days.includes(i + 1)}
+                      onChange={() => toggleDay(i + 1)}
+                    />
+                    {name}
+                  </label>
+                ))}
+              </div>
+              <div className="setting-row">
+                <input
+                  type="time"
+                  className="setting-input"
+                  value={schedule.start}
+                  onChange={(e) => saveSchedule({ start: e.target.value })}
+                />
+                <span className="setting-hint">to</span>
+                <input
+                  type="time"
+                  className="setting-input"
+                  value={schedule.end}
+                  onChange={(e) => saveSchedule({ end: e.target.value })}
+                />
+              </div>
+            </>
+          )}
+          {scheduleError && <p className="auth-error">{scheduleError}</p>}
+        </div>
+      )}
+
+      <div className="setting-group">
+        <label className="setting-label">Clear Copied Secrets</label>
+        <p className="setting-description">
+          Empty the clipboard a while after copying something that looks like a
+          password, key or card number, or anything from an app ClipSlot
+          doesn't capture. Only clears it if nothing else was copied since.
+        </p>
+        <div className="setting-row">
+          <select
+            className="setting-input"
+            value={clipboardClearSecs}
+            onChange={(e) => saveClipboardClearSecs(parseInt(e.target.value, 10))}
+          >
+            <option value={0}>Never</option>
+            <option value={15}>After 15 seconds</option>
+            <option value={30}>After 30 seconds</option>
+            <option value={60}>After 1 minute</option>
+            <option value={300}>After 5 minutes</option>
+          </select>
+          <button className="setting-btn" onClick={clearClipboard}>
+            {clipboardCleared ? "Cleared" : "Clear Clipboard Now"}
+          </button>
+        </div>
+      </div>
+
+      <div className="setting-group">
+        <label className="setting-label">
+          <input
+            type="checkbox"
+            checked={fetchLinkTitles}
+            onChange={(e) => toggleFetchLinkTitles(e.target.checked)}
+          />
+          Show page titles for copied links
+        </label>
+        <p className="setting-description">
+          When you copy a link, ClipSlot loads the page to find its title and
+          shows that in history. The site can see the request came from you.
+        </p>
+      </div>
+
+      <div className="setting-group">
+        <label className="setting-label">
+          <input
+            type="checkbox"
+            checked={deepLinkPaste}
+            onChange={(e) => toggleDeepLinkPaste(e.target.checked)}
+          />
+          Let clipslot:// links paste
+        </label>
+        <p className="setting-description">
+          Links like clipslot://paste-slot/1 paste into the focused app. Any web
+          page or app can open one, so leave this off unless you use them from
+          a launcher.
+        </p>
+      </div>
+
+      <div className="setting-group">
+        <label className="setting-label">
+          <input
+            type="checkbox"
+            checked={hideFromCapture}
+            onChange={(e) => toggleHideFromCapture(e.target.checked)}
+          />
+          Hide ClipSlot from screen sharing
+        </label>
+        <p className="setting-description">
+          ClipSlot's windows show up blank in screenshots, recordings and shared
+          screens, so a meeting doesn't see your clipboard history. Works on macOS
+          and Windows.
+        </p>
+      </div>
+
+      <div className="setting-group">
+        <label className="setting-label">
+          <input
+            type="checkbox"
+            checked={integrity}
+            onChange={(e) => toggleIntegrity(e.target.checked)}
+          />
+          Detect tampering with history
+        </label>
+        <p className="setting-description">
+          Signs each history item with your encryption key, so items added, changed or
+          deleted by something other than ClipSlot can be spotted. Items already in
+          history are signed when you turn this on.
+        </p>
+        {integrity && (
+          <div className="setting-row">
+            <button className="setting-btn" onClick={verifyIntegrity}>
+              Check History
+            </button>
+            {integrityReport && integrityReport.problems.length === 0 && (
+              <span className="setting-hint">
+                All {integrityReport.checked} items check out
+              </span>
+            )}
+          </div>
+        )}
+        {integrityReport && integrityReport.problems.length > 0 && (
+          <ul className="auth-error">
+            {integrityReport.problems.map((problem) => (
+              <li key={problem}>{problem}</li>
+            ))}
+          </ul>
+        )}
+        {integrityError && <p className="auth-error">{integrityError}</p>}
+      </div>
+
+      <div className="setting-group">
+        <label className="setting-label">
+          <input
+            type="checkbox"
+            checked={telemetry}
+            onChange={(e) => toggleTelemetry(e.target.checked)}
+          />
+          Send anonymous usage statistics
+        </label>
+        <p className="setting-description">
+          Once a day, sends the sync server how many items were captured, slots
+          saved and pasted, and syncs failed, with the app version and operating
+          system. Never any content, slot names or account details. Turning this
+          off deletes counts not yet sent.
+        </p>
+      </div>
+
+      <div className="setting-group">
+        <label className="setting-label">
+          <input
+            type="checkbox"
+            checked={localApi?.enabled ?? false}
+            onChange={(e) => configureLocalApi(e.target.checked, localApiPort)}
+          />
+          Local API for scripts
+        </label>
+        <p className="setting-description">
+          Lets scripts and automation tools on this computer read history and
+          slots, copy items and fill slots over HTTP. Only reachable from this
+          machine, and every request needs the token below.
+        </p>
+        {localApi?.enabled && (
+          <>
+            <div className="setting-row">
+              <input
+                type="number"
+                className="setting-input number-input"
+                value={localApiPort}
+                min={1024}
+                max={65535}
+                onChange={(e) => setLocalApiPort(parseInt(e.target.value, 10) || 0)}
+                onBlur={() => {
+                  if (localApiPort !== localApi.port) configureLocalApi(true, localApiPort);
+                }}
+              />
+              <span className="setting-hint">port</span>
+            </div>
+            <div className="setting-row">
+              <input
+                type="text"
+                className="setting-input api-token"
+                value={localApi.token}
+                readOnly
+                onFocus={(e) => e.target.select()}
+              />
+              <button className="setting-btn" onClick={regenerateLocalApiToken}>
+                Regenerate
+              </button>
+            </div>
+            <p className="setting-hint">
+              curl -H "Authorization: Bearer &lt;token&gt;"
+              http://127.0.0.1:{localApi.port}/v1/slots
+            </p>
+          </>
         )}
+        {localApiError && <p className="auth-error">{localApiError}</p>}
       </div>
     </div>
   );
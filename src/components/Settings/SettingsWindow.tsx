@@ -1,5 +1,6 @@
 import { useState } from "react";
 import AccountTab from "./AccountTab";
+import DebugTab from "./DebugTab";
 import GeneralTab from "./GeneralTab";
 import PrivacyTab from "./PrivacyTab";
 import SlotsTab from "./SlotsTab";
@@ -10,6 +11,7 @@ const TABS = [
   { id: "privacy", label: "Privacy" },
   { id: "slots", label: "Slots" },
   { id: "account", label: "Account" },
+  { id: "debug", label: "Debug" },
 ] as const;
 
 type TabId = (typeof TABS)[number]["id"];
@@ -40,6 +42,7 @@ export default function SettingsWindow() {
         {activeTab === "privacy" && <PrivacyTab />}
         {activeTab === "slots" && <SlotsTab />}
         {activeTab === "account" && <AccountTab />}
+        {activeTab === "debug" && <DebugTab />}
       </div>
     </div>
   );
@@ -1,23 +1,30 @@
 import { useEffect, useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
+import { getSettings, updateSettings, type SnippetTrigger } from "./settingsApi";
 
-interface SlotInfo {
+interface SlotSummary {
   slot_number: number;
   name: string;
-  content: string | null;
   content_preview: string | null;
   updated_at: number;
   is_empty: boolean;
 }
 
 export default function SlotsTab() {
-  const [slots, setSlots] = useState<SlotInfo[]>([]);
+  const [slots, setSlots] = useState<SlotSummary[]>([]);
   const [editingSlot, setEditingSlot] = useState<number | null>(null);
   const [editName, setEditName] = useState("");
+  const [slotCount, setSlotCount] = useState(10);
+  const [pasteConfirmKb, setPasteConfirmKb] = useState(512);
+  const [snippetsEnabled, setSnippetsEnabled] = useState(false);
+  const [triggers, setTriggers] = useState<SnippetTrigger[]>([]);
+  const [newKeyword, setNewKeyword] = useState("");
+  const [newKeywordSlot, setNewKeywordSlot] = useState(1);
+  const [snippetError, setSnippetError] = useState<string | null>(null);
 
   const loadSlots = async () => {
     try {
-      const result = await invoke<SlotInfo[]>("get_all_slots");
+      const result = await invoke<SlotSummary[]>("get_all_slots");
       setSlots(result);
     } catch (e) {
       console.error("Failed to load slots:", e);
@@ -26,8 +33,63 @@ export default function SlotsTab() {
 
   useEffect(() => {
     loadSlots();
+    invoke<number>("get_slot_count").then(setSlotCount);
+    getSettings().then((settings) => {
+      setPasteConfirmKb(settings.paste_confirm_kb);
+      setSnippetsEnabled(settings.snippet_expansion_enabled);
+      setTriggers(settings.snippet_triggers);
+    });
   }, []);
 
+  const toggleSnippets = async (enabled: boolean) => {
+    setSnippetsEnabled(enabled);
+    try {
+      await updateSettings({ snippet_expansion_enabled: enabled });
+    } catch (e) {
+      console.error("Failed to save snippet expansion setting:", e);
+      setSnippetsEnabled(!enabled);
+    }
+  };
+
+  const saveTriggers = async (next: SnippetTrigger[]) => {
+    setSnippetError(null);
+    try {
+      await updateSettings({ snippet_triggers: next });
+      setTriggers(next);
+      return true;
+    } catch (e) {
+      setSnippetError(String(e));
+      return false;
+    }
+  };
+
+  const handleAddTrigger = async () => {
+    const keyword = newKeyword.trim();
+    if (!keyword) return;
+    if (await saveTriggers([...triggers, { keyword, slot: newKeywordSlot }])) {
+      setNewKeyword("");
+    }
+  };
+
+  const handleSlotCount = async () => {
+    try {
+      await invoke("set_slot_count", { slotCount });
+      await loadSlots();
+    } catch (e) {
+      console.error("Failed to set slot count:", e);
+      setSlotCount(await invoke<number>("get_slot_count"));
+    }
+  };
+
+  const savePasteConfirmKb = async () => {
+    try {
+      await updateSettings({ paste_confirm_kb: pasteConfirmKb });
+    } catch (e) {
+      console.error("Failed to save paste confirmation size:", e);
+      setPasteConfirmKb((await getSettings()).paste_confirm_kb);
+    }
+  };
+
   const handleRename = async (slotNumber: number) => {
     const trimmed = editName.trim();
     if (!trimmed) return;
@@ -59,10 +121,24 @@ export default function SlotsTab() {
       <div className="setting-group">
         <label className="setting-label">Permanent Slots</label>
         <p className="setting-description">
-          Manage your 10 permanent clipboard slots. Keyboard shortcuts cover
-          slots 1-5 (Save: Cmd+Ctrl+1-5, Paste: Cmd+Option+1-5). Slots 6-10
+          Manage your permanent clipboard slots. Keyboard shortcuts cover
+          slots 1-5 (Save: Cmd+Ctrl+1-5, Paste: Cmd+Option+1-5); the rest
           are available via the UI and sync.
         </p>
+        <div className="setting-row">
+          <input
+            type="number"
+            className="setting-input number-input"
+            value={slotCount}
+            min={1}
+            max={10}
+            onChange={(e) =>
+              setSlotCount(Math.min(10, Math.max(1, parseInt(e.target.value, 10) || 1)))
+            }
+            onBlur={handleSlotCount}
+          />
+          <span className="setting-hint">slots, shared by all your devices</span>
+        </div>
 
         <div className="slots-list">
           {slots.map((slot) => (
@@ -112,6 +188,94 @@ export default function SlotsTab() {
           ))}
         </div>
       </div>
+
+      <div className="setting-group">
+        <label className="setting-label">Large Pastes</label>
+        <p className="setting-description">
+          Pasting a slot bigger than this with its shortcut asks first, so a
+          mistyped shortcut doesn't drop a huge paste into a chat. 0 never asks.
+        </p>
+        <div className="setting-row">
+          <input
+            type="number"
+            className="setting-input number-input"
+            value={pasteConfirmKb}
+            min={0}
+            max={102400}
+            onChange={(e) => setPasteConfirmKb(Math.max(0, parseInt(e.target.value, 10) || 0))}
+            onBlur={savePasteConfirmKb}
+          />
+          <span className="setting-hint">KB</span>
+        </div>
+      </div>
+
+      <div className="setting-group">
+        <label className="setting-label">
+          <input
+            type="checkbox"
+            checked={snippetsEnabled}
+            onChange={(e) => toggleSnippets(e.target.checked)}
+          />
+          Expand typed keywords
+        </label>
+        <p className="setting-description">
+          Type a keyword such as ;addr anywhere and ClipSlot replaces it with a
+          slot's content. To spot keywords, ClipSlot has to watch every key you
+          type while this is on. Keystrokes are never stored or sent anywhere.
+        </p>
+        {snippetsEnabled && (
+          <>
+            <div className="setting-row">
+              <input
+                type="text"
+                className="setting-input"
+                placeholder=";addr"
+                value={newKeyword}
+                onChange={(e) => setNewKeyword(e.target.value)}
+                onKeyDown={(e) => e.key === "Enter" && handleAddTrigger()}
+              />
+              <select
+                className="setting-input"
+                value={newKeywordSlot}
+                onChange={(e) => setNewKeywordSlot(parseInt(e.target.value, 10))}
+              >
+                {slots.map((slot) => (
+                  <option key={slot.slot_number} value={slot.slot_number}>
+                    {slot.name}
+                  </option>
+                ))}
+              </select>
+              <button className="setting-btn" onClick={handleAddTrigger}>
+                Add
+              </button>
+            </div>
+            {triggers.length > 0 ? (
+              <ul className="app-list">
+                {triggers.map((trigger) => (
+                  <li key={trigger.keyword} className="app-list-item">
+                    <span>
+                      {trigger.keyword} →{" "}
+                      {slots.find((s) => s.slot_number === trigger.slot)?.name ??
+                        `Slot ${trigger.slot}`}
+                    </span>
+                    <button
+                      className="remove-btn"
+                      onClick={() =>
+                        saveTriggers(triggers.filter((t) => t.keyword !== trigger.keyword))
+                      }
+                    >
+                      Remove
+                    </button>
+                  </li>
+                ))}
+              </ul>
+            ) : (
+              <p className="setting-empty">No keywords yet.</p>
+            )}
+            {snippetError && <p className="auth-error">{snippetError}</p>}
+          </>
+        )}
+      </div>
     </div>
   );
 }
@@ -0,0 +1,84 @@
+import { invoke } from "@tauri-apps/api/core";
+
+export type LogLevel = "error" | "warning" | "info" | "debug";
+// How clipboard content shows in the log
+export type LogRedaction = "off" | "lengths" | "hashes";
+
+// "system" follows the operating system's language
+export type Language = "system" | "en" | "de" | "fr" | "es";
+
+/** Mirrors `CaptureSchedule` in src-tauri/src/clipboard/schedule.rs. */
+export interface CaptureSchedule {
+  mode: "off" | "skip" | "only";
+  // 1 (Monday) to 7 (Sunday)
+  days: number[];
+  // "HH:MM", local time; an end before the start runs past midnight
+  start: string;
+  end: string;
+}
+
+export type SyncContentKind = "text" | "link" | "code" | "file";
+
+/** Mirrors `HistorySyncFilter` in src-tauri/src/sync/filter.rs. */
+export interface HistorySyncFilter {
+  exclude_sensitive: boolean;
+  // KB of content; 0 for no limit
+  max_size_kb: number;
+  content_kinds: SyncContentKind[];
+}
+
+/** Mirrors `SnippetTrigger` in src-tauri/src/slots/expander.rs. */
+export interface SnippetTrigger {
+  // Typed without Shift: a-z, 0-9 and ;,./-='`
+  keyword: string;
+  slot: number;
+}
+
+/** Mirrors `Settings` in src-tauri/src/storage/settings.rs. */
+export interface Settings {
+  history_limit: number;
+  // Seconds in which the same content isn't captured twice; 0 for none
+  dedup_window_secs: number;
+  // Also skip content matching any of this many latest items; 0 for none
+  dedup_recent_items: number;
+  auto_clear_on_quit: boolean;
+  excluded_apps: string[];
+  app_filter_mode: "exclude" | "only";
+  allowed_apps: string[];
+  capture_schedule: CaptureSchedule;
+  fetch_link_titles: boolean;
+  // Seconds before a copied secret is cleared; 0 to leave it
+  clipboard_clear_secs: number;
+  // Slot content over this many KB is pasted only once confirmed; 0 never asks
+  paste_confirm_kb: number;
+  // Let clipslot://paste-slot and paste-item links paste
+  deep_link_paste: boolean;
+  hide_from_screen_capture: boolean;
+  sync_server_url: string;
+  history_sync_enabled: boolean;
+  history_sync_filter: HistorySyncFilter;
+  search_index_enabled: boolean;
+  history_integrity_enabled: boolean;
+  tray_history_count: number;
+  tray_sections: string[];
+  tray_left_click: "menu" | "picker";
+  picker_order: "recent" | "frequent";
+  language: Language;
+  snippet_expansion_enabled: boolean;
+  snippet_triggers: SnippetTrigger[];
+  check_updates_on_startup: boolean;
+  telemetry_enabled: boolean;
+  local_api_enabled: boolean;
+  local_api_port: number;
+  log_level: LogLevel;
+  log_redaction: LogRedaction;
+}
+
+export function getSettings(): Promise<Settings> {
+  return invoke<Settings>("get_settings");
+}
+
+/** Saves only the given fields; rejects with a message if any is invalid. */
+export function updateSettings(changes: Partial<Settings>): Promise<Settings> {
+  return invoke<Settings>("update_settings", { changes });
+}
@@ -9,6 +9,9 @@ const page = params.get("page");
 const SettingsWindow = React.lazy(
   () => import("./components/Settings/SettingsWindow")
 );
+const QuickPicker = React.lazy(() => import("./components/QuickPicker"));
+const MobileApp = React.lazy(() => import("./components/MobileApp"));
+const ConfirmPaste = React.lazy(() => import("./components/ConfirmPaste"));
 
 ReactDOM.createRoot(document.getElementById("root") as HTMLElement).render(
   <React.StrictMode>
@@ -16,6 +19,18 @@ ReactDOM.createRoot(document.getElementById("root") as HTMLElement).render(
       <React.Suspense fallback={<div>Loading...</div>}>
         <SettingsWindow />
       </React.Suspense>
+    ) : page === "picker" ? (
+      <React.Suspense fallback={null}>
+        <QuickPicker />
+      </React.Suspense>
+    ) : page === "confirm-paste" ? (
+      <React.Suspense fallback={null}>
+        <ConfirmPaste />
+      </React.Suspense>
+    ) : page === "mobile" ? (
+      <React.Suspense fallback={null}>
+        <MobileApp />
+      </React.Suspense>
     ) : (
       <App />
     )}